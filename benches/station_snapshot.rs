@@ -0,0 +1,118 @@
+//! Allocations per request when answering from a Paris-sized snapshot
+//!
+//! Run with `cargo bench --bench station_snapshot`.
+
+use chrono::Utc;
+use serde_json::json;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use velib_mcp::mcp::types::FindNearbyStationsInput;
+use velib_mcp::{
+    BikeAvailability, Coordinates, McpToolHandler, RealTimeStatus, ServiceCapabilities,
+    StationReference, StationStatus, VelibDataClient,
+};
+
+const STATIONS: usize = 1500;
+const ITERATIONS: u64 = 200;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A grid of stations over central Paris, all with live availability
+fn snapshot() -> (Vec<StationReference>, HashMap<String, RealTimeStatus>) {
+    let reference: Vec<StationReference> = (0..STATIONS)
+        .map(|i| StationReference {
+            station_code: format!("{}", 10000 + i).into(),
+            name: format!("Station {i} - Rue de Test").into(),
+            coordinates: Coordinates::new(
+                48.82 + (i / 40) as f64 * 0.002,
+                2.27 + (i % 40) as f64 * 0.003,
+            ),
+            capacity: 30,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        })
+        .collect();
+    let realtime = reference
+        .iter()
+        .map(|station| {
+            let status = RealTimeStatus::new(
+                BikeAvailability::new(5, 3),
+                10,
+                StationStatus::Open,
+                Utc::now(),
+            );
+            (station.station_code.to_string(), status)
+        })
+        .collect();
+    (reference, realtime)
+}
+
+async fn measure<F, Fut>(name: &str, mut call: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    // Warm up, so snapshots built on first use are not counted
+    call().await;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        call().await;
+    }
+    let elapsed = started.elapsed();
+    println!(
+        "{name:<24} {:>10} allocs/call {:>12} bytes/call {:>10.1} µs/call",
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS,
+        (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) / ITERATIONS,
+        elapsed.as_secs_f64() * 1e6 / ITERATIONS as f64,
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    runtime.block_on(async {
+        let (reference, realtime) = snapshot();
+        let mut client = VelibDataClient::new();
+        client.seed_cache(reference, realtime).await;
+        let handler = McpToolHandler::with_data_client(client);
+
+        measure("get_complete_stations", || async {
+            handler.get_complete_stations(true).await.unwrap();
+        })
+        .await;
+        measure("find_nearby_stations", || async {
+            let input: FindNearbyStationsInput =
+                serde_json::from_value(json!({"latitude": 48.85, "longitude": 2.33}))
+                    .expect("valid input");
+            handler.find_nearby_stations(input).await.unwrap();
+        })
+        .await;
+        measure("get_system_overview", || async {
+            handler.get_system_overview().await.unwrap();
+        })
+        .await;
+    });
+}
@@ -0,0 +1,12 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Generate the gRPC service from proto/velib.proto, with a bundled protoc so
+    // building doesn't need one installed
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("bundled protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::compile_protos("proto/velib.proto").expect("valid proto/velib.proto");
+    }
+}
@@ -0,0 +1,196 @@
+//! A scripted agent conversation against an in-process server
+//!
+//! Serves a small fixed snapshot of central Paris, so it runs offline:
+//!
+//! ```bash
+//! cargo run --example agent_demo
+//! ```
+
+use chrono::Utc;
+use std::collections::HashMap;
+use velib_mcp::mcp::{
+    AvailabilityFilter, FindNearbyStationsInput, McpClient, PlanBikeJourneyInput,
+    SearchStationsByNameInput, SortField, SortOptions,
+};
+use velib_mcp::{
+    BikeAvailability, Coordinates, McpServer, McpToolHandler, RealTimeStatus, ServiceCapabilities,
+    StationReference, StationStatus, VelibDataClient,
+};
+
+/// (code, name, latitude, longitude, mechanical, electric, docks)
+#[rustfmt::skip]
+const SNAPSHOT: &[(&str, &str, f64, f64, u16, u16, u16)] = &[
+    ("13123", "Bibliothèque François Mitterrand - Quai", 48.8336, 2.3771, 0, 1, 24),
+    ("13124", "Avenue de France - Tolbiac", 48.8322, 2.3745, 6, 4, 12),
+    ("13125", "Rue Neuve Tolbiac", 48.8347, 2.3733, 2, 0, 20),
+    ("4021", "Hôtel de Ville - Rivoli", 48.8569, 2.3520, 9, 3, 2),
+    ("4022", "Place de l'Hôtel de Ville", 48.8561, 2.3508, 1, 1, 17),
+    ("4023", "Pont d'Arcole", 48.8552, 2.3502, 3, 2, 11),
+];
+
+#[tokio::main]
+async fn main() -> velib_mcp::Result<()> {
+    let base_url = serve_snapshot().await?;
+    let client = McpClient::new(&base_url).with_api_key("demo");
+
+    let info = client.initialize().await?;
+    println!(
+        "Connected to {} {} (tool API {})\n",
+        info["serverInfo"]["name"],
+        info["serverInfo"]["version"],
+        info["serverInfo"]["toolApiVersion"]
+    );
+
+    println!("User: I'm at the BnF, is there a bike around? I need to get to Hôtel de Ville.\n");
+
+    // 1. Resolve the landmark to stations
+    let search = client
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "BnF".to_string(),
+            limit: 3,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await?;
+    let here = search
+        .stations
+        .first()
+        .expect("the BnF landmark resolves to stations")
+        .reference
+        .coordinates;
+    println!(
+        "Agent -> search_stations_by_name(\"BnF\"): {} stations around {:?}",
+        search.stations.len(),
+        search.search_metadata.matched_landmark
+    );
+
+    // 2. Look for stations that actually have bikes, fullest first
+    let nearby = client
+        .find_nearby_stations(FindNearbyStationsInput {
+            latitude: here.latitude,
+            longitude: here.longitude,
+            radius_meters: 500,
+            limit: 3,
+            availability_filter: Some(AvailabilityFilter {
+                min_bikes: Some(2),
+                ..Default::default()
+            }),
+            sort: SortOptions::by(SortField::BikesAvailable),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await?;
+    println!("Agent -> find_nearby_stations(min_bikes: 2, sort_by: bikes_available):");
+    for found in &nearby.stations {
+        let bikes = found
+            .station
+            .real_time
+            .as_ref()
+            .map_or(0, |rt| rt.bikes.total());
+        println!(
+            "  {} - {} bikes, {} m",
+            found.station.reference.name, bikes, found.distance_meters
+        );
+    }
+
+    // 3. Plan the ride to the destination
+    let destination = client
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "Hôtel de Ville".to_string(),
+            limit: 1,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await?
+        .stations
+        .first()
+        .expect("Hôtel de Ville resolves to stations")
+        .reference
+        .coordinates;
+    let plan = client
+        .plan_bike_journey(PlanBikeJourneyInput {
+            origin: here,
+            destination,
+            preferences: None,
+            route_format: None,
+        })
+        .await?;
+    let best = plan
+        .journey
+        .recommendations
+        .first()
+        .expect("a journey between two served areas");
+    println!(
+        "Agent -> plan_bike_journey: pick up at {}, drop off at {} (confidence {:.2})\n",
+        best.pickup_station.reference.name,
+        best.dropoff_station.reference.name,
+        best.confidence_score
+    );
+
+    println!(
+        "Agent: Walk {} m to {} and ride to {}, {} m from Hôtel de Ville.",
+        best.walk_to_pickup,
+        best.pickup_station.reference.name,
+        best.dropoff_station.reference.name,
+        best.walk_from_dropoff
+    );
+
+    Ok(())
+}
+
+/// Serve `SNAPSHOT` on a random local port and return its base URL
+async fn serve_snapshot() -> velib_mcp::Result<String> {
+    let reference: Vec<StationReference> = SNAPSHOT
+        .iter()
+        .map(|&(code, name, latitude, longitude, ..)| StationReference {
+            station_code: code.into(),
+            name: name.into(),
+            coordinates: Coordinates::new(latitude, longitude),
+            capacity: 30,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        })
+        .collect();
+    let realtime: HashMap<String, RealTimeStatus> = SNAPSHOT
+        .iter()
+        .map(|&(code, _, _, _, mechanical, electric, docks)| {
+            let status = RealTimeStatus::new(
+                BikeAvailability::new(mechanical, electric),
+                docks,
+                StationStatus::Open,
+                Utc::now(),
+            );
+            (code.to_string(), status)
+        })
+        .collect();
+
+    let mut data_client = VelibDataClient::new();
+    data_client.seed_cache(reference, realtime).await;
+    let server = McpServer::with_tool_handler(McpToolHandler::with_data_client(data_client));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| velib_mcp::Error::Internal(e.into()))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| velib_mcp::Error::Internal(e.into()))?;
+    let router = server.router();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    Ok(format!("http://{addr}"))
+}
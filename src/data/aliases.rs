@@ -0,0 +1,266 @@
+use crate::types::{Coordinates, VelibStation};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const BUNDLED_ALIASES: &str = include_str!("aliases.toml");
+
+// Radius used when a landmark has coordinates but no explicit radius
+const DEFAULT_LANDMARK_RADIUS_METERS: u32 = 400;
+
+/// A landmark users refer to by name, resolved to coordinates and/or stations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandmarkAlias {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<Coordinates>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub station_codes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius_meters: Option<u32>,
+}
+
+impl LandmarkAlias {
+    /// Whether the (already normalized) query designates this landmark
+    fn matches(&self, normalized_query: &str) -> bool {
+        normalize(&self.name) == normalized_query
+            || self
+                .aliases
+                .iter()
+                .any(|alias| normalize(alias) == normalized_query)
+    }
+
+    /// Stations belonging to this landmark, closest first
+    #[must_use]
+    pub fn resolve<'a>(
+        &self,
+        stations: impl IntoIterator<Item = &'a VelibStation>,
+    ) -> Vec<&'a VelibStation> {
+        let radius = f64::from(self.radius_meters.unwrap_or(DEFAULT_LANDMARK_RADIUS_METERS));
+
+        let mut resolved: Vec<(&VelibStation, f64)> = stations
+            .into_iter()
+            .filter_map(|station| {
+                let distance = self
+                    .coordinates
+                    .map(|center| center.distance_to(&station.reference.coordinates));
+
+                let listed = self
+                    .station_codes
+                    .iter()
+                    .any(|code| **code == *station.reference.station_code);
+                let nearby = distance.is_some_and(|d| d <= radius);
+
+                (listed || nearby).then(|| (station, distance.unwrap_or(0.0)))
+            })
+            .collect();
+
+        resolved.sort_by(|a, b| a.1.total_cmp(&b.1));
+        resolved.into_iter().map(|(station, _)| station).collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    landmark: Vec<LandmarkAlias>,
+}
+
+/// Curated dictionary mapping landmark names to stations
+#[derive(Debug, Clone, Default)]
+pub struct AliasTable {
+    landmarks: Vec<LandmarkAlias>,
+}
+
+impl AliasTable {
+    /// The alias table shipped with the server
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_ALIASES).expect("bundled alias table must be valid")
+    }
+
+    /// Parse an alias table from TOML
+    pub fn parse(source: &str) -> Result<Self> {
+        let file: AliasFile = toml::from_str(source)
+            .map_err(|e| Error::Config(format!("Invalid alias table: {e}")))?;
+
+        for landmark in &file.landmark {
+            if landmark.coordinates.is_none() && landmark.station_codes.is_empty() {
+                return Err(Error::Config(format!(
+                    "Landmark '{}' needs coordinates or station_codes",
+                    landmark.name
+                )));
+            }
+        }
+
+        Ok(Self {
+            landmarks: file.landmark,
+        })
+    }
+
+    /// Load the bundled table extended with the entries of a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read alias table {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+
+        let mut table = Self::bundled();
+        table.extend(Self::parse(&source)?);
+        Ok(table)
+    }
+
+    /// Merge another table into this one, replacing landmarks with the same name
+    pub fn extend(&mut self, other: AliasTable) {
+        for landmark in other.landmarks {
+            let key = normalize(&landmark.name);
+            self.landmarks
+                .retain(|existing| normalize(&existing.name) != key);
+            self.landmarks.push(landmark);
+        }
+    }
+
+    /// Find the landmark designated by a free-text query
+    #[must_use]
+    pub fn lookup(&self, query: &str) -> Option<&LandmarkAlias> {
+        let normalized = normalize(query);
+        if normalized.is_empty() {
+            return None;
+        }
+        self.landmarks
+            .iter()
+            .find(|landmark| landmark.matches(&normalized))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.landmarks.is_empty()
+    }
+}
+
+/// Lowercase, strip French diacritics and collapse punctuation to single spaces
+#[must_use]
+pub fn normalize(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à' | 'â' | 'ä' | 'á' => folded.push('a'),
+            'é' | 'è' | 'ê' | 'ë' => folded.push('e'),
+            'î' | 'ï' | 'í' => folded.push('i'),
+            'ô' | 'ö' | 'ó' => folded.push('o'),
+            'ù' | 'û' | 'ü' | 'ú' => folded.push('u'),
+            'ÿ' => folded.push('y'),
+            'ç' => folded.push('c'),
+            'œ' => folded.push_str("oe"),
+            'æ' => folded.push_str("ae"),
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ServiceCapabilities, StationReference};
+
+    fn station(code: &str, latitude: f64, longitude: f64) -> VelibStation {
+        VelibStation::new(StationReference {
+            station_code: code.into(),
+            name: format!("Station {code}").into(),
+            coordinates: Coordinates::new(latitude, longitude),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        })
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Sacré-Cœur"), "sacre coeur");
+        assert_eq!(normalize("  Gare de l'Est "), "gare de l est");
+        assert_eq!(normalize("BnF"), "bnf");
+    }
+
+    #[test]
+    fn test_bundled_table_lookup() {
+        let table = AliasTable::bundled();
+        assert!(!table.is_empty());
+
+        assert_eq!(
+            table.lookup("bnf").unwrap().name,
+            "Bibliothèque François Mitterrand"
+        );
+        assert_eq!(table.lookup("sacre coeur").unwrap().name, "Sacré-Cœur");
+        assert_eq!(table.lookup("GARE DE LYON").unwrap().name, "Gare de Lyon");
+        assert!(table.lookup("nowhere in particular").is_none());
+    }
+
+    #[test]
+    fn test_resolve_by_radius_and_codes() {
+        let landmark = LandmarkAlias {
+            name: "Test".to_string(),
+            aliases: vec![],
+            coordinates: Some(Coordinates::new(48.8566, 2.3522)),
+            station_codes: vec!["far_listed".to_string()],
+            radius_meters: Some(300),
+        };
+        let stations = vec![
+            station("near", 48.8570, 2.3525),
+            station("far", 48.8700, 2.3700),
+            station("far_listed", 48.8800, 2.3800),
+        ];
+
+        let codes: Vec<&str> = landmark
+            .resolve(&stations)
+            .iter()
+            .map(|s| &*s.reference.station_code)
+            .collect();
+        assert_eq!(codes, vec!["near", "far_listed"]);
+    }
+
+    #[test]
+    fn test_extend_overrides_by_name() {
+        let mut table = AliasTable::bundled();
+        let count = table.len();
+        let overrides = AliasTable::parse(
+            r#"
+            [[landmark]]
+            name = "gare de lyon"
+            station_codes = ["12001"]
+
+            [[landmark]]
+            name = "Campus Jussieu"
+            aliases = ["Jussieu"]
+            coordinates = { latitude = 48.8462, longitude = 2.3550 }
+            "#,
+        )
+        .unwrap();
+
+        table.extend(overrides);
+        assert_eq!(table.len(), count + 1);
+        assert_eq!(
+            table.lookup("Gare de Lyon").unwrap().station_codes,
+            vec!["12001"]
+        );
+        assert!(table.lookup("jussieu").is_some());
+    }
+
+    #[test]
+    fn test_parse_rejects_unresolvable_landmark() {
+        let result = AliasTable::parse("[[landmark]]\nname = \"Nowhere\"\n");
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+}
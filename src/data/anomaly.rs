@@ -0,0 +1,286 @@
+use crate::types::{RealTimeStatus, StationStatus};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// How far back the citywide baseline looks
+pub const BASELINE_HOURS: i64 = 12;
+
+/// Refreshes needed in the baseline before anything is flagged
+const MIN_BASELINE_SAMPLES: usize = 6;
+
+/// Share of the usual bikes missing before a shortage is flagged, then escalated
+const BIKE_SHORTAGE_RATIO: f64 = 0.35;
+const SEVERE_BIKE_SHORTAGE_RATIO: f64 = 0.6;
+
+/// Extra share of all stations closed before closures are flagged, then escalated
+const CLOSURE_SURGE_SHARE: f64 = 0.1;
+const SEVERE_CLOSURE_SURGE_SHARE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdvisoryKind {
+    /// Far fewer bikes docked across the city than usual
+    BikeShortage,
+    /// Far more stations closed or in maintenance than usual
+    StationClosures,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdvisorySeverity {
+    Warning,
+    Critical,
+}
+
+/// An unusual citywide condition, such as a strike day or a system outage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    pub kind: AdvisoryKind,
+    pub severity: AdvisorySeverity,
+    /// First refresh the condition was seen in
+    pub since: DateTime<Utc>,
+    /// Bikes docked, or stations closed, in the latest refresh
+    pub observed: u32,
+    /// The same figure averaged over the baseline
+    pub expected: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CitywideSample {
+    at: DateTime<Utc>,
+    bikes: u32,
+    closed: u32,
+    stations: u32,
+}
+
+impl CitywideSample {
+    fn of(snapshot: &HashMap<String, RealTimeStatus>, at: DateTime<Utc>) -> Self {
+        let mut sample = Self {
+            at,
+            bikes: 0,
+            closed: 0,
+            stations: snapshot.len() as u32,
+        };
+        for status in snapshot.values() {
+            sample.bikes += u32::from(status.bikes.total());
+            if status.status != StationStatus::Open {
+                sample.closed += 1;
+            }
+        }
+        sample
+    }
+
+    fn closed_share(&self) -> f64 {
+        f64::from(self.closed) / f64::from(self.stations.max(1))
+    }
+}
+
+/// Flags citywide drops in docked bikes and surges of closed stations
+///
+/// Each refresh is compared with the average of the unremarkable refreshes of
+/// the last `BASELINE_HOURS`. Anomalous refreshes stay out of the baseline, so
+/// an outage does not lower the bar for itself; one lasting longer than the
+/// baseline window is eventually taken as the new normal.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    window: Duration,
+    baseline: VecDeque<CitywideSample>,
+    active: Vec<Advisory>,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(Duration::hours(BASELINE_HOURS))
+    }
+}
+
+impl AnomalyDetector {
+    #[must_use]
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            baseline: VecDeque::new(),
+            active: Vec::new(),
+        }
+    }
+
+    /// Record a refreshed snapshot; returns advisories raised or escalated by it
+    pub fn observe(
+        &mut self,
+        snapshot: &HashMap<String, RealTimeStatus>,
+        now: DateTime<Utc>,
+    ) -> Vec<Advisory> {
+        if snapshot.is_empty() {
+            return Vec::new();
+        }
+        let sample = CitywideSample::of(snapshot, now);
+        while self
+            .baseline
+            .front()
+            .is_some_and(|oldest| now - oldest.at > self.window)
+        {
+            self.baseline.pop_front();
+        }
+
+        let detected = self.detect(&sample);
+        let mut raised = Vec::new();
+        for mut advisory in detected.iter().cloned() {
+            match self
+                .active
+                .iter()
+                .find(|active| active.kind == advisory.kind)
+            {
+                Some(previous) => {
+                    advisory.since = previous.since;
+                    if advisory.severity > previous.severity {
+                        raised.push(advisory.clone());
+                    }
+                }
+                None => raised.push(advisory.clone()),
+            }
+            self.active.retain(|active| active.kind != advisory.kind);
+            self.active.push(advisory);
+        }
+        self.active
+            .retain(|active| detected.iter().any(|found| found.kind == active.kind));
+
+        if detected.is_empty() {
+            self.baseline.push_back(sample);
+        }
+        raised
+    }
+
+    /// Conditions flagged by the latest refresh
+    #[must_use]
+    pub fn advisories(&self) -> Vec<Advisory> {
+        self.active.clone()
+    }
+
+    fn detect(&self, sample: &CitywideSample) -> Vec<Advisory> {
+        if self.baseline.len() < MIN_BASELINE_SAMPLES {
+            return Vec::new();
+        }
+        let count = self.baseline.len() as f64;
+        let expected_bikes = self
+            .baseline
+            .iter()
+            .map(|s| f64::from(s.bikes))
+            .sum::<f64>()
+            / count;
+        let expected_closed_share = self
+            .baseline
+            .iter()
+            .map(CitywideSample::closed_share)
+            .sum::<f64>()
+            / count;
+
+        let mut advisories = Vec::new();
+        if expected_bikes > 0.0 {
+            let missing = 1.0 - f64::from(sample.bikes) / expected_bikes;
+            if missing >= BIKE_SHORTAGE_RATIO {
+                advisories.push(Advisory {
+                    kind: AdvisoryKind::BikeShortage,
+                    severity: if missing >= SEVERE_BIKE_SHORTAGE_RATIO {
+                        AdvisorySeverity::Critical
+                    } else {
+                        AdvisorySeverity::Warning
+                    },
+                    since: sample.at,
+                    observed: sample.bikes,
+                    expected: expected_bikes.round() as u32,
+                    message: format!(
+                        "{:.0}% fewer bikes docked citywide than usual",
+                        missing * 100.0
+                    ),
+                });
+            }
+        }
+
+        let surge = sample.closed_share() - expected_closed_share;
+        if surge >= CLOSURE_SURGE_SHARE {
+            advisories.push(Advisory {
+                kind: AdvisoryKind::StationClosures,
+                severity: if surge >= SEVERE_CLOSURE_SURGE_SHARE {
+                    AdvisorySeverity::Critical
+                } else {
+                    AdvisorySeverity::Warning
+                },
+                since: sample.at,
+                observed: sample.closed,
+                expected: (expected_closed_share * f64::from(sample.stations)).round() as u32,
+                message: format!(
+                    "{} of {} stations closed, usually about {:.0}",
+                    sample.closed,
+                    sample.stations,
+                    expected_closed_share * f64::from(sample.stations)
+                ),
+            });
+        }
+        advisories
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::BikeAvailability;
+
+    fn snapshot(bikes_per_station: u16, closed: usize) -> HashMap<String, RealTimeStatus> {
+        (0..20)
+            .map(|i| {
+                let status = if i < closed {
+                    StationStatus::Closed
+                } else {
+                    StationStatus::Open
+                };
+                (
+                    format!("{i}"),
+                    RealTimeStatus::new(
+                        BikeAvailability::new(bikes_per_station, 0),
+                        10,
+                        status,
+                        Utc::now(),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_citywide_drops_and_closures() {
+        let start = Utc::now();
+        let mut detector = AnomalyDetector::default();
+        for minute in 0..MIN_BASELINE_SAMPLES as i64 {
+            let at = start + Duration::minutes(minute);
+            assert!(detector.observe(&snapshot(10, 1), at).is_empty());
+        }
+        assert!(detector.advisories().is_empty());
+
+        // Strike day: half the bikes gone and a quarter of stations closed
+        let strike_at = start + Duration::minutes(10);
+        let raised = detector.observe(&snapshot(5, 5), strike_at);
+        assert_eq!(raised.len(), 2);
+        let shortage = &raised[0];
+        assert_eq!(shortage.kind, AdvisoryKind::BikeShortage);
+        assert_eq!(shortage.severity, AdvisorySeverity::Warning);
+        assert_eq!((shortage.observed, shortage.expected), (100, 200));
+        assert_eq!(raised[1].kind, AdvisoryKind::StationClosures);
+        assert_eq!(raised[1].expected, 1);
+
+        // Still going: nothing new raised, but escalation is
+        let later = strike_at + Duration::minutes(1);
+        assert!(detector.observe(&snapshot(5, 5), later).is_empty());
+        let raised = detector.observe(&snapshot(2, 5), later + Duration::minutes(1));
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].severity, AdvisorySeverity::Critical);
+        assert_eq!(raised[0].since, strike_at);
+        assert_eq!(detector.advisories().len(), 2);
+
+        // The outage did not drag the baseline down, and recovery clears it
+        let recovered = detector.observe(&snapshot(10, 1), later + Duration::minutes(2));
+        assert!(recovered.is_empty());
+        assert!(detector.advisories().is_empty());
+    }
+}
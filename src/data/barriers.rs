@@ -0,0 +1,198 @@
+use crate::data::aliases::normalize;
+use crate::geometry::segments_cross;
+use crate::types::Coordinates;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const BUNDLED_BARRIERS: &str = include_str!("barriers.toml");
+
+/// A place a barrier can be crossed on foot, e.g. a bridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Crossing {
+    pub name: String,
+    #[serde(flatten)]
+    pub coordinates: Coordinates,
+}
+
+/// A river or railway walkers can only cross at its crossings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Barrier {
+    pub name: String,
+    pub line: Vec<Coordinates>,
+    pub crossings: Vec<Crossing>,
+}
+
+impl Barrier {
+    /// Whether the straight walk from `from` to `to` runs across this barrier
+    fn separates(&self, from: &Coordinates, to: &Coordinates) -> bool {
+        self.line
+            .windows(2)
+            .any(|segment| segments_cross(from, to, &segment[0], &segment[1]))
+    }
+
+    /// Shortest walk from `from` to `to` through one of the crossings
+    fn crossed_distance(&self, from: &Coordinates, to: &Coordinates) -> Option<f64> {
+        self.crossings
+            .iter()
+            .map(|crossing| {
+                from.distance_to(&crossing.coordinates) + crossing.coordinates.distance_to(to)
+            })
+            .min_by(f64::total_cmp)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BarrierFile {
+    #[serde(default)]
+    barrier: Vec<Barrier>,
+}
+
+/// Rivers and railways that lengthen walks between the stations on either side
+#[derive(Debug, Clone, Default)]
+pub struct BarrierMap {
+    barriers: Vec<Barrier>,
+}
+
+impl BarrierMap {
+    /// The barriers shipped with the server
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_BARRIERS).expect("bundled barrier map must be valid")
+    }
+
+    /// Parse barriers from TOML
+    pub fn parse(source: &str) -> Result<Self> {
+        let file: BarrierFile = toml::from_str(source)
+            .map_err(|e| Error::Config(format!("Invalid barrier map: {e}")))?;
+
+        for barrier in &file.barrier {
+            if barrier.line.len() < 2 || barrier.crossings.is_empty() {
+                return Err(Error::Config(format!(
+                    "Barrier '{}' needs at least 2 line points and a crossing",
+                    barrier.name
+                )));
+            }
+        }
+
+        Ok(Self {
+            barriers: file.barrier,
+        })
+    }
+
+    /// Load the bundled barriers extended with the entries of a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read barrier map {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+
+        let mut map = Self::bundled();
+        map.extend(Self::parse(&source)?);
+        Ok(map)
+    }
+
+    /// Merge another map into this one, replacing barriers with the same name
+    pub fn extend(&mut self, other: BarrierMap) {
+        for barrier in other.barriers {
+            let key = normalize(&barrier.name);
+            self.barriers
+                .retain(|existing| normalize(&existing.name) != key);
+            self.barriers.push(barrier);
+        }
+    }
+
+    /// Walking distance from `from` to `to` in meters
+    ///
+    /// The straight-line distance, lengthened by the detour through the best
+    /// crossing of every barrier the straight line runs across. Detours are
+    /// added up barrier by barrier, which is close enough for walks of a few
+    /// hundred meters.
+    #[must_use]
+    pub fn walking_distance(&self, from: &Coordinates, to: &Coordinates) -> f64 {
+        let direct = from.distance_to(to);
+        let detours: f64 = self
+            .barriers
+            .iter()
+            .filter(|barrier| barrier.separates(from, to))
+            .filter_map(|barrier| barrier.crossed_distance(from, to))
+            .map(|crossed| (crossed - direct).max(0.0))
+            .sum();
+        direct + detours
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.barriers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.barriers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_map_is_valid() {
+        let map = BarrierMap::bundled();
+        assert!(map.len() >= 2);
+    }
+
+    #[test]
+    fn test_walks_across_the_seine_go_through_a_bridge() {
+        let map = BarrierMap::bundled();
+
+        // Quai de la Rapée to Quai d'Austerlitz, just downstream of Pont d'Austerlitz
+        let right_bank = Coordinates::new(48.8458, 2.3672);
+        let left_bank = Coordinates::new(48.8437, 2.3630);
+        let direct = right_bank.distance_to(&left_bank);
+        let walked = map.walking_distance(&right_bank, &left_bank);
+        assert!(walked >= direct);
+        assert!(walked - direct < 100.0, "{walked} vs {direct}");
+
+        // Quai Saint-Bernard to Quai de la Rapée between bridges: via Pont
+        // d'Austerlitz or Pont de Sully
+        let left_bank = Coordinates::new(48.8475, 2.3600);
+        let right_bank = Coordinates::new(48.8478, 2.3660);
+        let direct = left_bank.distance_to(&right_bank);
+        let walked = map.walking_distance(&left_bank, &right_bank);
+        assert!(walked > direct + 150.0, "{walked} vs {direct}");
+
+        // Same bank: unchanged
+        let hotel_de_ville = Coordinates::new(48.8566, 2.3522);
+        let chatelet = Coordinates::new(48.8584, 2.3474);
+        assert_eq!(
+            map.walking_distance(&hotel_de_ville, &chatelet),
+            hotel_de_ville.distance_to(&chatelet)
+        );
+    }
+
+    #[test]
+    fn test_custom_barriers_replace_bundled_ones() {
+        let mut map = BarrierMap::bundled();
+        let count = map.len();
+        map.extend(
+            BarrierMap::parse(
+                r#"
+                [[barrier]]
+                name = "seine"
+                line = [{ latitude = 48.0, longitude = 2.0 }, { latitude = 48.1, longitude = 2.0 }]
+                crossings = [{ name = "Gué", latitude = 48.05, longitude = 2.0 }]
+                "#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(map.len(), count);
+
+        assert!(BarrierMap::parse(
+            "[[barrier]]\nname = \"x\"\nline = [{ latitude = 48.0, longitude = 2.0 }]\ncrossings = []"
+        )
+        .is_err());
+    }
+}
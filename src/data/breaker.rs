@@ -0,0 +1,104 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Consecutive failed upstream fetches before the breaker opens
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open breaker rejects fetches before letting a trial through
+pub const DEFAULT_COOLDOWN_SECONDS: i64 = 30;
+
+/// Stops hammering an upstream that keeps failing
+///
+/// After `failure_threshold` consecutive failures the breaker opens and
+/// fetches fail fast for `cooldown`. The first fetch after that is a trial:
+/// success closes the breaker, failure opens it for another cooldown.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_FAILURE_THRESHOLD,
+            Duration::seconds(DEFAULT_COOLDOWN_SECONDS),
+        )
+    }
+}
+
+impl CircuitBreaker {
+    #[must_use]
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    /// Seconds until a fetch may be attempted, or `None` if it may go ahead now
+    #[must_use]
+    pub fn rejects(&self, now: DateTime<Utc>) -> Option<u64> {
+        self.open_until
+            .filter(|&until| now < until)
+            .map(|until| (until - now).num_seconds().max(1) as u64)
+    }
+
+    /// Whether fetches are currently failing fast or on trial
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.open_until.is_some()
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    pub fn record_failure(&mut self, now: DateTime<Utc>) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.is_open() || self.consecutive_failures >= self.failure_threshold {
+            self.open_until = Some(now + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_fails_fast() {
+        let now = Utc::now();
+        let mut breaker = CircuitBreaker::new(3, Duration::seconds(30));
+
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.rejects(now), None);
+
+        breaker.record_failure(now);
+        assert!(breaker.is_open());
+        assert_eq!(breaker.rejects(now + Duration::seconds(10)), Some(20));
+        assert_eq!(breaker.rejects(now + Duration::seconds(30)), None);
+    }
+
+    #[test]
+    fn test_trial_result_closes_or_reopens() {
+        let now = Utc::now();
+        let mut breaker = CircuitBreaker::new(1, Duration::seconds(30));
+        breaker.record_failure(now);
+
+        // A failed trial reopens right away, without waiting for the threshold
+        let trial = now + Duration::seconds(31);
+        breaker.record_failure(trial);
+        assert!(breaker.rejects(trial + Duration::seconds(1)).is_some());
+
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.rejects(trial + Duration::seconds(1)), None);
+    }
+}
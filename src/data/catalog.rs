@@ -0,0 +1,231 @@
+use crate::types::{Coordinates, StationReference};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default interval between reference catalog validations
+pub const DEFAULT_REFERENCE_REPORT_INTERVAL_HOURS: u64 = 24;
+
+/// A station moving less than this is taken as a coordinate correction, not a move
+pub const MOVE_THRESHOLD_METERS: f64 = 50.0;
+
+/// A station as listed in a report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogStation {
+    pub station_code: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvalidStation {
+    pub station_code: String,
+    pub name: String,
+    /// From [`StationReference::validate`]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenamedStation {
+    pub station_code: String,
+    pub previous_name: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovedStation {
+    pub station_code: String,
+    pub name: String,
+    pub previous_coordinates: Coordinates,
+    pub coordinates: Coordinates,
+    pub distance_meters: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResizedStation {
+    pub station_code: String,
+    pub name: String,
+    pub previous_capacity: u16,
+    pub capacity: u16,
+}
+
+/// Validation of the reference catalog and how it changed since the previous
+/// one, served as `velib://reports/reference-changes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceReport {
+    pub generated_at: DateTime<Utc>,
+    /// When the catalog compared against was validated; `None` on the first
+    /// validation, which lists no changes
+    pub compared_with: Option<DateTime<Utc>>,
+    pub total_stations: usize,
+    /// Stations failing validation, sorted by code
+    pub invalid: Vec<InvalidStation>,
+    pub added: Vec<CatalogStation>,
+    pub removed: Vec<CatalogStation>,
+    pub renamed: Vec<RenamedStation>,
+    /// Moved by at least `MOVE_THRESHOLD_METERS`
+    pub moved: Vec<MovedStation>,
+    pub resized: Vec<ResizedStation>,
+}
+
+/// What a validated catalog held, to compare the next one against
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceSnapshot {
+    taken_at: Option<DateTime<Utc>>,
+    stations: HashMap<String, StationReference>,
+}
+
+impl ReferenceSnapshot {
+    /// Validate `stations` and report how they differ from this snapshot,
+    /// which then holds them
+    pub fn update(&mut self, stations: &[StationReference], now: DateTime<Utc>) -> ReferenceReport {
+        let current: HashMap<String, StationReference> = stations
+            .iter()
+            .map(|station| (station.station_code.to_string(), station.clone()))
+            .collect();
+        let listed = |station: &StationReference| CatalogStation {
+            station_code: station.station_code.to_string(),
+            name: station.name.to_string(),
+        };
+
+        let mut report = ReferenceReport {
+            generated_at: now,
+            compared_with: self.taken_at,
+            total_stations: current.len(),
+            invalid: Vec::new(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            renamed: Vec::new(),
+            moved: Vec::new(),
+            resized: Vec::new(),
+        };
+        for station in current.values() {
+            if let Err(reason) = station.validate() {
+                report.invalid.push(InvalidStation {
+                    station_code: station.station_code.to_string(),
+                    name: station.name.to_string(),
+                    reason,
+                });
+            }
+        }
+        if self.taken_at.is_some() {
+            for (code, station) in &current {
+                let Some(previous) = self.stations.get(code) else {
+                    report.added.push(listed(station));
+                    continue;
+                };
+                if previous.name != station.name {
+                    report.renamed.push(RenamedStation {
+                        station_code: code.clone(),
+                        previous_name: previous.name.to_string(),
+                        name: station.name.to_string(),
+                    });
+                }
+                let moved = previous.coordinates.distance_to(&station.coordinates);
+                if moved >= MOVE_THRESHOLD_METERS {
+                    report.moved.push(MovedStation {
+                        station_code: code.clone(),
+                        name: station.name.to_string(),
+                        previous_coordinates: previous.coordinates,
+                        coordinates: station.coordinates,
+                        distance_meters: moved.round() as u32,
+                    });
+                }
+                if previous.capacity != station.capacity {
+                    report.resized.push(ResizedStation {
+                        station_code: code.clone(),
+                        name: station.name.to_string(),
+                        previous_capacity: previous.capacity,
+                        capacity: station.capacity,
+                    });
+                }
+            }
+            report.removed = self
+                .stations
+                .iter()
+                .filter(|(code, _)| !current.contains_key(*code))
+                .map(|(_, station)| listed(station))
+                .collect();
+        }
+
+        report
+            .invalid
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        report
+            .added
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        report
+            .removed
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        report
+            .renamed
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        report
+            .moved
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        report
+            .resized
+            .sort_by(|a, b| a.station_code.cmp(&b.station_code));
+
+        self.taken_at = Some(now);
+        self.stations = current;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ServiceCapabilities;
+    use chrono::Duration;
+
+    fn station(code: &str, name: &str, latitude: f64, capacity: u16) -> StationReference {
+        StationReference {
+            station_code: code.into(),
+            name: name.into(),
+            coordinates: Coordinates::new(latitude, 2.3737),
+            capacity,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    #[test]
+    fn test_catalog_changes_since_previous_day() {
+        let start = Utc::now();
+        let mut snapshot = ReferenceSnapshot::default();
+        let first = snapshot.update(
+            &[
+                station("12001", "Gare de Lyon - Diderot", 48.8446, 30),
+                station("12002", "Gare de Lyon - Chalon", 48.8440, 20),
+                station("12003", "Bercy", 48.8400, 25),
+                station("12004", "Daumesnil", 48.8390, 25),
+            ],
+            start,
+        );
+        assert_eq!(first.compared_with, None);
+        assert!(first.added.is_empty() && first.invalid.is_empty());
+
+        let next = snapshot.update(
+            &[
+                station("12001", "Gare de Lyon - Diderot", 48.8447, 30),
+                station("12002", "Gare de Lyon - Van Gogh", 48.8440, 20),
+                station("12003", "Bercy", 48.8420, 0),
+                station("12005", "Reuilly", 48.8470, 22),
+            ],
+            start + Duration::days(1),
+        );
+        assert_eq!(next.compared_with, Some(start));
+        assert_eq!(next.total_stations, 4);
+        assert_eq!(next.added[0].station_code, "12005");
+        assert_eq!(next.removed[0].station_code, "12004");
+        assert_eq!(next.renamed[0].previous_name, "Gare de Lyon - Chalon");
+        // 12001 moved about 11m, within the correction threshold
+        assert_eq!(next.moved.len(), 1);
+        assert_eq!(next.moved[0].station_code, "12003");
+        assert!(next.moved[0].distance_meters > 200);
+        assert_eq!(next.resized[0].capacity, 0);
+        assert_eq!(next.invalid[0].station_code, "12003");
+    }
+}
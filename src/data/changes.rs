@@ -0,0 +1,385 @@
+use crate::events::StationUpdate;
+use crate::storage::Storage;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Refreshes with changes kept in memory by default, whatever their age
+pub const CHANGE_LOG_REFRESHES: usize = 120;
+/// Hours of refreshes kept in memory by default
+pub const CHANGE_RETENTION_HOURS: i64 = 2;
+/// Hours spilled refreshes stay in storage by default
+pub const CHANGE_SPILL_HOURS: i64 = 24;
+/// Storage log namespace of the refreshes past memory retention
+pub const CHANGE_LOG_NAMESPACE: &str = "changes";
+
+/// A station whose availability differs from a baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationChange {
+    /// `None` when the station was not in the baseline
+    pub previous: Option<StationUpdate>,
+    pub current: StationUpdate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChangeEntry {
+    snapshot: String,
+    at: DateTime<Utc>,
+    /// Station code to its state before and after this refresh
+    changes: HashMap<String, StationChange>,
+}
+
+/// How much of the change log stays in memory, and how long what leaves it
+/// stays in storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeRetention {
+    /// Refreshes older than this leave memory
+    pub memory: Duration,
+    /// At most this many refreshes stay in memory, the oldest leaving first
+    pub max_entries: usize,
+    /// Spilled refreshes older than this are pruned from storage
+    pub spilled: Duration,
+}
+
+impl Default for ChangeRetention {
+    fn default() -> Self {
+        Self {
+            memory: Duration::hours(CHANGE_RETENTION_HOURS),
+            max_entries: CHANGE_LOG_REFRESHES,
+            spilled: Duration::hours(CHANGE_SPILL_HOURS),
+        }
+    }
+}
+
+/// Retention settings and what the change log holds, reported by `velib://health`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeLogStats {
+    pub entries: usize,
+    /// Station changes held across those entries
+    pub station_changes: usize,
+    pub oldest: Option<DateTime<Utc>>,
+    pub retention_hours: f64,
+    pub max_entries: usize,
+    /// Whether refreshes past retention are spilled to storage rather than dropped
+    pub spills_to_storage: bool,
+    pub spilled_retention_hours: f64,
+    /// Refreshes spilled since startup
+    pub spilled_entries: u64,
+}
+
+/// The stations changed by each recent real-time refresh, so that a client
+/// holding an older snapshot id only fetches what differs since
+///
+/// Snapshot ids hash the published state, so a refresh that changes nothing
+/// keeps the previous id and adds no entry. Refreshes past [`ChangeRetention`]
+/// are dropped, or spilled to storage when there is one, where baselines
+/// older than memory are then looked up.
+#[derive(Clone, Default)]
+pub struct ChangeLog {
+    entries: VecDeque<ChangeEntry>,
+    retention: ChangeRetention,
+    storage: Option<Arc<dyn Storage>>,
+    spilled: u64,
+}
+
+impl std::fmt::Debug for ChangeLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangeLog")
+            .field("entries", &self.entries.len())
+            .field("retention", &self.retention)
+            .field("storage", &self.storage.is_some())
+            .field("spilled", &self.spilled)
+            .finish()
+    }
+}
+
+impl ChangeLog {
+    #[must_use]
+    pub fn with_retention(mut self, retention: ChangeRetention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Spill refreshes past retention to `storage` instead of dropping them
+    #[must_use]
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Log the stations differing between two published states
+    pub fn record(
+        &mut self,
+        previous: &HashMap<String, StationUpdate>,
+        current: &HashMap<String, StationUpdate>,
+        at: DateTime<Utc>,
+    ) {
+        let changes: HashMap<String, StationChange> = current
+            .iter()
+            .filter(|(code, update)| previous.get(*code) != Some(update))
+            .map(|(code, update)| {
+                let change = StationChange {
+                    previous: previous.get(code).cloned(),
+                    current: update.clone(),
+                };
+                (code.clone(), change)
+            })
+            .collect();
+        if changes.is_empty() && !self.entries.is_empty() {
+            return;
+        }
+        self.entries.push_back(ChangeEntry {
+            snapshot: snapshot_id(current),
+            at,
+            changes,
+        });
+        self.evict(at);
+    }
+
+    /// Move refreshes past retention out of memory, always keeping the latest
+    fn evict(&mut self, now: DateTime<Utc>) {
+        let oldest_kept = now - self.retention.memory;
+        while self.entries.len() > 1
+            && (self.entries.len() > self.retention.max_entries
+                || self
+                    .entries
+                    .front()
+                    .is_some_and(|entry| entry.at < oldest_kept))
+        {
+            let Some(entry) = self.entries.pop_front() else {
+                break;
+            };
+            if let Some(storage) = &self.storage {
+                let spilled = serde_json::to_vec(&entry)
+                    .map_err(crate::Error::from)
+                    .and_then(|bytes| storage.append(CHANGE_LOG_NAMESPACE, entry.at, &bytes));
+                match spilled {
+                    Ok(()) => self.spilled += 1,
+                    Err(e) => warn!("Failed to spill change log entry: {}", e),
+                }
+            }
+        }
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.prune_log(CHANGE_LOG_NAMESPACE, now - self.retention.spilled) {
+                warn!("Failed to prune spilled change log: {}", e);
+            }
+        }
+    }
+
+    /// Refreshes spilled to storage, oldest first
+    fn spilled_entries(&self) -> Vec<ChangeEntry> {
+        let Some(storage) = &self.storage else {
+            return Vec::new();
+        };
+        match storage.read_log(CHANGE_LOG_NAMESPACE, DateTime::<Utc>::MIN_UTC) {
+            Ok(entries) => entries
+                .into_iter()
+                .filter_map(|(_, bytes)| serde_json::from_slice(&bytes).ok())
+                .collect(),
+            Err(e) => {
+                warn!("Failed to read spilled change log: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Retention settings and how much the log holds
+    #[must_use]
+    pub fn stats(&self) -> ChangeLogStats {
+        let hours = |duration: Duration| duration.num_seconds() as f64 / 3600.0;
+        ChangeLogStats {
+            entries: self.entries.len(),
+            station_changes: self.entries.iter().map(|entry| entry.changes.len()).sum(),
+            oldest: self.entries.front().map(|entry| entry.at),
+            retention_hours: hours(self.retention.memory),
+            max_entries: self.retention.max_entries,
+            spills_to_storage: self.storage.is_some(),
+            spilled_retention_hours: hours(self.retention.spilled),
+            spilled_entries: self.spilled,
+        }
+    }
+
+    /// Id and time of the latest published state
+    #[must_use]
+    pub fn latest(&self) -> Option<(&str, DateTime<Utc>)> {
+        self.entries
+            .back()
+            .map(|entry| (entry.snapshot.as_str(), entry.at))
+    }
+
+    /// Stations differing from the state with id `snapshot`, sorted by code;
+    /// `None` when that state is no longer (or was never) logged
+    #[must_use]
+    pub fn since_snapshot(&self, snapshot: &str) -> Option<Vec<StationChange>> {
+        self.changes_since(|entry| entry.snapshot == snapshot)
+    }
+
+    /// Stations differing from the state published at `at`; `None` when the
+    /// log does not reach back that far
+    #[must_use]
+    pub fn since_time(&self, at: DateTime<Utc>) -> Option<Vec<StationChange>> {
+        self.changes_since(|entry| entry.at <= at)
+    }
+
+    /// Net changes after the latest entry matching `baseline`, in memory or else spilled
+    fn changes_since(&self, baseline: impl Fn(&ChangeEntry) -> bool) -> Option<Vec<StationChange>> {
+        if let Some(index) = self.entries.iter().rposition(&baseline) {
+            return Some(net_changes(self.entries.iter().skip(index + 1)));
+        }
+        let spilled = self.spilled_entries();
+        let index = spilled.iter().rposition(&baseline)?;
+        Some(net_changes(
+            spilled.iter().skip(index + 1).chain(&self.entries),
+        ))
+    }
+}
+
+fn net_changes<'a>(entries: impl Iterator<Item = &'a ChangeEntry>) -> Vec<StationChange> {
+    let mut net: HashMap<&str, StationChange> = HashMap::new();
+    for entry in entries {
+        for (code, change) in &entry.changes {
+            net.entry(code)
+                .and_modify(|net| net.current = change.current.clone())
+                .or_insert_with(|| change.clone());
+        }
+    }
+    let mut changes: Vec<StationChange> = net
+        .into_values()
+        .filter(|change| change.previous.as_ref() != Some(&change.current))
+        .collect();
+    changes.sort_by(|a, b| a.current.station_code.cmp(&b.current.station_code));
+    changes
+}
+
+/// Short hex hash of a published state, independent of map order
+fn snapshot_id(state: &HashMap<String, StationUpdate>) -> String {
+    format!("{:016x}", state_hash(state))
+}
+
+/// Hash of the bikes, docks and status of every station, independent of map
+/// order
+pub(crate) fn state_hash(state: &HashMap<String, StationUpdate>) -> u64 {
+    let mut codes: Vec<&String> = state.keys().collect();
+    codes.sort();
+    let mut hasher = DefaultHasher::new();
+    for code in codes {
+        let update = &state[code];
+        code.hash(&mut hasher);
+        update.bikes.mechanical.hash(&mut hasher);
+        update.bikes.electric.hash(&mut hasher);
+        update.available_docks.hash(&mut hasher);
+        format!("{:?}", update.status).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, RealTimeStatus, StationStatus};
+    use chrono::Duration;
+
+    fn state(bikes: &[(&str, u16)]) -> HashMap<String, StationUpdate> {
+        bikes
+            .iter()
+            .map(|&(code, mechanical)| {
+                let status = RealTimeStatus::new(
+                    BikeAvailability::new(mechanical, 0),
+                    10,
+                    StationStatus::Open,
+                    Utc::now(),
+                );
+                (code.to_string(), StationUpdate::new(code, &status))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_changes_since_a_snapshot_are_netted() {
+        let start = Utc::now();
+        let first = state(&[("1", 5), ("2", 5)]);
+        let second = state(&[("1", 6), ("2", 5)]);
+        let third = state(&[("1", 5), ("2", 4), ("3", 1)]);
+
+        let mut log = ChangeLog::default();
+        log.record(&HashMap::new(), &first, start);
+        let (baseline, _) = log.latest().unwrap();
+        let baseline = baseline.to_string();
+        assert_eq!(log.since_snapshot(&baseline), Some(vec![]));
+
+        log.record(&first, &second, start + Duration::minutes(1));
+        // Unchanged refreshes keep the id
+        let (unchanged, _) = log.latest().unwrap();
+        let unchanged = unchanged.to_string();
+        log.record(&second, &second, start + Duration::minutes(2));
+        assert_eq!(log.latest().unwrap().0, unchanged);
+        log.record(&second, &third, start + Duration::minutes(3));
+
+        // Station 1 went back to 5 bikes, so only 2 and the new 3 differ
+        let changes = log.since_snapshot(&baseline).unwrap();
+        let codes: Vec<&str> = changes
+            .iter()
+            .map(|change| change.current.station_code.as_str())
+            .collect();
+        assert_eq!(codes, vec!["2", "3"]);
+        assert!(changes[1].previous.is_none());
+
+        let since_second = log.since_time(start + Duration::minutes(2)).unwrap();
+        assert_eq!(since_second.len(), 3);
+        assert!(log.since_time(start - Duration::minutes(1)).is_none());
+        assert!(log.since_snapshot("0000000000000000").is_none());
+    }
+
+    #[test]
+    fn test_refreshes_past_retention_spill_to_storage() {
+        let start = Utc::now();
+        let retention = ChangeRetention {
+            memory: Duration::minutes(10),
+            max_entries: 3,
+            spilled: Duration::minutes(30),
+        };
+        let storage = crate::storage::open(crate::storage::MEMORY_URL).unwrap();
+        let mut log = ChangeLog::default()
+            .with_retention(retention)
+            .with_storage(Arc::clone(&storage));
+        let mut dropping = ChangeLog::default().with_retention(retention);
+
+        let mut previous = HashMap::new();
+        let mut snapshots = Vec::new();
+        for minute in 0..6 {
+            let current = state(&[("1", minute)]);
+            let at = start + Duration::minutes(i64::from(minute));
+            log.record(&previous, &current, at);
+            dropping.record(&previous, &current, at);
+            snapshots.push(log.latest().unwrap().0.to_string());
+            previous = current;
+        }
+        let stats = log.stats();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.spilled_entries, 3);
+        assert!(stats.spills_to_storage);
+        assert_eq!(stats.oldest, Some(start + Duration::minutes(3)));
+
+        // The first baselines left memory but are read back from storage
+        let changes = log.since_snapshot(&snapshots[0]).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].current.bikes.mechanical, 5);
+        assert!(log.since_time(start + Duration::minutes(1)).is_some());
+        assert!(dropping.since_snapshot(&snapshots[0]).is_none());
+        assert_eq!(dropping.stats().spilled_entries, 0);
+
+        // Past the age limit only the latest refresh stays, and old spills are pruned
+        let later = start + Duration::hours(1);
+        log.record(&previous, &state(&[("1", 9)]), later);
+        assert_eq!(log.stats().entries, 1);
+        assert!(log.since_snapshot(&snapshots[0]).is_none());
+        assert_eq!(
+            storage.read_log(CHANGE_LOG_NAMESPACE, start).unwrap().len(),
+            0
+        );
+    }
+}
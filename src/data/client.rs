@@ -1,28 +1,148 @@
+use crate::clock::Clock;
+use crate::data::anomaly::{Advisory, AnomalyDetector};
 use crate::data::cache::InMemoryCache;
+use crate::data::catalog::{ReferenceReport, ReferenceSnapshot};
+use crate::data::changes::{ChangeLog, ChangeRetention};
+use crate::data::conditional::{ConditionalCache, Validators};
+use crate::data::enriched::EnrichedStation;
+use crate::data::export::HistoryRecord;
+use crate::data::fleet::{FleetHistory, FleetSample};
+use crate::data::gbfs::{parse_station_information, StationIdMapping, ID_MAPPING_TTL_MINUTES};
+use crate::data::history::{AvailabilityHistory, HistoryStats};
+use crate::data::intern::StringInterner;
+use crate::data::kiosk::KioskCodeIndex;
+use crate::data::memory::{resident_bytes, MemoryGuard, MemoryStats};
+use crate::data::merge::{merge_duplicates, DuplicatePolicy};
+use crate::data::mirrors::UpstreamMirrors;
+use crate::data::outage::OutageDetector;
+use crate::data::probe::{ProbeOutcome, UpstreamProbe};
 use crate::data::retry::{RetryConfig, RetryPolicy, RetryableHttpClient};
+use crate::data::scheduler::{FetchPriority, UpstreamScheduler};
+#[cfg(feature = "simulation")]
+use crate::data::simulation::SimulatedCity;
+use crate::data::trie::{NameTrie, StationNameSuggestion};
+use crate::data::version::DataVersioner;
+use crate::data::watchdog::{FeedStatus, FeedWatchdog};
+use crate::events::{EventBus, StationUpdate};
+use crate::metrics::Metrics;
+use crate::phases::{self, Phase};
+use crate::storage::Storage;
 use crate::types::{
-    BikeAvailability, RealTimeStatus, ServiceCapabilities, StationReference, StationStatus,
-    VelibStation,
+    AvailabilityThresholds, BikeAvailability, DataFreshness, RealTimeStatus, ServiceCapabilities,
+    StationReference, StationStatus, VelibStation,
 };
 use crate::{Error, Result};
 use chrono::{DateTime, Duration, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
-use tracing::{debug, info};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
 
 // Paris Open Data API endpoints
 const VELIB_STATIONS_URL: &str = "https://opendata.paris.fr/api/explore/v2.1/catalog/datasets/velib-emplacement-des-stations/records";
 const VELIB_REALTIME_URL: &str = "https://opendata.paris.fr/api/explore/v2.1/catalog/datasets/velib-disponibilite-en-temps-reel/records";
+// Velib Metropole GBFS feed, listing the ids other Velib datasets use
+const VELIB_GBFS_STATION_INFORMATION_URL: &str = "https://velib-metropole-opendata.smovengo.cloud/opendata/Velib_Metropole/station_information.json";
 
 // Cache TTLs
 const REFERENCE_CACHE_TTL_MINUTES: i64 = 5; // 5 minutes for reference data
-const REALTIME_CACHE_TTL_MINUTES: i64 = 2; // 2 minutes for real-time data
+const REALTIME_CACHE_TTL_MINUTES: i64 = 10; // 10 minutes, the most any tool tolerates
+
+/// Oldest real-time snapshot served to callers without their own freshness policy
+pub const DEFAULT_REALTIME_MAX_AGE_SECONDS: i64 = 120;
+
+// Reads of data whose newest duedate is younger than this count as fresh
+const FRESH_DATA_MINUTES: i64 = 5;
+
+// Records per upstream page, the API's limit
+const PAGE_SIZE: usize = 100;
+
+// Cache keys
+const REFERENCE_CACHE_KEY: &str = "all_reference_stations";
+const REALTIME_CACHE_KEY: &str = "all_realtime_status";
+
+/// A raw real-time snapshot with trends and frozen feed tags applied
+#[derive(Debug)]
+struct AnnotatedRealtime {
+    raw: Arc<HashMap<String, RealTimeStatus>>,
+    frozen: bool,
+    statuses: Arc<HashMap<String, RealTimeStatus>>,
+}
+
+/// Reference stations joined with a real-time snapshot
+#[derive(Debug)]
+struct MergedStations {
+    reference: Arc<[StationReference]>,
+    realtime: Option<Arc<HashMap<String, RealTimeStatus>>>,
+    stations: Arc<[EnrichedStation]>,
+}
+
+impl MergedStations {
+    fn is_of(
+        &self,
+        reference: &Arc<[StationReference]>,
+        realtime: Option<&Arc<HashMap<String, RealTimeStatus>>>,
+    ) -> bool {
+        Arc::ptr_eq(&self.reference, reference)
+            && match (&self.realtime, realtime) {
+                (Some(merged), Some(realtime)) => Arc::ptr_eq(merged, realtime),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
 
 #[derive(Debug)]
 pub struct VelibDataClient {
     client: RetryableHttpClient,
-    reference_cache: InMemoryCache<String, Vec<StationReference>>,
-    realtime_cache: InMemoryCache<String, HashMap<String, RealTimeStatus>>,
+    // Dataset endpoints, Paris Open Data unless pointed at another network
+    stations_url: String,
+    realtime_url: String,
+    gbfs_url: String,
+    // The dataset endpoints' circuit breaker, and mirrors to fail over to
+    upstreams: UpstreamMirrors,
+    scheduler: UpstreamScheduler,
+    reference_cache: InMemoryCache<String, Arc<[StationReference]>>,
+    realtime_cache: InMemoryCache<String, Arc<HashMap<String, RealTimeStatus>>>,
+    // Upstream pages with their ETag/Last-Modified, revalidated on refresh
+    pages: ConditionalCache,
+    // When the cached real-time snapshot was fetched, to judge it against a max age
+    realtime_fetched_at: Option<DateTime<Utc>>,
+    // Numbers the reference and real-time snapshots for `_meta.data_version`
+    versions: DataVersioner,
+    // What readers were last handed, reused until the snapshots behind it change
+    annotated: Option<AnnotatedRealtime>,
+    reference_only: Option<MergedStations>,
+    with_realtime: Option<MergedStations>,
+    name_index: Arc<NameTrie>,
+    code_index: Arc<KioskCodeIndex>,
+    // Station codes and names, shared by every snapshot that mentions them
+    interner: StringInterner,
+    watchdog: FeedWatchdog,
+    history: AvailabilityHistory,
+    fleet: FleetHistory,
+    // Trims the history and caches when resident memory passes a soft limit
+    memory: MemoryGuard,
+    anomalies: AnomalyDetector,
+    outages: OutageDetector,
+    // Which entry a station code listed twice in the reference dataset keeps
+    duplicate_policy: DuplicatePolicy,
+    availability_thresholds: AvailabilityThresholds,
+    metrics: Arc<Metrics>,
+    events: Arc<EventBus>,
+    // Last availability published on `events`, to only announce changes
+    published: HashMap<String, StationUpdate>,
+    changes: ChangeLog,
+    // Catalog as of the last validation, and what that validation found
+    reference_snapshot: ReferenceSnapshot,
+    reference_report: Option<ReferenceReport>,
+    // Station codes joined with GBFS ids, and when they were
+    id_mapping: Option<StationIdMapping>,
+    last_probe: Option<UpstreamProbe>,
+    clock: Arc<dyn Clock>,
+    // Serves a synthetic city instead of the upstream API
+    #[cfg(feature = "simulation")]
+    simulation: Option<Arc<SimulatedCity>>,
 }
 
 impl Default for VelibDataClient {
@@ -36,8 +156,41 @@ impl VelibDataClient {
     pub fn new() -> Self {
         Self {
             client: RetryableHttpClient::new(),
+            stations_url: VELIB_STATIONS_URL.to_string(),
+            realtime_url: VELIB_REALTIME_URL.to_string(),
+            gbfs_url: VELIB_GBFS_STATION_INFORMATION_URL.to_string(),
+            upstreams: UpstreamMirrors::default(),
+            scheduler: UpstreamScheduler::default(),
             reference_cache: InMemoryCache::new(Duration::minutes(REFERENCE_CACHE_TTL_MINUTES)),
             realtime_cache: InMemoryCache::new(Duration::minutes(REALTIME_CACHE_TTL_MINUTES)),
+            pages: ConditionalCache::default(),
+            realtime_fetched_at: None,
+            versions: DataVersioner::default(),
+            annotated: None,
+            reference_only: None,
+            with_realtime: None,
+            name_index: Arc::default(),
+            code_index: Arc::default(),
+            interner: StringInterner::new(),
+            watchdog: FeedWatchdog::default(),
+            history: AvailabilityHistory::default(),
+            fleet: FleetHistory::default(),
+            memory: MemoryGuard::default(),
+            anomalies: AnomalyDetector::default(),
+            outages: OutageDetector::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            availability_thresholds: AvailabilityThresholds::default(),
+            metrics: Arc::default(),
+            events: Arc::default(),
+            published: HashMap::new(),
+            changes: ChangeLog::default(),
+            reference_snapshot: ReferenceSnapshot::default(),
+            reference_report: None,
+            id_mapping: None,
+            last_probe: None,
+            clock: crate::clock::system(),
+            #[cfg(feature = "simulation")]
+            simulation: None,
         }
     }
 
@@ -61,39 +214,403 @@ impl VelibDataClient {
         let retry_policy = RetryPolicy::with_config(retry_config);
         Self {
             client: RetryableHttpClient::with_retry_policy(retry_policy),
+            stations_url: VELIB_STATIONS_URL.to_string(),
+            realtime_url: VELIB_REALTIME_URL.to_string(),
+            gbfs_url: VELIB_GBFS_STATION_INFORMATION_URL.to_string(),
+            upstreams: UpstreamMirrors::default(),
+            scheduler: UpstreamScheduler::default(),
             reference_cache: InMemoryCache::new(Duration::minutes(REFERENCE_CACHE_TTL_MINUTES)),
             realtime_cache: InMemoryCache::new(Duration::minutes(REALTIME_CACHE_TTL_MINUTES)),
+            pages: ConditionalCache::default(),
+            realtime_fetched_at: None,
+            versions: DataVersioner::default(),
+            annotated: None,
+            reference_only: None,
+            with_realtime: None,
+            name_index: Arc::default(),
+            code_index: Arc::default(),
+            interner: StringInterner::new(),
+            watchdog: FeedWatchdog::default(),
+            history: AvailabilityHistory::default(),
+            fleet: FleetHistory::default(),
+            memory: MemoryGuard::default(),
+            anomalies: AnomalyDetector::default(),
+            outages: OutageDetector::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            availability_thresholds: AvailabilityThresholds::default(),
+            metrics: Arc::default(),
+            events: Arc::default(),
+            published: HashMap::new(),
+            changes: ChangeLog::default(),
+            reference_snapshot: ReferenceSnapshot::default(),
+            reference_report: None,
+            id_mapping: None,
+            last_probe: None,
+            clock: crate::clock::system(),
+            #[cfg(feature = "simulation")]
+            simulation: None,
+        }
+    }
+
+    /// Read the time from `clock` instead of the wall clock, for deterministic
+    /// tests and replays
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Seed the retry jitter so retry delays repeat from one run to the next
+    #[must_use]
+    pub fn with_jitter_seed(mut self, seed: u64) -> Self {
+        self.client = self.client.with_jitter_seed(seed);
+        self
+    }
+
+    /// Serve `city` instead of Paris Open Data, for demos and offline development
+    ///
+    /// Availability is drawn from the city's demand curves at the client's
+    /// clock time, whenever the real-time cache expires.
+    #[cfg(feature = "simulation")]
+    #[must_use]
+    pub fn with_simulation(mut self, city: SimulatedCity) -> Self {
+        self.simulation = Some(Arc::new(city));
+        self
+    }
+
+    /// The clock this client reads the time from
+    #[must_use]
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        Arc::clone(&self.clock)
+    }
+
+    /// Consider the realtime feed frozen after `stale_after` without a newer `duedate`
+    #[must_use]
+    pub fn with_feed_stale_after(mut self, stale_after: Duration) -> Self {
+        self.watchdog = FeedWatchdog::new(stale_after);
+        self
+    }
+
+    /// Label station availability with `thresholds` instead of the defaults
+    #[must_use]
+    pub fn with_availability_thresholds(mut self, thresholds: AvailabilityThresholds) -> Self {
+        self.availability_thresholds = thresholds;
+        self
+    }
+
+    /// Keep availability history for `retention` instead of the default day
+    #[must_use]
+    pub fn with_history_retention(mut self, retention: Duration) -> Self {
+        self.history = self.history.with_retention(retention);
+        self.fleet = FleetHistory::new(retention);
+        self
+    }
+
+    /// Keep the change log within `retention`
+    #[must_use]
+    pub fn with_change_retention(mut self, retention: ChangeRetention) -> Self {
+        self.changes = std::mem::take(&mut self.changes).with_retention(retention);
+        self
+    }
+
+    /// Spill what leaves the change log to `storage`, for baselines older than memory
+    #[must_use]
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.changes = std::mem::take(&mut self.changes).with_storage(storage);
+        self
+    }
+
+    /// Choose which entry wins when the reference dataset lists a station code twice
+    #[must_use]
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Fetch reference stations from another endpoint serving the same Open
+    /// Data schema, e.g. a test network
+    #[must_use]
+    pub fn with_stations_url(mut self, url: &str) -> Self {
+        self.stations_url = url.to_string();
+        self
+    }
+
+    /// Fetch real-time availability from another endpoint serving the same
+    /// Open Data schema
+    #[must_use]
+    pub fn with_realtime_url(mut self, url: &str) -> Self {
+        self.realtime_url = url.to_string();
+        self
+    }
+
+    /// Read GBFS station ids from another `station_information.json`
+    #[must_use]
+    pub fn with_gbfs_url(mut self, url: &str) -> Self {
+        self.gbfs_url = url.to_string();
+        self
+    }
+
+    /// Trim the availability history and caches, with a warning, whenever the
+    /// process's resident memory passes `bytes`
+    #[must_use]
+    pub fn with_memory_soft_limit(mut self, bytes: u64) -> Self {
+        self.memory = MemoryGuard::new(Some(bytes));
+        self
+    }
+
+    /// Fail over to `mirrors`, base URLs serving the same dataset paths as the
+    /// reference and real-time endpoints, when those keep failing
+    #[must_use]
+    pub fn with_upstream_mirrors(mut self, mirrors: &[String]) -> Self {
+        self.upstreams = UpstreamMirrors::new(mirrors);
+        self
+    }
+
+    /// Keep upstream API calls under `requests_per_minute`
+    #[must_use]
+    pub fn with_upstream_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.scheduler = UpstreamScheduler::new(requests_per_minute);
+        self
+    }
+
+    /// Change the upstream request ceiling of a running client
+    pub fn set_upstream_rate_limit(&mut self, requests_per_minute: u32) {
+        self.scheduler.set_rate(requests_per_minute);
+    }
+
+    /// Change availability label thresholds; the current snapshot is relabelled on next read
+    pub fn set_availability_thresholds(&mut self, thresholds: AvailabilityThresholds) {
+        if thresholds != self.availability_thresholds {
+            self.availability_thresholds = thresholds;
+            self.annotated = None;
+        }
+    }
+
+    /// Counters shared with the rest of the server
+    #[must_use]
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Availability changes announced after each real-time refresh
+    #[must_use]
+    pub fn events(&self) -> Arc<EventBus> {
+        Arc::clone(&self.events)
+    }
+
+    /// Bike counts observed for a station since `since`, oldest first
+    #[must_use]
+    pub fn availability_history(
+        &self,
+        station_code: &str,
+        since: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, u16)> {
+        self.history.series(station_code, since)
+    }
+
+    /// Citywide bikes by type recorded since `since`, oldest first
+    #[must_use]
+    pub fn fleet_history(&self, since: DateTime<Utc>) -> Vec<FleetSample> {
+        self.fleet.since(since)
+    }
+
+    /// Trim and thin the availability history, returning what it holds afterwards
+    pub fn compact_history(&mut self) -> HistoryStats {
+        self.history.compact(self.clock.now())
+    }
+
+    /// Every station's recorded samples since `since`, for export
+    #[must_use]
+    pub fn history_records(&self, since: DateTime<Utc>) -> Vec<HistoryRecord> {
+        self.history.records(since)
+    }
+
+    /// Stations changed by recent real-time refreshes, by snapshot id
+    #[must_use]
+    pub fn change_log(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// How much the availability history holds
+    #[must_use]
+    pub fn history_stats(&self) -> HistoryStats {
+        self.history.stats()
+    }
+
+    /// Unusual citywide conditions flagged in the latest refresh
+    #[must_use]
+    pub fn advisories(&self) -> Vec<Advisory> {
+        self.anomalies.advisories()
+    }
+
+    /// Whether the upstream realtime feed has stopped producing new data
+    #[must_use]
+    pub fn feed_status(&self) -> FeedStatus {
+        self.watchdog.status(self.clock.now())
+    }
+
+    /// Result of the last [`Self::probe_upstream`], if any ran yet
+    #[must_use]
+    pub fn last_probe(&self) -> Option<UpstreamProbe> {
+        self.last_probe.clone()
+    }
+
+    /// Check both upstream datasets with a single-record request each
+    ///
+    /// Unlike a full fetch this bypasses the caches and costs two small
+    /// requests, so it can run on a schedule.
+    pub async fn probe_upstream(&mut self) -> UpstreamProbe {
+        let reference = self.probe_dataset(&self.stations_url.clone()).await;
+        let realtime = self.probe_dataset(&self.realtime_url.clone()).await;
+        let probe = UpstreamProbe {
+            checked_at: self.clock.now(),
+            reference,
+            realtime,
+        };
+        self.last_probe = Some(probe.clone());
+        probe
+    }
+
+    /// Validate every reference station and compare the catalog with the one
+    /// seen at the previous validation
+    pub async fn validate_reference(&mut self) -> Result<ReferenceReport> {
+        let stations = self
+            .fetch_reference_stations_as(FetchPriority::Background)
+            .await?;
+        let report = self.reference_snapshot.update(&stations, self.clock.now());
+        self.reference_report = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Reference station codes joined with the ids of the GBFS feed, rebuilt
+    /// once [`ID_MAPPING_TTL_MINUTES`] old
+    pub async fn station_id_mapping(&mut self) -> Result<StationIdMapping> {
+        let now = self.clock.now();
+        if let Some(mapping) = self.id_mapping.as_ref().filter(|mapping| {
+            now - mapping.generated_at < Duration::minutes(ID_MAPPING_TTL_MINUTES)
+        }) {
+            return Ok(mapping.clone());
         }
+        let reference = self.fetch_reference_stations().await?;
+        let url = self.gbfs_url.clone();
+        let cached = self.pages.get(&url, 0);
+        let response = phases::time_async(
+            Phase::Upstream,
+            self.upstream_get(
+                &url,
+                &[],
+                FetchPriority::Interactive,
+                cached.as_ref().map(|cached| &cached.validators),
+                false,
+            ),
+        )
+        .await?;
+        let document = match cached {
+            Some(cached) if response.status() == reqwest::StatusCode::NOT_MODIFIED => {
+                self.metrics.record_upstream_not_modified();
+                cached.body
+            }
+            _ => {
+                let validators = Validators::from_headers(response.headers());
+                let body = Arc::new(response.json::<Value>().await?);
+                self.pages.store(&url, 0, validators, Arc::clone(&body));
+                body
+            }
+        };
+        let gbfs = parse_station_information(&document)?;
+        let mapping = StationIdMapping::build(&reference, &gbfs, now);
+        if !mapping.unmatched_station_codes.is_empty() || !mapping.unmatched_gbfs_ids.is_empty() {
+            debug!(
+                unmatched_station_codes = mapping.unmatched_station_codes.len(),
+                unmatched_gbfs_ids = mapping.unmatched_gbfs_ids.len(),
+                "Some stations are missing from the open data or GBFS feed"
+            );
+        }
+        self.id_mapping = Some(mapping.clone());
+        Ok(mapping)
+    }
+
+    /// Result of the last [`Self::validate_reference`], if any ran yet
+    #[must_use]
+    pub fn reference_report(&self) -> Option<ReferenceReport> {
+        self.reference_report.clone()
+    }
+
+    async fn probe_dataset(&mut self, url: &str) -> ProbeOutcome {
+        if self.simulated_reference().is_some() {
+            return ProbeOutcome::from_result(&Ok::<(), Error>(()), 0);
+        }
+        let started = std::time::Instant::now();
+        let result = self
+            .upstream_get(
+                url,
+                &[("limit", &"1".to_string())],
+                FetchPriority::Background,
+                None,
+                true,
+            )
+            .await;
+        ProbeOutcome::from_result(&result, started.elapsed().as_millis() as u64)
     }
 
     /// Fetch all station reference data
-    pub async fn fetch_reference_stations(&mut self) -> Result<Vec<StationReference>> {
-        const CACHE_KEY: &str = "all_reference_stations";
+    pub async fn fetch_reference_stations(&mut self) -> Result<Arc<[StationReference]>> {
+        self.fetch_reference_stations_as(FetchPriority::Interactive)
+            .await
+    }
 
+    async fn fetch_reference_stations_as(
+        &mut self,
+        priority: FetchPriority,
+    ) -> Result<Arc<[StationReference]>> {
         // Check cache first
-        if let Some(cached) = self.reference_cache.get(&CACHE_KEY.to_string()).await {
+        let cached = phases::time_async(
+            Phase::Cache,
+            self.reference_cache.get(&REFERENCE_CACHE_KEY.to_string()),
+        )
+        .await;
+        if let Some(cached) = cached {
             debug!("Using cached reference stations: {} stations", cached.len());
             return Ok(cached);
         }
 
+        let all_stations = match self.simulated_reference() {
+            Some(stations) => stations,
+            None => self.download_reference_stations(priority).await?,
+        };
+        let all_stations = self.merge_duplicates(all_stations);
+        info!("Fetched {} reference stations", all_stations.len());
+        self.interner.release_unused();
+
+        self.name_index = Arc::new(NameTrie::build(&all_stations));
+        self.code_index = Arc::new(KioskCodeIndex::build(&all_stations));
+        self.outages.set_capacities(&all_stations);
+        let version = self.versions.observe_reference(&all_stations);
+        self.metrics.record_data_version(version);
+
+        // Cache the results
+        let all_stations: Arc<[StationReference]> = all_stations.into();
+        self.reference_cache
+            .insert(REFERENCE_CACHE_KEY.to_string(), Arc::clone(&all_stations))
+            .await;
+
+        Ok(all_stations)
+    }
+
+    async fn download_reference_stations(
+        &mut self,
+        priority: FetchPriority,
+    ) -> Result<Vec<StationReference>> {
         info!("Fetching reference stations from Paris Open Data API");
 
         let mut all_stations = Vec::new();
-        let mut offset = 0;
-        let limit = 100; // API limit
+        let url = self.stations_url.clone();
+        let mut page = 0;
 
         loop {
-            let query_params = &[
-                ("limit", &limit.to_string()),
-                ("offset", &offset.to_string()),
-            ];
-
-            let response = self
-                .client
-                .get_with_query(VELIB_STATIONS_URL, query_params)
-                .await?;
-
-            let json: Value = response.json().await?;
+            let json =
+                phases::time_async(Phase::Upstream, self.upstream_page(&url, page, priority))
+                    .await?;
             let records = json["results"]
                 .as_array()
                 .ok_or_else(|| Error::Internal(anyhow::anyhow!("Invalid API response format")))?;
@@ -108,50 +625,103 @@ impl VelibDataClient {
                 }
             }
 
-            offset += limit;
-            if records.len() < limit {
+            page += 1;
+            if records.len() < PAGE_SIZE {
                 break; // Last page
             }
         }
-
-        info!("Fetched {} reference stations", all_stations.len());
-
-        // Cache the results
-        self.reference_cache
-            .insert(CACHE_KEY.to_string(), all_stations.clone())
-            .await;
+        self.pages.truncate(&url, page);
 
         Ok(all_stations)
     }
 
+    /// One entry per station code, counting the entries merged away
+    fn merge_duplicates(&self, stations: Vec<StationReference>) -> Vec<StationReference> {
+        let (stations, merges) = merge_duplicates(stations, self.duplicate_policy);
+        if merges > 0 {
+            warn!("Merged {} duplicate reference station entries", merges);
+            self.metrics.record_station_merges(merges as u64);
+        }
+        stations
+    }
+
     /// Fetch real-time station status data
-    pub async fn fetch_realtime_status(&mut self) -> Result<HashMap<String, RealTimeStatus>> {
-        const CACHE_KEY: &str = "all_realtime_status";
+    pub async fn fetch_realtime_status(&mut self) -> Result<Arc<HashMap<String, RealTimeStatus>>> {
+        self.fetch_realtime_status_within(Duration::seconds(DEFAULT_REALTIME_MAX_AGE_SECONDS))
+            .await
+    }
 
-        // Check cache first
-        if let Some(cached) = self.realtime_cache.get(&CACHE_KEY.to_string()).await {
+    /// Fetch real-time status, serving the cached snapshot only if it is at most `max_age` old
+    pub async fn fetch_realtime_status_within(
+        &mut self,
+        max_age: Duration,
+    ) -> Result<Arc<HashMap<String, RealTimeStatus>>> {
+        let cached = phases::time_async(
+            Phase::Cache,
+            self.cached_realtime_within(max_age, self.clock.now()),
+        )
+        .await;
+        if let Some(cached) = cached {
             debug!("Using cached real-time status: {} stations", cached.len());
-            return Ok(cached);
+            return Ok(self.annotate_realtime(cached));
         }
+        self.fetch_realtime_status_as(FetchPriority::Interactive)
+            .await
+    }
 
+    async fn cached_realtime_within(
+        &self,
+        max_age: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<Arc<HashMap<String, RealTimeStatus>>> {
+        let fetched_at = self.realtime_fetched_at?;
+        if now - fetched_at > max_age {
+            return None;
+        }
+        self.realtime_cache
+            .get(&REALTIME_CACHE_KEY.to_string())
+            .await
+    }
+
+    /// Fetch from upstream, bypassing the cache
+    async fn fetch_realtime_status_as(
+        &mut self,
+        priority: FetchPriority,
+    ) -> Result<Arc<HashMap<String, RealTimeStatus>>> {
+        let all_status = match self.simulated_realtime() {
+            Some(status) => status,
+            None => self.download_realtime_status(priority).await?,
+        };
+        info!("Fetched real-time status for {} stations", all_status.len());
+        self.observe_realtime(&all_status);
+
+        // Cache the results
+        let all_status = Arc::new(all_status);
+        self.realtime_cache
+            .insert(REALTIME_CACHE_KEY.to_string(), Arc::clone(&all_status))
+            .await;
+        let fetched_at = self.clock.now();
+        self.realtime_fetched_at = Some(fetched_at);
+        self.metrics.record_realtime_snapshot(fetched_at);
+        self.enforce_memory_limit().await;
+
+        Ok(self.annotate_realtime(all_status))
+    }
+
+    async fn download_realtime_status(
+        &mut self,
+        priority: FetchPriority,
+    ) -> Result<HashMap<String, RealTimeStatus>> {
         info!("Fetching real-time status from Paris Open Data API");
 
         let mut all_status = HashMap::new();
-        let mut offset = 0;
-        let limit = 100; // API limit
+        let url = self.realtime_url.clone();
+        let mut page = 0;
 
         loop {
-            let query_params = &[
-                ("limit", &limit.to_string()),
-                ("offset", &offset.to_string()),
-            ];
-
-            let response = self
-                .client
-                .get_with_query(VELIB_REALTIME_URL, query_params)
-                .await?;
-
-            let json: Value = response.json().await?;
+            let json =
+                phases::time_async(Phase::Upstream, self.upstream_page(&url, page, priority))
+                    .await?;
             let records = json["results"]
                 .as_array()
                 .ok_or_else(|| Error::Internal(anyhow::anyhow!("Invalid API response format")))?;
@@ -166,45 +736,300 @@ impl VelibDataClient {
                 }
             }
 
-            offset += limit;
-            if records.len() < limit {
+            page += 1;
+            if records.len() < PAGE_SIZE {
                 break; // Last page
             }
         }
+        self.pages.truncate(&url, page);
 
-        info!("Fetched real-time status for {} stations", all_status.len());
+        Ok(all_status)
+    }
 
-        // Cache the results
-        self.realtime_cache
-            .insert(CACHE_KEY.to_string(), all_status.clone())
-            .await;
+    #[cfg(feature = "simulation")]
+    fn simulated_reference(&self) -> Option<Vec<StationReference>> {
+        self.simulation.as_ref().map(|city| city.stations())
+    }
 
-        Ok(all_status)
+    #[cfg(not(feature = "simulation"))]
+    fn simulated_reference(&self) -> Option<Vec<StationReference>> {
+        None
+    }
+
+    #[cfg(feature = "simulation")]
+    fn simulated_realtime(&self) -> Option<HashMap<String, RealTimeStatus>> {
+        let now = self.clock.now();
+        self.simulation
+            .as_ref()
+            .map(|city| city.availability_at(now))
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    fn simulated_realtime(&self) -> Option<HashMap<String, RealTimeStatus>> {
+        None
+    }
+
+    /// Fetch real-time status from upstream even if the cached copy is still valid
+    ///
+    /// Runs as background work, so it is skipped rather than queued when the
+    /// upstream request budget runs low.
+    pub async fn refresh_realtime_status(
+        &mut self,
+    ) -> Result<Arc<HashMap<String, RealTimeStatus>>> {
+        self.fetch_realtime_status_as(FetchPriority::Background)
+            .await
+    }
+
+    /// GET from the upstream API within the request budget, failing fast while
+    /// the circuit breaker is open
+    ///
+    /// With `mirrored`, a failed request is retried on the next mirror, and
+    /// endpoints whose breaker is open are skipped. With `validators` the
+    /// request is conditional, and may be answered `304`.
+    async fn upstream_get(
+        &mut self,
+        url: &str,
+        query: &[(&str, &String)],
+        priority: FetchPriority,
+        validators: Option<&Validators>,
+        mirrored: bool,
+    ) -> Result<reqwest::Response> {
+        let now = self.clock.now();
+        let order = if mirrored {
+            self.upstreams.order(now)
+        } else {
+            self.upstreams.primary(now)
+        }
+        .map_err(|retry_after_seconds| Error::UpstreamUnavailable {
+            retry_after_seconds,
+        })?;
+
+        let headers = validators
+            .map(Validators::conditional_headers)
+            .unwrap_or_default();
+        let mut response = Err(Error::UpstreamUnavailable {
+            retry_after_seconds: 1,
+        });
+        for (attempt, endpoint) in order.into_iter().enumerate() {
+            if attempt > 0 {
+                warn!(
+                    "Upstream request to {} failed, trying mirror {}",
+                    url,
+                    self.upstreams.name(endpoint)
+                );
+            }
+            if let Err(e) = self.scheduler.acquire(priority).await {
+                self.metrics.record_upstream_shed();
+                return Err(e);
+            }
+
+            let endpoint_url = self.upstreams.url(endpoint, url);
+            response = self
+                .client
+                .get_conditional(&endpoint_url, query, &headers)
+                .await;
+            let now = self.clock.now();
+            if response.is_ok() {
+                self.upstreams.record_success(endpoint);
+            } else {
+                self.upstreams.record_failure(endpoint, now);
+            }
+            self.metrics.record_upstream_fetch(response.is_ok(), now);
+            self.metrics
+                .record_breaker_state(self.upstreams.all_open(), now);
+            if response.is_ok() {
+                break;
+            }
+        }
+        response
+    }
+
+    /// Page `page` of the dataset at `url`, revalidated against the copy from
+    /// the last download so an unchanged page costs a `304` instead of a body
+    async fn upstream_page(
+        &mut self,
+        url: &str,
+        page: usize,
+        priority: FetchPriority,
+    ) -> Result<Arc<Value>> {
+        let query = &[
+            ("limit", &PAGE_SIZE.to_string()),
+            ("offset", &(page * PAGE_SIZE).to_string()),
+        ];
+        let cached = self.pages.get(url, page);
+        let response = self
+            .upstream_get(
+                url,
+                query,
+                priority,
+                cached.as_ref().map(|cached| &cached.validators),
+                true,
+            )
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            let cached = cached.ok_or_else(|| {
+                Error::Internal(anyhow::anyhow!("Unrequested 304 Not Modified from {url}"))
+            })?;
+            debug!("Page {} of {} not modified", page, url);
+            self.metrics.record_upstream_not_modified();
+            return Ok(cached.body);
+        }
+        let validators = Validators::from_headers(response.headers());
+        let body = Arc::new(response.json::<Value>().await?);
+        self.pages.store(url, page, validators, Arc::clone(&body));
+        Ok(body)
+    }
+
+    fn observe_realtime(&mut self, snapshot: &HashMap<String, RealTimeStatus>) {
+        self.metrics.record_realtime_refresh();
+        let unknown = snapshot
+            .values()
+            .filter(|status| status.unknown_freshness)
+            .count();
+        if unknown > 0 {
+            debug!("{} real-time records have no parseable duedate", unknown);
+            self.metrics.record_unknown_freshness(unknown as u64);
+        }
+        self.history.record(snapshot, self.clock.now());
+        self.fleet.record(snapshot, self.clock.now());
+        self.outages.observe(snapshot, self.clock.now());
+        for advisory in self.anomalies.observe(snapshot, self.clock.now()) {
+            warn!(
+                kind = ?advisory.kind,
+                severity = ?advisory.severity,
+                "Citywide anomaly: {}",
+                advisory.message
+            );
+        }
+        let current: HashMap<String, StationUpdate> = snapshot
+            .iter()
+            .map(|(code, status)| (code.clone(), StationUpdate::new(code, status)))
+            .collect();
+        self.events.publish_availability(&self.published, &current);
+        self.changes
+            .record(&self.published, &current, self.clock.now());
+        let version = self.versions.observe_realtime(&current);
+        self.metrics.record_data_version(version);
+        self.published = current;
+        if let Some(level) = self.watchdog.observe(snapshot, self.clock.now()) {
+            self.metrics.record_frozen_alarm();
+            let status = self.watchdog.status(self.clock.now());
+            warn!(
+                alarm_level = level,
+                frozen_for_minutes = status.frozen_for_minutes,
+                "Real-time feed is frozen: no newer duedate since {:?}",
+                status.newest_update
+            );
+        }
+    }
+
+    /// Add what only the client knows across refreshes: trends, availability
+    /// labels and frozen feed tags
+    ///
+    /// History only grows when a snapshot is fetched, so the annotated copy is
+    /// shared by every read of the same snapshot until the feed freezes or thaws.
+    fn annotate_realtime(
+        &mut self,
+        raw: Arc<HashMap<String, RealTimeStatus>>,
+    ) -> Arc<HashMap<String, RealTimeStatus>> {
+        let now = self.clock.now();
+        let feed = self.watchdog.status(now);
+        let fresh = !feed.frozen
+            && feed
+                .newest_update
+                .is_some_and(|newest| now - newest < Duration::minutes(FRESH_DATA_MINUTES));
+        self.metrics.record_realtime_read(fresh);
+
+        if let Some(annotated) = &self.annotated {
+            if Arc::ptr_eq(&annotated.raw, &raw) && annotated.frozen == feed.frozen {
+                return Arc::clone(&annotated.statuses);
+            }
+        }
+
+        let mut statuses = HashMap::clone(&raw);
+        self.history.annotate(&mut statuses, now);
+        for (code, status) in &mut statuses {
+            status.availability_label = Some(
+                self.availability_thresholds
+                    .label(status.bikes.total(), status.available_docks),
+            );
+            status.suspected_out_of_service = self.outages.is_suspected(code);
+        }
+        // A frozen upstream still answers, so its per-station timestamps can't be trusted
+        if feed.frozen {
+            for status in statuses.values_mut() {
+                status.data_freshness = status.data_freshness.at_least(DataFreshness::Stale);
+            }
+        }
+        let statuses = Arc::new(statuses);
+        self.annotated = Some(AnnotatedRealtime {
+            raw,
+            frozen: feed.frozen,
+            statuses: Arc::clone(&statuses),
+        });
+        statuses
     }
 
     /// Get all stations with optional real-time data
-    pub async fn get_all_stations(&mut self, include_realtime: bool) -> Result<Vec<VelibStation>> {
+    pub async fn get_all_stations(
+        &mut self,
+        include_realtime: bool,
+    ) -> Result<Arc<[EnrichedStation]>> {
+        self.get_all_stations_within(
+            include_realtime,
+            Duration::seconds(DEFAULT_REALTIME_MAX_AGE_SECONDS),
+        )
+        .await
+    }
+
+    /// Get all stations, with real-time data at most `max_age` old
+    ///
+    /// The stations are shared with every other caller reading the same
+    /// snapshots; they are only merged, and their derived fields computed,
+    /// again once either snapshot changes.
+    pub async fn get_all_stations_within(
+        &mut self,
+        include_realtime: bool,
+        max_age: Duration,
+    ) -> Result<Arc<[EnrichedStation]>> {
         let reference_stations = self.fetch_reference_stations().await?;
+        let realtime_status = if include_realtime {
+            Some(self.fetch_realtime_status_within(max_age).await?)
+        } else {
+            None
+        };
 
-        if !include_realtime {
-            return Ok(reference_stations
-                .into_iter()
-                .map(VelibStation::new)
-                .collect());
+        let merged = if include_realtime {
+            &mut self.with_realtime
+        } else {
+            &mut self.reference_only
+        };
+        if let Some(merged) = merged
+            .as_ref()
+            .filter(|merged| merged.is_of(&reference_stations, realtime_status.as_ref()))
+        {
+            return Ok(Arc::clone(&merged.stations));
         }
 
-        let realtime_status = self.fetch_realtime_status().await?;
-
-        let stations = reference_stations
-            .into_iter()
+        let stations: Arc<[EnrichedStation]> = reference_stations
+            .iter()
             .map(|ref_station| {
-                let mut station = VelibStation::new(ref_station);
-                if let Some(rt_status) = realtime_status.get(&station.reference.station_code) {
+                let mut station = VelibStation::new(ref_station.clone());
+                if let Some(rt_status) = realtime_status
+                    .as_ref()
+                    .and_then(|realtime| realtime.get(&*ref_station.station_code))
+                {
                     station = station.with_real_time(rt_status.clone());
                 }
-                station
+                EnrichedStation::new(station)
             })
             .collect();
+        *merged = Some(MergedStations {
+            reference: reference_stations,
+            realtime: realtime_status,
+            stations: Arc::clone(&stations),
+        });
 
         Ok(stations)
     }
@@ -215,23 +1040,60 @@ impl VelibDataClient {
         station_code: &str,
         include_realtime: bool,
     ) -> Result<Option<VelibStation>> {
-        let all_stations = self.get_all_stations(include_realtime).await?;
-        Ok(all_stations
-            .into_iter()
-            .find(|station| station.reference.station_code == station_code))
+        self.get_station_by_code_within(
+            station_code,
+            include_realtime,
+            Duration::seconds(DEFAULT_REALTIME_MAX_AGE_SECONDS),
+        )
+        .await
+    }
+
+    /// Get a specific station, with real-time data at most `max_age` old
+    ///
+    /// `station_code` may also be an identifier shown on the station's kiosk
+    /// or QR code, see [`KioskCodeIndex`].
+    pub async fn get_station_by_code_within(
+        &mut self,
+        station_code: &str,
+        include_realtime: bool,
+        max_age: Duration,
+    ) -> Result<Option<VelibStation>> {
+        let all_stations = self
+            .get_all_stations_within(include_realtime, max_age)
+            .await?;
+        let find = |code: &str| {
+            all_stations
+                .iter()
+                .find(|station| &*station.reference.station_code == code)
+                .map(|station| station.station.clone())
+        };
+        Ok(find(station_code).or_else(|| {
+            self.code_index
+                .resolve(station_code)
+                .and_then(|code| find(&code))
+        }))
+    }
+
+    /// Complete a station name prefix from the reference name index
+    pub async fn autocomplete_station_names(
+        &mut self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<StationNameSuggestion>> {
+        // Refreshes the reference data (and with it the index) when the cache expired
+        self.fetch_reference_stations().await?;
+        Ok(self.name_index.complete(prefix, limit))
     }
 
     /// Parse reference station data from API response
-    fn parse_reference_station(&self, record: &Value) -> Result<StationReference> {
+    fn parse_reference_station(&mut self, record: &Value) -> Result<StationReference> {
         let station_code = record["stationcode"]
             .as_str()
-            .ok_or_else(|| Error::Internal(anyhow::anyhow!("Missing station code")))?
-            .to_string();
+            .ok_or_else(|| Error::Internal(anyhow::anyhow!("Missing station code")))?;
 
         let name = record["name"]
             .as_str()
-            .ok_or_else(|| Error::Internal(anyhow::anyhow!("Missing station name")))?
-            .to_string();
+            .ok_or_else(|| Error::Internal(anyhow::anyhow!("Missing station name")))?;
 
         let capacity = record["capacity"]
             .as_u64()
@@ -260,13 +1122,28 @@ impl VelibDataClient {
             is_virtual_station: false,   // Not available in current API
         };
 
+        let text_field = |field: &str| {
+            record[field]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
         Ok(StationReference {
-            station_code,
-            name,
+            station_code: self.interner.intern(station_code),
+            name: self.interner.intern(name),
             coordinates,
             capacity,
             capabilities,
-        })
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+        .with_commune(
+            text_field("nom_arrondissement_communes"),
+            text_field("code_insee_commune"),
+        ))
     }
 
     /// Parse real-time status data from API response
@@ -282,37 +1159,122 @@ impl VelibDataClient {
 
         let available_docks = record["numdocksavailable"].as_u64().unwrap_or(0) as u16;
 
-        // Parse status
-        let status_str = record["is_installed"].as_str().unwrap_or("NON");
-
-        let status = match status_str {
-            "OUI" => {
-                let is_renting = record["is_renting"].as_str().unwrap_or("NON") == "OUI";
-                let is_returning = record["is_returning"].as_str().unwrap_or("NON") == "OUI";
-
-                if is_renting && is_returning {
-                    StationStatus::Open
-                } else {
-                    StationStatus::Maintenance
-                }
-            }
-            _ => StationStatus::Closed,
+        // Parse status; an installed station may still only rent or only take returns
+        let is_installed = record["is_installed"].as_str().unwrap_or("NON") == "OUI";
+        let is_renting = record["is_renting"].as_str().unwrap_or("NON") == "OUI";
+        let is_returning = record["is_returning"].as_str().unwrap_or("NON") == "OUI";
+        let status = if is_installed {
+            StationStatus::Open
+        } else {
+            StationStatus::Closed
         };
 
-        // Parse last update time
-        let default_time = Utc::now().to_rfc3339();
-        let last_update_str = record["duedate"].as_str().unwrap_or(&default_time);
-
-        let last_update = DateTime::parse_from_rfc3339(last_update_str)
-            .map_or_else(|_| Utc::now(), |dt| dt.with_timezone(&Utc));
+        // Parse last update time; a missing or malformed duedate stays unknown
+        // instead of being passed off as "now"
+        let last_update = record["duedate"]
+            .as_str()
+            .and_then(|duedate| DateTime::parse_from_rfc3339(duedate).ok())
+            .map(|dt| dt.with_timezone(&Utc));
 
         let bikes = BikeAvailability::new(mechanical_bikes, electric_bikes);
 
-        let real_time_status = RealTimeStatus::new(bikes, available_docks, status, last_update);
+        let real_time_status = match last_update {
+            Some(last_update) => RealTimeStatus::new_at(
+                bikes,
+                available_docks,
+                status,
+                last_update,
+                self.clock.now(),
+            ),
+            None => RealTimeStatus::with_unknown_update(bikes, available_docks, status),
+        }
+        .with_service(is_renting, is_returning);
 
         Ok((station_code, real_time_status))
     }
 
+    /// Seed the caches with an already-known snapshot, bypassing the upstream API
+    ///
+    /// Used to serve fixture data, e.g. in tests and offline demos.
+    pub async fn seed_cache(
+        &mut self,
+        reference: Vec<StationReference>,
+        realtime: HashMap<String, RealTimeStatus>,
+    ) {
+        let reference = self.merge_duplicates(reference);
+        self.name_index = Arc::new(NameTrie::build(&reference));
+        self.code_index = Arc::new(KioskCodeIndex::build(&reference));
+        self.outages.set_capacities(&reference);
+        let version = self.versions.observe_reference(&reference);
+        self.metrics.record_data_version(version);
+        self.reference_cache
+            .insert(REFERENCE_CACHE_KEY.to_string(), reference.into())
+            .await;
+        self.observe_realtime(&realtime);
+        self.realtime_cache
+            .insert(REALTIME_CACHE_KEY.to_string(), Arc::new(realtime))
+            .await;
+        let fetched_at = self.clock.now();
+        self.realtime_fetched_at = Some(fetched_at);
+        self.metrics.record_realtime_snapshot(fetched_at);
+    }
+
+    /// Resident memory and what the snapshots, history and caches hold
+    pub async fn memory_stats(&self) -> MemoryStats {
+        let history = self.history.stats();
+        let reference_stations = self
+            .reference_cache
+            .get(&REFERENCE_CACHE_KEY.to_string())
+            .await
+            .map_or(0, |stations| stations.len());
+        let realtime_stations = self
+            .realtime_cache
+            .get(&REALTIME_CACHE_KEY.to_string())
+            .await
+            .map_or(0, |statuses| statuses.len());
+        let (reference_cache_entries, realtime_cache_entries) = self.cache_stats().await;
+        MemoryStats {
+            resident_bytes: resident_bytes(),
+            soft_limit_bytes: self.memory.soft_limit_bytes(),
+            reference_stations,
+            realtime_stations,
+            history_bytes: history.approx_bytes,
+            history_samples: history.samples,
+            fleet_samples: self.fleet.len(),
+            reference_cache_entries,
+            realtime_cache_entries,
+            cached_pages: self.pages.len(),
+            trims: self.memory.trims(),
+            last_trimmed_at: self.memory.last_trimmed_at(),
+        }
+    }
+
+    /// Past the memory soft limit, halve the history kept and drop cached
+    /// pages and expired entries; returns whether it trimmed
+    pub async fn enforce_memory_limit(&mut self) -> bool {
+        let now = self.clock.now();
+        let resident = resident_bytes();
+        if !self.memory.should_trim(resident, now) {
+            return false;
+        }
+        let before = self.history.stats();
+        let keep = Duration::hours(before.retention_hours) / 2;
+        let after = self.history.trim(now, keep);
+        self.pages.clear();
+        self.reference_only = None;
+        self.with_realtime = None;
+        self.cleanup_cache().await;
+        warn!(
+            resident_bytes = resident,
+            soft_limit_bytes = self.memory.soft_limit_bytes(),
+            "Memory over the soft limit: trimmed history to the last {} minutes (~{} to ~{} bytes) and dropped cached pages",
+            keep.num_minutes(),
+            before.approx_bytes,
+            after.approx_bytes
+        );
+        true
+    }
+
     /// Clean up expired cache entries
     pub async fn cleanup_cache(&self) {
         self.reference_cache.cleanup_expired().await;
@@ -326,3 +1288,355 @@ impl VelibDataClient {
         (reference_size, realtime_size)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DataFreshness;
+    use serde_json::json;
+
+    fn realtime_record(duedate: Value) -> Value {
+        json!({
+            "stationcode": "16107",
+            "mechanical": 3,
+            "ebike": 2,
+            "numdocksavailable": 10,
+            "is_installed": "OUI",
+            "is_renting": "OUI",
+            "is_returning": "OUI",
+            "duedate": duedate
+        })
+    }
+
+    #[test]
+    fn test_parse_realtime_keeps_duedate() {
+        let client = VelibDataClient::new();
+        let (_, status) = client
+            .parse_realtime_status(&realtime_record(json!("2025-06-14T19:31:22+00:00")))
+            .unwrap();
+
+        assert_eq!(
+            status.last_update.unwrap().to_rfc3339(),
+            "2025-06-14T19:31:22+00:00"
+        );
+        assert!(!status.unknown_freshness);
+    }
+
+    #[test]
+    fn test_parse_realtime_does_not_fabricate_timestamps() {
+        let client = VelibDataClient::new();
+
+        for duedate in [json!("not a date"), Value::Null] {
+            let (_, status) = client
+                .parse_realtime_status(&realtime_record(duedate))
+                .unwrap();
+            assert!(status.last_update.is_none());
+            assert!(status.unknown_freshness);
+            assert_eq!(status.data_freshness, DataFreshness::VeryStale);
+        }
+    }
+
+    #[test]
+    fn test_parse_realtime_keeps_partial_service() {
+        let client = VelibDataClient::new();
+        let mut record = realtime_record(json!("2025-06-14T19:31:22+00:00"));
+        record["is_renting"] = json!("NON");
+
+        let (_, status) = client.parse_realtime_status(&record).unwrap();
+        assert_eq!(status.status, StationStatus::Maintenance);
+        assert!(!status.is_renting);
+        assert!(status.is_returning);
+
+        record["is_installed"] = json!("NON");
+        let (_, status) = client.parse_realtime_status(&record).unwrap();
+        assert_eq!(status.status, StationStatus::Closed);
+        assert!(!status.is_renting && !status.is_returning);
+    }
+
+    #[tokio::test]
+    async fn test_cached_realtime_is_judged_against_the_callers_max_age() {
+        let mut client = VelibDataClient::new();
+        let (code, status) = client
+            .parse_realtime_status(&realtime_record(json!("2025-06-14T19:31:22+00:00")))
+            .unwrap();
+        client
+            .seed_cache(Vec::new(), HashMap::from([(code, status)]))
+            .await;
+
+        let now = Utc::now();
+        client.realtime_fetched_at = Some(now - Duration::minutes(5));
+        assert!(client
+            .cached_realtime_within(Duration::minutes(2), now)
+            .await
+            .is_none());
+        assert_eq!(
+            client
+                .cached_realtime_within(Duration::minutes(10), now)
+                .await
+                .map(|cached| cached.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_reference_stations_share_interned_strings() {
+        let mut client = VelibDataClient::new();
+        let record = json!({
+            "stationcode": "16107",
+            "name": "Benjamin Godard - Victor Hugo",
+            "capacity": 35,
+            "coordonnees_geo": {"lat": 48.865983, "lon": 2.275725}
+        });
+        let first = client.parse_reference_station(&record).unwrap();
+        let refetched = client.parse_reference_station(&record).unwrap();
+        assert!(Arc::ptr_eq(&first.station_code, &refetched.station_code));
+        assert!(Arc::ptr_eq(&first.name, &refetched.name));
+
+        let serialized = serde_json::to_value(&first).unwrap();
+        assert_eq!(serialized["station_code"], "16107");
+        assert_eq!(serialized["name"], "Benjamin Godard - Victor Hugo");
+        let deserialized: StationReference = serde_json::from_value(serialized).unwrap();
+        assert_eq!(&*deserialized.station_code, "16107");
+    }
+
+    #[tokio::test]
+    async fn test_readers_share_a_snapshot_until_it_changes() {
+        let mut client = VelibDataClient::new();
+        let (code, status) = client
+            .parse_realtime_status(&realtime_record(json!("2025-06-14T19:31:22+00:00")))
+            .unwrap();
+        client
+            .seed_cache(Vec::new(), HashMap::from([(code.clone(), status.clone())]))
+            .await;
+
+        let first = client.get_all_stations(true).await.unwrap();
+        let again = client.get_all_stations(true).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        let reference_only = client.get_all_stations(false).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &reference_only));
+        assert!(Arc::ptr_eq(
+            &client.fetch_realtime_status().await.unwrap(),
+            &client.fetch_realtime_status().await.unwrap()
+        ));
+
+        client
+            .seed_cache(Vec::new(), HashMap::from([(code, status)]))
+            .await;
+        let refreshed = client.get_all_stations(true).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &refreshed));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_pages_are_revalidated_not_downloaded() {
+        use axum::{extract::State, http::HeaderMap, response::IntoResponse, routing::get};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Upstream {
+            // Current ETag and the bikes every record reports
+            version: Mutex<(String, u16)>,
+            full_responses: AtomicUsize,
+        }
+        let records = |upstream: State<Arc<Upstream>>, headers: HeaderMap| async move {
+            let (etag, bikes) = upstream.version.lock().unwrap().clone();
+            if headers
+                .get("if-none-match")
+                .is_some_and(|tag| tag == &*etag)
+            {
+                return axum::http::StatusCode::NOT_MODIFIED.into_response();
+            }
+            upstream.full_responses.fetch_add(1, Ordering::SeqCst);
+            let mut record = realtime_record(json!("2025-06-14T19:31:22+00:00"));
+            record["mechanical"] = json!(bikes);
+            ([("etag", etag)], axum::Json(json!({"results": [record]}))).into_response()
+        };
+        let upstream = Arc::new(Upstream::default());
+        *upstream.version.lock().unwrap() = ("\"v1\"".to_string(), 3);
+        let router = axum::Router::new()
+            .route("/", get(records))
+            .with_state(Arc::clone(&upstream));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut client = VelibDataClient::new().with_realtime_url(&url);
+        let bikes = |snapshot: &HashMap<String, RealTimeStatus>| snapshot["16107"].bikes.mechanical;
+        assert_eq!(bikes(&client.refresh_realtime_status().await.unwrap()), 3);
+        assert_eq!(bikes(&client.refresh_realtime_status().await.unwrap()), 3);
+        assert_eq!(upstream.full_responses.load(Ordering::SeqCst), 1);
+        assert_eq!(client.metrics().snapshot().upstream_not_modified, 1);
+
+        *upstream.version.lock().unwrap() = ("\"v2\"".to_string(), 7);
+        assert_eq!(bikes(&client.refresh_realtime_status().await.unwrap()), 7);
+        assert_eq!(upstream.full_responses.load(Ordering::SeqCst), 2);
+        assert_eq!(client.metrics().snapshot().upstream_not_modified, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_upstream_fails_over_to_a_mirror() {
+        use axum::routing::get;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        async fn serve(router: axum::Router) -> String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+            format!("http://{address}")
+        }
+        let primary_hits = Arc::new(AtomicUsize::new(0));
+        let hits = Arc::clone(&primary_hits);
+        let primary = serve(axum::Router::new().route(
+            "/datasets/realtime/records",
+            get(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+                async { axum::http::StatusCode::SERVICE_UNAVAILABLE }
+            }),
+        ))
+        .await;
+        let mirror = serve(axum::Router::new().route(
+            "/proxy/datasets/realtime/records",
+            get(|| async {
+                axum::Json(json!({
+                    "results": [realtime_record(json!("2025-06-14T19:31:22+00:00"))]
+                }))
+            }),
+        ))
+        .await;
+
+        let mut client = VelibDataClient::with_retry_config(RetryConfig {
+            max_attempts: 0,
+            base_delay_seconds: 0,
+            max_delay_seconds: 0,
+            use_jitter: false,
+        })
+        .with_realtime_url(&format!("{primary}/datasets/realtime/records"))
+        .with_upstream_mirrors(&[format!("{mirror}/proxy/")]);
+        for _ in 0..crate::data::breaker::DEFAULT_FAILURE_THRESHOLD {
+            let snapshot = client.refresh_realtime_status().await.unwrap();
+            assert_eq!(snapshot["16107"].bikes.mechanical, 3);
+        }
+        let threshold = crate::data::breaker::DEFAULT_FAILURE_THRESHOLD as usize;
+        assert_eq!(primary_hits.load(Ordering::SeqCst), threshold);
+
+        // The primary's breaker is open: the mirror answers without it being tried
+        assert!(client.refresh_realtime_status().await.is_ok());
+        assert_eq!(primary_hits.load(Ordering::SeqCst), threshold);
+        assert!(!client.metrics().snapshot().breaker_open);
+    }
+
+    #[tokio::test]
+    async fn test_memory_over_the_soft_limit_trims_history() {
+        if resident_bytes().is_none() {
+            return;
+        }
+        let start = Utc::now();
+        let clock = Arc::new(crate::clock::ManualClock::new(start));
+        let mut client = VelibDataClient::new()
+            .with_clock(clock.clone())
+            .with_memory_soft_limit(1);
+        let (code, mut status) = client
+            .parse_realtime_status(&realtime_record(json!("2025-06-14T19:31:22+00:00")))
+            .unwrap();
+        for hour in 0..12 {
+            status.last_update = Some(start + Duration::hours(hour));
+            clock.set(start + Duration::hours(hour));
+            client
+                .seed_cache(Vec::new(), HashMap::from([(code.clone(), status.clone())]))
+                .await;
+        }
+        assert_eq!(client.history_stats().samples, 12);
+
+        assert!(client.enforce_memory_limit().await);
+        // Half of the 24 hours retained still covers the 12 samples
+        assert_eq!(client.history_stats().samples, 12);
+        clock.advance(Duration::minutes(1));
+        assert!(!client.enforce_memory_limit().await);
+        // Twenty hours in, the last 12 hours only reach back to the eighth sample
+        clock.set(start + Duration::hours(20));
+        assert!(client.enforce_memory_limit().await);
+        assert_eq!(client.history_stats().samples, 4);
+
+        let stats = client.memory_stats().await;
+        assert_eq!(stats.trims, 2);
+        assert_eq!(stats.soft_limit_bytes, Some(1));
+        assert_eq!(stats.realtime_stations, 1);
+        assert!(stats.resident_bytes.is_some_and(|bytes| bytes > 1));
+    }
+
+    #[tokio::test]
+    async fn test_station_id_mapping_is_kept_for_an_hour() {
+        use axum::routing::get;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router = axum::Router::new().route(
+            "/",
+            get({
+                let requests = Arc::clone(&requests);
+                move || async move {
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    axum::Json(json!({"data": {"stations": [
+                        {"station_id": 213688169, "stationCode": "16107", "name": "Benjamin Godard"}
+                    ]}}))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let clock = Arc::new(crate::clock::ManualClock::new(Utc::now()));
+        let mut client = VelibDataClient::new()
+            .with_gbfs_url(&url)
+            .with_clock(clock.clone());
+        let reference = client
+            .parse_reference_station(&json!({
+                "stationcode": "16107",
+                "name": "Benjamin Godard - Victor Hugo",
+                "capacity": 35,
+                "coordonnees_geo": {"lat": 48.865983, "lon": 2.275725}
+            }))
+            .unwrap();
+        client.seed_cache(vec![reference], HashMap::new()).await;
+
+        let mapping = client.station_id_mapping().await.unwrap();
+        assert_eq!(mapping.mappings[0].gbfs_station_id, "213688169");
+        client.station_id_mapping().await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::minutes(ID_MAPPING_TTL_MINUTES));
+        client.station_id_mapping().await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_reference_entries_are_merged() {
+        let mut client = VelibDataClient::new();
+        let record = |name: &str, lat: f64| {
+            json!({
+                "stationcode": "16107",
+                "name": name,
+                "capacity": 35,
+                "coordonnees_geo": {"lat": lat, "lon": 2.275725}
+            })
+        };
+        let reference = vec![
+            client
+                .parse_reference_station(&record("Benjamin Godard", 48.865983))
+                .unwrap(),
+            client
+                .parse_reference_station(&record("Benjamin Godard - Victor Hugo", 48.8661))
+                .unwrap(),
+        ];
+        client.seed_cache(reference, HashMap::new()).await;
+
+        let stations = client.get_all_stations(false).await.unwrap();
+        assert_eq!(stations.len(), 1);
+        assert_eq!(
+            &*stations[0].reference.name,
+            "Benjamin Godard - Victor Hugo"
+        );
+        assert_eq!(client.metrics().snapshot().station_merges, 1);
+    }
+}
@@ -0,0 +1,144 @@
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What the upstream gave to identify a response: its `ETag` and `Last-Modified`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<HeaderValue>,
+    pub last_modified: Option<HeaderValue>,
+}
+
+impl Validators {
+    #[must_use]
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            etag: headers.get(ETAG).cloned(),
+            last_modified: headers.get(LAST_MODIFIED).cloned(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// `If-None-Match` and `If-Modified-Since` headers asking whether the response changed
+    #[must_use]
+    pub fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = &self.etag {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        headers
+    }
+}
+
+/// A downloaded page of a dataset, kept to answer a `304 Not Modified`
+#[derive(Debug, Clone)]
+pub struct CachedPage {
+    pub validators: Validators,
+    pub body: Arc<Value>,
+}
+
+/// The pages of each dataset as last downloaded, with their validators
+///
+/// Refreshes send the validators back so the upstream can answer unchanged
+/// pages with a bodiless `304`, served from here. Pages that came without
+/// validators can't be revalidated and are not kept.
+#[derive(Debug, Clone, Default)]
+pub struct ConditionalCache {
+    // Pages by dataset URL, indexed by page number
+    datasets: HashMap<String, Vec<Option<CachedPage>>>,
+}
+
+impl ConditionalCache {
+    #[must_use]
+    pub fn get(&self, dataset: &str, page: usize) -> Option<CachedPage> {
+        self.datasets.get(dataset)?.get(page)?.clone()
+    }
+
+    /// Remember `body` as page `page` of `dataset`, if the upstream gave validators
+    pub fn store(&mut self, dataset: &str, page: usize, validators: Validators, body: Arc<Value>) {
+        let pages = self.datasets.entry(dataset.to_string()).or_default();
+        if pages.len() <= page {
+            pages.resize(page + 1, None);
+        }
+        pages[page] = (!validators.is_empty()).then_some(CachedPage { validators, body });
+    }
+
+    /// Forget pages past the last one of the latest download, after the dataset shrank
+    pub fn truncate(&mut self, dataset: &str, pages: usize) {
+        if let Some(cached) = self.datasets.get_mut(dataset) {
+            cached.truncate(pages);
+        }
+    }
+
+    /// Pages kept across every dataset
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.datasets
+            .values()
+            .map(|pages| pages.iter().flatten().count())
+            .sum()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every page; the next refresh downloads them in full
+    pub fn clear(&mut self) {
+        self.datasets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_only_pages_with_validators_are_kept() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"v1\""));
+        let validators = Validators::from_headers(&headers);
+        assert_eq!(
+            validators.conditional_headers().get(IF_NONE_MATCH).unwrap(),
+            "\"v1\""
+        );
+        assert!(validators
+            .conditional_headers()
+            .get(IF_MODIFIED_SINCE)
+            .is_none());
+
+        let mut cache = ConditionalCache::default();
+        cache.store(
+            "stations",
+            1,
+            validators.clone(),
+            Arc::new(json!({"page": 1})),
+        );
+        cache.store(
+            "stations",
+            0,
+            Validators::default(),
+            Arc::new(json!({"page": 0})),
+        );
+        assert!(cache.get("stations", 0).is_none());
+        let page = cache.get("stations", 1).unwrap();
+        assert_eq!(page.validators, validators);
+        assert_eq!(page.body["page"], 1);
+
+        cache.truncate("stations", 1);
+        assert!(cache.get("stations", 1).is_none());
+        assert!(cache.get("realtime", 0).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::data::aliases::normalize;
+use crate::types::{DataFreshness, VelibStation};
+use serde::{Serialize, Serializer};
+use std::ops::Deref;
+
+/// A station with the values filters and aggregates need, computed once when
+/// the snapshot is merged rather than on every request
+///
+/// Derefs to the station and serializes as it, so it can be used wherever a
+/// [`VelibStation`] is read.
+#[derive(Debug)]
+pub struct EnrichedStation {
+    pub station: VelibStation,
+    /// Bikes docked over capacity, without real-time data or capacity `None`
+    pub occupancy: Option<f64>,
+    /// Bikes can be both taken and returned
+    pub operational: bool,
+    pub can_rent: bool,
+    pub can_return: bool,
+    /// `data_freshness` of the real-time data, `None` without it
+    pub freshness: Option<DataFreshness>,
+    // Normalized `district` then `commune`, compared with `district_key`
+    district_keys: Vec<String>,
+}
+
+impl EnrichedStation {
+    #[must_use]
+    pub fn new(station: VelibStation) -> Self {
+        let capacity = station.reference.capacity;
+        let occupancy = station
+            .real_time
+            .as_ref()
+            .filter(|_| capacity > 0)
+            .map(|rt| f64::from(rt.bikes.total()) / f64::from(capacity));
+        let district_keys = [&station.reference.district, &station.reference.commune]
+            .into_iter()
+            .flatten()
+            .map(|name| normalize(name))
+            .collect();
+        Self {
+            occupancy,
+            operational: station.is_operational(),
+            can_rent: station.can_rent(),
+            can_return: station.can_return(),
+            freshness: station.real_time.as_ref().map(|rt| rt.data_freshness),
+            district_keys,
+            station,
+        }
+    }
+
+    /// Whether the station's `district` or `commune` normalizes to `key`
+    #[must_use]
+    pub fn in_district(&self, key: &str) -> bool {
+        self.district_keys.iter().any(|name| name == key)
+    }
+}
+
+impl Deref for EnrichedStation {
+    type Target = VelibStation;
+
+    fn deref(&self) -> &VelibStation {
+        &self.station
+    }
+}
+
+impl Serialize for EnrichedStation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.station.serialize(serializer)
+    }
+}
+
+/// The form a district name is matched in, see [`EnrichedStation::in_district`]
+///
+/// A bare arrondissement number ("12", "12e", "1er") means that Paris arrondissement.
+#[must_use]
+pub fn district_key(district: &str) -> String {
+    let wanted = normalize(district);
+    let number = wanted
+        .strip_prefix("paris ")
+        .unwrap_or(&wanted)
+        .trim_end_matches("eme")
+        .trim_end_matches("er")
+        .trim_end_matches('e')
+        .parse::<u8>()
+        .ok()
+        .filter(|number| (1..=20).contains(number));
+    match number {
+        Some(1) => "paris 1er".to_string(),
+        Some(number) => format!("paris {number}e"),
+        None => wanted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+        StationStatus,
+    };
+    use chrono::Utc;
+
+    #[test]
+    fn test_derived_fields_are_computed_once() {
+        let reference = StationReference {
+            station_code: "12001".into(),
+            name: "Gare de Lyon - Diderot".into(),
+            coordinates: Coordinates::new(48.8446, 2.3737),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+        .with_commune(Some("Paris".to_string()), None);
+        let status = RealTimeStatus::new(
+            BikeAvailability::new(3, 2),
+            15,
+            StationStatus::Open,
+            Utc::now(),
+        )
+        .with_service(true, false);
+        let station = EnrichedStation::new(VelibStation::new(reference).with_real_time(status));
+
+        assert_eq!(station.occupancy, Some(0.25));
+        assert!(station.can_rent && !station.can_return && !station.operational);
+        assert_eq!(station.freshness, Some(DataFreshness::Fresh));
+        for district in ["Paris 12e", "12", "12ème", "paris"] {
+            assert!(station.in_district(&district_key(district)), "{district}");
+        }
+        assert!(!station.in_district(&district_key("2e")));
+        assert_eq!(
+            serde_json::to_value(&station).unwrap(),
+            serde_json::to_value(&station.station).unwrap()
+        );
+    }
+}
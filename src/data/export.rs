@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// One availability sample of a station, as exported for offline analysis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryRecord {
+    pub station_code: String,
+    pub observed_at: DateTime<Utc>,
+    pub bikes: u16,
+}
+
+/// File formats history can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    /// Encode `records` in this format
+    pub fn encode(self, records: &[HistoryRecord]) -> Result<Vec<u8>> {
+        match self {
+            Self::Csv => Ok(to_csv(records)),
+            Self::Parquet => to_parquet(records),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "parquet" => Ok(Self::Parquet),
+            other => Err(Error::Validation(format!(
+                "Unknown export format {other:?} (expected csv or parquet)"
+            ))),
+        }
+    }
+}
+
+/// `station_code,observed_at,bikes` with a header row; timestamps in RFC 3339
+#[must_use]
+pub fn to_csv(records: &[HistoryRecord]) -> Vec<u8> {
+    let mut csv = String::from("station_code,observed_at,bikes\n");
+    for record in records {
+        // Station codes are digits upstream, but quote anything that could break a row
+        let code = if record.station_code.contains([',', '"', '\n']) {
+            format!("\"{}\"", record.station_code.replace('"', "\"\""))
+        } else {
+            record.station_code.clone()
+        };
+        csv.push_str(&format!(
+            "{code},{},{}\n",
+            record
+                .observed_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            record.bikes
+        ));
+    }
+    csv.into_bytes()
+}
+
+#[cfg(feature = "parquet")]
+const PARQUET_SCHEMA: &str = "
+    message availability_history {
+        required binary station_code (STRING);
+        required int64 observed_at (TIMESTAMP(MILLIS,true));
+        required int32 bikes (INTEGER(16,false));
+    }
+";
+
+/// A single row group with `station_code`, `observed_at` (UTC milliseconds) and `bikes`
+#[cfg(feature = "parquet")]
+pub fn to_parquet(records: &[HistoryRecord]) -> Result<Vec<u8>> {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let internal = |e: parquet::errors::ParquetError| Error::Internal(e.into());
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(internal)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+
+    let mut buffer = Vec::new();
+    let mut writer =
+        SerializedFileWriter::new(&mut buffer, schema, properties).map_err(internal)?;
+    let mut row_group = writer.next_row_group().map_err(internal)?;
+
+    let codes: Vec<ByteArray> = records
+        .iter()
+        .map(|record| ByteArray::from(record.station_code.as_str()))
+        .collect();
+    let observed: Vec<i64> = records
+        .iter()
+        .map(|record| record.observed_at.timestamp_millis())
+        .collect();
+    let bikes: Vec<i32> = records
+        .iter()
+        .map(|record| i32::from(record.bikes))
+        .collect();
+
+    if let Some(mut column) = row_group.next_column().map_err(internal)? {
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&codes, None, None)
+            .map_err(internal)?;
+        column.close().map_err(internal)?;
+    }
+    if let Some(mut column) = row_group.next_column().map_err(internal)? {
+        column
+            .typed::<Int64Type>()
+            .write_batch(&observed, None, None)
+            .map_err(internal)?;
+        column.close().map_err(internal)?;
+    }
+    if let Some(mut column) = row_group.next_column().map_err(internal)? {
+        column
+            .typed::<Int32Type>()
+            .write_batch(&bikes, None, None)
+            .map_err(internal)?;
+        column.close().map_err(internal)?;
+    }
+    row_group.close().map_err(internal)?;
+    writer.close().map_err(internal)?;
+    Ok(buffer)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn to_parquet(_records: &[HistoryRecord]) -> Result<Vec<u8>> {
+    Err(Error::Config(
+        "Parquet export needs velib-mcp built with --features parquet".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<HistoryRecord> {
+        vec![
+            HistoryRecord {
+                station_code: "12001".to_string(),
+                observed_at: "2026-10-14T08:00:00Z".parse().unwrap(),
+                bikes: 7,
+            },
+            HistoryRecord {
+                station_code: "12001".to_string(),
+                observed_at: "2026-10-14T08:10:00Z".parse().unwrap(),
+                bikes: 4,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = String::from_utf8(to_csv(&records())).unwrap();
+        assert_eq!(
+            csv,
+            "station_code,observed_at,bikes\n\
+             12001,2026-10-14T08:00:00Z,7\n\
+             12001,2026-10-14T08:10:00Z,4\n"
+        );
+        assert_eq!(
+            "Parquet".parse::<ExportFormat>().unwrap(),
+            ExportFormat::Parquet
+        );
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_export_reads_back() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let bytes = to_parquet(&records()).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("velib-history-{}.parquet", uuid::Uuid::new_v4()));
+        std::fs::write(&path, bytes).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert!(
+            rows[1].contains("12001") && rows[1].contains("bikes: 4"),
+            "{rows:?}"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::data::history::HISTORY_RETENTION_HOURS;
+use crate::types::RealTimeStatus;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Bikes docked across the whole network at one real-time refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FleetSample {
+    pub at: DateTime<Utc>,
+    pub mechanical: u32,
+    pub electric: u32,
+}
+
+impl FleetSample {
+    /// Total the bikes of every station in `snapshot`
+    #[must_use]
+    pub fn from_snapshot(snapshot: &HashMap<String, RealTimeStatus>, at: DateTime<Utc>) -> Self {
+        let (mechanical, electric) = snapshot.values().fold((0, 0), |(m, e), status| {
+            (
+                m + u32::from(status.bikes.mechanical),
+                e + u32::from(status.bikes.electric),
+            )
+        });
+        Self {
+            at,
+            mechanical,
+            electric,
+        }
+    }
+}
+
+/// Citywide bikes by type at each real-time refresh, kept as long as the
+/// station history
+///
+/// One small sample per refresh, so a day at one refresh a minute stays
+/// under 40 KB without compaction.
+#[derive(Debug, Clone)]
+pub struct FleetHistory {
+    retention: Duration,
+    samples: VecDeque<FleetSample>,
+}
+
+impl Default for FleetHistory {
+    fn default() -> Self {
+        Self::new(Duration::hours(HISTORY_RETENTION_HOURS))
+    }
+}
+
+impl FleetHistory {
+    #[must_use]
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Add the totals of a refreshed snapshot and forget samples past retention
+    pub fn record(&mut self, snapshot: &HashMap<String, RealTimeStatus>, now: DateTime<Utc>) {
+        if self.samples.back().is_some_and(|last| last.at >= now) {
+            return;
+        }
+        self.samples
+            .push_back(FleetSample::from_snapshot(snapshot, now));
+        let cutoff = now - self.retention;
+        while self.samples.front().is_some_and(|first| first.at < cutoff) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Samples from `since` on, oldest first
+    #[must_use]
+    pub fn since(&self, since: DateTime<Utc>) -> Vec<FleetSample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.at >= since)
+            .copied()
+            .collect()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, StationStatus};
+
+    fn snapshot(mechanical: u16, electric: u16) -> HashMap<String, RealTimeStatus> {
+        let status = |mechanical, electric| {
+            RealTimeStatus::new(
+                BikeAvailability::new(mechanical, electric),
+                10,
+                StationStatus::Open,
+                Utc::now(),
+            )
+        };
+        HashMap::from([
+            ("16107".to_string(), status(mechanical, electric)),
+            ("8026".to_string(), status(1, 1)),
+        ])
+    }
+
+    #[test]
+    fn test_fleet_totals_kept_for_retention() {
+        let start = Utc::now();
+        let mut history = FleetHistory::new(Duration::hours(2));
+        history.record(&snapshot(4, 2), start);
+        // A snapshot seen again adds nothing
+        history.record(&snapshot(9, 9), start);
+        history.record(&snapshot(6, 5), start + Duration::hours(1));
+        assert_eq!(
+            history.since(start),
+            [
+                FleetSample {
+                    at: start,
+                    mechanical: 5,
+                    electric: 3
+                },
+                FleetSample {
+                    at: start + Duration::hours(1),
+                    mechanical: 7,
+                    electric: 6
+                },
+            ]
+        );
+
+        history.record(&snapshot(0, 0), start + Duration::hours(3));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.since(start + Duration::hours(2)).len(), 1);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::data::kiosk::KioskCodeIndex;
+use crate::types::StationReference;
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Rebuild the station id mapping at most this often, as stations rarely open or close
+pub const ID_MAPPING_TTL_MINUTES: i64 = 60;
+
+/// A station as the GBFS `station_information` feed lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GbfsStation {
+    pub station_id: String,
+    /// Velib's own `stationCode` extension, the open data `stationcode` give or take leading zeros
+    pub station_code: Option<String>,
+    pub name: String,
+}
+
+/// Read the stations of a GBFS `station_information.json` document
+///
+/// `station_id` is a string in the GBFS specification but a number in the
+/// Velib feed; both are accepted.
+pub fn parse_station_information(document: &Value) -> Result<Vec<GbfsStation>> {
+    let stations = document
+        .pointer("/data/stations")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            Error::Internal(anyhow::anyhow!(
+                "GBFS station_information has no data.stations"
+            ))
+        })?;
+    Ok(stations
+        .iter()
+        .filter_map(|station| {
+            let station_id = match station.get("station_id")? {
+                Value::String(id) => id.clone(),
+                Value::Number(id) => id.to_string(),
+                _ => return None,
+            };
+            let station_code =
+                ["stationCode", "station_code"].iter().find_map(|field| {
+                    match station.get(*field)? {
+                        Value::String(code) => Some(code.clone()),
+                        Value::Number(code) => Some(code.to_string()),
+                        _ => None,
+                    }
+                });
+            Some(GbfsStation {
+                station_id,
+                station_code,
+                name: station
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationIdPair {
+    pub station_code: String,
+    pub gbfs_station_id: String,
+    pub name: String,
+}
+
+/// Open data station codes joined with GBFS station ids, served as
+/// `velib://stations/id-mapping`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationIdMapping {
+    pub generated_at: DateTime<Utc>,
+    pub total_mappings: usize,
+    /// Sorted by station code
+    pub mappings: Vec<StationIdPair>,
+    /// Open data stations no GBFS station matched
+    pub unmatched_station_codes: Vec<String>,
+    /// GBFS stations matching no open data station, or several
+    pub unmatched_gbfs_ids: Vec<String>,
+}
+
+impl StationIdMapping {
+    /// Join `gbfs` stations to `reference` stations on their station codes
+    ///
+    /// Codes are compared the way dock kiosks are read, so "08026" matches "8026".
+    #[must_use]
+    pub fn build(
+        reference: &[StationReference],
+        gbfs: &[GbfsStation],
+        generated_at: DateTime<Utc>,
+    ) -> Self {
+        let index = KioskCodeIndex::build(reference);
+        let mut mappings = Vec::new();
+        let mut unmatched_gbfs_ids = Vec::new();
+        let mut matched = HashSet::new();
+        for station in gbfs {
+            let code = station
+                .station_code
+                .as_deref()
+                .and_then(|code| index.resolve(code))
+                .filter(|code| matched.insert(code.clone()));
+            match code {
+                Some(code) => mappings.push(StationIdPair {
+                    station_code: code.to_string(),
+                    gbfs_station_id: station.station_id.clone(),
+                    name: station.name.clone(),
+                }),
+                None => unmatched_gbfs_ids.push(station.station_id.clone()),
+            }
+        }
+        mappings.sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        unmatched_gbfs_ids.sort();
+        let mut unmatched_station_codes: Vec<String> = reference
+            .iter()
+            .filter(|station| !matched.contains(&station.station_code))
+            .map(|station| station.station_code.to_string())
+            .collect();
+        unmatched_station_codes.sort();
+        Self {
+            generated_at,
+            total_mappings: mappings.len(),
+            mappings,
+            unmatched_station_codes,
+            unmatched_gbfs_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinates, ServiceCapabilities};
+    use serde_json::json;
+
+    fn reference(code: &str) -> StationReference {
+        StationReference {
+            station_code: code.into(),
+            name: code.into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    #[test]
+    fn test_gbfs_ids_are_joined_on_station_codes() {
+        let gbfs = parse_station_information(&json!({"data": {"stations": [
+            {"station_id": 213688169, "stationCode": "16107", "name": "Benjamin Godard - Victor Hugo"},
+            {"station_id": "66526", "stationCode": "08026", "name": "Rond-Point des Champs-Élysées"},
+            {"station_id": 1, "stationCode": "16107", "name": "Duplicate"},
+            {"station_id": 2, "name": "No code"},
+            {"name": "No id"},
+        ]}}))
+        .unwrap();
+        assert_eq!(gbfs.len(), 4);
+
+        let reference = [reference("8026"), reference("16107"), reference("44015")];
+        let mapping = StationIdMapping::build(&reference, &gbfs, Utc::now());
+        let pairs: Vec<(&str, &str)> = mapping
+            .mappings
+            .iter()
+            .map(|pair| (&*pair.station_code, &*pair.gbfs_station_id))
+            .collect();
+        assert_eq!(pairs, [("16107", "213688169"), ("8026", "66526")]);
+        assert_eq!(mapping.unmatched_station_codes, ["44015"]);
+        assert_eq!(mapping.unmatched_gbfs_ids, ["1", "2"]);
+
+        assert!(matches!(
+            parse_station_information(&json!({"stations": []})),
+            Err(Error::Internal(_))
+        ));
+    }
+}
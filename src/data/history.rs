@@ -0,0 +1,444 @@
+use crate::data::export::HistoryRecord;
+use crate::types::{AvailabilityTrend, RealTimeStatus};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// How far back a station's trend looks
+pub const DEFAULT_TREND_WINDOW_MINUTES: i64 = 15;
+
+/// How long samples are kept by default, e.g. for sparklines
+pub const HISTORY_RETENTION_HOURS: i64 = 24;
+
+/// How often history is trimmed and thinned by the server's compaction task
+pub const DEFAULT_HISTORY_COMPACTION_INTERVAL_SECONDS: u64 = 600;
+
+/// Outside the trend window only one sample per bucket of this size is kept
+const COARSE_SAMPLE_MINUTES: i64 = 10;
+
+/// Shortest span of samples a trend is computed from
+const MIN_TREND_SPAN_MINUTES: i64 = 5;
+
+/// Net change in bikes over the window before a station counts as filling or emptying
+const TREND_THRESHOLD_BIKES: i32 = 3;
+
+/// Samples encoded as deltas after each keyframe
+const KEYFRAME_INTERVAL: usize = 32;
+
+/// A sample relative to the one before it
+#[derive(Debug, Clone, Copy)]
+struct Delta {
+    after_seconds: u32,
+    bikes_change: i16,
+}
+
+/// A keyframe holding absolute values, then the deltas recorded after it
+#[derive(Debug, Clone)]
+struct Segment {
+    start: DateTime<Utc>,
+    start_bikes: u16,
+    deltas: Vec<Delta>,
+    // Last decoded sample, so appending needs no decoding
+    end: DateTime<Utc>,
+    end_bikes: u16,
+}
+
+impl Segment {
+    fn new(at: DateTime<Utc>, bikes: u16) -> Self {
+        Self {
+            start: at,
+            start_bikes: bikes,
+            deltas: Vec::new(),
+            end: at,
+            end_bikes: bikes,
+        }
+    }
+
+    /// Append as a delta, or `false` when the sample needs a keyframe of its own
+    fn push(&mut self, at: DateTime<Utc>, bikes: u16) -> bool {
+        if self.deltas.len() >= KEYFRAME_INTERVAL {
+            return false;
+        }
+        let (Ok(after_seconds), Ok(bikes_change)) = (
+            u32::try_from((at - self.end).num_seconds()),
+            i16::try_from(i32::from(bikes) - i32::from(self.end_bikes)),
+        ) else {
+            return false;
+        };
+        self.deltas.push(Delta {
+            after_seconds,
+            bikes_change,
+        });
+        self.end = at;
+        self.end_bikes = bikes;
+        true
+    }
+
+    fn samples(&self) -> impl Iterator<Item = (DateTime<Utc>, u16)> + '_ {
+        let first = (self.start, self.start_bikes);
+        std::iter::once(first).chain(self.deltas.iter().scan(first, |(at, bikes), delta| {
+            *at += Duration::seconds(i64::from(delta.after_seconds));
+            *bikes = bikes.wrapping_add_signed(delta.bikes_change);
+            Some((*at, *bikes))
+        }))
+    }
+
+    fn bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.deltas.capacity() * std::mem::size_of::<Delta>()
+    }
+}
+
+/// One station's samples, oldest first
+#[derive(Debug, Clone, Default)]
+struct Track {
+    segments: VecDeque<Segment>,
+}
+
+impl Track {
+    fn from_samples(samples: impl IntoIterator<Item = (DateTime<Utc>, u16)>) -> Self {
+        let mut track = Self::default();
+        for (at, bikes) in samples {
+            track.push(at, bikes);
+        }
+        track
+    }
+
+    fn last(&self) -> Option<(DateTime<Utc>, u16)> {
+        self.segments
+            .back()
+            .map(|segment| (segment.end, segment.end_bikes))
+    }
+
+    fn push(&mut self, at: DateTime<Utc>, bikes: u16) {
+        if !self
+            .segments
+            .back_mut()
+            .is_some_and(|segment| segment.push(at, bikes))
+        {
+            self.segments.push_back(Segment::new(at, bikes));
+        }
+    }
+
+    /// Samples from `since` on, skipping segments that end before it
+    fn samples_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> impl Iterator<Item = (DateTime<Utc>, u16)> + '_ {
+        self.segments
+            .iter()
+            .filter(move |segment| segment.end >= since)
+            .flat_map(Segment::samples)
+            .filter(move |&(at, _)| at >= since)
+    }
+
+    fn len(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.deltas.len() + 1)
+            .sum()
+    }
+}
+
+/// Memory held by the availability history, reported by `velib://health`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HistoryStats {
+    pub stations: usize,
+    pub samples: usize,
+    pub keyframes: usize,
+    /// Estimate of the heap and inline memory the samples take
+    pub approx_bytes: usize,
+    pub retention_hours: i64,
+    pub last_compacted_at: Option<DateTime<Utc>>,
+}
+
+/// Bike counts per station over the retention window, sampled on every real-time refresh
+///
+/// Samples are keyed by the upstream `duedate` when known, so an unchanged
+/// record refreshed twice is only counted once. Each station keeps a keyframe
+/// every `KEYFRAME_INTERVAL` samples and small deltas in between. Recording
+/// only drops segments past retention; [`Self::compact`] trims the rest and
+/// thins samples older than the trend window to one per
+/// `COARSE_SAMPLE_MINUTES`, to bound memory across ~1500 stations.
+#[derive(Debug, Clone)]
+pub struct AvailabilityHistory {
+    window: Duration,
+    retention: Duration,
+    tracks: HashMap<String, Track>,
+    last_compacted_at: Option<DateTime<Utc>>,
+}
+
+impl Default for AvailabilityHistory {
+    fn default() -> Self {
+        Self::new(Duration::minutes(DEFAULT_TREND_WINDOW_MINUTES))
+    }
+}
+
+impl AvailabilityHistory {
+    #[must_use]
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            retention: Duration::hours(HISTORY_RETENTION_HOURS),
+            tracks: HashMap::new(),
+            last_compacted_at: None,
+        }
+    }
+
+    /// Keep samples for `retention` instead of `HISTORY_RETENTION_HOURS`
+    #[must_use]
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention.max(self.window);
+        self
+    }
+
+    /// Add a refreshed snapshot and forget segments entirely past retention
+    pub fn record(&mut self, snapshot: &HashMap<String, RealTimeStatus>, now: DateTime<Utc>) {
+        let cutoff = now - self.retention;
+        for (code, status) in snapshot {
+            let at = status.last_update.unwrap_or(now);
+            let track = self.tracks.entry(code.clone()).or_default();
+            match track.last() {
+                Some((last, _)) if last >= at => {}
+                _ => track.push(at, status.bikes.total()),
+            }
+            while track
+                .segments
+                .front()
+                .is_some_and(|segment| segment.end < cutoff)
+            {
+                track.segments.pop_front();
+            }
+        }
+        self.tracks.retain(|_, track| !track.segments.is_empty());
+    }
+
+    /// Drop samples past retention, thin those older than the trend window
+    /// and re-encode what is left
+    pub fn compact(&mut self, now: DateTime<Utc>) -> HistoryStats {
+        self.compact_within(now, self.retention)
+    }
+
+    /// Compact as if retention were `keep`, never under the trend window,
+    /// to shed memory; the configured retention applies again afterwards
+    pub fn trim(&mut self, now: DateTime<Utc>, keep: Duration) -> HistoryStats {
+        self.compact_within(now, keep.clamp(self.window, self.retention))
+    }
+
+    fn compact_within(&mut self, now: DateTime<Utc>, retention: Duration) -> HistoryStats {
+        let cutoff = now - retention;
+        let fine_after = now - self.window;
+        for track in self.tracks.values_mut() {
+            let mut samples: Vec<(DateTime<Utc>, u16)> = track.samples_since(cutoff).collect();
+            thin_out(&mut samples, fine_after);
+            *track = Track::from_samples(samples);
+        }
+        self.tracks.retain(|_, track| !track.segments.is_empty());
+        self.last_compacted_at = Some(now);
+        self.stats()
+    }
+
+    /// How much the history holds and roughly how much memory it takes
+    #[must_use]
+    pub fn stats(&self) -> HistoryStats {
+        let mut stats = HistoryStats {
+            stations: self.tracks.len(),
+            samples: 0,
+            keyframes: 0,
+            approx_bytes: 0,
+            retention_hours: self.retention.num_hours(),
+            last_compacted_at: self.last_compacted_at,
+        };
+        for (code, track) in &self.tracks {
+            stats.samples += track.len();
+            stats.keyframes += track.segments.len();
+            stats.approx_bytes += code.capacity()
+                + std::mem::size_of::<(String, Track)>()
+                + track.segments.iter().map(Segment::bytes).sum::<usize>();
+        }
+        stats
+    }
+
+    /// Trend of a station over the window, or `None` without enough history
+    #[must_use]
+    pub fn trend(&self, station_code: &str, now: DateTime<Utc>) -> Option<AvailabilityTrend> {
+        let cutoff = now - self.window;
+        let track = self.tracks.get(station_code)?;
+        let (oldest_at, oldest_bikes) = track.samples_since(cutoff).next()?;
+        let (latest_at, latest_bikes) = track.last()?;
+        if latest_at - oldest_at < Duration::minutes(MIN_TREND_SPAN_MINUTES) {
+            return None;
+        }
+
+        let change = i32::from(latest_bikes) - i32::from(oldest_bikes);
+        Some(if change >= TREND_THRESHOLD_BIKES {
+            AvailabilityTrend::Filling
+        } else if change <= -TREND_THRESHOLD_BIKES {
+            AvailabilityTrend::Emptying
+        } else {
+            AvailabilityTrend::Stable
+        })
+    }
+
+    /// A station's `(time, bikes)` samples since `since`, oldest first
+    #[must_use]
+    pub fn series(&self, station_code: &str, since: DateTime<Utc>) -> Vec<(DateTime<Utc>, u16)> {
+        self.tracks
+            .get(station_code)
+            .map(|track| track.samples_since(since).collect())
+            .unwrap_or_default()
+    }
+
+    /// Every station's samples since `since`, by station code then time
+    #[must_use]
+    pub fn records(&self, since: DateTime<Utc>) -> Vec<HistoryRecord> {
+        let mut codes: Vec<&String> = self.tracks.keys().collect();
+        codes.sort();
+        codes
+            .into_iter()
+            .flat_map(|code| {
+                self.tracks[code]
+                    .samples_since(since)
+                    .map(|(observed_at, bikes)| HistoryRecord {
+                        station_code: code.clone(),
+                        observed_at,
+                        bikes,
+                    })
+            })
+            .collect()
+    }
+
+    /// Set `trend` on every status that has enough history
+    pub fn annotate(&self, statuses: &mut HashMap<String, RealTimeStatus>, now: DateTime<Utc>) {
+        for (code, status) in statuses.iter_mut() {
+            status.trend = self.trend(code, now);
+        }
+    }
+}
+
+/// Keep the first sample of each coarse bucket among those before `fine_after`
+fn thin_out(samples: &mut Vec<(DateTime<Utc>, u16)>, fine_after: DateTime<Utc>) {
+    let mut last_bucket = None;
+    samples.retain(|&(at, _)| {
+        if at >= fine_after {
+            return true;
+        }
+        let bucket = at.timestamp() / (COARSE_SAMPLE_MINUTES * 60);
+        let keep = last_bucket != Some(bucket);
+        last_bucket = Some(bucket);
+        keep
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, StationStatus};
+
+    fn snapshot(bikes: u16, at: DateTime<Utc>) -> HashMap<String, RealTimeStatus> {
+        let status =
+            RealTimeStatus::new(BikeAvailability::new(bikes, 0), 20, StationStatus::Open, at);
+        HashMap::from([("16107".to_string(), status)])
+    }
+
+    #[test]
+    fn test_trend_follows_net_change_over_window() {
+        let now = Utc::now();
+        let mut history = AvailabilityHistory::default();
+
+        history.record(&snapshot(12, now - Duration::minutes(10)), now);
+        assert_eq!(history.trend("16107", now), None, "a single sample");
+
+        history.record(&snapshot(10, now - Duration::minutes(5)), now);
+        history.record(&snapshot(4, now), now);
+        assert_eq!(
+            history.trend("16107", now),
+            Some(AvailabilityTrend::Emptying)
+        );
+
+        let mut filling = AvailabilityHistory::default();
+        filling.record(&snapshot(2, now - Duration::minutes(8)), now);
+        filling.record(&snapshot(3, now), now);
+        assert_eq!(filling.trend("16107", now), Some(AvailabilityTrend::Stable));
+        filling.record(&snapshot(6, now + Duration::minutes(1)), now);
+        assert_eq!(
+            filling.trend("16107", now + Duration::minutes(1)),
+            Some(AvailabilityTrend::Filling)
+        );
+    }
+
+    #[test]
+    fn test_old_and_repeated_samples_are_ignored() {
+        let now = Utc::now();
+        let mut history = AvailabilityHistory::default();
+
+        // Emptied long ago, then unchanged records refreshed repeatedly
+        history.record(&snapshot(20, now - Duration::minutes(40)), now);
+        history.record(&snapshot(2, now - Duration::minutes(6)), now);
+        history.record(&snapshot(2, now - Duration::minutes(6)), now);
+        history.record(&snapshot(2, now), now);
+
+        assert_eq!(history.trend("16107", now), Some(AvailabilityTrend::Stable));
+        assert_eq!(history.tracks["16107"].len(), 3);
+
+        // Past the trend window, minute-by-minute samples thin out to one per bucket
+        let mut day = AvailabilityHistory::default();
+        for minute in (0..=120).rev() {
+            day.record(&snapshot(5, now - Duration::minutes(minute)), now);
+        }
+        day.compact(now);
+        let kept = day.series("16107", now - Duration::hours(3)).len();
+        assert!((25..=40).contains(&kept), "{kept} samples kept");
+
+        let next_day = now + Duration::hours(HISTORY_RETENTION_HOURS) + Duration::minutes(1);
+        day.record(&snapshot(5, next_day), next_day);
+        day.compact(next_day);
+        assert_eq!(day.series("16107", now - Duration::hours(3)).len(), 1);
+    }
+
+    #[test]
+    fn test_deltas_round_trip_across_keyframes() {
+        let start = Utc::now() - Duration::hours(2);
+        let mut history = AvailabilityHistory::default();
+        let recorded: Vec<(DateTime<Utc>, u16)> = (0..100)
+            .map(|i| (start + Duration::seconds(i * 61), (i * 7 % 25) as u16))
+            .collect();
+        for &(at, bikes) in &recorded {
+            history.record(&snapshot(bikes, at), at);
+        }
+
+        let decoded = history.series("16107", start);
+        assert_eq!(
+            decoded
+                .iter()
+                .map(|&(at, bikes)| (at.timestamp(), bikes))
+                .collect::<Vec<_>>(),
+            recorded
+                .iter()
+                .map(|&(at, bikes)| (at.timestamp(), bikes))
+                .collect::<Vec<_>>()
+        );
+        let stats = history.stats();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.keyframes, 100usize.div_ceil(KEYFRAME_INTERVAL + 1));
+        assert!(stats.approx_bytes < 100 * std::mem::size_of::<(DateTime<Utc>, u16)>());
+
+        let records = history.records(start + Duration::seconds(99 * 61));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].station_code, "16107");
+    }
+
+    #[test]
+    fn test_retention_is_configurable() {
+        let now = Utc::now();
+        let mut history = AvailabilityHistory::default().with_retention(Duration::hours(1));
+        history.record(&snapshot(3, now - Duration::minutes(90)), now);
+        history.record(&snapshot(4, now - Duration::minutes(30)), now);
+        history.record(&snapshot(5, now), now);
+
+        let stats = history.compact(now);
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.retention_hours, 1);
+        assert_eq!(stats.last_compacted_at, Some(now));
+        assert_eq!(history.series("16107", now - Duration::hours(2))[0].1, 4);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Hands out one shared `Arc<str>` per distinct string
+///
+/// Station codes and names come back identical on every refresh; interning
+/// them keeps a single copy however many snapshots and indexes refer to it.
+#[derive(Debug, Default)]
+pub struct StringInterner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl StringInterner {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `value`, added on first sight
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(value) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = Arc::from(value);
+        self.strings.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Forget strings nothing else refers to anymore
+    pub fn release_unused(&mut self) {
+        self.strings
+            .retain(|interned| Arc::strong_count(interned) > 1);
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interned_strings_are_shared() {
+        let mut interner = StringInterner::new();
+        let first = interner.intern("Gare de Lyon - Diderot");
+        let again = interner.intern(&String::from("Gare de Lyon - Diderot"));
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(interner.intern("12001").as_ref(), "12001");
+        assert_eq!(interner.len(), 2);
+
+        drop((first, again));
+        interner.release_unused();
+        assert_eq!(interner.len(), 0);
+    }
+}
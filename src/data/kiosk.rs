@@ -0,0 +1,159 @@
+use crate::types::StationReference;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Query parameters QR code links carry the station code in
+const QR_CODE_PARAMETERS: [&str; 4] = ["station", "stationcode", "station_code", "code"];
+
+/// Station identifiers as displayed on dock kiosks, resolved to `stationcode`s
+///
+/// Kiosks and their QR codes show codes with leading zeros ("08026"),
+/// separators ("16 107", "N° 16107") or inside a link, while the open data
+/// has "8026". Codes with a suffix, such as "16107_2", are also found by their
+/// digits alone when no other station has them. Rebuilt with the reference data.
+#[derive(Debug, Default)]
+pub struct KioskCodeIndex {
+    // Normalized code to station, `None` when several stations share it
+    codes: HashMap<String, Option<Arc<str>>>,
+    // The same for the leading digits of codes with a suffix
+    prefixes: HashMap<String, Option<Arc<str>>>,
+}
+
+impl KioskCodeIndex {
+    #[must_use]
+    pub fn build(stations: &[StationReference]) -> Self {
+        let mut index = Self::default();
+        for station in stations {
+            let code = &station.station_code;
+            insert(&mut index.codes, normalize_kiosk_code(code), code);
+            let digits: String = code.chars().take_while(char::is_ascii_digit).collect();
+            if !digits.is_empty() && digits.len() < code.len() {
+                insert(&mut index.prefixes, normalize_kiosk_code(&digits), code);
+            }
+        }
+        index
+    }
+
+    /// The `stationcode` a kiosk-displayed identifier or QR code payload
+    /// designates, if exactly one station matches
+    #[must_use]
+    pub fn resolve(&self, displayed: &str) -> Option<Arc<str>> {
+        let key = normalize_kiosk_code(displayed);
+        match self.codes.get(&key) {
+            Some(station) => station.clone(),
+            None => self.prefixes.get(&key).cloned().flatten(),
+        }
+    }
+}
+
+fn insert(codes: &mut HashMap<String, Option<Arc<str>>>, key: String, code: &Arc<str>) {
+    if key.is_empty() {
+        return;
+    }
+    codes
+        .entry(key)
+        .and_modify(|station| *station = None)
+        .or_insert_with(|| Some(Arc::clone(code)));
+}
+
+/// Reduce a displayed station identifier to the form codes are indexed under
+///
+/// Links keep their code query parameter or last path segment. Case,
+/// separators, a `N°` or `Station` prefix and leading zeros are dropped.
+#[must_use]
+pub fn normalize_kiosk_code(displayed: &str) -> String {
+    let displayed = displayed.trim();
+    let code = if displayed.contains("://") {
+        code_from_link(displayed)
+    } else {
+        displayed
+    };
+
+    let compact: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let numbered = ["STATION", "NO", "N"]
+        .iter()
+        .find_map(|prefix| {
+            compact
+                .strip_prefix(prefix)
+                .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .unwrap_or(&compact);
+    match numbered.trim_start_matches('0') {
+        "" if !numbered.is_empty() => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// The station code in a QR code link
+fn code_from_link(link: &str) -> &str {
+    let link = link.split('#').next().unwrap_or(link);
+    let (path, query) = link.split_once('?').unwrap_or((link, ""));
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| {
+            QR_CODE_PARAMETERS
+                .iter()
+                .any(|parameter| key.eq_ignore_ascii_case(parameter))
+        })
+        .map(|(_, value)| value)
+        .or_else(|| path.rsplit('/').find(|segment| !segment.is_empty()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinates, ServiceCapabilities};
+
+    fn station(code: &str) -> StationReference {
+        StationReference {
+            station_code: Arc::from(code),
+            name: Arc::from(code),
+            coordinates: Coordinates::new(48.86, 2.35),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    #[test]
+    fn test_normalizes_displayed_codes() {
+        for displayed in [
+            "16107",
+            " 16 107 ",
+            "016107",
+            "N° 16107",
+            "no.16107",
+            "Station 16-107",
+            "https://www.velib-metropole.fr/station/16107",
+            "https://www.velib-metropole.fr/station/16107/",
+            "https://velib.example/qr?lang=fr&stationCode=016107#map",
+        ] {
+            assert_eq!(normalize_kiosk_code(displayed), "16107", "{displayed}");
+        }
+        assert_eq!(normalize_kiosk_code("000"), "0");
+        assert_eq!(normalize_kiosk_code(""), "");
+    }
+
+    #[test]
+    fn test_resolves_unambiguous_codes_only() {
+        let index = KioskCodeIndex::build(&[
+            station("8026"),
+            station("16107_2"),
+            station("21010_1"),
+            station("21010_2"),
+        ]);
+        assert_eq!(index.resolve("08026").as_deref(), Some("8026"));
+        assert_eq!(index.resolve("16107").as_deref(), Some("16107_2"));
+        assert_eq!(index.resolve("16107 2").as_deref(), Some("16107_2"));
+        assert_eq!(index.resolve("21010"), None);
+        assert_eq!(index.resolve("99999"), None);
+    }
+}
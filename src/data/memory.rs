@@ -0,0 +1,187 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Shortest time between two trims, since freed memory rarely goes back to
+/// the system at once and resident size stays high for a while
+pub const MEMORY_TRIM_COOLDOWN_MINUTES: i64 = 10;
+
+/// Resident set size of this process, where the platform reports it
+#[must_use]
+pub fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// What the process and the data client's buffers hold, for `/metrics` and
+/// `velib://health`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// `None` where the platform does not report it
+    pub resident_bytes: Option<u64>,
+    pub soft_limit_bytes: Option<u64>,
+    /// Stations in the current reference and real-time snapshots
+    pub reference_stations: usize,
+    pub realtime_stations: usize,
+    /// Estimate of the availability history's memory, and its samples
+    pub history_bytes: usize,
+    pub history_samples: usize,
+    pub fleet_samples: usize,
+    pub reference_cache_entries: usize,
+    pub realtime_cache_entries: usize,
+    /// Upstream pages kept to answer `304 Not Modified`
+    pub cached_pages: usize,
+    /// Times the soft limit made the history and caches shrink
+    pub trims: u64,
+    pub last_trimmed_at: Option<DateTime<Utc>>,
+}
+
+impl MemoryStats {
+    /// Render as Prometheus gauges; unknown values are left out
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let gauges = [
+            (
+                "velib_memory_resident_bytes",
+                "Resident set size of the process",
+                self.resident_bytes,
+            ),
+            (
+                "velib_memory_soft_limit_bytes",
+                "Resident size above which history and caches are trimmed",
+                self.soft_limit_bytes,
+            ),
+            (
+                "velib_reference_stations",
+                "Stations in the reference snapshot",
+                Some(self.reference_stations as u64),
+            ),
+            (
+                "velib_realtime_stations",
+                "Stations in the real-time snapshot",
+                Some(self.realtime_stations as u64),
+            ),
+            (
+                "velib_history_bytes",
+                "Estimated memory of the availability history",
+                Some(self.history_bytes as u64),
+            ),
+            (
+                "velib_history_samples",
+                "Samples in the availability history",
+                Some(self.history_samples as u64),
+            ),
+            (
+                "velib_cache_entries",
+                "Entries in the reference and real-time caches",
+                Some((self.reference_cache_entries + self.realtime_cache_entries) as u64),
+            ),
+            (
+                "velib_cached_pages",
+                "Upstream pages kept for conditional requests",
+                Some(self.cached_pages as u64),
+            ),
+        ];
+        for (name, help, value) in gauges {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+                );
+            }
+        }
+        let name = "velib_memory_trims_total";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Times the memory soft limit trimmed history and caches\n# TYPE {name} counter\n{name} {}",
+            self.trims
+        );
+        out
+    }
+}
+
+/// Decides when resident memory over a soft limit calls for a trim
+#[derive(Debug, Clone, Default)]
+pub struct MemoryGuard {
+    soft_limit_bytes: Option<u64>,
+    trims: u64,
+    last_trimmed_at: Option<DateTime<Utc>>,
+}
+
+impl MemoryGuard {
+    /// No limit with `None`
+    #[must_use]
+    pub fn new(soft_limit_bytes: Option<u64>) -> Self {
+        Self {
+            soft_limit_bytes,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn soft_limit_bytes(&self) -> Option<u64> {
+        self.soft_limit_bytes
+    }
+
+    /// Whether `resident` bytes call for a trim now, which is then counted
+    pub fn should_trim(&mut self, resident: Option<u64>, now: DateTime<Utc>) -> bool {
+        let over = matches!(
+            (resident, self.soft_limit_bytes),
+            (Some(resident), Some(limit)) if resident > limit
+        );
+        let cooled_down = self
+            .last_trimmed_at
+            .is_none_or(|last| now - last >= Duration::minutes(MEMORY_TRIM_COOLDOWN_MINUTES));
+        if over && cooled_down {
+            self.trims += 1;
+            self.last_trimmed_at = Some(now);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[must_use]
+    pub fn trims(&self) -> u64 {
+        self.trims
+    }
+
+    #[must_use]
+    pub fn last_trimmed_at(&self) -> Option<DateTime<Utc>> {
+        self.last_trimmed_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trims_over_the_limit_once_per_cooldown() {
+        let now = Utc::now();
+        let mut guard = MemoryGuard::new(Some(512 * 1024 * 1024));
+        assert!(!guard.should_trim(Some(100 * 1024 * 1024), now));
+        assert!(!guard.should_trim(None, now));
+        assert!(guard.should_trim(Some(600 * 1024 * 1024), now));
+        assert!(!guard.should_trim(Some(600 * 1024 * 1024), now + Duration::minutes(1)));
+        assert!(guard.should_trim(
+            Some(600 * 1024 * 1024),
+            now + Duration::minutes(MEMORY_TRIM_COOLDOWN_MINUTES)
+        ));
+        assert_eq!(guard.trims(), 2);
+
+        assert!(!MemoryGuard::default().should_trim(Some(u64::MAX), now));
+        if cfg!(target_os = "linux") {
+            assert!(resident_bytes().is_some_and(|bytes| bytes > 0));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::types::StationReference;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Which entry a station code keeps when the reference dataset lists it more than once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// The entry listed last, usually the station after a relocation
+    #[default]
+    Latest,
+    /// The entry listed first
+    First,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = Error;
+
+    fn from_str(policy: &str) -> Result<Self> {
+        match policy.trim().to_ascii_lowercase().as_str() {
+            "latest" => Ok(Self::Latest),
+            "first" => Ok(Self::First),
+            other => Err(Error::Config(format!(
+                "Unknown duplicate station policy '{other}', expected latest or first"
+            ))),
+        }
+    }
+}
+
+/// Collapse entries sharing a station code into one, kept where the code first
+/// appeared, and return how many entries were merged away
+///
+/// `policy` picks the entry that wins; a commune and INSEE code missing from it
+/// are taken from the other. Entries disagreeing on name, position or capacity
+/// are logged.
+pub fn merge_duplicates(
+    stations: Vec<StationReference>,
+    policy: DuplicatePolicy,
+) -> (Vec<StationReference>, usize) {
+    let mut merged: Vec<StationReference> = Vec::with_capacity(stations.len());
+    let mut positions: HashMap<Arc<str>, usize> = HashMap::new();
+    let mut merges = 0;
+
+    for station in stations {
+        let Some(&position) = positions.get(&station.station_code) else {
+            positions.insert(station.station_code.clone(), merged.len());
+            merged.push(station);
+            continue;
+        };
+        merges += 1;
+        let kept = &merged[position];
+        if kept.name != station.name
+            || kept.coordinates != station.coordinates
+            || kept.capacity != station.capacity
+        {
+            warn!(
+                station_code = &*station.station_code,
+                first = ?(&*kept.name, kept.coordinates, kept.capacity),
+                latest = ?(&*station.name, station.coordinates, station.capacity),
+                "Reference dataset lists station twice with different details, keeping the {:?} entry",
+                policy
+            );
+        }
+        let (winner, other) = match policy {
+            DuplicatePolicy::Latest => (station, merged[position].clone()),
+            DuplicatePolicy::First => (merged[position].clone(), station),
+        };
+        merged[position] = if winner.commune.is_none() && winner.insee_code.is_none() {
+            winner.with_commune(other.commune, other.insee_code)
+        } else {
+            winner
+        };
+    }
+    (merged, merges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinates, ServiceCapabilities};
+
+    fn station(code: &str, name: &str, latitude: f64) -> StationReference {
+        StationReference {
+            station_code: code.into(),
+            name: name.into(),
+            coordinates: Coordinates::new(latitude, 2.3522),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    #[test]
+    fn test_one_entry_per_station_code() {
+        let stations = vec![
+            station("12001", "Gare de Lyon", 48.8443)
+                .with_commune(Some("Paris".to_string()), Some("75112".to_string())),
+            station("11001", "Bastille", 48.8532),
+            // Relocated across the forecourt, listed without its commune
+            station("12001", "Gare de Lyon - Parvis", 48.8446),
+        ];
+
+        let (latest, merges) = merge_duplicates(stations.clone(), DuplicatePolicy::Latest);
+        assert_eq!(merges, 1);
+        let codes: Vec<&str> = latest.iter().map(|s| &*s.station_code).collect();
+        assert_eq!(codes, ["12001", "11001"]);
+        assert_eq!(&*latest[0].name, "Gare de Lyon - Parvis");
+        assert_eq!(latest[0].coordinates.latitude, 48.8446);
+        assert_eq!(latest[0].insee_code.as_deref(), Some("75112"));
+
+        let (first, _) = merge_duplicates(stations, DuplicatePolicy::First);
+        assert_eq!(&*first[0].name, "Gare de Lyon");
+
+        assert_eq!(
+            "First".parse::<DuplicatePolicy>().unwrap(),
+            DuplicatePolicy::First
+        );
+        assert!(matches!(
+            "newest".parse::<DuplicatePolicy>(),
+            Err(Error::Config(_))
+        ));
+    }
+}
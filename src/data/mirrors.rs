@@ -0,0 +1,171 @@
+use crate::data::breaker::CircuitBreaker;
+use chrono::{DateTime, Utc};
+
+/// An upstream endpoint and the breaker guarding it
+#[derive(Debug, Clone)]
+struct Endpoint {
+    /// Prefixed to the path and query of dataset URLs; `None` for the
+    /// endpoints as configured
+    base: Option<String>,
+    breaker: CircuitBreaker,
+}
+
+/// The configured dataset endpoints followed by mirrors serving the same
+/// paths, each with its own circuit breaker
+///
+/// Requests go to endpoints whose breaker is closed, in configured order, then
+/// to those whose cooldown just ended, so an outage of the primary shifts
+/// traffic to a mirror until a trial request finds the primary back.
+#[derive(Debug, Clone)]
+pub struct UpstreamMirrors {
+    endpoints: Vec<Endpoint>,
+}
+
+impl Default for UpstreamMirrors {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl UpstreamMirrors {
+    /// The configured endpoints, then `mirrors` as base URLs such as
+    /// `https://velib-proxy.example.org` or `https://proxy.example.org/paris`
+    #[must_use]
+    pub fn new(mirrors: &[String]) -> Self {
+        let mut endpoints = vec![Endpoint {
+            base: None,
+            breaker: CircuitBreaker::default(),
+        }];
+        endpoints.extend(mirrors.iter().map(|base| Endpoint {
+            base: Some(base.trim_end_matches('/').to_string()),
+            breaker: CircuitBreaker::default(),
+        }));
+        Self { endpoints }
+    }
+
+    /// Endpoints to try now, best first, or the seconds until one may be
+    /// tried again when every breaker rejects
+    pub fn order(&self, now: DateTime<Utc>) -> std::result::Result<Vec<usize>, u64> {
+        let (closed, trial): (Vec<usize>, Vec<usize>) = (0..self.endpoints.len())
+            .filter(|&endpoint| self.endpoints[endpoint].breaker.rejects(now).is_none())
+            .partition(|&endpoint| !self.endpoints[endpoint].breaker.is_open());
+        if closed.is_empty() && trial.is_empty() {
+            return Err(self
+                .endpoints
+                .iter()
+                .filter_map(|endpoint| endpoint.breaker.rejects(now))
+                .min()
+                .unwrap_or(1));
+        }
+        Ok(closed.into_iter().chain(trial).collect())
+    }
+
+    /// Just the configured endpoint, for URLs no mirror serves, or the seconds
+    /// until its breaker lets a request through
+    pub fn primary(&self, now: DateTime<Utc>) -> std::result::Result<Vec<usize>, u64> {
+        self.endpoints[0]
+            .breaker
+            .rejects(now)
+            .map_or(Ok(vec![0]), Err)
+    }
+
+    /// `url` as served by `endpoint`: a mirror keeps its path and query
+    #[must_use]
+    pub fn url(&self, endpoint: usize, url: &str) -> String {
+        let Some(base) = self.endpoints.get(endpoint).and_then(|e| e.base.as_ref()) else {
+            return url.to_string();
+        };
+        match reqwest::Url::parse(url) {
+            Ok(parsed) => match parsed.query() {
+                Some(query) => format!("{base}{}?{query}", parsed.path()),
+                None => format!("{base}{}", parsed.path()),
+            },
+            Err(_) => url.to_string(),
+        }
+    }
+
+    /// How `endpoint` is named in logs: its base URL, or "primary"
+    #[must_use]
+    pub fn name(&self, endpoint: usize) -> &str {
+        self.endpoints
+            .get(endpoint)
+            .and_then(|e| e.base.as_deref())
+            .unwrap_or("primary")
+    }
+
+    pub fn record_success(&mut self, endpoint: usize) {
+        if let Some(endpoint) = self.endpoints.get_mut(endpoint) {
+            endpoint.breaker.record_success();
+        }
+    }
+
+    pub fn record_failure(&mut self, endpoint: usize, now: DateTime<Utc>) {
+        if let Some(endpoint) = self.endpoints.get_mut(endpoint) {
+            endpoint.breaker.record_failure(now);
+        }
+    }
+
+    /// Whether every endpoint's breaker is open, so upstream fetches fail fast
+    #[must_use]
+    pub fn all_open(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.breaker.is_open())
+    }
+
+    /// Number of endpoints, the configured one included
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::breaker::{DEFAULT_COOLDOWN_SECONDS, DEFAULT_FAILURE_THRESHOLD};
+    use chrono::Duration;
+
+    #[test]
+    fn test_open_breakers_move_endpoints_down_the_order() {
+        let now = Utc::now();
+        let mut mirrors = UpstreamMirrors::new(&["https://proxy.example.org/paris/".to_string()]);
+        assert_eq!(mirrors.order(now), Ok(vec![0, 1]));
+        assert_eq!(
+            mirrors.url(1, "https://opendata.paris.fr/api/records?limit=1"),
+            "https://proxy.example.org/paris/api/records?limit=1"
+        );
+        assert_eq!(
+            mirrors.url(0, "http://fixtures.internal/"),
+            "http://fixtures.internal/"
+        );
+
+        for _ in 0..DEFAULT_FAILURE_THRESHOLD {
+            mirrors.record_failure(0, now);
+        }
+        assert_eq!(mirrors.order(now), Ok(vec![1]));
+        assert!(!mirrors.all_open());
+
+        // Once its cooldown ends the primary gets a trial, after the healthy mirror
+        let later = now + Duration::seconds(DEFAULT_COOLDOWN_SECONDS);
+        assert_eq!(mirrors.order(later), Ok(vec![1, 0]));
+        mirrors.record_success(0);
+        assert_eq!(mirrors.order(later), Ok(vec![0, 1]));
+
+        for endpoint in [0, 1] {
+            for _ in 0..DEFAULT_FAILURE_THRESHOLD {
+                mirrors.record_failure(endpoint, later);
+            }
+        }
+        assert!(mirrors.all_open());
+        assert_eq!(
+            mirrors.order(later + Duration::seconds(10)),
+            Err(u64::try_from(DEFAULT_COOLDOWN_SECONDS - 10).unwrap())
+        );
+    }
+}
@@ -1,6 +1,58 @@
+pub mod aliases;
+pub mod anomaly;
+pub mod barriers;
+pub mod breaker;
 pub mod cache;
+pub mod catalog;
+pub mod changes;
 pub mod client;
+pub mod conditional;
+pub mod enriched;
+pub mod export;
+pub mod fleet;
+pub mod gbfs;
+pub mod history;
+pub mod intern;
+pub mod kiosk;
+pub mod memory;
+pub mod merge;
+pub mod mirrors;
+pub mod outage;
+pub mod probe;
 pub mod retry;
+pub mod scheduler;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+pub mod transit;
+pub mod trie;
+pub mod version;
+pub mod watchdog;
 
+pub use aliases::{AliasTable, LandmarkAlias};
+pub use anomaly::{Advisory, AdvisoryKind, AdvisorySeverity, AnomalyDetector};
+pub use barriers::{Barrier, BarrierMap, Crossing};
+pub use breaker::CircuitBreaker;
+pub use catalog::{ReferenceReport, ReferenceSnapshot};
+pub use changes::{ChangeLog, ChangeLogStats, ChangeRetention, StationChange};
 pub use client::VelibDataClient;
+pub use conditional::{CachedPage, ConditionalCache, Validators};
+pub use enriched::{district_key, EnrichedStation};
+pub use export::{ExportFormat, HistoryRecord};
+pub use fleet::{FleetHistory, FleetSample};
+pub use gbfs::{GbfsStation, StationIdMapping, StationIdPair};
+pub use history::{AvailabilityHistory, HistoryStats};
+pub use intern::StringInterner;
+pub use kiosk::KioskCodeIndex;
+pub use memory::{MemoryGuard, MemoryStats};
+pub use merge::{merge_duplicates, DuplicatePolicy};
+pub use mirrors::UpstreamMirrors;
+pub use outage::OutageDetector;
+pub use probe::{ProbeOutcome, UpstreamProbe};
 pub use retry::{RetryConfig, RetryPolicy, RetryStrategy, RetryableHttpClient};
+pub use scheduler::{FetchPriority, UpstreamScheduler};
+#[cfg(feature = "simulation")]
+pub use simulation::{Neighborhood, SimulatedCity};
+pub use transit::{TransitNetwork, TransitSelection, TransitStop};
+pub use trie::{NameTrie, StationNameSuggestion};
+pub use version::{DataVersion, DataVersioner};
+pub use watchdog::{FeedStatus, FeedWatchdog};
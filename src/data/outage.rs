@@ -0,0 +1,147 @@
+use crate::types::{RealTimeStatus, StationReference, StationStatus};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// How long a station must look dead before it is suspected out of service
+pub const SUSPECT_AFTER_MINUTES: i64 = 60;
+
+/// Share of its capacity a station must report as bikes or free docks to look alive
+const MIN_REPORTED_SHARE: f64 = 0.1;
+
+/// Flags stations whose reported bikes and docks stay far below their capacity,
+/// which usually means broken docks or a dead terminal rather than an empty station
+///
+/// Each refresh a station either looks dead, reporting (nearly) nothing it
+/// could hold, or not; a station is suspected once it has looked dead for
+/// `SUSPECT_AFTER_MINUTES` straight. Stations the upstream reports `Closed`
+/// are already known to be out of service and are left alone.
+#[derive(Debug, Clone, Default)]
+pub struct OutageDetector {
+    capacities: HashMap<String, u16>,
+    // First refresh of the current dead stretch, per station
+    dead_since: HashMap<String, DateTime<Utc>>,
+    suspected: HashSet<String>,
+}
+
+impl OutageDetector {
+    /// Remember each station's capacity, to judge what it reports against
+    pub fn set_capacities(&mut self, stations: &[StationReference]) {
+        self.capacities = stations
+            .iter()
+            .map(|station| (station.station_code.to_string(), station.capacity))
+            .collect();
+    }
+
+    /// Record a refresh, updating which stations are suspected
+    pub fn observe(&mut self, snapshot: &HashMap<String, RealTimeStatus>, now: DateTime<Utc>) {
+        let dead: HashSet<&String> = snapshot
+            .iter()
+            .filter(|(code, status)| self.looks_dead(code, status))
+            .map(|(code, _)| code)
+            .collect();
+        self.dead_since.retain(|code, _| dead.contains(code));
+        for code in dead {
+            self.dead_since.entry(code.clone()).or_insert(now);
+        }
+        self.suspected = self
+            .dead_since
+            .iter()
+            .filter(|(_, since)| now - **since >= Duration::minutes(SUSPECT_AFTER_MINUTES))
+            .map(|(code, _)| code.clone())
+            .collect();
+    }
+
+    #[must_use]
+    pub fn is_suspected(&self, station_code: &str) -> bool {
+        self.suspected.contains(station_code)
+    }
+
+    fn looks_dead(&self, station_code: &str, status: &RealTimeStatus) -> bool {
+        if status.status == StationStatus::Closed {
+            return false;
+        }
+        let reported = f64::from(status.bikes.total()) + f64::from(status.available_docks);
+        match self.capacities.get(station_code) {
+            Some(&capacity) if capacity > 0 => reported < f64::from(capacity) * MIN_REPORTED_SHARE,
+            _ => reported == 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, Coordinates, ServiceCapabilities};
+
+    fn reference(code: &str, capacity: u16) -> StationReference {
+        StationReference {
+            station_code: code.into(),
+            name: code.into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    fn snapshot(
+        readings: &[(&str, u16, u16, StationStatus)],
+        now: DateTime<Utc>,
+    ) -> HashMap<String, RealTimeStatus> {
+        readings
+            .iter()
+            .map(|(code, bikes, docks, status)| {
+                (
+                    code.to_string(),
+                    RealTimeStatus::new_at(
+                        BikeAvailability::new(*bikes, 0),
+                        *docks,
+                        status.clone(),
+                        now,
+                        now,
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stations_dead_for_an_hour_are_suspected() {
+        let start = Utc::now();
+        let mut detector = OutageDetector::default();
+        detector.set_capacities(&[
+            reference("dead", 30),
+            reference("crippled", 30),
+            reference("empty", 30),
+            reference("closed", 30),
+        ]);
+        let readings = [
+            ("dead", 0, 0, StationStatus::Open),
+            // 2 of 30 docks answering
+            ("crippled", 0, 2, StationStatus::Open),
+            // Empty but every dock free: simply popular
+            ("empty", 0, 30, StationStatus::Open),
+            ("closed", 0, 0, StationStatus::Closed),
+        ];
+
+        detector.observe(&snapshot(&readings, start), start);
+        assert!(!detector.is_suspected("dead"));
+        let later = start + Duration::minutes(SUSPECT_AFTER_MINUTES);
+        detector.observe(&snapshot(&readings, later), later);
+        assert!(detector.is_suspected("dead"));
+        assert!(detector.is_suspected("crippled"));
+        assert!(!detector.is_suspected("empty"));
+        assert!(!detector.is_suspected("closed"));
+
+        // One live reading clears the suspicion and restarts the count
+        let revived = later + Duration::minutes(5);
+        detector.observe(
+            &snapshot(&[("dead", 3, 20, StationStatus::Open)], revived),
+            revived,
+        );
+        assert!(!detector.is_suspected("dead"));
+        assert!(!detector.is_suspected("crippled"));
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Default interval between upstream health probes
+pub const DEFAULT_PROBE_INTERVAL_SECONDS: u64 = 60;
+
+/// Result of probing one upstream dataset with a single-record request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeOutcome {
+    pub healthy: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ProbeOutcome {
+    #[must_use]
+    pub fn from_result<T>(result: &crate::Result<T>, latency_ms: u64) -> Self {
+        Self {
+            healthy: result.is_ok(),
+            latency_ms,
+            error: result.as_ref().err().map(ToString::to_string),
+        }
+    }
+}
+
+/// Last scheduled probe of the upstream datasets, reported by `velib://health`
+///
+/// Health reads only look at this cached result, so polling the health
+/// resource never adds load on the upstream API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpstreamProbe {
+    pub checked_at: DateTime<Utc>,
+    pub reference: ProbeOutcome,
+    pub realtime: ProbeOutcome,
+}
+
+impl UpstreamProbe {
+    #[must_use]
+    pub fn healthy(&self) -> bool {
+        self.reference.healthy && self.realtime.healthy
+    }
+}
+
+/// `healthy`/`degraded` for a probed dataset, `unknown` before the first probe
+#[must_use]
+pub fn probe_status(outcome: Option<&ProbeOutcome>) -> &'static str {
+    match outcome {
+        Some(outcome) if outcome.healthy => "healthy",
+        Some(_) => "degraded",
+        None => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_outcome_keeps_the_error_message() {
+        let failed: crate::Result<()> = Err(Error::UpstreamUnavailable {
+            retry_after_seconds: 10,
+        });
+        let outcome = ProbeOutcome::from_result(&failed, 3);
+        assert!(!outcome.healthy);
+        assert!(outcome.error.unwrap().contains("retry after 10s"));
+        assert_eq!(probe_status(None), "unknown");
+    }
+
+    #[test]
+    fn test_probe_is_healthy_only_if_both_datasets_are() {
+        let ok = ProbeOutcome::from_result(&Ok::<(), Error>(()), 12);
+        let mut probe = UpstreamProbe {
+            checked_at: Utc::now(),
+            reference: ok.clone(),
+            realtime: ok,
+        };
+        assert!(probe.healthy());
+        assert_eq!(probe_status(Some(&probe.realtime)), "healthy");
+
+        probe.realtime.healthy = false;
+        assert!(!probe.healthy());
+        assert_eq!(probe_status(Some(&probe.realtime)), "degraded");
+    }
+}
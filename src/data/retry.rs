@@ -1,4 +1,6 @@
 use crate::{Error, Result};
+use reqwest::{header::HeaderMap, StatusCode};
+use std::sync::Mutex;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
@@ -103,6 +105,12 @@ impl RetryStrategy {
     /// Calculate delay for a given attempt number (0-based)
     #[must_use]
     pub fn calculate_delay(&self, attempt: u32) -> Duration {
+        self.calculate_delay_with(attempt, &mut fastrand::Rng::new())
+    }
+
+    /// Calculate delay for a given attempt number, drawing jitter from `rng`
+    #[must_use]
+    pub fn calculate_delay_with(&self, attempt: u32, rng: &mut fastrand::Rng) -> Duration {
         match self {
             RetryStrategy::ExponentialBackoff {
                 base_delay,
@@ -114,7 +122,7 @@ impl RetryStrategy {
 
                 if *use_jitter {
                     // Add jitter up to 25% of delay
-                    let jitter = (delay as f64 * 0.25 * fastrand::f64()).round() as u64;
+                    let jitter = (delay as f64 * 0.25 * rng.f64()).round() as u64;
                     Duration::from_secs(delay + jitter)
                 } else {
                     Duration::from_secs(delay)
@@ -130,6 +138,7 @@ impl RetryStrategy {
 pub struct RetryPolicy {
     config: RetryConfig,
     strategy: RetryStrategy,
+    rng: Mutex<fastrand::Rng>,
 }
 
 impl RetryPolicy {
@@ -148,7 +157,26 @@ impl RetryPolicy {
             use_jitter: config.use_jitter,
         };
 
-        Self { config, strategy }
+        Self {
+            config,
+            strategy,
+            rng: Mutex::new(fastrand::Rng::new()),
+        }
+    }
+
+    /// Draw jitter from a generator seeded with `seed`, so retry delays repeat
+    /// from one run to the next
+    #[must_use]
+    pub fn with_jitter_seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(fastrand::Rng::with_seed(seed)),
+            ..self
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        self.strategy.calculate_delay_with(attempt, &mut rng)
     }
 
     /// Execute a closure with retry logic
@@ -192,7 +220,7 @@ impl RetryPolicy {
                         }
                     }
 
-                    let delay = self.strategy.calculate_delay(attempt);
+                    let delay = self.delay_for(attempt);
                     warn!(
                         "Attempt {} failed, retrying in {:.2}s: {}",
                         attempt + 1,
@@ -280,6 +308,15 @@ impl RetryableHttpClient {
         }
     }
 
+    /// Seed the retry jitter, see [`RetryPolicy::with_jitter_seed`]
+    #[must_use]
+    pub fn with_jitter_seed(self, seed: u64) -> Self {
+        Self {
+            retry_policy: self.retry_policy.with_jitter_seed(seed),
+            ..self
+        }
+    }
+
     /// Make a GET request with retry logic
     pub async fn get(&self, url: &str) -> Result<reqwest::Response> {
         debug!("Making GET request to: {}", url);
@@ -316,6 +353,22 @@ impl RetryableHttpClient {
 
     /// Make a GET request with query parameters and retry logic
     pub async fn get_with_query<T>(&self, url: &str, query: &T) -> Result<reqwest::Response>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        self.get_conditional(url, query, &HeaderMap::new()).await
+    }
+
+    /// Make a GET request with query parameters, extra `headers` and retry logic
+    ///
+    /// Meant for conditional requests: a `304 Not Modified` is returned as a
+    /// response rather than an error.
+    pub async fn get_conditional<T>(
+        &self,
+        url: &str,
+        query: &T,
+        headers: &HeaderMap,
+    ) -> Result<reqwest::Response>
     where
         T: serde::Serialize + ?Sized,
     {
@@ -323,7 +376,13 @@ impl RetryableHttpClient {
 
         self.retry_policy
             .execute(|| async {
-                let response = self.client.get(url).query(query).send().await?;
+                let response = self
+                    .client
+                    .get(url)
+                    .query(query)
+                    .headers(headers.clone())
+                    .send()
+                    .await?;
 
                 debug!("Received response: {} {}", response.status(), url);
 
@@ -341,7 +400,8 @@ impl RetryableHttpClient {
                 }
 
                 // Check for other HTTP errors
-                if !response.status().is_success() {
+                if !response.status().is_success() && response.status() != StatusCode::NOT_MODIFIED
+                {
                     warn!("HTTP error {} for {}", response.status(), url);
                     return Err(Error::Http(response.error_for_status().unwrap_err()));
                 }
@@ -425,6 +485,24 @@ mod tests {
         assert!(delay2 <= Duration::from_secs(5));
     }
 
+    #[test]
+    fn test_seeded_jitter_repeats() {
+        let config = RetryConfig {
+            max_attempts: 3,
+            base_delay_seconds: 8,
+            max_delay_seconds: 60,
+            use_jitter: true,
+        };
+        let delays = |policy: RetryPolicy| -> Vec<Duration> {
+            (0..3).map(|attempt| policy.delay_for(attempt)).collect()
+        };
+
+        let first = delays(RetryPolicy::with_config(config.clone()).with_jitter_seed(42));
+        let second = delays(RetryPolicy::with_config(config).with_jitter_seed(42));
+        assert_eq!(first, second);
+        assert!(first[0] >= Duration::from_secs(8) && first[0] <= Duration::from_secs(10));
+    }
+
     #[test]
     fn test_is_retryable_error() {
         // Test rate limited error
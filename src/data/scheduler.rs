@@ -0,0 +1,158 @@
+use crate::{Error, Result};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Default ceiling on Paris Open Data requests per minute
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+
+/// Longest an interactive fetch waits for a request slot before failing
+const MAX_INTERACTIVE_WAIT: Duration = Duration::from_secs(10);
+
+/// Share of the budget kept for interactive calls when background work asks
+const BACKGROUND_RESERVE: f64 = 0.25;
+
+/// Who is asking for an upstream request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchPriority {
+    /// A client is waiting on the answer: queue for a slot
+    Interactive,
+    /// Polling and health probes: shed rather than eat into the reserve
+    Background,
+}
+
+/// Token bucket keeping every upstream request under one per-minute ceiling
+///
+/// The bucket holds up to a minute's worth of requests and refills
+/// continuously. Interactive fetches wait for a token (up to 10 seconds);
+/// background fetches only run while a quarter of the bucket is left for
+/// interactive traffic. Retries of one request share its token.
+#[derive(Debug, Clone)]
+pub struct UpstreamScheduler {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Default for UpstreamScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUESTS_PER_MINUTE)
+    }
+}
+
+impl UpstreamScheduler {
+    #[must_use]
+    pub fn new(requests_per_minute: u32) -> Self {
+        let capacity = f64::from(requests_per_minute.max(1));
+        Self {
+            capacity,
+            per_second: capacity / 60.0,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a request slot, waiting for one if `priority` allows it
+    pub async fn acquire(&mut self, priority: FetchPriority) -> Result<()> {
+        self.refill();
+        let needed = match priority {
+            FetchPriority::Interactive => 1.0,
+            FetchPriority::Background => 1.0 + self.capacity * BACKGROUND_RESERVE,
+        };
+
+        if self.tokens < needed {
+            let wait = Duration::from_secs_f64((needed - self.tokens) / self.per_second);
+            if priority == FetchPriority::Background || wait > MAX_INTERACTIVE_WAIT {
+                return Err(Error::UpstreamBudgetExhausted {
+                    retry_after_seconds: wait.as_secs().max(1),
+                });
+            }
+            tokio::time::sleep(wait).await;
+            self.refill();
+        }
+
+        self.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Change the ceiling, keeping the slots already used
+    pub fn set_rate(&mut self, requests_per_minute: u32) {
+        self.refill();
+        let used = self.capacity - self.tokens;
+        self.capacity = f64::from(requests_per_minute.max(1));
+        self.per_second = self.capacity / 60.0;
+        self.tokens = (self.capacity - used).max(0.0);
+    }
+
+    /// Request slots available right now
+    #[must_use]
+    pub fn available(&self) -> u32 {
+        self.tokens.floor() as u32
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_background_work_is_shed_before_the_reserve() {
+        let mut scheduler = UpstreamScheduler::new(8);
+
+        // 8 slots with 2 reserved: background work gets 6 of them
+        for _ in 0..6 {
+            scheduler.acquire(FetchPriority::Background).await.unwrap();
+        }
+        assert!(matches!(
+            scheduler.acquire(FetchPriority::Background).await,
+            Err(Error::UpstreamBudgetExhausted { .. })
+        ));
+
+        scheduler.acquire(FetchPriority::Interactive).await.unwrap();
+        scheduler.acquire(FetchPriority::Interactive).await.unwrap();
+        assert_eq!(scheduler.available(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_changes_keep_used_slots() {
+        let mut scheduler = UpstreamScheduler::new(10);
+        for _ in 0..4 {
+            scheduler.acquire(FetchPriority::Interactive).await.unwrap();
+        }
+        scheduler.set_rate(20);
+        assert_eq!(scheduler.available(), 16);
+        scheduler.set_rate(2);
+        assert_eq!(scheduler.available(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interactive_work_queues_for_a_slot() {
+        let mut scheduler = UpstreamScheduler::new(60);
+        for _ in 0..60 {
+            scheduler.acquire(FetchPriority::Interactive).await.unwrap();
+        }
+
+        // One token per second: the next call waits about a second
+        let started = Instant::now();
+        scheduler.acquire(FetchPriority::Interactive).await.unwrap();
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(990) && waited <= Duration::from_secs(2));
+
+        // Too slow a refill to wait for
+        let mut slow = UpstreamScheduler::new(1);
+        slow.acquire(FetchPriority::Interactive).await.unwrap();
+        assert!(matches!(
+            slow.acquire(FetchPriority::Interactive).await,
+            Err(Error::UpstreamBudgetExhausted {
+                retry_after_seconds: 60
+            })
+        ));
+    }
+}
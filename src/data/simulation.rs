@@ -0,0 +1,270 @@
+use crate::types::{
+    BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+    StationStatus,
+};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Stations in a simulated city unless configured otherwise
+pub const DEFAULT_SIMULATED_STATIONS: usize = 400;
+
+// Availability is redrawn every this many minutes, so repeated reads agree
+const SLOT_MINUTES: i64 = 5;
+// Grid spacing, about 330 m either way at Paris' latitude
+const LATITUDE_STEP: f64 = 0.003;
+const LONGITUDE_STEP: f64 = 0.0045;
+const CENTER: Coordinates = Coordinates {
+    latitude: 48.8566,
+    longitude: 2.3522,
+};
+
+/// What draws riders to a station, which sets its daily demand curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Full overnight, emptied by the morning commute
+    Residential,
+    /// Empty overnight, filled by the morning commute
+    Business,
+    /// Busiest in the afternoon and at weekends
+    Leisure,
+}
+
+impl Neighborhood {
+    fn street(self) -> &'static str {
+        match self {
+            Self::Residential => "Rue des Lilas",
+            Self::Business => "Avenue du Commerce",
+            Self::Leisure => "Allée du Parc",
+        }
+    }
+
+    /// Share of docks holding a bike at `hour` (UTC, fractional)
+    fn fill(self, hour: f64, weekend: bool) -> f64 {
+        // 1 between the commutes, 0 overnight
+        let workday = smoothstep(7.0, 9.0, hour) - smoothstep(17.0, 19.0, hour);
+        let commute = if weekend { 0.2 } else { 1.0 };
+        match self {
+            Self::Residential => 0.8 - 0.6 * workday * commute,
+            Self::Business => 0.15 + 0.7 * workday * commute,
+            Self::Leisure => {
+                let afternoon = smoothstep(12.0, 14.0, hour) - smoothstep(18.0, 21.0, hour);
+                let crowd = if weekend { 0.45 } else { 0.25 };
+                0.6 - crowd * afternoon
+            }
+        }
+    }
+}
+
+fn smoothstep(from: f64, to: f64, x: f64) -> f64 {
+    let t = ((x - from) / (to - from)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[derive(Debug, Clone)]
+struct SimulatedStation {
+    reference: StationReference,
+    neighborhood: Neighborhood,
+}
+
+/// A synthetic city of stations on a grid around central Paris, whose
+/// availability follows daily demand curves
+///
+/// The same seed and time always give the same availability, so demos and
+/// tests can be replayed; see [`crate::VelibDataClient::with_simulation`].
+#[derive(Debug, Clone)]
+pub struct SimulatedCity {
+    seed: u64,
+    stations: Vec<SimulatedStation>,
+}
+
+impl SimulatedCity {
+    #[must_use]
+    pub fn new(stations: usize, seed: u64) -> Self {
+        let side = (stations as f64).sqrt().ceil().max(1.0) as usize;
+        let middle = (side as f64 - 1.0) / 2.0;
+        let stations = (0..stations)
+            .map(|i| {
+                let (row, column) = (i / side, i % side);
+                let (dy, dx) = (row as f64 - middle, column as f64 - middle);
+                // Offices in the middle, homes around them, a park every few blocks
+                let from_middle = dy.hypot(dx) / middle.max(1.0);
+                let neighborhood = if (row * 7 + column * 3) % 11 == 0 {
+                    Neighborhood::Leisure
+                } else if from_middle < 0.35 {
+                    Neighborhood::Business
+                } else {
+                    Neighborhood::Residential
+                };
+                let mut rng = fastrand::Rng::with_seed(seed ^ i as u64);
+                let reference = StationReference {
+                    station_code: format!("{}", 90000 + i).into(),
+                    name: format!("{} {}", neighborhood.street(), i + 1).into(),
+                    coordinates: Coordinates::new(
+                        CENTER.latitude + dy * LATITUDE_STEP,
+                        CENTER.longitude + dx * LONGITUDE_STEP,
+                    ),
+                    capacity: rng.u16(15..=45),
+                    capabilities: ServiceCapabilities::default(),
+                    district: None,
+                    commune: None,
+                    insee_code: None,
+                };
+                SimulatedStation {
+                    reference,
+                    neighborhood,
+                }
+            })
+            .collect();
+        Self { seed, stations }
+    }
+
+    #[must_use]
+    pub fn stations(&self) -> Vec<StationReference> {
+        self.stations
+            .iter()
+            .map(|station| station.reference.clone())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn neighborhood(&self, station_code: &str) -> Option<Neighborhood> {
+        self.stations
+            .iter()
+            .find(|station| &*station.reference.station_code == station_code)
+            .map(|station| station.neighborhood)
+    }
+
+    /// Every station's availability at `at`
+    #[must_use]
+    pub fn availability_at(&self, at: DateTime<Utc>) -> HashMap<String, RealTimeStatus> {
+        let slot = at.timestamp().div_euclid(SLOT_MINUTES * 60);
+        let drawn_at = DateTime::from_timestamp(slot * SLOT_MINUTES * 60, 0).unwrap_or(at);
+        let hour = f64::from(drawn_at.hour()) + f64::from(drawn_at.minute()) / 60.0;
+        let weekend = matches!(drawn_at.weekday(), Weekday::Sat | Weekday::Sun);
+
+        self.stations
+            .iter()
+            .map(|station| {
+                let code = &station.reference.station_code;
+                let mut rng = fastrand::Rng::with_seed(self.slot_seed(code, slot));
+                let capacity = station.reference.capacity;
+                let fill = (station.neighborhood.fill(hour, weekend) + (rng.f64() - 0.5) * 0.2)
+                    .clamp(0.0, 1.0);
+                let bikes = (fill * f64::from(capacity)).round() as u16;
+                let electric = (f64::from(bikes) * (0.25 + rng.f64() * 0.3)).round() as u16;
+                // About one station in a hundred is out of service at any time
+                let closed = rng.u8(..100) == 0;
+                let (status, bikes, electric) = if closed {
+                    (StationStatus::Closed, 0, 0)
+                } else {
+                    (StationStatus::Open, bikes, electric)
+                };
+                let last_update = drawn_at - Duration::seconds(rng.i64(0..180));
+                let status = RealTimeStatus::new_at(
+                    BikeAvailability::new(bikes - electric, electric),
+                    capacity - bikes,
+                    status,
+                    last_update,
+                    at,
+                )
+                .with_service(!closed, !closed);
+                (code.to_string(), status)
+            })
+            .collect()
+    }
+
+    fn slot_seed(&self, station_code: &str, slot: i64) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (self.seed, station_code, slot).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VelibDataClient;
+    use std::sync::Arc;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn mean_fill(city: &SimulatedCity, neighborhood: Neighborhood, when: DateTime<Utc>) -> f64 {
+        let availability = city.availability_at(when);
+        let fills: Vec<f64> = city
+            .stations
+            .iter()
+            .filter(|station| station.neighborhood == neighborhood)
+            .map(|station| {
+                let status = &availability[&*station.reference.station_code];
+                f64::from(status.bikes.total()) / f64::from(station.reference.capacity)
+            })
+            .collect();
+        fills.iter().sum::<f64>() / fills.len() as f64
+    }
+
+    #[test]
+    fn test_commuters_move_bikes_downtown_and_back() {
+        let city = SimulatedCity::new(DEFAULT_SIMULATED_STATIONS, 7);
+        assert!(city.stations().iter().all(|s| s.validate().is_ok()));
+
+        // A Tuesday: homes full at night, offices full by late morning
+        let night = at("2025-06-17T03:00:00Z");
+        let morning = at("2025-06-17T11:00:00Z");
+        assert!(
+            mean_fill(&city, Neighborhood::Residential, night)
+                > mean_fill(&city, Neighborhood::Business, night) + 0.4
+        );
+        assert!(
+            mean_fill(&city, Neighborhood::Business, morning)
+                > mean_fill(&city, Neighborhood::Residential, morning) + 0.4
+        );
+        // On Saturday the commute barely happens
+        let saturday = at("2025-06-21T11:00:00Z");
+        assert!(
+            mean_fill(&city, Neighborhood::Residential, saturday)
+                > mean_fill(&city, Neighborhood::Business, saturday)
+        );
+    }
+
+    #[test]
+    fn test_same_seed_and_time_give_the_same_city() {
+        let when = at("2025-06-17T08:00:00Z");
+        let a = SimulatedCity::new(50, 1);
+        let b = SimulatedCity::new(50, 1);
+        let availability =
+            |city: &SimulatedCity, when| serde_json::to_value(city.availability_at(when)).unwrap();
+        assert_eq!(
+            serde_json::to_value(a.stations()).unwrap(),
+            serde_json::to_value(b.stations()).unwrap()
+        );
+        assert_eq!(availability(&a, when), availability(&b, when));
+        // Within a slot the city holds still, across slots it moves
+        assert_eq!(
+            availability(&a, when),
+            availability(&a, when + Duration::minutes(1))
+        );
+        assert_ne!(
+            availability(&a, when),
+            availability(&a, when + Duration::minutes(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_serves_the_simulation_without_network() {
+        let clock = Arc::new(crate::clock::ManualClock::new(at("2025-06-17T11:00:00Z")));
+        let mut client = VelibDataClient::new()
+            .with_clock(clock)
+            .with_simulation(SimulatedCity::new(30, 3));
+
+        let stations = client.fetch_reference_stations().await.unwrap();
+        assert_eq!(stations.len(), 30);
+        let realtime = client.fetch_realtime_status().await.unwrap();
+        assert_eq!(realtime.len(), 30);
+        assert!(client.probe_upstream().await.healthy());
+    }
+}
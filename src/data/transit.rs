@@ -0,0 +1,251 @@
+use crate::data::aliases::normalize;
+use crate::types::Coordinates;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const BUNDLED_TRANSIT: &str = include_str!("transit.toml");
+
+/// How far from a transit stop a station may be, unless a search sets its own distance
+pub const DEFAULT_TRANSIT_RADIUS_METERS: u32 = 300;
+
+/// A metro or RER stop and the lines serving it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitStop {
+    pub name: String,
+    /// Metro lines by number ("1", "14"), RER lines by letter ("A")
+    pub lines: Vec<String>,
+    pub coordinates: Coordinates,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransitFile {
+    #[serde(default)]
+    stop: Vec<TransitStop>,
+}
+
+/// The stops a `near_transit` filter designates
+#[derive(Debug, Clone)]
+pub struct TransitSelection<'a> {
+    /// "Ligne 1", "RER A", or the stop's name
+    pub label: String,
+    pub stops: Vec<&'a TransitStop>,
+}
+
+impl<'a> TransitSelection<'a> {
+    /// The closest selected stop within `radius_meters` of `coordinates`, and its distance
+    #[must_use]
+    pub fn nearest(
+        &self,
+        coordinates: &Coordinates,
+        radius_meters: u32,
+    ) -> Option<(&'a TransitStop, f64)> {
+        self.stops
+            .iter()
+            .map(|stop| (*stop, stop.coordinates.distance_to(coordinates)))
+            .filter(|(_, distance)| *distance <= f64::from(radius_meters))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Metro and RER stops, to find stations convenient to a line or a stop
+#[derive(Debug, Clone, Default)]
+pub struct TransitNetwork {
+    stops: Vec<TransitStop>,
+}
+
+impl TransitNetwork {
+    /// The stops shipped with the server
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_TRANSIT).expect("bundled transit stops must be valid")
+    }
+
+    /// Parse transit stops from TOML
+    pub fn parse(source: &str) -> Result<Self> {
+        let file: TransitFile = toml::from_str(source)
+            .map_err(|e| Error::Config(format!("Invalid transit stops: {e}")))?;
+
+        for stop in &file.stop {
+            if stop.lines.is_empty() {
+                return Err(Error::Config(format!(
+                    "Transit stop '{}' needs at least one line",
+                    stop.name
+                )));
+            }
+        }
+
+        Ok(Self { stops: file.stop })
+    }
+
+    /// Load the bundled stops extended with the entries of a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read transit stops {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+
+        let mut network = Self::bundled();
+        network.extend(Self::parse(&source)?);
+        Ok(network)
+    }
+
+    /// Merge other stops into these, replacing stops with the same name
+    pub fn extend(&mut self, other: TransitNetwork) {
+        for stop in other.stops {
+            let key = normalize(&stop.name);
+            self.stops
+                .retain(|existing| normalize(&existing.name) != key);
+            self.stops.push(stop);
+        }
+    }
+
+    /// The stops of a line ("Ligne 1", "M14", "RER A", "a") or the stop named `query`
+    pub fn select(&self, query: &str) -> Result<TransitSelection<'_>> {
+        let normalized = normalize(query);
+        if let Some(line) = line_id(&normalized) {
+            let stops: Vec<&TransitStop> = self
+                .stops
+                .iter()
+                .filter(|stop| stop.lines.iter().any(|served| normalize(served) == line))
+                .collect();
+            if !stops.is_empty() {
+                let label = if line.chars().all(|c| c.is_ascii_digit()) {
+                    format!("Ligne {line}")
+                } else {
+                    format!("RER {}", line.to_uppercase())
+                };
+                return Ok(TransitSelection { label, stops });
+            }
+        }
+
+        if let Some(stop) = self
+            .stops
+            .iter()
+            .find(|stop| normalize(&stop.name) == normalized)
+        {
+            return Ok(TransitSelection {
+                label: stop.name.clone(),
+                stops: vec![stop],
+            });
+        }
+
+        Err(Error::Validation(format!(
+            "Unknown transit line or stop '{query}'; known lines: {}",
+            self.lines().join(", ")
+        )))
+    }
+
+    /// Every line served by at least one stop, metro lines first
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .stops
+            .iter()
+            .flat_map(|stop| stop.lines.iter().cloned())
+            .collect();
+        lines.sort_by(|a, b| {
+            let number = |line: &str| line.parse::<u32>().ok();
+            match (number(a), number(b)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.cmp(b),
+            }
+        });
+        lines.dedup();
+        lines
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stops.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+    }
+}
+
+/// The line a normalized query names, without its "ligne", "metro" or "rer"
+/// prefix: "ligne 1" and "m1" give "1", "rer a" gives "a"
+fn line_id(normalized: &str) -> Option<String> {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    match words.as_slice() {
+        ["ligne" | "line" | "metro" | "rer" | "m", line] => Some((*line).to_string()),
+        [line] => match line.strip_prefix('m') {
+            Some(number) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => {
+                Some(number.to_string())
+            }
+            _ => Some((*line).to_string()),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_and_stops_resolve() {
+        let network = TransitNetwork::bundled();
+        assert!(network.len() > 50);
+
+        for query in ["Ligne 1", "ligne 1", "M1", "Métro 1", "1"] {
+            let selection = network.select(query).unwrap();
+            assert_eq!(selection.label, "Ligne 1", "{query}");
+            assert_eq!(selection.stops.len(), 25, "{query}");
+        }
+        let rer = network.select("RER A").unwrap();
+        assert_eq!(rer.label, "RER A");
+        assert!(rer.stops.iter().any(|stop| stop.name == "Auber"));
+
+        let stop = network.select("chatelet les halles").unwrap();
+        assert_eq!(stop.label, "Châtelet - Les Halles");
+        assert_eq!(stop.stops.len(), 1);
+
+        // Hôtel de Ville station, across the square from the metro stop
+        let (stop, distance) = network
+            .select("Ligne 1")
+            .unwrap()
+            .nearest(&Coordinates::new(48.8566, 2.3522), 300)
+            .unwrap();
+        assert_eq!(stop.name, "Hôtel de Ville");
+        assert!(distance < 100.0);
+
+        assert!(matches!(
+            network.select("Ligne 42"),
+            Err(Error::Validation(message)) if message.contains("1, 4, 14, A, B, C, D, E")
+        ));
+    }
+
+    #[test]
+    fn test_custom_stops_extend_bundled_ones() {
+        let mut network = TransitNetwork::bundled();
+        let count = network.len();
+        network.extend(
+            TransitNetwork::parse(
+                r#"
+                [[stop]]
+                name = "Place d'Italie"
+                lines = ["5", "6", "7"]
+                coordinates = { latitude = 48.8310, longitude = 2.3556 }
+
+                [[stop]]
+                name = "bastille"
+                lines = ["1", "5", "8"]
+                coordinates = { latitude = 48.8532, longitude = 2.3691 }
+                "#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(network.len(), count + 1);
+        assert_eq!(network.select("ligne 5").unwrap().stops.len(), 2);
+
+        assert!(TransitNetwork::parse("[[stop]]\nname = \"Nowhere\"\nlines = []\ncoordinates = { latitude = 48.0, longitude = 2.0 }").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::data::aliases::normalize;
+use crate::types::StationReference;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A station name/code pair returned by autocompletion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationNameSuggestion {
+    pub station_code: Arc<str>,
+    pub name: Arc<str>,
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: BTreeMap<char, TrieNode>,
+    // Indices into `NameTrie::entries` of names containing this path
+    stations: Vec<usize>,
+}
+
+/// Prefix index over normalized station names
+///
+/// Every word of a name is indexed, so "lyon" completes "Gare de Lyon - Diderot".
+/// Matches at the start of the name rank ahead of matches on later words.
+#[derive(Debug, Default)]
+pub struct NameTrie {
+    root: TrieNode,
+    entries: Vec<StationNameSuggestion>,
+    normalized_names: Vec<String>,
+}
+
+impl NameTrie {
+    #[must_use]
+    pub fn build(stations: &[StationReference]) -> Self {
+        let mut trie = Self::default();
+
+        for station in stations {
+            let index = trie.entries.len();
+            let normalized = normalize(&station.name);
+
+            let mut word_starts = vec![0];
+            word_starts.extend(normalized.match_indices(' ').map(|(i, _)| i + 1));
+            for start in word_starts {
+                trie.insert(&normalized[start..], index);
+            }
+
+            trie.entries.push(StationNameSuggestion {
+                station_code: station.station_code.clone(),
+                name: station.name.clone(),
+            });
+            trie.normalized_names.push(normalized);
+        }
+
+        trie
+    }
+
+    fn insert(&mut self, key: &str, index: usize) {
+        let mut node = &mut self.root;
+        for c in key.chars() {
+            node = node.children.entry(c).or_default();
+            if node.stations.last() != Some(&index) {
+                node.stations.push(index);
+            }
+        }
+    }
+
+    /// Names matching `prefix`, best matches first
+    #[must_use]
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<StationNameSuggestion> {
+        let normalized = normalize(prefix);
+        if normalized.is_empty() {
+            return Vec::new();
+        }
+
+        let mut node = &self.root;
+        for c in normalized.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return Vec::new(),
+            }
+        }
+
+        let mut matches = node.stations.clone();
+        matches.sort_by(|&a, &b| {
+            let a_leading = self.normalized_names[a].starts_with(&normalized);
+            let b_leading = self.normalized_names[b].starts_with(&normalized);
+            b_leading
+                .cmp(&a_leading)
+                .then_with(|| self.entries[a].name.cmp(&self.entries[b].name))
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|index| self.entries[index].clone())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Coordinates, ServiceCapabilities};
+
+    fn reference(code: &str, name: &str) -> StationReference {
+        StationReference {
+            station_code: code.into(),
+            name: name.into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        }
+    }
+
+    fn codes(suggestions: &[StationNameSuggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| &*s.station_code).collect()
+    }
+
+    #[test]
+    fn test_complete_prefix_and_inner_words() {
+        let trie = NameTrie::build(&[
+            reference("1", "Gare de Lyon - Diderot"),
+            reference("2", "Lyon - Bercy"),
+            reference("3", "Châtelet"),
+        ]);
+
+        // Leading matches rank before inner-word matches
+        assert_eq!(codes(&trie.complete("lyo", 10)), vec!["2", "1"]);
+        assert_eq!(codes(&trie.complete("chatel", 10)), vec!["3"]);
+        assert_eq!(codes(&trie.complete("Gare de L", 10)), vec!["1"]);
+        assert!(trie.complete("xyz", 10).is_empty());
+        assert!(trie.complete("   ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_complete_respects_limit_and_deduplicates() {
+        let trie = NameTrie::build(&[
+            reference("1", "Rue de la Roquette - Rue de la Paix"),
+            reference("2", "Rue Saint-Maur"),
+            reference("3", "Rue Oberkampf"),
+        ]);
+
+        // "rue" appears twice in the first name but yields one suggestion
+        assert_eq!(trie.complete("rue", 10).len(), 3);
+        assert_eq!(trie.complete("rue", 2).len(), 2);
+    }
+}
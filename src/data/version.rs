@@ -0,0 +1,121 @@
+use crate::data::changes::state_hash;
+use crate::events::StationUpdate;
+use crate::types::StationReference;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Which data an answer was computed from
+///
+/// Two answers with the same version saw the same reference stations and
+/// availability. The version restarts at 1 with the process; the hash only
+/// depends on the data, so it also compares answers from other instances of
+/// the same build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataVersion {
+    /// Goes up by one each time an ingested snapshot changes the data
+    pub version: u64,
+    /// 16 hex digits
+    pub hash: String,
+}
+
+/// Numbers the snapshots the data client ingests
+///
+/// A refresh that brings the same stations and availability as the previous
+/// one keeps the version, even when the upstream timestamps moved.
+#[derive(Debug, Clone, Default)]
+pub struct DataVersioner {
+    reference: Option<u64>,
+    realtime: Option<u64>,
+    current: Option<DataVersion>,
+}
+
+impl DataVersioner {
+    /// Note an ingested reference dataset and return the version now served
+    pub fn observe_reference(&mut self, stations: &[StationReference]) -> DataVersion {
+        let mut stations: Vec<&StationReference> = stations.iter().collect();
+        stations.sort_by(|a, b| a.station_code.cmp(&b.station_code));
+        let mut hasher = DefaultHasher::new();
+        for station in stations {
+            station.station_code.hash(&mut hasher);
+            station.name.hash(&mut hasher);
+            station.coordinates.latitude.to_bits().hash(&mut hasher);
+            station.coordinates.longitude.to_bits().hash(&mut hasher);
+            station.capacity.hash(&mut hasher);
+        }
+        self.reference = Some(hasher.finish());
+        self.advance()
+    }
+
+    /// Note an ingested real-time snapshot and return the version now served
+    pub fn observe_realtime(&mut self, state: &HashMap<String, StationUpdate>) -> DataVersion {
+        self.realtime = Some(state_hash(state));
+        self.advance()
+    }
+
+    /// `None` until a snapshot was ingested
+    #[must_use]
+    pub fn current(&self) -> Option<&DataVersion> {
+        self.current.as_ref()
+    }
+
+    fn advance(&mut self) -> DataVersion {
+        let mut hasher = DefaultHasher::new();
+        (self.reference, self.realtime).hash(&mut hasher);
+        let hash = format!("{:016x}", hasher.finish());
+        let version = match &self.current {
+            Some(current) if current.hash == hash => return current.clone(),
+            Some(current) => current.version + 1,
+            None => 1,
+        };
+        let current = DataVersion { version, hash };
+        self.current = Some(current.clone());
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, RealTimeStatus, StationStatus};
+    use chrono::{Duration, Utc};
+
+    fn state(mechanical: u16, published: chrono::DateTime<Utc>) -> HashMap<String, StationUpdate> {
+        let status = RealTimeStatus::new(
+            BikeAvailability::new(mechanical, 2),
+            10,
+            StationStatus::Open,
+            published,
+        );
+        HashMap::from([("16107".to_string(), StationUpdate::new("16107", &status))])
+    }
+
+    #[test]
+    fn test_version_moves_only_when_the_data_does() {
+        let now = Utc::now();
+        let mut versions = DataVersioner::default();
+        assert!(versions.current().is_none());
+
+        let first = versions.observe_realtime(&state(4, now));
+        assert_eq!(first.version, 1);
+        assert_eq!(first.hash.len(), 16);
+        // Same availability, newer upstream timestamp
+        assert_eq!(
+            versions.observe_realtime(&state(4, now + Duration::minutes(1))),
+            first
+        );
+
+        let second = versions.observe_realtime(&state(5, now));
+        assert_eq!(second.version, 2);
+        assert_ne!(second.hash, first.hash);
+        // Back to earlier data: same hash, but a later version
+        let third = versions.observe_realtime(&state(4, now));
+        assert_eq!((third.version, &third.hash), (3, &first.hash));
+
+        let mut other = DataVersioner::default();
+        assert_eq!(other.observe_realtime(&state(4, now)).hash, first.hash);
+        assert_ne!(other.observe_reference(&[]).hash, first.hash);
+        assert_eq!(versions.current(), Some(&third));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::types::RealTimeStatus;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Minutes without a newer `duedate` before the realtime feed counts as frozen
+pub const DEFAULT_FROZEN_FEED_MINUTES: i64 = 15;
+
+/// Health of the upstream realtime feed as seen across refreshes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedStatus {
+    pub frozen: bool,
+    /// Most recent station `duedate` seen so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_update: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frozen_for_minutes: Option<i64>,
+}
+
+/// Detects an upstream that keeps answering but stopped publishing new data
+///
+/// Each refresh reports the newest station `duedate`. When it has not moved for
+/// `stale_after`, the feed is frozen and an alarm is raised; further alarms are
+/// raised at 2x, 4x, 8x... that delay so logs stay quiet during long outages.
+#[derive(Debug, Clone)]
+pub struct FeedWatchdog {
+    stale_after: Duration,
+    newest_update: Option<DateTime<Utc>>,
+    advanced_at: Option<DateTime<Utc>>,
+    refreshes_since_advance: u32,
+    alarm_level: u32,
+}
+
+impl Default for FeedWatchdog {
+    fn default() -> Self {
+        Self::new(Duration::minutes(DEFAULT_FROZEN_FEED_MINUTES))
+    }
+}
+
+impl FeedWatchdog {
+    #[must_use]
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            stale_after,
+            newest_update: None,
+            advanced_at: None,
+            refreshes_since_advance: 0,
+            alarm_level: 0,
+        }
+    }
+
+    /// Record a refreshed snapshot; returns the new alarm level when it escalates
+    pub fn observe(
+        &mut self,
+        snapshot: &HashMap<String, RealTimeStatus>,
+        now: DateTime<Utc>,
+    ) -> Option<u32> {
+        let newest = snapshot
+            .values()
+            .filter_map(|status| status.last_update)
+            .max();
+
+        if newest.is_some() && newest > self.newest_update {
+            self.newest_update = newest;
+            self.advanced_at = Some(now);
+            self.refreshes_since_advance = 0;
+            self.alarm_level = 0;
+            return None;
+        }
+
+        self.refreshes_since_advance += 1;
+        let level = self.alarm_level_at(now);
+        if level > self.alarm_level {
+            self.alarm_level = level;
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn is_frozen(&self, now: DateTime<Utc>) -> bool {
+        self.frozen_for(now).is_some()
+    }
+
+    #[must_use]
+    pub fn status(&self, now: DateTime<Utc>) -> FeedStatus {
+        let frozen_for = self.frozen_for(now);
+        FeedStatus {
+            frozen: frozen_for.is_some(),
+            newest_update: self.newest_update,
+            frozen_for_minutes: frozen_for.map(|d| d.num_minutes()),
+        }
+    }
+
+    fn frozen_for(&self, now: DateTime<Utc>) -> Option<Duration> {
+        if self.refreshes_since_advance == 0 {
+            return None;
+        }
+        let unchanged_for = now - self.advanced_at?;
+        (unchanged_for >= self.stale_after).then_some(unchanged_for)
+    }
+
+    // Level 1 at `stale_after`, level 2 at twice that, level 3 at four times...
+    fn alarm_level_at(&self, now: DateTime<Utc>) -> u32 {
+        let Some(frozen_for) = self.frozen_for(now) else {
+            return 0;
+        };
+        if self.stale_after <= Duration::zero() {
+            return 1;
+        }
+        let ratio = frozen_for.num_seconds() as f64 / self.stale_after.num_seconds().max(1) as f64;
+        1 + ratio.log2().max(0.0).floor() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BikeAvailability, StationStatus};
+
+    fn snapshot(last_update: DateTime<Utc>) -> HashMap<String, RealTimeStatus> {
+        HashMap::from([(
+            "16107".to_string(),
+            RealTimeStatus::new(
+                BikeAvailability::new(1, 1),
+                5,
+                StationStatus::Open,
+                last_update,
+            ),
+        )])
+    }
+
+    #[test]
+    fn test_frozen_feed_escalates_exponentially() {
+        let start = Utc::now();
+        let mut watchdog = FeedWatchdog::new(Duration::minutes(10));
+        let frozen = snapshot(start);
+
+        assert_eq!(watchdog.observe(&frozen, start), None);
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(5)),
+            None
+        );
+        assert!(!watchdog.is_frozen(start + Duration::minutes(5)));
+
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(10)),
+            Some(1)
+        );
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(15)),
+            None
+        );
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(20)),
+            Some(2)
+        );
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(30)),
+            None
+        );
+        assert_eq!(
+            watchdog.observe(&frozen, start + Duration::minutes(40)),
+            Some(3)
+        );
+
+        let status = watchdog.status(start + Duration::minutes(40));
+        assert!(status.frozen);
+        assert_eq!(status.frozen_for_minutes, Some(40));
+        assert_eq!(status.newest_update, Some(start));
+    }
+
+    #[test]
+    fn test_newer_duedate_clears_alarm() {
+        let start = Utc::now();
+        let mut watchdog = FeedWatchdog::new(Duration::minutes(10));
+
+        watchdog.observe(&snapshot(start), start);
+        watchdog.observe(&snapshot(start), start + Duration::minutes(12));
+        assert!(watchdog.is_frozen(start + Duration::minutes(12)));
+
+        let later = start + Duration::minutes(13);
+        watchdog.observe(&snapshot(later), later);
+        assert!(!watchdog.is_frozen(later + Duration::minutes(5)));
+    }
+}
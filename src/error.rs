@@ -1,3 +1,4 @@
+use serde::Serialize;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -13,14 +14,41 @@ pub enum Error {
     })]
     RateLimited { retry_after_seconds: Option<u64> },
 
+    #[error(
+        "Upstream API unavailable after repeated failures: retry after {retry_after_seconds}s"
+    )]
+    UpstreamUnavailable { retry_after_seconds: u64 },
+
+    #[error("Upstream request budget exhausted: retry after {retry_after_seconds}s")]
+    UpstreamBudgetExhausted { retry_after_seconds: u64 },
+
+    #[error("Quota exhausted: {used} of {limit} {period} tool calls used, resets at {resets_at}")]
+    QuotaExceeded {
+        period: &'static str,
+        limit: u32,
+        used: u32,
+        resets_at: chrono::DateTime<chrono::Utc>,
+    },
+
+    #[error("Operation {operation} timed out after {timeout_ms} ms")]
+    Timeout { operation: String, timeout_ms: u64 },
+
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
 
     #[error("Invalid coordinates: latitude {latitude}, longitude {longitude}")]
     InvalidCoordinates { latitude: f64, longitude: f64 },
 
-    #[error("Coordinates outside service area: {distance_km:.1}km from Paris (max: 50km)")]
-    OutsideServiceArea { distance_km: f64 },
+    #[error(
+        "Coordinates outside service area: {distance_km:.1}km from its center (max: {max_km:.1}km)"
+    )]
+    OutsideServiceArea { distance_km: f64, max_km: f64 },
+
+    #[error(
+        "Coordinates outside service area boundary: latitude {latitude}, longitude {longitude}"
+    )]
+    OutsideServiceBoundary { latitude: f64, longitude: f64 },
+
     #[error("Search radius too large: {radius}m (max: {max}m)")]
     SearchRadiusTooLarge { radius: u32, max: u32 },
 
@@ -33,12 +61,27 @@ pub enum Error {
     #[error("MCP protocol error: {0}")]
     McpProtocol(String),
 
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+
+    #[error("Tool disabled on this server: {tool}")]
+    ToolDisabled { tool: String },
+
+    #[error("Resource not found: {uri}")]
+    ResourceNotFound { uri: String },
+
     #[error("Data validation error: {0}")]
     Validation(String),
 
     #[error("Cache error: {0}")]
     Cache(String),
 
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     #[error("Internal error: {0}")]
     Internal(#[from] anyhow::Error),
 }
@@ -50,15 +93,25 @@ impl Error {
         match self {
             Error::Http(_) => -32001,
             Error::RateLimited { .. } => -32001, // Server error (rate limit)
+            Error::UpstreamUnavailable { .. } => -32001, // Server error (breaker open)
+            Error::UpstreamBudgetExhausted { .. } => -32001, // Server error (request budget)
+            Error::QuotaExceeded { .. } => -32001, // Server error (client quota)
+            Error::Timeout { .. } => -32001,     // Server error (time limit)
             Error::Json(_) => -32700,            // Parse error
             Error::InvalidCoordinates { .. } => -32602, // Invalid params
             Error::OutsideServiceArea { .. } => -32602, // Invalid params
+            Error::OutsideServiceBoundary { .. } => -32602, // Invalid params
             Error::SearchRadiusTooLarge { .. } => -32602, // Invalid params
             Error::ResultLimitExceeded { .. } => -32602, // Invalid params
             Error::StationNotFound { .. } => -32600, // Invalid request
             Error::McpProtocol(_) => -32603,     // Internal error
+            Error::MethodNotFound(_) => -32601,  // Method not found
+            Error::ToolDisabled { .. } => -32601, // Method not found
+            Error::ResourceNotFound { .. } => -32002, // MCP resource not found
             Error::Validation(_) => -32602,      // Invalid params
             Error::Cache(_) => -32603,           // Internal error
+            Error::Storage(_) => -32603,         // Internal error
+            Error::Config(_) => -32603,          // Internal error
             Error::Internal(_) => -32603,        // Internal error
         }
     }
@@ -69,16 +122,228 @@ impl Error {
         match self {
             Error::Http(_) => "http_error",
             Error::RateLimited { .. } => "rate_limited",
+            Error::UpstreamUnavailable { .. } => "upstream_unavailable",
+            Error::UpstreamBudgetExhausted { .. } => "upstream_budget_exhausted",
+            Error::QuotaExceeded { .. } => "quota_exceeded",
+            Error::Timeout { .. } => "timeout",
             Error::Json(_) => "json_error",
             Error::InvalidCoordinates { .. } => "invalid_coordinates",
-            Error::OutsideServiceArea { .. } => "outside_service_area",
+            Error::OutsideServiceArea { .. } | Error::OutsideServiceBoundary { .. } => {
+                "outside_service_area"
+            }
             Error::SearchRadiusTooLarge { .. } => "search_radius_too_large",
             Error::ResultLimitExceeded { .. } => "result_limit_exceeded",
             Error::StationNotFound { .. } => "station_not_found",
             Error::McpProtocol(_) => "mcp_protocol_error",
+            Error::MethodNotFound(_) => "method_not_found",
+            Error::ToolDisabled { .. } => "tool_disabled",
+            Error::ResourceNotFound { .. } => "resource_not_found",
             Error::Validation(_) => "validation_error",
             Error::Cache(_) => "cache_error",
+            Error::Storage(_) => "storage_error",
+            Error::Config(_) => "config_error",
             Error::Internal(_) => "internal_error",
         }
     }
+
+    /// What the error means for a caller, as listed in `velib://docs/errors`
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Error::Http(_) => "The upstream Velib API could not be reached",
+            Error::RateLimited { .. } => {
+                "The upstream Velib API is rate limiting this server; retry after the delay in the message"
+            }
+            Error::UpstreamUnavailable { .. } => {
+                "The upstream API failed repeatedly and calls to it are paused; retry after the delay in the message"
+            }
+            Error::UpstreamBudgetExhausted { .. } => {
+                "This server spent its upstream request budget; retry after the delay in the message"
+            }
+            Error::QuotaExceeded { .. } => "The caller used up its tool call quota for the period",
+            Error::Timeout { .. } => "The call ran past its time limit",
+            Error::Json(_) => "Arguments or data could not be read as the expected JSON",
+            Error::InvalidCoordinates { .. } => {
+                "Coordinates are not finite or out of range, or outside the service area's bounding box"
+            }
+            Error::OutsideServiceArea { .. } | Error::OutsideServiceBoundary { .. } => {
+                "Coordinates lie outside the area the service covers"
+            }
+            Error::SearchRadiusTooLarge { .. } => "The search radius is above the maximum",
+            Error::ResultLimitExceeded { .. } => "The result limit is above the maximum",
+            Error::StationNotFound { .. } => "No station has this code",
+            Error::McpProtocol(_) => "The MCP exchange could not be completed",
+            Error::MethodNotFound(_) => "The method or tool does not exist",
+            Error::ToolDisabled { .. } => "The tool exists but this server does not serve it",
+            Error::ResourceNotFound { .. } => "No resource has this URI",
+            Error::Validation(_) => "An argument is missing, malformed or out of range",
+            Error::Cache(_) => "The server's cache failed",
+            Error::Storage(_) => "The server's storage failed",
+            Error::Config(_) => "The server is misconfigured",
+            Error::Internal(_) => "An unexpected server failure",
+        }
+    }
+
+    /// Fields of a JSON-RPC error's `data` besides `error_type`
+    #[must_use]
+    pub fn data_fields(&self) -> &'static [&'static str] {
+        match self {
+            Error::QuotaExceeded { .. } => &["quota"],
+            Error::Timeout { .. } => &["operation", "timeout_ms"],
+            _ => &[],
+        }
+    }
+
+    /// One error of every variant, from which the catalog is built
+    #[must_use]
+    pub fn examples() -> Vec<Error> {
+        let http = reqwest::Client::new()
+            .get("http://[invalid")
+            .build()
+            .expect_err("a malformed URL fails to build");
+        let json = serde_json::from_str::<serde_json::Value>("{")
+            .expect_err("an unclosed object fails to parse");
+        vec![
+            Error::Http(http),
+            Error::RateLimited {
+                retry_after_seconds: Some(30),
+            },
+            Error::UpstreamUnavailable {
+                retry_after_seconds: 30,
+            },
+            Error::UpstreamBudgetExhausted {
+                retry_after_seconds: 60,
+            },
+            Error::QuotaExceeded {
+                period: "daily",
+                limit: 1000,
+                used: 1000,
+                resets_at: chrono::DateTime::UNIX_EPOCH,
+            },
+            Error::Timeout {
+                operation: "plan_bike_journey".to_string(),
+                timeout_ms: 30_000,
+            },
+            Error::Json(json),
+            Error::InvalidCoordinates {
+                latitude: 91.0,
+                longitude: 2.35,
+            },
+            Error::OutsideServiceArea {
+                distance_km: 120.0,
+                max_km: 50.0,
+            },
+            Error::OutsideServiceBoundary {
+                latitude: 48.95,
+                longitude: 2.35,
+            },
+            Error::SearchRadiusTooLarge {
+                radius: 10_000,
+                max: 5000,
+            },
+            Error::ResultLimitExceeded {
+                limit: 500,
+                max: 100,
+            },
+            Error::StationNotFound {
+                station_code: "99999".to_string(),
+            },
+            Error::McpProtocol("unsupported protocol version".to_string()),
+            Error::MethodNotFound("tools/unknown".to_string()),
+            Error::ToolDisabled {
+                tool: "plan_bike_journey".to_string(),
+            },
+            Error::ResourceNotFound {
+                uri: "velib://unknown".to_string(),
+            },
+            Error::Validation("limit must be at least 1".to_string()),
+            Error::Cache("entry expired".to_string()),
+            Error::Storage("disk full".to_string()),
+            Error::Config("VELIB_SERVICE_RADIUS_KM is not a number".to_string()),
+            Error::Internal(anyhow::anyhow!("unexpected")),
+        ]
+    }
+
+    /// Every `error_type` a call can fail with and the code it comes with,
+    /// taken from the same methods that build error responses
+    #[must_use]
+    pub fn catalog() -> Vec<ErrorCatalogEntry> {
+        let mut entries: Vec<ErrorCatalogEntry> = Vec::new();
+        for error in Self::examples() {
+            if entries
+                .iter()
+                .any(|entry| entry.error_type == error.error_type())
+            {
+                continue;
+            }
+            let code = error.mcp_error_code();
+            entries.push(ErrorCatalogEntry {
+                error_type: error.error_type(),
+                code,
+                code_meaning: code_meaning(code),
+                description: error.description(),
+                data_fields: error.data_fields(),
+            });
+        }
+        entries
+    }
+
+    /// [`Self::catalog`] as the Markdown table of the interface specification
+    #[must_use]
+    pub fn catalog_markdown() -> String {
+        let mut table = "| `error_type` | Code | Signification JSON-RPC | Description |\n\
+                         |---|---|---|---|\n"
+            .to_string();
+        for entry in Self::catalog() {
+            table.push_str(&format!(
+                "| `{}` | `{}` | {} | {} |\n",
+                entry.error_type, entry.code, entry.code_meaning, entry.description
+            ));
+        }
+        table
+    }
+}
+
+/// One `error_type` of the catalog served as `velib://docs/errors`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorCatalogEntry {
+    pub error_type: &'static str,
+    pub code: i32,
+    /// What JSON-RPC and MCP reserve `code` for
+    pub code_meaning: &'static str,
+    pub description: &'static str,
+    /// Fields of the error's `data` besides `error_type`
+    pub data_fields: &'static [&'static str],
+}
+
+fn code_meaning(code: i32) -> &'static str {
+    match code {
+        -32700 => "Parse error",
+        -32600 => "Invalid Request",
+        -32601 => "Method not found",
+        -32602 => "Invalid params",
+        -32603 => "Internal error",
+        -32002 => "Resource not found",
+        _ => "Server error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interface_spec_lists_the_catalog() {
+        let catalog = Error::catalog();
+        let error_types: Vec<&str> = catalog.iter().map(|entry| entry.error_type).collect();
+        assert_eq!(error_types.len(), Error::examples().len() - 1);
+        assert!(error_types.contains(&"outside_service_area"));
+
+        let spec = include_str!("../docs/api/mcp_interface_spec.md");
+        assert!(
+            spec.contains(&Error::catalog_markdown()),
+            "the error table of docs/api/mcp_interface_spec.md is out of date:\n{}",
+            Error::catalog_markdown()
+        );
+    }
 }
@@ -0,0 +1,205 @@
+use crate::types::{BikeAvailability, RealTimeStatus, StationStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+
+/// Events a slow subscriber may fall behind by before it starts missing some
+const EVENT_BUFFER: usize = 64;
+
+/// A change pushed to `/events` subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// Stations whose availability changed in a real-time refresh
+    AvailabilityChanged {
+        id: u64,
+        at: DateTime<Utc>,
+        stations: Vec<StationUpdate>,
+    },
+    /// The service announcements in effect, after one was published or withdrawn
+    ServiceAnnouncements {
+        id: u64,
+        at: DateTime<Utc>,
+        announcements: Vec<Announcement>,
+    },
+}
+
+impl ServerEvent {
+    /// Name used as the SSE `event:` field
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AvailabilityChanged { .. } => "availability_changed",
+            Self::ServiceAnnouncements { .. } => "service_announcements",
+        }
+    }
+
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::AvailabilityChanged { id, .. } | Self::ServiceAnnouncements { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationUpdate {
+    pub station_code: String,
+    pub bikes: BikeAvailability,
+    pub available_docks: u16,
+    pub status: StationStatus,
+}
+
+impl StationUpdate {
+    #[must_use]
+    pub fn new(station_code: &str, status: &RealTimeStatus) -> Self {
+        Self {
+            station_code: station_code.to_string(),
+            bikes: status.bikes,
+            available_docks: status.available_docks,
+            status: status.status.clone(),
+        }
+    }
+}
+
+/// What a service announcement is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementKind {
+    /// Planned downtime or reduced service
+    Maintenance,
+    /// Paris Open Data is slow, stale or failing
+    DegradedUpstream,
+    Info,
+}
+
+/// A notice from the operators explaining why data may look odd, e.g. a
+/// maintenance window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: String,
+    pub kind: AnnouncementKind,
+    pub message: String,
+    pub published_at: DateTime<Utc>,
+    /// Withdrawn automatically from then on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Announcement {
+    #[must_use]
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Fan-out of server events to any number of live subscribers
+///
+/// Publishing never blocks: with nobody listening events are dropped, and a
+/// subscriber more than `EVENT_BUFFER` events behind skips the oldest ones.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<ServerEvent>,
+    next_id: AtomicU64,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            sender,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish the stations that differ from `previous`; returns the event published, if any
+    pub fn publish_availability(
+        &self,
+        previous: &HashMap<String, StationUpdate>,
+        current: &HashMap<String, StationUpdate>,
+    ) -> Option<ServerEvent> {
+        let mut stations: Vec<StationUpdate> = current
+            .iter()
+            .filter(|(code, update)| previous.get(*code) != Some(update))
+            .map(|(_, update)| update.clone())
+            .collect();
+        if stations.is_empty() {
+            return None;
+        }
+        stations.sort_by(|a, b| a.station_code.cmp(&b.station_code));
+
+        let event = ServerEvent::AvailabilityChanged {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            at: Utc::now(),
+            stations,
+        };
+        // An error only means there is no subscriber right now
+        let _ = self.sender.send(event.clone());
+        Some(event)
+    }
+
+    /// Publish the announcements now in effect
+    pub fn publish_announcements(&self, announcements: Vec<Announcement>) -> ServerEvent {
+        let event = ServerEvent::ServiceAnnouncements {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            at: Utc::now(),
+            announcements,
+        };
+        let _ = self.sender.send(event.clone());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(code: &str, mechanical: u16) -> (String, StationUpdate) {
+        let status = RealTimeStatus::new(
+            BikeAvailability::new(mechanical, 0),
+            10,
+            StationStatus::Open,
+            Utc::now(),
+        );
+        (code.to_string(), StationUpdate::new(code, &status))
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_stations_are_published() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+
+        let first = HashMap::from([update("1", 3), update("2", 5)]);
+        let second = HashMap::from([update("1", 3), update("2", 4)]);
+        bus.publish_availability(&HashMap::new(), &first);
+        bus.publish_availability(&first, &second);
+        assert!(bus.publish_availability(&second, &second).is_none());
+
+        let Ok(ServerEvent::AvailabilityChanged { id, stations, .. }) = receiver.recv().await
+        else {
+            panic!("expected an availability change");
+        };
+        assert_eq!((id, stations.len()), (1, 2));
+        let Ok(ServerEvent::AvailabilityChanged { id, stations, .. }) = receiver.recv().await
+        else {
+            panic!("expected an availability change");
+        };
+        assert_eq!(id, 2);
+        assert_eq!(stations, vec![update("2", 4).1]);
+        assert!(receiver.try_recv().is_err());
+    }
+}
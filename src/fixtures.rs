@@ -0,0 +1,295 @@
+//! Realistic station datasets for tests, behind the `fixtures` feature
+//!
+//! Each scenario is a `Vec<VelibStation>` with reference and real-time data.
+//! [`split`] turns one into what [`VelibDataClient::seed_cache`] takes, and
+//! [`seeded_client`] does both, so tests exercise edge cases without network
+//! access or copy-pasted JSON.
+
+use crate::data::VelibDataClient;
+use crate::types::{
+    BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+    StationStatus, VelibStation,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// How long before now the real-time data of [`stale_data`] was published
+pub const STALE_DATA_AGE_MINUTES: i64 = 90;
+
+/// One station: `(code, name, latitude, longitude, capacity, commune)`
+type Site<'a> = (&'a str, &'a str, f64, f64, u16, &'a str);
+
+/// Sixteen stations within about 800 m of Châtelet, most 200 m or less from
+/// their nearest neighbour, as dense as the network gets, with mixed
+/// availability: two empty, one full, one with only electric bikes
+#[must_use]
+pub fn central_paris() -> Vec<VelibStation> {
+    const SITES: [Site<'static>; 16] = [
+        ("1013", "Châtelet - Rivoli", 48.8589, 2.3470, 35, "Paris"),
+        ("1014", "Les Halles - Berger", 48.8614, 2.3453, 52, "Paris"),
+        (
+            "1015",
+            "Pont Neuf - Quai du Louvre",
+            48.8593,
+            2.3422,
+            24,
+            "Paris",
+        ),
+        ("1016", "Saint-Eustache", 48.8634, 2.3449, 30, "Paris"),
+        (
+            "1020",
+            "Rambuteau - Sébastopol",
+            48.8621,
+            2.3504,
+            28,
+            "Paris",
+        ),
+        ("4001", "Hôtel de Ville", 48.8566, 2.3522, 40, "Paris"),
+        (
+            "4002",
+            "Saint-Jacques - Victoria",
+            48.8577,
+            2.3488,
+            22,
+            "Paris",
+        ),
+        (
+            "4005",
+            "Beaubourg - Centre Pompidou",
+            48.8607,
+            2.3522,
+            36,
+            "Paris",
+        ),
+        ("4007", "Lobau - Rivoli", 48.8562, 2.3547, 26, "Paris"),
+        ("4010", "Pont d'Arcole", 48.8553, 2.3509, 20, "Paris"),
+        ("4012", "Quai de Gesvres", 48.8571, 2.3495, 25, "Paris"),
+        ("4104", "Archives - Rambuteau", 48.8610, 2.3567, 31, "Paris"),
+        ("1023", "Bourse du Commerce", 48.8627, 2.3424, 29, "Paris"),
+        ("1024", "Louvre - Rivoli", 48.8606, 2.3405, 33, "Paris"),
+        ("3008", "Réaumur - Sébastopol", 48.8656, 2.3517, 27, "Paris"),
+        (
+            "4020",
+            "Île de la Cité - Lutèce",
+            48.8550,
+            2.3470,
+            18,
+            "Paris",
+        ),
+    ];
+    // (mechanical, electric) per site, docks filling the rest of capacity
+    const AVAILABILITY: [(u16, u16); 16] = [
+        (12, 6),
+        (20, 14),
+        (0, 0),
+        (9, 3),
+        (15, 13),
+        (18, 7),
+        (4, 2),
+        (0, 0),
+        (11, 5),
+        (14, 6),
+        (7, 4),
+        (0, 9),
+        (10, 10),
+        (22, 11),
+        (5, 1),
+        (8, 3),
+    ];
+    let now = Utc::now();
+    SITES
+        .iter()
+        .zip(AVAILABILITY)
+        .map(|(site, (mechanical, electric))| {
+            let docks = site.4 - mechanical - electric;
+            station(
+                site,
+                BikeAvailability::new(mechanical, electric),
+                docks,
+                StationStatus::Open,
+                now,
+            )
+        })
+        .collect()
+}
+
+/// Six stations spread over the eastern suburbs, 700 m to 3 km from their
+/// nearest neighbour, where the closest station can be out of walking distance
+#[must_use]
+pub fn sparse_suburb() -> Vec<VelibStation> {
+    const SITES: [Site<'static>; 6] = [
+        (
+            "31104",
+            "Montreuil - Mairie",
+            48.8621,
+            2.4419,
+            30,
+            "Montreuil",
+        ),
+        (
+            "31008",
+            "Montreuil - Croix de Chavaux",
+            48.8579,
+            2.4352,
+            28,
+            "Montreuil",
+        ),
+        (
+            "32013",
+            "Vincennes - Château",
+            48.8440,
+            2.4380,
+            36,
+            "Vincennes",
+        ),
+        (
+            "41301",
+            "Fontenay-sous-Bois - RER",
+            48.8508,
+            2.4637,
+            24,
+            "Fontenay-sous-Bois",
+        ),
+        (
+            "42016",
+            "Rosny-sous-Bois - Gare",
+            48.8733,
+            2.4849,
+            20,
+            "Rosny-sous-Bois",
+        ),
+        (
+            "35013",
+            "Bagnolet - Gallieni",
+            48.8634,
+            2.4162,
+            26,
+            "Bagnolet",
+        ),
+    ];
+    const AVAILABILITY: [(u16, u16); 6] = [(6, 2), (3, 1), (12, 4), (0, 1), (2, 0), (8, 5)];
+    let now = Utc::now();
+    SITES
+        .iter()
+        .zip(AVAILABILITY)
+        .map(|(site, (mechanical, electric))| {
+            station(
+                site,
+                BikeAvailability::new(mechanical, electric),
+                site.4 - mechanical - electric,
+                StationStatus::Open,
+                now,
+            )
+        })
+        .collect()
+}
+
+/// [`central_paris`] with every station closed and emptied, as on a strike
+/// day or during a network outage
+#[must_use]
+pub fn all_closed() -> Vec<VelibStation> {
+    let now = Utc::now();
+    central_paris()
+        .into_iter()
+        .map(|station| VelibStation {
+            real_time: Some(RealTimeStatus::new(
+                BikeAvailability::new(0, 0),
+                0,
+                StationStatus::Closed,
+                now,
+            )),
+            ..station
+        })
+        .collect()
+}
+
+/// [`central_paris`] whose real-time data was last published
+/// [`STALE_DATA_AGE_MINUTES`] ago, as when the upstream feed freezes
+#[must_use]
+pub fn stale_data() -> Vec<VelibStation> {
+    let published = Utc::now() - Duration::minutes(STALE_DATA_AGE_MINUTES);
+    central_paris()
+        .into_iter()
+        .map(|mut station| {
+            if let Some(status) = station.real_time.take() {
+                station.real_time = Some(RealTimeStatus::new(
+                    status.bikes,
+                    status.available_docks,
+                    status.status,
+                    published,
+                ));
+            }
+            station
+        })
+        .collect()
+}
+
+/// An open Paris station with 18 docks: 5 mechanical and 3 electric bikes and
+/// 10 free docks, published now, for tests that place their own stations
+#[must_use]
+pub fn open_station(code: &str, name: &str, latitude: f64, longitude: f64) -> VelibStation {
+    station(
+        &(code, name, latitude, longitude, 18, "Paris"),
+        BikeAvailability::new(5, 3),
+        10,
+        StationStatus::Open,
+        Utc::now(),
+    )
+}
+
+/// Reference stations and real-time statuses by station code, as
+/// [`VelibDataClient::seed_cache`] takes them
+#[must_use]
+pub fn split(
+    stations: &[VelibStation],
+) -> (Vec<StationReference>, HashMap<String, RealTimeStatus>) {
+    let references = stations
+        .iter()
+        .map(|station| station.reference.clone())
+        .collect();
+    let statuses = stations
+        .iter()
+        .filter_map(|station| {
+            let status = station.real_time.clone()?;
+            Some((station.reference.station_code.to_string(), status))
+        })
+        .collect();
+    (references, statuses)
+}
+
+/// A data client serving `stations` without network access
+pub async fn seeded_client(stations: &[VelibStation]) -> VelibDataClient {
+    let (references, statuses) = split(stations);
+    let mut client = VelibDataClient::new();
+    client.seed_cache(references, statuses).await;
+    client
+}
+
+fn station(
+    &(code, name, latitude, longitude, capacity, commune): &Site,
+    bikes: BikeAvailability,
+    available_docks: u16,
+    status: StationStatus,
+    published: DateTime<Utc>,
+) -> VelibStation {
+    let reference = StationReference {
+        station_code: code.into(),
+        name: name.into(),
+        coordinates: Coordinates::new(latitude, longitude),
+        capacity,
+        capabilities: ServiceCapabilities::default(),
+        district: None,
+        commune: None,
+        insee_code: None,
+    }
+    .with_commune(Some(commune.to_string()), None);
+    VelibStation {
+        reference,
+        real_time: Some(RealTimeStatus::new(
+            bikes,
+            available_docks,
+            status,
+            published,
+        )),
+    }
+}
@@ -0,0 +1,431 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::types::Coordinates;
+use crate::{Error, Result};
+
+/// A GeoJSON polygon or multipolygon
+///
+/// Positions are `[longitude, latitude]` as in GeoJSON. The first ring of each
+/// polygon is its outline, later rings are holes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Geometry {
+    Polygon {
+        coordinates: Vec<Vec<[f64; 2]>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<[f64; 2]>>>,
+    },
+}
+
+/// A GeoJSON file may hold a bare geometry or a feature wrapping one
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GeoJsonDocument {
+    Geometry(Geometry),
+    Feature { geometry: Geometry },
+}
+
+impl Geometry {
+    /// Load a polygon from a GeoJSON geometry or feature file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("Cannot read {}: {e}", path.display())))?;
+        let document: GeoJsonDocument = serde_json::from_str(&content).map_err(|e| {
+            Error::Config(format!(
+                "{} is not a GeoJSON Polygon or MultiPolygon: {e}",
+                path.display()
+            ))
+        })?;
+        let geometry = match document {
+            GeoJsonDocument::Geometry(geometry) | GeoJsonDocument::Feature { geometry } => geometry,
+        };
+        geometry
+            .validate()
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        Ok(geometry)
+    }
+
+    fn polygons(&self) -> impl Iterator<Item = &Vec<Vec<[f64; 2]>>> {
+        match self {
+            Geometry::Polygon { coordinates } => std::slice::from_ref(coordinates).iter(),
+            Geometry::MultiPolygon { coordinates } => coordinates.iter(),
+        }
+    }
+
+    /// Reject polygons without an outline or with rings of fewer than three points
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let mut polygons = self.polygons().peekable();
+        if polygons.peek().is_none() {
+            return Err("geometry has no polygon".to_string());
+        }
+        for rings in polygons {
+            if rings.is_empty() {
+                return Err("polygon has no outline".to_string());
+            }
+            for ring in rings {
+                let closed = ring.len() > 1 && ring.first() == ring.last();
+                let points = ring.len() - usize::from(closed);
+                if points < 3 {
+                    return Err("polygon rings need at least three points".to_string());
+                }
+                if ring.iter().flatten().any(|value| !value.is_finite()) {
+                    return Err("polygon positions must be finite numbers".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if coordinates fall inside the outline of a polygon and outside its holes
+    ///
+    /// Points exactly on an edge may fall either way.
+    #[must_use]
+    pub fn contains(&self, coords: &Coordinates) -> bool {
+        self.polygons().any(|rings| {
+            rings.split_first().is_some_and(|(outline, holes)| {
+                ring_contains(outline, coords)
+                    && !holes.iter().any(|hole| ring_contains(hole, coords))
+            })
+        })
+    }
+
+    /// Smallest `(south, west, north, east)` box around every outline
+    #[must_use]
+    pub fn bounding_box(&self) -> (f64, f64, f64, f64) {
+        self.polygons()
+            .filter_map(|rings| rings.first())
+            .flatten()
+            .fold(
+                (
+                    f64::INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::NEG_INFINITY,
+                ),
+                |(south, west, north, east), &[longitude, latitude]| {
+                    (
+                        south.min(latitude),
+                        west.min(longitude),
+                        north.max(latitude),
+                        east.max(longitude),
+                    )
+                },
+            )
+    }
+}
+
+/// Where a point lies relative to a route
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteProjection {
+    /// Distance from the point to the closest point of the route
+    pub distance_meters: f64,
+    /// Distance along the route from its start to that closest point
+    pub progress_meters: f64,
+}
+
+/// Length of the polyline through `route`
+#[must_use]
+pub fn route_length_meters(route: &[Coordinates]) -> f64 {
+    route
+        .windows(2)
+        .map(|segment| segment[0].distance_to(&segment[1]))
+        .sum()
+}
+
+/// Project `point` onto the polyline through `route`, `None` for an empty route
+///
+/// Segments are short enough to treat as straight lines on a plane tangent at
+/// their start.
+#[must_use]
+pub fn project_onto_route(route: &[Coordinates], point: &Coordinates) -> Option<RouteProjection> {
+    let (first, _) = route.split_first()?;
+    let mut best = RouteProjection {
+        distance_meters: point.distance_to(first),
+        progress_meters: 0.0,
+    };
+    let mut travelled = 0.0;
+    for segment in route.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let scale = start.latitude.to_radians().cos();
+        let (dx, dy) = (
+            (end.longitude - start.longitude) * scale,
+            end.latitude - start.latitude,
+        );
+        let (px, py) = (
+            (point.longitude - start.longitude) * scale,
+            point.latitude - start.latitude,
+        );
+        let length_squared = dx * dx + dy * dy;
+        let t = if length_squared > 0.0 {
+            ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let closest = Coordinates::new(
+            start.latitude + t * (end.latitude - start.latitude),
+            start.longitude + t * (end.longitude - start.longitude),
+        );
+        let segment_length = start.distance_to(&end);
+        let distance_meters = point.distance_to(&closest);
+        if distance_meters < best.distance_meters {
+            best = RouteProjection {
+                distance_meters,
+                progress_meters: travelled + t * segment_length,
+            };
+        }
+        travelled += segment_length;
+    }
+    Some(best)
+}
+
+/// Whether the straight walks `a`→`b` and `c`→`d` cross
+///
+/// Walks are short enough to treat as straight lines on a plane tangent at `a`.
+#[must_use]
+pub fn segments_cross(a: &Coordinates, b: &Coordinates, c: &Coordinates, d: &Coordinates) -> bool {
+    let scale = a.latitude.to_radians().cos();
+    let point = |p: &Coordinates| ((p.longitude - a.longitude) * scale, p.latitude - a.latitude);
+    let (a, b, c, d) = (point(a), point(b), point(c), point(d));
+    // Which side of the line through `p` and `q` the point `r` lies on
+    let side = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        ((q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)).signum()
+    };
+    side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0
+}
+
+/// A GeoJSON LineString, for drawing a route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub struct LineString {
+    /// `[longitude, latitude]` positions, as in GeoJSON
+    pub coordinates: Vec<[f64; 2]>,
+}
+
+impl LineString {
+    #[must_use]
+    pub fn new(route: &[Coordinates]) -> Self {
+        Self {
+            coordinates: route
+                .iter()
+                .map(|point| [point.longitude, point.latitude])
+                .collect(),
+        }
+    }
+}
+
+/// Encode `route` in the Google encoded polyline format
+///
+/// `precision` is the number of decimal digits kept: 5 in the original
+/// format, 6 for Valhalla and OSRM's `polyline6`.
+#[must_use]
+pub fn encode_polyline(route: &[Coordinates], precision: u32) -> String {
+    let factor = 10_f64.powi(precision as i32);
+    let mut encoded = String::new();
+    let mut previous = (0_i64, 0_i64);
+    for point in route {
+        let current = (
+            (point.latitude * factor).round() as i64,
+            (point.longitude * factor).round() as i64,
+        );
+        for delta in [current.0 - previous.0, current.1 - previous.1] {
+            let mut value = if delta < 0 { !(delta << 1) } else { delta << 1 };
+            while value >= 0x20 {
+                encoded.push(char::from((0x20 | (value & 0x1f)) as u8 + 63));
+                value >>= 5;
+            }
+            encoded.push(char::from(value as u8 + 63));
+        }
+        previous = current;
+    }
+    encoded
+}
+
+/// Decode a Google encoded polyline of the given `precision`
+pub fn decode_polyline(encoded: &str, precision: u32) -> Result<Vec<Coordinates>> {
+    let factor = 10_f64.powi(precision as i32);
+    let mut bytes = encoded.bytes();
+    let mut next_delta = || -> Option<Result<i64>> {
+        let mut value = 0_i64;
+        let mut shift = 0;
+        loop {
+            let byte = match bytes.next() {
+                Some(byte) => byte,
+                None if shift == 0 => return None,
+                None => return Some(Err(invalid_polyline())),
+            };
+            let chunk = match byte.checked_sub(63) {
+                Some(chunk) if shift <= 60 => i64::from(chunk),
+                _ => return Some(Err(invalid_polyline())),
+            };
+            value |= (chunk & 0x1f) << shift;
+            shift += 5;
+            if chunk < 0x20 {
+                break;
+            }
+        }
+        Some(Ok(if value & 1 == 1 {
+            !(value >> 1)
+        } else {
+            value >> 1
+        }))
+    };
+
+    let mut route = Vec::new();
+    let (mut latitude, mut longitude) = (0_i64, 0_i64);
+    while let Some(delta) = next_delta() {
+        latitude += delta?;
+        longitude += next_delta().ok_or_else(invalid_polyline)??;
+        route.push(Coordinates::new(
+            latitude as f64 / factor,
+            longitude as f64 / factor,
+        ));
+    }
+    Ok(route)
+}
+
+fn invalid_polyline() -> Error {
+    Error::Validation("Invalid encoded polyline".to_string())
+}
+
+/// Even-odd ray casting; the ring may or may not repeat its first point
+fn ring_contains(ring: &[[f64; 2]], coords: &Coordinates) -> bool {
+    let (x, y) = (coords.longitude, coords.latitude);
+    let mut inside = false;
+    let mut previous = ring[ring.len() - 1];
+    for &current in ring {
+        let [x1, y1] = previous;
+        let [x2, y2] = current;
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Roughly the 4th arrondissement, with the Île Saint-Louis cut out
+    fn marais() -> Geometry {
+        serde_json::from_value(json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[2.344, 48.851], [2.369, 48.851], [2.369, 48.863], [2.344, 48.863], [2.344, 48.851]],
+                [[2.353, 48.849], [2.362, 48.849], [2.362, 48.853], [2.353, 48.853]]
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_point_in_polygon_with_hole() {
+        let area = marais();
+        assert!(area.contains(&Coordinates::new(48.8566, 2.3522)));
+        assert!(!area.contains(&Coordinates::new(48.8515, 2.3570)));
+        assert!(!area.contains(&Coordinates::new(48.8446, 2.3737)));
+        assert_eq!(area.bounding_box(), (48.851, 2.344, 48.863, 2.369));
+    }
+
+    #[test]
+    fn test_multipolygon_contains_any_part() {
+        let area: Geometry = serde_json::from_value(json!({
+            "type": "MultiPolygon",
+            "coordinates": [
+                [[[2.34, 48.85], [2.36, 48.85], [2.35, 48.86]]],
+                [[[2.37, 48.84], [2.38, 48.84], [2.38, 48.85], [2.37, 48.85]]]
+            ]
+        }))
+        .unwrap();
+        assert!(area.validate().is_ok());
+        assert!(area.contains(&Coordinates::new(48.8446, 2.3737)));
+        assert!(area.contains(&Coordinates::new(48.852, 2.35)));
+        assert!(!area.contains(&Coordinates::new(48.852, 2.365)));
+    }
+
+    #[test]
+    fn test_projection_onto_route() {
+        // Gare de Lyon to Bastille, then up to République
+        let route = [
+            Coordinates::new(48.8443, 2.3744),
+            Coordinates::new(48.8532, 2.3691),
+            Coordinates::new(48.8674, 2.3636),
+        ];
+        let length = route_length_meters(&route);
+        assert!((2600.0..2800.0).contains(&length), "{length}");
+
+        let start = project_onto_route(&route, &route[0]).unwrap();
+        assert_eq!(start.progress_meters, 0.0);
+        let end = project_onto_route(&route, &route[2]).unwrap();
+        assert!((end.progress_meters - length).abs() < 1.0);
+        assert!(end.distance_meters < 1.0);
+
+        // Just east of the second leg
+        let aside = project_onto_route(&route, &Coordinates::new(48.8600, 2.3700)).unwrap();
+        assert!((150.0..300.0).contains(&aside.distance_meters));
+        assert!(aside.progress_meters > route[0].distance_to(&route[1]));
+
+        assert!(project_onto_route(&[], &route[0]).is_none());
+    }
+
+    #[test]
+    fn test_segments_cross() {
+        let west = Coordinates::new(48.857, 2.340);
+        let east = Coordinates::new(48.857, 2.360);
+        let north = Coordinates::new(48.860, 2.350);
+        let south = Coordinates::new(48.850, 2.350);
+        assert!(segments_cross(&west, &east, &north, &south));
+        assert!(segments_cross(&north, &south, &east, &west));
+        // Stopping short of the other walk
+        assert!(!segments_cross(
+            &west,
+            &Coordinates::new(48.857, 2.345),
+            &north,
+            &south
+        ));
+        // Parallel walks
+        assert!(!segments_cross(
+            &west,
+            &east,
+            &north,
+            &Coordinates::new(48.860, 2.360)
+        ));
+    }
+
+    #[test]
+    fn test_polyline_round_trip() {
+        // The example of the format's documentation
+        let route = [
+            Coordinates::new(38.5, -120.2),
+            Coordinates::new(40.7, -120.95),
+            Coordinates::new(43.252, -126.453),
+        ];
+        assert_eq!(encode_polyline(&route, 5), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        let decoded = decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert!((decoded[2].longitude + 126.453).abs() < 1e-9);
+
+        let precise = encode_polyline(&route, 6);
+        assert_eq!(decode_polyline(&precise, 6).unwrap()[1].latitude, 40.7);
+        assert!(decode_polyline("_p~iF~ps|U_", 5).is_err());
+        assert!(decode_polyline(" ", 5).is_err());
+    }
+
+    #[test]
+    fn test_degenerate_polygons_are_rejected() {
+        let line = Geometry::Polygon {
+            coordinates: vec![vec![[2.34, 48.85], [2.36, 48.85], [2.34, 48.85]]],
+        };
+        assert!(line.validate().is_err());
+        assert!(Geometry::MultiPolygon {
+            coordinates: vec![]
+        }
+        .validate()
+        .is_err());
+        assert!(marais().validate().is_ok());
+    }
+}
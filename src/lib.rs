@@ -1,12 +1,26 @@
+pub mod clock;
 pub mod data;
 pub mod error;
+pub mod events;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+pub mod geometry;
+pub mod logging;
 pub mod mcp;
+pub mod metrics;
+pub mod phases;
+pub mod routing;
 pub mod server;
+pub mod storage;
 pub mod types;
 
 // Re-export main types for convenience
+pub use clock::{Clock, ManualClock, SystemClock};
 pub use data::VelibDataClient;
 pub use error::{Error, Result};
+pub use events::{Announcement, AnnouncementKind, EventBus, ServerEvent, StationUpdate};
+pub use geometry::Geometry;
 pub use mcp::{McpServer, McpToolHandler};
-pub use server::{parse_server_address, Server};
+pub use metrics::{Metrics, MetricsSnapshot, UsageReport};
+pub use server::{parse_server_address, AppConfig, Server};
 pub use types::*;
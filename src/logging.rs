@@ -0,0 +1,38 @@
+use std::sync::OnceLock;
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter, Registry};
+
+use crate::{Error, Result};
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Log to stdout, filtered by `RUST_LOG` until [`set_filter`] replaces it
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .init();
+    let _ = FILTER.set(handle);
+}
+
+/// Parse `directives` in the `RUST_LOG` syntax; empty logs errors only
+pub fn parse_filter(directives: &str) -> Result<EnvFilter> {
+    if directives.trim().is_empty() {
+        return Ok(EnvFilter::new("error"));
+    }
+    EnvFilter::try_new(directives)
+        .map_err(|e| Error::Config(format!("Invalid log level {directives:?}: {e}")))
+}
+
+/// Replace the log filter of a running server
+///
+/// Does nothing unless logging was set up with [`init`].
+pub fn set_filter(directives: &str) -> Result<()> {
+    let filter = parse_filter(directives)?;
+    if let Some(handle) = FILTER.get() {
+        handle
+            .reload(filter)
+            .map_err(|e| Error::Config(format!("Cannot change log level: {e}")))?;
+    }
+    Ok(())
+}
@@ -1,19 +1,232 @@
-use velib_mcp::{parse_server_address, Server};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::io::Write;
+use velib_mcp::data::ExportFormat;
+use velib_mcp::mcp::loadtest::{parse_duration, serve_fixture};
+use velib_mcp::mcp::{schema, LoadTest, McpClient, ToolRegistry};
+use velib_mcp::server::selftest;
+use velib_mcp::{parse_server_address, AppConfig, Server};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+const HISTORY_EXPORT_USAGE: &str = "usage: velib-mcp history export [--format csv|parquet] \
+     [--since <YYYY-MM-DD|RFC 3339>] [--server <url>] [--output <path>]";
+
+const BENCH_USAGE: &str = "usage: velib-mcp bench [--tool <name>] [--arguments <json>] \
+     [--concurrency <n>] [--duration <60s|2m>] [--server <url>] [--api-key <key>]";
+
+const SELFTEST_USAGE: &str = "usage: velib-mcp selftest";
+
+const SCHEMA_USAGE: &str = "usage: velib-mcp schema [--ts] [--output <path>]";
+
+const TUI_USAGE: &str =
+    "usage: velib-mcp tui [--server <url>] [--api-key <key>] [--interval <2s|1m>]";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Read and clear the socket activation variables while this is the only thread
+    #[cfg(unix)]
+    let inherited_socket = velib_mcp::server::InheritedSocket::take();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(
+            #[cfg(unix)]
+            inherited_socket,
+        ))
+}
+
+async fn run(
+    #[cfg(unix)] inherited_socket: Option<velib_mcp::server::InheritedSocket>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize tracing, its level can change on configuration reloads
+    velib_mcp::logging::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => {}
+        ["history", "export", options @ ..] => return export_history(options).await,
+        ["bench", options @ ..] => return bench(options).await,
+        ["selftest"] => return self_test().await,
+        ["schema", options @ ..] => return export_schema(options),
+        ["tui", options @ ..] => return tui(options).await,
+        _ => {
+            return Err(format!(
+            "{HISTORY_EXPORT_USAGE}\n{BENCH_USAGE}\n{SELFTEST_USAGE}\n{SCHEMA_USAGE}\n{TUI_USAGE}"
+        )
+            .into())
+        }
+    }
 
     // Parse server address from environment variables
     let addr = parse_server_address()
         .expect("Failed to parse server address from IP and PORT environment variables");
 
     // Create and run server
-    let server = Server::new(addr);
+    let config = AppConfig::load()?;
+    let server = Server::with_config(addr, &config)?;
+    #[cfg(unix)]
+    let server = server.with_inherited_socket(inherited_socket);
     server.run().await?;
 
     Ok(())
 }
+
+/// Fetch recorded history from a running server and write it to a file or stdout
+async fn export_history(options: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut format = ExportFormat::Csv;
+    let mut since = None;
+    let mut server = None;
+    let mut output = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or(HISTORY_EXPORT_USAGE)?;
+        match *option {
+            "--format" => format = value.parse()?,
+            "--since" => since = Some(parse_since(value)?),
+            "--server" => server = Some((*value).to_string()),
+            "--output" => output = Some(*value),
+            _ => return Err(HISTORY_EXPORT_USAGE.into()),
+        }
+    }
+
+    let token = std::env::var("VELIB_ADMIN_TOKEN")
+        .map_err(|_| "VELIB_ADMIN_TOKEN must be set to export history")?;
+    let server = match server {
+        Some(server) => server,
+        None => format!("http://{}", parse_server_address()?),
+    };
+
+    let bytes = McpClient::new(&server)
+        .export_history(&token, format, since)
+        .await?;
+    match output {
+        Some(path) => std::fs::write(path, bytes)?,
+        None => std::io::stdout().write_all(&bytes)?,
+    }
+    Ok(())
+}
+
+/// Load a running server, or a fixture one started in this process, and
+/// print latency percentiles and error rates
+async fn bench(options: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tool = "find_nearby_stations";
+    let mut arguments = None;
+    let mut concurrency = 10;
+    let mut duration = std::time::Duration::from_secs(10);
+    let mut server = None;
+    let mut api_key = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or(BENCH_USAGE)?;
+        match *option {
+            "--tool" => tool = value,
+            "--arguments" => arguments = Some(serde_json::from_str(value)?),
+            "--concurrency" => concurrency = value.parse()?,
+            "--duration" => duration = parse_duration(value)?,
+            "--server" => server = Some((*value).to_string()),
+            "--api-key" => api_key = Some(*value),
+            _ => return Err(BENCH_USAGE.into()),
+        }
+    }
+
+    let arguments = arguments
+        .or_else(|| LoadTest::sample_arguments(tool))
+        .ok_or_else(|| format!("--arguments is required for {tool}"))?;
+    let mut load = LoadTest::new(tool, arguments)
+        .with_concurrency(concurrency)
+        .with_duration(duration);
+    if let Some(api_key) = api_key {
+        load = load.with_api_key(api_key);
+    }
+    let server = match server {
+        Some(server) => server,
+        None => format!("http://{}", serve_fixture().await?),
+    };
+
+    let report = load.run(&server).await?;
+    println!("{report}");
+    Ok(())
+}
+
+/// Check the configuration, upstream and every tool, print a JSON report and
+/// exit non-zero if anything failed
+async fn self_test() -> Result<(), Box<dyn std::error::Error>> {
+    let report = selftest::run(AppConfig::load()).await;
+    println!("{report}");
+    if !report.passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Write the tool input schemas as a JSON Schema bundle, or as TypeScript
+/// declarations with `--ts`
+fn export_schema(options: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut typescript = false;
+    let mut output = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--ts" => typescript = true,
+            "--output" => output = Some(*options.next().ok_or(SCHEMA_USAGE)?),
+            _ => return Err(SCHEMA_USAGE.into()),
+        }
+    }
+
+    let registry = ToolRegistry::builtin();
+    let contents = if typescript {
+        schema::typescript(&registry)
+    } else {
+        serde_json::to_string_pretty(&schema::bundle(&registry))? + "\n"
+    };
+    match output {
+        Some(path) => std::fs::write(path, contents)?,
+        None => std::io::stdout().write_all(contents.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Show station totals, caches, recent tool calls and error counters of a
+/// running server, refreshed in place until Ctrl-C
+#[cfg(feature = "tui")]
+async fn tui(options: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut server = None;
+    let mut api_key = None;
+    let mut interval = std::time::Duration::from_secs(2);
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or(TUI_USAGE)?;
+        match *option {
+            "--server" => server = Some((*value).to_string()),
+            "--api-key" => api_key = Some(*value),
+            "--interval" => interval = parse_duration(value)?,
+            _ => return Err(TUI_USAGE.into()),
+        }
+    }
+
+    let server = match server {
+        Some(server) => server,
+        None => format!("http://{}", parse_server_address()?),
+    };
+    let mut client = McpClient::new(&server);
+    if let Some(api_key) = api_key {
+        client = client.with_api_key(api_key);
+    }
+    velib_mcp::mcp::tui::run(&client, &server, interval).await?;
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+async fn tui(_options: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("velib-mcp tui needs a build with --features tui".into())
+}
+
+/// A calendar date (midnight UTC) or a full RFC 3339 timestamp
+fn parse_since(value: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+}
@@ -0,0 +1,228 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::announcements::AnnouncementDraft;
+use super::auth::secret_eq;
+use super::server::McpServer;
+use crate::data::ExportFormat;
+
+#[derive(Clone)]
+struct AdminState {
+    token: Arc<str>,
+    server: McpServer,
+}
+
+/// `/admin/quotas` to list usage, `/admin/quotas/:key` to view (GET) or reset (DELETE) one key,
+/// given as is or as its [`key_id`](super::auth::key_id),
+/// `/admin/history` to download recorded availability, `/admin/config/reload` (POST) to
+/// apply the configuration again, `/admin/announcements` to list (GET) or publish (POST)
+/// service announcements and `/admin/announcements/:id` (DELETE) to withdraw one
+pub(crate) fn routes(token: &str, server: McpServer) -> Router {
+    Router::new()
+        .route("/admin/quotas", get(list_quotas))
+        .route("/admin/quotas/:key", get(get_quota).delete(reset_quota))
+        .route("/admin/history", get(export_history))
+        .route("/admin/config/reload", post(reload_config))
+        .route(
+            "/admin/announcements",
+            get(list_announcements).post(publish_announcement),
+        )
+        .route("/admin/announcements/:id", delete(withdraw_announcement))
+        .with_state(AdminState {
+            token: Arc::from(token),
+            server,
+        })
+}
+
+fn authorized(state: &AdminState, headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| secret_eq(&state.token, token.trim()))
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(json!({"error": "admin token required"})),
+    )
+        .into_response()
+}
+
+async fn list_quotas(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    let limits = state.server.quotas().limits();
+    Json(json!({
+        "limits": {"daily": limits.daily, "monthly": limits.monthly},
+        "keys": state.server.quotas().list(chrono::Utc::now()).await,
+    }))
+    .into_response()
+}
+
+async fn get_quota(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(key): Path<String>,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    Json(state.server.quotas().get(&key, chrono::Utc::now()).await).into_response()
+}
+
+async fn reset_quota(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(key): Path<String>,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    let reset = state.server.quotas().reset(&key).await;
+    Json(json!({"key": key, "reset": reset})).into_response()
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+    since: Option<DateTime<Utc>>,
+}
+
+/// Recorded availability since `since` (all of it by default) as CSV or Parquet
+async fn export_history(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    let format = match query
+        .format
+        .as_deref()
+        .unwrap_or("csv")
+        .parse::<ExportFormat>()
+    {
+        Ok(format) => format,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+    let records = state
+        .server
+        .handler()
+        .history_records(query.since.unwrap_or(DateTime::<Utc>::MIN_UTC))
+        .await;
+    match format.encode(&records) {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"velib-history.{}\"",
+                        format.extension()
+                    ),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+/// Read the configuration again; the version in effect, or why it was refused
+async fn reload_config(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    match state.server.reload().await {
+        Ok(version) => Json(version).into_response(),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": e.to_string(),
+                "config": state.server.config_version(),
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn list_announcements(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    Json(json!({"announcements": state.server.announcements().await})).into_response()
+}
+
+/// Publish an announcement to every client; the announcement, or why it was refused
+async fn publish_announcement(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    let draft: AnnouncementDraft = match serde_json::from_slice(&body) {
+        Ok(draft) => draft,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Invalid announcement: {e}")})),
+            )
+                .into_response()
+        }
+    };
+    match state.server.publish_announcement(draft).await {
+        Ok(announcement) => (StatusCode::CREATED, Json(announcement)).into_response(),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+async fn withdraw_announcement(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    if state.server.withdraw_announcement(&id).await {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("No announcement {id}")})),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use crate::events::{Announcement, AnnouncementKind};
+use crate::{Error, Result};
+
+/// Longest announcement message accepted, so tool results stay small
+pub const MAX_MESSAGE_CHARS: usize = 500;
+
+/// An announcement as an operator submits it to `POST /admin/announcements`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnouncementDraft {
+    pub kind: AnnouncementKind,
+    pub message: String,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Service announcements in effect, attached to tool results until withdrawn or expired
+#[derive(Debug, Default)]
+pub struct AnnouncementStore {
+    announcements: RwLock<Vec<Announcement>>,
+}
+
+impl AnnouncementStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn publish(
+        &self,
+        draft: AnnouncementDraft,
+        now: DateTime<Utc>,
+    ) -> Result<Announcement> {
+        let message = draft.message.trim();
+        if message.is_empty() || message.chars().count() > MAX_MESSAGE_CHARS {
+            return Err(Error::Validation(format!(
+                "Announcement message must be 1 to {MAX_MESSAGE_CHARS} characters"
+            )));
+        }
+        if draft.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(Error::Validation(
+                "Announcement expires_at must be in the future".to_string(),
+            ));
+        }
+        let announcement = Announcement {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: draft.kind,
+            message: message.to_string(),
+            published_at: now,
+            expires_at: draft.expires_at,
+        };
+        let mut announcements = self.announcements.write().await;
+        announcements.retain(|announcement| announcement.is_active(now));
+        announcements.push(announcement.clone());
+        Ok(announcement)
+    }
+
+    /// Returns whether an announcement with that id was in effect
+    pub async fn withdraw(&self, id: &str) -> bool {
+        let mut announcements = self.announcements.write().await;
+        let before = announcements.len();
+        announcements.retain(|announcement| announcement.id != id);
+        announcements.len() < before
+    }
+
+    /// Announcements not yet expired, oldest first
+    pub async fn active(&self, now: DateTime<Utc>) -> Vec<Announcement> {
+        self.announcements
+            .read()
+            .await
+            .iter()
+            .filter(|announcement| announcement.is_active(now))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Set `_meta.announcements` on a tool result when there are any
+#[must_use]
+pub fn attach(mut result: Value, announcements: &[Announcement]) -> Value {
+    if !announcements.is_empty() {
+        super::meta::insert(&mut result, "announcements", json!(announcements));
+    }
+    result
+}
+
+/// The MCP `notifications/message` pushing the announcements now in effect
+#[must_use]
+pub fn notification(announcements: &[Announcement]) -> Value {
+    let level = if announcements
+        .iter()
+        .any(|announcement| announcement.kind != AnnouncementKind::Info)
+    {
+        "warning"
+    } else {
+        "info"
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/message",
+        "params": {
+            "level": level,
+            "logger": "velib-mcp/announcements",
+            "data": {"announcements": announcements}
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn draft(message: &str, expires_at: Option<DateTime<Utc>>) -> AnnouncementDraft {
+        AnnouncementDraft {
+            kind: AnnouncementKind::Maintenance,
+            message: message.to_string(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_announcements_last_until_withdrawn_or_expired() {
+        let store = AnnouncementStore::new();
+        let now = Utc::now();
+        let window = store
+            .publish(
+                draft("Upgrade tonight", Some(now + Duration::hours(1))),
+                now,
+            )
+            .await
+            .unwrap();
+        let lasting = store.publish(draft(" Strike ", None), now).await.unwrap();
+        assert_eq!(lasting.message, "Strike");
+        assert_eq!(store.active(now).await.len(), 2);
+        assert_eq!(
+            store.active(now + Duration::hours(2)).await,
+            vec![lasting.clone()]
+        );
+
+        assert!(store.withdraw(&window.id).await);
+        assert!(!store.withdraw(&window.id).await);
+        assert_eq!(
+            notification(&store.active(now).await)["params"]["level"],
+            "warning"
+        );
+
+        for invalid in [draft("", None), draft("Late", Some(now))] {
+            assert!(matches!(
+                store.publish(invalid, now).await,
+                Err(Error::Validation(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::{header, request::Parts, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Who a request comes from, as an [`AuthProvider`] established it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Which provider vouched for the caller, e.g. `key` or `oidc:<issuer>`, so
+    /// equal subjects from different providers stay different callers
+    pub namespace: String,
+    /// Stable identifier of the caller: the API key itself, or a token's `sub`
+    pub subject: String,
+}
+
+impl Principal {
+    #[must_use]
+    pub fn new(namespace: impl Into<String>, subject: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            subject: subject.into(),
+        }
+    }
+
+    /// Session, favorites and quotas follow the caller across requests and tokens
+    pub(crate) fn session_key(&self) -> String {
+        format!("{}:{}", self.namespace, self.subject)
+    }
+}
+
+pub type AuthFuture<'a> = Pin<Box<dyn Future<Output = Option<Principal>> + Send + 'a>>;
+
+/// A way of recognizing the bearer tokens presented to the HTTP and WebSocket transports
+pub trait AuthProvider: Send + Sync {
+    /// The caller `token` identifies, or `None` if this provider doesn't accept it
+    fn authenticate<'a>(&'a self, token: &'a str) -> AuthFuture<'a>;
+}
+
+/// Static bearer keys, each key being its own caller
+#[derive(Debug, Clone)]
+pub struct ApiKeys(pub Arc<[String]>);
+
+impl AuthProvider for ApiKeys {
+    fn authenticate<'a>(&'a self, token: &'a str) -> AuthFuture<'a> {
+        let accepted = self.0.iter().any(|key| secret_eq(key, token));
+        Box::pin(async move { accepted.then(|| Principal::new("key", token)) })
+    }
+}
+
+/// The session of an HTTP request, set by [`require_bearer`] for the handlers behind it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HttpSession(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for HttpSession {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<Self>().cloned().unwrap_or_default())
+    }
+}
+
+/// Providers tried in turn on each request's bearer token; anyone may call when there are none
+pub(crate) type Providers = Arc<[Arc<dyn AuthProvider>]>;
+
+/// A bearer token no provider accepted, while some are configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Unauthenticated;
+
+/// The caller `presented` identifies, trying each of `providers` in turn
+///
+/// `Ok(None)` when there are no providers, as anyone may call then.
+pub(crate) async fn authenticate(
+    providers: &[Arc<dyn AuthProvider>],
+    presented: Option<&str>,
+) -> Result<Option<Principal>, Unauthenticated> {
+    if providers.is_empty() {
+        return Ok(None);
+    }
+    if let Some(token) = presented {
+        for provider in providers {
+            if let Some(principal) = provider.authenticate(token).await {
+                return Ok(Some(principal));
+            }
+        }
+    }
+    Err(Unauthenticated)
+}
+
+/// Turn away HTTP requests no provider recognizes, and identify the session of the others
+pub(crate) async fn require_bearer(
+    State(providers): State<Providers>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let presented = bearer(request.headers());
+    let session = match authenticate(&providers, presented).await {
+        Ok(Some(principal)) => Some(principal.session_key()),
+        // Nobody vouched for the token: it keeps a session but is not metered
+        Ok(None) => presented.map(|token| format!("bearer:{token}")),
+        Err(Unauthenticated) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(json!({"error": "API key required"})),
+            )
+                .into_response()
+        }
+    };
+    request.extensions_mut().insert(HttpSession(session));
+    next.run(request).await
+}
+
+/// Compare a presented secret in constant time, so timing doesn't reveal how
+/// much of it matches
+pub(crate) fn secret_eq(expected: &str, presented: &str) -> bool {
+    expected.len() == presented.len()
+        && openssl::memcmp::eq(expected.as_bytes(), presented.as_bytes())
+}
+
+/// Hex SHA-256 of an API key or session key, stored instead of the key itself
+pub fn key_id(key: &str) -> String {
+    openssl::sha::sha256(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Whether `id` looks like a [`key_id`]
+pub(crate) fn is_key_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// The bearer token of a request, if it has a non-empty one
+pub(crate) fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
@@ -0,0 +1,180 @@
+use serde_json::Value;
+
+/// Rough size of a token in serialized JSON, to turn a token budget into bytes
+pub const BYTES_PER_TOKEN: u64 = 4;
+
+/// Smallest budget honored; below it even an error would not fit
+pub const MIN_RESPONSE_BYTES: u64 = 256;
+
+// Fields that repeat what other fields say or only serve rendering
+const OPTIONAL_FIELDS: &[&str] = &["svg", "geojson", "polyline", "markdown"];
+
+/// Largest serialized tool result a caller asked for, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseBudget {
+    bytes: usize,
+}
+
+impl ResponseBudget {
+    /// The tighter of `max_response_bytes` and `max_response_tokens`, if either is set
+    #[must_use]
+    pub fn from_hints(max_bytes: Option<u64>, max_tokens: Option<u64>) -> Option<Self> {
+        let tokens_as_bytes = max_tokens.map(|tokens| tokens.saturating_mul(BYTES_PER_TOKEN));
+        let bytes = match (max_bytes, tokens_as_bytes) {
+            (Some(a), Some(b)) => a.min(b),
+            (bytes, tokens) => bytes.or(tokens)?,
+        };
+        Some(Self {
+            bytes: usize::try_from(bytes.max(MIN_RESPONSE_BYTES)).unwrap_or(usize::MAX),
+        })
+    }
+
+    #[must_use]
+    pub fn bytes(self) -> usize {
+        self.bytes
+    }
+
+    /// Shrink a tool result until it fits: first drop the extra content
+    /// blocks, then optional fields, then halve the longest lists
+    ///
+    /// A result that had to shrink says so with `truncated_due_to_budget: true`
+    /// in its JSON text. One still too large once every list is down to a
+    /// single item is returned as small as it got.
+    #[must_use]
+    pub fn fit(self, mut result: Value) -> Value {
+        if size(&result) <= self.bytes {
+            return result;
+        }
+        let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+            return result;
+        };
+        content.truncate(1);
+        let Some(mut output) = content
+            .first()
+            .and_then(|block| block.get("text"))
+            .and_then(Value::as_str)
+            .and_then(|text| serde_json::from_str::<Value>(text).ok())
+        else {
+            return result;
+        };
+
+        if let Some(object) = output.as_object_mut() {
+            object.insert("truncated_due_to_budget".to_string(), Value::Bool(true));
+        }
+        let fits = |result: &mut Value, output: &Value| {
+            // Pretty printing costs too much room here
+            result["content"][0]["text"] = Value::String(output.to_string());
+            size(result) <= self.bytes
+        };
+        if fits(&mut result, &output) {
+            return result;
+        }
+        strip_optional(&mut output);
+        while !fits(&mut result, &output) && halve_longest_list(&mut output) {}
+        result
+    }
+}
+
+fn size(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len())
+}
+
+fn strip_optional(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|key, field| {
+                !(field.is_null()
+                    || OPTIONAL_FIELDS.contains(&key.as_str())
+                    || (key.ends_with("_text") && field.is_string()))
+            });
+            object.values_mut().for_each(strip_optional);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_optional),
+        _ => {}
+    }
+}
+
+/// Halve the list with the most items; `false` once no list has more than one
+fn halve_longest_list(value: &mut Value) -> bool {
+    fn longest(value: &Value) -> usize {
+        match value {
+            Value::Object(object) => object.values().map(longest).max().unwrap_or(0),
+            Value::Array(items) => items
+                .iter()
+                .map(longest)
+                .max()
+                .unwrap_or(0)
+                .max(items.len()),
+            _ => 0,
+        }
+    }
+    fn halve(value: &mut Value, wanted: usize) -> bool {
+        match value {
+            Value::Array(items) if items.len() == wanted => {
+                items.truncate(wanted / 2);
+                true
+            }
+            Value::Array(items) => items.iter_mut().any(|item| halve(item, wanted)),
+            Value::Object(object) => object.values_mut().any(|field| halve(field, wanted)),
+            _ => false,
+        }
+    }
+
+    let wanted = longest(value);
+    wanted >= 2 && halve(value, wanted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool_result(output: &Value) -> Value {
+        json!({"content": [
+            {"type": "text", "text": serde_json::to_string_pretty(output).unwrap()},
+            {"type": "text", "text": "| a table |"}
+        ]})
+    }
+
+    fn output_of(result: &Value) -> Value {
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_hints_pick_the_tighter_budget() {
+        assert_eq!(ResponseBudget::from_hints(None, None), None);
+        let budget = ResponseBudget::from_hints(Some(10_000), Some(1000)).unwrap();
+        assert_eq!(budget.bytes(), 4000);
+        let floor = ResponseBudget::from_hints(Some(10), None).unwrap();
+        assert_eq!(floor.bytes(), MIN_RESPONSE_BYTES as usize);
+    }
+
+    #[test]
+    fn test_results_within_budget_are_untouched() {
+        let result = tool_result(&json!({"stations": [1, 2, 3]}));
+        let budget = ResponseBudget::from_hints(Some(10_000), None).unwrap();
+        assert_eq!(budget.fit(result.clone()), result);
+    }
+
+    #[test]
+    fn test_lists_shrink_until_the_result_fits() {
+        let stations: Vec<Value> = (0..100)
+            .map(|i| json!({"station_code": format!("{i}"), "distance_meters": i, "distance_text": format!("{i} m")}))
+            .collect();
+        let result =
+            tool_result(&json!({"stations": stations, "svg": "<svg/>", "total_found": 100}));
+        let budget = ResponseBudget::from_hints(None, Some(250)).unwrap();
+
+        let fitted = budget.fit(result);
+        assert!(size(&fitted) <= budget.bytes());
+        assert_eq!(fitted["content"].as_array().unwrap().len(), 1);
+        let output = output_of(&fitted);
+        assert_eq!(output["truncated_due_to_budget"], true);
+        assert_eq!(output["total_found"], 100);
+        assert!(output.get("svg").is_none());
+        let kept = output["stations"].as_array().unwrap();
+        assert!(!kept.is_empty() && kept.len() < 100);
+        assert_eq!(kept[0]["station_code"], "0");
+        assert!(kept[0].get("distance_text").is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::data::ExportFormat;
+use crate::mcp::encoding::{Encoding, MSGPACK};
+use crate::mcp::types::{
+    FindNearbyStationsInput, FindNearbyStationsOutput, GetStationByCodeInput,
+    GetStationByCodeOutput, JsonRpcResponse, PlanBikeJourneyInput, PlanBikeJourneyOutput,
+    SearchStationsByNameInput, SearchStationsByNameOutput,
+};
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Typed JSON-RPC client for a running velib-mcp server
+///
+/// # Example
+/// ```no_run
+/// # async fn demo() -> velib_mcp::Result<()> {
+/// use velib_mcp::mcp::{McpClient, SearchStationsByNameInput};
+///
+/// let client = McpClient::new("http://127.0.0.1:8080").with_api_key("commuter");
+/// let found = client
+///     .search_stations_by_name(SearchStationsByNameInput {
+///         query: "Gare de Lyon".to_string(),
+///         limit: 5,
+///         fuzzy: true,
+///         sort: Default::default(),
+///         lenient: None,
+///         district: None,
+///         near_transit: None,
+///         transit_radius_meters: None,
+///     })
+///     .await?;
+/// println!("{} stations", found.stations.len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct McpClient {
+    http: reqwest::Client,
+    base_url: String,
+    endpoint: String,
+    api_key: Option<String>,
+    resource_encoding: Encoding,
+    next_id: AtomicU64,
+}
+
+impl McpClient {
+    /// Client for the server at `base_url`, e.g. `http://127.0.0.1:8080`
+    #[must_use]
+    pub fn new(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: format!("{base_url}/mcp"),
+            base_url,
+            api_key: None,
+            resource_encoding: Encoding::Json,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Send a bearer API key so session-scoped tools (preferences, favorites) work
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Ask for resources in MessagePack, which is cheaper to encode and decode
+    /// than JSON for the full station datasets
+    #[must_use]
+    pub fn with_msgpack(mut self) -> Self {
+        self.resource_encoding = Encoding::MessagePack;
+        self
+    }
+
+    /// Read a resource such as `velib://stations/realtime` from `/resources`
+    ///
+    /// The body is decoded according to its `Content-Type`, so a server
+    /// answering JSON to a MessagePack request still works.
+    pub async fn read_resource<T: DeserializeOwned>(&self, uri: &str) -> Result<T> {
+        let accept = match self.resource_encoding {
+            Encoding::Json => "application/json",
+            Encoding::MessagePack => MSGPACK,
+        };
+        let mut request = self
+            .http
+            .get(format!("{}/resources/{uri}", self.base_url))
+            .header(reqwest::header::ACCEPT, accept);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::ResourceNotFound {
+                uri: uri.to_string(),
+            });
+        }
+        let response = response.error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.bytes().await?;
+        match Encoding::of_content_type(content_type.as_deref()) {
+            Encoding::Json => Ok(serde_json::from_slice(&bytes)?),
+            Encoding::MessagePack => rmp_serde::from_slice(&bytes)
+                .map_err(|e| Error::McpProtocol(format!("Invalid MessagePack from {uri}: {e}"))),
+        }
+    }
+
+    /// Download recorded availability history from `/admin/history`
+    ///
+    /// Needs the server's `VELIB_ADMIN_TOKEN`; `since` defaults to everything
+    /// the server still holds.
+    pub async fn export_history(
+        &self,
+        admin_token: &str,
+        format: ExportFormat,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<u8>> {
+        let mut query = vec![("format", format.extension().to_string())];
+        if let Some(since) = since {
+            query.push((
+                "since",
+                since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            ));
+        }
+        let response = self
+            .http
+            .get(format!("{}/admin/history", self.base_url))
+            .query(&query)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: Value = response.json().await.unwrap_or_default();
+            return Err(Error::McpProtocol(format!(
+                "history export failed ({status}): {}",
+                body["error"].as_str().unwrap_or("no details")
+            )));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Send a JSON-RPC request and return its `result`
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = self.http.post(&self.endpoint).json(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: JsonRpcResponse = request.send().await?.json().await?;
+        if let Some(error) = response.error {
+            return Err(Error::McpProtocol(format!(
+                "{method} failed ({}): {}",
+                error.code, error.message
+            )));
+        }
+        response
+            .result
+            .ok_or_else(|| Error::McpProtocol(format!("{method} returned no result")))
+    }
+
+    pub async fn initialize(&self) -> Result<Value> {
+        self.request(
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "velib-mcp-client", "version": env!("CARGO_PKG_VERSION")}
+            }),
+        )
+        .await
+    }
+
+    /// Call a tool and decode the JSON carried by its text content
+    pub async fn call_tool<I: Serialize, O: DeserializeOwned>(
+        &self,
+        name: &str,
+        arguments: &I,
+    ) -> Result<O> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({"name": name, "arguments": serde_json::to_value(arguments)?}),
+            )
+            .await?;
+        let text = result["content"][0]["text"]
+            .as_str()
+            .ok_or_else(|| Error::McpProtocol(format!("{name} returned no text content")))?;
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub async fn find_nearby_stations(
+        &self,
+        input: FindNearbyStationsInput,
+    ) -> Result<FindNearbyStationsOutput> {
+        self.call_tool("find_nearby_stations", &input).await
+    }
+
+    pub async fn get_station_by_code(
+        &self,
+        input: GetStationByCodeInput,
+    ) -> Result<GetStationByCodeOutput> {
+        self.call_tool("get_station_by_code", &input).await
+    }
+
+    pub async fn search_stations_by_name(
+        &self,
+        input: SearchStationsByNameInput,
+    ) -> Result<SearchStationsByNameOutput> {
+        self.call_tool("search_stations_by_name", &input).await
+    }
+
+    pub async fn plan_bike_journey(
+        &self,
+        input: PlanBikeJourneyInput,
+    ) -> Result<PlanBikeJourneyOutput> {
+        self.call_tool("plan_bike_journey", &input).await
+    }
+}
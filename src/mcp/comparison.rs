@@ -0,0 +1,121 @@
+use crate::mcp::humanize::{Locale, UnitSystem};
+use crate::mcp::types::StationComparison;
+use crate::types::StationStatus;
+
+/// Share of observed samples in which a station had both a bike and a free dock
+///
+/// Docks are estimated from capacity, since history only keeps bike counts.
+/// `None` without samples.
+#[must_use]
+pub fn reliability_score(bike_counts: &[u16], capacity: u16) -> Option<f64> {
+    if bike_counts.is_empty() {
+        return None;
+    }
+    let usable = bike_counts
+        .iter()
+        .filter(|&&bikes| bikes > 0 && bikes < capacity)
+        .count();
+    Some(usable as f64 / bike_counts.len() as f64)
+}
+
+/// One row per station, for clients that display text as is
+#[must_use]
+pub fn markdown_table(stations: &[StationComparison], units: UnitSystem) -> String {
+    let with_distance = stations.iter().any(|s| s.distance_meters.is_some());
+    let mut table = String::from("| Station | Status | Mechanical | Electric | Docks |");
+    if with_distance {
+        table.push_str(" Distance |");
+    }
+    table.push_str(" Reliability |\n|---|---|---:|---:|---:|");
+    if with_distance {
+        table.push_str("---:|");
+    }
+    table.push_str("---:|\n");
+
+    for station in stations {
+        let status = match &station.status {
+            Some(StationStatus::Open) => "open",
+            Some(StationStatus::Closed) => "closed",
+            Some(StationStatus::Maintenance) => "partial",
+            None => "unknown",
+        };
+        table.push_str(&format!(
+            "| {} ({}) | {status} | {} | {} | {}/{} |",
+            station.name.replace('|', "/"),
+            station.station_code,
+            station.mechanical_bikes,
+            station.electric_bikes,
+            station.available_docks,
+            station.capacity,
+        ));
+        if with_distance {
+            match station.distance_meters {
+                Some(meters) if units == UnitSystem::Metric => {
+                    table.push_str(&format!(" {meters} m |"));
+                }
+                Some(meters) => table.push_str(&format!(
+                    " {} |",
+                    Locale::English.distance(f64::from(meters), units)
+                )),
+                None => table.push_str(" – |"),
+            }
+        }
+        match station.reliability_score {
+            Some(score) => table.push_str(&format!(" {:.0}% |\n", score * 100.0)),
+            None => table.push_str(" – |\n"),
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(code: &str, distance_meters: Option<u32>) -> StationComparison {
+        StationComparison {
+            station_code: code.to_string(),
+            name: "Gare de Lyon | Diderot".to_string(),
+            status: Some(StationStatus::Open),
+            mechanical_bikes: 5,
+            electric_bikes: 3,
+            available_docks: 10,
+            capacity: 30,
+            distance_meters,
+            walk_time_minutes: None,
+            reliability_score: Some(0.75),
+            reliability_samples: 4,
+        }
+    }
+
+    #[test]
+    fn test_reliability_counts_empty_and_full_samples_against() {
+        assert_eq!(reliability_score(&[0, 5, 10, 30], 30), Some(0.5));
+        assert_eq!(reliability_score(&[], 30), None);
+    }
+
+    #[test]
+    fn test_markdown_table_rows() {
+        let table = markdown_table(
+            &[row("12001", Some(120)), row("12002", None)],
+            UnitSystem::Metric,
+        );
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "| Station | Status | Mechanical | Electric | Docks | Distance | Reliability |"
+        );
+        assert_eq!(
+            lines[2],
+            "| Gare de Lyon / Diderot (12001) | open | 5 | 3 | 10/30 | 120 m | 75% |"
+        );
+        assert!(lines[3].ends_with("| – | 75% |"));
+
+        let without_distance = markdown_table(&[row("12001", None)], UnitSystem::Metric);
+        assert!(!without_distance.contains("Distance"));
+
+        let imperial = markdown_table(&[row("12001", Some(120))], UnitSystem::Imperial);
+        assert!(imperial.contains("| 394 ft |"));
+    }
+}
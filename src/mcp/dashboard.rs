@@ -0,0 +1,8 @@
+use axum::response::Html;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// `/dashboard`: a live map of every station, colored by bike availability
+pub(crate) async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
@@ -0,0 +1,192 @@
+use super::meta::DataStamp;
+use crate::data::cache::InMemoryCache;
+use chrono::Duration;
+use serde_json::Value;
+
+/// Default lifetime of a cached tool response
+pub const DEFAULT_DEDUP_TTL_SECONDS: u64 = 5;
+
+// Tools whose result only depends on their arguments and the shared data;
+// session-scoped and mutating tools always run
+const DEDUPLICATED_TOOLS: &[&str] = &[
+    "find_nearby_stations",
+    "get_station_by_code",
+    "search_stations_by_name",
+    "autocomplete_station_names",
+    "render_station_sparkline",
+    "get_area_statistics",
+    "get_station_density",
+    "find_stations_along_route",
+    "compare_stations",
+    "check_dropoff_feasibility",
+    "compare_with_typical",
+    "diff_station_status",
+    "get_system_overview",
+    "get_fleet_composition",
+    "plan_bike_journey",
+    "plan_multiple_journeys",
+];
+
+/// How a tool call was answered, reported as `_meta.cache` in its result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// The tool is never deduplicated, or deduplication is off
+    Bypass,
+}
+
+impl CacheStatus {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Bypass => "bypass",
+        }
+    }
+}
+
+/// Short-lived cache answering repeated identical tool calls
+///
+/// Agents retrying or fanning out often send the exact same call within
+/// seconds; those share one computed result, and the data it was computed from.
+#[derive(Debug)]
+pub struct ResponseCache {
+    entries: Option<InMemoryCache<String, (Value, DataStamp)>>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(std::time::Duration::from_secs(DEFAULT_DEDUP_TTL_SECONDS))
+    }
+}
+
+impl ResponseCache {
+    /// A zero `ttl` disables deduplication
+    #[must_use]
+    pub fn new(ttl: std::time::Duration) -> Self {
+        let entries = (!ttl.is_zero()).then(|| {
+            InMemoryCache::new(Duration::from_std(ttl).unwrap_or_else(|_| Duration::zero()))
+        });
+        Self { entries }
+    }
+
+    /// Cache key for a call, or `None` if the call must not be deduplicated
+    #[must_use]
+    pub fn key(&self, tool_name: &str, arguments: &Value) -> Option<String> {
+        if self.entries.is_none() || !DEDUPLICATED_TOOLS.contains(&tool_name) {
+            return None;
+        }
+        let mut key = format!("{tool_name}:");
+        write_canonical(arguments, &mut key);
+        Some(key)
+    }
+
+    pub async fn get(&self, key: &str) -> Option<(Value, DataStamp)> {
+        self.entries.as_ref()?.get(&key.to_string()).await
+    }
+
+    pub async fn insert(&self, key: String, result: Value, data: DataStamp) {
+        if let Some(entries) = &self.entries {
+            entries.cleanup_expired().await;
+            entries.insert(key, (result, data)).await;
+        }
+    }
+}
+
+/// Serialize with object keys sorted, so argument order doesn't matter
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Report `status` in the result's `_meta` block
+#[must_use]
+pub fn with_cache_status(mut result: Value, status: CacheStatus) -> Value {
+    super::meta::insert(&mut result, "cache", Value::from(status.as_str()));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let cache = ResponseCache::default();
+        let a = cache.key(
+            "find_nearby_stations",
+            &json!({"latitude": 48.85, "longitude": 2.35, "availability_filter": {"min_bikes": 1, "bike_type": "any"}}),
+        );
+        let b = cache.key(
+            "find_nearby_stations",
+            &json!({"availability_filter": {"bike_type": "any", "min_bikes": 1}, "longitude": 2.35, "latitude": 48.85}),
+        );
+        assert!(a.is_some());
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            cache.key(
+                "find_nearby_stations",
+                &json!({"latitude": 48.85, "longitude": 2.36})
+            )
+        );
+
+        assert!(cache.key("add_favorite_station", &json!({})).is_none());
+        assert!(ResponseCache::new(std::time::Duration::ZERO)
+            .key("find_nearby_stations", &json!({}))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_results_carry_their_status() {
+        let cache = ResponseCache::default();
+        let key = cache
+            .key("get_station_by_code", &json!({"station_code": "4001"}))
+            .unwrap();
+        assert!(cache.get(&key).await.is_none());
+
+        let data = DataStamp {
+            version: Some(crate::data::DataVersion {
+                version: 3,
+                hash: "00c0ffee00c0ffee".to_string(),
+            }),
+            snapshot_at: Some(chrono::Utc::now()),
+        };
+        cache
+            .insert(key.clone(), json!({"content": []}), data.clone())
+            .await;
+        let (cached, cached_data) = cache.get(&key).await.unwrap();
+        assert_eq!(cached_data, data);
+        let hit = with_cache_status(cached, CacheStatus::Hit);
+        assert_eq!(hit["_meta"]["cache"], "hit");
+        assert_eq!(hit["content"], json!([]));
+    }
+}
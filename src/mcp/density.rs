@@ -0,0 +1,328 @@
+use crate::mcp::types::{CoverageGap, CoverageGapArea, GeographicBounds};
+use crate::types::Coordinates;
+
+/// Default distance beyond which a grid cell counts as uncovered
+pub const DEFAULT_GAP_DISTANCE_METERS: u32 = 300;
+
+// Cells are widened past the requested size to keep the grid this small
+const MAX_GRID_CELLS: usize = 10_000;
+
+/// Width and height of `bounds` in meters, measured through its middle
+#[must_use]
+pub fn dimensions_meters(bounds: &GeographicBounds) -> (f64, f64) {
+    let middle_latitude = (bounds.north + bounds.south) / 2.0;
+    let middle_longitude = (bounds.east + bounds.west) / 2.0;
+    let width = Coordinates::new(middle_latitude, bounds.west)
+        .distance_to(&Coordinates::new(middle_latitude, bounds.east));
+    let height = Coordinates::new(bounds.south, middle_longitude)
+        .distance_to(&Coordinates::new(bounds.north, middle_longitude));
+    (width, height)
+}
+
+/// Surface of `bounds` in km²
+#[must_use]
+pub fn area_km2(bounds: &GeographicBounds) -> f64 {
+    let (width, height) = dimensions_meters(bounds);
+    width * height / 1_000_000.0
+}
+
+/// `bounds` grown by `meters` on every side
+#[must_use]
+pub fn widen(bounds: &GeographicBounds, meters: u32) -> GeographicBounds {
+    // On the sphere used by `Coordinates::distance_to`
+    const METERS_PER_DEGREE: f64 = 111_195.0;
+    let latitude_margin = f64::from(meters) / METERS_PER_DEGREE;
+    let longitude_margin = latitude_margin
+        / bounds
+            .north
+            .abs()
+            .max(bounds.south.abs())
+            .to_radians()
+            .cos();
+    GeographicBounds {
+        north: bounds.north + latitude_margin,
+        south: bounds.south - latitude_margin,
+        east: bounds.east + longitude_margin,
+        west: bounds.west - longitude_margin,
+    }
+}
+
+/// A grid laid over an area and how many of its cells have a station nearby
+#[derive(Debug, Clone)]
+pub struct CoverageGrid {
+    pub cell_size_meters: u32,
+    pub cells: usize,
+    /// Cells whose center is farther than the gap distance from every station,
+    /// farthest first
+    pub gaps: Vec<CoverageGap>,
+}
+
+/// Rows and columns of a grid of roughly `cell_size_meters` over some bounds
+struct GridLayout {
+    bounds: GeographicBounds,
+    columns: usize,
+    rows: usize,
+    cell_size: f64,
+}
+
+impl GridLayout {
+    fn new(bounds: &GeographicBounds, cell_size_meters: u32) -> Self {
+        let (width, height) = dimensions_meters(bounds);
+        let mut cell_size = f64::from(cell_size_meters.max(1));
+        let cells_for = |size: f64| {
+            (
+                (width / size).ceil().max(1.0),
+                (height / size).ceil().max(1.0),
+            )
+        };
+        let (mut columns, mut rows) = cells_for(cell_size);
+        while columns * rows > MAX_GRID_CELLS as f64 {
+            cell_size *= (columns * rows / MAX_GRID_CELLS as f64).sqrt().max(1.05);
+            (columns, rows) = cells_for(cell_size);
+        }
+        Self {
+            bounds: bounds.clone(),
+            columns: columns as usize,
+            rows: rows as usize,
+            cell_size,
+        }
+    }
+
+    fn center(&self, row: usize, column: usize) -> Coordinates {
+        let latitude_step = (self.bounds.north - self.bounds.south) / self.rows as f64;
+        let longitude_step = (self.bounds.east - self.bounds.west) / self.columns as f64;
+        Coordinates::new(
+            self.bounds.south + latitude_step * (row as f64 + 0.5),
+            self.bounds.west + longitude_step * (column as f64 + 0.5),
+        )
+    }
+
+    fn cell_km2(&self) -> f64 {
+        area_km2(&self.bounds) / (self.rows * self.columns) as f64
+    }
+}
+
+fn nearest_station(point: &Coordinates, stations: &[Coordinates]) -> f64 {
+    stations
+        .iter()
+        .map(|station| point.distance_to(station))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Lay a grid of roughly `cell_size_meters` over `bounds` and find the cells
+/// with no station within `gap_distance_meters` of their center
+#[must_use]
+pub fn coverage_grid(
+    bounds: &GeographicBounds,
+    stations: &[Coordinates],
+    cell_size_meters: u32,
+    gap_distance_meters: u32,
+) -> CoverageGrid {
+    let grid = GridLayout::new(bounds, cell_size_meters);
+    let gap_distance = f64::from(gap_distance_meters);
+
+    let mut gaps = Vec::new();
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let center = grid.center(row, column);
+            let nearest = nearest_station(&center, stations);
+            if nearest > gap_distance {
+                gaps.push(CoverageGap {
+                    center,
+                    nearest_station_meters: nearest.is_finite().then_some(nearest as u32),
+                });
+            }
+        }
+    }
+    gaps.sort_by(|a, b| {
+        b.nearest_station_meters
+            .unwrap_or(u32::MAX)
+            .cmp(&a.nearest_station_meters.unwrap_or(u32::MAX))
+    });
+
+    CoverageGrid {
+        cell_size_meters: grid.cell_size.round() as u32,
+        cells: grid.columns * grid.rows,
+        gaps,
+    }
+}
+
+/// Uncovered areas found by [`gap_areas`]
+#[derive(Debug, Clone)]
+pub struct GapAnalysis {
+    pub cell_size_meters: u32,
+    /// Grid cells whose center lies in the analyzed area
+    pub cells: usize,
+    /// Largest first
+    pub gaps: Vec<CoverageGapArea>,
+}
+
+/// Group the grid cells over `bounds` lying `within` the analyzed area and
+/// farther than `gap_distance_meters` from every station into connected areas
+///
+/// Cells touching by a side or a corner belong to the same gap.
+#[must_use]
+pub fn gap_areas(
+    bounds: &GeographicBounds,
+    stations: &[Coordinates],
+    cell_size_meters: u32,
+    gap_distance_meters: u32,
+    within: impl Fn(&Coordinates) -> bool,
+) -> GapAnalysis {
+    let grid = GridLayout::new(bounds, cell_size_meters);
+    let gap_distance = f64::from(gap_distance_meters);
+
+    let mut cells = 0;
+    // Distance to the nearest station of each uncovered cell
+    let mut uncovered: Vec<Option<f64>> = vec![None; grid.rows * grid.columns];
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let center = grid.center(row, column);
+            if !within(&center) {
+                continue;
+            }
+            cells += 1;
+            let nearest = nearest_station(&center, stations);
+            if nearest > gap_distance {
+                uncovered[row * grid.columns + column] = Some(nearest);
+            }
+        }
+    }
+
+    let cell_km2 = grid.cell_km2();
+    let mut gaps = Vec::new();
+    for start in 0..uncovered.len() {
+        if uncovered[start].is_none() {
+            continue;
+        }
+        let mut pending = vec![start];
+        let mut members = Vec::new();
+        let mut farthest = 0.0_f64;
+        while let Some(cell) = pending.pop() {
+            let Some(nearest) = uncovered[cell].take() else {
+                continue;
+            };
+            farthest = farthest.max(nearest);
+            let (row, column) = (cell / grid.columns, cell % grid.columns);
+            members.push(grid.center(row, column));
+            for neighbor_row in row.saturating_sub(1)..=(row + 1).min(grid.rows - 1) {
+                for neighbor_column in column.saturating_sub(1)..=(column + 1).min(grid.columns - 1)
+                {
+                    let neighbor = neighbor_row * grid.columns + neighbor_column;
+                    if uncovered[neighbor].is_some() {
+                        pending.push(neighbor);
+                    }
+                }
+            }
+        }
+        let count = members.len() as f64;
+        gaps.push(CoverageGapArea {
+            centroid: Coordinates::new(
+                members.iter().map(|c| c.latitude).sum::<f64>() / count,
+                members.iter().map(|c| c.longitude).sum::<f64>() / count,
+            ),
+            area_km2: count * cell_km2,
+            cells: members.len() as u32,
+            nearest_station_meters: farthest.is_finite().then_some(farthest as u32),
+        });
+    }
+    gaps.sort_by(|a, b| b.area_km2.total_cmp(&a.area_km2));
+
+    GapAnalysis {
+        cell_size_meters: grid.cell_size.round() as u32,
+        cells,
+        gaps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // About 1.1km east-west and 1.1km north-south around Gare de Lyon
+    fn gare_de_lyon() -> GeographicBounds {
+        GeographicBounds {
+            north: 48.850,
+            south: 48.840,
+            east: 2.3800,
+            west: 2.3650,
+        }
+    }
+
+    #[test]
+    fn test_area_of_bounds() {
+        let area = area_km2(&gare_de_lyon());
+        assert!((1.1..1.3).contains(&area), "{area}");
+    }
+
+    #[test]
+    fn test_cells_far_from_stations_are_gaps() {
+        let stations = [Coordinates::new(48.8446, 2.3737)];
+        let grid = coverage_grid(&gare_de_lyon(), &stations, 300, 300);
+
+        assert_eq!(grid.cells, 16);
+        assert!(!grid.gaps.is_empty() && grid.gaps.len() < grid.cells);
+        let distances: Vec<u32> = grid
+            .gaps
+            .iter()
+            .map(|gap| gap.nearest_station_meters.unwrap())
+            .collect();
+        assert!(distances.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(distances.iter().all(|&meters| meters > 300));
+
+        let empty = coverage_grid(&gare_de_lyon(), &[], 300, 300);
+        assert_eq!(empty.gaps.len(), empty.cells);
+        assert!(empty.gaps[0].nearest_station_meters.is_none());
+    }
+
+    #[test]
+    fn test_uncovered_cells_group_into_areas() {
+        // A line of stations down the middle leaves a strip uncovered on each side
+        let stations: Vec<Coordinates> = (0..=10)
+            .map(|step| Coordinates::new(48.840 + 0.001 * f64::from(step), 2.3725))
+            .collect();
+        let analysis = gap_areas(&gare_de_lyon(), &stations, 50, 300, |_| true);
+
+        assert_eq!(analysis.gaps.len(), 2);
+        let (west, east) = if analysis.gaps[0].centroid.longitude < 2.3725 {
+            (&analysis.gaps[0], &analysis.gaps[1])
+        } else {
+            (&analysis.gaps[1], &analysis.gaps[0])
+        };
+        assert!(west.centroid.longitude < 2.368 && east.centroid.longitude > 2.377);
+        for gap in [west, east] {
+            assert!((0.2..0.35).contains(&gap.area_km2), "{}", gap.area_km2);
+            assert!(gap.nearest_station_meters.unwrap() > 500);
+        }
+
+        // Only the western half analyzed
+        let half = gap_areas(&gare_de_lyon(), &stations, 50, 300, |center| {
+            center.longitude < 2.3725
+        });
+        assert_eq!(half.gaps.len(), 1);
+        assert!(half.cells < analysis.cells);
+    }
+
+    #[test]
+    fn test_widen_by_meters() {
+        let bounds = gare_de_lyon();
+        let wider = widen(&bounds, 300);
+        let (width, height) = dimensions_meters(&bounds);
+        let (wider_width, wider_height) = dimensions_meters(&wider);
+        assert!((wider_height - height - 600.0).abs() < 5.0);
+        assert!(wider_width - width >= 600.0);
+    }
+
+    #[test]
+    fn test_grid_size_is_capped() {
+        let paris = GeographicBounds {
+            north: 48.91,
+            south: 48.81,
+            east: 2.42,
+            west: 2.25,
+        };
+        let grid = coverage_grid(&paris, &[], 10, 300);
+        assert!(grid.cells <= MAX_GRID_CELLS);
+        assert!(grid.cell_size_meters > 100);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::mcp::session::UserPreferences;
+use crate::mcp::types::{FavoriteStationStatus, StationWithDistance};
+use crate::types::Coordinates;
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// Which leg of the commute a digest is prepared for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommuteProfile {
+    /// Leaving home: best pickup is searched around `home`
+    Morning,
+    /// Leaving work: best pickup is searched around `work`
+    Evening,
+}
+
+impl CommuteProfile {
+    /// Where the commuter picks up a bike for this leg
+    #[must_use]
+    pub fn origin(&self, preferences: &UserPreferences) -> Option<Coordinates> {
+        match self {
+            CommuteProfile::Morning => preferences.home,
+            CommuteProfile::Evening => preferences.work,
+        }
+    }
+}
+
+impl FromStr for CommuteProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "morning" => Ok(CommuteProfile::Morning),
+            "evening" => Ok(CommuteProfile::Evening),
+            other => Err(Error::Validation(format!(
+                "Unknown commute profile '{other}', expected 'morning' or 'evening'"
+            ))),
+        }
+    }
+}
+
+/// Compact commute summary meant to be fetched on a schedule and pasted into a chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommuteDigest {
+    pub profile: CommuteProfile,
+    pub generated_at: DateTime<Utc>,
+    /// Pickup search center, if the profile's location is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Coordinates>,
+    pub favorites: Vec<FavoriteStationStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_pickup: Option<StationWithDistance>,
+}
+
+impl CommuteDigest {
+    /// Render the digest as a few short plain-text lines
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let mut text = String::new();
+        let title = match self.profile {
+            CommuteProfile::Morning => "Morning commute",
+            CommuteProfile::Evening => "Evening commute",
+        };
+        let _ = writeln!(text, "{title} ({})", self.generated_at.format("%H:%M UTC"));
+
+        match &self.best_pickup {
+            _ if self.origin.is_none() => {
+                let _ = writeln!(
+                    text,
+                    "Best pickup: set home/work with set_preferences to get a suggestion"
+                );
+            }
+            Some(pickup) => {
+                let bikes = pickup
+                    .station
+                    .real_time
+                    .as_ref()
+                    .map(|rt| rt.bikes.total())
+                    .unwrap_or(0);
+                let _ = writeln!(
+                    text,
+                    "Best pickup: {} ({} bikes, {} m away)",
+                    pickup.station.reference.name, bikes, pickup.distance_meters
+                );
+            }
+            None => {
+                let _ = writeln!(text, "Best pickup: none available nearby");
+            }
+        }
+
+        for status in &self.favorites {
+            let name = status.favorite.label.clone().unwrap_or_else(|| {
+                status
+                    .station
+                    .as_ref()
+                    .map(|station| station.reference.name.to_string())
+                    .unwrap_or_else(|| status.favorite.station_code.clone())
+            });
+            let line = match status.station.as_ref().and_then(|s| s.real_time.as_ref()) {
+                Some(rt) => format!(
+                    "{name}: {} mechanical, {} electric, {} docks",
+                    rt.bikes.mechanical, rt.bikes.electric, rt.available_docks
+                ),
+                None if status.found => format!("{name}: no live data"),
+                None => format!("{name}: station no longer exists"),
+            };
+            let _ = writeln!(text, "- {line}");
+        }
+
+        text.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::favorites::FavoriteStation;
+
+    #[test]
+    fn test_profile_parsing_and_origin() {
+        let preferences = UserPreferences {
+            home: Some(Coordinates::new(48.85, 2.35)),
+            ..Default::default()
+        };
+
+        let morning: CommuteProfile = "morning".parse().unwrap();
+        assert_eq!(morning.origin(&preferences), preferences.home);
+        assert!("evening"
+            .parse::<CommuteProfile>()
+            .unwrap()
+            .origin(&preferences)
+            .is_none());
+        assert!("lunch".parse::<CommuteProfile>().is_err());
+    }
+
+    #[test]
+    fn test_summary_without_live_data() {
+        let digest = CommuteDigest {
+            profile: CommuteProfile::Evening,
+            generated_at: Utc::now(),
+            origin: Some(Coordinates::new(48.87, 2.33)),
+            favorites: vec![FavoriteStationStatus {
+                favorite: FavoriteStation {
+                    station_code: "16107".to_string(),
+                    label: Some("Office".to_string()),
+                    added_at: Utc::now(),
+                },
+                station: None,
+                found: false,
+            }],
+            best_pickup: None,
+        };
+
+        let summary = digest.summary();
+        assert!(summary.starts_with("Evening commute"));
+        assert!(summary.contains("Best pickup: none available nearby"));
+        assert!(summary.ends_with("- Office: station no longer exists"));
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Furthest arrival time a drop-off can be checked for, in minutes from now
+pub const MAX_ARRIVAL_MINUTES: i64 = 120;
+
+/// Recent history the docking rate is measured over
+pub const RATE_WINDOW_MINUTES: i64 = 30;
+
+/// A sample this close to the same time the previous day gives the usual level
+pub const PATTERN_TOLERANCE_MINUTES: i64 = 15;
+
+// Shortest span of samples a rate is measured over
+const MIN_RATE_SPAN_MINUTES: i64 = 5;
+
+/// What is known about a station's docks when estimating a drop-off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockSignals {
+    pub free_docks: u16,
+    pub capacity: u16,
+    /// Change in free docks per hour over the last `RATE_WINDOW_MINUTES`
+    pub docks_change_per_hour: Option<f64>,
+    /// Free docks around the same time of day, the previous day
+    pub usual_free_docks: Option<u16>,
+}
+
+/// Change in free docks per hour between the first and last `(time, bikes)` samples
+///
+/// Docks are estimated from capacity, so a bike taken frees a dock. `None` when
+/// the samples span less than 5 minutes.
+#[must_use]
+pub fn docks_change_per_hour(samples: &[(DateTime<Utc>, u16)]) -> Option<f64> {
+    let (first_at, first_bikes) = samples.first()?;
+    let (last_at, last_bikes) = samples.last()?;
+    let span = *last_at - *first_at;
+    if span < Duration::minutes(MIN_RATE_SPAN_MINUTES) {
+        return None;
+    }
+    let hours = span.num_seconds() as f64 / 3600.0;
+    Some((f64::from(*first_bikes) - f64::from(*last_bikes)) / hours)
+}
+
+/// Free docks in the sample closest to `at`, if one is within `PATTERN_TOLERANCE_MINUTES`
+#[must_use]
+pub fn usual_free_docks(
+    samples: &[(DateTime<Utc>, u16)],
+    at: DateTime<Utc>,
+    capacity: u16,
+) -> Option<u16> {
+    samples
+        .iter()
+        .filter(|(observed_at, _)| {
+            (*observed_at - at).abs() <= Duration::minutes(PATTERN_TOLERANCE_MINUTES)
+        })
+        .min_by_key(|(observed_at, _)| (*observed_at - at).abs())
+        .map(|(_, bikes)| capacity.saturating_sub(*bikes))
+}
+
+/// Free docks expected `minutes_ahead` from now
+///
+/// The current level follows the recent rate, and leans towards the usual
+/// level, when known, the further ahead the estimate is (at most halfway, an
+/// hour or more ahead).
+#[must_use]
+pub fn expected_free_docks(signals: &DockSignals, minutes_ahead: f64) -> f64 {
+    let capacity = f64::from(signals.capacity.max(signals.free_docks));
+    let rate = signals.docks_change_per_hour.unwrap_or(0.0);
+    let projected =
+        (f64::from(signals.free_docks) + rate * minutes_ahead / 60.0).clamp(0.0, capacity);
+    match signals.usual_free_docks {
+        Some(usual) => {
+            let weight = (minutes_ahead / 60.0).min(1.0) * 0.5;
+            projected * (1.0 - weight) + f64::from(usual) * weight
+        }
+        None => projected,
+    }
+}
+
+/// Chance at least one dock is free `minutes_ahead` from now
+///
+/// The expected level is taken as the center of a spread that widens with the
+/// horizon, and more so without a recent rate to go by.
+#[must_use]
+pub fn dock_probability(signals: &DockSignals, minutes_ahead: f64) -> f64 {
+    let expected = expected_free_docks(signals, minutes_ahead);
+    let mut spread = 1.0 + 0.4 * minutes_ahead.sqrt();
+    if signals.docks_change_per_hour.is_none() && minutes_ahead > 0.0 {
+        spread += 1.0;
+    }
+    // Logistic approximation of the normal distribution
+    let z = (expected - 0.5) / spread;
+    1.0 / (1.0 + (-1.702 * z).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals(free_docks: u16, rate: Option<f64>, usual: Option<u16>) -> DockSignals {
+        DockSignals {
+            free_docks,
+            capacity: 20,
+            docks_change_per_hour: rate,
+            usual_free_docks: usual,
+        }
+    }
+
+    #[test]
+    fn test_rate_and_usual_level_from_history() {
+        let start = Utc::now();
+        let samples = [
+            (start, 10),
+            (start + Duration::minutes(10), 12),
+            (start + Duration::minutes(30), 16),
+        ];
+        // 6 more bikes in half an hour: 12 fewer free docks per hour
+        assert_eq!(docks_change_per_hour(&samples), Some(-12.0));
+        assert_eq!(docks_change_per_hour(&samples[..1]), None);
+
+        let at = start + Duration::minutes(12);
+        assert_eq!(usual_free_docks(&samples, at, 20), Some(8));
+        assert_eq!(
+            usual_free_docks(&samples, start + Duration::hours(2), 20),
+            None
+        );
+    }
+
+    #[test]
+    fn test_probability_follows_trend_and_pattern() {
+        let now = dock_probability(&signals(6, None, None), 0.0);
+        assert!(now > 0.95, "{now}");
+        assert!(dock_probability(&signals(0, None, None), 0.0) < 0.5);
+
+        // Filling at 12 bikes an hour leaves no dock after half an hour
+        let filling = signals(6, Some(-12.0), None);
+        assert_eq!(expected_free_docks(&filling, 30.0), 0.0);
+        assert!(dock_probability(&filling, 30.0) < 0.5);
+
+        // A station usually full at that time is less likely to have room
+        let stable = dock_probability(&signals(6, Some(0.0), None), 60.0);
+        let usually_full = dock_probability(&signals(6, Some(0.0), Some(0)), 60.0);
+        assert_eq!(
+            expected_free_docks(&signals(6, Some(0.0), Some(0)), 60.0),
+            3.0
+        );
+        assert!(usually_full < stable);
+    }
+}
@@ -0,0 +1,136 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+/// Media type of MessagePack bodies
+pub const MSGPACK: &str = "application/msgpack";
+
+// Older names still sent by some MessagePack libraries
+const MSGPACK_ALIASES: &[&str] = &[MSGPACK, "application/x-msgpack", "application/vnd.msgpack"];
+
+/// How a resource body is encoded on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// Pick MessagePack when `Accept` prefers it at least as much as JSON
+    #[must_use]
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Self::Json;
+        };
+
+        let (mut msgpack, mut json) = (0.0_f32, 0.0_f32);
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if MSGPACK_ALIASES.contains(&media_type.as_str()) {
+                msgpack = msgpack.max(quality);
+            } else if media_type == "application/json" {
+                json = json.max(quality);
+            }
+        }
+
+        if msgpack > 0.0 && msgpack >= json {
+            Self::MessagePack
+        } else {
+            Self::Json
+        }
+    }
+
+    /// Decide from a response `Content-Type` how to decode its body
+    #[must_use]
+    pub fn of_content_type(content_type: Option<&str>) -> Self {
+        let media_type = content_type
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase());
+        match media_type {
+            Some(media_type) if MSGPACK_ALIASES.contains(&media_type.as_str()) => Self::MessagePack,
+            _ => Self::Json,
+        }
+    }
+
+    /// Serialize `body` as a response in this encoding
+    pub fn respond<T: Serialize>(self, body: &T) -> Response {
+        let mut response = match self {
+            Self::Json => Json(body).into_response(),
+            // Named fields keep the maps self-describing, like the JSON
+            Self::MessagePack => match rmp_serde::to_vec_named(body) {
+                Ok(bytes) => (
+                    [(header::CONTENT_TYPE, HeaderValue::from_static(MSGPACK))],
+                    bytes,
+                )
+                    .into_response(),
+                Err(e) => {
+                    error!("Failed to encode MessagePack response: {}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            },
+        };
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("accept"));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_negotiation() {
+        assert_eq!(Encoding::negotiate(&HeaderMap::new()), Encoding::Json);
+        assert_eq!(Encoding::negotiate(&accept("*/*")), Encoding::Json);
+        assert_eq!(
+            Encoding::negotiate(&accept("application/msgpack")),
+            Encoding::MessagePack
+        );
+        assert_eq!(
+            Encoding::negotiate(&accept("application/json, application/x-msgpack;q=0.5")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::negotiate(&accept("application/json;q=0.8, application/vnd.msgpack")),
+            Encoding::MessagePack
+        );
+        assert_eq!(
+            Encoding::negotiate(&accept("application/msgpack;q=0")),
+            Encoding::Json
+        );
+    }
+
+    #[test]
+    fn test_content_type_detection() {
+        assert_eq!(Encoding::of_content_type(None), Encoding::Json);
+        assert_eq!(
+            Encoding::of_content_type(Some("application/json")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::of_content_type(Some("Application/MsgPack; charset=binary")),
+            Encoding::MessagePack
+        );
+    }
+}
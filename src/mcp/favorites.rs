@@ -0,0 +1,276 @@
+use super::auth::{is_key_id, key_id};
+use crate::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+const MAX_FAVORITES_PER_SESSION: usize = 20;
+
+/// Sessions whose favorites are kept at most; past it the one unused the
+/// longest is forgotten
+pub const MAX_FAVORITE_SESSIONS: usize = 10_000;
+
+/// Favorites unused for this many days are forgotten by [`FavoritesStore::sweep`]
+pub const FAVORITES_IDLE_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FavoriteStation {
+    pub station_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// The favorites of one session and when it last used them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionFavorites {
+    last_used: DateTime<Utc>,
+    stations: Vec<FavoriteStation>,
+}
+
+/// What files hold per session, including those written before sessions were
+/// hashed and dated
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedFavorites {
+    Dated(SessionFavorites),
+    Undated(Vec<FavoriteStation>),
+}
+
+/// Favorite stations keyed by session, optionally persisted to a JSON file
+///
+/// Sessions are stored as their [`key_id`], so the file holds no bearer keys.
+/// At most [`MAX_FAVORITE_SESSIONS`] are kept, and [`Self::sweep`] forgets
+/// unused ones.
+#[derive(Debug, Default)]
+pub struct FavoritesStore {
+    path: Option<PathBuf>,
+    favorites: RwLock<HashMap<String, SessionFavorites>>,
+}
+
+impl FavoritesStore {
+    /// A store that only lives as long as the process
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load favorites from `path`, which is created on first write if missing
+    pub fn load(path: &Path) -> Result<Self> {
+        let saved: HashMap<String, SavedFavorites> = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(Error::Config(format!(
+                    "Cannot read favorites file {}: {e}",
+                    path.to_string_lossy()
+                )))
+            }
+        };
+
+        let now = Utc::now();
+        let favorites = saved
+            .into_iter()
+            .map(|(session, saved)| {
+                let id = if is_key_id(&session) {
+                    session
+                } else {
+                    key_id(&session)
+                };
+                let favorites = match saved {
+                    SavedFavorites::Dated(favorites) => favorites,
+                    SavedFavorites::Undated(stations) => SessionFavorites {
+                        last_used: now,
+                        stations,
+                    },
+                };
+                (id, favorites)
+            })
+            .collect();
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            favorites: RwLock::new(favorites),
+        })
+    }
+
+    /// Add (or relabel) a favorite and return the session's favorites
+    pub async fn add(
+        &self,
+        session: &str,
+        station_code: &str,
+        label: Option<String>,
+    ) -> Result<Vec<FavoriteStation>> {
+        let id = key_id(session);
+        let now = Utc::now();
+        let mut favorites = self.favorites.write().await;
+        if !favorites.contains_key(&id) && favorites.len() >= MAX_FAVORITE_SESSIONS {
+            if let Some(unused) = favorites
+                .iter()
+                .min_by_key(|(_, favorites)| favorites.last_used)
+                .map(|(id, _)| id.clone())
+            {
+                favorites.remove(&unused);
+            }
+        }
+        let stored = favorites.entry(id).or_insert_with(|| SessionFavorites {
+            last_used: now,
+            stations: Vec::new(),
+        });
+        stored.last_used = now;
+        let entries = &mut stored.stations;
+
+        if let Some(existing) = entries
+            .iter_mut()
+            .find(|favorite| favorite.station_code == station_code)
+        {
+            existing.label = label;
+        } else {
+            if entries.len() >= MAX_FAVORITES_PER_SESSION {
+                return Err(Error::Validation(format!(
+                    "At most {MAX_FAVORITES_PER_SESSION} favorite stations per session"
+                )));
+            }
+            entries.push(FavoriteStation {
+                station_code: station_code.to_string(),
+                label,
+                added_at: now,
+            });
+        }
+
+        let entries = entries.clone();
+        self.persist(&favorites).await?;
+        Ok(entries)
+    }
+
+    pub async fn list(&self, session: &str) -> Vec<FavoriteStation> {
+        let mut favorites = self.favorites.write().await;
+        // Only saved with the next change, a restart at worst makes them look older
+        favorites
+            .get_mut(&key_id(session))
+            .map(|stored| {
+                stored.last_used = Utc::now();
+                stored.stations.clone()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forget the favorites of sessions that didn't use them for
+    /// [`FAVORITES_IDLE_DAYS`] before `now`; returns how many
+    pub async fn sweep(&self, now: DateTime<Utc>) -> Result<usize> {
+        let oldest = now - Duration::days(FAVORITES_IDLE_DAYS);
+        let mut favorites = self.favorites.write().await;
+        let before = favorites.len();
+        favorites.retain(|_, stored| stored.last_used >= oldest);
+        let swept = before - favorites.len();
+        if swept > 0 {
+            self.persist(&favorites).await?;
+        }
+        Ok(swept)
+    }
+
+    async fn persist(&self, favorites: &HashMap<String, SessionFavorites>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write to a sibling file first so a crash never leaves a truncated store
+        let contents = serde_json::to_vec_pretty(favorites)?;
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, contents)
+            .await
+            .map_err(|e| Error::Internal(e.into()))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .map_err(|e| Error::Internal(e.into()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_deduplicates_and_relabels() {
+        let store = FavoritesStore::in_memory();
+        store.add("a", "16107", None).await.unwrap();
+        let favorites = store
+            .add("a", "16107", Some("Home".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].label.as_deref(), Some("Home"));
+        assert!(store.list("b").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_favorites_survive_reload() {
+        let path =
+            std::env::temp_dir().join(format!("velib-favorites-{}.json", uuid::Uuid::new_v4()));
+
+        let store = FavoritesStore::load(&path).unwrap();
+        store
+            .add("key:commuter", "16107", Some("Work".to_string()))
+            .await
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("commuter"));
+
+        let reloaded = FavoritesStore::load(&path).unwrap();
+        let favorites = reloaded.list("key:commuter").await;
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].station_code, "16107");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sessions_saved_in_the_clear_are_hashed_on_load() {
+        let path =
+            std::env::temp_dir().join(format!("velib-favorites-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{"key:commuter": [{"station_code": "16107", "added_at": "2026-10-01T08:00:00Z"}]}"#,
+        )
+        .unwrap();
+
+        let store = FavoritesStore::load(&path).unwrap();
+        assert_eq!(store.list("key:commuter").await.len(), 1);
+        store.add("key:commuter", "12001", None).await.unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("commuter"));
+        assert!(contents.contains(&key_id("key:commuter")));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unused_favorites_are_swept() {
+        let store = FavoritesStore::in_memory();
+        store.add("a", "16107", None).await.unwrap();
+        let now = Utc::now();
+        assert_eq!(store.sweep(now).await.unwrap(), 0);
+        assert_eq!(
+            store
+                .sweep(now + Duration::days(FAVORITES_IDLE_DAYS + 1))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(store.list("a").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_favorites_limit() {
+        let store = FavoritesStore::in_memory();
+        for code in 0..MAX_FAVORITES_PER_SESSION {
+            store.add("a", &code.to_string(), None).await.unwrap();
+        }
+        assert!(store.add("a", "one-too-many", None).await.is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Code, Request, Response, Status};
+use tracing::info;
+
+use crate::mcp::types::{
+    self as tools, default_max_walk, default_min_results, default_radius, default_tool_limit,
+    BikeTypePreference, GeographicBounds, JourneyPreferences, StationWithDistance,
+};
+use crate::mcp::{McpServer, Principal};
+use crate::types::{AvailabilityLabel, BikeTypeFilter, Coordinates, StationStatus, VelibStation};
+use crate::Error;
+
+/// Types and service generated from `proto/velib.proto`
+pub mod proto {
+    tonic::include_proto!("velib.v1");
+}
+
+use proto::velib_server::{Velib, VelibServer};
+
+/// The station lookups of an [`McpServer`] as the `velib.v1.Velib` gRPC service
+///
+/// Calls share the server's handler, API keys and identity providers, quotas,
+/// time limits and usage statistics with `tools/call`. Keys and tokens go in
+/// the `authorization` metadata as `Bearer <key>`.
+#[derive(Clone)]
+pub struct VelibGrpc {
+    server: McpServer,
+}
+
+impl VelibGrpc {
+    #[must_use]
+    pub fn new(server: McpServer) -> Self {
+        Self { server }
+    }
+
+    #[must_use]
+    pub fn into_service(self) -> VelibServer<Self> {
+        VelibServer::new(self)
+    }
+
+    /// Who a call comes from, as the HTTP transport would establish it;
+    /// refuses calls no provider accepts, when the server has any
+    async fn caller<T>(&self, request: &Request<T>) -> Result<Option<Principal>, Status> {
+        self.server
+            .authenticate(bearer(request).as_deref())
+            .await
+            .map_err(|_| Status::unauthenticated("API key required"))
+    }
+}
+
+/// The bearer key in a call's `authorization` metadata
+fn bearer<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// Serve `server`'s station lookups over gRPC on `listener` until the process exits
+pub async fn serve(listener: TcpListener, server: McpServer) -> crate::Result<()> {
+    let addr = listener
+        .local_addr()
+        .map_err(|e| Error::Internal(e.into()))?;
+    info!("Serving gRPC on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(VelibGrpc::new(server).into_service())
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+        .map_err(|e| Error::Internal(e.into()))
+}
+
+#[tonic::async_trait]
+impl Velib for VelibGrpc {
+    async fn find_nearby_stations(
+        &self,
+        request: Request<proto::FindNearbyStationsRequest>,
+    ) -> Result<Response<proto::FindNearbyStationsResponse>, Status> {
+        let principal = self.caller(&request).await?;
+        let request = request.into_inner();
+        let input = tools::FindNearbyStationsInput {
+            latitude: request.latitude,
+            longitude: request.longitude,
+            radius_meters: request.radius_meters.unwrap_or_else(default_radius),
+            limit: request
+                .limit
+                .map_or_else(default_tool_limit, saturating_u16),
+            availability_filter: request.availability_filter.map(|filter| {
+                tools::AvailabilityFilter {
+                    min_bikes: filter.min_bikes.map(saturating_u16),
+                    min_docks: filter.min_docks.map(saturating_u16),
+                    bike_type: match filter.bike_type() {
+                        proto::BikeType::Any => None,
+                        bike_type => Some(bike_type.into()),
+                    },
+                    exclude_out_of_service: !filter.include_out_of_service,
+                    purpose: None,
+                }
+            }),
+            sort: Default::default(),
+            lenient: request.lenient,
+            district: request.district,
+            auto_expand: request.auto_expand,
+            barrier_aware: request.barrier_aware,
+            min_results: default_min_results(),
+            near_transit: None,
+            transit_radius_meters: None,
+        };
+        let output = self
+            .server
+            .run_typed(
+                principal.as_ref(),
+                "find_nearby_stations",
+                |handler| async move { handler.find_nearby_stations(input).await },
+            )
+            .await?;
+        Ok(Response::new(proto::FindNearbyStationsResponse {
+            stations: output.stations.iter().map(Into::into).collect(),
+            radius_meters: output.search_metadata.radius_meters,
+            total_found: output.search_metadata.total_found,
+            has_more: output.search_metadata.has_more,
+        }))
+    }
+
+    async fn get_station_by_code(
+        &self,
+        request: Request<proto::GetStationByCodeRequest>,
+    ) -> Result<Response<proto::GetStationByCodeResponse>, Status> {
+        let principal = self.caller(&request).await?;
+        let input = tools::GetStationByCodeInput {
+            station_code: request.into_inner().station_code,
+            include_real_time: true,
+        };
+        let output = self
+            .server
+            .run_typed(
+                principal.as_ref(),
+                "get_station_by_code",
+                |handler| async move { handler.get_station_by_code(input).await },
+            )
+            .await?;
+        Ok(Response::new(proto::GetStationByCodeResponse {
+            station: output.station.as_ref().map(Into::into),
+            found: output.found,
+        }))
+    }
+
+    async fn search_stations_by_name(
+        &self,
+        request: Request<proto::SearchStationsByNameRequest>,
+    ) -> Result<Response<proto::SearchStationsByNameResponse>, Status> {
+        let principal = self.caller(&request).await?;
+        let request = request.into_inner();
+        let input = tools::SearchStationsByNameInput {
+            query: request.query,
+            limit: request
+                .limit
+                .map_or_else(default_tool_limit, saturating_u16),
+            fuzzy: request.fuzzy.unwrap_or(true),
+            sort: Default::default(),
+            lenient: request.lenient,
+            district: request.district,
+            near_transit: None,
+            transit_radius_meters: None,
+        };
+        let output = self
+            .server
+            .run_typed(
+                principal.as_ref(),
+                "search_stations_by_name",
+                |handler| async move { handler.search_stations_by_name(input).await },
+            )
+            .await?;
+        Ok(Response::new(proto::SearchStationsByNameResponse {
+            stations: output.stations.iter().map(Into::into).collect(),
+            total_found: output.search_metadata.total_found,
+        }))
+    }
+
+    async fn get_area_statistics(
+        &self,
+        request: Request<proto::GetAreaStatisticsRequest>,
+    ) -> Result<Response<proto::GetAreaStatisticsResponse>, Status> {
+        let principal = self.caller(&request).await?;
+        let request = request.into_inner();
+        let polygon = request
+            .polygon
+            .map(|polygon| serde_json::from_str(&polygon))
+            .transpose()
+            .map_err(|e| Status::invalid_argument(format!("Invalid GeoJSON polygon: {e}")))?;
+        let input = tools::GetAreaStatisticsInput {
+            bounds: request.bounds.map(Into::into),
+            polygon,
+            include_real_time: true,
+            window_minutes: None,
+        };
+        let output = self
+            .server
+            .run_typed(
+                principal.as_ref(),
+                "get_area_statistics",
+                |handler| async move { handler.get_area_statistics(input).await },
+            )
+            .await?;
+        Ok(Response::new(proto::GetAreaStatisticsResponse {
+            area_stats: Some((&output.area_stats).into()),
+            bounds: Some((&output.bounds).into()),
+            by_district: output
+                .by_district
+                .iter()
+                .map(|district| proto::DistrictStatistics {
+                    district: district.district.clone(),
+                    stats: Some((&district.stats).into()),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn plan_bike_journey(
+        &self,
+        request: Request<proto::PlanBikeJourneyRequest>,
+    ) -> Result<Response<proto::PlanBikeJourneyResponse>, Status> {
+        let principal = self.caller(&request).await?;
+        let request = request.into_inner();
+        let mut bike_types: Vec<BikeTypeFilter> = request.bike_types().map(Into::into).collect();
+        let preferences = (!bike_types.is_empty()
+            || request.max_walk_distance.is_some()
+            || request.barrier_aware)
+            .then(|| JourneyPreferences {
+                bike_type: if bike_types.len() > 1 {
+                    BikeTypePreference::Ordered(bike_types)
+                } else {
+                    bike_types
+                        .pop()
+                        .map_or_else(Default::default, BikeTypePreference::Single)
+                },
+                max_walk_distance: request.max_walk_distance.unwrap_or_else(default_max_walk),
+                barrier_aware: request.barrier_aware,
+                include_suspected_out_of_service: false,
+            });
+        let input = tools::PlanBikeJourneyInput {
+            origin: request
+                .origin
+                .map(Into::into)
+                .ok_or_else(|| Status::invalid_argument("origin is required"))?,
+            destination: request
+                .destination
+                .map(Into::into)
+                .ok_or_else(|| Status::invalid_argument("destination is required"))?,
+            preferences,
+            route_format: request
+                .include_route
+                .then_some(tools::RouteFormat::Polyline),
+        };
+        let output = self
+            .server
+            .run_typed(
+                principal.as_ref(),
+                "plan_bike_journey",
+                |handler| async move { handler.plan_bike_journey(input).await },
+            )
+            .await?;
+        let journey = output.journey;
+        Ok(Response::new(proto::PlanBikeJourneyResponse {
+            pickup_stations: journey.pickup_stations.iter().map(Into::into).collect(),
+            dropoff_stations: journey.dropoff_stations.iter().map(Into::into).collect(),
+            recommendations: journey
+                .recommendations
+                .iter()
+                .map(|recommendation| proto::JourneyRecommendation {
+                    pickup_station: Some((&recommendation.pickup_station).into()),
+                    dropoff_station: Some((&recommendation.dropoff_station).into()),
+                    walk_to_pickup: recommendation.walk_to_pickup,
+                    walk_to_pickup_minutes: recommendation.walk_to_pickup_minutes,
+                    walk_from_dropoff: recommendation.walk_from_dropoff,
+                    walk_from_dropoff_minutes: recommendation.walk_from_dropoff_minutes,
+                    confidence_score: recommendation.confidence_score,
+                    bike_type: proto::BikeType::from(&recommendation.bike_type).into(),
+                    preference_level: u32::try_from(recommendation.preference_level)
+                        .unwrap_or(u32::MAX),
+                    route_polyline: recommendation
+                        .route
+                        .as_ref()
+                        .and_then(|route| route.polyline.clone()),
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// Oversized values still reach the handler, which refuses or clamps them
+fn saturating_u16(value: u32) -> u16 {
+    u16::try_from(value).unwrap_or(u16::MAX)
+}
+
+impl From<Error> for Status {
+    fn from(error: Error) -> Self {
+        let code = match &error {
+            Error::InvalidCoordinates { .. }
+            | Error::OutsideServiceArea { .. }
+            | Error::OutsideServiceBoundary { .. }
+            | Error::SearchRadiusTooLarge { .. }
+            | Error::ResultLimitExceeded { .. }
+            | Error::Validation(_)
+            | Error::Json(_) => Code::InvalidArgument,
+            Error::StationNotFound { .. } | Error::ResourceNotFound { .. } => Code::NotFound,
+            Error::RateLimited { .. }
+            | Error::QuotaExceeded { .. }
+            | Error::UpstreamBudgetExhausted { .. } => Code::ResourceExhausted,
+            Error::UpstreamUnavailable { .. } => Code::Unavailable,
+            Error::Timeout { .. } => Code::DeadlineExceeded,
+            Error::ToolDisabled { .. } => Code::Unimplemented,
+            _ => Code::Internal,
+        };
+        Status::new(code, error.to_string())
+    }
+}
+
+impl From<proto::Coordinates> for Coordinates {
+    fn from(coordinates: proto::Coordinates) -> Self {
+        Coordinates::new(coordinates.latitude, coordinates.longitude)
+    }
+}
+
+impl From<Coordinates> for proto::Coordinates {
+    fn from(coordinates: Coordinates) -> Self {
+        Self {
+            latitude: coordinates.latitude,
+            longitude: coordinates.longitude,
+        }
+    }
+}
+
+impl From<proto::Bounds> for GeographicBounds {
+    fn from(bounds: proto::Bounds) -> Self {
+        Self {
+            north: bounds.north,
+            south: bounds.south,
+            east: bounds.east,
+            west: bounds.west,
+        }
+    }
+}
+
+impl From<&GeographicBounds> for proto::Bounds {
+    fn from(bounds: &GeographicBounds) -> Self {
+        Self {
+            north: bounds.north,
+            south: bounds.south,
+            east: bounds.east,
+            west: bounds.west,
+        }
+    }
+}
+
+impl From<proto::BikeType> for BikeTypeFilter {
+    fn from(bike_type: proto::BikeType) -> Self {
+        match bike_type {
+            proto::BikeType::Any => Self::AnyType,
+            proto::BikeType::Mechanical => Self::MechanicalOnly,
+            proto::BikeType::Electric => Self::ElectricOnly,
+        }
+    }
+}
+
+impl From<&BikeTypeFilter> for proto::BikeType {
+    fn from(bike_type: &BikeTypeFilter) -> Self {
+        match bike_type {
+            BikeTypeFilter::AnyType => Self::Any,
+            BikeTypeFilter::MechanicalOnly => Self::Mechanical,
+            BikeTypeFilter::ElectricOnly => Self::Electric,
+        }
+    }
+}
+
+impl From<&VelibStation> for proto::Station {
+    fn from(station: &VelibStation) -> Self {
+        let reference = &station.reference;
+        Self {
+            station_code: reference.station_code.to_string(),
+            name: reference.name.to_string(),
+            coordinates: Some(reference.coordinates.into()),
+            capacity: reference.capacity.into(),
+            district: reference.district.clone(),
+            commune: reference.commune.clone(),
+            real_time: station.real_time.as_ref().map(|real_time| {
+                let status = match real_time.status {
+                    StationStatus::Open => proto::StationStatus::Open,
+                    StationStatus::Closed => proto::StationStatus::Closed,
+                    StationStatus::Maintenance => proto::StationStatus::Maintenance,
+                };
+                let label = match real_time.availability_label {
+                    None => proto::AvailabilityLabel::Unspecified,
+                    Some(AvailabilityLabel::Empty) => proto::AvailabilityLabel::Empty,
+                    Some(AvailabilityLabel::Low) => proto::AvailabilityLabel::Low,
+                    Some(AvailabilityLabel::Balanced) => proto::AvailabilityLabel::Balanced,
+                    Some(AvailabilityLabel::NearFull) => proto::AvailabilityLabel::NearFull,
+                    Some(AvailabilityLabel::Full) => proto::AvailabilityLabel::Full,
+                };
+                proto::RealTimeStatus {
+                    mechanical_bikes: real_time.bikes.mechanical.into(),
+                    electric_bikes: real_time.bikes.electric.into(),
+                    available_docks: real_time.available_docks.into(),
+                    status: status.into(),
+                    last_update: real_time.last_update.map(|at| at.to_rfc3339()),
+                    availability_label: label.into(),
+                    is_renting: real_time.is_renting,
+                    is_returning: real_time.is_returning,
+                }
+            }),
+        }
+    }
+}
+
+impl From<&StationWithDistance> for proto::StationWithDistance {
+    fn from(station: &StationWithDistance) -> Self {
+        Self {
+            station: Some((&station.station).into()),
+            distance_meters: station.distance_meters,
+            walk_time_minutes: station.walk_time_minutes,
+        }
+    }
+}
+
+impl From<&tools::AreaStatistics> for proto::AreaStatistics {
+    fn from(stats: &tools::AreaStatistics) -> Self {
+        Self {
+            total_stations: stats.total_stations,
+            operational_stations: stats.operational_stations,
+            total_capacity: stats.total_capacity,
+            mechanical_bikes: stats.available_bikes.mechanical,
+            electric_bikes: stats.available_bikes.electric,
+            available_docks: stats.available_docks,
+            occupancy_rate: stats.occupancy_rate,
+        }
+    }
+}
@@ -1,28 +1,86 @@
-use crate::data::VelibDataClient;
+use crate::data::transit::DEFAULT_TRANSIT_RADIUS_METERS;
+use crate::data::{
+    district_key, AliasTable, BarrierMap, EnrichedStation, FleetSample, StationChange,
+    TransitNetwork, TransitSelection, VelibDataClient,
+};
+use crate::events::StationUpdate;
+use crate::geometry::{
+    encode_polyline, project_onto_route, route_length_meters, Geometry, LineString,
+};
+use crate::mcp::comparison;
+use crate::mcp::density;
+use crate::mcp::dropoff::{self, DockSignals};
+use crate::mcp::favorites::FavoriteStation;
+use crate::mcp::humanize::UnitSystem;
+use crate::mcp::sanitize::Sanitize;
+use crate::mcp::sorting::sort_stations;
+use crate::mcp::sparkline;
 use crate::mcp::types::{
-    AreaStatistics, AvailableBikesStats, BikeJourney, FindNearbyStationsInput,
-    FindNearbyStationsOutput, GetAreaStatisticsInput, GetAreaStatisticsOutput,
-    GetStationByCodeInput, GetStationByCodeOutput, JourneyPreferences, JourneyRecommendation,
-    PlanBikeJourneyInput, PlanBikeJourneyOutput, SearchMetadata, SearchStationsByNameInput,
-    SearchStationsByNameOutput, StationWithDistance, TextSearchMetadata,
+    AppliedLimits, AreaStatistics, AutocompleteStationNamesInput, AutocompleteStationNamesOutput,
+    AvailabilityFilter, AvailabilityWindow, AvailableBikesStats, BikeJourney, BikeRoute,
+    BikeTypePreference, CheckDropoffFeasibilityInput, CheckDropoffFeasibilityOutput,
+    CompareStationsInput, CompareStationsOutput, CompareWithTypicalInput, CompareWithTypicalOutput,
+    CoverageExtent, CoverageGapsReport, CoverageSummary, DiffStationStatusInput,
+    DiffStationStatusOutput, DistrictStatistics, DropoffEstimate, FavoriteStationStatus,
+    FindNearbyStationsInput, FindNearbyStationsOutput, FindStationsAlongRouteInput,
+    FindStationsAlongRouteOutput, FleetComposition, FleetCompositionPoint, GeographicBounds,
+    GetAreaStatisticsInput, GetAreaStatisticsOutput, GetFavoritesStatusInput,
+    GetFavoritesStatusOutput, GetFleetCompositionInput, GetFleetCompositionOutput,
+    GetStationByCodeInput, GetStationByCodeOutput, GetStationDensityInput, GetStationDensityOutput,
+    GetSystemOverviewOutput, JourneyPreferences, JourneyRecommendation, PlanBikeJourneyInput,
+    PlanBikeJourneyOutput, PlanMultipleJourneysInput, PlanMultipleJourneysOutput,
+    RenderStationSparklineInput, RenderStationSparklineOutput, RouteFormat, RouteSource,
+    SearchMetadata, SearchStationsByNameInput, SearchStationsByNameOutput, SharedStationUse,
+    SharedStationWarning, SparklineFormat, StationAlongRoute, StationComparison, StationPurpose,
+    StationStatusChange, StationWithDistance, TextSearchMetadata, TypicalLevel,
+};
+use crate::mcp::typical;
+use crate::routing::{RoutingBackend, DEFAULT_DETOUR_FACTOR};
+use crate::server::AppConfig;
+use crate::types::{
+    BikeAvailability, BikeTypeFilter, Coordinates, ServiceArea, VelibStation, WalkingSpeed,
 };
-use crate::types::{BikeTypeFilter, Coordinates, VelibStation};
 use crate::{Error, Result};
+use chrono::Timelike;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
 
 const MAX_SEARCH_RADIUS: u32 = 5000; // 5km
 const MAX_RESULT_LIMIT: u16 = 100;
-
-// Paris City Hall coordinates - reference point for service area validation
-const PARIS_CITY_HALL: Coordinates = Coordinates {
-    latitude: 48.8565,
-    longitude: 2.3514,
-};
-
+const MAX_SPARKLINE_HOURS: u16 = 24;
+const MAX_AVAILABILITY_WINDOW_MINUTES: u32 = 24 * 60;
+const MAX_COVERAGE_DISTANCE: u32 = 2000; // 2km
+const MAX_LISTED_GAPS: usize = 50;
+const MAX_ROUTE_BUFFER: u32 = 1000; // 1km
+const MAX_ROUTE_WAYPOINTS: usize = 500;
+const MAX_COMPARED_STATIONS: usize = 5;
+const MAX_DROPOFF_WALK: u32 = 2000; // 2km
+const MAX_HOLD_WINDOW_MINUTES: u16 = 60;
+const DROPOFF_ALTERNATIVES: usize = 3;
+const MAX_BULK_JOURNEYS: usize = 10;
+
+/// Tool implementations over the shared data client
+///
+/// Clones share the data client; each can carry its own freshness policy, see
+/// [`Self::with_max_data_age`].
+#[derive(Clone)]
 pub struct McpToolHandler {
     data_client: Arc<RwLock<VelibDataClient>>,
+    aliases: Arc<AliasTable>,
+    lenient_limits: bool,
+    max_data_age: chrono::Duration,
+    service_area: ServiceArea,
+    walking_speed: WalkingSpeed,
+    barriers: Arc<BarrierMap>,
+    transit: Arc<TransitNetwork>,
+    routing: Option<Arc<RoutingBackend>>,
+    detour_factor: f64,
+    coverage_gap_meters: u32,
+    // Shared with the data client, but readable without waiting for its lock
+    metrics: Arc<crate::metrics::Metrics>,
+    clock: Arc<dyn crate::clock::Clock>,
 }
 
 impl Default for McpToolHandler {
@@ -34,78 +92,313 @@ impl Default for McpToolHandler {
 impl McpToolHandler {
     #[must_use]
     pub fn new() -> Self {
-        Self {
-            data_client: Arc::new(RwLock::new(VelibDataClient::new())),
-        }
+        Self::with_data_client(VelibDataClient::new())
     }
 
     #[must_use]
     pub fn with_data_client(data_client: VelibDataClient) -> Self {
         Self {
+            metrics: data_client.metrics(),
+            clock: data_client.clock(),
             data_client: Arc::new(RwLock::new(data_client)),
+            aliases: Arc::new(AliasTable::bundled()),
+            lenient_limits: false,
+            max_data_age: default_max_data_age(),
+            service_area: ServiceArea::paris(),
+            walking_speed: WalkingSpeed::default(),
+            barriers: Arc::new(BarrierMap::bundled()),
+            transit: Arc::new(TransitNetwork::bundled()),
+            routing: None,
+            detour_factor: DEFAULT_DETOUR_FACTOR,
+            coverage_gap_meters: density::DEFAULT_GAP_DISTANCE_METERS,
+        }
+    }
+
+    /// Create a handler from application configuration
+    pub fn with_config(config: &AppConfig) -> Result<Self> {
+        let aliases = match &config.aliases_path {
+            Some(path) => AliasTable::load(path)?,
+            None => AliasTable::bundled(),
+        };
+
+        let barriers = match &config.barriers_path {
+            Some(path) => BarrierMap::load(path)?,
+            None => BarrierMap::bundled(),
+        };
+
+        let transit = match &config.transit_path {
+            Some(path) => TransitNetwork::load(path)?,
+            None => TransitNetwork::bundled(),
+        };
+
+        let mut service_area = config.service_area.clone();
+        if let Some(path) = &config.service_area_path {
+            service_area.polygon = Some(Geometry::load(path)?);
+        }
+
+        let mut data_client =
+            VelibDataClient::new().with_upstream_rate_limit(config.upstream_requests_per_minute);
+        if let Some(minutes) = config.feed_stale_after_minutes {
+            data_client = data_client.with_feed_stale_after(chrono::Duration::minutes(minutes));
+        }
+        data_client = data_client
+            .with_history_retention(chrono::Duration::hours(config.history_retention_hours))
+            .with_change_retention(config.change_retention)
+            .with_availability_thresholds(config.availability_thresholds)
+            .with_duplicate_policy(config.duplicate_stations);
+        if let Some(url) = &config.stations_url {
+            data_client = data_client.with_stations_url(url);
+        }
+        if let Some(url) = &config.realtime_url {
+            data_client = data_client.with_realtime_url(url);
+        }
+        if let Some(mb) = config.memory_soft_limit_mb {
+            data_client = data_client.with_memory_soft_limit(mb.saturating_mul(1024 * 1024));
+        }
+        if !config.upstream_mirrors.is_empty() {
+            data_client = data_client.with_upstream_mirrors(&config.upstream_mirrors);
+        }
+        if let Some(url) = &config.gbfs_url {
+            data_client = data_client.with_gbfs_url(url);
+        }
+        if let Some(url) = &config.storage_url {
+            data_client = data_client.with_storage(crate::storage::open(url)?);
+        }
+        if let Some(stations) = config.simulation_stations {
+            #[cfg(feature = "simulation")]
+            {
+                data_client = data_client.with_simulation(crate::data::SimulatedCity::new(
+                    stations,
+                    config.simulation_seed,
+                ));
+            }
+            #[cfg(not(feature = "simulation"))]
+            tracing::warn!(
+                "Not simulating {} stations: built without the simulation feature",
+                stations
+            );
+        }
+
+        Ok(Self {
+            metrics: data_client.metrics(),
+            clock: data_client.clock(),
+            data_client: Arc::new(RwLock::new(data_client)),
+            aliases: Arc::new(aliases),
+            lenient_limits: config.lenient_limits,
+            max_data_age: default_max_data_age(),
+            service_area,
+            walking_speed: config.walking_speed,
+            barriers: Arc::new(barriers),
+            transit: Arc::new(transit),
+            routing: config
+                .routing_url
+                .as_deref()
+                .map(|url| Arc::new(RoutingBackend::new(config.routing_engine, url))),
+            detour_factor: config.walk_detour_factor,
+            coverage_gap_meters: config.coverage_gap_meters,
+        })
+    }
+
+    /// Replace the landmark alias table consulted by name search
+    #[must_use]
+    pub fn with_aliases(mut self, aliases: AliasTable) -> Self {
+        self.aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Replace the rivers and railways barrier-aware distances walk around
+    #[must_use]
+    pub fn with_barriers(mut self, barriers: BarrierMap) -> Self {
+        self.barriers = Arc::new(barriers);
+        self
+    }
+
+    /// Measure walks over the street network with an external routing engine
+    #[must_use]
+    pub fn with_routing(mut self, routing: RoutingBackend) -> Self {
+        self.routing = Some(Arc::new(routing));
+        self
+    }
+
+    /// Lengthen straight-line walks by `factor` when no routing engine answers
+    #[must_use]
+    pub fn with_detour_factor(mut self, factor: f64) -> Self {
+        self.detour_factor = factor;
+        self
+    }
+
+    /// Report coverage gaps where the nearest station is farther than `meters`
+    #[must_use]
+    pub fn with_coverage_gap_distance(mut self, meters: u32) -> Self {
+        self.coverage_gap_meters = meters;
+        self
+    }
+
+    /// Clamp oversized limits by default rather than rejecting the call
+    ///
+    /// Requests can still opt in or out with their own `lenient` flag.
+    #[must_use]
+    pub fn with_lenient_limits(mut self, lenient: bool) -> Self {
+        self.lenient_limits = lenient;
+        self
+    }
+
+    /// Accept query coordinates only within `service_area`
+    #[must_use]
+    pub fn with_service_area(mut self, service_area: ServiceArea) -> Self {
+        self.service_area = service_area;
+        self
+    }
+
+    /// Convert distances to `walk_time_minutes` at this pace
+    #[must_use]
+    pub fn with_walking_speed(mut self, walking_speed: WalkingSpeed) -> Self {
+        self.walking_speed = walking_speed;
+        self
+    }
+
+    /// A clone with the tunables of `config` applied, for a configuration reload
+    ///
+    /// The shared data client's request ceiling and label thresholds change in
+    /// place, for every clone.
+    pub async fn reconfigured(&self, config: &AppConfig) -> Self {
+        {
+            let mut client = self.data_client.write().await;
+            client.set_upstream_rate_limit(config.upstream_requests_per_minute);
+            client.set_availability_thresholds(config.availability_thresholds);
         }
+        self.clone()
+            .with_lenient_limits(config.lenient_limits)
+            .with_walking_speed(config.walking_speed)
+    }
+
+    /// Answer from cached real-time data only while it is at most `max_age` old
+    ///
+    /// The server scopes a clone to each tool's policy from the
+    /// [`ToolRegistry`](crate::mcp::registry::ToolRegistry).
+    #[must_use]
+    pub fn with_max_data_age(mut self, max_age: std::time::Duration) -> Self {
+        self.max_data_age = chrono::Duration::from_std(max_age).unwrap_or(self.max_data_age);
+        self
+    }
+
+    /// Check `limit` (and `radius_meters`) against the maxima
+    ///
+    /// Strict mode rejects oversized values; lenient mode clamps them and
+    /// reports the values it used.
+    fn check_limits(
+        &self,
+        lenient: Option<bool>,
+        limit: u16,
+        radius_meters: Option<u32>,
+    ) -> Result<CheckedLimits> {
+        let lenient = lenient.unwrap_or(self.lenient_limits);
+        let mut checked = CheckedLimits {
+            applied: AppliedLimits {
+                limit,
+                radius_meters,
+            },
+            truncated: false,
+        };
+
+        if let Some(radius) = radius_meters.filter(|&radius| radius > MAX_SEARCH_RADIUS) {
+            if !lenient {
+                return Err(Error::SearchRadiusTooLarge {
+                    radius,
+                    max: MAX_SEARCH_RADIUS,
+                });
+            }
+            checked.applied.radius_meters = Some(MAX_SEARCH_RADIUS);
+            checked.truncated = true;
+        }
+
+        if limit > MAX_RESULT_LIMIT {
+            if !lenient {
+                return Err(Error::ResultLimitExceeded {
+                    limit,
+                    max: MAX_RESULT_LIMIT,
+                });
+            }
+            checked.applied.limit = MAX_RESULT_LIMIT;
+            checked.truncated = true;
+        }
+
+        Ok(checked)
     }
 
     pub async fn find_nearby_stations(
         &self,
         input: FindNearbyStationsInput,
     ) -> Result<FindNearbyStationsOutput> {
+        let input = input.sanitize()?;
         let start_time = Instant::now();
 
         // Validate input parameters
-        if input.radius_meters > MAX_SEARCH_RADIUS {
-            return Err(Error::SearchRadiusTooLarge {
-                radius: input.radius_meters,
-                max: MAX_SEARCH_RADIUS,
-            });
-        }
-
-        if input.limit > MAX_RESULT_LIMIT {
-            return Err(Error::ResultLimitExceeded {
-                limit: input.limit,
-                max: MAX_RESULT_LIMIT,
-            });
-        }
+        let limits = self.check_limits(input.lenient, input.limit, Some(input.radius_meters))?;
+        let radius_meters = limits.applied.radius_meters.unwrap_or(input.radius_meters);
 
         let query_point = Coordinates::new(input.latitude, input.longitude);
-        if !query_point.is_valid_paris_metro() {
-            return Err(Error::InvalidCoordinates {
-                latitude: input.latitude,
-                longitude: input.longitude,
-            });
-        }
-
-        // Enforce 50km distance limit from Paris City Hall
-        if !query_point.is_within_paris_service_area() {
-            let distance_km = query_point.distance_to(&PARIS_CITY_HALL) / 1000.0;
-            return Err(Error::OutsideServiceArea { distance_km });
-        }
+        self.service_area.check(&query_point)?;
 
         // Fetch live station data
         let mut data_client = self.data_client.write().await;
-        let all_stations = data_client.get_all_stations(true).await?;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+
+        let district = input.district.as_deref().map(district_key);
+        let (transit, transit_radius) =
+            self.transit_filter(input.near_transit.as_deref(), input.transit_radius_meters)?;
+
+        // With auto_expand, anything up to the maximum radius is a candidate
+        let candidate_radius = if input.auto_expand {
+            MAX_SEARCH_RADIUS.max(radius_meters)
+        } else {
+            radius_meters
+        };
 
         // Filter stations by distance and bike type
         let mut nearby_stations: Vec<StationWithDistance> = all_stations
-            .into_iter()
+            .iter()
             .filter_map(|station| {
-                let distance = query_point.distance_to(&station.reference.coordinates) as u32;
+                let distance = self.walking_distance(
+                    &query_point,
+                    &station.reference.coordinates,
+                    input.barrier_aware,
+                    candidate_radius,
+                );
 
                 // Check if within search radius
-                if distance <= input.radius_meters {
-                    // Check if station has the requested bike type (if specified)
+                if distance <= candidate_radius {
+                    // Check the requested bike type and minimum availability (if specified)
                     let has_requested_bikes = match &input.availability_filter {
-                        Some(filter) => match &filter.bike_type {
-                            Some(bike_type) => station.has_available_bikes(bike_type),
-                            None => true,
-                        },
+                        Some(filter) => matches_availability(station, filter),
                         None => true, // No filter specified
                     };
 
-                    if has_requested_bikes && station.is_operational() {
+                    let in_requested_district = district
+                        .as_deref()
+                        .is_none_or(|district| station.in_district(district));
+                    let near_requested_transit = transit.as_ref().is_none_or(|transit| {
+                        transit
+                            .nearest(&station.reference.coordinates, transit_radius)
+                            .is_some()
+                    });
+
+                    let purpose = input
+                        .availability_filter
+                        .as_ref()
+                        .and_then(|filter| filter.purpose);
+
+                    if has_requested_bikes
+                        && in_requested_district
+                        && near_requested_transit
+                        && serves(station, purpose)
+                    {
                         Some(StationWithDistance {
-                            station,
+                            station: station.station.clone(),
                             distance_meters: distance,
+                            walk_time_minutes: self.walking_speed.minutes(distance),
                         })
                     } else {
                         None
@@ -115,23 +408,70 @@ impl McpToolHandler {
                 }
             })
             .collect();
+        drop(data_client);
+
+        self.route_walks(
+            &query_point,
+            nearby_stations.iter_mut(),
+            input.barrier_aware,
+        )
+        .await;
+        nearby_stations.retain(|s| s.distance_meters <= candidate_radius);
+
+        let requested_radius = radius_meters;
+        let mut radius_meters = radius_meters;
+        if input.auto_expand {
+            let wanted = usize::from(input.min_results.max(1));
+            while radius_meters < candidate_radius
+                && nearby_stations
+                    .iter()
+                    .filter(|s| s.distance_meters <= radius_meters)
+                    .count()
+                    < wanted
+            {
+                radius_meters = radius_meters.saturating_mul(2).min(candidate_radius);
+            }
+            nearby_stations.retain(|s| s.distance_meters <= radius_meters);
+        }
 
-        // Sort by distance
+        // Sort by distance, then by the requested key before limiting
         nearby_stations.sort_by_key(|s| s.distance_meters);
+        sort_stations(&mut nearby_stations, &input.sort)?;
 
         // Limit results
-        nearby_stations.truncate(input.limit as usize);
+        let total_found = nearby_stations.len() as u32;
+        nearby_stations.truncate(limits.applied.limit as usize);
 
         let stations = nearby_stations;
+        let has_more = stations.len() < total_found as usize;
+        let guidance = has_more.then(|| {
+            let widen = if limits.applied.limit < MAX_RESULT_LIMIT {
+                format!("raise limit (up to {MAX_RESULT_LIMIT}), ")
+            } else {
+                String::new()
+            };
+            format!(
+                "Showing {} of {total_found} stations within {radius_meters} m; \
+                 {widen}narrow radius_meters or add an availability_filter to see the others",
+                stations.len()
+            )
+        });
 
         let search_time = start_time.elapsed().as_millis() as u64;
 
         Ok(FindNearbyStationsOutput {
             search_metadata: SearchMetadata {
                 query_point,
-                radius_meters: input.radius_meters,
-                total_found: stations.len() as u32,
+                radius_meters,
+                expanded_from_meters: (radius_meters != requested_radius)
+                    .then_some(requested_radius),
+                total_found,
+                has_more,
+                guidance,
                 search_time_ms: search_time,
+                truncated: limits.truncated,
+                applied_limits: limits.reported(),
+                near_transit: transit.map(|transit| transit.label),
             },
             stations,
         })
@@ -143,7 +483,7 @@ impl McpToolHandler {
     ) -> Result<GetStationByCodeOutput> {
         let mut data_client = self.data_client.write().await;
         let station = data_client
-            .get_station_by_code(&input.station_code, true)
+            .get_station_by_code_within(&input.station_code, true, self.max_data_age)
             .await?;
 
         Ok(GetStationByCodeOutput {
@@ -158,24 +498,55 @@ impl McpToolHandler {
     ) -> Result<SearchStationsByNameOutput> {
         let start_time = Instant::now();
 
-        if input.query.len() < 2 {
+        if input.query.len() < 2 && input.near_transit.is_none() {
             return Err(Error::Internal(anyhow::anyhow!("Search query too short")));
         }
+        let (transit, transit_radius) =
+            self.transit_filter(input.near_transit.as_deref(), input.transit_radius_meters)?;
 
-        if input.limit > MAX_RESULT_LIMIT {
-            return Err(Error::ResultLimitExceeded {
-                limit: input.limit,
-                max: MAX_RESULT_LIMIT,
-            });
-        }
+        let limits = self.check_limits(input.lenient, input.limit, None)?;
 
         // Fetch live station data and search by name
         let mut data_client = self.data_client.write().await;
-        let all_stations = data_client.get_all_stations(true).await?;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+
+        // Landmark aliases ("BnF", "Sacré-Cœur") resolve to their nearby stations,
+        // which rank ahead of plain name matches
+        let landmark = self.aliases.lookup(&input.query);
+        let district = input.district.as_deref().map(district_key);
+        let candidates: Vec<&EnrichedStation> = all_stations
+            .iter()
+            .filter(|station| {
+                district
+                    .as_deref()
+                    .is_none_or(|district| station.in_district(district))
+            })
+            .filter(|station| {
+                transit.as_ref().is_none_or(|transit| {
+                    transit
+                        .nearest(&station.reference.coordinates, transit_radius)
+                        .is_some()
+                })
+            })
+            .collect();
+
+        let mut landmark_stations: Vec<VelibStation> = landmark
+            .map(|landmark| landmark.resolve(candidates.iter().map(|station| &station.station)))
+            .unwrap_or_default()
+            .into_iter()
+            .cloned()
+            .collect();
+        let landmark_codes: HashSet<Arc<str>> = landmark_stations
+            .iter()
+            .map(|station| station.reference.station_code.clone())
+            .collect();
 
         let query_lower = input.query.to_lowercase();
-        let mut matching_stations: Vec<VelibStation> = all_stations
+        let mut matching_stations: Vec<VelibStation> = candidates
             .into_iter()
+            .filter(|station| !landmark_codes.contains(&station.reference.station_code))
             .filter(|station| {
                 let name_lower = station.reference.name.to_lowercase();
                 if input.fuzzy {
@@ -186,15 +557,31 @@ impl McpToolHandler {
                     name_lower.starts_with(&query_lower)
                 }
             })
+            .map(|station| station.station.clone())
             .collect();
 
-        // Sort by name for consistent results
-        matching_stations.sort_by(|a, b| a.reference.name.cmp(&b.reference.name));
+        // Sort by name for consistent results, or closest to the transit stops first
+        match &transit {
+            Some(transit) => matching_stations.sort_by(|a, b| {
+                let distance = |station: &VelibStation| {
+                    transit
+                        .nearest(&station.reference.coordinates, transit_radius)
+                        .map_or(f64::INFINITY, |(_, distance)| distance)
+                };
+                distance(a)
+                    .total_cmp(&distance(b))
+                    .then_with(|| a.reference.name.cmp(&b.reference.name))
+            }),
+            None => matching_stations.sort_by(|a, b| a.reference.name.cmp(&b.reference.name)),
+        }
+
+        landmark_stations.append(&mut matching_stations);
+        sort_stations(&mut landmark_stations, &input.sort)?;
 
         // Limit results
-        matching_stations.truncate(input.limit as usize);
+        landmark_stations.truncate(limits.applied.limit as usize);
 
-        let stations = matching_stations;
+        let stations = landmark_stations;
         let search_time = start_time.elapsed().as_millis() as u64;
 
         Ok(SearchStationsByNameOutput {
@@ -203,149 +590,995 @@ impl McpToolHandler {
                 total_found: stations.len() as u32,
                 fuzzy_enabled: input.fuzzy,
                 search_time_ms: search_time,
+                matched_landmark: landmark.map(|landmark| landmark.name.clone()),
+                truncated: limits.truncated,
+                applied_limits: limits.reported(),
+                near_transit: transit.map(|transit| transit.label),
             },
             stations,
         })
     }
 
+    pub async fn autocomplete_station_names(
+        &self,
+        input: AutocompleteStationNamesInput,
+    ) -> Result<AutocompleteStationNamesOutput> {
+        if input.prefix.trim().is_empty() {
+            return Err(Error::Validation("Prefix cannot be empty".to_string()));
+        }
+
+        let limits = self.check_limits(input.lenient, input.limit, None)?;
+
+        let mut data_client = self.data_client.write().await;
+        let suggestions = data_client
+            .autocomplete_station_names(&input.prefix, limits.applied.limit as usize)
+            .await?;
+
+        Ok(AutocompleteStationNamesOutput {
+            prefix: input.prefix,
+            suggestions,
+            truncated: limits.truncated,
+            applied_limits: limits.reported(),
+        })
+    }
+
+    /// Current status of each favorite, fetched in a single pass over live data
+    pub async fn get_favorites_status(
+        &self,
+        favorites: Vec<FavoriteStation>,
+        input: GetFavoritesStatusInput,
+    ) -> Result<GetFavoritesStatusOutput> {
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+
+        let mut favorites: Vec<FavoriteStationStatus> = favorites
+            .into_iter()
+            .map(|favorite| {
+                let station = all_stations
+                    .iter()
+                    .find(|station| *station.reference.station_code == *favorite.station_code)
+                    .map(|station| station.station.clone());
+                FavoriteStationStatus {
+                    found: station.is_some(),
+                    favorite,
+                    station,
+                }
+            })
+            .collect();
+        sort_stations(&mut favorites, &input.sort)?;
+
+        Ok(GetFavoritesStatusOutput {
+            favorites,
+            checked_at: self.clock.now(),
+        })
+    }
+
+    /// Draw a station's recent bike availability as a sparkline
+    pub async fn render_station_sparkline(
+        &self,
+        input: RenderStationSparklineInput,
+    ) -> Result<RenderStationSparklineOutput> {
+        if !(1..=MAX_SPARKLINE_HOURS).contains(&input.hours) {
+            return Err(Error::Validation(format!(
+                "hours must be between 1 and {MAX_SPARKLINE_HOURS}"
+            )));
+        }
+
+        let mut data_client = self.data_client.write().await;
+        let station = data_client
+            .get_station_by_code_within(&input.station_code, true, self.max_data_age)
+            .await?
+            .ok_or_else(|| Error::StationNotFound {
+                station_code: input.station_code.clone(),
+            })?;
+        let since = self.clock.now() - chrono::Duration::hours(i64::from(input.hours));
+        let values: Vec<u16> = data_client
+            .availability_history(&input.station_code, since)
+            .into_iter()
+            .map(|(_, bikes)| bikes)
+            .collect();
+
+        let scale_max = station.reference.capacity;
+        Ok(RenderStationSparklineOutput {
+            station_code: input.station_code,
+            name: station.reference.name.to_string(),
+            hours: input.hours,
+            samples: values.len(),
+            min_bikes: values.iter().min().copied(),
+            max_bikes: values.iter().max().copied(),
+            scale_max,
+            sparkline: sparkline::unicode(&values, scale_max),
+            svg: (input.format == SparklineFormat::Svg).then(|| sparkline::svg(&values, scale_max)),
+        })
+    }
+
     pub async fn get_area_statistics(
         &self,
         input: GetAreaStatisticsInput,
     ) -> Result<GetAreaStatisticsOutput> {
+        let input = input.sanitize()?;
+        let bounds = match (input.bounds, &input.polygon) {
+            (Some(bounds), None) => bounds,
+            (None, Some(polygon)) => {
+                polygon.validate().map_err(Error::Validation)?;
+                GeographicBounds::around(polygon)
+            }
+            _ => {
+                return Err(Error::Validation(
+                    "Exactly one of bounds or polygon is required".to_string(),
+                ))
+            }
+        };
+        if input
+            .window_minutes
+            .is_some_and(|minutes| !(1..=MAX_AVAILABILITY_WINDOW_MINUTES).contains(&minutes))
+        {
+            return Err(Error::Validation(format!(
+                "window_minutes must be between 1 and {MAX_AVAILABILITY_WINDOW_MINUTES}"
+            )));
+        }
+
         // Fetch live station data
         let mut data_client = self.data_client.write().await;
-        let all_stations = data_client.get_all_stations(true).await?;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
 
-        // Filter stations within the specified bounds
-        let area_stations: Vec<&VelibStation> = all_stations
+        // Filter stations within the specified bounds or polygon
+        let area_stations: Vec<&EnrichedStation> = all_stations
             .iter()
-            .filter(|station| input.bounds.contains(&station.reference.coordinates))
+            .filter(|station| {
+                let coordinates = &station.reference.coordinates;
+                bounds.contains(coordinates)
+                    && input
+                        .polygon
+                        .as_ref()
+                        .is_none_or(|polygon| polygon.contains(coordinates))
+            })
             .collect();
 
-        // Calculate area statistics from live data
-        let total_stations = area_stations.len() as u32;
-        let operational_stations = area_stations
-            .iter()
-            .filter(|station| station.is_operational())
-            .count() as u32;
-
-        let mut total_capacity = 0u32;
-        let mut total_mechanical = 0u32;
-        let mut total_electric = 0u32;
-        let mut total_available_docks = 0u32;
+        let mut districts: HashMap<&str, Vec<&EnrichedStation>> = HashMap::new();
+        for &station in &area_stations {
+            if let Some(district) = &station.reference.district {
+                districts.entry(district).or_default().push(station);
+            }
+        }
+        let mut by_district: Vec<DistrictStatistics> = districts
+            .into_iter()
+            .map(|(district, stations)| DistrictStatistics {
+                district: district.to_string(),
+                stats: area_statistics(&stations),
+            })
+            .collect();
+        // "Paris 2e" before "Paris 10e"
+        by_district.sort_by_cached_key(|stats| {
+            let name = &stats.district;
+            let digits_at = name
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or(name.len());
+            let number: String = name[digits_at..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            (
+                name[..digits_at].to_string(),
+                number.parse::<u32>().unwrap_or(0),
+            )
+        });
+
+        let window = input.window_minutes.map(|minutes| {
+            let since = self.clock.now() - chrono::Duration::minutes(i64::from(minutes));
+            let tracks: Vec<WindowTrack> = area_stations
+                .iter()
+                .map(|station| {
+                    let current = station.real_time.as_ref().map_or(0, |rt| rt.bikes.total());
+                    (
+                        data_client.availability_history(&station.reference.station_code, since),
+                        current,
+                    )
+                })
+                .collect();
+            availability_window(minutes, &tracks)
+        });
 
-        for station in &area_stations {
-            total_capacity += u32::from(station.reference.capacity);
+        Ok(GetAreaStatisticsOutput {
+            area_stats: area_statistics(&area_stations),
+            bounds,
+            by_district,
+            window,
+        })
+    }
 
-            if let Some(rt) = &station.real_time {
-                total_mechanical += u32::from(rt.bikes.mechanical);
-                total_electric += u32::from(rt.bikes.electric);
-                total_available_docks += u32::from(rt.available_docks);
+    pub async fn get_station_density(
+        &self,
+        input: GetStationDensityInput,
+    ) -> Result<GetStationDensityOutput> {
+        let input = input.sanitize()?;
+        for (name, meters) in [
+            ("gap_distance_meters", input.gap_distance_meters),
+            ("cell_size_meters", input.cell_size_meters),
+        ] {
+            if meters == 0 || meters > MAX_COVERAGE_DISTANCE {
+                return Err(Error::Validation(format!(
+                    "{name} must be between 1 and {MAX_COVERAGE_DISTANCE}"
+                )));
             }
         }
 
-        let total_bikes = total_mechanical + total_electric;
-        let occupancy_rate = if total_capacity > 0 {
-            f64::from(total_bikes) / f64::from(total_capacity)
-        } else {
-            0.0
+        // Station placement and capacity come from the reference data alone
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client.get_all_stations(false).await?;
+        drop(data_client);
+
+        let (bounds, stations): (GeographicBounds, Vec<&EnrichedStation>) =
+            match (input.bounds, input.district.as_deref()) {
+                (Some(bounds), None) => {
+                    if bounds.north <= bounds.south || bounds.east <= bounds.west {
+                        return Err(Error::Validation(
+                            "bounds must have north above south and east of west".to_string(),
+                        ));
+                    }
+                    let stations = all_stations
+                        .iter()
+                        .filter(|station| bounds.contains(&station.reference.coordinates))
+                        .collect();
+                    (bounds, stations)
+                }
+                (None, Some(district)) => {
+                    let key = district_key(district);
+                    let stations: Vec<&EnrichedStation> = all_stations
+                        .iter()
+                        .filter(|station| station.in_district(&key))
+                        .collect();
+                    if stations.is_empty() {
+                        return Err(Error::Validation(format!(
+                            "No stations in district {district}"
+                        )));
+                    }
+                    let outline = Geometry::Polygon {
+                        coordinates: vec![stations
+                            .iter()
+                            .map(|station| {
+                                let coords = &station.reference.coordinates;
+                                [coords.longitude, coords.latitude]
+                            })
+                            .collect()],
+                    };
+                    (GeographicBounds::around(&outline), stations)
+                }
+                _ => {
+                    return Err(Error::Validation(
+                        "Exactly one of bounds or district is required".to_string(),
+                    ))
+                }
+            };
+
+        let area_km2 = density::area_km2(&bounds);
+        let total_stations = stations.len() as u32;
+        let total_capacity: u32 = stations
+            .iter()
+            .map(|station| u32::from(station.reference.capacity))
+            .sum();
+        let per_km2 = |count: u32| {
+            if area_km2 > 0.0 {
+                f64::from(count) / area_km2
+            } else {
+                0.0
+            }
         };
 
-        let stats = AreaStatistics {
+        // Stations just outside the area still cover its edges
+        let margin = density::widen(&bounds, input.gap_distance_meters);
+        let nearby: Vec<Coordinates> = all_stations
+            .iter()
+            .map(|station| station.reference.coordinates)
+            .filter(|coords| margin.contains(coords))
+            .collect();
+        let mut grid = density::coverage_grid(
+            &bounds,
+            &nearby,
+            input.cell_size_meters,
+            input.gap_distance_meters,
+        );
+        let uncovered_cells = grid.gaps.len() as u32;
+        grid.gaps.truncate(MAX_LISTED_GAPS);
+
+        Ok(GetStationDensityOutput {
+            district: input.district,
+            area_km2,
             total_stations,
-            operational_stations,
             total_capacity,
-            available_bikes: AvailableBikesStats {
-                mechanical: total_mechanical,
-                electric: total_electric,
-                total: total_bikes,
+            stations_per_km2: per_km2(total_stations),
+            capacity_per_km2: per_km2(total_capacity),
+            coverage: CoverageSummary {
+                cell_size_meters: grid.cell_size_meters,
+                gap_distance_meters: input.gap_distance_meters,
+                grid_cells: grid.cells as u32,
+                uncovered_cells,
+                covered_ratio: 1.0 - f64::from(uncovered_cells) / grid.cells as f64,
+                gaps: grid.gaps,
             },
-            available_docks: total_available_docks,
-            occupancy_rate,
+            bounds,
+        })
+    }
+
+    /// Areas of the service area farther than the configured gap distance from
+    /// every station, for `velib://coverage/gaps`
+    ///
+    /// Gaps are looked for within the service area polygon, or within the
+    /// stations' bounding box when none is configured, on a grid of half the
+    /// gap distance.
+    pub async fn coverage_gaps(&self) -> Result<CoverageGapsReport> {
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client.get_all_stations(false).await?;
+        drop(data_client);
+        let stations: Vec<Coordinates> = all_stations
+            .iter()
+            .map(|station| station.reference.coordinates)
+            .collect();
+
+        let polygon = self.service_area.polygon.as_ref();
+        let (extent, bounds) = match polygon {
+            Some(polygon) => (
+                CoverageExtent::ServicePolygon,
+                GeographicBounds::around(polygon),
+            ),
+            None => {
+                if stations.is_empty() {
+                    return Err(Error::Validation(
+                        "No stations to find coverage gaps around".to_string(),
+                    ));
+                }
+                let outline = Geometry::Polygon {
+                    coordinates: vec![stations
+                        .iter()
+                        .map(|coords| [coords.longitude, coords.latitude])
+                        .collect()],
+                };
+                (
+                    CoverageExtent::StationExtent,
+                    GeographicBounds::around(&outline),
+                )
+            }
         };
 
-        Ok(GetAreaStatisticsOutput {
-            area_stats: stats,
-            bounds: input.bounds,
+        let gap_distance = self.coverage_gap_meters;
+        let analysis = density::gap_areas(
+            &bounds,
+            &stations,
+            (gap_distance / 2).max(1),
+            gap_distance,
+            |center| polygon.is_none_or(|polygon| polygon.contains(center)),
+        );
+        let mut gaps = analysis.gaps;
+        let total_gaps = gaps.len() as u32;
+        let uncovered_km2 = gaps.iter().map(|gap| gap.area_km2).sum();
+        gaps.truncate(MAX_LISTED_GAPS);
+
+        Ok(CoverageGapsReport {
+            generated_at: self.clock.now(),
+            extent,
+            bounds,
+            gap_distance_meters: gap_distance,
+            cell_size_meters: analysis.cell_size_meters,
+            grid_cells: analysis.cells as u32,
+            uncovered_km2,
+            total_gaps,
+            gaps,
         })
     }
 
-    pub async fn plan_bike_journey(
+    /// Stations within `buffer_meters` of a route, in the order the route passes them
+    ///
+    /// When more stations qualify than `limit`, the ones closest to the route are kept.
+    pub async fn find_stations_along_route(
         &self,
-        input: PlanBikeJourneyInput,
-    ) -> Result<PlanBikeJourneyOutput> {
-        if !input.origin.is_valid_paris_metro() {
-            return Err(Error::InvalidCoordinates {
-                latitude: input.origin.latitude,
-                longitude: input.origin.longitude,
+        input: FindStationsAlongRouteInput,
+    ) -> Result<FindStationsAlongRouteOutput> {
+        let input = input.sanitize()?;
+        let limits = self.check_limits(input.lenient, input.limit, None)?;
+        if input.buffer_meters > MAX_ROUTE_BUFFER {
+            return Err(Error::SearchRadiusTooLarge {
+                radius: input.buffer_meters,
+                max: MAX_ROUTE_BUFFER,
             });
         }
+        if !(2..=MAX_ROUTE_WAYPOINTS).contains(&input.waypoints.len()) {
+            return Err(Error::Validation(format!(
+                "A route needs between 2 and {MAX_ROUTE_WAYPOINTS} waypoints"
+            )));
+        }
+        for waypoint in &input.waypoints {
+            self.service_area.check(waypoint)?;
+        }
 
-        if !input.destination.is_valid_paris_metro() {
-            return Err(Error::InvalidCoordinates {
-                latitude: input.destination.latitude,
-                longitude: input.destination.longitude,
-            });
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+        drop(data_client);
+
+        let purpose = input
+            .availability_filter
+            .as_ref()
+            .and_then(|filter| filter.purpose);
+        let buffer = f64::from(input.buffer_meters);
+        let mut stations: Vec<StationAlongRoute> = all_stations
+            .iter()
+            .filter(|station| {
+                input
+                    .availability_filter
+                    .as_ref()
+                    .is_none_or(|filter| matches_availability(station, filter))
+                    && serves(station, purpose)
+            })
+            .filter_map(|station| {
+                let projection =
+                    project_onto_route(&input.waypoints, &station.reference.coordinates)?;
+                (projection.distance_meters <= buffer).then(|| StationAlongRoute {
+                    station: station.station.clone(),
+                    distance_from_route_meters: projection.distance_meters as u32,
+                    progress_meters: projection.progress_meters as u32,
+                })
+            })
+            .collect();
+
+        stations.sort_by_key(|s| s.distance_from_route_meters);
+        stations.truncate(limits.applied.limit as usize);
+        stations.sort_by_key(|s| (s.progress_meters, s.distance_from_route_meters));
+
+        Ok(FindStationsAlongRouteOutput {
+            route_length_meters: route_length_meters(&input.waypoints) as u32,
+            buffer_meters: input.buffer_meters,
+            total_found: stations.len() as u32,
+            truncated: limits.truncated,
+            applied_limits: limits.reported(),
+            stations,
+        })
+    }
+
+    pub async fn compare_stations(
+        &self,
+        input: CompareStationsInput,
+    ) -> Result<CompareStationsOutput> {
+        let input = input.sanitize()?;
+        if !(2..=MAX_COMPARED_STATIONS).contains(&input.station_codes.len()) {
+            return Err(Error::Validation(format!(
+                "Compare between 2 and {MAX_COMPARED_STATIONS} stations"
+            )));
+        }
+        let distinct: HashSet<&str> = input.station_codes.iter().map(String::as_str).collect();
+        if distinct.len() != input.station_codes.len() {
+            return Err(Error::Validation(
+                "station_codes must not repeat a station".to_string(),
+            ));
         }
 
-        // Enforce 50km distance limit from Paris City Hall for both origin and destination
-        if !input.origin.is_within_paris_service_area() {
-            let distance_km = input.origin.distance_to(&PARIS_CITY_HALL) / 1000.0;
-            return Err(Error::OutsideServiceArea { distance_km });
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+        let since = self.clock.now() - chrono::Duration::hours(24);
+
+        let mut stations = Vec::with_capacity(input.station_codes.len());
+        for code in &input.station_codes {
+            let station = all_stations
+                .iter()
+                .find(|station| *station.reference.station_code == **code)
+                .ok_or_else(|| Error::StationNotFound {
+                    station_code: code.clone(),
+                })?;
+            let bike_counts: Vec<u16> = data_client
+                .availability_history(code, since)
+                .into_iter()
+                .map(|(_, bikes)| bikes)
+                .collect();
+            let real_time = station.real_time.as_ref();
+            let distance_meters = input
+                .reference_point
+                .map(|point| point.distance_to(&station.reference.coordinates) as u32);
+            stations.push(StationComparison {
+                station_code: code.clone(),
+                name: station.reference.name.to_string(),
+                status: real_time.map(|rt| rt.status.clone()),
+                mechanical_bikes: real_time.map_or(0, |rt| rt.bikes.mechanical),
+                electric_bikes: real_time.map_or(0, |rt| rt.bikes.electric),
+                available_docks: real_time.map_or(0, |rt| rt.available_docks),
+                capacity: station.reference.capacity,
+                distance_meters,
+                walk_time_minutes: distance_meters.map(|meters| self.walking_speed.minutes(meters)),
+                reliability_score: comparison::reliability_score(
+                    &bike_counts,
+                    station.reference.capacity,
+                ),
+                reliability_samples: bike_counts.len(),
+            });
         }
 
-        if !input.destination.is_within_paris_service_area() {
-            let distance_km = input.destination.distance_to(&PARIS_CITY_HALL) / 1000.0;
-            return Err(Error::OutsideServiceArea { distance_km });
+        Ok(CompareStationsOutput {
+            markdown: comparison::markdown_table(&stations, UnitSystem::Metric),
+            stations,
+        })
+    }
+
+    /// Chance a dock is free when arriving at a destination station, and the
+    /// nearby stations most likely to have one
+    ///
+    /// Combines the current free docks with the rate they changed at over the
+    /// last half hour and their level at the same time the previous day.
+    pub async fn check_dropoff_feasibility(
+        &self,
+        input: CheckDropoffFeasibilityInput,
+    ) -> Result<CheckDropoffFeasibilityOutput> {
+        let input = input.sanitize()?;
+        self.service_area.check(&input.destination)?;
+        if input.max_walk_meters > MAX_DROPOFF_WALK {
+            return Err(Error::SearchRadiusTooLarge {
+                radius: input.max_walk_meters,
+                max: MAX_DROPOFF_WALK,
+            });
         }
+        if input.hold_window_minutes > MAX_HOLD_WINDOW_MINUTES {
+            return Err(Error::Validation(format!(
+                "hold_window_minutes must be at most {MAX_HOLD_WINDOW_MINUTES}"
+            )));
+        }
+        let now = self.clock.now();
+        let arrival_time = input.arrival_time.unwrap_or(now).max(now);
+        if arrival_time - now > chrono::Duration::minutes(dropoff::MAX_ARRIVAL_MINUTES) {
+            return Err(Error::Validation(format!(
+                "arrival_time must be within {} minutes",
+                dropoff::MAX_ARRIVAL_MINUTES
+            )));
+        }
+        let minutes_ahead =
+            (arrival_time - now).num_minutes() + i64::from(input.hold_window_minutes);
 
-        // Find nearby stations for pickup and dropoff using live data
         let mut data_client = self.data_client.write().await;
-        let all_stations = data_client.get_all_stations(true).await?;
-
-        // Get preferences or use defaults
-        let preferences = input.preferences.unwrap_or_default();
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
 
-        // Find pickup stations near origin
-        let mut pickup_candidates: Vec<StationWithDistance> = all_stations
+        let mut nearby: Vec<(&EnrichedStation, u32)> = all_stations
             .iter()
-            .filter_map(|station| {
-                let distance = input.origin.distance_to(&station.reference.coordinates) as u32;
+            .map(|station| {
+                let distance = input
+                    .destination
+                    .distance_to(&station.reference.coordinates)
+                    as u32;
+                (station, distance)
+            })
+            .filter(|&(_, distance)| distance <= input.max_walk_meters)
+            .collect();
+        nearby.sort_by_key(|&(_, distance)| distance);
+
+        let target = match &input.station_code {
+            Some(code) => {
+                let station = all_stations
+                    .iter()
+                    .find(|station| *station.reference.station_code == **code)
+                    .ok_or_else(|| Error::StationNotFound {
+                        station_code: code.clone(),
+                    })?;
+                let distance = input
+                    .destination
+                    .distance_to(&station.reference.coordinates)
+                    as u32;
+                Some((station, distance))
+            }
+            None => nearby.first().copied(),
+        };
 
-                if distance <= preferences.max_walk_distance
-                    && station.is_operational()
-                    && station.has_available_bikes(&preferences.bike_type)
-                {
-                    Some(StationWithDistance {
-                        station: station.clone(),
-                        distance_meters: distance,
+        let usual_at = arrival_time
+            + chrono::Duration::minutes(i64::from(input.hold_window_minutes))
+            - chrono::Duration::hours(24);
+        let estimate = |station: &EnrichedStation, distance_meters: u32| {
+            let code = &*station.reference.station_code;
+            let capacity = station.reference.capacity;
+            let recent = data_client.availability_history(
+                code,
+                now - chrono::Duration::minutes(dropoff::RATE_WINDOW_MINUTES),
+            );
+            let tolerance = chrono::Duration::minutes(dropoff::PATTERN_TOLERANCE_MINUTES);
+            let previous_day = data_client.availability_history(code, usual_at - tolerance);
+            let signals = DockSignals {
+                free_docks: station
+                    .real_time
+                    .as_ref()
+                    .map_or(0, |rt| rt.available_docks),
+                capacity,
+                docks_change_per_hour: dropoff::docks_change_per_hour(&recent),
+                usual_free_docks: dropoff::usual_free_docks(&previous_day, usual_at, capacity),
+            };
+            let probability = if station.can_return {
+                dropoff::dock_probability(&signals, minutes_ahead as f64)
+            } else {
+                0.0
+            };
+            DropoffEstimate {
+                station_code: code.to_string(),
+                name: station.reference.name.to_string(),
+                distance_meters,
+                walk_time_minutes: self.walking_speed.minutes(distance_meters),
+                available_docks: signals.free_docks,
+                capacity,
+                trend: station.real_time.as_ref().and_then(|rt| rt.trend),
+                docks_change_per_hour: signals
+                    .docks_change_per_hour
+                    .map(|rate| (rate * 10.0).round() / 10.0),
+                usual_free_docks: signals.usual_free_docks,
+                expected_free_docks: (dropoff::expected_free_docks(&signals, minutes_ahead as f64)
+                    * 10.0)
+                    .round()
+                    / 10.0,
+                probability: (probability * 100.0).round() / 100.0,
+            }
+        };
+
+        let checked = target.map(|(station, distance)| estimate(station, distance));
+        let mut alternatives: Vec<DropoffEstimate> = nearby
+            .iter()
+            .filter(|(station, _)| {
+                station.can_return
+                    && checked.as_ref().is_none_or(|checked| {
+                        *checked.station_code != *station.reference.station_code
                     })
-                } else {
-                    None
+            })
+            .map(|&(station, distance)| estimate(station, distance))
+            .collect();
+        alternatives.sort_by(|a, b| {
+            b.probability
+                .total_cmp(&a.probability)
+                .then(a.distance_meters.cmp(&b.distance_meters))
+        });
+        alternatives.truncate(DROPOFF_ALTERNATIVES);
+
+        Ok(CheckDropoffFeasibilityOutput {
+            arrival_time,
+            minutes_ahead: minutes_ahead as u32,
+            station: checked,
+            alternatives,
+        })
+    }
+
+    /// Current bikes at a station or around a point against the same weekday
+    /// and hour in previous weeks
+    pub async fn compare_with_typical(
+        &self,
+        input: CompareWithTypicalInput,
+    ) -> Result<CompareWithTypicalOutput> {
+        let center = match (&input.station_code, input.latitude, input.longitude) {
+            (Some(_), None, None) => None,
+            (None, Some(latitude), Some(longitude)) => {
+                if input.radius_meters > MAX_SEARCH_RADIUS {
+                    return Err(Error::SearchRadiusTooLarge {
+                        radius: input.radius_meters,
+                        max: MAX_SEARCH_RADIUS,
+                    });
                 }
+                let center = Coordinates::new(latitude, longitude);
+                self.service_area.check(&center)?;
+                Some(center)
+            }
+            _ => {
+                return Err(Error::Validation(
+                    "Give either station_code or both latitude and longitude".to_string(),
+                ))
+            }
+        };
+
+        let now = self.clock.now();
+        let mut data_client = self.data_client.write().await;
+        let stations: Vec<VelibStation> = match center {
+            None => {
+                let code = input.station_code.clone().unwrap_or_default();
+                let station = data_client
+                    .get_station_by_code_within(&code, true, self.max_data_age)
+                    .await?
+                    .ok_or(Error::StationNotFound { station_code: code })?;
+                vec![station]
+            }
+            Some(center) => data_client
+                .get_all_stations_within(true, self.max_data_age)
+                .await?
+                .iter()
+                .filter(|station| {
+                    center.distance_to(&station.reference.coordinates)
+                        <= f64::from(input.radius_meters)
+                })
+                .map(|station| station.station.clone())
+                .collect(),
+        };
+
+        let weeks: Vec<chrono::DateTime<chrono::Utc>> = (1..=typical::MAX_TYPICAL_WEEKS)
+            .map(|week| now - chrono::Duration::weeks(week))
+            .collect();
+        let since = now
+            - chrono::Duration::weeks(typical::MAX_TYPICAL_WEEKS)
+            - chrono::Duration::minutes(typical::TYPICAL_TOLERANCE_MINUTES);
+        // Current bikes and the level in each past week, per station
+        let observed: Vec<(u32, Vec<Option<u16>>)> = stations
+            .iter()
+            .map(|station| {
+                let samples =
+                    data_client.availability_history(&station.reference.station_code, since);
+                let current = station
+                    .real_time
+                    .as_ref()
+                    .map_or(0, |rt| u32::from(rt.bikes.total()));
+                let levels = weeks
+                    .iter()
+                    .map(|&at| typical::observed_near(&samples, at))
+                    .collect();
+                (current, levels)
             })
             .collect();
+        let retention_hours = data_client.history_stats().retention_hours;
+        drop(data_client);
 
-        pickup_candidates.sort_by_key(|s| s.distance_meters);
-        pickup_candidates.truncate(3);
+        // Every week adds up the same stations: those with a sample in each
+        // week any of them has one for
+        let mut counted: Vec<usize> = (0..weeks.len())
+            .filter(|&week| observed.iter().any(|(_, levels)| levels[week].is_some()))
+            .collect();
+        let mut complete: Vec<&(u32, Vec<Option<u16>>)> = observed
+            .iter()
+            .filter(|(_, levels)| counted.iter().all(|&week| levels[week].is_some()))
+            .collect();
+        if complete.is_empty() {
+            counted.clear();
+            complete = observed.iter().collect();
+        }
+        let current_bikes: u32 = complete.iter().map(|(current, _)| current).sum();
+        let weekly: Vec<f64> = counted
+            .iter()
+            .map(|&week| {
+                complete
+                    .iter()
+                    .map(|(_, levels)| f64::from(levels[week].unwrap_or(0)))
+                    .sum()
+            })
+            .collect();
+        let comparison = typical::deviation(f64::from(current_bikes), &weekly);
+        let level = TypicalLevel::from_deviation(comparison.map(|(_, deviation)| deviation));
+
+        let weekday = now.format("%A").to_string();
+        let hour_utc = now.hour();
+        let summary = match comparison {
+            _ if stations.is_empty() => format!(
+                "No station within {} meters of the requested point",
+                input.radius_meters
+            ),
+            Some((typical_bikes, _)) => format!(
+                "{} for a {weekday} at {hour_utc:02}:00 UTC: {current_bikes} bikes against \
+                 {typical_bikes:.1} on average over the last {} weeks",
+                level.describe(),
+                weekly.len()
+            ),
+            None => format!(
+                "No history from previous {weekday}s around {hour_utc:02}:00 UTC: history is \
+                 kept for {retention_hours} hours, VELIB_HISTORY_RETENTION_HOURS must be over \
+                 168 to compare with last week"
+            ),
+        };
+
+        Ok(CompareWithTypicalOutput {
+            station_code: center
+                .is_none()
+                .then(|| stations[0].reference.station_code.to_string()),
+            stations_compared: complete.len() as u32,
+            weekday: weekday.to_lowercase(),
+            hour_utc,
+            current_bikes,
+            typical_bikes: comparison
+                .map(|(typical_bikes, _)| (typical_bikes * 10.0).round() / 10.0),
+            weeks_compared: weekly.len() as u32,
+            deviation: comparison.map(|(_, deviation)| (deviation * 100.0).round() / 100.0),
+            level,
+            summary,
+        })
+    }
+
+    /// Stations whose availability changed since a snapshot id or time
+    /// returned earlier, so a polling client only fetches what moved
+    pub async fn diff_station_status(
+        &self,
+        input: DiffStationStatusInput,
+    ) -> Result<DiffStationStatusOutput> {
+        if input.since_snapshot.is_some() && input.since.is_some() {
+            return Err(Error::Validation(
+                "Give since_snapshot or since, not both".to_string(),
+            ));
+        }
+
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+        let log = data_client.change_log();
+        let (snapshot, snapshot_at) = log
+            .latest()
+            .ok_or_else(|| Error::Internal(anyhow::anyhow!("No real-time snapshot yet")))?;
+        let baseline = match (&input.since_snapshot, input.since) {
+            (Some(since_snapshot), _) => log.since_snapshot(since_snapshot),
+            (None, Some(since)) => log.since_time(since),
+            (None, None) => None,
+        };
+        let full_resync = baseline.is_none();
+        let changes = baseline.unwrap_or_else(|| {
+            let mut everything: Vec<StationChange> = all_stations
+                .iter()
+                .filter_map(|station| {
+                    let status = station.real_time.as_ref()?;
+                    Some(StationChange {
+                        previous: None,
+                        current: StationUpdate::new(&station.reference.station_code, status),
+                    })
+                })
+                .collect();
+            everything.sort_by(|a, b| a.current.station_code.cmp(&b.current.station_code));
+            everything
+        });
+
+        Ok(DiffStationStatusOutput {
+            snapshot: snapshot.to_string(),
+            snapshot_at,
+            full_resync,
+            changes: changes.into_iter().map(status_change).collect(),
+        })
+    }
+
+    /// Citywide totals and any advisories about unusual conditions
+    pub async fn get_system_overview(&self) -> Result<GetSystemOverviewOutput> {
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+        let stations: Vec<&EnrichedStation> = all_stations.iter().collect();
+        Ok(GetSystemOverviewOutput {
+            system: area_statistics(&stations),
+            advisories: data_client.advisories(),
+        })
+    }
+
+    /// Mechanical and electric bikes docked citywide, and optionally how the
+    /// split moved over a trailing window
+    pub async fn get_fleet_composition(
+        &self,
+        input: GetFleetCompositionInput,
+    ) -> Result<GetFleetCompositionOutput> {
+        if input
+            .window_minutes
+            .is_some_and(|minutes| !(1..=MAX_AVAILABILITY_WINDOW_MINUTES).contains(&minutes))
+        {
+            return Err(Error::Validation(format!(
+                "window_minutes must be between 1 and {MAX_AVAILABILITY_WINDOW_MINUTES}"
+            )));
+        }
+        if !(1..=MAX_AVAILABILITY_WINDOW_MINUTES).contains(&input.interval_minutes) {
+            return Err(Error::Validation(format!(
+                "interval_minutes must be between 1 and {MAX_AVAILABILITY_WINDOW_MINUTES}"
+            )));
+        }
+
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+        let reporting: Vec<&BikeAvailability> = all_stations
+            .iter()
+            .filter_map(|station| station.real_time.as_ref().map(|rt| &rt.bikes))
+            .collect();
+        let current = fleet_composition(
+            reporting
+                .iter()
+                .map(|bikes| u32::from(bikes.mechanical))
+                .sum(),
+            reporting
+                .iter()
+                .map(|bikes| u32::from(bikes.electric))
+                .sum(),
+        );
+
+        let history = input.window_minutes.map_or_else(Vec::new, |minutes| {
+            let since = self.clock.now() - chrono::Duration::minutes(i64::from(minutes));
+            fleet_points(&data_client.fleet_history(since), input.interval_minutes)
+        });
+
+        Ok(GetFleetCompositionOutput {
+            current,
+            stations_reporting: u32::try_from(reporting.len()).unwrap_or(u32::MAX),
+            window_minutes: input.window_minutes,
+            history,
+        })
+    }
+
+    pub async fn plan_bike_journey(
+        &self,
+        input: PlanBikeJourneyInput,
+    ) -> Result<PlanBikeJourneyOutput> {
+        let input = input.sanitize()?;
+        self.service_area.check(&input.origin)?;
+        self.service_area.check(&input.destination)?;
+
+        // Find nearby stations for pickup and dropoff using live data
+        let mut data_client = self.data_client.write().await;
+        let all_stations = data_client
+            .get_all_stations_within(true, self.max_data_age)
+            .await?;
+
+        // Get preferences or use defaults
+        let preferences = input.preferences.unwrap_or_default();
+        if preferences.bike_type.levels().is_empty() {
+            return Err(Error::Validation(
+                "bike_type needs at least one bike type".to_string(),
+            ));
+        }
+
+        let suspected = |station: &EnrichedStation| {
+            station
+                .station
+                .real_time
+                .as_ref()
+                .is_some_and(|rt| rt.suspected_out_of_service)
+        };
+
+        // Find pickup stations near origin, with the most preferred bike type
+        // each has; stations with a more preferred type come first
+        let mut pickup_candidates: Vec<(StationWithDistance, usize, &BikeTypeFilter)> =
+            all_stations
+                .iter()
+                .filter_map(|station| {
+                    let distance = self.walking_distance(
+                        &input.origin,
+                        &station.reference.coordinates,
+                        preferences.barrier_aware,
+                        preferences.max_walk_distance,
+                    );
+                    if distance > preferences.max_walk_distance
+                        || !station.can_rent
+                        || (suspected(station) && !preferences.include_suspected_out_of_service)
+                    {
+                        return None;
+                    }
+                    let (level, bike_type) = preferences
+                        .bike_type
+                        .first_match(|bike_type| station.has_available_bikes(bike_type))?;
+                    Some((
+                        StationWithDistance {
+                            station: station.station.clone(),
+                            distance_meters: distance,
+                            walk_time_minutes: self.walking_speed.minutes(distance),
+                        },
+                        level,
+                        bike_type,
+                    ))
+                })
+                .collect();
 
         // Find dropoff stations near destination
         let mut dropoff_candidates: Vec<StationWithDistance> = all_stations
             .iter()
             .filter_map(|station| {
-                let distance = input
-                    .destination
-                    .distance_to(&station.reference.coordinates)
-                    as u32;
+                let distance = self.walking_distance(
+                    &input.destination,
+                    &station.reference.coordinates,
+                    preferences.barrier_aware,
+                    preferences.max_walk_distance,
+                );
 
                 if distance <= preferences.max_walk_distance
-                    && station.is_operational()
+                    && station.can_return
+                    && (!suspected(station) || preferences.include_suspected_out_of_service)
                     && station.has_available_docks(1)
                 // At least 1 dock available
                 {
                     Some(StationWithDistance {
-                        station: station.clone(),
+                        station: station.station.clone(),
                         distance_meters: distance,
+                        walk_time_minutes: self.walking_speed.minutes(distance),
                     })
                 } else {
                     None
@@ -353,19 +1586,35 @@ impl McpToolHandler {
             })
             .collect();
 
+        drop(data_client);
+
+        self.route_walks(
+            &input.origin,
+            pickup_candidates.iter_mut().map(|(station, _, _)| station),
+            preferences.barrier_aware,
+        )
+        .await;
+        pickup_candidates.retain(|(s, _, _)| s.distance_meters <= preferences.max_walk_distance);
+        pickup_candidates.sort_by_key(|(s, level, _)| (*level, s.distance_meters));
+        pickup_candidates.truncate(3);
+
+        self.route_walks(
+            &input.destination,
+            dropoff_candidates.iter_mut(),
+            preferences.barrier_aware,
+        )
+        .await;
+        dropoff_candidates.retain(|s| s.distance_meters <= preferences.max_walk_distance);
         dropoff_candidates.sort_by_key(|s| s.distance_meters);
         dropoff_candidates.truncate(3);
 
-        let pickup_stations = pickup_candidates;
-        let dropoff_stations = dropoff_candidates;
-
         // Generate journey recommendations
         let mut recommendations = Vec::new();
 
-        if !pickup_stations.is_empty() && !dropoff_stations.is_empty() {
-            // Create recommendations by pairing closest pickup with closest dropoff
-            let best_pickup = &pickup_stations[0];
-            let best_dropoff = &dropoff_stations[0];
+        if let (Some((best_pickup, preference_level, bike_type)), Some(best_dropoff)) =
+            (pickup_candidates.first(), dropoff_candidates.first())
+        {
+            // Create recommendations by pairing the best pickup with the closest dropoff
 
             // Calculate confidence score based on walking distances
             let max_walk = f64::from(preferences.max_walk_distance);
@@ -377,11 +1626,32 @@ impl McpToolHandler {
                 pickup_station: best_pickup.station.clone(),
                 dropoff_station: best_dropoff.station.clone(),
                 walk_to_pickup: best_pickup.distance_meters,
+                walk_to_pickup_minutes: best_pickup.walk_time_minutes,
                 walk_from_dropoff: best_dropoff.distance_meters,
+                walk_from_dropoff_minutes: best_dropoff.walk_time_minutes,
                 confidence_score: confidence_score.clamp(0.1, 1.0),
+                bike_type: (*bike_type).clone(),
+                preference_level: *preference_level,
+                route: match input.route_format {
+                    Some(format) => Some(
+                        self.bike_route(
+                            &best_pickup.station.reference.coordinates,
+                            &best_dropoff.station.reference.coordinates,
+                            format,
+                        )
+                        .await,
+                    ),
+                    None => None,
+                },
             });
         }
 
+        let pickup_stations = pickup_candidates
+            .into_iter()
+            .map(|(station, _, _)| station)
+            .collect();
+        let dropoff_stations = dropoff_candidates;
+
         Ok(PlanBikeJourneyOutput {
             journey: BikeJourney {
                 pickup_stations,
@@ -391,6 +1661,175 @@ impl McpToolHandler {
         })
     }
 
+    /// Plan several journeys at once, concurrently, and flag the stations
+    /// their best recommendations would compete for
+    pub async fn plan_multiple_journeys(
+        &self,
+        input: PlanMultipleJourneysInput,
+    ) -> Result<PlanMultipleJourneysOutput> {
+        let input = input.sanitize()?;
+        if !(1..=MAX_BULK_JOURNEYS).contains(&input.journeys.len()) {
+            return Err(Error::Validation(format!(
+                "Plan between 1 and {MAX_BULK_JOURNEYS} journeys"
+            )));
+        }
+        for journey in &input.journeys {
+            self.service_area.check(&journey.origin)?;
+            self.service_area.check(&journey.destination)?;
+        }
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, journey) in input.journeys.into_iter().enumerate() {
+            let handler = self.clone();
+            let input = PlanBikeJourneyInput {
+                origin: journey.origin,
+                destination: journey.destination,
+                preferences: input.preferences.clone(),
+                route_format: input.route_format,
+            };
+            tasks.spawn(async move { (index, handler.plan_bike_journey(input).await) });
+        }
+
+        let mut planned = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            let (index, output) = joined
+                .map_err(|e| Error::Internal(anyhow::anyhow!("Journey planning failed: {e}")))?;
+            planned.push((index, output?.journey));
+        }
+        planned.sort_by_key(|(index, _)| *index);
+        let journeys: Vec<BikeJourney> = planned.into_iter().map(|(_, journey)| journey).collect();
+
+        Ok(PlanMultipleJourneysOutput {
+            warnings: shared_station_warnings(&journeys),
+            journeys,
+        })
+    }
+
+    /// The stops a `near_transit` filter designates, and how far from them stations may be
+    fn transit_filter(
+        &self,
+        near_transit: Option<&str>,
+        radius_meters: Option<u32>,
+    ) -> Result<(Option<TransitSelection<'_>>, u32)> {
+        let radius = radius_meters.unwrap_or(DEFAULT_TRANSIT_RADIUS_METERS);
+        if radius > MAX_SEARCH_RADIUS {
+            return Err(Error::SearchRadiusTooLarge {
+                radius,
+                max: MAX_SEARCH_RADIUS,
+            });
+        }
+        let selection = near_transit
+            .map(|query| self.transit.select(query))
+            .transpose()?;
+        Ok((selection, radius))
+    }
+
+    /// Meters from `from` to `to`, walking around barriers if `barrier_aware`
+    ///
+    /// Barriers only lengthen walks, so ones already beyond `within` as the
+    /// crow flies are left as they are. With a routing engine this is the
+    /// straight line, a lower bound [`Self::route_walks`] then replaces.
+    fn walking_distance(
+        &self,
+        from: &Coordinates,
+        to: &Coordinates,
+        barrier_aware: bool,
+        within: u32,
+    ) -> u32 {
+        if self.routing.is_some() {
+            return from.distance_to(to) as u32;
+        }
+        self.estimated_walk(from, to, barrier_aware, within)
+    }
+
+    /// [`Self::walking_distance`] without a routing engine: the straight line,
+    /// around barriers, lengthened by the detour factor
+    fn estimated_walk(
+        &self,
+        from: &Coordinates,
+        to: &Coordinates,
+        barrier_aware: bool,
+        within: u32,
+    ) -> u32 {
+        let direct = from.distance_to(to);
+        let walked = if barrier_aware && direct <= f64::from(within) {
+            self.barriers.walking_distance(from, to)
+        } else {
+            direct
+        };
+        (walked * self.detour_factor) as u32
+    }
+
+    /// Replace the straight-line distances of `stations` from `from` with
+    /// street-network ones from the routing engine, if one is configured
+    ///
+    /// Stations the engine finds no path to, or all of them when it fails,
+    /// fall back to [`Self::estimated_walk`].
+    async fn route_walks<'a>(
+        &self,
+        from: &Coordinates,
+        stations: impl Iterator<Item = &'a mut StationWithDistance>,
+        barrier_aware: bool,
+    ) {
+        let Some(routing) = &self.routing else {
+            return;
+        };
+        let mut stations: Vec<&mut StationWithDistance> = stations.collect();
+        if stations.is_empty() {
+            return;
+        }
+        let targets: Vec<Coordinates> = stations
+            .iter()
+            .map(|s| s.station.reference.coordinates)
+            .collect();
+        let routed = routing
+            .walking_distances(from, &targets)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Routing engine failed, estimating walks: {e}");
+                vec![None; targets.len()]
+            });
+
+        for (station, routed) in stations.iter_mut().zip(routed) {
+            station.distance_meters = routed.unwrap_or_else(|| {
+                let to = &station.station.reference.coordinates;
+                self.estimated_walk(from, to, barrier_aware, u32::MAX)
+            });
+            station.walk_time_minutes = self.walking_speed.minutes(station.distance_meters);
+        }
+    }
+
+    /// The ride from `from` to `to` in `format`, from the routing engine if
+    /// one is configured and answers, else a straight line
+    async fn bike_route(
+        &self,
+        from: &Coordinates,
+        to: &Coordinates,
+        format: RouteFormat,
+    ) -> BikeRoute {
+        let routed = match &self.routing {
+            Some(routing) => routing
+                .cycling_route(from, to)
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!("Routing engine failed, drawing a straight ride: {e}")
+                })
+                .ok()
+                .filter(|route| route.len() >= 2),
+            None => None,
+        };
+        let (source, route) = match routed {
+            Some(route) => (RouteSource::RoutingEngine, route),
+            None => (RouteSource::StraightLine, vec![*from, *to]),
+        };
+        BikeRoute {
+            source,
+            distance_meters: route_length_meters(&route) as u32,
+            geojson: (format == RouteFormat::GeoJson).then(|| LineString::new(&route)),
+            polyline: (format == RouteFormat::Polyline).then(|| encode_polyline(&route, 5)),
+        }
+    }
+
     /// Clean up expired cache entries in the data client
     pub async fn cleanup_cache(&self) {
         let data_client = self.data_client.read().await;
@@ -404,7 +1843,7 @@ impl McpToolHandler {
     }
 
     /// Get reference stations for resource endpoints
-    pub async fn get_reference_stations(&self) -> Result<Vec<crate::types::StationReference>> {
+    pub async fn get_reference_stations(&self) -> Result<Arc<[crate::types::StationReference]>> {
         let mut data_client = self.data_client.write().await;
         data_client.fetch_reference_stations().await
     }
@@ -412,7 +1851,7 @@ impl McpToolHandler {
     /// Get real-time status for resource endpoints
     pub async fn get_realtime_status(
         &self,
-    ) -> Result<std::collections::HashMap<String, crate::types::RealTimeStatus>> {
+    ) -> Result<Arc<std::collections::HashMap<String, crate::types::RealTimeStatus>>> {
         let mut data_client = self.data_client.write().await;
         data_client.fetch_realtime_status().await
     }
@@ -421,25 +1860,428 @@ impl McpToolHandler {
     pub async fn get_complete_stations(
         &self,
         include_realtime: bool,
-    ) -> Result<Vec<crate::types::VelibStation>> {
+    ) -> Result<Arc<[EnrichedStation]>> {
         let mut data_client = self.data_client.write().await;
         data_client.get_all_stations(include_realtime).await
     }
 
-    /// Test connectivity to data sources for health checks
-    pub async fn test_connectivity(&self) -> Result<()> {
+    /// Force a real-time refresh, e.g. from the background poller
+    pub async fn refresh_realtime_status(&self) -> Result<usize> {
+        let mut data_client = self.data_client.write().await;
+        Ok(data_client.refresh_realtime_status().await?.len())
+    }
+
+    /// When the real-time data tools answer from was fetched
+    #[must_use]
+    pub fn data_snapshot_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.metrics.realtime_snapshot_at()
+    }
+
+    /// Which reference and real-time data tools answer from
+    #[must_use]
+    pub fn data_version(&self) -> Option<crate::data::DataVersion> {
+        self.metrics.data_version()
+    }
+
+    /// [`Self::data_version`] and [`Self::data_snapshot_at`] as of now
+    #[must_use]
+    pub fn data_stamp(&self) -> super::meta::DataStamp {
+        super::meta::DataStamp {
+            version: self.data_version(),
+            snapshot_at: self.data_snapshot_at(),
+        }
+    }
+
+    /// Unusual citywide conditions, e.g. a strike day or an outage
+    pub async fn advisories(&self) -> Vec<crate::data::Advisory> {
+        let data_client = self.data_client.read().await;
+        data_client.advisories()
+    }
+
+    /// Count a request whose handler panicked
+    pub fn record_panic(&self) {
+        self.metrics.record_panic();
+    }
+
+    /// Get the upstream feed watchdog status for health checks
+    pub async fn feed_status(&self) -> crate::data::FeedStatus {
+        let data_client = self.data_client.read().await;
+        data_client.feed_status()
+    }
+
+    /// Trim and thin the availability history, then trim further if memory
+    /// is past its soft limit
+    pub async fn compact_history(&self) -> crate::data::HistoryStats {
+        let mut data_client = self.data_client.write().await;
+        let stats = data_client.compact_history();
+        if data_client.enforce_memory_limit().await {
+            data_client.history_stats()
+        } else {
+            stats
+        }
+    }
+
+    /// Resident memory and what the snapshots, history and caches hold
+    pub async fn memory_stats(&self) -> crate::data::MemoryStats {
+        let data_client = self.data_client.read().await;
+        data_client.memory_stats().await
+    }
+
+    /// Every station's recorded samples since `since`, for export
+    pub async fn history_records(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<crate::data::HistoryRecord> {
+        let data_client = self.data_client.read().await;
+        data_client.history_records(since)
+    }
+
+    /// How much the availability history holds
+    pub async fn history_stats(&self) -> crate::data::HistoryStats {
+        let data_client = self.data_client.read().await;
+        data_client.history_stats()
+    }
+
+    /// Retention settings of the change log and how much it holds
+    pub async fn change_log_stats(&self) -> crate::data::ChangeLogStats {
+        let data_client = self.data_client.read().await;
+        data_client.change_log().stats()
+    }
+
+    /// Get the bus announcing availability changes
+    pub async fn events(&self) -> Arc<crate::events::EventBus> {
+        let data_client = self.data_client.read().await;
+        data_client.events()
+    }
+
+    /// Get the counters recorded by the data client
+    pub async fn metrics(&self) -> Arc<crate::metrics::Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Probe the upstream datasets and keep the result for health checks
+    pub async fn probe_upstream(&self) -> crate::data::UpstreamProbe {
+        let mut data_client = self.data_client.write().await;
+        data_client.probe_upstream().await
+    }
+
+    /// Validate the reference catalog and compare it with the previous validation
+    pub async fn validate_reference(&self) -> Result<crate::data::ReferenceReport> {
+        let mut data_client = self.data_client.write().await;
+        data_client.validate_reference().await
+    }
+
+    /// The last reference catalog validation, running one if none did yet
+    pub async fn reference_report(&self) -> Result<crate::data::ReferenceReport> {
         let mut data_client = self.data_client.write().await;
-        // Simple connectivity test by fetching reference data
-        data_client.get_all_stations(false).await?;
-        Ok(())
+        match data_client.reference_report() {
+            Some(report) => Ok(report),
+            None => data_client.validate_reference().await,
+        }
+    }
+
+    /// Reference station codes joined with the GBFS feed's station ids
+    pub async fn station_id_mapping(&self) -> Result<crate::data::StationIdMapping> {
+        let mut data_client = self.data_client.write().await;
+        data_client.station_id_mapping().await
+    }
+
+    /// Get the result of the last scheduled upstream probe
+    pub async fn last_probe(&self) -> Option<crate::data::UpstreamProbe> {
+        let data_client = self.data_client.read().await;
+        data_client.last_probe()
+    }
+}
+
+/// Outcome of [`McpToolHandler::check_limits`]
+fn default_max_data_age() -> chrono::Duration {
+    chrono::Duration::seconds(crate::data::client::DEFAULT_REALTIME_MAX_AGE_SECONDS)
+}
+
+struct CheckedLimits {
+    applied: AppliedLimits,
+    truncated: bool,
+}
+
+impl CheckedLimits {
+    /// The applied limits, reported only when they differ from the request
+    fn reported(&self) -> Option<AppliedLimits> {
+        self.truncated.then_some(self.applied)
     }
 }
 
+fn status_change(change: StationChange) -> StationStatusChange {
+    let StationChange { previous, current } = change;
+    StationStatusChange {
+        bikes_delta: previous
+            .as_ref()
+            .map(|previous| i32::from(current.bikes.total()) - i32::from(previous.bikes.total())),
+        previous_status: previous
+            .map(|previous| previous.status)
+            .filter(|status| *status != current.status),
+        station_code: current.station_code,
+        mechanical_bikes: current.bikes.mechanical,
+        electric_bikes: current.bikes.electric,
+        available_docks: current.available_docks,
+        status: current.status,
+    }
+}
+
+/// A station's `(time, bikes)` samples in a window, oldest first, and its current bike count
+type WindowTrack = (Vec<(chrono::DateTime<chrono::Utc>, u16)>, u16);
+
+/// Min, max and mean of the bikes available across stations over a window
+///
+/// At every sampled instant a station counts its latest sample, or its first
+/// one before it was sampled; the current counts close the window as one more
+/// instant.
+fn availability_window(minutes: u32, tracks: &[WindowTrack]) -> AvailabilityWindow {
+    let mut instants: Vec<chrono::DateTime<chrono::Utc>> = tracks
+        .iter()
+        .flat_map(|(samples, _)| samples.iter().map(|&(at, _)| at))
+        .collect();
+    instants.sort_unstable();
+    instants.dedup();
+
+    let mut totals: Vec<u32> = instants
+        .iter()
+        .map(|&instant| {
+            tracks
+                .iter()
+                .map(|(samples, current)| {
+                    let seen = samples.partition_point(|&(at, _)| at <= instant);
+                    samples
+                        .get(seen.saturating_sub(1))
+                        .map_or(*current, |&(_, bikes)| bikes)
+                })
+                .map(u32::from)
+                .sum()
+        })
+        .collect();
+    totals.push(tracks.iter().map(|&(_, current)| u32::from(current)).sum());
+
+    AvailabilityWindow {
+        window_minutes: minutes,
+        samples: totals.len(),
+        min_bikes: totals.iter().copied().min().unwrap_or(0),
+        max_bikes: totals.iter().copied().max().unwrap_or(0),
+        mean_bikes: f64::from(totals.iter().sum::<u32>()) / totals.len() as f64,
+    }
+}
+
+fn fleet_composition(mechanical: u32, electric: u32) -> FleetComposition {
+    let total = mechanical + electric;
+    FleetComposition {
+        mechanical,
+        electric,
+        total,
+        electric_share: if total == 0 {
+            0.0
+        } else {
+            f64::from(electric) / f64::from(total)
+        },
+    }
+}
+
+/// Mean composition of `samples` per clock-aligned interval, oldest first
+fn fleet_points(samples: &[FleetSample], interval_minutes: u32) -> Vec<FleetCompositionPoint> {
+    let interval = i64::from(interval_minutes) * 60;
+    let mut buckets: Vec<(i64, Vec<&FleetSample>)> = Vec::new();
+    for sample in samples {
+        let start = sample.at.timestamp().div_euclid(interval) * interval;
+        match buckets.last_mut() {
+            Some((bucket, members)) if *bucket == start => members.push(sample),
+            _ => buckets.push((start, vec![sample])),
+        }
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(start, members)| {
+            let count = u32::try_from(members.len()).ok()?;
+            let mechanical: u32 = members.iter().map(|sample| sample.mechanical).sum();
+            let electric: u32 = members.iter().map(|sample| sample.electric).sum();
+            let mean = |sum: u32| (f64::from(sum) / f64::from(count)).round() as u32;
+            Some(FleetCompositionPoint {
+                at: chrono::DateTime::from_timestamp(start, 0)?,
+                samples: members.len(),
+                composition: FleetComposition {
+                    // The share of the summed counts, not of the rounded means
+                    electric_share: fleet_composition(mechanical, electric).electric_share,
+                    ..fleet_composition(mean(mechanical), mean(electric))
+                },
+            })
+        })
+        .collect()
+}
+
+/// Aggregate capacity and live availability over `stations`
+/// Stations the best recommendations of several `journeys` share, when they
+/// hold fewer bikes (for pickups) or docks (for dropoffs) than journeys use them
+fn shared_station_warnings(journeys: &[BikeJourney]) -> Vec<SharedStationWarning> {
+    let mut groups: Vec<(
+        SharedStationUse,
+        &VelibStation,
+        Vec<usize>,
+        Vec<&BikeTypeFilter>,
+    )> = Vec::new();
+    for (index, journey) in journeys.iter().enumerate() {
+        let Some(best) = journey.recommendations.first() else {
+            continue;
+        };
+        for (station_use, station) in [
+            (SharedStationUse::Pickup, &best.pickup_station),
+            (SharedStationUse::Dropoff, &best.dropoff_station),
+        ] {
+            let group = groups.iter_mut().find(|(u, s, _, _)| {
+                *u == station_use && s.reference.station_code == station.reference.station_code
+            });
+            match group {
+                Some((_, _, indices, bike_types)) => {
+                    indices.push(index);
+                    bike_types.push(&best.bike_type);
+                }
+                None => groups.push((station_use, station, vec![index], vec![&best.bike_type])),
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, _, indices, _)| indices.len() > 1)
+        .filter_map(|(station_use, station, indices, bike_types)| {
+            let real_time = station.real_time.as_ref();
+            let available = match station_use {
+                SharedStationUse::Pickup => real_time.map_or(0, |rt| match bike_types.first() {
+                    Some(BikeTypeFilter::MechanicalOnly)
+                        if bike_types
+                            .iter()
+                            .all(|t| **t == BikeTypeFilter::MechanicalOnly) =>
+                    {
+                        rt.bikes.mechanical
+                    }
+                    Some(BikeTypeFilter::ElectricOnly)
+                        if bike_types
+                            .iter()
+                            .all(|t| **t == BikeTypeFilter::ElectricOnly) =>
+                    {
+                        rt.bikes.electric
+                    }
+                    _ => rt.bikes.total(),
+                }),
+                SharedStationUse::Dropoff => real_time.map_or(0, |rt| rt.available_docks),
+            };
+            if indices.len() <= usize::from(available) {
+                return None;
+            }
+            let (verb, what) = match station_use {
+                SharedStationUse::Pickup => ("pick up at", "bike"),
+                SharedStationUse::Dropoff => ("drop off at", "dock"),
+            };
+            let message = format!(
+                "{} journeys {verb} {} ({}), which has {available} {what}{} available",
+                indices.len(),
+                station.reference.name,
+                station.reference.station_code,
+                if available == 1 { "" } else { "s" },
+            );
+            Some(SharedStationWarning {
+                station_code: station.reference.station_code.to_string(),
+                station_name: station.reference.name.to_string(),
+                station_use,
+                journeys: indices,
+                available,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn area_statistics(stations: &[&EnrichedStation]) -> AreaStatistics {
+    let total_stations = stations.len() as u32;
+    let operational_stations = stations
+        .iter()
+        .filter(|station| station.operational)
+        .count() as u32;
+
+    let mut total_capacity = 0u32;
+    let mut total_mechanical = 0u32;
+    let mut total_electric = 0u32;
+    let mut total_available_docks = 0u32;
+
+    for station in stations {
+        total_capacity += u32::from(station.reference.capacity);
+
+        if let Some(rt) = &station.real_time {
+            total_mechanical += u32::from(rt.bikes.mechanical);
+            total_electric += u32::from(rt.bikes.electric);
+            total_available_docks += u32::from(rt.available_docks);
+        }
+    }
+
+    let total_bikes = total_mechanical + total_electric;
+    let occupancy_rate = if total_capacity > 0 {
+        f64::from(total_bikes) / f64::from(total_capacity)
+    } else {
+        0.0
+    };
+
+    AreaStatistics {
+        total_stations,
+        operational_stations,
+        total_capacity,
+        available_bikes: AvailableBikesStats {
+            mechanical: total_mechanical,
+            electric: total_electric,
+            total: total_bikes,
+        },
+        available_docks: total_available_docks,
+        occupancy_rate,
+    }
+}
+
+/// Whether a station offers the service `purpose` needs, or full service without one
+fn serves(station: &EnrichedStation, purpose: Option<StationPurpose>) -> bool {
+    match purpose {
+        Some(StationPurpose::Pickup) => station.can_rent,
+        Some(StationPurpose::Dropoff) => station.can_return,
+        None => station.operational,
+    }
+}
+
+/// Whether a station satisfies the bike type and minimum counts of a filter
+fn matches_availability(station: &VelibStation, filter: &AvailabilityFilter) -> bool {
+    if let Some(bike_type) = &filter.bike_type {
+        if !station.has_available_bikes(bike_type) {
+            return false;
+        }
+    }
+
+    let Some(rt) = &station.real_time else {
+        // Without real-time data only the unconstrained filter can match
+        return filter.min_bikes.is_none() && filter.min_docks.is_none();
+    };
+
+    let bikes = match filter
+        .bike_type
+        .as_ref()
+        .unwrap_or(&BikeTypeFilter::AnyType)
+    {
+        BikeTypeFilter::MechanicalOnly => rt.bikes.mechanical,
+        BikeTypeFilter::ElectricOnly => rt.bikes.electric,
+        BikeTypeFilter::AnyType => rt.bikes.total(),
+    };
+    filter.min_bikes.is_none_or(|min| bikes >= min)
+        && filter.min_docks.is_none_or(|min| rt.available_docks >= min)
+}
+
 impl Default for JourneyPreferences {
     fn default() -> Self {
         Self {
-            bike_type: BikeTypeFilter::AnyType,
+            bike_type: BikeTypePreference::default(),
             max_walk_distance: 500,
+            barrier_aware: false,
+            include_suspected_out_of_service: false,
         }
     }
 }
@@ -0,0 +1,185 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::Error;
+
+/// Methods some route answers; anything else is turned away before routing
+const ALLOWED_METHODS: [Method; 4] = [Method::GET, Method::HEAD, Method::POST, Method::DELETE];
+
+/// An address, or a block of them in CIDR notation (`203.0.113.0/24`, `2001:db8::/32`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 peers of a dual-stack socket show up as `::ffff:a.b.c.d`
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = Error;
+
+    fn from_str(range: &str) -> crate::Result<Self> {
+        let invalid = || Error::Config(format!("Invalid IP address or CIDR block '{range}'"));
+        let (address, prefix_len) = match range.trim().split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (range.trim(), None),
+        };
+        let network: IpAddr = address.parse().map_err(|_| invalid())?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse()
+                .ok()
+                .filter(|&len| len <= max_len)
+                .ok_or_else(invalid)?,
+            None => max_len,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// Turn away traffic no route should see before it reaches them, and mark
+/// every response `X-Content-Type-Options: nosniff`
+///
+/// Rejected: methods no route answers (`405`), `POST /mcp` bodies declared as
+/// anything but `application/json` (`415`) and, when `allowed_ips` is not empty, peers
+/// outside it (`403`). Each rejection is logged. The peer address comes from
+/// the connection, so the server must be served with
+/// `into_make_service_with_connect_info::<SocketAddr>()` for the allowlist to
+/// let anyone in.
+pub(crate) fn layer(router: Router, allowed_ips: Arc<[IpRange]>) -> Router {
+    router.layer(middleware::from_fn_with_state(allowed_ips, harden))
+}
+
+async fn harden(
+    State(allowed_ips): State<Arc<[IpRange]>>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = peer.map(|ConnectInfo(peer)| peer.ip());
+    let mut response = match rejection(&allowed_ips, peer, &request) {
+        Some((status, reason)) => {
+            warn!(
+                method = %request.method(),
+                path = request.uri().path(),
+                peer = ?peer,
+                status = status.as_u16(),
+                "Rejected request: {}",
+                reason
+            );
+            let mut response = (status, Json(json!({ "error": reason }))).into_response();
+            if status == StatusCode::METHOD_NOT_ALLOWED {
+                response.headers_mut().insert(
+                    header::ALLOW,
+                    HeaderValue::from_static("GET, HEAD, POST, DELETE"),
+                );
+            }
+            response
+        }
+        None => next.run(request).await,
+    };
+    response.headers_mut().insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    response
+}
+
+/// Why `request` is turned away, if it is
+fn rejection(
+    allowed_ips: &[IpRange],
+    peer: Option<IpAddr>,
+    request: &Request,
+) -> Option<(StatusCode, &'static str)> {
+    if !allowed_ips.is_empty() {
+        let allowed = peer.is_some_and(|peer| allowed_ips.iter().any(|range| range.contains(peer)));
+        if !allowed {
+            return Some((StatusCode::FORBIDDEN, "Address not allowed"));
+        }
+    }
+    if !ALLOWED_METHODS.contains(request.method()) {
+        return Some((StatusCode::METHOD_NOT_ALLOWED, "Method not allowed"));
+    }
+    if request.method() == Method::POST && request.uri().path() == "/mcp" {
+        // Some clients send no type at all; browsers always declare one, so
+        // cross-site form posts still get turned away
+        let json = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .is_none_or(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.split(';').next())
+                    .is_some_and(|media_type| {
+                        media_type.trim().eq_ignore_ascii_case("application/json")
+                    })
+            });
+        if !json {
+            return Some((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Content-Type must be application/json",
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_ranges() {
+        let block: IpRange = "203.0.113.0/24".parse().unwrap();
+        assert!(block.contains("203.0.113.77".parse().unwrap()));
+        assert!(!block.contains("203.0.114.1".parse().unwrap()));
+        // The same peer seen through a dual-stack socket
+        assert!(block.contains("::ffff:203.0.113.77".parse().unwrap()));
+
+        let single: IpRange = " 2001:db8::1 ".parse().unwrap();
+        assert!(single.contains("2001:db8::1".parse().unwrap()));
+        assert!(!single.contains("2001:db8::2".parse().unwrap()));
+        let everyone: IpRange = "0.0.0.0/0".parse().unwrap();
+        assert!(everyone.contains("198.51.100.7".parse().unwrap()));
+        assert!(!everyone.contains("::1".parse().unwrap()));
+
+        for invalid in ["203.0.113.0/33", "localhost", "10.0.0.0/", ""] {
+            assert!(matches!(invalid.parse::<IpRange>(), Err(Error::Config(_))));
+        }
+    }
+}
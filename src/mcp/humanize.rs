@@ -0,0 +1,281 @@
+use serde_json::{Map, Value};
+
+/// Language numbers and units are written in by [`result`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+}
+
+/// Units distances and areas are written in; the numeric fields stay metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    /// From `metric` or `imperial`, in any case
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("metric") {
+            Some(Self::Metric)
+        } else if name.eq_ignore_ascii_case("imperial") {
+            Some(Self::Imperial)
+        } else {
+            None
+        }
+    }
+}
+
+const FEET_PER_METER: f64 = 3.280_84;
+const METERS_PER_MILE: f64 = 1_609.344;
+const SQUARE_MILES_PER_KM2: f64 = 0.386_102;
+
+impl Locale {
+    /// From a language tag such as `fr` or `fr-FR`; English for anything unsupported
+    #[must_use]
+    pub fn from_language(tag: &str) -> Self {
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        if primary.eq_ignore_ascii_case("fr") {
+            Self::French
+        } else {
+            Self::English
+        }
+    }
+
+    fn decimal(self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{value:.decimals$}");
+        let (integer, fraction) = formatted
+            .split_once('.')
+            .map_or((formatted.as_str(), None), |(i, f)| (i, Some(f)));
+        let (sign, digits) = integer
+            .strip_prefix('-')
+            .map_or(("", integer), |digits| ("-", digits));
+
+        let (group, point) = match self {
+            Self::English => (',', '.'),
+            Self::French => (' ', ','),
+        };
+        let mut grouped = String::from(sign);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(group);
+            }
+            grouped.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            grouped.push(point);
+            grouped.push_str(fraction);
+        }
+        grouped
+    }
+
+    /// A distance such as "350 m" or "0.8 mi"
+    #[must_use]
+    pub fn distance(self, meters: f64, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Metric if meters < 1000.0 => {
+                format!("{} m", self.decimal(meters.round(), 0))
+            }
+            UnitSystem::Metric => format!("{} km", self.decimal(meters / 1000.0, 1)),
+            // Under a tenth of a mile, feet read better
+            UnitSystem::Imperial if meters < METERS_PER_MILE / 10.0 => {
+                format!("{} ft", self.decimal((meters * FEET_PER_METER).round(), 0))
+            }
+            UnitSystem::Imperial => {
+                format!("{} mi", self.decimal(meters / METERS_PER_MILE, 1))
+            }
+        }
+    }
+
+    fn area(self, km2: f64, units: UnitSystem) -> String {
+        match units {
+            UnitSystem::Metric => format!("{} km²", self.decimal(km2, 1)),
+            UnitSystem::Imperial => {
+                format!("{} mi²", self.decimal(km2 * SQUARE_MILES_PER_KM2, 1))
+            }
+        }
+    }
+
+    fn duration(self, minutes: f64, mode: Mode) -> String {
+        let minutes = minutes.round() as i64;
+        let time = match (minutes >= 60, self) {
+            (false, _) => format!("{minutes} min"),
+            (true, Self::English) => format!("{} h {} min", minutes / 60, minutes % 60),
+            (true, Self::French) => format!("{} h {:02}", minutes / 60, minutes % 60),
+        };
+        match (mode, self) {
+            (Mode::Plain, _) => time,
+            (Mode::Walk, Self::English) => format!("≈ {time} walk"),
+            (Mode::Walk, Self::French) => format!("≈ {time} à pied"),
+            (Mode::Bike, Self::English) => format!("≈ {time} by bike"),
+            (Mode::Bike, Self::French) => format!("≈ {time} à vélo"),
+        }
+    }
+
+    fn percent(self, share: f64) -> String {
+        let percent = self.decimal((share * 100.0).round(), 0);
+        match self {
+            Self::English => format!("{percent}%"),
+            Self::French => format!("{percent} %"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Plain,
+    Walk,
+    Bike,
+}
+
+/// Preformatted text for a numeric field, under the name it is added as
+fn text_for(key: &str, value: f64, locale: Locale, units: UnitSystem) -> Option<(String, String)> {
+    if let Some(stem) = key.strip_suffix("_meters") {
+        return Some((format!("{stem}_text"), locale.distance(value, units)));
+    }
+    if let Some(stem) = key.strip_suffix("_minutes") {
+        let mode = if stem.contains("walk") {
+            Mode::Walk
+        } else if stem.contains("bike") || stem.contains("ride") {
+            Mode::Bike
+        } else {
+            Mode::Plain
+        };
+        return Some((format!("{stem}_text"), locale.duration(value, mode)));
+    }
+    if let Some(stem) = key.strip_suffix("_km2") {
+        return Some((format!("{stem}_text"), locale.area(value, units)));
+    }
+    if matches!(key, "probability" | "occupancy" | "reliability_score") {
+        return Some((format!("{key}_text"), locale.percent(value)));
+    }
+    None
+}
+
+/// Add a `<field>_text` string next to every distance, duration, area and
+/// share in `value`, leaving the numbers themselves untouched
+pub fn humanize(value: &mut Value, locale: Locale, units: UnitSystem) {
+    match value {
+        Value::Object(object) => {
+            let additions: Map<String, Value> = object
+                .iter()
+                .filter_map(|(key, field)| text_for(key, field.as_f64()?, locale, units))
+                .filter(|(name, _)| !object.contains_key(name))
+                .map(|(name, text)| (name, Value::String(text)))
+                .collect();
+            for field in object.values_mut() {
+                humanize(field, locale, units);
+            }
+            object.extend(additions);
+        }
+        Value::Array(items) => {
+            for item in items {
+                humanize(item, locale, units);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Humanize the JSON text blocks of a tool result; other blocks are left alone
+#[must_use]
+pub fn result(mut result: Value, locale: Locale, units: UnitSystem) -> Value {
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return result;
+    };
+    for block in content {
+        let Some(text) = block.get("text").and_then(Value::as_str) else {
+            continue;
+        };
+        let Ok(mut output) = serde_json::from_str::<Value>(text) else {
+            continue;
+        };
+        humanize(&mut output, locale, units);
+        if let Ok(text) = serde_json::to_string_pretty(&output) {
+            block["text"] = Value::String(text);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_numbers_formatted_per_locale() {
+        let mut output = json!({
+            "stations": [{
+                "distance_meters": 350,
+                "walk_time_minutes": 5,
+                "occupancy": 0.853
+            }],
+            "search_metadata": {"radius_meters": 1250},
+            "total_area_km2": 1234.56,
+            "walk_to_pickup_minutes": 75
+        });
+        humanize(&mut output, Locale::French, UnitSystem::Metric);
+
+        let station = &output["stations"][0];
+        assert_eq!(station["distance_meters"], 350);
+        assert_eq!(station["distance_text"], "350 m");
+        assert_eq!(station["walk_time_text"], "≈ 5 min à pied");
+        assert_eq!(station["occupancy_text"], "85 %");
+        assert_eq!(output["search_metadata"]["radius_text"], "1,2 km");
+        assert_eq!(output["total_area_text"], "1 234,6 km²");
+        assert_eq!(output["walk_to_pickup_text"], "≈ 1 h 15 à pied");
+
+        let mut output = json!({"distance_meters": 1250, "walk_time_minutes": 5});
+        humanize(&mut output, Locale::English, UnitSystem::Metric);
+        assert_eq!(output["distance_text"], "1.2 km");
+        assert_eq!(output["walk_time_text"], "≈ 5 min walk");
+    }
+
+    #[test]
+    fn test_imperial_distances_and_areas() {
+        let mut output = json!({
+            "distance_meters": 100,
+            "search_metadata": {"radius_meters": 1250},
+            "total_area_km2": 10.0,
+            "walk_time_minutes": 5
+        });
+        humanize(&mut output, Locale::English, UnitSystem::Imperial);
+        assert_eq!(output["distance_meters"], 100);
+        assert_eq!(output["distance_text"], "328 ft");
+        assert_eq!(output["search_metadata"]["radius_text"], "0.8 mi");
+        assert_eq!(output["total_area_text"], "3.9 mi²");
+        assert_eq!(output["walk_time_text"], "≈ 5 min walk");
+
+        assert_eq!(
+            UnitSystem::from_name("Imperial"),
+            Some(UnitSystem::Imperial)
+        );
+        assert_eq!(UnitSystem::from_name("furlongs"), None);
+    }
+
+    #[test]
+    fn test_language_tags() {
+        assert_eq!(Locale::from_language("fr-FR"), Locale::French);
+        assert_eq!(Locale::from_language("FR"), Locale::French);
+        assert_eq!(Locale::from_language("en"), Locale::English);
+        assert_eq!(Locale::from_language("de"), Locale::English);
+    }
+
+    #[test]
+    fn test_only_json_text_blocks_are_humanized() {
+        let tool_result = json!({"content": [
+            {"type": "text", "text": "{\"distance_meters\": 80}"},
+            {"type": "text", "text": "| distance_meters |"}
+        ]});
+        let humanized = result(tool_result, Locale::English, UnitSystem::Metric);
+        let first: Value =
+            serde_json::from_str(humanized["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(first["distance_text"], "80 m");
+        assert_eq!(humanized["content"][1]["text"], "| distance_meters |");
+    }
+}
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{Error, Result};
+
+/// Requests remembered by default
+pub const DEFAULT_JOURNAL_ENTRIES: usize = 128;
+
+/// Bytes per entry on disk, so an entry is rewritten in place
+const SLOT_BYTES: usize = 256;
+
+/// Longest method name kept, so entries always fit their slot
+const MAX_METHOD_CHARS: usize = 64;
+
+/// One journaled request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Increases with every request, across restarts of the same journal
+    pub seq: u64,
+    pub started_at: DateTime<Utc>,
+    pub method: String,
+    /// Registered tool of a `tools/call`, `unknown` for other names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// When the real-time snapshot served at the start was fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_at: Option<DateTime<Utc>>,
+    /// Configuration version in effect at the start
+    pub config_version: u64,
+    /// How long the request took, unset while it runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    /// Error type of a failed request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The last requests a server handled, in a ring of fixed-size slots on disk
+///
+/// Each request is written when it starts and again when it finishes. After
+/// a crash, the entries never finished tell what was in flight. Writes are
+/// not synced, so the journal survives the process dying but not the machine.
+#[derive(Debug)]
+pub struct RequestJournal {
+    file: Mutex<File>,
+    slots: u64,
+    next_seq: AtomicU64,
+}
+
+impl RequestJournal {
+    /// Open the journal at `path` keeping `entries` requests, returning the
+    /// requests the previous process left unfinished, oldest first
+    pub fn open(path: &Path, entries: usize) -> Result<(Self, Vec<JournalEntry>)> {
+        let unreadable = |e: std::io::Error| {
+            Error::Config(format!(
+                "Cannot open request journal {}: {e}",
+                path.to_string_lossy()
+            ))
+        };
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(unreadable)?;
+        let mut previous = Vec::new();
+        file.read_to_end(&mut previous).map_err(unreadable)?;
+        let mut recorded: Vec<JournalEntry> = previous
+            .chunks(SLOT_BYTES)
+            .filter_map(|slot| serde_json::from_slice(slot.trim_ascii()).ok())
+            .collect();
+        recorded.sort_by_key(|entry| entry.seq);
+
+        // Start over with blank lines for slots, sized for `entries`
+        let slots = entries.max(1);
+        let mut blank = vec![b' '; SLOT_BYTES];
+        blank[SLOT_BYTES - 1] = b'\n';
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| file.write_all(&blank.repeat(slots)))
+            .map_err(unreadable)?;
+
+        let next_seq = recorded.last().map_or(1, |entry| entry.seq + 1);
+        let unfinished = recorded
+            .into_iter()
+            .filter(|entry| entry.elapsed_ms.is_none())
+            .collect();
+        let journal = Self {
+            file: Mutex::new(file),
+            slots: slots as u64,
+            next_seq: AtomicU64::new(next_seq),
+        };
+        Ok((journal, unfinished))
+    }
+
+    /// Record the start of a request, returning the entry to pass to [`Self::finish`]
+    pub fn begin(
+        &self,
+        method: &str,
+        tool: Option<&str>,
+        snapshot_at: Option<DateTime<Utc>>,
+        config_version: u64,
+    ) -> JournalEntry {
+        let entry = JournalEntry {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            started_at: Utc::now(),
+            method: method.chars().take(MAX_METHOD_CHARS).collect(),
+            tool: tool.map(str::to_string),
+            snapshot_at,
+            config_version,
+            elapsed_ms: None,
+            error: None,
+        };
+        self.write(&entry);
+        entry
+    }
+
+    /// Record that the request of `entry` finished after `elapsed`
+    pub fn finish(&self, mut entry: JournalEntry, elapsed: Duration, error: Option<&str>) {
+        // Newer requests took the slot over while this one ran
+        if self.next_seq.load(Ordering::Relaxed) - entry.seq > self.slots {
+            return;
+        }
+        entry.elapsed_ms = Some(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+        entry.error = error.map(str::to_string);
+        self.write(&entry);
+    }
+
+    fn write(&self, entry: &JournalEntry) {
+        let mut slot = match serde_json::to_vec(entry) {
+            Ok(json) if json.len() < SLOT_BYTES => json,
+            _ => return,
+        };
+        slot.resize(SLOT_BYTES - 1, b' ');
+        slot.push(b'\n');
+
+        let offset = (entry.seq % self.slots) * SLOT_BYTES as u64;
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        let written = file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.write_all(&slot));
+        if let Err(e) = written {
+            warn!("Cannot write request journal: {}", e);
+        }
+    }
+}
+
+/// Log what the previous process was doing when it stopped without finishing
+pub fn log_unfinished(unfinished: &[JournalEntry]) {
+    if unfinished.is_empty() {
+        return;
+    }
+    warn!(
+        "{} requests were in flight when the server last stopped:",
+        unfinished.len()
+    );
+    for entry in unfinished {
+        warn!(
+            "  #{} {}{} started {}, real-time snapshot {}, configuration version {}",
+            entry.seq,
+            entry.method,
+            entry
+                .tool
+                .as_deref()
+                .map(|tool| format!(" {tool}"))
+                .unwrap_or_default(),
+            entry.started_at.to_rfc3339(),
+            entry
+                .snapshot_at
+                .map_or_else(|| "none".to_string(), |at| at.to_rfc3339()),
+            entry.config_version
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfinished_requests_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("velib-{}.journal", uuid::Uuid::new_v4()));
+        let snapshot_at = Utc::now();
+
+        let (journal, unfinished) = RequestJournal::open(&path, 4).unwrap();
+        assert!(unfinished.is_empty());
+        let done = journal.begin("tools/call", Some("find_nearby_stations"), None, 1);
+        journal.finish(done, Duration::from_millis(12), None);
+        let stuck = journal.begin(
+            "tools/call",
+            Some("plan_bike_journey"),
+            Some(snapshot_at),
+            2,
+        );
+        // The crash: nothing finishes the second request
+        drop(journal);
+
+        let (journal, unfinished) = RequestJournal::open(&path, 4).unwrap();
+        assert_eq!(unfinished, vec![stuck]);
+        assert_eq!(unfinished[0].snapshot_at, Some(snapshot_at));
+
+        // Sequence numbers go on, and slots wrap around
+        let entries: Vec<_> = (0..6)
+            .map(|_| journal.begin(&"x".repeat(500), None, None, 1))
+            .collect();
+        assert_eq!(entries[0].seq, 3);
+        assert_eq!(entries[0].method.len(), MAX_METHOD_CHARS);
+        journal.finish(entries[0].clone(), Duration::ZERO, None);
+        drop(journal);
+
+        let (_, unfinished) = RequestJournal::open(&path, 4).unwrap();
+        let seqs: Vec<u64> = unfinished.iter().map(|entry| entry.seq).collect();
+        assert_eq!(seqs, vec![5, 6, 7, 8]);
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            4 * SLOT_BYTES as u64
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
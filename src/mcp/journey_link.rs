@@ -0,0 +1,208 @@
+use crate::data::cache::InMemoryCache;
+use crate::mcp::widget::escape_html;
+use crate::types::Coordinates;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// How long a `/journey/{id}` page stays available
+pub const JOURNEY_PAGE_TTL_HOURS: i64 = 24;
+
+/// A station a shared journey goes through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JourneyStop {
+    pub station_code: String,
+    pub name: String,
+    pub coordinates: Coordinates,
+}
+
+/// A planned journey as shared with someone else: walk to the pickup station,
+/// ride to the dropoff station, then walk on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedJourney {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Coordinates>,
+    pub pickup: JourneyStop,
+    pub dropoff: JourneyStop,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<Coordinates>,
+}
+
+fn point(coordinates: &Coordinates) -> String {
+    format!("{},{}", coordinates.latitude, coordinates.longitude)
+}
+
+/// Google Maps cycling directions through both stations
+///
+/// The journey starts at `origin` and ends at `destination` when given, with
+/// the stations as waypoints; otherwise it starts or ends at the station.
+#[must_use]
+pub fn google_maps_url(journey: &SharedJourney) -> String {
+    let mut waypoints = Vec::new();
+    let origin = match &journey.origin {
+        Some(origin) => {
+            waypoints.push(point(&journey.pickup.coordinates));
+            point(origin)
+        }
+        None => point(&journey.pickup.coordinates),
+    };
+    let destination = match &journey.destination {
+        Some(destination) => {
+            waypoints.push(point(&journey.dropoff.coordinates));
+            point(destination)
+        }
+        None => point(&journey.dropoff.coordinates),
+    };
+
+    let mut params = vec![
+        ("api", "1".to_string()),
+        ("origin", origin),
+        ("destination", destination),
+        ("travelmode", "bicycling".to_string()),
+    ];
+    if !waypoints.is_empty() {
+        params.push(("waypoints", waypoints.join("|")));
+    }
+    Url::parse_with_params("https://www.google.com/maps/dir/", &params)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// RFC 5870 `geo:` URI of the pickup station, labelled with its name
+///
+/// A geo URI holds a single point, so it only gets the rider to the bikes;
+/// map apps on phones open it directly.
+#[must_use]
+pub fn geo_uri(journey: &SharedJourney) -> String {
+    let at = point(&journey.pickup.coordinates);
+    let label = url_encode(&journey.pickup.name);
+    format!("geo:{at}?q={at}({label})")
+}
+
+fn url_encode(text: &str) -> String {
+    let mut url = Url::parse("geo:0,0").expect("static URL is valid");
+    url.query_pairs_mut().append_pair("q", text);
+    // Map apps read `+` literally outside of form data
+    url.query()
+        .and_then(|query| query.strip_prefix("q="))
+        .unwrap_or_default()
+        .replace('+', "%20")
+}
+
+/// A small standalone page describing the journey, with map links
+#[must_use]
+pub fn journey_page(journey: &SharedJourney, expires_at: DateTime<Utc>) -> String {
+    let stop = |role: &str, stop: &JourneyStop| {
+        format!(
+            "<li><b>{role}</b>: {} <span style=\"color:#666\">({})</span></li>",
+            escape_html(&stop.name),
+            escape_html(&stop.station_code)
+        )
+    };
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+         <title>Vélib' journey</title></head>\
+         <body style=\"font-family:sans-serif;max-width:480px;margin:24px auto;padding:0 12px\">\
+         <h1 style=\"font-size:20px\">Vélib' journey</h1><ol>{}{}</ol>\
+         <p><a href=\"{}\">Open in Google Maps</a> · <a href=\"{}\">Open in a map app</a></p>\
+         <p style=\"color:#666;font-size:12px\">This link expires {}.</p></body></html>",
+        stop("Pick up a bike at", &journey.pickup),
+        stop("Return it at", &journey.dropoff),
+        escape_html(&google_maps_url(journey)),
+        escape_html(&geo_uri(journey)),
+        expires_at.format("%Y-%m-%d %H:%M UTC"),
+    )
+}
+
+/// Journeys shared as `/journey/{id}` pages, forgotten after a day
+#[derive(Debug)]
+pub struct JourneyLinkStore {
+    journeys: InMemoryCache<String, (SharedJourney, DateTime<Utc>)>,
+    ttl: Duration,
+}
+
+impl Default for JourneyLinkStore {
+    fn default() -> Self {
+        Self::new(Duration::hours(JOURNEY_PAGE_TTL_HOURS))
+    }
+}
+
+impl JourneyLinkStore {
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            journeys: InMemoryCache::new(ttl),
+            ttl,
+        }
+    }
+
+    /// Keep `journey` for the store's lifetime; returns its id and expiry
+    pub async fn share(&self, journey: SharedJourney) -> (String, DateTime<Utc>) {
+        // Expired journeys are dropped as new ones come in
+        self.journeys.cleanup_expired().await;
+        let id = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+        let expires_at = Utc::now() + self.ttl;
+        self.journeys
+            .insert(id.clone(), (journey, expires_at))
+            .await;
+        (id, expires_at)
+    }
+
+    /// The journey shared as `id`, with its expiry, unless it has expired
+    pub async fn get(&self, id: &str) -> Option<(SharedJourney, DateTime<Utc>)> {
+        self.journeys.get(&id.to_string()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journey(origin: Option<Coordinates>) -> SharedJourney {
+        SharedJourney {
+            origin,
+            pickup: JourneyStop {
+                station_code: "12001".to_string(),
+                name: "Gare de Lyon - Diderot".to_string(),
+                coordinates: Coordinates::new(48.8446, 2.3737),
+            },
+            dropoff: JourneyStop {
+                station_code: "4001".to_string(),
+                name: "Hôtel de Ville".to_string(),
+                coordinates: Coordinates::new(48.8566, 2.3522),
+            },
+            destination: None,
+        }
+    }
+
+    #[test]
+    fn test_map_links() {
+        assert_eq!(
+            google_maps_url(&journey(None)),
+            "https://www.google.com/maps/dir/?api=1&origin=48.8446%2C2.3737\
+             &destination=48.8566%2C2.3522&travelmode=bicycling"
+        );
+        let from_home = google_maps_url(&journey(Some(Coordinates::new(48.84, 2.37))));
+        assert!(from_home.contains("origin=48.84%2C2.37&"));
+        assert!(from_home.ends_with("&waypoints=48.8446%2C2.3737"));
+
+        assert_eq!(
+            geo_uri(&journey(None)),
+            "geo:48.8446,2.3737?q=48.8446,2.3737(Gare%20de%20Lyon%20-%20Diderot)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_journeys_expire() {
+        let store = JourneyLinkStore::new(Duration::milliseconds(50));
+        let (id, _) = store.share(journey(None)).await;
+        assert_eq!(id.len(), 12);
+        let (shared, _) = store.get(&id).await.unwrap();
+        assert_eq!(shared, journey(None));
+        assert!(journey_page(&shared, Utc::now()).contains("Hôtel de Ville"));
+
+        tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+        assert!(store.get(&id).await.is_none());
+    }
+}
@@ -0,0 +1,87 @@
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::server::AppConfig;
+use crate::Result;
+
+/// Where a reload reads the configuration from, e.g. [`AppConfig::load`]
+pub type ConfigSource = Arc<dyn Fn() -> Result<AppConfig> + Send + Sync>;
+
+/// Which configuration is in effect, as reported by `/health`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConfigVersion {
+    /// 1 at startup, then one more per successful reload
+    pub version: u64,
+    pub loaded_at: DateTime<Utc>,
+}
+
+/// The configuration a server runs with, replaced as a whole on reload
+pub struct LiveConfig {
+    current: ArcSwap<AppConfig>,
+    version: ArcSwap<ConfigVersion>,
+    source: Option<ConfigSource>,
+    // Reloads apply one at a time, so versions follow the order they were applied in
+    reloading: tokio::sync::Mutex<()>,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self::new(AppConfig::default())
+    }
+}
+
+impl LiveConfig {
+    #[must_use]
+    pub fn new(config: AppConfig) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(config),
+            version: ArcSwap::from_pointee(ConfigVersion {
+                version: 1,
+                loaded_at: Utc::now(),
+            }),
+            source: None,
+            reloading: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Reload from `source`
+    #[must_use]
+    pub fn with_source(mut self, source: ConfigSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The configuration in effect
+    #[must_use]
+    pub fn current(&self) -> Arc<AppConfig> {
+        self.current.load_full()
+    }
+
+    #[must_use]
+    pub fn version(&self) -> ConfigVersion {
+        **self.version.load()
+    }
+
+    #[must_use]
+    pub fn source(&self) -> Option<&ConfigSource> {
+        self.source.as_ref()
+    }
+
+    /// Hold while applying a new configuration
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.reloading.lock().await
+    }
+
+    /// Make `config` the one in effect, under a new version
+    pub fn replace(&self, config: AppConfig) -> ConfigVersion {
+        let version = ConfigVersion {
+            version: self.version().version + 1,
+            loaded_at: Utc::now(),
+        };
+        self.current.store(Arc::new(config));
+        self.version.store(Arc::new(version));
+        version
+    }
+}
@@ -0,0 +1,365 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::mcp::{McpServer, McpToolHandler};
+use crate::types::{
+    BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+    StationStatus,
+};
+use crate::{Error, Result, VelibDataClient};
+
+/// Stations in the snapshot served by [`serve_fixture`], about as many as Paris has
+pub const FIXTURE_STATIONS: usize = 1500;
+
+/// Sustained load on one tool, as run by `velib-mcp bench`
+///
+/// Every worker sends its next call as soon as the previous one is answered.
+#[derive(Debug, Clone)]
+pub struct LoadTest {
+    tool: String,
+    arguments: Value,
+    concurrency: usize,
+    duration: Duration,
+    api_key: Option<String>,
+}
+
+impl LoadTest {
+    /// Call `tool` with `arguments`, by default from 10 workers for 10 seconds
+    #[must_use]
+    pub fn new(tool: &str, arguments: Value) -> Self {
+        Self {
+            tool: tool.to_string(),
+            arguments,
+            concurrency: 10,
+            duration: Duration::from_secs(10),
+            api_key: None,
+        }
+    }
+
+    /// Arguments that work against both a live server and [`serve_fixture`],
+    /// for the tools that have such
+    #[must_use]
+    pub fn sample_arguments(tool: &str) -> Option<Value> {
+        match tool {
+            "find_nearby_stations" => Some(json!({"latitude": 48.8566, "longitude": 2.3522})),
+            "plan_bike_journey" => Some(json!({
+                "origin": {"latitude": 48.8443, "longitude": 2.3741},
+                "destination": {"latitude": 48.8738, "longitude": 2.2950}
+            })),
+            "get_area_statistics" => Some(json!({
+                "bounds": {"north": 48.87, "south": 48.84, "east": 2.37, "west": 2.32}
+            })),
+            "get_system_overview" => Some(json!({})),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Send a bearer API key, needed by servers with `VELIB_API_KEYS` set
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Load the server at `base_url`, e.g. `http://127.0.0.1:8080`, and report
+    /// how it held up
+    pub async fn run(&self, base_url: &str) -> Result<LoadReport> {
+        let http = reqwest::Client::builder()
+            .pool_max_idle_per_host(self.concurrency)
+            .build()?;
+        let endpoint = format!("{}/mcp", base_url.trim_end_matches('/'));
+        let started = Instant::now();
+        let deadline = started + self.duration;
+
+        let workers: Vec<_> = (0..self.concurrency)
+            .map(|worker| {
+                let http = http.clone();
+                let endpoint = endpoint.clone();
+                let load = self.clone();
+                tokio::spawn(async move {
+                    let mut latencies = Vec::new();
+                    let mut errors = HashMap::<String, u64>::new();
+                    let mut id = worker as u64 * 1_000_000_000;
+                    while Instant::now() < deadline {
+                        id += 1;
+                        let sent = Instant::now();
+                        let outcome = load.call(&http, &endpoint, id).await;
+                        latencies.push(sent.elapsed());
+                        if let Err(kind) = outcome {
+                            *errors.entry(kind).or_default() += 1;
+                        }
+                    }
+                    (latencies, errors)
+                })
+            })
+            .collect();
+
+        let mut latencies = Vec::new();
+        let mut errors = BTreeMap::new();
+        for worker in workers {
+            let (worker_latencies, worker_errors) = worker
+                .await
+                .map_err(|e| Error::Internal(anyhow::anyhow!("Load test worker failed: {e}")))?;
+            latencies.extend(worker_latencies);
+            for (kind, count) in worker_errors {
+                *errors.entry(kind).or_default() += count;
+            }
+        }
+        latencies.sort_unstable();
+        Ok(LoadReport {
+            tool: self.tool.clone(),
+            concurrency: self.concurrency,
+            elapsed: started.elapsed(),
+            latencies,
+            errors,
+        })
+    }
+
+    /// One tool call, failing with the kind of error it ran into
+    async fn call(
+        &self,
+        http: &reqwest::Client,
+        endpoint: &str,
+        id: u64,
+    ) -> std::result::Result<(), String> {
+        let mut request = http.post(endpoint).json(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": self.tool, "arguments": self.arguments}
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await.map_err(|_| "transport".to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("http_{}", status.as_u16()));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|_| "invalid_response".to_string())?;
+        if let Some(error) = body.get("error") {
+            return Err(match error["data"]["error_type"].as_str() {
+                Some(kind) => kind.to_string(),
+                None => format!("rpc_{}", error["code"]),
+            });
+        }
+        if body["result"]["isError"] == true {
+            return Err("tool_error".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Latencies and failures seen by a [`LoadTest`]
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    pub tool: String,
+    pub concurrency: usize,
+    pub elapsed: Duration,
+    /// Every call's latency, failed ones included, fastest first
+    pub latencies: Vec<Duration>,
+    /// Failed calls per kind: the server's `error_type`, `http_<status>`,
+    /// `tool_error` or `transport`
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl LoadReport {
+    #[must_use]
+    pub fn requests(&self) -> usize {
+        self.latencies.len()
+    }
+
+    #[must_use]
+    pub fn failed(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    /// Share of calls that failed, 0 when none were made
+    #[must_use]
+    pub fn error_rate(&self) -> f64 {
+        if self.latencies.is_empty() {
+            return 0.0;
+        }
+        self.failed() as f64 / self.requests() as f64
+    }
+
+    #[must_use]
+    pub fn throughput(&self) -> f64 {
+        self.requests() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Latency under which `percent` of calls completed (nearest rank)
+    #[must_use]
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        let rank = (percent / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies
+            .get(rank.clamp(1, self.latencies.len().max(1)) - 1)
+            .copied()
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} calls in {:.1}s from {} workers ({:.1}/s)",
+            self.tool,
+            self.requests(),
+            self.elapsed.as_secs_f64(),
+            self.concurrency,
+            self.throughput()
+        )?;
+        let ms = |percent: f64| {
+            self.percentile(percent)
+                .map_or(0.0, |latency| latency.as_secs_f64() * 1000.0)
+        };
+        writeln!(
+            f,
+            "latency p50 {:.2} ms, p90 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            ms(50.0),
+            ms(90.0),
+            ms(99.0),
+            ms(100.0)
+        )?;
+        write!(
+            f,
+            "errors {} ({:.2}%)",
+            self.failed(),
+            self.error_rate() * 100.0
+        )?;
+        for (kind, count) in &self.errors {
+            write!(f, ", {kind} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve a Paris-sized fixture snapshot on a local port, for load tests
+/// without network access
+///
+/// Identical calls are not deduplicated, so every call runs its tool. The
+/// snapshot expires like any other after five minutes, when the server tries
+/// the upstream API.
+pub async fn serve_fixture() -> Result<SocketAddr> {
+    let reference: Vec<StationReference> = (0..FIXTURE_STATIONS)
+        .map(|i| StationReference {
+            station_code: format!("{}", 10000 + i).into(),
+            name: format!("Station {i} - Rue de Test").into(),
+            coordinates: Coordinates::new(
+                48.82 + (i / 40) as f64 * 0.002,
+                2.27 + (i % 40) as f64 * 0.003,
+            ),
+            capacity: 30,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        })
+        .collect();
+    let realtime = reference
+        .iter()
+        .map(|station| {
+            let status = RealTimeStatus::new(
+                BikeAvailability::new(5, 3),
+                10,
+                StationStatus::Open,
+                Utc::now(),
+            );
+            (station.station_code.to_string(), status)
+        })
+        .collect();
+    let mut client = VelibDataClient::new();
+    client.seed_cache(reference, realtime).await;
+
+    let app = McpServer::with_tool_handler(McpToolHandler::with_data_client(client))
+        .with_dedup_ttl(Duration::ZERO)
+        .router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| Error::Internal(e.into()))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| Error::Internal(e.into()))?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::error!("Fixture server stopped: {}", e);
+        }
+    });
+    Ok(addr)
+}
+
+/// Parse a duration such as `60s`, `2m`, `500ms` or `30` (seconds)
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let invalid = || Error::Validation(format!("Invalid duration {value:?}, e.g. 60s or 2m"));
+    let (number, unit) = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .map_or((value, "s"), |at| value.split_at(at));
+    let number: f64 = number.trim().parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_units() {
+        assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+        for invalid in ["", "s", "10h", "-1s"] {
+            assert!(parse_duration(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_percentiles_use_nearest_rank() {
+        let report = LoadReport {
+            tool: "find_nearby_stations".to_string(),
+            concurrency: 1,
+            elapsed: Duration::from_secs(1),
+            latencies: (1..=100).map(Duration::from_millis).collect(),
+            errors: BTreeMap::from([("timeout".to_string(), 5)]),
+        };
+        assert_eq!(report.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(report.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(report.percentile(100.0), Some(Duration::from_millis(100)));
+        assert_eq!(report.percentile(0.0), Some(Duration::from_millis(1)));
+        assert!((report.error_rate() - 0.05).abs() < 1e-9);
+        assert!(report.to_string().contains("timeout 5"));
+
+        let empty = LoadReport {
+            latencies: Vec::new(),
+            errors: BTreeMap::new(),
+            ..report
+        };
+        assert_eq!(empty.percentile(50.0), None);
+        assert_eq!(empty.error_rate(), 0.0);
+    }
+}
@@ -0,0 +1,188 @@
+use super::humanize::{Locale, UnitSystem};
+use crate::data::DataVersion;
+use crate::{Error, Result};
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+
+/// [`DataVersion::version`] of the data a `GET /resources/{uri}` answer came from
+pub const DATA_VERSION_HEADER: HeaderName = HeaderName::from_static("x-data-version");
+/// [`DataVersion::hash`] of the same data
+pub const DATA_HASH_HEADER: HeaderName = HeaderName::from_static("x-data-hash");
+
+/// How a caller wants one request's results written, from its `params._meta`
+///
+/// Built per JSON-RPC request and handed down with it, so concurrent calls
+/// from different clients never see each other's settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestContext {
+    /// `_meta.locale`, a language tag such as `fr-FR`
+    pub locale: Option<Locale>,
+    /// `_meta.units`, `metric` unless the caller asks for `imperial`
+    pub units: UnitSystem,
+}
+
+impl RequestContext {
+    /// Read `locale` and `units` from a request's `_meta`, if it has one
+    pub fn from_meta(meta: Option<&Value>) -> Result<Self> {
+        let Some(meta) = meta else {
+            return Ok(Self::default());
+        };
+        let units = match meta.get("units") {
+            None | Some(Value::Null) => UnitSystem::default(),
+            Some(units) => units
+                .as_str()
+                .and_then(UnitSystem::from_name)
+                .ok_or_else(|| {
+                    Error::Validation(format!(
+                        "Unsupported _meta.units {units}, expected \"metric\" or \"imperial\""
+                    ))
+                })?,
+        };
+        Ok(Self {
+            locale: meta
+                .get("locale")
+                .and_then(Value::as_str)
+                .map(Locale::from_language),
+            units,
+        })
+    }
+
+    /// This request's locale, else the session's `language`, else English
+    #[must_use]
+    pub fn locale_or(&self, language: Option<&str>) -> Locale {
+        self.locale
+            .or_else(|| language.map(Locale::from_language))
+            .unwrap_or_default()
+    }
+
+    /// Response cache key for a call made with this context, as results
+    /// rendered in other units can't be shared
+    #[must_use]
+    pub fn scoped_key(&self, key: String) -> String {
+        match self.units {
+            UnitSystem::Metric => key,
+            UnitSystem::Imperial => format!("{key}|imperial"),
+        }
+    }
+}
+
+/// Set `_meta.<key>` on a result; results that aren't objects are left alone
+pub fn insert(result: &mut Value, key: &str, value: Value) {
+    if let Some(object) = result.as_object_mut() {
+        let meta = object
+            .entry("_meta")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Which data a result was computed from, taken along with the result so a
+/// cached answer keeps reporting the data it saw
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataStamp {
+    pub version: Option<DataVersion>,
+    pub snapshot_at: Option<DateTime<Utc>>,
+}
+
+/// Report how long the server spent on a request and when the real-time data
+/// it answers from was fetched, so callers can judge staleness per call
+#[must_use]
+pub fn with_timing(
+    mut result: Value,
+    processing: std::time::Duration,
+    data_snapshot_at: Option<DateTime<Utc>>,
+) -> Value {
+    let processing_ms = (processing.as_secs_f64() * 100_000.0).round() / 100.0;
+    insert(&mut result, "processing_ms", Value::from(processing_ms));
+    if let Some(at) = data_snapshot_at {
+        insert(
+            &mut result,
+            "data_snapshot_at",
+            Value::from(at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        );
+    }
+    result
+}
+
+/// Report which data a result was computed from as `_meta.data_version` and
+/// `_meta.data_hash`, so callers can tell whether two answers saw the same data
+#[must_use]
+pub fn with_data_version(mut result: Value, version: Option<&DataVersion>) -> Value {
+    if let Some(version) = version {
+        insert(&mut result, "data_version", Value::from(version.version));
+        insert(&mut result, "data_hash", Value::from(version.hash.as_str()));
+    }
+    result
+}
+
+/// The same for HTTP responses, as [`DATA_VERSION_HEADER`] and [`DATA_HASH_HEADER`]
+pub fn insert_data_version_headers(headers: &mut HeaderMap, version: &DataVersion) {
+    headers.insert(DATA_VERSION_HEADER, HeaderValue::from(version.version));
+    if let Ok(hash) = HeaderValue::from_str(&version.hash) {
+        headers.insert(DATA_HASH_HEADER, hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_context_from_meta() {
+        let context =
+            RequestContext::from_meta(Some(&json!({"locale": "fr-CA", "units": "imperial"})))
+                .unwrap();
+        assert_eq!(context.locale, Some(Locale::French));
+        assert_eq!(context.units, UnitSystem::Imperial);
+        assert_eq!(context.locale_or(Some("en")), Locale::French);
+
+        let context = RequestContext::from_meta(None).unwrap();
+        assert_eq!(context, RequestContext::default());
+        assert_eq!(context.locale_or(Some("fr")), Locale::French);
+        assert_eq!(context.scoped_key("k".to_string()), "k");
+
+        assert!(matches!(
+            RequestContext::from_meta(Some(&json!({"units": "nautical"}))),
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_timing_joins_existing_meta() {
+        let result = json!({"content": [], "_meta": {"cache": "hit"}});
+        let at = DateTime::parse_from_rfc3339("2025-06-14T19:31:22Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let result = with_timing(result, std::time::Duration::from_micros(1500), Some(at));
+        let version = DataVersion {
+            version: 7,
+            hash: "00c0ffee00c0ffee".to_string(),
+        };
+        let result = with_data_version(result, Some(&version));
+        assert_eq!(
+            result["_meta"],
+            json!({
+                "cache": "hit",
+                "processing_ms": 1.5,
+                "data_snapshot_at": "2025-06-14T19:31:22Z",
+                "data_version": 7,
+                "data_hash": "00c0ffee00c0ffee"
+            })
+        );
+    }
+
+    #[test]
+    fn test_snapshot_left_out_before_any_fetch() {
+        let result = with_timing(json!({}), std::time::Duration::ZERO, None);
+        assert_eq!(result, json!({"_meta": {"processing_ms": 0.0}}));
+        assert_eq!(with_data_version(result.clone(), None), result);
+
+        let scalar = with_timing(json!(null), std::time::Duration::ZERO, None);
+        assert!(scalar.is_null());
+    }
+}
@@ -1,7 +1,71 @@
+mod admin;
+pub mod announcements;
+pub mod auth;
+pub mod budget;
+pub mod client;
+pub mod comparison;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+pub mod dedup;
+pub mod density;
+pub mod digest;
+pub mod dropoff;
+pub mod encoding;
+pub mod favorites;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
+pub mod hardening;
+pub mod humanize;
+pub mod journal;
+pub mod journey_link;
+pub mod live_config;
+pub mod loadtest;
+pub mod meta;
+pub mod oidc;
+mod panics;
+pub mod precision;
+pub mod prompts;
+pub mod quota;
+pub mod registry;
+pub mod resumption;
+pub mod sanitize;
+pub mod schema;
 pub mod server;
+pub mod session;
+pub mod signing;
+pub mod slow_calls;
+pub mod sorting;
+pub mod sparkline;
+pub mod subscriptions;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod types;
+pub mod typical;
+pub mod widget;
 
+pub use announcements::{AnnouncementDraft, AnnouncementStore};
+pub use auth::{key_id, ApiKeys, AuthFuture, AuthProvider, Principal};
+pub use client::McpClient;
+pub use dedup::{CacheStatus, ResponseCache};
+pub use digest::{CommuteDigest, CommuteProfile};
+pub use favorites::{FavoriteStation, FavoritesStore};
 pub use handlers::McpToolHandler;
+pub use hardening::IpRange;
+pub use journal::{JournalEntry, RequestJournal};
+pub use journey_link::JourneyLinkStore;
+pub use live_config::{ConfigSource, ConfigVersion, LiveConfig};
+pub use loadtest::{LoadReport, LoadTest};
+pub use oidc::{OidcConfig, OidcProvider};
+pub use panics::install_panic_hook;
+pub use precision::OutputPrecision;
+pub use quota::{QuotaLimits, QuotaStore};
+pub use registry::{
+    ApiLifecycle, CostHints, FunctionFormat, LatencyClass, ResultSize, ToolDefinition,
+    ToolRegistry, ToolSelection, TOOL_API_VERSION,
+};
 pub use server::McpServer;
+pub use session::{SessionStore, UserPreferences};
+pub use signing::ResponseSigner;
+pub use sorting::{SortDirection, SortField, SortOptions};
 pub use types::*;
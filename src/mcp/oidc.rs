@@ -0,0 +1,591 @@
+use base64::prelude::{Engine as _, BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Public};
+use openssl::rsa::Rsa;
+use openssl::sign::Verifier;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, warn};
+
+use super::auth::{AuthFuture, AuthProvider, Principal};
+use crate::{Error, Result};
+
+/// How long a fetched key set is trusted before it is fetched again
+pub const JWKS_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+// A token signed with an unknown key refetches the key set at most this often,
+// so the issuer rotating keys is picked up without forged tokens flooding it
+const JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+// Clock skew tolerated on `exp` and `nbf`
+const CLOCK_LEEWAY_SECONDS: i64 = 60;
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The identity provider whose tokens open the MCP endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Expected `iss` claim; the key set is discovered from its
+    /// `/.well-known/openid-configuration`
+    pub issuer: String,
+    /// Must be in the `aud` claim, when set
+    pub audience: Option<String>,
+    /// Scopes every token must grant, in `scope` or `scp`
+    pub required_scopes: Vec<String>,
+    /// Key set endpoint, skipping discovery
+    pub jwks_url: Option<String>,
+}
+
+/// Why a bearer token was turned down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    Malformed,
+    UnsupportedAlgorithm(String),
+    /// Signed with a key missing from the key set, perhaps one rotated in since it was fetched
+    UnknownKey,
+    BadSignature,
+    Expired,
+    NotYetValid,
+    WrongIssuer,
+    WrongAudience,
+    MissingScope(String),
+}
+
+/// Signature algorithms accepted, all asymmetric so the key set is public
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Rs256,
+    Rs384,
+    Rs512,
+    Es256,
+    Es384,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "RS256" => Some(Self::Rs256),
+            "RS384" => Some(Self::Rs384),
+            "RS512" => Some(Self::Rs512),
+            "ES256" => Some(Self::Es256),
+            "ES384" => Some(Self::Es384),
+            _ => None,
+        }
+    }
+
+    fn digest(self) -> MessageDigest {
+        match self {
+            Self::Rs256 | Self::Es256 => MessageDigest::sha256(),
+            Self::Rs384 | Self::Es384 => MessageDigest::sha384(),
+            Self::Rs512 => MessageDigest::sha512(),
+        }
+    }
+
+    /// Bytes of each of `r` and `s` in a JWS ECDSA signature
+    fn ecdsa_component_len(self) -> Option<usize> {
+        match self {
+            Self::Es256 => Some(32),
+            Self::Es384 => Some(48),
+            _ => None,
+        }
+    }
+}
+
+/// A verification key from a JSON Web Key Set
+#[derive(Debug, Clone)]
+struct VerifyingKey {
+    kid: Option<String>,
+    key: PKey<Public>,
+    curve: Option<Nid>,
+}
+
+impl VerifyingKey {
+    fn from_jwk(jwk: &Value) -> Option<Self> {
+        let field = |name: &str| -> Option<BigNum> {
+            let bytes = BASE64_URL_SAFE_NO_PAD
+                .decode(jwk.get(name)?.as_str()?)
+                .ok()?;
+            BigNum::from_slice(&bytes).ok()
+        };
+        if jwk
+            .get("use")
+            .and_then(Value::as_str)
+            .is_some_and(|usage| usage != "sig")
+        {
+            return None;
+        }
+        let (key, curve) = match jwk.get("kty")?.as_str()? {
+            "RSA" => {
+                let rsa = Rsa::from_public_components(field("n")?, field("e")?).ok()?;
+                (PKey::from_rsa(rsa).ok()?, None)
+            }
+            "EC" => {
+                let curve = match jwk.get("crv")?.as_str()? {
+                    "P-256" => Nid::X9_62_PRIME256V1,
+                    "P-384" => Nid::SECP384R1,
+                    _ => return None,
+                };
+                let group = EcGroup::from_curve_name(curve).ok()?;
+                let (x, y) = (field("x")?, field("y")?);
+                let ec = EcKey::from_public_key_affine_coordinates(&group, &x, &y).ok()?;
+                (PKey::from_ec_key(ec).ok()?, Some(curve))
+            }
+            _ => return None,
+        };
+        Some(Self {
+            kid: jwk.get("kid").and_then(Value::as_str).map(str::to_string),
+            key,
+            curve,
+        })
+    }
+
+    fn verify(&self, algorithm: Algorithm, signed: &[u8], signature: &[u8]) -> bool {
+        let expected_curve = match algorithm {
+            Algorithm::Es256 => Some(Nid::X9_62_PRIME256V1),
+            Algorithm::Es384 => Some(Nid::SECP384R1),
+            _ => None,
+        };
+        if self.curve != expected_curve {
+            return false;
+        }
+        // JWS carries ECDSA signatures as raw `r || s`, openssl expects DER
+        let der;
+        let signature = match algorithm.ecdsa_component_len() {
+            Some(len) if signature.len() == 2 * len => {
+                let (r, s) = signature.split_at(len);
+                let parsed = BigNum::from_slice(r)
+                    .and_then(|r| Ok((r, BigNum::from_slice(s)?)))
+                    .and_then(|(r, s)| EcdsaSig::from_private_components(r, s))
+                    .and_then(|sig| sig.to_der());
+                let Ok(encoded) = parsed else { return false };
+                der = encoded;
+                &der[..]
+            }
+            Some(_) => return false,
+            None => signature,
+        };
+        Verifier::new(algorithm.digest(), &self.key)
+            .and_then(|mut verifier| {
+                verifier.update(signed)?;
+                verifier.verify(signature)
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The keys an issuer signs tokens with
+#[derive(Debug, Clone, Default)]
+pub struct KeySet {
+    keys: Vec<VerifyingKey>,
+}
+
+impl KeySet {
+    /// Read a JSON Web Key Set, skipping keys that can't verify signatures here
+    #[must_use]
+    pub fn from_jwks(jwks: &Value) -> Self {
+        let keys = jwks
+            .get("keys")
+            .and_then(Value::as_array)
+            .map(|keys| keys.iter().filter_map(VerifyingKey::from_jwk).collect())
+            .unwrap_or_default();
+        Self { keys }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The key `kid` names, or the only key when the token names none
+    fn find(&self, kid: Option<&str>) -> Option<&VerifyingKey> {
+        match kid {
+            Some(kid) => self.keys.iter().find(|key| key.kid.as_deref() == Some(kid)),
+            None if self.keys.len() == 1 => self.keys.first(),
+            None => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+/// Check `token`'s signature against `keys` and its claims against `config`,
+/// and return who it identifies
+pub fn verify(
+    token: &str,
+    keys: &KeySet,
+    config: &OidcConfig,
+    now: DateTime<Utc>,
+) -> std::result::Result<Principal, Rejection> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(Rejection::Malformed);
+    };
+    let decode = |part: &str| {
+        BASE64_URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|_| Rejection::Malformed)
+    };
+    let header: Header =
+        serde_json::from_slice(&decode(header)?).map_err(|_| Rejection::Malformed)?;
+    let algorithm =
+        Algorithm::parse(&header.alg).ok_or(Rejection::UnsupportedAlgorithm(header.alg))?;
+    let key = keys
+        .find(header.kid.as_deref())
+        .ok_or(Rejection::UnknownKey)?;
+    let signed = &token[..token.len() - signature.len() - 1];
+    if !key.verify(algorithm, signed.as_bytes(), &decode(signature)?) {
+        return Err(Rejection::BadSignature);
+    }
+
+    let claims: Value =
+        serde_json::from_slice(&decode(payload)?).map_err(|_| Rejection::Malformed)?;
+    let timestamp = |name: &str| claims.get(name).and_then(Value::as_i64);
+    let now = now.timestamp();
+    if timestamp("exp").is_none_or(|exp| exp + CLOCK_LEEWAY_SECONDS <= now) {
+        return Err(Rejection::Expired);
+    }
+    if timestamp("nbf").is_some_and(|nbf| nbf - CLOCK_LEEWAY_SECONDS > now) {
+        return Err(Rejection::NotYetValid);
+    }
+    let issuer = claims
+        .get("iss")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if issuer.trim_end_matches('/') != config.issuer.trim_end_matches('/') {
+        return Err(Rejection::WrongIssuer);
+    }
+    if let Some(audience) = &config.audience {
+        let accepted = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        };
+        if !accepted {
+            return Err(Rejection::WrongAudience);
+        }
+    }
+    let granted: Vec<&str> = match (claims.get("scope"), claims.get("scp")) {
+        (Some(Value::String(scope)), _) => scope.split_whitespace().collect(),
+        (_, Some(Value::Array(scp))) => scp.iter().filter_map(Value::as_str).collect(),
+        (_, Some(Value::String(scp))) => scp.split_whitespace().collect(),
+        _ => Vec::new(),
+    };
+    if let Some(missing) = config
+        .required_scopes
+        .iter()
+        .find(|scope| !granted.contains(&scope.as_str()))
+    {
+        return Err(Rejection::MissingScope(missing.clone()));
+    }
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .filter(|sub| !sub.is_empty())
+        .ok_or(Rejection::Malformed)?;
+    Ok(Principal::new(
+        format!("oidc:{}", config.issuer.trim_end_matches('/')),
+        subject,
+    ))
+}
+
+#[derive(Debug)]
+struct FetchedKeys {
+    keys: Arc<KeySet>,
+    fetched_at: Instant,
+}
+
+/// Accepts JWT bearer tokens issued by an OpenID Connect provider
+///
+/// The issuer's key set is fetched on first use and cached for
+/// [`JWKS_CACHE_TTL`]; a token signed with a key it doesn't hold fetches it
+/// again, so key rotations are picked up.
+#[derive(Debug)]
+pub struct OidcProvider {
+    config: OidcConfig,
+    http: reqwest::Client,
+    keys: RwLock<Option<FetchedKeys>>,
+    // Held while fetching, so concurrent requests wait for one fetch
+    fetching: Mutex<()>,
+}
+
+impl OidcProvider {
+    #[must_use]
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::builder()
+                .timeout(JWKS_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            keys: RwLock::new(None),
+            fetching: Mutex::new(()),
+        }
+    }
+
+    /// The cached key set, fetched again if older than `max_age`
+    async fn keys(&self, max_age: Duration) -> Option<Arc<KeySet>> {
+        let fresh = |fetched: &Option<FetchedKeys>| {
+            fetched
+                .as_ref()
+                .filter(|fetched| fetched.fetched_at.elapsed() < max_age)
+                .map(|fetched| Arc::clone(&fetched.keys))
+        };
+        if let Some(keys) = fresh(&*self.keys.read().await) {
+            return Some(keys);
+        }
+        let _fetching = self.fetching.lock().await;
+        if let Some(keys) = fresh(&*self.keys.read().await) {
+            return Some(keys);
+        }
+        match self.fetch_keys().await {
+            Ok(keys) => {
+                debug!(issuer = %self.config.issuer, keys = keys.len(), "Fetched OIDC key set");
+                let keys = Arc::new(keys);
+                *self.keys.write().await = Some(FetchedKeys {
+                    keys: Arc::clone(&keys),
+                    fetched_at: Instant::now(),
+                });
+                Some(keys)
+            }
+            Err(e) => {
+                // Keep verifying with the keys we had until the issuer answers again
+                warn!(issuer = %self.config.issuer, "Failed to fetch OIDC key set: {}", e);
+                self.keys
+                    .read()
+                    .await
+                    .as_ref()
+                    .map(|fetched| Arc::clone(&fetched.keys))
+            }
+        }
+    }
+
+    async fn fetch_keys(&self) -> Result<KeySet> {
+        let jwks_url = match &self.config.jwks_url {
+            Some(url) => url.clone(),
+            None => {
+                let discovery = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                let document: Value = self
+                    .http
+                    .get(&discovery)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                document
+                    .get("jwks_uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Config(format!("{discovery} has no jwks_uri")))?
+                    .to_string()
+            }
+        };
+        let jwks: Value = self
+            .http
+            .get(&jwks_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(KeySet::from_jwks(&jwks))
+    }
+
+    async fn validate(&self, token: &str) -> Option<Principal> {
+        let keys = self.keys(JWKS_CACHE_TTL).await?;
+        let verified = match verify(token, &keys, &self.config, Utc::now()) {
+            Err(Rejection::UnknownKey) => {
+                let keys = self.keys(JWKS_REFETCH_INTERVAL).await?;
+                verify(token, &keys, &self.config, Utc::now())
+            }
+            verified => verified,
+        };
+        verified
+            .map_err(|rejection| debug!("Rejected bearer token: {:?}", rejection))
+            .ok()
+    }
+}
+
+impl AuthProvider for OidcProvider {
+    fn authenticate<'a>(&'a self, token: &'a str) -> AuthFuture<'a> {
+        // Static API keys aren't JWTs; skip them without touching the key set
+        if token.split('.').count() != 3 {
+            return Box::pin(async { None });
+        }
+        Box::pin(self.validate(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+    use serde_json::json;
+
+    /// Sign `claims` as an RS256 JWT with `key`, naming it `kid`
+    fn sign(key: &PKey<Private>, kid: &str, claims: &Value) -> String {
+        let encode = |value: &Value| BASE64_URL_SAFE_NO_PAD.encode(value.to_string());
+        let signed = format!(
+            "{}.{}",
+            encode(&json!({"alg": "RS256", "typ": "JWT", "kid": kid})),
+            encode(claims)
+        );
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(signed.as_bytes()).unwrap();
+        let signature = signer.sign_to_vec().unwrap();
+        format!("{signed}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// The public JWK of an RSA `key`
+    fn jwk(key: &PKey<Private>, kid: &str) -> Value {
+        let rsa = key.rsa().unwrap();
+        json!({
+            "kty": "RSA",
+            "use": "sig",
+            "kid": kid,
+            "n": BASE64_URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            "e": BASE64_URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+        })
+    }
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            issuer: "https://login.example.com/".to_string(),
+            audience: Some("velib-mcp".to_string()),
+            required_scopes: vec!["velib.read".to_string()],
+            jwks_url: None,
+        }
+    }
+
+    #[test]
+    fn test_tokens_are_checked_against_keys_and_claims() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let keys = KeySet::from_jwks(&json!({"keys": [
+            jwk(&key, "2026-10"),
+            {"kty": "oct", "k": "c2VjcmV0"},
+        ]}));
+        assert_eq!(keys.len(), 1);
+        let now = Utc::now();
+        let claims = json!({
+            "iss": "https://login.example.com",
+            "sub": "alice",
+            "aud": ["velib-mcp", "other"],
+            "scp": ["velib.read"],
+            "exp": now.timestamp() + 300,
+        });
+        let token = sign(&key, "2026-10", &claims);
+        assert_eq!(
+            verify(&token, &keys, &config(), now).unwrap().subject,
+            "alice"
+        );
+
+        let with = |changes: Value| {
+            let mut claims = claims.clone();
+            claims
+                .as_object_mut()
+                .unwrap()
+                .extend(changes.as_object().unwrap().clone());
+            sign(&key, "2026-10", &claims)
+        };
+        let rejected = |token: &str| verify(token, &keys, &config(), now).unwrap_err();
+        assert_eq!(
+            rejected(&with(json!({"exp": now.timestamp() - 120}))),
+            Rejection::Expired
+        );
+        assert_eq!(
+            rejected(&with(json!({"aud": "someone-else"}))),
+            Rejection::WrongAudience
+        );
+        assert_eq!(
+            rejected(&with(json!({"iss": "https://evil.example.com"}))),
+            Rejection::WrongIssuer
+        );
+        assert_eq!(
+            rejected(&with(json!({"scp": [], "scope": "openid profile"}))),
+            Rejection::MissingScope("velib.read".to_string())
+        );
+        assert_eq!(
+            rejected(&sign(&key, "2025-04", &claims)),
+            Rejection::UnknownKey
+        );
+
+        // A token re-signed by someone else, or stripped of its signature, is refused
+        let other = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        assert_eq!(
+            rejected(&sign(&other, "2026-10", &claims)),
+            Rejection::BadSignature
+        );
+        let unsigned = format!(
+            "{}.{}.",
+            BASE64_URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+            token.split('.').nth(1).unwrap()
+        );
+        assert_eq!(
+            rejected(&unsigned),
+            Rejection::UnsupportedAlgorithm("none".to_string())
+        );
+        assert_eq!(rejected("not-a-token"), Rejection::Malformed);
+    }
+
+    #[test]
+    fn test_es256_signatures_are_verified() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec = EcKey::generate(&group).unwrap();
+        let mut ctx = openssl::bn::BigNumContext::new().unwrap();
+        let (mut x, mut y) = (BigNum::new().unwrap(), BigNum::new().unwrap());
+        ec.public_key()
+            .affine_coordinates(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+        let keys = KeySet::from_jwks(&json!({"keys": [{
+            "kty": "EC",
+            "crv": "P-256",
+            "x": BASE64_URL_SAFE_NO_PAD.encode(x.to_vec_padded(32).unwrap()),
+            "y": BASE64_URL_SAFE_NO_PAD.encode(y.to_vec_padded(32).unwrap()),
+        }]}));
+
+        let now = Utc::now();
+        let encode = |value: Value| BASE64_URL_SAFE_NO_PAD.encode(value.to_string());
+        let signed = format!(
+            "{}.{}",
+            encode(json!({"alg": "ES256"})),
+            encode(json!({
+                "iss": "https://login.example.com",
+                "sub": "bob",
+                "aud": "velib-mcp",
+                "scope": "openid velib.read",
+                "exp": now.timestamp() + 300,
+            }))
+        );
+        let digest = openssl::hash::hash(MessageDigest::sha256(), signed.as_bytes()).unwrap();
+        let sig = EcdsaSig::sign(&digest, &ec).unwrap();
+        let mut raw = sig.r().to_vec_padded(32).unwrap();
+        raw.extend(sig.s().to_vec_padded(32).unwrap());
+        let token = format!("{signed}.{}", BASE64_URL_SAFE_NO_PAD.encode(raw));
+
+        assert_eq!(
+            verify(&token, &keys, &config(), now).unwrap().subject,
+            "bob"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use axum::{
+    body::Body,
+    http::{header, Response, StatusCode},
+};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Once;
+use std::task::{Context, Poll};
+use tower_http::catch_panic::CatchPanicLayer;
+use tracing::error;
+
+use super::types::{JsonRpcError, JsonRpcResponse};
+use crate::Error;
+
+thread_local! {
+    // Set by the hook, taken right after unwinding stops on the same thread
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Log every panic once, with a backtrace and a correlation id that the
+/// last-resort error response repeats
+///
+/// Replaces the default hook, which would print the panic to stderr again.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        panic::set_hook(Box::new(|info| {
+            let correlation_id = uuid::Uuid::new_v4().to_string();
+            error!(
+                correlation_id = %correlation_id,
+                "Panic: {}\n{}",
+                info,
+                Backtrace::force_capture()
+            );
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(correlation_id));
+        }));
+    });
+}
+
+/// Correlation id of the panic just caught on this thread
+fn take_correlation_id(payload: &(dyn Any + Send)) -> String {
+    LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .unwrap_or_else(|| {
+            // No hook installed: log here, without a backtrace
+            let correlation_id = uuid::Uuid::new_v4().to_string();
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("non-string payload");
+            error!(correlation_id = %correlation_id, "Panic: {}", message);
+            correlation_id
+        })
+}
+
+/// `-32603` for a request whose handler panicked
+pub(crate) fn panic_response(id: Value, correlation_id: &str) -> JsonRpcResponse {
+    let mut error = JsonRpcError::from(Error::Internal(anyhow::anyhow!(
+        "the request handler panicked"
+    )));
+    if let Some(data) = &mut error.data {
+        data["correlation_id"] = json!(correlation_id);
+    }
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(error),
+    }
+}
+
+/// Answer HTTP requests whose handler panicked with a `500` JSON-RPC error
+/// instead of dropping the connection; `on_panic` runs for each of them
+pub(crate) fn catch_panic_layer<F>(
+    on_panic: F,
+) -> CatchPanicLayer<impl Fn(Box<dyn Any + Send>) -> Response<Body> + Clone>
+where
+    F: Fn() + Clone + Send + Sync + 'static,
+{
+    CatchPanicLayer::custom(move |payload: Box<dyn Any + Send>| {
+        on_panic();
+        let correlation_id = take_correlation_id(payload.as_ref());
+        // The request id is gone with the panicking handler
+        let body =
+            serde_json::to_vec(&panic_response(Value::Null, &correlation_id)).unwrap_or_default();
+        Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("static response parts are valid")
+    })
+}
+
+/// Run `future`, turning a panic into `Err` with its correlation id
+pub(crate) async fn catch_panic<F: Future>(future: F) -> std::result::Result<F::Output, String> {
+    CatchUnwind(Box::pin(future)).await
+}
+
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = std::result::Result<F::Output, String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.0.as_mut();
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(take_correlation_id(payload.as_ref()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_panics_become_internal_errors() {
+        install_panic_hook();
+        let panics = Arc::new(AtomicU64::new(0));
+        let router = Router::new()
+            .route("/boom", get(|| async { panic!("boom") as &str }))
+            .layer(catch_panic_layer({
+                let panics = Arc::clone(&panics);
+                move || {
+                    panics.fetch_add(1, Ordering::Relaxed);
+                }
+            }));
+
+        let response = router
+            .oneshot(
+                axum::http::Request::get("/boom")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], -32603);
+        assert_eq!(body["error"]["data"]["error_type"], "internal_error");
+        assert!(body["error"]["data"]["correlation_id"].is_string());
+        assert_eq!(panics.load(Ordering::Relaxed), 1);
+
+        let caught = catch_panic(async { panic!("boom") as u8 }).await;
+        assert_eq!(caught.unwrap_err().len(), 36);
+        assert_eq!(catch_panic(async { 7 }).await, Ok(7));
+    }
+
+    #[test]
+    fn test_release_profile_unwinds() {
+        // cargo forces unwinding in test builds, so check what the shipped binary gets
+        let manifest: toml::Value = toml::from_str(include_str!("../../Cargo.toml")).unwrap();
+        let panic = manifest
+            .get("profile")
+            .and_then(|profiles| profiles.get("release"))
+            .and_then(|release| release.get("panic"))
+            .and_then(toml::Value::as_str);
+        assert_ne!(
+            panic,
+            Some("abort"),
+            "panic = \"abort\" in the release profile disables panic recovery"
+        );
+    }
+}
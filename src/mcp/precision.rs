@@ -0,0 +1,160 @@
+use crate::mcp::sanitize::COORDINATE_DECIMALS;
+use serde_json::Value;
+
+/// Decimal places rates, scores and other fractional values are written with
+pub const DEFAULT_OUTPUT_DECIMALS: u32 = 3;
+/// Most decimal places worth writing; an f64 holds about 15 significant digits
+pub const MAX_OUTPUT_DECIMALS: u32 = 15;
+
+/// Fields whose numbers, nested ones included, are positions rather than measures
+const COORDINATE_FIELDS: &[&str] = &[
+    "latitude",
+    "longitude",
+    "lat",
+    "lon",
+    "lng",
+    "north",
+    "south",
+    "east",
+    "west",
+    "coordinates",
+    "bbox",
+];
+
+/// How many decimal places numbers in responses keep, so an occupancy of
+/// 0.5104166666666666 is written 0.51 instead of spending tokens on noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputPrecision {
+    /// For latitudes, longitudes, bounds and GeoJSON positions (default 6, about 11 cm)
+    pub coordinate_decimals: u32,
+    /// For every other fractional number (default 3)
+    pub decimals: u32,
+}
+
+impl Default for OutputPrecision {
+    fn default() -> Self {
+        Self {
+            coordinate_decimals: COORDINATE_DECIMALS.unsigned_abs(),
+            decimals: DEFAULT_OUTPUT_DECIMALS,
+        }
+    }
+}
+
+impl OutputPrecision {
+    /// Round every fractional number in `value`; integers are left alone
+    pub fn round(self, value: &mut Value) {
+        self.round_with(value, self.decimals);
+    }
+
+    /// `value` with its fractional numbers rounded
+    #[must_use]
+    pub fn rounded(self, mut value: Value) -> Value {
+        self.round(&mut value);
+        value
+    }
+
+    fn round_with(self, value: &mut Value, decimals: u32) {
+        match value {
+            Value::Object(object) => {
+                for (key, field) in object.iter_mut() {
+                    let decimals = if COORDINATE_FIELDS.contains(&key.as_str()) {
+                        self.coordinate_decimals
+                    } else {
+                        decimals
+                    };
+                    self.round_with(field, decimals);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.round_with(item, decimals);
+                }
+            }
+            Value::Number(number) if number.is_f64() => {
+                if let Some(rounded) = number
+                    .as_f64()
+                    .map(|float| round(float, decimals))
+                    .and_then(serde_json::Number::from_f64)
+                {
+                    *number = rounded;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Round the JSON text blocks of a tool result; other blocks are left alone
+    #[must_use]
+    pub fn result(self, mut result: Value) -> Value {
+        let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+            return result;
+        };
+        for block in content {
+            let Some(text) = block.get("text").and_then(Value::as_str) else {
+                continue;
+            };
+            let Ok(mut output) = serde_json::from_str::<Value>(text) else {
+                continue;
+            };
+            self.round(&mut output);
+            if let Ok(text) = serde_json::to_string_pretty(&output) {
+                block["text"] = Value::String(text);
+            }
+        }
+        result
+    }
+}
+
+fn round(value: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(i32::try_from(decimals.min(MAX_OUTPUT_DECIMALS)).unwrap_or(0));
+    let rounded = (value * scale).round() / scale;
+    // Very large values would overflow the scale; they have no decimals worth keeping anyway
+    if rounded.is_finite() {
+        rounded
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rates_and_coordinates_rounded_separately() {
+        let mut output = json!({
+            "stations": [{
+                "coordinates": {"latitude": 48.856_613_456, "longitude": 2.352_221_999},
+                "occupancy": 0.510_416_666_666_666_6,
+                "distance_meters": 350,
+                "capacity": 30
+            }],
+            "bounds": {"north": 48.902_145_1, "south": 48.815_573_9},
+            "route": {"type": "LineString", "coordinates": [[2.352_221_999, 48.856_613_456]]},
+            "reliability_score": 0.999_6
+        });
+        OutputPrecision::default().round(&mut output);
+
+        let station = &output["stations"][0];
+        assert_eq!(station["coordinates"]["latitude"], 48.856_613);
+        assert_eq!(station["coordinates"]["longitude"], 2.352_222);
+        assert_eq!(station["occupancy"], 0.51);
+        assert_eq!(station["distance_meters"], 350);
+        assert!(station["capacity"].is_u64());
+        assert_eq!(output["bounds"]["north"], 48.902_145);
+        assert_eq!(output["route"]["coordinates"][0][1], 48.856_613);
+        assert_eq!(output["reliability_score"], 1.0);
+
+        let coarse = OutputPrecision {
+            coordinate_decimals: 4,
+            decimals: 1,
+        };
+        let result = coarse.result(json!({
+            "content": [{"type": "text", "text": "{\"latitude\": 48.856613, \"rate\": 0.25}"}]
+        }));
+        let text: Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(text, json!({"latitude": 48.8566, "rate": 0.3}));
+    }
+}
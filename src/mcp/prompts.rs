@@ -0,0 +1,139 @@
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptArgument {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+/// A prompt template as advertised by `prompts/list`
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[must_use]
+pub fn builtin_prompts() -> Vec<PromptDefinition> {
+    vec![
+        PromptDefinition {
+            name: "find_bike_near",
+            description: "Find a station with bikes available near a place or landmark",
+            arguments: vec![PromptArgument {
+                name: "place",
+                description: "Address, station name or landmark, e.g. \"BnF\"",
+                required: true,
+            }],
+        },
+        PromptDefinition {
+            name: "plan_trip",
+            description: "Plan a Velib trip between two places",
+            arguments: vec![
+                PromptArgument {
+                    name: "origin",
+                    description: "Where the trip starts",
+                    required: true,
+                },
+                PromptArgument {
+                    name: "destination",
+                    description: "Where the trip ends",
+                    required: true,
+                },
+                PromptArgument {
+                    name: "bike_type",
+                    description: "mechanical, electric or any",
+                    required: false,
+                },
+            ],
+        },
+        PromptDefinition {
+            name: "commute_check",
+            description: "Check the session's favorite stations before leaving",
+            arguments: vec![],
+        },
+    ]
+}
+
+/// The `prompts/list` result
+#[must_use]
+pub fn list() -> Value {
+    json!({ "prompts": builtin_prompts() })
+}
+
+/// The `prompts/get` result for a prompt rendered with `arguments`
+pub fn get(name: &str, arguments: &Map<String, Value>) -> Result<Value> {
+    let prompt = builtin_prompts()
+        .into_iter()
+        .find(|prompt| prompt.name == name)
+        .ok_or_else(|| Error::Validation(format!("Unknown prompt: {name}")))?;
+
+    let argument = |name: &str| arguments.get(name).and_then(Value::as_str);
+    for expected in prompt.arguments.iter().filter(|argument| argument.required) {
+        if argument(expected.name).is_none() {
+            return Err(Error::Validation(format!(
+                "Prompt '{}' needs the '{}' argument",
+                prompt.name, expected.name
+            )));
+        }
+    }
+
+    let text = match prompt.name {
+        "find_bike_near" => format!(
+            "Find a Velib station with bikes available near {}. Resolve the place with \
+             search_stations_by_name, then use find_nearby_stations around the best match.",
+            argument("place").unwrap_or_default()
+        ),
+        "plan_trip" => format!(
+            "Plan a Velib trip from {} to {}{}. Resolve both places with search_stations_by_name, \
+             then call plan_bike_journey and summarize the best pickup and dropoff.",
+            argument("origin").unwrap_or_default(),
+            argument("destination").unwrap_or_default(),
+            argument("bike_type")
+                .map(|bike_type| format!(" on a {bike_type} bike"))
+                .unwrap_or_default()
+        ),
+        _ => "Call get_favorites_status and tell me, in two sentences, which of my favorite \
+              stations to head to right now and why."
+            .to_string(),
+    };
+
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [
+            {
+                "role": "user",
+                "content": {"type": "text", "text": text}
+            }
+        ]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_renders_arguments() {
+        let arguments = json!({"origin": "Bastille", "destination": "BnF"});
+        let prompt = get("plan_trip", arguments.as_object().unwrap()).unwrap();
+
+        let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.starts_with("Plan a Velib trip from Bastille to BnF."));
+    }
+
+    #[test]
+    fn test_get_rejects_missing_arguments_and_unknown_prompts() {
+        assert!(matches!(
+            get("find_bike_near", &Map::new()),
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            get("nonexistent", &Map::new()),
+            Err(Error::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,548 @@
+use super::auth::{is_key_id, key_id};
+use crate::{Error, Result};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, RwLock};
+
+/// Quota key shared by callers without an API key
+pub const ANONYMOUS: &str = "anonymous";
+
+/// Keys counted at most; past it the key idle the longest is forgotten
+pub const MAX_QUOTA_KEYS: usize = 10_000;
+
+/// How often counts are written to `VELIB_QUOTA_PATH`, see [`QuotaStore::flush`]
+pub const QUOTA_FLUSH_SECONDS: u64 = 30;
+
+/// Tool calls allowed per API key; `None` leaves a period unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaLimits {
+    pub daily: Option<u32>,
+    pub monthly: Option<u32>,
+}
+
+impl QuotaLimits {
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.daily.is_none() && self.monthly.is_none()
+    }
+}
+
+/// Calls counted for one key in the current UTC day and month
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct KeyUsage {
+    day: NaiveDate,
+    daily_calls: u32,
+    month: NaiveDate,
+    monthly_calls: u32,
+}
+
+impl KeyUsage {
+    fn new(today: NaiveDate) -> Self {
+        Self {
+            day: today,
+            daily_calls: 0,
+            month: first_of_month(today),
+            monthly_calls: 0,
+        }
+    }
+
+    /// Start over whichever periods have ended
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.day != today {
+            self.day = today;
+            self.daily_calls = 0;
+        }
+        if self.month != first_of_month(today) {
+            self.month = first_of_month(today);
+            self.monthly_calls = 0;
+        }
+    }
+}
+
+/// Usage of one period against its limit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodUsage {
+    pub used: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Quota usage of an API key, as shown by the admin endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaUsage {
+    /// [`key_id`] of the caller's session key, or [`ANONYMOUS`]
+    pub key_id: String,
+    pub daily: PeriodUsage,
+    pub monthly: PeriodUsage,
+}
+
+/// Daily and monthly tool call counts per API key, optionally persisted to a JSON file
+///
+/// Keys are stored as their [`key_id`], never as given. Calls are counted in
+/// memory; [`Self::flush`] writes the counts out, on an interval and at shutdown.
+#[derive(Debug, Default)]
+pub struct QuotaStore {
+    // Replaced when the configuration is reloaded, counts are kept
+    limits: std::sync::RwLock<QuotaLimits>,
+    path: Option<PathBuf>,
+    usage: RwLock<HashMap<String, KeyUsage>>,
+    // Counts changed since the last flush
+    dirty: AtomicBool,
+    // One flush writes the file at a time
+    flushing: Mutex<()>,
+}
+
+impl QuotaStore {
+    /// A store that only lives as long as the process
+    #[must_use]
+    pub fn in_memory(limits: QuotaLimits) -> Self {
+        Self {
+            limits: std::sync::RwLock::new(limits),
+            ..Self::default()
+        }
+    }
+
+    /// Load counts from `path`, which is created on first write if missing
+    pub fn load(path: &Path, limits: QuotaLimits) -> Result<Self> {
+        let usage: HashMap<String, KeyUsage> = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(Error::Config(format!(
+                    "Cannot read quota file {}: {e}",
+                    path.to_string_lossy()
+                )))
+            }
+        };
+
+        // Files written before keys were hashed hold the API keys in the clear
+        let usage = usage
+            .into_iter()
+            .map(|(key, entry)| {
+                let id = if key == ANONYMOUS || is_key_id(&key) {
+                    key
+                } else {
+                    key_id(&format!("key:{key}"))
+                };
+                (id, entry)
+            })
+            .collect();
+
+        Ok(Self {
+            limits: std::sync::RwLock::new(limits),
+            path: Some(path.to_path_buf()),
+            usage: RwLock::new(usage),
+            ..Self::default()
+        })
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> QuotaLimits {
+        *self
+            .limits
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Apply new limits to the usage counted so far
+    pub fn set_limits(&self, limits: QuotaLimits) {
+        *self
+            .limits
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = limits;
+    }
+
+    /// Count a tool call against the caller with session key `key`, or the
+    /// shared [`ANONYMOUS`] quota for `None`; or refuse it once a period is exhausted
+    ///
+    /// Refused calls are not counted. Without limits nothing is tracked.
+    pub async fn charge(&self, key: Option<&str>, now: DateTime<Utc>) -> Result<QuotaUsage> {
+        let id = key.map_or_else(|| ANONYMOUS.to_string(), key_id);
+        let limits = self.limits();
+        if limits.is_unlimited() {
+            return Ok(self.summarize(&id, &KeyUsage::new(now.date_naive())));
+        }
+
+        let today = now.date_naive();
+        let mut usage = self.usage.write().await;
+        if !usage.contains_key(&id) && usage.len() >= MAX_QUOTA_KEYS {
+            make_room(&mut usage, today);
+        }
+        let entry = usage
+            .entry(id.clone())
+            .or_insert_with(|| KeyUsage::new(today));
+        entry.roll_over(today);
+
+        for (period, used, limit, resets_at) in [
+            ("daily", entry.daily_calls, limits.daily, next_day(today)),
+            (
+                "monthly",
+                entry.monthly_calls,
+                limits.monthly,
+                next_month(today),
+            ),
+        ] {
+            if let Some(limit) = limit.filter(|&limit| used >= limit) {
+                return Err(Error::QuotaExceeded {
+                    period,
+                    limit,
+                    used,
+                    resets_at,
+                });
+            }
+        }
+
+        entry.daily_calls += 1;
+        entry.monthly_calls += 1;
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(self.summarize(&id, entry))
+    }
+
+    /// Current usage of every key seen this month, busiest first
+    pub async fn list(&self, now: DateTime<Utc>) -> Vec<QuotaUsage> {
+        let today = now.date_naive();
+        let usage = self.usage.read().await;
+        let mut keys: Vec<QuotaUsage> = usage
+            .iter()
+            .map(|(key, entry)| {
+                let mut entry = entry.clone();
+                entry.roll_over(today);
+                self.summarize(key, &entry)
+            })
+            .filter(|summary| summary.monthly.used > 0)
+            .collect();
+        keys.sort_by(|a, b| {
+            b.monthly
+                .used
+                .cmp(&a.monthly.used)
+                .then_with(|| a.key_id.cmp(&b.key_id))
+        });
+        keys
+    }
+
+    /// Current usage of one key, given as an API key, a session key or a [`key_id`]
+    pub async fn get(&self, key: &str, now: DateTime<Utc>) -> QuotaUsage {
+        let today = now.date_naive();
+        let usage = self.usage.read().await;
+        let id = lookup_id(&usage, key);
+        let mut entry = usage
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| KeyUsage::new(today));
+        entry.roll_over(today);
+        self.summarize(&id, &entry)
+    }
+
+    /// Forget the counts of `key`, given as an API key, a session key or a
+    /// [`key_id`]; returns whether it had any
+    pub async fn reset(&self, key: &str) -> bool {
+        let mut usage = self.usage.write().await;
+        let id = lookup_id(&usage, key);
+        let existed = usage.remove(&id).is_some();
+        if existed {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        existed
+    }
+
+    /// Write the counts to the store's file if they changed since the last
+    /// flush, forgetting keys unused this month
+    ///
+    /// Only copying the counts holds the lock tool calls wait on; the file is
+    /// written after.
+    pub async fn flush(&self, now: DateTime<Utc>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _flushing = self.flushing.lock().await;
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let snapshot = {
+            let mut usage = self.usage.write().await;
+            let month = first_of_month(now.date_naive());
+            usage.retain(|_, entry| entry.month >= month);
+            usage.clone()
+        };
+        let written = write_atomically(path, &snapshot).await;
+        if written.is_err() {
+            // Try again at the next flush
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        written
+    }
+
+    fn summarize(&self, key_id: &str, entry: &KeyUsage) -> QuotaUsage {
+        let limits = self.limits();
+        let period = |used: u32, limit: Option<u32>, resets_at| PeriodUsage {
+            used,
+            limit,
+            remaining: limit.map(|limit| limit.saturating_sub(used)),
+            resets_at,
+        };
+        QuotaUsage {
+            key_id: key_id.to_string(),
+            daily: period(entry.daily_calls, limits.daily, next_day(entry.day)),
+            monthly: period(entry.monthly_calls, limits.monthly, next_month(entry.month)),
+        }
+    }
+}
+
+async fn write_atomically(path: &Path, usage: &HashMap<String, KeyUsage>) -> Result<()> {
+    // Write to a sibling file first so a crash never leaves a truncated store
+    let contents = serde_json::to_vec_pretty(usage)?;
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, contents)
+        .await
+        .map_err(|e| Error::Internal(e.into()))?;
+    tokio::fs::rename(&tmp_path, path)
+        .await
+        .map_err(|e| Error::Internal(e.into()))?;
+    Ok(())
+}
+
+/// The stored id of `key` given by an admin, who may know the id, the session
+/// key or, most often, the API key
+fn lookup_id(usage: &HashMap<String, KeyUsage>, key: &str) -> String {
+    if usage.contains_key(key) {
+        return key.to_string();
+    }
+    let session_key = key_id(key);
+    if usage.contains_key(&session_key) {
+        return session_key;
+    }
+    key_id(&format!("key:{key}"))
+}
+
+/// Forget keys unused this month, or else the one idle the longest
+fn make_room(usage: &mut HashMap<String, KeyUsage>, today: NaiveDate) {
+    let month = first_of_month(today);
+    usage.retain(|_, entry| entry.month >= month);
+    if usage.len() < MAX_QUOTA_KEYS {
+        return;
+    }
+    if let Some(idle) = usage
+        .iter()
+        .min_by_key(|(_, entry)| entry.day)
+        .map(|(id, _)| id.clone())
+    {
+        usage.remove(&idle);
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("every month has a first day")
+}
+
+fn next_day(date: NaiveDate) -> DateTime<Utc> {
+    midnight(date + Days::new(1))
+}
+
+fn next_month(date: NaiveDate) -> DateTime<Utc> {
+    midnight(first_of_month(date) + Months::new(1))
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight exists")
+        .and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        timestamp.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_daily_quota_is_enforced_and_resets() {
+        let store = QuotaStore::in_memory(QuotaLimits {
+            daily: Some(2),
+            monthly: None,
+        });
+        let now = at("2026-10-15T10:00:00Z");
+        store.charge(Some("key:commuter"), now).await.unwrap();
+        let usage = store.charge(Some("key:commuter"), now).await.unwrap();
+        assert_eq!(usage.daily.remaining, Some(0));
+
+        match store.charge(Some("key:commuter"), now).await {
+            Err(Error::QuotaExceeded {
+                period,
+                limit,
+                resets_at,
+                ..
+            }) => {
+                assert_eq!(period, "daily");
+                assert_eq!(limit, 2);
+                assert_eq!(resets_at, at("2026-10-16T00:00:00Z"));
+            }
+            other => panic!("expected an exhausted quota, got {other:?}"),
+        }
+        assert!(store.charge(Some("key:tourist"), now).await.is_ok());
+
+        let tomorrow = at("2026-10-16T00:00:01Z");
+        let usage = store.charge(Some("key:commuter"), tomorrow).await.unwrap();
+        assert_eq!(usage.daily.used, 1);
+        assert_eq!(usage.monthly.used, 3);
+    }
+
+    #[tokio::test]
+    async fn test_monthly_quota_and_reset() {
+        let store = QuotaStore::in_memory(QuotaLimits {
+            daily: None,
+            monthly: Some(1),
+        });
+        store
+            .charge(Some("key:commuter"), at("2026-10-31T23:00:00Z"))
+            .await
+            .unwrap();
+        let exhausted = store
+            .charge(Some("key:commuter"), at("2026-10-31T23:30:00Z"))
+            .await;
+        assert!(matches!(
+            exhausted,
+            Err(Error::QuotaExceeded { period: "monthly", resets_at, .. })
+                if resets_at == at("2026-11-01T00:00:00Z")
+        ));
+
+        assert!(store.reset("commuter").await);
+        assert!(!store.reset("commuter").await);
+        assert!(store
+            .charge(Some("key:commuter"), at("2026-10-31T23:45:00Z"))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_quotas_survive_reload() {
+        let path = std::env::temp_dir().join(format!("velib-quotas-{}.json", uuid::Uuid::new_v4()));
+        let limits = QuotaLimits {
+            daily: Some(10),
+            monthly: Some(100),
+        };
+        let now = at("2026-10-15T10:00:00Z");
+
+        let store = QuotaStore::load(&path, limits).unwrap();
+        store.charge(Some("key:commuter"), now).await.unwrap();
+        store.charge(Some("key:commuter"), now).await.unwrap();
+        // Counted in memory until flushed
+        assert!(!path.exists());
+        store.flush(now).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("commuter"));
+        assert!(contents.contains(&key_id("key:commuter")));
+
+        let reloaded = QuotaStore::load(&path, limits).unwrap();
+        let usage = reloaded.get("commuter", now).await;
+        assert_eq!(usage.key_id, key_id("key:commuter"));
+        assert_eq!(usage.daily.used, 2);
+        assert_eq!(usage.monthly.remaining, Some(98));
+        assert_eq!(reloaded.get(&key_id("key:commuter"), now).await, usage);
+        assert_eq!(reloaded.list(now).await.len(), 1);
+
+        // Entries of past months are dropped when saving
+        reloaded
+            .charge(Some("key:commuter"), at("2026-11-02T08:00:00Z"))
+            .await
+            .unwrap();
+        reloaded
+            .charge(Some("key:tourist"), at("2026-12-01T08:00:00Z"))
+            .await
+            .unwrap();
+        reloaded.flush(at("2026-12-01T08:00:00Z")).await.unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&key_id("key:commuter")));
+        assert!(contents.contains(&key_id("key:tourist")));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keys_saved_in_the_clear_are_hashed_on_load() {
+        let path = std::env::temp_dir().join(format!("velib-quotas-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{"commuter": {"day": "2026-10-15", "daily_calls": 3, "month": "2026-10-01", "monthly_calls": 7}}"#,
+        )
+        .unwrap();
+        let store = QuotaStore::load(
+            &path,
+            QuotaLimits {
+                daily: Some(10),
+                monthly: None,
+            },
+        )
+        .unwrap();
+        let listed = store.list(at("2026-10-15T10:00:00Z")).await;
+        assert_eq!(listed[0].key_id, key_id("key:commuter"));
+        assert_eq!(listed[0].daily.used, 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_keys_are_always_hashed() {
+        let store = QuotaStore::in_memory(QuotaLimits {
+            daily: Some(1),
+            monthly: None,
+        });
+        let now = at("2026-10-15T10:00:00Z");
+        let hex_key = "ab".repeat(32);
+        store.charge(Some(&hex_key), now).await.unwrap();
+        store.charge(Some(ANONYMOUS), now).await.unwrap();
+        // Neither shares the anonymous quota
+        store.charge(None, now).await.unwrap();
+
+        let ids: Vec<String> = store
+            .list(now)
+            .await
+            .into_iter()
+            .map(|usage| usage.key_id)
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert!(!ids.contains(&hex_key));
+        assert!(ids.contains(&key_id(&hex_key)));
+        assert!(ids.contains(&key_id(ANONYMOUS)));
+        assert!(ids.iter().any(|id| id == ANONYMOUS));
+    }
+
+    #[tokio::test]
+    async fn test_key_count_is_capped() {
+        let store = QuotaStore::in_memory(QuotaLimits {
+            daily: Some(10),
+            monthly: None,
+        });
+        store
+            .charge(Some("key:oldest"), at("2026-10-14T10:00:00Z"))
+            .await
+            .unwrap();
+        let now = at("2026-10-15T10:00:00Z");
+        for n in 1..=MAX_QUOTA_KEYS {
+            store.charge(Some(&format!("key:{n}")), now).await.unwrap();
+        }
+        let listed = store.list(now).await;
+        assert_eq!(listed.len(), MAX_QUOTA_KEYS);
+        assert!(listed
+            .iter()
+            .all(|usage| usage.key_id != key_id("key:oldest")));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_store_tracks_nothing() {
+        let store = QuotaStore::in_memory(QuotaLimits::default());
+        let now = at("2026-10-15T10:00:00Z");
+        for _ in 0..3 {
+            store.charge(Some("key:commuter"), now).await.unwrap();
+        }
+        assert!(store.list(now).await.is_empty());
+    }
+}
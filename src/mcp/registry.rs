@@ -0,0 +1,1216 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::types::ServiceArea;
+use crate::Error;
+
+/// Version of the tool argument contract advertised in `initialize`
+///
+/// Minor bumps only add tools or optional fields. Fields deprecated in a minor
+/// release keep being accepted, through [`upgrade_arguments`], until the next
+/// major release.
+pub const TOOL_API_VERSION: &str = "1.34";
+
+/// Announced retirement of the current tool API major version
+///
+/// Sent on every HTTP response, next to `X-API-Version`, as `Deprecation`
+/// (RFC 9745) and `Sunset` (RFC 8594) headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApiLifecycle {
+    /// When the current major version was deprecated
+    pub deprecated_at: Option<DateTime<Utc>>,
+    /// When the current major version stops being served
+    pub sunset_at: Option<DateTime<Utc>>,
+}
+
+impl ApiLifecycle {
+    /// Headers telling HTTP clients which tool API they talk to and when it goes away
+    #[must_use]
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("x-api-version"),
+            HeaderValue::from_static(TOOL_API_VERSION),
+        );
+        if let Some(deprecated_at) = self.deprecated_at {
+            headers.insert(
+                HeaderName::from_static("deprecation"),
+                header_value(&format!("@{}", deprecated_at.timestamp())),
+            );
+        }
+        if let Some(sunset_at) = self.sunset_at {
+            headers.insert(
+                HeaderName::from_static("sunset"),
+                header_value(&sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            );
+        }
+        headers
+    }
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).expect("formatted dates are valid header values")
+}
+
+/// A tool as advertised by `tools/list`
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(rename = "inputSchema")]
+    pub input_schema: Value,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Oldest real-time data the tool may answer from; older cached data is
+    /// refetched first. `None` for tools that don't read real-time data.
+    #[serde(skip)]
+    pub max_data_age: Option<Duration>,
+    /// Longest a call may run before failing with a timeout
+    #[serde(skip)]
+    pub timeout: Duration,
+    /// Advertised as `_meta.costHints`
+    #[serde(rename = "_meta", serialize_with = "cost_hints_meta")]
+    pub cost: CostHints,
+}
+
+/// How expensive a tool is relative to the others, so planners can try the
+/// cheaper ones first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostHints {
+    /// Expected latency with warm caches
+    pub latency: LatencyClass,
+    /// A call may have to wait for the upstream API when cached data is too old
+    pub upstream_fetch: bool,
+    /// Typical size of the result
+    pub result_size: ResultSize,
+}
+
+impl CostHints {
+    const fn new(latency: LatencyClass, upstream_fetch: bool, result_size: ResultSize) -> Self {
+        Self {
+            latency,
+            upstream_fetch,
+            result_size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyClass {
+    /// A lookup or a handful of stations
+    Fast,
+    /// A scan of every station
+    Moderate,
+    /// Geometry over every station, or walking routes to several of them
+    Slow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSize {
+    /// Under about 2 KB: one station or a summary
+    Small,
+    /// A page of stations
+    Medium,
+    /// A grid or a list of changes across the city
+    Large,
+}
+
+fn cost_hints_meta<S: serde::Serializer>(
+    cost: &CostHints,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    json!({ "costHints": cost }).serialize(serializer)
+}
+
+/// Time limit of most tools: a cold cache can wait 10 seconds for an upstream
+/// request slot before fetching
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time limit of tools scanning every station geometrically
+const SLOW_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SLOW_TOOLS: &[&str] = &[
+    "get_area_statistics",
+    "get_station_density",
+    "find_stations_along_route",
+    "plan_bike_journey",
+    "plan_multiple_journeys",
+];
+
+/// A deprecated argument and how calls still using it are rewritten
+struct ArgumentShim {
+    tool: &'static str,
+    field: &'static str,
+    deprecated_in: &'static str,
+    upgrade: fn(&mut serde_json::Map<String, Value>),
+}
+
+const ARGUMENT_SHIMS: &[ArgumentShim] = &[
+    // Both flags were never honored: responses always carry real-time data
+    ArgumentShim {
+        tool: "get_station_by_code",
+        field: "include_real_time",
+        deprecated_in: "1.1",
+        upgrade: |args| {
+            args.remove("include_real_time");
+        },
+    },
+    ArgumentShim {
+        tool: "get_area_statistics",
+        field: "include_real_time",
+        deprecated_in: "1.1",
+        upgrade: |args| {
+            args.remove("include_real_time");
+        },
+    },
+];
+
+/// A deprecated argument found in a tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedArgument {
+    pub field: &'static str,
+    pub deprecated_in: &'static str,
+}
+
+/// Rewrite arguments written against an older tool API into the current shape
+///
+/// Returns the deprecated arguments the call used, so they can be reported.
+pub fn upgrade_arguments(tool_name: &str, arguments: &mut Value) -> Vec<DeprecatedArgument> {
+    let Some(args) = arguments.as_object_mut() else {
+        return Vec::new();
+    };
+
+    let mut deprecated = Vec::new();
+    for shim in ARGUMENT_SHIMS {
+        if shim.tool == tool_name && args.contains_key(shim.field) {
+            (shim.upgrade)(args);
+            deprecated.push(DeprecatedArgument {
+                field: shim.field,
+                deprecated_in: shim.deprecated_in,
+            });
+        }
+    }
+    deprecated
+}
+
+/// The override for `name` in `timeouts`, which wins over a `default` one
+fn timeout_override(name: &str, timeouts: &[(String, Duration)]) -> Option<Duration> {
+    let find = |wanted: &str| {
+        timeouts
+            .iter()
+            .rev()
+            .find(|(tool, _)| tool == wanted)
+            .map(|&(_, timeout)| timeout)
+    };
+    find(name).or_else(|| find("default"))
+}
+
+/// Which tools a deployment serves
+///
+/// Tools left out disappear from `tools/list` and `/functions.json`, and
+/// calling them fails with a `tool_disabled` error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolSelection {
+    /// Only these tools, when set
+    pub enabled: Option<Vec<String>>,
+    /// Never these tools, even when `enabled` lists them
+    pub disabled: Vec<String>,
+}
+
+impl ToolSelection {
+    #[must_use]
+    pub fn allows(&self, tool: &str) -> bool {
+        self.enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.iter().any(|name| name == tool))
+            && !self.disabled.iter().any(|name| name == tool)
+    }
+
+    /// Refuse names that are not tools of this server, which would silently
+    /// select nothing
+    pub fn validate(&self) -> crate::Result<()> {
+        let builtin = builtin_tools();
+        let unknown: Vec<&str> = self
+            .enabled
+            .iter()
+            .flatten()
+            .chain(&self.disabled)
+            .map(String::as_str)
+            .filter(|name| !builtin.iter().any(|tool| tool.name == *name))
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(format!(
+                "Unknown tools in tool selection: {}",
+                unknown.join(", ")
+            )))
+        }
+    }
+}
+
+/// The tools this server exposes
+#[derive(Debug, Clone)]
+pub struct ToolRegistry {
+    tools: Vec<ToolDefinition>,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ToolRegistry {
+    #[must_use]
+    pub fn builtin() -> Self {
+        let mut tools = builtin_tools();
+        for tool in &mut tools {
+            if SLOW_TOOLS.contains(&tool.name) {
+                tool.timeout = SLOW_TOOL_TIMEOUT;
+            }
+            tool.input_schema["properties"]["max_response_bytes"] = json!({
+                "type": "integer",
+                "minimum": 256,
+                "description": "Shrink the result to fit this many bytes: extra content blocks, optional fields and then the longest lists go first, and truncated_due_to_budget is set"
+            });
+            tool.input_schema["properties"]["max_response_tokens"] = json!({
+                "type": "integer",
+                "minimum": 64,
+                "description": "Same as max_response_bytes, counting 4 bytes per token"
+            });
+            tool.input_schema["properties"]["humanize"] = json!({
+                "type": ["boolean", "string"],
+                "description": "Add preformatted strings next to distances, durations, areas and shares (\"350 m\", \"≈ 5 min à pied\"): true for the session language, or a language such as \"fr\""
+            });
+        }
+        Self { tools }
+    }
+
+    /// Advertise the coordinate bounds of `area` in tool schemas
+    #[must_use]
+    pub fn with_service_area(mut self, area: &ServiceArea) -> Self {
+        for tool in &mut self.tools {
+            let properties = &mut tool.input_schema["properties"];
+            if properties["latitude"]["minimum"].is_number() {
+                properties["latitude"]["minimum"] = json!(area.south);
+                properties["latitude"]["maximum"] = json!(area.north);
+                properties["longitude"]["minimum"] = json!(area.west);
+                properties["longitude"]["maximum"] = json!(area.east);
+            }
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn tools(&self) -> &[ToolDefinition] {
+        &self.tools
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ToolDefinition> {
+        self.tools.iter().find(|tool| tool.name == name)
+    }
+
+    /// Freshness policy of a tool, `None` if unknown or not reading real-time data
+    #[must_use]
+    pub fn max_data_age(&self, name: &str) -> Option<Duration> {
+        self.get(name).and_then(|tool| tool.max_data_age)
+    }
+
+    /// Override time limits: `default` applies to every tool, other names to that tool
+    #[must_use]
+    pub fn with_timeouts(mut self, timeouts: &[(String, Duration)]) -> Self {
+        for tool in &mut self.tools {
+            tool.timeout = timeout_override(tool.name, timeouts).unwrap_or(tool.timeout);
+        }
+        self
+    }
+
+    /// Time limit of a tool, [`DEFAULT_TOOL_TIMEOUT`] if unknown
+    #[must_use]
+    pub fn timeout(&self, name: &str) -> Duration {
+        self.get(name)
+            .map_or(DEFAULT_TOOL_TIMEOUT, |tool| tool.timeout)
+    }
+
+    /// Time limit of a tool with `overrides` as in [`Self::with_timeouts`] on top
+    #[must_use]
+    pub fn timeout_for(&self, name: &str, overrides: &[(String, Duration)]) -> Duration {
+        timeout_override(name, overrides).unwrap_or_else(|| self.timeout(name))
+    }
+
+    /// The `tools/list` result, with the tools `selection` allows
+    #[must_use]
+    pub fn list(&self, selection: &ToolSelection) -> Value {
+        let tools: Vec<&ToolDefinition> = self
+            .tools
+            .iter()
+            .filter(|tool| selection.allows(tool.name))
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    /// The tools `selection` allows as function declarations other agent
+    /// frameworks accept as-is, leaving out deprecated ones
+    #[must_use]
+    pub fn functions(&self, format: FunctionFormat, selection: &ToolSelection) -> Value {
+        let tools = self
+            .tools
+            .iter()
+            .filter(|tool| !tool.deprecated && selection.allows(tool.name));
+        match format {
+            FunctionFormat::OpenAi => tools
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.input_schema
+                        }
+                    })
+                })
+                .collect(),
+            FunctionFormat::Anthropic => tools
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.input_schema
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Shape of the `/functions.json` manifest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FunctionFormat {
+    /// `tools` of the OpenAI Chat Completions API: `{"type": "function", "function": {...}}`
+    #[default]
+    OpenAi,
+    /// `tools` of the Anthropic Messages API: `{"name", "description", "input_schema"}`
+    Anthropic,
+}
+
+impl FromStr for FunctionFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> crate::Result<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAi),
+            "anthropic" => Ok(Self::Anthropic),
+            other => Err(Error::Validation(format!(
+                "Unknown function format {other:?} (expected openai or anthropic)"
+            ))),
+        }
+    }
+}
+
+/// Add the shared `sort_by` / `sort_direction` arguments to a tool schema
+fn with_sorting(mut schema: Value) -> Value {
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert(
+            "sort_by".to_string(),
+            json!({
+                "type": "string",
+                "enum": ["distance", "bikes_available", "docks_available", "relevance", "name"],
+                "default": "relevance"
+            }),
+        );
+        properties.insert(
+            "sort_direction".to_string(),
+            json!({
+                "type": "string",
+                "enum": ["ascending", "descending"],
+                "description": "Defaults to descending for availability and ascending otherwise"
+            }),
+        );
+    }
+    schema
+}
+
+fn builtin_tools() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "find_nearby_stations",
+            description: "Find Velib stations within a radius of coordinates",
+            input_schema: with_sorting(json!({
+                "type": "object",
+                "properties": {
+                    "latitude": {"type": "number", "minimum": 48.7, "maximum": 49.0},
+                    "longitude": {"type": "number", "minimum": 2.0, "maximum": 2.6},
+                    "radius_meters": {"type": "integer", "minimum": 100, "maximum": 5000, "default": 500},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 10},
+                    "availability_filter": {
+                        "type": "object",
+                        "properties": {
+                            "min_bikes": {"type": "integer", "minimum": 0},
+                            "min_docks": {"type": "integer", "minimum": 0},
+                            "bike_type": {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                            "purpose": {"type": "string", "enum": ["pickup", "dropoff"], "description": "Accept stations that only rent (pickup) or only take returns (dropoff); by default stations must do both"}
+                        }
+                    },
+                    "lenient": {"type": "boolean", "description": "Clamp an oversized limit (or radius) to the maximum and flag the response as truncated instead of failing"},
+                    "district": {"type": "string", "description": "Only stations in this district: a Paris arrondissement (\"Paris 12e\", \"12e\") or a commune (\"Boulogne-Billancourt\")"},
+                    "auto_expand": {"type": "boolean", "default": false, "description": "Double the radius, up to 5000 m, until min_results stations are found; the radius used is reported in search_metadata"},
+                    "min_results": {"type": "integer", "minimum": 1, "default": 1},
+                    "barrier_aware": {"type": "boolean", "default": false, "description": "Measure distance_meters as a walk through the nearest bridge or crossing when the Seine or a railway lies in between, leaving out stations that are close only as the crow flies"},
+                    "near_transit": {"type": "string", "description": "Only stations near a metro or RER line (\"Ligne 1\", \"RER A\") or stop (\"Châtelet - Les Halles\")"},
+                    "transit_radius_meters": {"type": "integer", "minimum": 1, "maximum": 5000, "default": 300, "description": "How far from a stop of near_transit stations may be"}
+                },
+                "required": ["latitude", "longitude"]
+            })),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "get_station_by_code",
+            description: "Get detailed information about a specific station",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "station_code": {"type": "string", "description": "Open data station code, or the code shown on the station's kiosk or in its QR code link (e.g. \"08026\", \"N° 8026\")"},
+                    "include_real_time": {"type": "boolean", "deprecated": true, "description": "Deprecated since tool API 1.1 and ignored: real-time data is always included"}
+                },
+                "required": ["station_code"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "search_stations_by_name",
+            description: "Search stations by name or landmark (e.g. \"BnF\", \"Gare de Lyon\") with optional fuzzy matching",
+            input_schema: with_sorting(json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "minLength": 2},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": 50, "default": 10},
+                    "fuzzy": {"type": "boolean", "default": true},
+                    "lenient": {"type": "boolean", "description": "Clamp an oversized limit (or radius) to the maximum and flag the response as truncated instead of failing"},
+                    "district": {"type": "string", "description": "Only stations in this district: a Paris arrondissement (\"Paris 12e\", \"12e\") or a commune (\"Boulogne-Billancourt\")"},
+                    "near_transit": {"type": "string", "description": "Only stations near a metro or RER line (\"Ligne 1\", \"RER A\") or stop (\"Châtelet - Les Halles\"), closest first; the query may then be left out"},
+                    "transit_radius_meters": {"type": "integer", "minimum": 1, "maximum": 5000, "default": 300, "description": "How far from a stop of near_transit stations may be"}
+                },
+                "anyOf": [{"required": ["query"]}, {"required": ["near_transit"]}]
+            })),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(300)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "autocomplete_station_names",
+            description: "Complete a partial station name into matching name/code pairs",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "prefix": {"type": "string", "minLength": 1},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 10},
+                    "lenient": {"type": "boolean", "description": "Clamp an oversized limit (or radius) to the maximum and flag the response as truncated instead of failing"}
+                },
+                "required": ["prefix"]
+            }),
+            deprecated: false,
+            max_data_age: None,
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "set_preferences",
+            description: "Store defaults (bike type, max walk distance, home/work, language) applied to later calls in this session",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "bike_type": {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                    "max_walk_distance": {"type": "integer", "minimum": 0},
+                    "home": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "work": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "language": {"type": "string"}
+                }
+            }),
+            deprecated: false,
+            max_data_age: None,
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, false, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "add_favorite_station",
+            description: "Save a station (with an optional label such as \"Home\") to this session's favorites",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "station_code": {"type": "string"},
+                    "label": {"type": "string"}
+                },
+                "required": ["station_code"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(600)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "list_favorite_stations",
+            description: "List this session's favorite stations",
+            input_schema: json!({"type": "object", "properties": {}}),
+            deprecated: false,
+            max_data_age: None,
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, false, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "get_favorites_status",
+            description: "Get real-time availability for every favorite station in one call",
+            input_schema: with_sorting(json!({"type": "object", "properties": {}})),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "render_station_sparkline",
+            description: "Draw a station's bike availability over the last hours (up to 24) as a unicode sparkline, optionally with an SVG chart",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "station_code": {"type": "string"},
+                    "hours": {"type": "integer", "minimum": 1, "maximum": 24, "default": 24},
+                    "format": {"type": "string", "enum": ["text", "svg"], "default": "text"}
+                },
+                "required": ["station_code"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(300)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "get_area_statistics",
+            description: "Get aggregated statistics for a geographic area (bounding box or GeoJSON polygon), overall and per district",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "bounds": {
+                        "type": "object",
+                        "properties": {
+                            "north": {"type": "number"},
+                            "south": {"type": "number"},
+                            "east": {"type": "number"},
+                            "west": {"type": "number"}
+                        },
+                        "required": ["north", "south", "east", "west"]
+                    },
+                    "polygon": {
+                        "type": "object",
+                        "description": "GeoJSON Polygon or MultiPolygon ([longitude, latitude] positions) to use instead of bounds",
+                        "properties": {
+                            "type": {"type": "string", "enum": ["Polygon", "MultiPolygon"]},
+                            "coordinates": {"type": "array"}
+                        },
+                        "required": ["type", "coordinates"]
+                    },
+                    "include_real_time": {"type": "boolean", "deprecated": true, "description": "Deprecated since tool API 1.1 and ignored: real-time data is always included"},
+                    "window_minutes": {"type": "integer", "minimum": 1, "maximum": 1440, "description": "Also report min/max/mean available bikes over this many trailing minutes of history"}
+                },
+                "oneOf": [{"required": ["bounds"]}, {"required": ["polygon"]}]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(600)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Slow, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "get_station_density",
+            description: "Get stations and docking capacity per km² for a bounding box or district, and the coverage gaps: grid cells with no station within 300 m",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "bounds": {
+                        "type": "object",
+                        "properties": {
+                            "north": {"type": "number"},
+                            "south": {"type": "number"},
+                            "east": {"type": "number"},
+                            "west": {"type": "number"}
+                        },
+                        "required": ["north", "south", "east", "west"]
+                    },
+                    "district": {"type": "string", "description": "A Paris arrondissement (\"Paris 12e\", \"12e\") or a commune, measured over the box around its stations"},
+                    "gap_distance_meters": {"type": "integer", "minimum": 1, "maximum": 2000, "default": 300, "description": "Cells with no station this close to their center are gaps"},
+                    "cell_size_meters": {"type": "integer", "minimum": 1, "maximum": 2000, "default": 300, "description": "Grid cell size; widened for large areas"}
+                },
+                "oneOf": [{"required": ["bounds"]}, {"required": ["district"]}]
+            }),
+            deprecated: false,
+            max_data_age: None,
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Slow, true, ResultSize::Large),
+        },
+        ToolDefinition {
+            name: "find_stations_along_route",
+            description: "Find stations within a buffer of a route (ordered waypoints), in the order the route passes them, to swap bikes or bail out mid-ride",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "waypoints": {
+                        "type": "array",
+                        "minItems": 2,
+                        "maxItems": 500,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "latitude": {"type": "number"},
+                                "longitude": {"type": "number"}
+                            },
+                            "required": ["latitude", "longitude"]
+                        }
+                    },
+                    "buffer_meters": {"type": "integer", "minimum": 0, "maximum": 1000, "default": 200},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 10, "description": "Keeps the stations closest to the route"},
+                    "availability_filter": {
+                        "type": "object",
+                        "properties": {
+                            "min_bikes": {"type": "integer", "minimum": 0},
+                            "min_docks": {"type": "integer", "minimum": 0},
+                            "bike_type": {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                            "purpose": {"type": "string", "enum": ["pickup", "dropoff"], "description": "Accept stations that only rent (pickup) or only take returns (dropoff); by default stations must do both"}
+                        }
+                    },
+                    "lenient": {"type": "boolean", "description": "Clamp an oversized limit to the maximum and flag the response as truncated instead of failing"}
+                },
+                "required": ["waypoints"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(60)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Slow, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "compare_stations",
+            description: "Compare 2 to 5 stations side by side (bikes by type, docks, status, distance to an optional point, reliability over the last day), as JSON and a markdown table",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "station_codes": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "minItems": 2,
+                        "maxItems": 5,
+                        "uniqueItems": true
+                    },
+                    "reference_point": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    }
+                },
+                "required": ["station_codes"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "check_dropoff_feasibility",
+            description: "Estimate the chance a dock will be free when arriving at a destination station, from current docks, the recent trend and the level at the same time the previous day, with the nearby stations most likely to have one",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "destination": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "arrival_time": {"type": "string", "format": "date-time", "description": "Estimated arrival, at most 2 hours ahead; now when omitted"},
+                    "hold_window_minutes": {"type": "integer", "minimum": 0, "maximum": 60, "default": 5, "description": "Minutes past arrival_time the estimate must still hold, to cover a late arrival"},
+                    "station_code": {"type": "string", "description": "Check this station instead of the one closest to the destination"},
+                    "max_walk_meters": {"type": "integer", "minimum": 0, "maximum": 2000, "default": 500, "description": "How far from the destination the station and alternatives may be"}
+                },
+                "required": ["destination"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(60)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "compare_with_typical",
+            description: "Compare current bikes at a station, or around a point, with the usual level at the same weekday and hour over the previous weeks of recorded history, to tell whether it is unusually empty or full right now",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "station_code": {"type": "string", "description": "Compare this station; otherwise give latitude and longitude"},
+                    "latitude": {"type": "number"},
+                    "longitude": {"type": "number"},
+                    "radius_meters": {"type": "integer", "minimum": 1, "maximum": 5000, "default": 500, "description": "Stations around latitude and longitude added up"}
+                }
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "diff_station_status",
+            description: "List only the stations whose bikes, docks or status changed since a snapshot id returned by a previous call (or since a time), for bots polling the network. Returns the new snapshot id to pass next time; without a usable baseline every station is listed",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "since_snapshot": {"type": "string", "description": "snapshot returned by a previous call"},
+                    "since": {"type": "string", "format": "date-time", "description": "Diff against the state published at this time instead"}
+                }
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(60)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Large),
+        },
+        ToolDefinition {
+            name: "generate_journey_link",
+            description: "Turn a planned journey into a link the user can tap: Google Maps cycling directions through both stations, a geo: URI of the pickup station, or a page on this server kept for a day",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pickup_station_code": {"type": "string"},
+                    "dropoff_station_code": {"type": "string"},
+                    "origin": {
+                        "type": "object",
+                        "description": "Where the walk to the pickup station starts",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "destination": {
+                        "type": "object",
+                        "description": "Where the walk from the dropoff station ends",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "format": {"type": "string", "enum": ["google_maps", "geo", "page"], "default": "google_maps"}
+                },
+                "required": ["pickup_station_code", "dropoff_station_code"]
+            }),
+            deprecated: false,
+            max_data_age: None,
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Fast, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "get_system_overview",
+            description: "Get citywide totals (stations, bikes by type, docks, occupancy) and advisories about unusual conditions such as strike days or outages",
+            input_schema: json!({"type": "object", "properties": {}}),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "get_fleet_composition",
+            description: "Get how many mechanical and electric bikes are docked citywide and the electric share, optionally with its evolution over the last hours",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "window_minutes": {"type": "integer", "minimum": 1, "maximum": 1440, "description": "Also report the split over this many trailing minutes"},
+                    "interval_minutes": {"type": "integer", "minimum": 1, "maximum": 1440, "default": 60, "description": "Width of each history point"}
+                }
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(120)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Moderate, true, ResultSize::Small),
+        },
+        ToolDefinition {
+            name: "plan_bike_journey",
+            description: "Plan a bike journey with pickup and dropoff suggestions",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "origin": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "destination": {
+                        "type": "object",
+                        "properties": {
+                            "latitude": {"type": "number"},
+                            "longitude": {"type": "number"}
+                        },
+                        "required": ["latitude", "longitude"]
+                    },
+                    "preferences": {
+                        "type": "object",
+                        "properties": {
+                            "bike_type": {
+                                "description": "A bike type, or several in order of preference: [\"electric\", \"mechanical\"] prefers e-bikes and falls back to mechanical ones",
+                                "oneOf": [
+                                    {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                                    {
+                                        "type": "array",
+                                        "items": {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                                        "minItems": 1
+                                    }
+                                ]
+                            },
+                            "max_walk_distance": {"type": "integer", "minimum": 0, "default": 500},
+                            "barrier_aware": {"type": "boolean", "default": false, "description": "Measure walks to and from stations through the nearest bridge or crossing when the Seine or a railway lies in between"},
+                            "include_suspected_out_of_service": {"type": "boolean", "default": false, "description": "Also consider stations flagged suspected_out_of_service, which report far fewer bikes and docks than they hold and have for an hour or more"}
+                        }
+                    },
+                    "route_format": {
+                        "type": "string",
+                        "enum": ["geojson", "polyline"],
+                        "description": "Add the ride from pickup to dropoff to each recommendation as a GeoJSON LineString or a Google encoded polyline, from the routing engine when the server has one, else a straight line"
+                    }
+                },
+                "required": ["origin", "destination"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(60)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Slow, true, ResultSize::Medium),
+        },
+        ToolDefinition {
+            name: "plan_multiple_journeys",
+            description: "Plan up to 10 bike journeys at once, such as the legs between a delivery runner's stops, warning when several would compete for the last bikes or docks of a station",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "journeys": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "origin": {
+                                    "type": "object",
+                                    "properties": {
+                                        "latitude": {"type": "number"},
+                                        "longitude": {"type": "number"}
+                                    },
+                                    "required": ["latitude", "longitude"]
+                                },
+                                "destination": {
+                                    "type": "object",
+                                    "properties": {
+                                        "latitude": {"type": "number"},
+                                        "longitude": {"type": "number"}
+                                    },
+                                    "required": ["latitude", "longitude"]
+                                }
+                            },
+                            "required": ["origin", "destination"]
+                        },
+                        "minItems": 1,
+                        "maxItems": 10
+                    },
+                    "preferences": {
+                        "type": "object",
+                        "description": "Shared by every journey, as in plan_bike_journey",
+                        "properties": {
+                            "bike_type": {
+                                "description": "A bike type, or several in order of preference",
+                                "oneOf": [
+                                    {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                                    {
+                                        "type": "array",
+                                        "items": {"type": "string", "enum": ["mechanical", "electric", "any"]},
+                                        "minItems": 1
+                                    }
+                                ]
+                            },
+                            "max_walk_distance": {"type": "integer", "minimum": 0, "default": 500},
+                            "barrier_aware": {"type": "boolean", "default": false},
+                            "include_suspected_out_of_service": {"type": "boolean", "default": false}
+                        }
+                    },
+                    "route_format": {
+                        "type": "string",
+                        "enum": ["geojson", "polyline"],
+                        "description": "Add each ride to its recommendations as a GeoJSON LineString or a Google encoded polyline"
+                    }
+                },
+                "required": ["journeys"]
+            }),
+            deprecated: false,
+            max_data_age: Some(Duration::from_secs(60)),
+            timeout: DEFAULT_TOOL_TIMEOUT,
+            cost: CostHints::new(LatencyClass::Slow, true, ResultSize::Large),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_strips_deprecated_arguments() {
+        let mut arguments = json!({"station_code": "16107", "include_real_time": false});
+        let deprecated = upgrade_arguments("get_station_by_code", &mut arguments);
+
+        assert_eq!(
+            deprecated,
+            vec![DeprecatedArgument {
+                field: "include_real_time",
+                deprecated_in: "1.1"
+            }]
+        );
+        assert_eq!(arguments, json!({"station_code": "16107"}));
+
+        let mut current = json!({"station_code": "16107"});
+        assert!(upgrade_arguments("get_station_by_code", &mut current).is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_headers() {
+        let headers = ApiLifecycle::default().headers();
+        assert_eq!(headers["x-api-version"], TOOL_API_VERSION);
+        assert!(!headers.contains_key("deprecation") && !headers.contains_key("sunset"));
+
+        let lifecycle = ApiLifecycle {
+            deprecated_at: Some("2026-03-01T00:00:00Z".parse().unwrap()),
+            sunset_at: Some("2026-09-01T00:00:00Z".parse().unwrap()),
+        };
+        let headers = lifecycle.headers();
+        assert_eq!(headers["deprecation"], "@1772323200");
+        assert_eq!(headers["sunset"], "Tue, 01 Sep 2026 00:00:00 GMT");
+    }
+
+    #[test]
+    fn test_deprecated_fields_are_flagged_in_schemas() {
+        let registry = ToolRegistry::builtin();
+        for shim in ARGUMENT_SHIMS {
+            let tool = registry.get(shim.tool).expect("shim targets a known tool");
+            assert_eq!(
+                tool.input_schema["properties"][shim.field]["deprecated"], true,
+                "{}.{} should be flagged deprecated",
+                shim.tool, shim.field
+            );
+        }
+    }
+
+    #[test]
+    fn test_freshness_policies_fit_the_realtime_cache() {
+        let registry = ToolRegistry::builtin();
+        assert!(
+            registry.max_data_age("plan_bike_journey")
+                < registry.max_data_age("get_area_statistics")
+        );
+        assert_eq!(registry.max_data_age("set_preferences"), None);
+
+        // Cached snapshots are only kept 10 minutes
+        for tool in registry.tools() {
+            assert!(
+                tool.max_data_age
+                    .is_none_or(|age| age <= Duration::from_secs(600)),
+                "{} tolerates data older than the cache keeps",
+                tool.name
+            );
+        }
+        assert!(!registry
+            .list(&ToolSelection::default())
+            .to_string()
+            .contains("max_data_age"));
+    }
+
+    #[test]
+    fn test_timeouts_default_and_override() {
+        let registry = ToolRegistry::builtin();
+        assert_eq!(
+            registry.timeout("get_station_by_code"),
+            DEFAULT_TOOL_TIMEOUT
+        );
+        assert!(registry.timeout("plan_bike_journey") > DEFAULT_TOOL_TIMEOUT);
+        assert!(!registry
+            .list(&ToolSelection::default())
+            .to_string()
+            .contains("timeout"));
+
+        // Named tools win over `default`, wherever it is listed
+        let registry = registry.with_timeouts(&[
+            ("plan_bike_journey".to_string(), Duration::from_secs(40)),
+            ("default".to_string(), Duration::from_secs(5)),
+        ]);
+        assert_eq!(
+            registry.timeout("get_station_by_code"),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            registry.timeout("plan_bike_journey"),
+            Duration::from_secs(40)
+        );
+
+        // As a reloaded configuration overrides them
+        let overrides = [("get_station_by_code".to_string(), Duration::from_secs(1))];
+        assert_eq!(
+            registry.timeout_for("get_station_by_code", &overrides),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            registry.timeout_for("plan_bike_journey", &overrides),
+            Duration::from_secs(40)
+        );
+    }
+
+    #[test]
+    fn test_cost_hints_follow_tool_behavior() {
+        let registry = ToolRegistry::builtin();
+        for tool in registry.tools() {
+            // Slow tools get the longer time limit, and reading real-time data may fetch it
+            assert_eq!(
+                tool.cost.latency == LatencyClass::Slow,
+                SLOW_TOOLS.contains(&tool.name),
+                "{}",
+                tool.name
+            );
+            if tool.max_data_age.is_some() {
+                assert!(tool.cost.upstream_fetch, "{}", tool.name);
+            }
+        }
+
+        let listed = registry.list(&ToolSelection::default());
+        let plan = listed["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|tool| tool["name"] == "plan_bike_journey")
+            .unwrap();
+        assert_eq!(
+            plan["_meta"]["costHints"],
+            json!({"latency": "slow", "upstreamFetch": true, "resultSize": "medium"})
+        );
+    }
+
+    #[test]
+    fn test_schemas_follow_the_service_area() {
+        // The builtin schemas already describe the default area
+        assert_eq!(
+            ToolRegistry::builtin().list(&ToolSelection::default()),
+            ToolRegistry::builtin()
+                .with_service_area(&ServiceArea::paris())
+                .list(&ToolSelection::default())
+        );
+
+        let area = ServiceArea {
+            south: 48.8,
+            north: 48.9,
+            ..ServiceArea::paris()
+        };
+        let registry = ToolRegistry::builtin().with_service_area(&area);
+        let latitude =
+            &registry.get("find_nearby_stations").unwrap().input_schema["properties"]["latitude"];
+        assert_eq!(latitude["minimum"], 48.8);
+        assert_eq!(latitude["maximum"], 48.9);
+    }
+
+    #[test]
+    fn test_function_manifests() {
+        let registry = ToolRegistry::builtin();
+        let openai = registry.functions(FunctionFormat::OpenAi, &ToolSelection::default());
+        let anthropic = registry.functions("Anthropic".parse().unwrap(), &ToolSelection::default());
+        let current = registry.tools().iter().filter(|tool| !tool.deprecated);
+        assert_eq!(openai.as_array().unwrap().len(), current.count());
+        // Both APIs require object schemas
+        for function in openai.as_array().unwrap() {
+            assert_eq!(function["function"]["parameters"]["type"], "object");
+        }
+
+        let nearby = registry.get("find_nearby_stations").unwrap();
+        assert_eq!(openai[0]["type"], "function");
+        assert_eq!(openai[0]["function"]["name"], nearby.name);
+        assert_eq!(openai[0]["function"]["parameters"], nearby.input_schema);
+        assert_eq!(anthropic[0]["name"], nearby.name);
+        assert_eq!(anthropic[0]["input_schema"], nearby.input_schema);
+        assert!("gemini".parse::<FunctionFormat>().is_err());
+    }
+
+    #[test]
+    fn test_tool_selection() {
+        let registry = ToolRegistry::builtin();
+        let names = |selection: &ToolSelection| -> Vec<String> {
+            registry.list(selection)["tools"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|tool| tool["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let without_journeys = ToolSelection {
+            enabled: None,
+            disabled: vec!["plan_bike_journey".to_string()],
+        };
+        assert_eq!(names(&without_journeys).len(), registry.tools().len() - 1);
+        assert!(!names(&without_journeys).contains(&"plan_bike_journey".to_string()));
+
+        // Disabling wins over enabling
+        let lookups = ToolSelection {
+            enabled: Some(vec![
+                "get_station_by_code".to_string(),
+                "plan_bike_journey".to_string(),
+            ]),
+            disabled: vec!["plan_bike_journey".to_string()],
+        };
+        assert_eq!(names(&lookups), vec!["get_station_by_code"]);
+        assert_eq!(
+            registry
+                .functions(FunctionFormat::Anthropic, &lookups)
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(lookups.validate().is_ok());
+
+        let typo = ToolSelection {
+            enabled: None,
+            disabled: vec!["plan_journey".to_string()],
+        };
+        assert!(
+            matches!(typo.validate(), Err(Error::Config(message)) if message.contains("plan_journey"))
+        );
+    }
+}
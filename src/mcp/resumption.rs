@@ -0,0 +1,111 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a dropped WebSocket session can be resumed, unless configured
+pub const DEFAULT_SESSION_GRACE_SECONDS: u64 = 300;
+
+#[derive(Debug)]
+struct Detached {
+    until: Instant,
+    generation: u64,
+}
+
+/// WebSocket sessions whose connection dropped, kept for a grace window so a
+/// client reconnecting with the session token gets its preferences and
+/// subscriptions back
+#[derive(Debug, Default)]
+pub struct DetachedSessions {
+    detached: Mutex<HashMap<String, Detached>>,
+    generations: AtomicU64,
+}
+
+impl DetachedSessions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the session of `token` resumable for `grace`, returning the
+    /// detachment [`DetachedSessions::expire`] checks
+    pub async fn detach(&self, token: &str, grace: Duration) -> u64 {
+        let generation = self.generations.fetch_add(1, Ordering::Relaxed);
+        let mut detached = self.detached.lock().await;
+        detached.insert(
+            token.to_string(),
+            Detached {
+                until: Instant::now() + grace,
+                generation,
+            },
+        );
+        generation
+    }
+
+    /// Take back the session of `token`, if its connection dropped less than
+    /// its grace window ago
+    pub async fn resume(&self, token: &str) -> bool {
+        let mut detached = self.detached.lock().await;
+        detached
+            .remove(token)
+            .is_some_and(|session| session.until > Instant::now())
+    }
+
+    /// Whether the session of `token` is still waiting out the detachment
+    /// `generation`, which is then over; its state can be dropped
+    pub async fn expire(&self, token: &str, generation: u64) -> bool {
+        let mut detached = self.detached.lock().await;
+        if detached
+            .get(token)
+            .is_some_and(|session| session.generation == generation)
+        {
+            detached.remove(token);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// `notifications/session`, sent when a WebSocket connects: the token to
+/// reconnect with, and whether this connection resumed an earlier session
+#[must_use]
+pub fn session_notification(token: &str, resumed: bool, grace: Duration) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/session",
+        "params": {
+            "token": token,
+            "resumed": resumed,
+            "graceSeconds": grace.as_secs()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sessions_resume_within_their_grace_window() {
+        let sessions = DetachedSessions::new();
+        assert!(!sessions.resume("unknown").await);
+
+        let generation = sessions.detach("a", Duration::from_secs(60)).await;
+        assert!(sessions.resume("a").await);
+        // Resumed, so the timer of that detachment finds nothing to drop
+        assert!(!sessions.expire("a", generation).await);
+        assert!(!sessions.resume("a").await);
+
+        let first = sessions.detach("b", Duration::from_secs(60)).await;
+        assert!(sessions.resume("b").await);
+        let second = sessions.detach("b", Duration::from_secs(60)).await;
+        assert!(!sessions.expire("b", first).await);
+        assert!(sessions.expire("b", second).await);
+        assert!(!sessions.resume("b").await);
+
+        sessions.detach("c", Duration::ZERO).await;
+        assert!(!sessions.resume("c").await);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::geometry::Geometry;
+use crate::mcp::types::{
+    CheckDropoffFeasibilityInput, CompareStationsInput, FindNearbyStationsInput,
+    FindStationsAlongRouteInput, GenerateJourneyLinkInput, GeographicBounds,
+    GetAreaStatisticsInput, GetStationDensityInput, PlanBikeJourneyInput,
+    PlanMultipleJourneysInput,
+};
+use crate::types::Coordinates;
+use crate::{Error, Result};
+
+/// Decimal places coordinates are rounded to, about 11 cm of latitude
+pub const COORDINATE_DECIMALS: i32 = 6;
+
+/// Tool inputs carrying coordinates, checked and rounded before they reach any distance math
+pub trait Sanitize: Sized {
+    /// Reject non-finite or out-of-range coordinates, and round the others
+    /// to [`COORDINATE_DECIMALS`] places
+    fn sanitize(self) -> Result<Self>;
+}
+
+/// A finite latitude in [-90, 90] and longitude in [-180, 180], rounded
+pub fn coordinates(coords: Coordinates) -> Result<Coordinates> {
+    if !coords.latitude.is_finite()
+        || !coords.longitude.is_finite()
+        || !(-90.0..=90.0).contains(&coords.latitude)
+        || !(-180.0..=180.0).contains(&coords.longitude)
+    {
+        return Err(Error::InvalidCoordinates {
+            latitude: coords.latitude,
+            longitude: coords.longitude,
+        });
+    }
+    Ok(Coordinates::new(
+        round(coords.latitude),
+        round(coords.longitude),
+    ))
+}
+
+/// Bounds whose corners are valid coordinates, rounded
+pub fn bounds(bounds: GeographicBounds) -> Result<GeographicBounds> {
+    let north_east = coordinates(Coordinates::new(bounds.north, bounds.east))?;
+    let south_west = coordinates(Coordinates::new(bounds.south, bounds.west))?;
+    Ok(GeographicBounds {
+        north: north_east.latitude,
+        south: south_west.latitude,
+        east: north_east.longitude,
+        west: south_west.longitude,
+    })
+}
+
+/// A polygon whose positions are all valid coordinates, rounded
+pub fn geometry(mut geometry: Geometry) -> Result<Geometry> {
+    let rings: Vec<&mut Vec<[f64; 2]>> = match &mut geometry {
+        Geometry::Polygon { coordinates } => coordinates.iter_mut().collect(),
+        Geometry::MultiPolygon { coordinates } => coordinates.iter_mut().flatten().collect(),
+    };
+    for position in rings.into_iter().flatten() {
+        let [longitude, latitude] = *position;
+        let rounded = coordinates(Coordinates::new(latitude, longitude))?;
+        *position = [rounded.longitude, rounded.latitude];
+    }
+    Ok(geometry)
+}
+
+fn round(value: f64) -> f64 {
+    let scale = 10f64.powi(COORDINATE_DECIMALS);
+    (value * scale).round() / scale
+}
+
+impl Sanitize for FindNearbyStationsInput {
+    fn sanitize(mut self) -> Result<Self> {
+        let point = coordinates(Coordinates::new(self.latitude, self.longitude))?;
+        self.latitude = point.latitude;
+        self.longitude = point.longitude;
+        Ok(self)
+    }
+}
+
+impl Sanitize for GetAreaStatisticsInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.bounds = self.bounds.map(bounds).transpose()?;
+        self.polygon = self.polygon.map(geometry).transpose()?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for GetStationDensityInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.bounds = self.bounds.map(bounds).transpose()?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for FindStationsAlongRouteInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.waypoints = self
+            .waypoints
+            .into_iter()
+            .map(coordinates)
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for CompareStationsInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.reference_point = self.reference_point.map(coordinates).transpose()?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for CheckDropoffFeasibilityInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.destination = coordinates(self.destination)?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for GenerateJourneyLinkInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.origin = self.origin.map(coordinates).transpose()?;
+        self.destination = self.destination.map(coordinates).transpose()?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for PlanBikeJourneyInput {
+    fn sanitize(mut self) -> Result<Self> {
+        self.origin = coordinates(self.origin)?;
+        self.destination = coordinates(self.destination)?;
+        Ok(self)
+    }
+}
+
+impl Sanitize for PlanMultipleJourneysInput {
+    fn sanitize(mut self) -> Result<Self> {
+        for journey in &mut self.journeys {
+            journey.origin = coordinates(journey.origin)?;
+            journey.destination = coordinates(journey.destination)?;
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_are_checked_and_rounded() {
+        let rounded = coordinates(Coordinates::new(48.856_613_456_789, 2.352_221_999_9)).unwrap();
+        assert_eq!(rounded, Coordinates::new(48.856_613, 2.352_222));
+
+        for (latitude, longitude) in [
+            (f64::NAN, 2.35),
+            (48.85, f64::INFINITY),
+            (f64::NEG_INFINITY, 2.35),
+            (90.5, 2.35),
+            (48.85, -180.1),
+        ] {
+            assert!(matches!(
+                coordinates(Coordinates::new(latitude, longitude)),
+                Err(Error::InvalidCoordinates { .. })
+            ));
+        }
+        assert!(coordinates(Coordinates::new(-90.0, 180.0)).is_ok());
+
+        let polygon = geometry(Geometry::Polygon {
+            coordinates: vec![vec![
+                [2.340_000_4, 48.83],
+                [2.38, 48.83],
+                [2.38, 48.860_000_9],
+            ]],
+        })
+        .unwrap();
+        assert_eq!(
+            polygon,
+            Geometry::Polygon {
+                coordinates: vec![vec![[2.34, 48.83], [2.38, 48.83], [2.38, 48.860_001]]],
+            }
+        );
+        assert!(geometry(Geometry::MultiPolygon {
+            coordinates: vec![vec![vec![[2.34, f64::NAN]]]],
+        })
+        .is_err());
+        assert!(bounds(GeographicBounds {
+            north: 48.86,
+            south: 48.83,
+            east: f64::NAN,
+            west: 2.34,
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::mcp::registry::{ToolDefinition, ToolRegistry, TOOL_API_VERSION};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+
+/// Name of the type generated for a tool's arguments: `find_nearby_stations`
+/// gives `FindNearbyStationsInput`
+#[must_use]
+pub fn input_type_name(tool: &str) -> String {
+    let mut name: String = tool
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    name.push_str("Input");
+    name
+}
+
+/// Every tool's input schema as one JSON Schema document, under `$defs`
+#[must_use]
+pub fn bundle(registry: &ToolRegistry) -> Value {
+    let definitions: Map<String, Value> = registry
+        .tools()
+        .iter()
+        .map(|tool| {
+            let mut schema = tool.input_schema.clone();
+            schema["title"] = json!(tool.name);
+            schema["description"] = json!(tool.description);
+            if tool.deprecated {
+                schema["deprecated"] = json!(true);
+            }
+            (input_type_name(tool.name), schema)
+        })
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Velib MCP tool inputs",
+        "toolApiVersion": TOOL_API_VERSION,
+        "$defs": definitions,
+    })
+}
+
+/// TypeScript declarations of every tool's arguments, with a `ToolInputs` map by tool name
+#[must_use]
+pub fn typescript(registry: &ToolRegistry) -> String {
+    let mut out = format!(
+        "// Generated by `velib-mcp schema --ts` for tool API {TOOL_API_VERSION}. Do not edit.\n"
+    );
+    for tool in registry.tools() {
+        out.push('\n');
+        write_tool(&mut out, tool);
+    }
+
+    let names: Vec<String> = registry
+        .tools()
+        .iter()
+        .map(|tool| format!("\"{}\"", tool.name))
+        .collect();
+    let _ = write!(
+        out,
+        "\nexport type ToolName =\n  | {};\n\n/** Arguments of each tool, by name */\nexport interface ToolInputs {{\n",
+        names.join("\n  | ")
+    );
+    for tool in registry.tools() {
+        let _ = writeln!(out, "  {}: {};", tool.name, input_type_name(tool.name));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn write_tool(out: &mut String, tool: &ToolDefinition) {
+    let mut doc = vec![tool.description.to_string()];
+    if tool.deprecated {
+        doc.push("@deprecated".to_string());
+    }
+    write_doc(out, "", &doc);
+    let _ = write!(out, "export interface {} ", input_type_name(tool.name));
+    out.push_str(&object_type(&tool.input_schema, 0));
+    out.push('\n');
+}
+
+fn write_doc(out: &mut String, indent: &str, lines: &[String]) {
+    match lines {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "{indent}/** {} */", line.replace("*/", "*\\/"));
+        }
+        lines => {
+            let _ = writeln!(out, "{indent}/**");
+            for line in lines {
+                let _ = writeln!(out, "{indent} * {}", line.replace("*/", "*\\/"));
+            }
+            let _ = writeln!(out, "{indent} */");
+        }
+    }
+}
+
+/// The TypeScript type of `schema`, its nested objects indented by `depth` levels
+fn ts_type(schema: &Value, depth: usize) -> String {
+    if let Some(values) = schema["enum"].as_array() {
+        return values
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join(" | ");
+    }
+    if let Some(alternatives) = schema["oneOf"].as_array().or(schema["anyOf"].as_array()) {
+        // Alternatives only listing required properties constrain an object, they don't type it
+        if alternatives
+            .iter()
+            .any(|alternative| alternative.get("type").is_some())
+        {
+            return alternatives
+                .iter()
+                .map(|alternative| ts_type(alternative, depth))
+                .collect::<Vec<_>>()
+                .join(" | ");
+        }
+    }
+    match &schema["type"] {
+        Value::String(kind) => scalar_type(kind, schema, depth),
+        Value::Array(kinds) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|kind| scalar_type(kind, schema, depth))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn scalar_type(kind: &str, schema: &Value, depth: usize) -> String {
+    match kind {
+        "string" => "string".to_string(),
+        "number" | "integer" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => match schema.get("items") {
+            Some(items) => {
+                let item = ts_type(items, depth);
+                if item.contains(' ') {
+                    format!("({item})[]")
+                } else {
+                    format!("{item}[]")
+                }
+            }
+            None => "unknown[]".to_string(),
+        },
+        "object" => object_type(schema, depth),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn object_type(schema: &Value, depth: usize) -> String {
+    let Some(properties) = schema["properties"].as_object().filter(|p| !p.is_empty()) else {
+        return "Record<string, unknown>".to_string();
+    };
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let indent = "  ".repeat(depth + 1);
+    let mut out = "{\n".to_string();
+    for (name, property) in properties {
+        let mut doc = Vec::new();
+        if let Some(description) = property["description"].as_str() {
+            doc.push(description.to_string());
+        }
+        if let Some(default) = property.get("default") {
+            doc.push(format!("@default {default}"));
+        }
+        if property["deprecated"] == true {
+            doc.push("@deprecated".to_string());
+        }
+        write_doc(&mut out, &indent, &doc);
+        let key = if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            name.clone()
+        } else {
+            format!("\"{name}\"")
+        };
+        let optional = if required.contains(&name.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        let _ = writeln!(
+            out,
+            "{indent}{key}{optional}: {};",
+            ts_type(property, depth + 1)
+        );
+    }
+    out.push_str(&"  ".repeat(depth));
+    out.push('}');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript_declares_every_tool() {
+        let registry = ToolRegistry::builtin();
+        let declarations = typescript(&registry);
+        for tool in registry.tools() {
+            let name = input_type_name(tool.name);
+            assert!(declarations.contains(&format!("export interface {name} {{")));
+            assert!(declarations.contains(&format!("  {}: {name};", tool.name)));
+        }
+        assert_eq!(
+            input_type_name("find_nearby_stations"),
+            "FindNearbyStationsInput"
+        );
+
+        let nearby = declarations
+            .split("export interface FindNearbyStationsInput {")
+            .nth(1)
+            .unwrap();
+        assert!(nearby.contains("\n  latitude: number;"));
+        assert!(nearby.contains("\n  limit?: number;"));
+        assert!(nearby.contains("\n  humanize?: boolean | string;"));
+        assert!(nearby.contains("\n  sort_direction?: \"ascending\" | \"descending\";"));
+        assert!(declarations.contains("bike_type?: \"mechanical\" | \"electric\" | \"any\" | (\"mechanical\" | \"electric\" | \"any\")[];"));
+
+        let bundle = bundle(&registry);
+        assert_eq!(bundle["toolApiVersion"], TOOL_API_VERSION);
+        assert_eq!(
+            bundle["$defs"]["FindNearbyStationsInput"]["title"],
+            "find_nearby_stations"
+        );
+        assert_eq!(
+            bundle["$defs"].as_object().unwrap().len(),
+            registry.tools().len()
+        );
+    }
+}
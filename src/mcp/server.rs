@@ -1,23 +1,119 @@
+use arc_swap::ArcSwap;
 use axum::{
-    extract::{ws::WebSocket, WebSocketUpgrade},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    body::Bytes,
+    extract::{ws::WebSocket, Path, Query, WebSocketUpgrade},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use tokio::task::JoinHandle;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tracing::{debug, error, info, warn};
 
+use super::admin;
+use super::announcements::{self, AnnouncementDraft, AnnouncementStore};
+use super::auth::{require_bearer, ApiKeys, AuthProvider, HttpSession, Providers};
+use super::budget::ResponseBudget;
+use super::comparison;
+use super::dedup::{self, CacheStatus, ResponseCache};
+use super::digest::{CommuteDigest, CommuteProfile};
+use super::encoding::Encoding;
+use super::favorites::FavoritesStore;
 use super::handlers::McpToolHandler;
-use super::types::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use super::hardening::{self, IpRange};
+use super::humanize::{self, Locale, UnitSystem};
+use super::journal::{self, RequestJournal};
+use super::journey_link::{self, JourneyLinkStore, JourneyStop, SharedJourney};
+use super::live_config::{ConfigSource, ConfigVersion, LiveConfig};
+use super::meta::{self, DataStamp, RequestContext};
+use super::oidc::OidcProvider;
+use super::panics;
+use super::precision::OutputPrecision;
+use super::prompts;
+use super::quota::QuotaStore;
+use super::registry::{self, ApiLifecycle, FunctionFormat, ToolRegistry, TOOL_API_VERSION};
+use super::resumption::{self, DetachedSessions};
+use super::sanitize::Sanitize;
+use super::session::{self, SessionStore, UserPreferences};
+use super::signing::{self, ResponseSigner};
+use super::slow_calls::FinishedCall;
+use super::sorting::SortOptions;
+use super::subscriptions::{self, SubscriptionStore};
+use super::types::{
+    AddFavoriteStationInput, AvailabilityFilter, FindNearbyStationsInput, GenerateJourneyLinkInput,
+    GenerateJourneyLinkOutput, GetFavoritesStatusInput, GetStationByCodeInput,
+    GetStationByCodeOutput, JourneyLinkFormat, JsonRpcError, JsonRpcRequest, JsonRpcResponse,
+    ListFavoriteStationsOutput, StationPurpose,
+};
+use super::widget;
+use crate::data::probe::probe_status;
+use crate::events::{Announcement, ServerEvent};
+use crate::phases::{self, Phase};
+use crate::server::AppConfig;
 use crate::{Error, Result};
 
+// MCP specification revision implemented by this server
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+// SSE messages queued for a client before its connection task waits
+const EVENT_STREAM_BUFFER: usize = 16;
+
+// Pickup search radius when the session has no max walk distance
+const DIGEST_PICKUP_RADIUS: u32 = 500;
+
+#[derive(Clone)]
 pub struct McpServer {
-    tool_handler: Arc<McpToolHandler>,
+    context: McpContext,
     clients: Arc<RwLock<HashMap<String, WebSocketClient>>>,
+    lifecycle: ApiLifecycle,
+    admin_token: Option<String>,
+    api_keys: Arc<[String]>,
+    auth_providers: Vec<Arc<dyn AuthProvider>>,
+    allowed_ips: Arc<[IpRange]>,
+}
+
+/// Shared state needed to process any MCP request
+#[derive(Clone)]
+struct McpContext {
+    // Replaced with the new tunables on configuration reloads
+    handler: Arc<ArcSwap<McpToolHandler>>,
+    sessions: Arc<SessionStore>,
+    /// WebSocket sessions waiting to be resumed after their connection dropped
+    detached: Arc<DetachedSessions>,
+    favorites: Arc<FavoritesStore>,
+    registry: Arc<ToolRegistry>,
+    responses: Arc<ArcSwap<ResponseCache>>,
+    config: Arc<LiveConfig>,
+    quotas: Arc<QuotaStore>,
+    subscriptions: Arc<SubscriptionStore>,
+    announcements: Arc<AnnouncementStore>,
+    journeys: Arc<JourneyLinkStore>,
+    /// Records requests on disk, for diagnosing crashes
+    journal: Option<Arc<RequestJournal>>,
+    /// Absolute URL this server is reached at, for links to its own pages
+    public_url: Option<Arc<str>>,
+    /// Signs resource payloads for archiving, when a key is configured
+    signer: Option<Arc<ResponseSigner>>,
+}
+
+impl McpContext {
+    /// The tool handler in effect
+    fn handler(&self) -> Arc<McpToolHandler> {
+        self.handler.load_full()
+    }
 }
 
 #[derive(Debug)]
@@ -36,29 +132,542 @@ impl Default for McpServer {
 impl McpServer {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_tool_handler(McpToolHandler::new())
+    }
+
+    /// Create a server from application configuration
+    pub fn with_config(config: &AppConfig) -> Result<Self> {
+        config.tools.validate()?;
+        let favorites = match &config.favorites_path {
+            Some(path) => FavoritesStore::load(path)?,
+            None => FavoritesStore::in_memory(),
+        };
+
+        let quotas = match &config.quota_path {
+            Some(path) => QuotaStore::load(path, config.quota_limits)?,
+            None => QuotaStore::in_memory(config.quota_limits),
+        };
+
+        let mut server = Self::with_tool_handler(McpToolHandler::with_config(config)?)
+            .with_favorites(favorites)
+            .with_dedup_ttl(config.dedup_ttl)
+            .with_api_lifecycle(config.api_lifecycle)
+            .with_quotas(quotas);
+        if let Some(token) = &config.admin_token {
+            server = server.with_admin_token(token);
+        }
+        server = server.with_api_keys(&config.api_keys);
+        if let Some(oidc) = &config.oidc {
+            server = server.with_auth_provider(OidcProvider::new(oidc.clone()));
+        }
+        server = server.with_allowed_ips(&config.allowed_ips);
+        if let Some(url) = &config.public_url {
+            server = server.with_public_url(url);
+        }
+        if let Some(path) = &config.journal_path {
+            let (request_journal, unfinished) = RequestJournal::open(path, config.journal_entries)?;
+            journal::log_unfinished(&unfinished);
+            server = server.with_journal(request_journal);
+        }
+        if let Some(path) = &config.signing_key_path {
+            server = server.with_response_signer(ResponseSigner::load(path)?);
+        }
+        server.context.registry =
+            Arc::new(ToolRegistry::builtin().with_service_area(&config.service_area));
+        // Time limits are read from the live configuration, so reloads change them
+        server.context.config =
+            Arc::new(LiveConfig::new(config.clone()).with_source(Arc::new(AppConfig::load)));
+        Ok(server)
+    }
+
+    /// Create a server around an existing tool handler
+    #[must_use]
+    pub fn with_tool_handler(tool_handler: McpToolHandler) -> Self {
         Self {
-            tool_handler: Arc::new(McpToolHandler::new()),
+            context: McpContext {
+                handler: Arc::new(ArcSwap::from_pointee(tool_handler)),
+                sessions: Arc::new(SessionStore::new()),
+                detached: Arc::new(DetachedSessions::new()),
+                favorites: Arc::new(FavoritesStore::in_memory()),
+                registry: Arc::new(ToolRegistry::builtin()),
+                responses: Arc::new(ArcSwap::from_pointee(ResponseCache::default())),
+                config: Arc::new(LiveConfig::default()),
+                quotas: Arc::new(QuotaStore::default()),
+                subscriptions: Arc::new(SubscriptionStore::new()),
+                announcements: Arc::new(AnnouncementStore::new()),
+                journeys: Arc::new(JourneyLinkStore::default()),
+                journal: None,
+                public_url: None,
+                signer: None,
+            },
             clients: Arc::new(RwLock::new(HashMap::new())),
+            lifecycle: ApiLifecycle::default(),
+            admin_token: None,
+            api_keys: Arc::from([]),
+            auth_providers: Vec::new(),
+            allowed_ips: Arc::from([]),
+        }
+    }
+
+    /// Count tool calls against per-key daily and monthly quotas
+    #[must_use]
+    pub fn with_quotas(mut self, quotas: QuotaStore) -> Self {
+        self.context.quotas = Arc::new(quotas);
+        self
+    }
+
+    /// Serve the `/admin` endpoints to callers presenting `token` as bearer
+    #[must_use]
+    pub fn with_admin_token(mut self, token: &str) -> Self {
+        self.admin_token = Some(token.to_string());
+        self
+    }
+
+    /// Only serve the MCP endpoints to callers presenting one of `keys` as bearer
+    ///
+    /// An empty list leaves them open, as by default.
+    #[must_use]
+    pub fn with_api_keys(mut self, keys: &[String]) -> Self {
+        self.api_keys = Arc::from(keys);
+        self
+    }
+
+    /// Also let in HTTP and WebSocket callers whose bearer token `provider` accepts,
+    /// such as JWTs from an [`OidcProvider`]
+    ///
+    /// Once a provider or API key is set, callers need a token one of them accepts.
+    #[must_use]
+    pub fn with_auth_provider(mut self, provider: impl AuthProvider + 'static) -> Self {
+        self.auth_providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Only answer HTTP requests from peers within `ranges`
+    ///
+    /// An empty list lets anyone in, as by default.
+    #[must_use]
+    pub fn with_allowed_ips(mut self, ranges: &[IpRange]) -> Self {
+        self.allowed_ips = Arc::from(ranges);
+        self
+    }
+
+    /// Announce a deprecation or sunset of the tool API on every HTTP response
+    #[must_use]
+    pub fn with_api_lifecycle(mut self, lifecycle: ApiLifecycle) -> Self {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    /// Answer identical tool calls made within `ttl` from one result; zero disables it
+    #[must_use]
+    pub fn with_dedup_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.context.responses = Arc::new(ArcSwap::from_pointee(ResponseCache::new(ttl)));
+        self
+    }
+
+    /// Absolute URL clients reach this server at, needed for `page` journey links
+    #[must_use]
+    pub fn with_public_url(mut self, url: &str) -> Self {
+        self.context.public_url = Some(Arc::from(url.trim_end_matches('/')));
+        self
+    }
+
+    /// Record every JSON-RPC request in `journal`
+    #[must_use]
+    pub fn with_journal(mut self, journal: RequestJournal) -> Self {
+        self.context.journal = Some(Arc::new(journal));
+        self
+    }
+
+    /// Sign resource payloads with `signer` and serve its public key at `/public-key`
+    #[must_use]
+    pub fn with_response_signer(mut self, signer: ResponseSigner) -> Self {
+        self.context.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Override tool time limits, see [`ToolRegistry::with_timeouts`]
+    #[must_use]
+    pub fn with_tool_timeouts(mut self, timeouts: &[(String, Duration)]) -> Self {
+        let registry = self.context.registry.as_ref().clone();
+        self.context.registry = Arc::new(registry.with_timeouts(timeouts));
+        self
+    }
+
+    /// Round numbers in tool results and resources to `precision` instead of the configured one
+    #[must_use]
+    pub fn with_output_precision(mut self, precision: OutputPrecision) -> Self {
+        let mut config = self.context.config.current().as_ref().clone();
+        config.output_precision = precision;
+        let live = LiveConfig::new(config);
+        self.context.config = Arc::new(match self.context.config.source() {
+            Some(source) => live.with_source(Arc::clone(source)),
+            None => live,
+        });
+        self
+    }
+
+    /// Reload the configuration from `source` instead of [`AppConfig::load`]
+    #[must_use]
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        let config = self.context.config.current().as_ref().clone();
+        self.context.config = Arc::new(LiveConfig::new(config).with_source(source));
+        self
+    }
+
+    /// Which configuration is in effect
+    #[must_use]
+    pub fn config_version(&self) -> ConfigVersion {
+        self.context.config.version()
+    }
+
+    /// Read the configuration again and apply what can change at runtime
+    ///
+    /// Rate limits and quotas, the deduplication window, label thresholds,
+    /// walking speed, lenient limits, tool time limits and the log level
+    /// change; everything else needs a restart. Nothing changes if the new
+    /// configuration can't be read.
+    pub async fn reload(&self) -> Result<ConfigVersion> {
+        reload_config(&self.context).await
+    }
+
+    pub(crate) fn quotas(&self) -> Arc<QuotaStore> {
+        Arc::clone(&self.context.quotas)
+    }
+
+    pub(crate) fn handler(&self) -> Arc<McpToolHandler> {
+        self.context.handler()
+    }
+
+    /// Announce `draft` to every client: attached to tool results from now on
+    /// and pushed to `/events` and WebSocket connections
+    pub async fn publish_announcement(&self, draft: AnnouncementDraft) -> Result<Announcement> {
+        let now = chrono::Utc::now();
+        let announcement = self.context.announcements.publish(draft, now).await?;
+        info!(
+            "Published {:?} announcement {}: {}",
+            announcement.kind, announcement.id, announcement.message
+        );
+        self.broadcast_announcements().await;
+        Ok(announcement)
+    }
+
+    /// Returns whether an announcement with that id was in effect
+    pub async fn withdraw_announcement(&self, id: &str) -> bool {
+        let withdrawn = self.context.announcements.withdraw(id).await;
+        if withdrawn {
+            info!("Withdrew announcement {}", id);
+            self.broadcast_announcements().await;
+        }
+        withdrawn
+    }
+
+    pub async fn announcements(&self) -> Vec<Announcement> {
+        self.context.announcements.active(chrono::Utc::now()).await
+    }
+
+    async fn broadcast_announcements(&self) {
+        let active = self.announcements().await;
+        self.context
+            .handler()
+            .events()
+            .await
+            .publish_announcements(active);
+    }
+
+    /// The API keys, then the providers added with [`Self::with_auth_provider`],
+    /// as every transport tries them on a caller's bearer token
+    fn providers(&self) -> Providers {
+        let mut providers = self.auth_providers.clone();
+        if !self.api_keys.is_empty() {
+            providers.insert(0, Arc::new(ApiKeys(Arc::clone(&self.api_keys))));
+        }
+        Providers::from(providers)
+    }
+
+    /// Who the bearer token `presented` to a transport other than HTTP, such as
+    /// gRPC, identifies; see [`super::auth::authenticate`]
+    #[cfg(feature = "grpc")]
+    pub(crate) async fn authenticate(
+        &self,
+        presented: Option<&str>,
+    ) -> std::result::Result<Option<super::auth::Principal>, super::auth::Unauthenticated> {
+        super::auth::authenticate(&self.providers(), presented).await
+    }
+
+    /// Whether the configuration in effect lets callers use `tool`
+    pub(crate) fn serves_tool(&self, tool: &str) -> bool {
+        self.context.config.current().tools.allows(tool)
+    }
+
+    /// Run `tool` for a caller outside MCP, such as gRPC, under the quota,
+    /// time limit, data age policy and usage statistics of `tools/call`;
+    /// `principal` is who [`Self::authenticate`] found the caller to be
+    #[cfg(feature = "grpc")]
+    pub(crate) async fn run_typed<T, F, Fut>(
+        &self,
+        principal: Option<&super::auth::Principal>,
+        tool: &'static str,
+        call: F,
+    ) -> Result<T>
+    where
+        F: FnOnce(McpToolHandler) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let context = &self.context;
+        if !self.serves_tool(tool) {
+            return Err(Error::ToolDisabled {
+                tool: tool.to_string(),
+            });
+        }
+        let started = std::time::Instant::now();
+        let current = context.handler();
+        let handler = match context.registry.max_data_age(tool) {
+            Some(max_age) => current.as_ref().clone().with_max_data_age(max_age),
+            None => current.as_ref().clone(),
+        };
+        let limit = context
+            .registry
+            .timeout_for(tool, &context.config.current().tool_timeouts);
+
+        let (result, timings) = phases::measure(async {
+            match context
+                .quotas
+                .charge(
+                    principal
+                        .map(super::auth::Principal::session_key)
+                        .as_deref(),
+                    chrono::Utc::now(),
+                )
+                .await
+            {
+                Ok(_) => tokio::time::timeout(limit, call(handler))
+                    .await
+                    .unwrap_or_else(|_| {
+                        warn!("{} timed out after {:?}", tool, limit);
+                        Err(Error::Timeout {
+                            operation: tool.to_string(),
+                            timeout_ms: u64::try_from(limit.as_millis()).unwrap_or(u64::MAX),
+                        })
+                    }),
+                Err(e) => Err(e),
+            }
+        })
+        .await;
+        let elapsed = started.elapsed();
+        let error_type = result.as_ref().err().map(Error::error_type);
+        current
+            .metrics()
+            .await
+            .record_tool_call(tool, elapsed, error_type, chrono::Utc::now());
+        FinishedCall {
+            tool,
+            request_id: &Value::Null,
+            arguments: None,
+            elapsed,
+            timings,
+            error_type,
         }
+        .log_if_slower_than(context.config.current().slow_call_threshold);
+        result
+    }
+
+    /// Replace the favorite stations store
+    #[must_use]
+    pub fn with_favorites(mut self, favorites: FavoritesStore) -> Self {
+        self.context.favorites = Arc::new(favorites);
+        self
+    }
+
+    /// Probe the upstream datasets every `interval`; `velib://health` reports the last result
+    pub fn spawn_health_probe(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let handler = self.context.handler();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let probe = handler.probe_upstream().await;
+                if probe.healthy() {
+                    debug!("Upstream probe healthy");
+                } else {
+                    warn!(
+                        reference = ?probe.reference.error,
+                        realtime = ?probe.realtime.error,
+                        "Upstream probe failed"
+                    );
+                }
+            }
+        })
+    }
+
+    /// Write quota counts to `VELIB_QUOTA_PATH` every `interval`, see [`QuotaStore::flush`]
+    pub fn spawn_quota_flush(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                server.flush_quotas().await;
+            }
+        })
+    }
+
+    /// Write quota counts changed since the last flush, as the server stops
+    pub async fn flush_quotas(&self) {
+        if let Err(e) = self.context.quotas.flush(chrono::Utc::now()).await {
+            warn!("Failed to save quota usage: {}", e);
+        }
+    }
+
+    /// Compact the availability history and forget idle sessions and
+    /// favorites every `interval`
+    pub fn spawn_history_compaction(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let handler = self.context.handler();
+        let sessions = Arc::clone(&self.context.sessions);
+        let favorites = Arc::clone(&self.context.favorites);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let stats = handler.compact_history().await;
+                debug!(
+                    "Compacted history: {} samples for {} stations, ~{} bytes",
+                    stats.samples, stats.stations, stats.approx_bytes
+                );
+                let swept = sessions.sweep(session::SESSION_IDLE_TIMEOUT).await;
+                if swept > 0 {
+                    debug!("Forgot {} idle sessions", swept);
+                }
+                match favorites.sweep(chrono::Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(swept) => debug!("Forgot the favorites of {} unused sessions", swept),
+                    Err(e) => warn!("Failed to save favorites: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Re-validate the reference catalog every `interval` for
+    /// `velib://reports/reference-changes`
+    pub fn spawn_reference_validation(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let handler = self.context.handler();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match handler.validate_reference().await {
+                    Ok(report) => info!(
+                        stations = report.total_stations,
+                        invalid = report.invalid.len(),
+                        added = report.added.len(),
+                        removed = report.removed.len(),
+                        renamed = report.renamed.len(),
+                        moved = report.moved.len(),
+                        "Validated reference catalog"
+                    ),
+                    Err(e) => warn!("Reference catalog validation failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Refresh real-time data every `interval` so the feed watchdog sees upstream stalls
+    /// even while no client is asking
+    pub fn spawn_realtime_polling(&self, interval: std::time::Duration) -> JoinHandle<()> {
+        let handler = self.context.handler();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match handler.refresh_realtime_status().await {
+                    Ok(count) => debug!("Polled real-time status for {} stations", count),
+                    Err(e) => warn!("Background real-time refresh failed: {}", e),
+                }
+            }
+        })
     }
 
     pub fn router(&self) -> Router {
-        let handler = Arc::clone(&self.tool_handler);
+        self.with_hardening(self.with_version_headers(self.routes()))
+    }
+
+    /// Reject disallowed methods, non-JSON `POST /mcp` bodies and peers outside
+    /// the allowlist, and mark responses `X-Content-Type-Options: nosniff`
+    pub fn with_hardening(&self, router: Router) -> Router {
+        hardening::layer(router, Arc::clone(&self.allowed_ips))
+    }
+
+    /// Add `X-API-Version`, and `Deprecation`/`Sunset` once announced, to every route of `router`
+    pub fn with_version_headers(&self, router: Router) -> Router {
+        let headers = self.lifecycle.headers();
+        router.layer(middleware::map_response(move |mut response: Response| {
+            let headers = headers.clone();
+            async move {
+                response.headers_mut().extend(headers);
+                response
+            }
+        }))
+    }
+
+    pub(crate) fn routes(&self) -> Router {
+        let context = self.context.clone();
         let clients = Arc::clone(&self.clients);
+        let providers = self.providers();
 
-        Router::new()
+        let resources = get({
+            let context = context.clone();
+            move |headers: HeaderMap, session: HttpSession, uri: Path<String>| async move {
+                let encoding = Encoding::negotiate(&headers);
+                let mut response =
+                    handle_resource(uri, &context, session.0.as_deref(), encoding).await;
+                if let Some(version) = context.handler().data_version() {
+                    meta::insert_data_version_headers(response.headers_mut(), &version);
+                }
+                response
+            }
+        });
+        let resources = match &context.signer {
+            Some(signer) => resources.layer(middleware::from_fn_with_state(
+                Arc::clone(signer),
+                signing::sign_response,
+            )),
+            None => resources,
+        };
+        let router = Router::new()
             .route(
                 "/mcp",
                 post({
-                    let handler = Arc::clone(&handler);
-                    move |Json(request): Json<JsonRpcRequest>| async move {
-                        match Self::process_jsonrpc_request(handler, request).await {
+                    let context = context.clone();
+                    move |HttpSession(session): HttpSession, body: Bytes| async move {
+                        // Malformed bodies get JSON-RPC errors, not axum's plain rejections
+                        let request = match parse_jsonrpc_request(&body) {
+                            Ok(request) => request,
+                            Err(response) => return Json(*response).into_response(),
+                        };
+                        if request.is_notification() {
+                            return StatusCode::ACCEPTED.into_response();
+                        }
+                        let id = request.id.clone();
+                        let session = session.as_deref();
+                        match Self::process_jsonrpc_request(&context, session, session, request)
+                            .await
+                        {
                             Ok(response) => Json(response).into_response(),
                             Err(e) => {
                                 tracing::error!("HTTP request error: {}", e);
                                 (
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(json!({"error": e.to_string()})),
+                                    Json(JsonRpcResponse {
+                                        jsonrpc: "2.0".to_string(),
+                                        id,
+                                        result: None,
+                                        error: Some(JsonRpcError::from(e)),
+                                    }),
                                 )
                                     .into_response()
                             }
@@ -69,34 +678,131 @@ impl McpServer {
             .route(
                 "/mcp/ws",
                 get({
-                    let handler = Arc::clone(&handler);
+                    let context = context.clone();
                     let clients = Arc::clone(&clients);
-                    move |ws: WebSocketUpgrade| async move {
+                    move |HttpSession(principal): HttpSession,
+                          ws: WebSocketUpgrade,
+                          query: Query<WebSocketQuery>| async move {
                         ws.on_upgrade(move |socket| {
-                            Self::handle_websocket_connection(socket, handler, clients)
+                            Self::handle_websocket_connection(
+                                socket,
+                                context,
+                                clients,
+                                principal,
+                                query.0.resume,
+                            )
                         })
                     }
                 }),
             )
+            .route("/resources/*uri", resources)
+            .route(
+                "/events",
+                get({
+                    let context = context.clone();
+                    move |HttpSession(session): HttpSession| async move {
+                        event_stream(&context, session).await
+                    }
+                }),
+            )
+            .route(
+                "/functions.json",
+                get({
+                    let context = context.clone();
+                    move |query: Query<FunctionsQuery>| async move {
+                        functions_manifest(&context, query.format.as_deref())
+                    }
+                }),
+            )
+            // Widgets are embedded in pages, journey pages opened from shared links and
+            // metrics scraped, none of which can send a key
+            .route_layer(middleware::from_fn_with_state(providers, require_bearer))
+            .route(
+                "/widget/station/:code",
+                get({
+                    let context = context.clone();
+                    move |code: axum::extract::Path<String>| async move {
+                        station_widget(&context, &code).await
+                    }
+                }),
+            )
             .route(
-                "/resources/*uri",
+                "/journey/:id",
                 get({
-                    let handler = Arc::clone(&handler);
-                    move |uri: axum::extract::Path<String>| {
-                        let handler = Arc::clone(&handler);
-                        async move { handle_resource(uri, handler).await }
+                    let context = context.clone();
+                    move |id: axum::extract::Path<String>| async move {
+                        journey_page(&context, &id).await
                     }
                 }),
             )
+            .route(
+                "/metrics",
+                get({
+                    let context = context.clone();
+                    move || async move { metrics_endpoint(&context).await }
+                }),
+            );
+
+        let router = match &self.admin_token {
+            Some(token) => router.merge(admin::routes(token, self.clone())),
+            None => router,
+        };
+
+        // Anyone checking an archived payload needs the key, not just API clients
+        let router = match &context.signer {
+            Some(signer) => {
+                let signer = Arc::clone(signer);
+                router.route(
+                    "/public-key",
+                    get(move || async move {
+                        match signer.public_key_document() {
+                            Ok(document) => Json(document).into_response(),
+                            Err(e) => {
+                                error!("Failed to encode public key: {}", e);
+                                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                            }
+                        }
+                    }),
+                )
+            }
+            None => router,
+        };
+
+        #[cfg(feature = "dashboard")]
+        let router = router.route("/dashboard", get(super::dashboard::page));
+
+        let handler = context.handler();
+        router.layer(panics::catch_panic_layer(move || handler.record_panic()))
     }
 
     async fn handle_websocket_connection(
         mut socket: WebSocket,
-        handler: Arc<McpToolHandler>,
+        context: McpContext,
         clients: Arc<RwLock<HashMap<String, WebSocketClient>>>,
+        // Session of the upgrade request, which quotas are charged to
+        principal: Option<String>,
+        resume: Option<String>,
     ) {
         let client_id = uuid::Uuid::new_v4().to_string();
-        info!("New WebSocket connection: {}", client_id);
+        // Preferences and subscriptions set over WebSocket outlive the
+        // connection by the grace window, for a client reconnecting with the token
+        let resumed = match resume {
+            Some(token) if context.detached.resume(&token).await => Some(token),
+            _ => None,
+        };
+        let token = resumed
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let session = format!("ws:{token}");
+        info!(
+            "New WebSocket connection: {}{}",
+            client_id,
+            if resumed.is_some() {
+                " (resumed session)"
+            } else {
+                ""
+            }
+        );
 
         // Add client to the map
         {
@@ -109,14 +815,57 @@ impl McpServer {
             );
         }
 
-        // Handle messages
-        while let Some(msg) = socket.recv().await {
+        let mut events = context.handler().events().await.subscribe();
+
+        let grace = context.config.current().session_grace;
+        let greeting = resumption::session_notification(&token, resumed.is_some(), grace);
+        // A connection that failed already ends at the first read below
+        if let Err(e) = socket
+            .send(axum::extract::ws::Message::Text(greeting.to_string()))
+            .await
+        {
+            error!("Failed to send WebSocket session token: {}", e);
+        }
+
+        // Handle messages, and notify subscribed resources as refreshes come in
+        loop {
+            let msg = tokio::select! {
+                msg = socket.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                event = events.recv() => {
+                    // A lagging connection skips what it missed instead of being disconnected
+                    let Ok(event) = event else { continue };
+                    let notified =
+                        Self::notify_subscribers(&mut socket, &context, &session, &event).await;
+                    if let Err(e) = notified {
+                        error!("Failed to send WebSocket notification: {}", e);
+                        break;
+                    }
+                    continue;
+                }
+            };
             match msg {
                 Ok(axum::extract::ws::Message::Text(text)) => {
-                    match serde_json::from_str::<JsonRpcRequest>(&text) {
+                    match parse_jsonrpc_request(text.as_bytes()) {
+                        Ok(request) if request.is_notification() => {
+                            debug!("Received notification: {}", request.method);
+                        }
                         Ok(request) => {
-                            match Self::process_jsonrpc_request(Arc::clone(&handler), request).await
-                            {
+                            let id = request.id.clone();
+                            let processed = panics::catch_panic(Self::process_jsonrpc_request(
+                                &context,
+                                Some(&session),
+                                principal.as_deref(),
+                                request,
+                            ))
+                            .await
+                            .unwrap_or_else(|correlation_id| {
+                                context.handler().record_panic();
+                                Ok(panics::panic_response(id, &correlation_id))
+                            });
+                            match processed {
                                 Ok(response) => {
                                     let response_text = match serde_json::to_string(&response) {
                                         Ok(text) => text,
@@ -153,19 +902,7 @@ impl McpServer {
                                 }
                             }
                         }
-                        Err(e) => {
-                            warn!("Invalid JSON-RPC request: {}", e);
-                            let error_response = JsonRpcResponse {
-                                jsonrpc: "2.0".to_string(),
-                                id: json!(null),
-                                result: None,
-                                error: Some(JsonRpcError {
-                                    code: -32700,
-                                    message: "Parse error".to_string(),
-                                    data: Some(json!({"original_error": e.to_string()})),
-                                }),
-                            };
-
+                        Err(error_response) => {
                             if let Ok(response_text) = serde_json::to_string(&error_response) {
                                 let _ = socket
                                     .send(axum::extract::ws::Message::Text(response_text))
@@ -191,182 +928,157 @@ impl McpServer {
             let mut clients_guard = clients.write().await;
             clients_guard.remove(&client_id);
         }
+        let grace = context.config.current().session_grace;
+        if grace.is_zero() {
+            context.sessions.remove(&session).await;
+            context.subscriptions.remove(&session).await;
+        } else {
+            let generation = context.detached.detach(&token, grace).await;
+            let context = context.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(grace).await;
+                if context.detached.expire(&token, generation).await {
+                    context.sessions.remove(&session).await;
+                    context.subscriptions.remove(&session).await;
+                }
+            });
+        }
 
         info!("WebSocket connection terminated: {}", client_id);
     }
 
+    /// Push announcements, and updates to the resources `session` subscribed to
+    async fn notify_subscribers(
+        socket: &mut WebSocket,
+        context: &McpContext,
+        session: &str,
+        event: &ServerEvent,
+    ) -> std::result::Result<(), axum::Error> {
+        if let ServerEvent::ServiceAnnouncements { announcements, .. } = event {
+            let notification = announcements::notification(announcements).to_string();
+            socket
+                .send(axum::extract::ws::Message::Text(notification))
+                .await?;
+        }
+        for uri in context.subscriptions.matching(session, event).await {
+            let notification = subscriptions::updated_notification(uri).to_string();
+            socket
+                .send(axum::extract::ws::Message::Text(notification))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Answer `request` for `session`, charging tool calls to the quota of
+    /// `principal`: the session an HTTP request or WebSocket upgrade authenticated as
     async fn process_jsonrpc_request(
-        handler: Arc<McpToolHandler>,
+        context: &McpContext,
+        session: Option<&str>,
+        principal: Option<&str>,
+        request: JsonRpcRequest,
+    ) -> Result<JsonRpcResponse> {
+        let Some(journal) = &context.journal else {
+            return Self::answer_jsonrpc_request(context, session, principal, request).await;
+        };
+        let tool = (request.method == "tools/call").then(|| {
+            request
+                .params
+                .get("name")
+                .and_then(Value::as_str)
+                .and_then(|name| context.registry.get(name))
+                .map_or("unknown", |tool| tool.name)
+        });
+        let entry = journal.begin(
+            &request.method,
+            tool,
+            context.handler().data_snapshot_at(),
+            context.config.version().version,
+        );
+        let started = std::time::Instant::now();
+        let response = Self::answer_jsonrpc_request(context, session, principal, request).await;
+        let error_type = match &response {
+            Ok(response) => response
+                .error
+                .as_ref()
+                .and_then(|error| error.data.as_ref())
+                .and_then(|data| data.get("error_type"))
+                .and_then(Value::as_str),
+            Err(e) => Some(e.error_type()),
+        };
+        journal.finish(entry, started.elapsed(), error_type);
+        response
+    }
+
+    async fn answer_jsonrpc_request(
+        context: &McpContext,
+        session: Option<&str>,
+        principal: Option<&str>,
         request: JsonRpcRequest,
     ) -> Result<JsonRpcResponse> {
+        let started = std::time::Instant::now();
+        // Tool results report the data they were computed from, which for a
+        // cached one predates this request
+        let mut computed_from = None;
         let result = match request.method.as_str() {
-            "tools/list" => Ok(json!({
-                "tools": [
-                    {
-                        "name": "find_nearby_stations",
-                        "description": "Find Velib stations within a radius of coordinates",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "latitude": {"type": "number", "minimum": 48.7, "maximum": 49.0},
-                                "longitude": {"type": "number", "minimum": 2.0, "maximum": 2.6},
-                                "radius_meters": {"type": "integer", "minimum": 100, "maximum": 5000, "default": 500},
-                                "limit": {"type": "integer", "minimum": 1, "maximum": 100, "default": 10},
-                                "availability_filter": {"type": "object"}
-                            },
-                            "required": ["latitude", "longitude"]
-                        }
-                    },
-                    {
-                        "name": "get_station_by_code",
-                        "description": "Get detailed information about a specific station",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "station_code": {"type": "string"},
-                                "include_real_time": {"type": "boolean", "default": true}
-                            },
-                            "required": ["station_code"]
-                        }
-                    },
-                    {
-                        "name": "search_stations_by_name",
-                        "description": "Search stations by name with optional fuzzy matching",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "query": {"type": "string", "minLength": 2},
-                                "limit": {"type": "integer", "minimum": 1, "maximum": 50, "default": 10},
-                                "fuzzy": {"type": "boolean", "default": true}
-                            },
-                            "required": ["query"]
-                        }
-                    },
-                    {
-                        "name": "get_area_statistics",
-                        "description": "Get aggregated statistics for a geographic area",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "bounds": {
-                                    "type": "object",
-                                    "properties": {
-                                        "north": {"type": "number"},
-                                        "south": {"type": "number"},
-                                        "east": {"type": "number"},
-                                        "west": {"type": "number"}
-                                    },
-                                    "required": ["north", "south", "east", "west"]
-                                },
-                                "include_real_time": {"type": "boolean", "default": true}
-                            },
-                            "required": ["bounds"]
-                        }
-                    },
-                    {
-                        "name": "plan_bike_journey",
-                        "description": "Plan a bike journey with pickup and dropoff suggestions",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "origin": {
-                                    "type": "object",
-                                    "properties": {
-                                        "latitude": {"type": "number"},
-                                        "longitude": {"type": "number"}
-                                    },
-                                    "required": ["latitude", "longitude"]
-                                },
-                                "destination": {
-                                    "type": "object",
-                                    "properties": {
-                                        "latitude": {"type": "number"},
-                                        "longitude": {"type": "number"}
-                                    },
-                                    "required": ["latitude", "longitude"]
-                                },
-                                "preferences": {"type": "object"}
-                            },
-                            "required": ["origin", "destination"]
-                        }
-                    }
-                ]
+            "initialize" => Ok(json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {
+                    "tools": {},
+                    "resources": {"subscribe": true},
+                    "prompts": {}
+                },
+                "serverInfo": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "toolApiVersion": TOOL_API_VERSION
+                }
             })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(context.registry.list(&context.config.current().tools)),
             "tools/call" => {
-                let params = request
+                let started = std::time::Instant::now();
+                let (result, timings) = phases::measure(async {
+                    match context
+                        .quotas
+                        .charge(quota_key(principal), chrono::Utc::now())
+                        .await
+                    {
+                        Ok(_) => Self::call_tool(context, session, &request.params)
+                            .await
+                            .map(|(result, data)| {
+                                computed_from = Some(data);
+                                result
+                            }),
+                        Err(e) => Err(e),
+                    }
+                })
+                .await;
+                // Only registered names are kept, so usage stats stay bounded
+                let tool = request
                     .params
-                    .as_object()
-                    .ok_or_else(|| Error::McpProtocol("Invalid params".to_string()))?;
-                let tool_name = params
                     .get("name")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| Error::McpProtocol("Missing tool name".to_string()))?;
-                let empty_args = json!({});
-                let arguments = params.get("arguments").unwrap_or(&empty_args);
-
-                match tool_name {
-                    "find_nearby_stations" => {
-                        let input = serde_json::from_value(arguments.clone())?;
-                        let output = handler.find_nearby_stations(input).await?;
-                        Ok(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": serde_json::to_string_pretty(&output)?
-                                }
-                            ]
-                        }))
-                    }
-                    "get_station_by_code" => {
-                        let input = serde_json::from_value(arguments.clone())?;
-                        let output = handler.get_station_by_code(input).await?;
-                        Ok(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": serde_json::to_string_pretty(&output)?
-                                }
-                            ]
-                        }))
-                    }
-                    "search_stations_by_name" => {
-                        let input = serde_json::from_value(arguments.clone())?;
-                        let output = handler.search_stations_by_name(input).await?;
-                        Ok(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": serde_json::to_string_pretty(&output)?
-                                }
-                            ]
-                        }))
-                    }
-                    "get_area_statistics" => {
-                        let input = serde_json::from_value(arguments.clone())?;
-                        let output = handler.get_area_statistics(input).await?;
-                        Ok(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": serde_json::to_string_pretty(&output)?
-                                }
-                            ]
-                        }))
-                    }
-                    "plan_bike_journey" => {
-                        let input = serde_json::from_value(arguments.clone())?;
-                        let output = handler.plan_bike_journey(input).await?;
-                        Ok(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": serde_json::to_string_pretty(&output)?
-                                }
-                            ]
-                        }))
-                    }
-                    _ => Err(Error::McpProtocol(format!("Unknown tool: {tool_name}"))),
+                    .and_then(Value::as_str)
+                    .and_then(|name| context.registry.get(name))
+                    .map_or("unknown", |tool| tool.name);
+                let elapsed = started.elapsed();
+                let error_type = result.as_ref().err().map(Error::error_type);
+                context.handler().metrics().await.record_tool_call(
+                    tool,
+                    elapsed,
+                    error_type,
+                    chrono::Utc::now(),
+                );
+                FinishedCall {
+                    tool,
+                    request_id: &request.id,
+                    arguments: request.params.get("arguments"),
+                    elapsed,
+                    timings,
+                    error_type,
                 }
+                .log_if_slower_than(context.config.current().slow_call_threshold);
+                let active = context.announcements.active(chrono::Utc::now()).await;
+                result.map(|result| announcements::attach(result, &active))
             }
             "resources/list" => Ok(json!({
                 "resources": [
@@ -388,27 +1100,133 @@ impl McpServer {
                         "description": "Combined reference and real-time data for all stations",
                         "mimeType": "application/json"
                     },
+                    {
+                        "uri": "velib://stations/id-mapping",
+                        "name": "Station Identifier Mapping",
+                        "description": "Open data station codes with the matching GBFS station_id, to join results with other Velib datasets",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://digest/morning",
+                        "name": "Morning Commute Digest",
+                        "description": "Favorite station statuses and the best pickup near home, for the requesting session",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://digest/evening",
+                        "name": "Evening Commute Digest",
+                        "description": "Favorite station statuses and the best pickup near work, for the requesting session",
+                        "mimeType": "application/json"
+                    },
                     {
                         "uri": "velib://health",
                         "name": "Service Health Status",
                         "description": "System health and data source status information",
                         "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://stats/usage",
+                        "name": "Tool Usage Statistics",
+                        "description": "Tool call counts, median latencies and top error types over the last hour and day",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://coverage/gaps",
+                        "name": "Station Coverage Gaps",
+                        "description": "Areas of the service area farther than a set distance from every station, with their centroid and size",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://reports/reference-changes",
+                        "name": "Reference Catalog Changes",
+                        "description": "Daily validation of every reference station, with stations added, removed, renamed, moved or resized since the previous one",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": "velib://docs/errors",
+                        "name": "Error Catalog",
+                        "description": "Every error_type a call can fail with, its JSON-RPC code and the fields of its data",
+                        "mimeType": "application/json"
                     }
                 ]
             })),
-            _ => Err(Error::McpProtocol(format!(
-                "Unknown method: {}",
-                request.method
-            ))),
+            "resources/read" => {
+                let uri = request
+                    .params
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Validation("Missing resource uri".to_string()))?;
+                let precision = context.config.current().output_precision;
+                read_resource(context, session, uri)
+                    .await
+                    .and_then(|mut content| {
+                        precision.round(&mut content);
+                        let text = pretty(&content)?;
+                        let mut item = json!({
+                            "uri": uri,
+                            "mimeType": "application/json",
+                        });
+                        if let Some(signer) = &context.signer {
+                            item["_meta"] = signer.content_meta(&text)?;
+                        }
+                        item["text"] = Value::String(text);
+                        Ok(json!({ "contents": [item] }))
+                    })
+            }
+            "resources/subscribe" => {
+                let session = require_session(session, "Resource subscriptions")?;
+                let uri = request
+                    .params
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Validation("Missing resource uri".to_string()))?;
+                context
+                    .subscriptions
+                    .subscribe(session, uri)
+                    .await
+                    .map(|()| json!({}))
+            }
+            "resources/unsubscribe" => {
+                let session = require_session(session, "Resource subscriptions")?;
+                let uri = request
+                    .params
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Validation("Missing resource uri".to_string()))?;
+                context.subscriptions.unsubscribe(session, uri).await;
+                Ok(json!({}))
+            }
+            "prompts/list" => Ok(prompts::list()),
+            "prompts/get" => {
+                let name = request
+                    .params
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Validation("Missing prompt name".to_string()))?;
+                let arguments = request
+                    .params
+                    .get("arguments")
+                    .and_then(Value::as_object)
+                    .cloned()
+                    .unwrap_or_default();
+                prompts::get(name, &arguments)
+            }
+            _ => Err(Error::MethodNotFound(request.method.clone())),
         };
 
         match result {
-            Ok(result_value) => Ok(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(result_value),
-                error: None,
-            }),
+            Ok(result_value) => {
+                let data = computed_from.unwrap_or_else(|| context.handler().data_stamp());
+                Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(meta::with_data_version(
+                        meta::with_timing(result_value, started.elapsed(), data.snapshot_at),
+                        data.version.as_ref(),
+                    )),
+                    error: None,
+                })
+            }
             Err(e) => Ok(JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
@@ -417,16 +1235,540 @@ impl McpServer {
             }),
         }
     }
+
+    /// Run a `tools/call`, or answer it from the response cache, along with
+    /// the data the result was computed from
+    async fn call_tool(
+        context: &McpContext,
+        session: Option<&str>,
+        params: &Value,
+    ) -> Result<(Value, DataStamp)> {
+        let params = params
+            .as_object()
+            .ok_or_else(|| Error::McpProtocol("Invalid params".to_string()))?;
+        let tool_name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| Error::McpProtocol("Missing tool name".to_string()))?;
+        if !context.config.current().tools.allows(tool_name) {
+            return Err(Error::ToolDisabled {
+                tool: tool_name.to_string(),
+            });
+        }
+        let mut arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+        // Session preferences fill in whatever the call leaves out
+        let mut language = None;
+        if let Some(session) = session {
+            if let Some(preferences) = context.sessions.get(session).await {
+                preferences.apply_defaults(tool_name, &mut arguments);
+                language = preferences.language;
+            }
+        }
+
+        // Only the presentation of the result depends on `humanize` and the
+        // response size hints, so calls with and without them share a cached response
+        let request = RequestContext::from_meta(params.get("_meta"))?;
+        let locale = match arguments
+            .as_object_mut()
+            .and_then(|args| args.remove("humanize"))
+        {
+            Some(Value::Bool(true)) => Some(request.locale_or(language.as_deref())),
+            Some(Value::String(tag)) => Some(Locale::from_language(&tag)),
+            _ => None,
+        };
+        let mut hint = |name: &str| {
+            arguments
+                .as_object_mut()
+                .and_then(|args| args.remove(name))
+                .and_then(|value| value.as_u64())
+        };
+        let budget =
+            ResponseBudget::from_hints(hint("max_response_bytes"), hint("max_response_tokens"));
+        let precision = context.config.current().output_precision;
+        let present = |mut result: Value| {
+            phases::time(Phase::Serialization, || {
+                result = precision.result(result);
+                if let Some(locale) = locale {
+                    result = humanize::result(result, locale, request.units);
+                }
+                match budget {
+                    Some(budget) => budget.fit(result),
+                    None => result,
+                }
+            })
+        };
+
+        // Calls written against an older tool API keep working until the next major
+        for deprecated in registry::upgrade_arguments(tool_name, &mut arguments) {
+            warn!(
+                "{}.{} is deprecated since tool API {}",
+                tool_name, deprecated.field, deprecated.deprecated_in
+            );
+        }
+
+        // The client may ask for an answer sooner than the tool's own limit
+        let mut limit = context
+            .registry
+            .timeout_for(tool_name, &context.config.current().tool_timeouts);
+        if let Some(max_time_ms) = params
+            .get("_meta")
+            .and_then(|meta| meta.get("max_time_ms"))
+            .and_then(Value::as_u64)
+        {
+            limit = limit.min(Duration::from_millis(max_time_ms));
+        }
+
+        let responses = context.responses.load_full();
+        let Some(key) = responses
+            .key(tool_name, &arguments)
+            .map(|key| request.scoped_key(key))
+        else {
+            let result =
+                Self::run_tool_within(context, session, &request, tool_name, arguments, limit)
+                    .await?;
+            return Ok((
+                dedup::with_cache_status(present(result), CacheStatus::Bypass),
+                context.handler().data_stamp(),
+            ));
+        };
+        let cached = phases::time_async(Phase::Cache, responses.get(&key)).await;
+        if let Some((cached, data)) = cached {
+            debug!(
+                "Answering repeated {} call from the response cache",
+                tool_name
+            );
+            return Ok((
+                dedup::with_cache_status(present(cached), CacheStatus::Hit),
+                data,
+            ));
+        }
+        let result =
+            Self::run_tool_within(context, session, &request, tool_name, arguments, limit).await?;
+        let data = context.handler().data_stamp();
+        phases::time_async(
+            Phase::Cache,
+            responses.insert(key, result.clone(), data.clone()),
+        )
+        .await;
+        Ok((
+            dedup::with_cache_status(present(result), CacheStatus::Miss),
+            data,
+        ))
+    }
+
+    async fn run_tool_within(
+        context: &McpContext,
+        session: Option<&str>,
+        request: &RequestContext,
+        tool_name: &str,
+        arguments: Value,
+        limit: Duration,
+    ) -> Result<Value> {
+        tokio::time::timeout(
+            limit,
+            Self::run_tool(context, session, request, tool_name, arguments),
+        )
+        .await
+        .map_err(|_| {
+            warn!("{} timed out after {:?}", tool_name, limit);
+            Error::Timeout {
+                operation: tool_name.to_string(),
+                timeout_ms: u64::try_from(limit.as_millis()).unwrap_or(u64::MAX),
+            }
+        })?
+    }
+
+    async fn run_tool(
+        context: &McpContext,
+        session: Option<&str>,
+        request: &RequestContext,
+        tool_name: &str,
+        arguments: Value,
+    ) -> Result<Value> {
+        // The data layer serves cache or refetches depending on the tool's policy
+        let current = context.handler();
+        let scoped;
+        let handler = match context.registry.max_data_age(tool_name) {
+            Some(max_age) => {
+                scoped = current.as_ref().clone().with_max_data_age(max_age);
+                &scoped
+            }
+            None => current.as_ref(),
+        };
+        match tool_name {
+            "set_preferences" => {
+                let session = require_session(session, "Preferences")?;
+                let update: UserPreferences = serde_json::from_value(arguments.clone())?;
+                let preferences = context.sessions.update(session, update).await;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&preferences)?
+                        }
+                    ]
+                }))
+            }
+            "add_favorite_station" => {
+                let session = require_session(session, "Favorites")?;
+                let input: AddFavoriteStationInput = serde_json::from_value(arguments.clone())?;
+                let lookup = handler
+                    .get_station_by_code(GetStationByCodeInput {
+                        station_code: input.station_code.clone(),
+                        include_real_time: false,
+                    })
+                    .await?;
+                if !lookup.found {
+                    return Err(Error::StationNotFound {
+                        station_code: input.station_code,
+                    });
+                }
+                let favorites = context
+                    .favorites
+                    .add(session, &input.station_code, input.label)
+                    .await?;
+                let output = ListFavoriteStationsOutput { favorites };
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "list_favorite_stations" => {
+                let session = require_session(session, "Favorites")?;
+                let output = ListFavoriteStationsOutput {
+                    favorites: context.favorites.list(session).await,
+                };
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_favorites_status" => {
+                let session = require_session(session, "Favorites")?;
+                let input = serde_json::from_value(arguments.clone())?;
+                let favorites = context.favorites.list(session).await;
+                let output = handler.get_favorites_status(favorites, input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "find_nearby_stations" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.find_nearby_stations(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_station_by_code" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.get_station_by_code(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "search_stations_by_name" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.search_stations_by_name(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "autocomplete_station_names" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.autocomplete_station_names(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_area_statistics" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.get_area_statistics(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_station_density" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.get_station_density(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "find_stations_along_route" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.find_stations_along_route(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "compare_stations" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let mut output = handler.compare_stations(input).await?;
+                if request.units != UnitSystem::Metric {
+                    output.markdown = comparison::markdown_table(&output.stations, request.units);
+                }
+                // The table again on its own, for clients that show text blocks as is
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        },
+                        {
+                            "type": "text",
+                            "text": output.markdown
+                        }
+                    ]
+                }))
+            }
+            "check_dropoff_feasibility" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.check_dropoff_feasibility(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "compare_with_typical" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.compare_with_typical(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "diff_station_status" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.diff_station_status(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "generate_journey_link" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = generate_journey_link(context, handler, input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_system_overview" => {
+                let output = handler.get_system_overview().await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "get_fleet_composition" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.get_fleet_composition(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "plan_bike_journey" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.plan_bike_journey(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "plan_multiple_journeys" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.plan_multiple_journeys(input).await?;
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": pretty(&output)?
+                        }
+                    ]
+                }))
+            }
+            "render_station_sparkline" => {
+                let input = serde_json::from_value(arguments.clone())?;
+                let output = handler.render_station_sparkline(input).await?;
+                let mut content = vec![json!({
+                    "type": "text",
+                    "text": pretty(&output)?
+                })];
+                // Clients that render images can show the chart inline
+                if let Some(svg) = &output.svg {
+                    content.push(json!({
+                        "type": "image",
+                        "data": BASE64_STANDARD.encode(svg),
+                        "mimeType": "image/svg+xml"
+                    }));
+                }
+                Ok(json!({ "content": content }))
+            }
+            _ => Err(Error::McpProtocol(format!("Unknown tool: {tool_name}"))),
+        }
+    }
+}
+
+/// Pretty-printed JSON of a tool output, timed as serialization
+fn pretty<T: serde::Serialize + ?Sized>(output: &T) -> Result<String> {
+    Ok(phases::time(Phase::Serialization, || {
+        serde_json::to_string_pretty(output)
+    })?)
+}
+
+/// Session-scoped tools cannot serve anonymous HTTP calls
+fn require_session<'a>(session: Option<&'a str>, feature: &str) -> Result<&'a str> {
+    session.ok_or_else(|| {
+        Error::Validation(format!(
+            "{feature} need a session: connect over WebSocket or send an Authorization bearer key"
+        ))
+    })
+}
+
+/// Quotas follow the caller an auth provider recognized; everyone else,
+/// including bearer tokens nobody checked, shares the anonymous quota
+fn quota_key(principal: Option<&str>) -> Option<&str> {
+    principal.filter(|principal| !principal.starts_with("bearer:"))
+}
+
+/// Read the configuration from the context's source and apply it
+async fn reload_config(context: &McpContext) -> Result<ConfigVersion> {
+    let source = context
+        .config
+        .source()
+        .ok_or_else(|| Error::Config("no configuration source to reload from".to_string()))?;
+    let config = source()?;
+
+    let _reloading = context.config.lock().await;
+    let previous = context.config.current();
+    if config.log_level != previous.log_level {
+        crate::logging::set_filter(config.log_level.as_deref().unwrap_or_default())?;
+    }
+    let handler = context.handler().reconfigured(&config).await;
+    context.handler.store(Arc::new(handler));
+    if config.dedup_ttl != previous.dedup_ttl {
+        context
+            .responses
+            .store(Arc::new(ResponseCache::new(config.dedup_ttl)));
+    }
+    context.quotas.set_limits(config.quota_limits);
+    let version = context.config.replace(config);
+    info!("Applied configuration version {}", version.version);
+    Ok(version)
 }
 
 async fn handle_resource(
     axum::extract::Path(uri): axum::extract::Path<String>,
-    handler: Arc<McpToolHandler>,
+    context: &McpContext,
+    session: Option<&str>,
+    encoding: Encoding,
 ) -> Response {
+    let handler = context.handler();
+    let precision = context.config.current().output_precision;
+
+    if let Some(profile) = uri.strip_prefix("velib://digest/") {
+        return match get_digest_resource(context, session, profile).await {
+            Ok(response) => encoding.respond(&precision.rounded(response)),
+            Err(e) => {
+                error!("Failed to build commute digest: {}", e);
+                let status = match e {
+                    Error::Validation(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (
+                    status,
+                    Json(json!({
+                        "error": "Failed to build commute digest",
+                        "details": e.to_string()
+                    })),
+                )
+                    .into_response()
+            }
+        };
+    }
+
     match uri.as_str() {
         "velib://stations/reference" => {
             match get_reference_stations_resource(Arc::clone(&handler)).await {
-                Ok(response) => Json(response).into_response(),
+                Ok(response) => encoding.respond(&precision.rounded(response)),
                 Err(e) => {
                     error!("Failed to get reference stations: {}", e);
                     (
@@ -442,7 +1784,7 @@ async fn handle_resource(
         }
         "velib://stations/realtime" => {
             match get_realtime_stations_resource(Arc::clone(&handler)).await {
-                Ok(response) => Json(response).into_response(),
+                Ok(response) => encoding.respond(&precision.rounded(response)),
                 Err(e) => {
                     error!("Failed to get real-time stations: {}", e);
                     (
@@ -458,7 +1800,7 @@ async fn handle_resource(
         }
         "velib://stations/complete" => {
             match get_complete_stations_resource(Arc::clone(&handler)).await {
-                Ok(response) => Json(response).into_response(),
+                Ok(response) => encoding.respond(&precision.rounded(response)),
                 Err(e) => {
                     error!("Failed to get complete stations: {}", e);
                     (
@@ -472,8 +1814,22 @@ async fn handle_resource(
                 }
             }
         }
+        "velib://stations/id-mapping" => match get_id_mapping_resource(&handler).await {
+            Ok(response) => encoding.respond(&precision.rounded(response)),
+            Err(e) => {
+                error!("Failed to build station id mapping: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": "Failed to build station id mapping",
+                        "details": e.to_string()
+                    })),
+                )
+                    .into_response()
+            }
+        },
         "velib://health" => match get_health_resource(Arc::clone(&handler)).await {
-            Ok(response) => Json(response).into_response(),
+            Ok(response) => encoding.respond(&precision.rounded(response)),
             Err(e) => {
                 error!("Failed to get health status: {}", e);
                 (
@@ -486,6 +1842,51 @@ async fn handle_resource(
                     .into_response()
             }
         },
+        "velib://stats/usage" => match get_usage_resource(&handler).await {
+            Ok(response) => encoding.respond(&precision.rounded(response)),
+            Err(e) => {
+                error!("Failed to get usage statistics: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": "Failed to build usage statistics",
+                        "details": e.to_string()
+                    })),
+                )
+                    .into_response()
+            }
+        },
+        "velib://coverage/gaps" => match get_coverage_gaps_resource(&handler).await {
+            Ok(response) => encoding.respond(&precision.rounded(response)),
+            Err(e) => {
+                error!("Failed to find coverage gaps: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": "Failed to find coverage gaps",
+                        "details": e.to_string()
+                    })),
+                )
+                    .into_response()
+            }
+        },
+        "velib://reports/reference-changes" => {
+            match get_reference_changes_resource(&handler).await {
+                Ok(response) => encoding.respond(&precision.rounded(response)),
+                Err(e) => {
+                    error!("Failed to validate reference catalog: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": "Failed to validate reference catalog",
+                            "details": e.to_string()
+                        })),
+                    )
+                        .into_response()
+                }
+            }
+        }
+        "velib://docs/errors" => encoding.respond(&precision.rounded(get_error_catalog_resource())),
         _ => (
             StatusCode::NOT_FOUND,
             Json(json!({"error": "Resource not found"})),
@@ -494,12 +1895,124 @@ async fn handle_resource(
     }
 }
 
+/// Parse a JSON-RPC request body, or build the error response to send back
+///
+/// Invalid JSON is a `-32700` parse error. Valid JSON that isn't a request
+/// object is `-32600`, echoing the request's `id` when it has one.
+fn parse_jsonrpc_request(body: &[u8]) -> std::result::Result<JsonRpcRequest, Box<JsonRpcResponse>> {
+    let rejection = |id: Value, code: i32, message: &str, e: serde_json::Error| {
+        warn!("Invalid JSON-RPC request: {}", e);
+        Box::new(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcError {
+                code,
+                message: message.to_string(),
+                data: Some(json!({"original_error": e.to_string()})),
+            }),
+        })
+    };
+
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|e| rejection(Value::Null, -32700, "Parse error", e))?;
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| rejection(id, -32600, "Invalid Request", e))
+}
+
+/// Resolve a resource URI to its JSON content, as served by `resources/read`
+async fn read_resource(context: &McpContext, session: Option<&str>, uri: &str) -> Result<Value> {
+    if let Some(profile) = uri.strip_prefix("velib://digest/") {
+        return get_digest_resource(context, session, profile).await;
+    }
+
+    let handler = context.handler();
+    match uri {
+        "velib://stations/reference" => get_reference_stations_resource(handler).await,
+        "velib://stations/realtime" => get_realtime_stations_resource(handler).await,
+        "velib://stations/complete" => get_complete_stations_resource(handler).await,
+        "velib://stations/id-mapping" => get_id_mapping_resource(&handler).await,
+        "velib://health" => get_health_resource(handler).await,
+        "velib://stats/usage" => get_usage_resource(&handler).await,
+        "velib://coverage/gaps" => get_coverage_gaps_resource(&handler).await,
+        "velib://reports/reference-changes" => get_reference_changes_resource(&handler).await,
+        "velib://docs/errors" => Ok(get_error_catalog_resource()),
+        _ => Err(Error::ResourceNotFound {
+            uri: uri.to_string(),
+        }),
+    }
+}
+
+/// Get the commute digest of a session for the given profile
+async fn get_digest_resource(
+    context: &McpContext,
+    session: Option<&str>,
+    profile: &str,
+) -> Result<Value> {
+    let profile: CommuteProfile = profile.parse()?;
+    let session = require_session(session, "Digests")?;
+    let preferences = context.sessions.get(session).await.unwrap_or_default();
+
+    let favorites = context.favorites.list(session).await;
+    let favorites = context
+        .handler()
+        .get_favorites_status(favorites, GetFavoritesStatusInput::default())
+        .await?
+        .favorites;
+
+    let origin = profile.origin(&preferences);
+    let best_pickup = match origin {
+        Some(origin) => {
+            let output = context
+                .handler()
+                .find_nearby_stations(FindNearbyStationsInput {
+                    latitude: origin.latitude,
+                    longitude: origin.longitude,
+                    radius_meters: preferences
+                        .max_walk_distance
+                        .unwrap_or(DIGEST_PICKUP_RADIUS),
+                    limit: 1,
+                    availability_filter: Some(AvailabilityFilter {
+                        min_bikes: Some(1),
+                        bike_type: preferences.bike_type,
+                        purpose: Some(StationPurpose::Pickup),
+                        ..Default::default()
+                    }),
+                    sort: SortOptions::default(),
+                    lenient: None,
+                    district: None,
+                    auto_expand: false,
+                    barrier_aware: false,
+                    min_results: 1,
+                    near_transit: None,
+                    transit_radius_meters: None,
+                })
+                .await?;
+            output.stations.into_iter().next()
+        }
+        None => None,
+    };
+
+    let digest = CommuteDigest {
+        profile,
+        generated_at: chrono::Utc::now(),
+        origin,
+        favorites,
+        best_pickup,
+    };
+
+    Ok(json!({
+        "summary": digest.summary(),
+        "digest": digest
+    }))
+}
+
 /// Get reference stations resource data
 async fn get_reference_stations_resource(handler: Arc<McpToolHandler>) -> Result<Value> {
     let stations = handler.get_reference_stations().await?;
 
     Ok(json!({
-        "stations": stations,
+        "stations": &*stations,
         "metadata": {
             "total_stations": stations.len(),
             "last_updated": chrono::Utc::now(),
@@ -525,7 +2038,9 @@ async fn get_realtime_stations_resource(handler: Arc<McpToolHandler>) -> Result<
                 "available_docks": status.available_docks,
                 "status": status.status,
                 "last_update": status.last_update,
-                "data_freshness": status.data_freshness
+                "data_freshness": status.data_freshness,
+                "unknown_freshness": status.unknown_freshness,
+                "availability_label": status.availability_label
             })
         })
         .collect();
@@ -541,12 +2056,233 @@ async fn get_realtime_stations_resource(handler: Arc<McpToolHandler>) -> Result<
     }))
 }
 
+async fn journey_stop(handler: &McpToolHandler, station_code: &str) -> Result<JourneyStop> {
+    let station = handler
+        .get_station_by_code(GetStationByCodeInput {
+            station_code: station_code.to_string(),
+            include_real_time: false,
+        })
+        .await?
+        .station
+        .ok_or_else(|| Error::StationNotFound {
+            station_code: station_code.to_string(),
+        })?;
+    Ok(JourneyStop {
+        station_code: station_code.to_string(),
+        name: station.reference.name.to_string(),
+        coordinates: station.reference.coordinates,
+    })
+}
+
+/// A link handing a planned journey to the user, see [`JourneyLinkFormat`]
+async fn generate_journey_link(
+    context: &McpContext,
+    handler: &McpToolHandler,
+    input: GenerateJourneyLinkInput,
+) -> Result<GenerateJourneyLinkOutput> {
+    let input = input.sanitize()?;
+    let journey = SharedJourney {
+        origin: input.origin,
+        pickup: journey_stop(handler, &input.pickup_station_code).await?,
+        dropoff: journey_stop(handler, &input.dropoff_station_code).await?,
+        destination: input.destination,
+    };
+    let (url, expires_at) = match input.format {
+        JourneyLinkFormat::GoogleMaps => (journey_link::google_maps_url(&journey), None),
+        JourneyLinkFormat::Geo => (journey_link::geo_uri(&journey), None),
+        JourneyLinkFormat::Page => {
+            let public_url = context.public_url.as_deref().ok_or_else(|| {
+                Error::Validation(
+                    "page links need the server's public URL (VELIB_PUBLIC_URL); \
+                     use google_maps or geo instead"
+                        .to_string(),
+                )
+            })?;
+            let (id, expires_at) = context.journeys.share(journey).await;
+            (format!("{public_url}/journey/{id}"), Some(expires_at))
+        }
+    };
+    Ok(GenerateJourneyLinkOutput {
+        url,
+        format: input.format,
+        expires_at,
+    })
+}
+
+/// `/journey/{id}`: a journey shared with `generate_journey_link`
+async fn journey_page(context: &McpContext, id: &str) -> Response {
+    match context.journeys.get(id).await {
+        Some((journey, expires_at)) => {
+            Html(journey_link::journey_page(&journey, expires_at)).into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Html("<p>This journey link has expired or does not exist.</p>".to_string()),
+        )
+            .into_response(),
+    }
+}
+
+/// `/widget/station/{code}`: an embeddable HTML card for one station
+async fn station_widget(context: &McpContext, station_code: &str) -> Response {
+    let lookup = context
+        .handler()
+        .get_station_by_code(GetStationByCodeInput {
+            station_code: station_code.to_string(),
+            include_real_time: true,
+        })
+        .await;
+    match lookup {
+        Ok(GetStationByCodeOutput {
+            station: Some(station),
+            ..
+        }) => (
+            [(header::CACHE_CONTROL, "no-cache")],
+            Html(widget::station_card(&station)),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::NOT_FOUND,
+            Html(format!(
+                "<div>Unknown station {}</div>",
+                widget::escape_html(station_code)
+            )),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to render station widget: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html("<div>Station data unavailable</div>".to_string()),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Stream server events (availability changes) to a client as SSE
+async fn event_stream(
+    context: &McpContext,
+    session: Option<String>,
+) -> Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>> {
+    let mut receiver = context.handler().events().await.subscribe();
+    let subscriptions = Arc::clone(&context.subscriptions);
+    let (sender, stream) = tokio::sync::mpsc::channel(EVENT_STREAM_BUFFER);
+    tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                // A lagging subscriber skips what it missed instead of being disconnected
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let mut messages: Vec<SseEvent> = SseEvent::default()
+                .event(event.name())
+                .id(event.id().to_string())
+                .json_data(&event)
+                .into_iter()
+                .collect();
+            // MCP clients reading the stream get announcements as notifications too
+            if let ServerEvent::ServiceAnnouncements { announcements, .. } = &event {
+                messages.extend(
+                    SseEvent::default()
+                        .event("message")
+                        .json_data(announcements::notification(announcements)),
+                );
+            }
+            // Clients identified by an API key also get the MCP notifications
+            // for the resources they subscribed to over `/mcp`
+            if let Some(session) = &session {
+                for uri in subscriptions.matching(session, &event).await {
+                    messages.extend(
+                        SseEvent::default()
+                            .event("message")
+                            .json_data(subscriptions::updated_notification(uri)),
+                    );
+                }
+            }
+            for message in messages {
+                if sender.send(Ok(message)).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Sse::new(ReceiverStream::new(stream)).keep_alive(KeepAlive::default())
+}
+
+/// `/mcp/ws?resume=<token>` picks up the session of an earlier connection
+#[derive(serde::Deserialize)]
+struct WebSocketQuery {
+    resume: Option<String>,
+}
+
+/// `/metrics`: counters and SLO ratios in the Prometheus text format
+#[derive(serde::Deserialize)]
+struct FunctionsQuery {
+    format: Option<String>,
+}
+
+/// The tool registry as an OpenAI (default) or Anthropic function manifest;
+/// calls are then made through `tools/call`
+fn functions_manifest(context: &McpContext, format: Option<&str>) -> Response {
+    match format.unwrap_or("openai").parse::<FunctionFormat>() {
+        Ok(format) => Json(
+            context
+                .registry
+                .functions(format, &context.config.current().tools),
+        )
+        .into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+async fn metrics_endpoint(context: &McpContext) -> Response {
+    let handler = context.handler();
+    let snapshot = handler.metrics().await.snapshot();
+    let memory = handler.memory_stats().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        snapshot.to_prometheus() + &memory.to_prometheus(),
+    )
+        .into_response()
+}
+
+/// Get tool usage statistics from the metrics subsystem
+async fn get_usage_resource(handler: &McpToolHandler) -> Result<Value> {
+    let report = handler.metrics().await.usage_at(chrono::Utc::now());
+    Ok(serde_json::to_value(report)?)
+}
+
+async fn get_coverage_gaps_resource(handler: &McpToolHandler) -> Result<Value> {
+    Ok(serde_json::to_value(handler.coverage_gaps().await?)?)
+}
+
+/// Get reference station codes joined with GBFS station ids
+async fn get_id_mapping_resource(handler: &McpToolHandler) -> Result<Value> {
+    Ok(serde_json::to_value(handler.station_id_mapping().await?)?)
+}
+
+/// Get the last reference catalog validation report
+async fn get_reference_changes_resource(handler: &McpToolHandler) -> Result<Value> {
+    Ok(serde_json::to_value(handler.reference_report().await?)?)
+}
+
+/// Get the error catalog, built from the same [`Error`] methods as error responses
+fn get_error_catalog_resource() -> Value {
+    json!({"errors": Error::catalog()})
+}
+
 /// Get complete stations resource data (reference + real-time)
 async fn get_complete_stations_resource(handler: Arc<McpToolHandler>) -> Result<Value> {
     let stations = handler.get_complete_stations(true).await?;
 
     Ok(json!({
-        "stations": stations,
+        "stations": &*stations,
         "metadata": {
             "total_stations": stations.len(),
             "data_freshness": "Fresh",
@@ -570,32 +2306,57 @@ async fn get_health_resource(handler: Arc<McpToolHandler>) -> Result<Value> {
         0.0
     };
 
-    // Test data source connectivity
-    let (realtime_status, reference_status) = match handler.test_connectivity().await {
-        Ok(()) => ("healthy", "healthy"),
-        Err(_) => ("degraded", "degraded"),
+    // Data source connectivity as of the last scheduled probe, never probed per request
+    let probe = handler.last_probe().await;
+    let reference_status = probe_status(probe.as_ref().map(|probe| &probe.reference));
+    let mut realtime_status = probe_status(probe.as_ref().map(|probe| &probe.realtime));
+
+    // Fetches that succeed but never return newer data still mean a degraded feed
+    let feed = handler.feed_status().await;
+    if feed.frozen {
+        realtime_status = "degraded";
+    }
+    // Age of the newest availability record; unknown until data came in
+    let lag_seconds = feed
+        .newest_update
+        .map(|newest| (chrono::Utc::now() - newest).num_seconds().max(0));
+    let probe_failed = probe.as_ref().is_some_and(|probe| !probe.healthy());
+    let overall_status = if feed.frozen || probe_failed {
+        "degraded"
+    } else {
+        "healthy"
     };
 
     Ok(json!({
-        "status": "healthy",
+        "status": overall_status,
         "version": "1.0.0",
         "uptime_seconds": 0, // TODO: Add real uptime tracking
         "data_sources": {
             "real_time": {
                 "status": realtime_status,
-                "last_update": chrono::Utc::now(),
-                "lag_seconds": 45 // TODO: Calculate real lag
+                "last_update": feed.newest_update,
+                "lag_seconds": lag_seconds,
+                "frozen": feed.frozen,
+                "frozen_for_minutes": feed.frozen_for_minutes,
+                "probe": probe.as_ref().map(|probe| &probe.realtime)
             },
             "reference": {
                 "status": reference_status,
-                "last_update": chrono::Utc::now()
-            }
+                "last_update": chrono::Utc::now(),
+                "probe": probe.as_ref().map(|probe| &probe.reference)
+            },
+            "last_checked": probe.as_ref().map(|probe| probe.checked_at)
         },
         "cache_stats": {
             "hit_rate": hit_rate.min(1.0),
             "entries": total_entries,
             "reference_cache_size": reference_cache_size,
             "realtime_cache_size": realtime_cache_size
-        }
+        },
+        "history": handler.history_stats().await,
+        "memory": handler.memory_stats().await,
+        "change_log": handler.change_log_stats().await,
+        "advisories": handler.advisories().await,
+        "metrics": handler.metrics().await.snapshot()
     }))
 }
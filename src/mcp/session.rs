@@ -0,0 +1,245 @@
+use crate::types::{BikeTypeFilter, Coordinates};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+/// Sessions kept at most; past it the one idle the longest is forgotten
+pub const MAX_SESSIONS: usize = 10_000;
+
+/// Sessions unused for this long are forgotten by [`SessionStore::sweep`]
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Defaults a client sets once and that later tool calls fall back to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserPreferences {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bike_type: Option<BikeTypeFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_walk_distance: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home: Option<Coordinates>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work: Option<Coordinates>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl UserPreferences {
+    /// Overwrite the fields that are set in `update`, keeping the others
+    pub fn merge(&mut self, update: UserPreferences) {
+        if update.bike_type.is_some() {
+            self.bike_type = update.bike_type;
+        }
+        if update.max_walk_distance.is_some() {
+            self.max_walk_distance = update.max_walk_distance;
+        }
+        if update.home.is_some() {
+            self.home = update.home;
+        }
+        if update.work.is_some() {
+            self.work = update.work;
+        }
+        if update.language.is_some() {
+            self.language = update.language;
+        }
+    }
+
+    /// Fill arguments the client left out of a tool call with these preferences
+    ///
+    /// Explicit arguments always win over stored preferences.
+    pub fn apply_defaults(&self, tool_name: &str, arguments: &mut Value) {
+        let Some(args) = arguments.as_object_mut() else {
+            return;
+        };
+
+        match tool_name {
+            "find_nearby_stations" => {
+                if let Some(max_walk) = self.max_walk_distance {
+                    args.entry("radius_meters").or_insert(json!(max_walk));
+                }
+                if let Some(bike_type) = &self.bike_type {
+                    let filter = args
+                        .entry("availability_filter")
+                        .or_insert_with(|| json!({}));
+                    if let Some(filter) = filter.as_object_mut() {
+                        filter.entry("bike_type").or_insert(json!(bike_type));
+                    }
+                }
+            }
+            "plan_bike_journey" | "plan_multiple_journeys" => {
+                let preferences = args.entry("preferences").or_insert_with(|| json!({}));
+                if let Some(preferences) = preferences.as_object_mut() {
+                    if let Some(bike_type) = &self.bike_type {
+                        preferences.entry("bike_type").or_insert(json!(bike_type));
+                    }
+                    if let Some(max_walk) = self.max_walk_distance {
+                        preferences
+                            .entry("max_walk_distance")
+                            .or_insert(json!(max_walk));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Session {
+    preferences: UserPreferences,
+    last_seen: Instant,
+}
+
+/// Preferences keyed by session (WebSocket connection or HTTP API key)
+///
+/// At most [`MAX_SESSIONS`] are kept, and [`Self::sweep`] forgets idle ones.
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, session: &str) -> Option<UserPreferences> {
+        let mut sessions = self.sessions.write().await;
+        let stored = sessions.get_mut(session)?;
+        stored.last_seen = Instant::now();
+        Some(stored.preferences.clone())
+    }
+
+    /// Merge an update into a session's preferences and return the result
+    pub async fn update(&self, session: &str, update: UserPreferences) -> UserPreferences {
+        let mut sessions = self.sessions.write().await;
+        if !sessions.contains_key(session) && sessions.len() >= MAX_SESSIONS {
+            if let Some(idle) = sessions
+                .iter()
+                .min_by_key(|(_, stored)| stored.last_seen)
+                .map(|(key, _)| key.clone())
+            {
+                sessions.remove(&idle);
+            }
+        }
+        let stored = sessions
+            .entry(session.to_string())
+            .or_insert_with(|| Session {
+                preferences: UserPreferences::default(),
+                last_seen: Instant::now(),
+            });
+        stored.last_seen = Instant::now();
+        stored.preferences.merge(update);
+        stored.preferences.clone()
+    }
+
+    pub async fn remove(&self, session: &str) -> Option<UserPreferences> {
+        let mut sessions = self.sessions.write().await;
+        sessions.remove(session).map(|stored| stored.preferences)
+    }
+
+    /// Forget sessions unused for `idle`; returns how many
+    pub async fn sweep(&self, idle: Duration) -> usize {
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, stored| stored.last_seen.elapsed() < idle);
+        before - sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_unset_fields() {
+        let mut preferences = UserPreferences {
+            bike_type: Some(BikeTypeFilter::ElectricOnly),
+            language: Some("fr".to_string()),
+            ..Default::default()
+        };
+
+        preferences.merge(UserPreferences {
+            max_walk_distance: Some(300),
+            language: Some("en".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(preferences.bike_type, Some(BikeTypeFilter::ElectricOnly));
+        assert_eq!(preferences.max_walk_distance, Some(300));
+        assert_eq!(preferences.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_apply_defaults_fills_missing_arguments_only() {
+        let preferences = UserPreferences {
+            bike_type: Some(BikeTypeFilter::ElectricOnly),
+            max_walk_distance: Some(800),
+            ..Default::default()
+        };
+
+        let mut nearby = json!({"latitude": 48.85, "longitude": 2.35, "radius_meters": 200});
+        preferences.apply_defaults("find_nearby_stations", &mut nearby);
+        assert_eq!(nearby["radius_meters"], 200);
+        assert_eq!(nearby["availability_filter"]["bike_type"], "electric");
+
+        let mut journey = json!({
+            "origin": {"latitude": 48.85, "longitude": 2.35},
+            "destination": {"latitude": 48.86, "longitude": 2.34},
+            "preferences": {"bike_type": "mechanical"}
+        });
+        preferences.apply_defaults("plan_bike_journey", &mut journey);
+        assert_eq!(journey["preferences"]["bike_type"], "mechanical");
+        assert_eq!(journey["preferences"]["max_walk_distance"], 800);
+    }
+
+    #[tokio::test]
+    async fn test_session_store_isolates_sessions() {
+        let store = SessionStore::new();
+        store
+            .update(
+                "a",
+                UserPreferences {
+                    max_walk_distance: Some(400),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert_eq!(store.get("a").await.unwrap().max_walk_distance, Some(400));
+        assert!(store.get("b").await.is_none());
+
+        store.remove("a").await;
+        assert!(store.get("a").await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_sessions_are_swept_and_capped() {
+        let store = SessionStore::new();
+        store.update("idle", UserPreferences::default()).await;
+        store.update("active", UserPreferences::default()).await;
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(store.get("active").await.is_some());
+        tokio::time::advance(SESSION_IDLE_TIMEOUT - Duration::from_secs(30)).await;
+
+        assert_eq!(store.sweep(SESSION_IDLE_TIMEOUT).await, 1);
+        assert!(store.get("idle").await.is_none());
+        assert!(store.get("active").await.is_some());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        for n in 1..MAX_SESSIONS {
+            store
+                .update(&format!("session-{n}"), UserPreferences::default())
+                .await;
+        }
+        assert_eq!(store.sessions.read().await.len(), MAX_SESSIONS);
+        store.update("newest", UserPreferences::default()).await;
+        assert_eq!(store.sessions.read().await.len(), MAX_SESSIONS);
+        assert!(store.get("active").await.is_none());
+        assert!(store.get("newest").await.is_some());
+    }
+}
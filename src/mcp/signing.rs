@@ -0,0 +1,187 @@
+use crate::{Error, Result};
+use axum::extract::{Request, State};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use openssl::pkey::{Id, PKey, Private};
+use openssl::sign::Signer;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
+
+/// Base64 Ed25519 signature of the response body, exactly as sent
+pub const SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-signature");
+/// Which key made [`SIGNATURE_HEADER`], as listed by `/public-key`
+pub const SIGNATURE_KEY_ID_HEADER: HeaderName = HeaderName::from_static("x-signature-key-id");
+
+/// Signs resource payloads with an Ed25519 key, so archived copies can be
+/// checked against the public key served at `/public-key`
+pub struct ResponseSigner {
+    key: PKey<Private>,
+    public_key: Vec<u8>,
+    key_id: String,
+}
+
+impl std::fmt::Debug for ResponseSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseSigner")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResponseSigner {
+    /// Read a PEM (PKCS#8) Ed25519 private key, as made by
+    /// `openssl genpkey -algorithm ed25519`
+    pub fn load(path: &Path) -> Result<Self> {
+        let pem = std::fs::read(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read signing key {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+        Self::from_pem(&pem)
+    }
+
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        let key = PKey::private_key_from_pem(pem)
+            .map_err(|e| Error::Config(format!("Invalid signing key: {e}")))?;
+        Self::from_key(key)
+    }
+
+    /// A signer with a fresh key, for tests and one-off runs
+    pub fn generate() -> Result<Self> {
+        let key = PKey::generate_ed25519()
+            .map_err(|e| Error::Internal(anyhow::anyhow!("Cannot generate signing key: {e}")))?;
+        Self::from_key(key)
+    }
+
+    fn from_key(key: PKey<Private>) -> Result<Self> {
+        if key.id() != Id::ED25519 {
+            return Err(Error::Config(
+                "Signing key must be an Ed25519 key".to_string(),
+            ));
+        }
+        let public_key = key
+            .raw_public_key()
+            .map_err(|e| Error::Config(format!("Invalid signing key: {e}")))?;
+        let digest = openssl::sha::sha256(&public_key);
+        let key_id = digest[..8]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        Ok(Self {
+            key,
+            public_key,
+            key_id,
+        })
+    }
+
+    /// First 8 bytes of the SHA-256 of the raw public key, in hex
+    #[must_use]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Base64 detached signature of `payload`
+    pub fn sign(&self, payload: &[u8]) -> Result<String> {
+        let signature = Signer::new_without_digest(&self.key)
+            .and_then(|mut signer| signer.sign_oneshot_to_vec(payload))
+            .map_err(|e| Error::Internal(anyhow::anyhow!("Cannot sign payload: {e}")))?;
+        Ok(BASE64_STANDARD.encode(signature))
+    }
+
+    /// What `/public-key` serves: the raw key in base64, and as a PEM SubjectPublicKeyInfo
+    pub fn public_key_document(&self) -> Result<Value> {
+        let pem = self
+            .key
+            .public_key_to_pem()
+            .map_err(|e| Error::Internal(anyhow::anyhow!("Cannot encode public key: {e}")))?;
+        Ok(json!({
+            "algorithm": "Ed25519",
+            "key_id": self.key_id,
+            "public_key": BASE64_STANDARD.encode(&self.public_key),
+            "pem": String::from_utf8_lossy(&pem),
+        }))
+    }
+
+    /// `_meta` of a `resources/read` content item, signing its `text`
+    pub fn content_meta(&self, text: &str) -> Result<Value> {
+        Ok(json!({
+            "signature": self.sign(text.as_bytes())?,
+            "signatureKeyId": self.key_id,
+        }))
+    }
+}
+
+/// Sign the body of successful responses in [`SIGNATURE_HEADER`]
+pub(crate) async fn sign_response(
+    State(signer): State<Arc<ResponseSigner>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Failed to buffer response for signing: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    match signer.sign(&bytes) {
+        // Base64 and hex are always valid header values
+        Ok(signature) => {
+            if let (Ok(signature), Ok(key_id)) = (
+                HeaderValue::try_from(signature),
+                HeaderValue::try_from(signer.key_id()),
+            ) {
+                parts.headers.insert(SIGNATURE_HEADER, signature);
+                parts.headers.insert(SIGNATURE_KEY_ID_HEADER, key_id);
+            }
+        }
+        Err(e) => error!("Failed to sign response: {}", e),
+    }
+    Response::from_parts(parts, bytes.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::sign::Verifier;
+
+    #[test]
+    fn test_signatures_verify_against_the_public_key() {
+        let signer = ResponseSigner::generate().unwrap();
+        let payload = br#"{"stations": []}"#;
+        let signature = BASE64_STANDARD
+            .decode(signer.sign(payload).unwrap())
+            .unwrap();
+
+        let document = signer.public_key_document().unwrap();
+        let public =
+            PKey::public_key_from_pem(document["pem"].as_str().unwrap().as_bytes()).unwrap();
+        assert_eq!(
+            BASE64_STANDARD
+                .decode(document["public_key"].as_str().unwrap())
+                .unwrap(),
+            public.raw_public_key().unwrap()
+        );
+        assert_eq!(document["key_id"], signer.key_id());
+        let mut verifier = Verifier::new_without_digest(&public).unwrap();
+        assert!(verifier.verify_oneshot(&signature, payload).unwrap());
+        let mut verifier = Verifier::new_without_digest(&public).unwrap();
+        assert!(!verifier.verify_oneshot(&signature, b"{}").unwrap());
+
+        let rsa = PKey::from_rsa(openssl::rsa::Rsa::generate(2048).unwrap()).unwrap();
+        assert!(matches!(
+            ResponseSigner::from_pem(&rsa.private_key_to_pem_pkcs8().unwrap()),
+            Err(Error::Config(_))
+        ));
+    }
+}
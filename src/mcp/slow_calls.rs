@@ -0,0 +1,76 @@
+use serde_json::Value;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::phases::PhaseTimings;
+
+/// Tool calls lasting at least this long are logged, unless configured otherwise
+pub const DEFAULT_SLOW_CALL_MS: u64 = 2000;
+
+/// A finished tool call, as the slow call log sees it
+#[derive(Debug)]
+pub(crate) struct FinishedCall<'a> {
+    pub tool: &'a str,
+    /// JSON-RPC id of the request, `null` outside MCP
+    pub request_id: &'a Value,
+    pub arguments: Option<&'a Value>,
+    pub elapsed: Duration,
+    pub timings: PhaseTimings,
+    pub error_type: Option<&'static str>,
+}
+
+impl FinishedCall<'_> {
+    /// Log a warning with the call's context and where its time went if it
+    /// lasted at least `threshold`, and say whether it did
+    pub(crate) fn log_if_slower_than(&self, threshold: Option<Duration>) -> bool {
+        let Some(threshold) = threshold.filter(|threshold| self.elapsed >= *threshold) else {
+            return false;
+        };
+        let timings = &self.timings;
+        let arguments = self.arguments.unwrap_or(&Value::Null);
+        let compute = self
+            .elapsed
+            .saturating_sub(timings.cache + timings.upstream + timings.serialization);
+        warn!(
+            tool = self.tool,
+            request_id = %self.request_id,
+            arguments = %arguments,
+            elapsed_ms = self.elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            dominant_phase = timings.dominant(self.elapsed),
+            cache_ms = timings.cache.as_millis() as u64,
+            upstream_ms = timings.upstream.as_millis() as u64,
+            serialization_ms = timings.serialization.as_millis() as u64,
+            compute_ms = compute.as_millis() as u64,
+            error_type = self.error_type,
+            "Slow tool call: {} took {} ms, mostly {}",
+            self.tool,
+            self.elapsed.as_millis(),
+            timings.dominant(self.elapsed)
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_calls_over_the_threshold_are_logged() {
+        let call = FinishedCall {
+            tool: "plan_bike_journey",
+            request_id: &Value::Null,
+            arguments: None,
+            elapsed: Duration::from_millis(2500),
+            timings: PhaseTimings {
+                upstream: Duration::from_millis(2000),
+                ..PhaseTimings::default()
+            },
+            error_type: None,
+        };
+        assert!(call.log_if_slower_than(Some(Duration::from_millis(DEFAULT_SLOW_CALL_MS))));
+        assert!(!call.log_if_slower_than(Some(Duration::from_secs(3))));
+        assert!(!call.log_if_slower_than(None));
+    }
+}
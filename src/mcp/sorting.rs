@@ -0,0 +1,224 @@
+use crate::mcp::types::{FavoriteStationStatus, StationWithDistance};
+use crate::types::VelibStation;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Distance,
+    BikesAvailable,
+    DocksAvailable,
+    /// The tool's own ranking (distance, landmark first, saved order)
+    Relevance,
+    Name,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortField {
+    /// Fewest first for distance and names, most first for availability
+    #[must_use]
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            Self::BikesAvailable | Self::DocksAvailable => SortDirection::Descending,
+            Self::Distance | Self::Relevance | Self::Name => SortDirection::Ascending,
+        }
+    }
+}
+
+/// `sort_by` / `sort_direction` arguments shared by every station-listing tool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<SortField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<SortDirection>,
+}
+
+impl SortOptions {
+    #[must_use]
+    pub fn by(field: SortField) -> Self {
+        Self {
+            sort_by: Some(field),
+            sort_direction: None,
+        }
+    }
+
+    #[must_use]
+    pub fn direction(self) -> SortDirection {
+        self.sort_direction.unwrap_or_else(|| {
+            self.sort_by
+                .unwrap_or(SortField::Relevance)
+                .default_direction()
+        })
+    }
+}
+
+/// A tool result entry that can be reordered by [`sort_stations`]
+pub trait SortableStation {
+    /// Whether entries carry a distance, i.e. the tool searched around a point
+    const HAS_DISTANCE: bool = false;
+
+    fn station(&self) -> Option<&VelibStation>;
+
+    fn distance_meters(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl SortableStation for VelibStation {
+    fn station(&self) -> Option<&VelibStation> {
+        Some(self)
+    }
+}
+
+impl SortableStation for StationWithDistance {
+    const HAS_DISTANCE: bool = true;
+
+    fn station(&self) -> Option<&VelibStation> {
+        Some(&self.station)
+    }
+
+    fn distance_meters(&self) -> Option<u32> {
+        Some(self.distance_meters)
+    }
+}
+
+impl SortableStation for FavoriteStationStatus {
+    fn station(&self) -> Option<&VelibStation> {
+        self.station.as_ref()
+    }
+}
+
+/// Reorder `items` as requested, keeping the tool's ranking for ties
+///
+/// Entries missing the sort key (no real-time data, unknown favorite) always
+/// sort last, whatever the direction.
+pub fn sort_stations<T: SortableStation>(items: &mut [T], options: &SortOptions) -> Result<()> {
+    let field = match options.sort_by {
+        None | Some(SortField::Relevance) => return Ok(()),
+        Some(SortField::Distance) if !T::HAS_DISTANCE => {
+            return Err(Error::Validation(
+                "sort_by 'distance' needs a search point; this tool has none".to_string(),
+            ))
+        }
+        Some(field) => field,
+    };
+    let direction = options.direction();
+
+    items.sort_by(|a, b| match field {
+        SortField::Distance => compare(a.distance_meters(), b.distance_meters(), direction),
+        SortField::BikesAvailable => compare(bikes(a), bikes(b), direction),
+        SortField::DocksAvailable => compare(docks(a), docks(b), direction),
+        SortField::Name => compare(name(a), name(b), direction),
+        SortField::Relevance => Ordering::Equal,
+    });
+    Ok(())
+}
+
+fn bikes<T: SortableStation>(item: &T) -> Option<u16> {
+    item.station()?
+        .real_time
+        .as_ref()
+        .map(|rt| rt.bikes.total())
+}
+
+fn docks<T: SortableStation>(item: &T) -> Option<u16> {
+    item.station()?
+        .real_time
+        .as_ref()
+        .map(|rt| rt.available_docks)
+}
+
+fn name<T: SortableStation>(item: &T) -> Option<&str> {
+    item.station().map(|station| &*station.reference.name)
+}
+
+fn compare<K: Ord>(a: Option<K>, b: Option<K>, direction: SortDirection) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match direction {
+            SortDirection::Ascending => a.cmp(&b),
+            SortDirection::Descending => b.cmp(&a),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+        StationStatus,
+    };
+
+    fn station(code: &str, bikes: Option<u16>) -> VelibStation {
+        let reference = StationReference {
+            station_code: code.into(),
+            name: format!("Station {code}").into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity: 30,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        };
+        let real_time = bikes.map(|bikes| {
+            RealTimeStatus::new(
+                BikeAvailability::new(bikes, 0),
+                30 - bikes,
+                StationStatus::Open,
+                chrono::Utc::now(),
+            )
+        });
+        VelibStation {
+            reference,
+            real_time,
+        }
+    }
+
+    fn codes(stations: &[VelibStation]) -> Vec<&str> {
+        stations
+            .iter()
+            .map(|station| &*station.reference.station_code)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_keeps_missing_keys_last_in_both_directions() {
+        let mut stations = vec![
+            station("1", Some(2)),
+            station("2", None),
+            station("3", Some(7)),
+        ];
+
+        sort_stations(&mut stations, &SortOptions::by(SortField::BikesAvailable)).unwrap();
+        assert_eq!(codes(&stations), vec!["3", "1", "2"]);
+
+        let ascending = SortOptions {
+            sort_by: Some(SortField::BikesAvailable),
+            sort_direction: Some(SortDirection::Ascending),
+        };
+        sort_stations(&mut stations, &ascending).unwrap();
+        assert_eq!(codes(&stations), vec!["1", "3", "2"]);
+    }
+
+    #[test]
+    fn test_distance_sort_needs_a_search_point() {
+        let mut stations = vec![station("1", Some(2))];
+        assert!(matches!(
+            sort_stations(&mut stations, &SortOptions::by(SortField::Distance)),
+            Err(Error::Validation(_))
+        ));
+        assert!(sort_stations(&mut stations, &SortOptions::by(SortField::Relevance)).is_ok());
+    }
+}
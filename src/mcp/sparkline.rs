@@ -0,0 +1,63 @@
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const SVG_WIDTH: u32 = 240;
+const SVG_HEIGHT: u32 = 48;
+
+/// A one-line unicode sparkline of `values` scaled to `0..=max`
+#[must_use]
+pub fn unicode(values: &[u16], max: u16) -> String {
+    let max = f64::from(max.max(1));
+    values
+        .iter()
+        .map(|&value| {
+            let level = (f64::from(value) / max * (BARS.len() - 1) as f64).round() as usize;
+            BARS[level.min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// A small standalone SVG line chart of `values` scaled to `0..=max`
+#[must_use]
+pub fn svg(values: &[u16], max: u16) -> String {
+    let max = f64::from(max.max(1));
+    let step = if values.len() > 1 {
+        f64::from(SVG_WIDTH) / (values.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let x = i as f64 * step;
+            let y = f64::from(SVG_HEIGHT) * (1.0 - f64::from(value).min(max) / max);
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" \
+         viewBox=\"0 0 {SVG_WIDTH} {SVG_HEIGHT}\">\
+         <polyline fill=\"none\" stroke=\"#1a9850\" stroke-width=\"2\" points=\"{}\"/></svg>",
+        points.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_scales_to_max() {
+        assert_eq!(unicode(&[0, 5, 10], 10), "▁▅█");
+        assert_eq!(unicode(&[3, 3], 0), "██");
+        assert_eq!(unicode(&[], 10), "");
+    }
+
+    #[test]
+    fn test_svg_plots_one_point_per_value() {
+        let chart = svg(&[0, 10], 10);
+        assert!(chart.starts_with("<svg"));
+        assert!(chart.contains("points=\"0.0,48.0 240.0,0.0\""));
+    }
+}
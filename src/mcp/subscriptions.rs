@@ -0,0 +1,123 @@
+use crate::events::ServerEvent;
+use crate::{Error, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::RwLock;
+
+/// Resources that change when real-time data is refreshed
+pub const SUBSCRIBABLE_RESOURCES: &[&str] =
+    &["velib://stations/realtime", "velib://stations/complete"];
+
+/// Resources an event makes stale
+#[must_use]
+pub fn updated_resources(event: &ServerEvent) -> &'static [&'static str] {
+    match event {
+        ServerEvent::AvailabilityChanged { .. } => SUBSCRIBABLE_RESOURCES,
+        ServerEvent::ServiceAnnouncements { .. } => &[],
+    }
+}
+
+/// The `notifications/resources/updated` message for `uri`
+#[must_use]
+pub fn updated_notification(uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/updated",
+        "params": {"uri": uri}
+    })
+}
+
+/// Resource subscriptions keyed by session (WebSocket connection or HTTP API key)
+#[derive(Debug, Default)]
+pub struct SubscriptionStore {
+    subscriptions: RwLock<HashMap<String, BTreeSet<String>>>,
+}
+
+impl SubscriptionStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn subscribe(&self, session: &str, uri: &str) -> Result<()> {
+        if !SUBSCRIBABLE_RESOURCES.contains(&uri) {
+            return Err(Error::Validation(format!(
+                "{uri} does not support subscriptions (supported: {})",
+                SUBSCRIBABLE_RESOURCES.join(", ")
+            )));
+        }
+        let mut subscriptions = self.subscriptions.write().await;
+        subscriptions
+            .entry(session.to_string())
+            .or_default()
+            .insert(uri.to_string());
+        Ok(())
+    }
+
+    /// Returns whether the session was subscribed to `uri`
+    pub async fn unsubscribe(&self, session: &str, uri: &str) -> bool {
+        let mut subscriptions = self.subscriptions.write().await;
+        let Some(uris) = subscriptions.get_mut(session) else {
+            return false;
+        };
+        let removed = uris.remove(uri);
+        if uris.is_empty() {
+            subscriptions.remove(session);
+        }
+        removed
+    }
+
+    /// Subscribed resources among those `event` updated
+    pub async fn matching(&self, session: &str, event: &ServerEvent) -> Vec<&'static str> {
+        let subscriptions = self.subscriptions.read().await;
+        let Some(uris) = subscriptions.get(session) else {
+            return Vec::new();
+        };
+        updated_resources(event)
+            .iter()
+            .copied()
+            .filter(|uri| uris.contains(*uri))
+            .collect()
+    }
+
+    pub async fn remove(&self, session: &str) {
+        let mut subscriptions = self.subscriptions.write().await;
+        subscriptions.remove(session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refresh() -> ServerEvent {
+        ServerEvent::AvailabilityChanged {
+            id: 1,
+            at: chrono::Utc::now(),
+            stations: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_match_refreshes() {
+        let store = SubscriptionStore::new();
+        store
+            .subscribe("ws:a", "velib://stations/realtime")
+            .await
+            .unwrap();
+        assert!(store
+            .subscribe("ws:a", "velib://stations/reference")
+            .await
+            .is_err());
+
+        assert_eq!(
+            store.matching("ws:a", &refresh()).await,
+            vec!["velib://stations/realtime"]
+        );
+        assert!(store.matching("ws:b", &refresh()).await.is_empty());
+
+        assert!(store.unsubscribe("ws:a", "velib://stations/realtime").await);
+        assert!(!store.unsubscribe("ws:a", "velib://stations/realtime").await);
+        assert!(store.matching("ws:a", &refresh()).await.is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use crate::mcp::types::{AreaStatistics, GetSystemOverviewOutput};
+use crate::mcp::McpClient;
+use crate::metrics::UsageReport;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::Duration;
+
+/// Columns drawn when `COLUMNS` does not say how wide the terminal is
+pub const DEFAULT_WIDTH: usize = 100;
+
+/// Tools listed under recent tool calls, busiest first
+const TOOLS_SHOWN: usize = 10;
+
+/// How long each read may take before its section is skipped for this
+/// refresh, as a tool call can wait on a slow upstream
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything `velib-mcp tui` shows about a running server at one refresh
+#[derive(Debug, Clone)]
+pub struct DebugSnapshot {
+    pub server: String,
+    pub taken_at: DateTime<Utc>,
+    /// Network totals from `get_system_overview`
+    pub stations: Option<AreaStatistics>,
+    /// The `velib://health` resource: feed, caches, memory and counters
+    pub health: Option<Value>,
+    /// The `velib://stats/usage` resource
+    pub usage: Option<UsageReport>,
+    /// What could not be read this time, shown instead of the section
+    pub failures: Vec<String>,
+}
+
+impl DebugSnapshot {
+    /// Read the station totals, health and tool usage of the server behind
+    /// `client`; parts that fail are listed in `failures`
+    pub async fn take(client: &McpClient, server: &str) -> Self {
+        let no_arguments = json!({});
+        let (stations, health, usage) = tokio::join!(
+            within(
+                "get_system_overview",
+                client
+                    .call_tool::<_, GetSystemOverviewOutput>("get_system_overview", &no_arguments)
+            ),
+            within("velib://health", client.read_resource("velib://health")),
+            within(
+                "velib://stats/usage",
+                client.read_resource("velib://stats/usage")
+            ),
+        );
+        let mut failures = Vec::new();
+        Self {
+            server: server.to_string(),
+            taken_at: Utc::now(),
+            stations: kept(stations, &mut failures).map(|overview| overview.system),
+            health: kept(health, &mut failures),
+            usage: kept(usage, &mut failures),
+            failures,
+        }
+    }
+
+    /// The screen for this snapshot, each line cut to `width` columns
+    #[must_use]
+    pub fn render(&self, width: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "velib-mcp · {} · {} · Ctrl-C to quit\n",
+            self.server,
+            self.taken_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+
+        if let Some(stations) = &self.stations {
+            let _ = writeln!(
+                out,
+                "STATIONS\n  {} stations, {} operational, capacity {}\n  \
+                 bikes {} ({} mechanical, {} electric)   docks {}   occupancy {:.3}\n",
+                stations.total_stations,
+                stations.operational_stations,
+                stations.total_capacity,
+                stations.available_bikes.total,
+                stations.available_bikes.mechanical,
+                stations.available_bikes.electric,
+                stations.available_docks,
+                stations.occupancy_rate
+            );
+        }
+
+        if let Some(health) = &self.health {
+            let text = |pointer: &str| {
+                health
+                    .pointer(pointer)
+                    .and_then(Value::as_str)
+                    .unwrap_or("?")
+                    .to_string()
+            };
+            let count = |pointer: &str| health.pointer(pointer).and_then(Value::as_u64);
+            let number = |pointer: &str| count(pointer).map_or("?".to_string(), |n| n.to_string());
+            let feed =
+                if health.pointer("/data_sources/real_time/frozen") == Some(&Value::Bool(true)) {
+                    format!(
+                        "frozen for {} min",
+                        number("/data_sources/real_time/frozen_for_minutes")
+                    )
+                } else {
+                    "live".to_string()
+                };
+            let breaker = if health.pointer("/metrics/breaker_open") == Some(&Value::Bool(true)) {
+                "open"
+            } else {
+                "closed"
+            };
+            let _ = writeln!(
+                out,
+                "HEALTH  {}\n  real-time feed {}, newest data {}\n  \
+                 upstream fetches {}, failed {}, not modified {}, shed {}, breaker {}\n",
+                text("/status"),
+                feed,
+                text("/data_sources/real_time/last_update"),
+                number("/metrics/upstream_fetches"),
+                number("/metrics/upstream_fetch_failures"),
+                number("/metrics/upstream_not_modified"),
+                number("/metrics/upstream_shed"),
+                breaker
+            );
+            let memory = match (
+                count("/memory/resident_bytes"),
+                count("/memory/soft_limit_bytes"),
+            ) {
+                (Some(resident), Some(limit)) => {
+                    format!("{} of {}", megabytes(resident), megabytes(limit))
+                }
+                (Some(resident), None) => megabytes(resident),
+                (None, _) => "?".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "CACHES\n  reference {} entries, real-time {}, upstream pages {}\n  \
+                 history {} samples, {}   resident memory {}, {} trims\n",
+                number("/cache_stats/reference_cache_size"),
+                number("/cache_stats/realtime_cache_size"),
+                number("/memory/cached_pages"),
+                number("/memory/history_samples"),
+                count("/memory/history_bytes").map_or("?".to_string(), megabytes),
+                memory,
+                number("/memory/trims")
+            );
+        }
+
+        if let Some(usage) = &self.usage {
+            let hour = &usage.last_hour;
+            let _ = writeln!(
+                out,
+                "TOOL CALLS, last hour  {} calls, {} errors",
+                hour.total_calls, hour.total_errors
+            );
+            if !hour.tools.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "  {:<32} {:>7} {:>7} {:>10}",
+                    "tool", "calls", "errors", "median ms"
+                );
+            }
+            for tool in hour.tools.iter().take(TOOLS_SHOWN) {
+                let _ = writeln!(
+                    out,
+                    "  {:<32} {:>7} {:>7} {:>10}",
+                    tool.tool, tool.calls, tool.errors, tool.median_latency_ms
+                );
+            }
+            out.push('\n');
+        }
+
+        if self.health.is_some() || self.usage.is_some() {
+            let count = |pointer: &str| {
+                self.health
+                    .as_ref()
+                    .and_then(|health| health.pointer(pointer))
+                    .and_then(Value::as_u64)
+                    .map_or("?".to_string(), |n| n.to_string())
+            };
+            let _ = writeln!(
+                out,
+                "ERRORS\n  panics {}   frozen feed alarms {}   records without timestamp {}",
+                count("/metrics/panics"),
+                count("/metrics/upstream_frozen_alarms"),
+                count("/metrics/unknown_freshness_records")
+            );
+            for error in self
+                .usage
+                .iter()
+                .flat_map(|usage| &usage.last_day.top_errors)
+            {
+                let _ = writeln!(out, "  {:<32} {:>7} today", error.error_type, error.count);
+            }
+            out.push('\n');
+        }
+
+        if !self.failures.is_empty() {
+            out.push_str("NOT AVAILABLE\n");
+            for failure in &self.failures {
+                let _ = writeln!(out, "  {failure}");
+            }
+        }
+
+        out.lines()
+            .map(|line| line.chars().take(width).collect::<String>() + "\n")
+            .collect()
+    }
+}
+
+/// Redraw what the server behind `client` reports every `interval`, on the
+/// terminal's alternate screen, until Ctrl-C
+pub async fn run(client: &McpClient, server: &str, interval: Duration) -> std::io::Result<()> {
+    let width = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_WIDTH);
+    let mut stdout = std::io::stdout();
+    // Alternate screen, cursor hidden
+    write!(stdout, "\x1b[?1049h\x1b[?25l")?;
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    let result = loop {
+        let refresh = async {
+            let snapshot = DebugSnapshot::take(client, server).await;
+            write!(stdout, "\x1b[H\x1b[2J{}", snapshot.render(width))?;
+            stdout.flush()?;
+            tokio::time::sleep(interval).await;
+            std::io::Result::Ok(())
+        };
+        tokio::select! {
+            refreshed = refresh => {
+                if let Err(e) = refreshed {
+                    break Err(e);
+                }
+            }
+            _ = &mut interrupted => break Ok(()),
+        }
+    };
+    write!(stdout, "\x1b[?25h\x1b[?1049l")?;
+    stdout.flush()?;
+    result
+}
+
+async fn within<T>(
+    what: &str,
+    read: impl std::future::Future<Output = crate::Result<T>>,
+) -> std::result::Result<T, String> {
+    match tokio::time::timeout(READ_TIMEOUT, read).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(format!("{what}: {e}")),
+        Err(_) => Err(format!(
+            "{what}: no answer within {} s",
+            READ_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+fn kept<T>(read: std::result::Result<T, String>, failures: &mut Vec<String>) -> Option<T> {
+    read.map_err(|failure| failures.push(failure)).ok()
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::types::AvailableBikesStats;
+    use crate::metrics::{ErrorCount, ToolUsage, UsageSummary};
+
+    #[test]
+    fn test_render_shows_every_section() {
+        let summary = UsageSummary {
+            total_calls: 12,
+            total_errors: 2,
+            tools: vec![ToolUsage {
+                tool: "find_nearby_stations".to_string(),
+                calls: 12,
+                errors: 2,
+                median_latency_ms: 8,
+            }],
+            top_errors: vec![ErrorCount {
+                error_type: "validation_error".to_string(),
+                count: 2,
+            }],
+        };
+        let snapshot = DebugSnapshot {
+            server: "http://127.0.0.1:8080".to_string(),
+            taken_at: Utc::now(),
+            stations: Some(AreaStatistics {
+                total_stations: 1503,
+                operational_stations: 1480,
+                total_capacity: 45_000,
+                available_bikes: AvailableBikesStats {
+                    mechanical: 6000,
+                    electric: 3000,
+                    total: 9000,
+                },
+                available_docks: 30_000,
+                occupancy_rate: 0.2,
+            }),
+            health: Some(json!({
+                "status": "degraded",
+                "data_sources": {"real_time": {"frozen": true, "frozen_for_minutes": 25}},
+                "cache_stats": {"reference_cache_size": 1, "realtime_cache_size": 1},
+                "memory": {"resident_bytes": 89_128_960, "history_samples": 5400},
+                "metrics": {"upstream_fetches": 40, "upstream_fetch_failures": 3, "panics": 0}
+            })),
+            usage: Some(UsageReport {
+                generated_at: Utc::now(),
+                last_hour: summary.clone(),
+                last_day: summary,
+            }),
+            failures: vec!["velib://coverage/gaps: timed out".to_string()],
+        };
+
+        let screen = snapshot.render(DEFAULT_WIDTH);
+        for expected in [
+            "1503 stations, 1480 operational",
+            "HEALTH  degraded",
+            "real-time feed frozen for 25 min",
+            "fetches 40, failed 3",
+            "history 5400 samples",
+            "resident memory 85.0 MB",
+            "12 calls, 2 errors",
+            "find_nearby_stations",
+            "validation_error",
+            "timed out",
+        ] {
+            assert!(
+                screen.contains(expected),
+                "{expected:?} missing from\n{screen}"
+            );
+        }
+        assert!(snapshot
+            .render(20)
+            .lines()
+            .all(|line| line.chars().count() <= 20));
+    }
+}
@@ -1,4 +1,10 @@
-use crate::types::{BikeTypeFilter, Coordinates, DataSource, VelibStation};
+use crate::data::{Advisory, StationNameSuggestion};
+use crate::geometry::{Geometry, LineString};
+use crate::mcp::favorites::FavoriteStation;
+use crate::mcp::sorting::SortOptions;
+use crate::types::{
+    AvailabilityTrend, BikeTypeFilter, Coordinates, DataSource, StationStatus, VelibStation,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +30,17 @@ pub struct AvailabilityFilter {
     pub bike_type: Option<BikeTypeFilter>,
     #[serde(default = "default_true")]
     pub exclude_out_of_service: bool,
+    /// Only require what this trip needs: renting for a pickup, returning for a dropoff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<StationPurpose>,
+}
+
+/// Why a station is being looked for, which decides the service it must offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StationPurpose {
+    Pickup,
+    Dropoff,
 }
 
 fn default_true() -> bool {
@@ -82,6 +99,18 @@ impl GeographicBounds {
             && coords.longitude >= self.west
             && coords.longitude <= self.east
     }
+
+    /// The smallest bounds containing `geometry`
+    #[must_use]
+    pub fn around(geometry: &Geometry) -> Self {
+        let (south, west, north, east) = geometry.bounding_box();
+        Self {
+            north,
+            south,
+            east,
+            west,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +118,9 @@ pub struct StationWithDistance {
     #[serde(flatten)]
     pub station: VelibStation,
     pub distance_meters: u32,
+    /// `distance_meters` at the configured walking speed
+    #[serde(default)]
+    pub walk_time_minutes: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,8 +128,51 @@ pub struct JourneyRecommendation {
     pub pickup_station: VelibStation,
     pub dropoff_station: VelibStation,
     pub walk_to_pickup: u32,
+    #[serde(default)]
+    pub walk_to_pickup_minutes: u32,
     pub walk_from_dropoff: u32,
+    #[serde(default)]
+    pub walk_from_dropoff_minutes: u32,
     pub confidence_score: f64,
+    /// The preferred bike type available at the pickup station
+    pub bike_type: BikeTypeFilter,
+    /// Position of `bike_type` in the requested preferences, 1 for the first choice
+    pub preference_level: usize,
+    /// The ride from pickup to dropoff, when a `route_format` was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<BikeRoute>,
+}
+
+/// Geometry of a ride, for clients to draw
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BikeRoute {
+    pub source: RouteSource,
+    pub distance_meters: u32,
+    /// With `route_format: "geojson"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geojson: Option<LineString>,
+    /// Google encoded polyline (precision 5), with `route_format: "polyline"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polyline: Option<String>,
+}
+
+/// Where a [`BikeRoute`] comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSource {
+    /// The configured routing engine's cycling route
+    RoutingEngine,
+    /// A straight line from pickup to dropoff, without a routing engine
+    StraightLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteFormat {
+    /// A GeoJSON LineString
+    GeoJson,
+    /// A Google encoded polyline
+    Polyline,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,36 +210,284 @@ pub struct FindNearbyStationsInput {
     pub limit: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub availability_filter: Option<AvailabilityFilter>,
+    #[serde(flatten)]
+    pub sort: SortOptions,
+    /// Clamp an oversized `limit`/`radius_meters` instead of failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lenient: Option<bool>,
+    /// Only stations in this district ("Paris 12e", "12e", "Boulogne-Billancourt")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    /// Double the radius, up to the maximum, until `min_results` stations are found
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_expand: bool,
+    #[serde(default = "default_min_results")]
+    pub min_results: u16,
+    /// Measure distances around rivers and railways, through their crossings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub barrier_aware: bool,
+    /// Only stations near a stop of this metro or RER line ("Ligne 1", "RER A") or this stop ("Châtelet")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_transit: Option<String>,
+    /// How far from a `near_transit` stop a station may be, 300 m when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transit_radius_meters: Option<u32>,
+}
+
+pub(crate) fn default_min_results() -> u16 {
+    1
 }
 
-fn default_radius() -> u32 {
+pub(crate) fn default_radius() -> u32 {
     500
 }
-fn default_tool_limit() -> u16 {
+pub(crate) fn default_tool_limit() -> u16 {
     10
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetStationByCodeInput {
     pub station_code: String,
+    /// Deprecated since tool API 1.1 and ignored: real-time data is always included
     #[serde(default = "default_true")]
     pub include_real_time: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchStationsByNameInput {
+    /// May be empty when `near_transit` is set
+    #[serde(default)]
     pub query: String,
     #[serde(default = "default_tool_limit")]
     pub limit: u16,
     #[serde(default = "default_true")]
     pub fuzzy: bool,
+    #[serde(flatten)]
+    pub sort: SortOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lenient: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    /// Only stations near a stop of this metro or RER line ("Ligne 1", "RER A") or this stop ("Châtelet")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_transit: Option<String>,
+    /// How far from a `near_transit` stop a station may be, 300 m when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transit_radius_meters: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutocompleteStationNamesInput {
+    pub prefix: String,
+    #[serde(default = "default_tool_limit")]
+    pub limit: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lenient: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddFavoriteStationInput {
+    pub station_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GetFavoritesStatusInput {
+    #[serde(flatten)]
+    pub sort: SortOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderStationSparklineInput {
+    pub station_code: String,
+    /// How many hours of history to draw, at most 24
+    #[serde(default = "default_sparkline_hours")]
+    pub hours: u16,
+    #[serde(default)]
+    pub format: SparklineFormat,
+}
+
+fn default_sparkline_hours() -> u16 {
+    24
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SparklineFormat {
+    /// Unicode block characters only
+    #[default]
+    Text,
+    /// Unicode sparkline plus an SVG chart
+    Svg,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetAreaStatisticsInput {
-    pub bounds: GeographicBounds,
+    /// Either `bounds` or `polygon` delimits the area
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<GeographicBounds>,
+    /// A GeoJSON Polygon or MultiPolygon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Geometry>,
+    /// Deprecated since tool API 1.1 and ignored: real-time data is always included
     #[serde(default = "default_true")]
     pub include_real_time: bool,
+    /// Also report bike availability over this many trailing minutes of history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetStationDensityInput {
+    /// Either `bounds` or `district` delimits the area
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<GeographicBounds>,
+    /// A Paris arrondissement or commune, measured over the box around its stations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    /// Grid cells with no station this close to their center are coverage gaps
+    #[serde(default = "default_gap_distance")]
+    pub gap_distance_meters: u32,
+    #[serde(default = "default_gap_distance")]
+    pub cell_size_meters: u32,
+}
+
+fn default_gap_distance() -> u32 {
+    crate::mcp::density::DEFAULT_GAP_DISTANCE_METERS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindStationsAlongRouteInput {
+    /// The route as an ordered polyline, from start to finish
+    pub waypoints: Vec<Coordinates>,
+    /// How far from the route a station may be
+    #[serde(default = "default_route_buffer")]
+    pub buffer_meters: u32,
+    #[serde(default = "default_tool_limit")]
+    pub limit: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub availability_filter: Option<AvailabilityFilter>,
+    /// Clamp an oversized `limit` instead of failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lenient: Option<bool>,
+}
+
+fn default_route_buffer() -> u32 {
+    200
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareStationsInput {
+    /// Between 2 and 5 distinct station codes
+    pub station_codes: Vec<String>,
+    /// Report each station's distance from this point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_point: Option<Coordinates>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDropoffFeasibilityInput {
+    pub destination: Coordinates,
+    /// Estimated arrival, at most 2 hours ahead; now when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrival_time: Option<DateTime<Utc>>,
+    /// Minutes past `arrival_time` the estimate must still hold, to cover a late arrival
+    #[serde(default = "default_hold_window")]
+    pub hold_window_minutes: u16,
+    /// Check this station instead of the one closest to `destination`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub station_code: Option<String>,
+    /// How far from `destination` the station and alternatives may be
+    #[serde(default = "default_dropoff_walk")]
+    pub max_walk_meters: u32,
+}
+
+fn default_hold_window() -> u16 {
+    5
+}
+
+fn default_dropoff_walk() -> u32 {
+    500
+}
+
+/// Either `station_code` or a `latitude`/`longitude` area
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareWithTypicalInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub station_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// Around `latitude`/`longitude`
+    #[serde(default = "default_typical_radius")]
+    pub radius_meters: u32,
+}
+
+fn default_typical_radius() -> u32 {
+    500
+}
+
+/// The baseline changes are listed against; with neither, every station is listed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffStationStatusInput {
+    /// `snapshot` returned by a previous call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_snapshot: Option<String>,
+    /// Diff against the state published at this time instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFleetCompositionInput {
+    /// Also report the split over this many trailing minutes of history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_minutes: Option<u32>,
+    /// Width of each history point, aligned to the clock (60 gives one per hour)
+    #[serde(default = "default_fleet_interval")]
+    pub interval_minutes: u32,
+}
+
+fn default_fleet_interval() -> u32 {
+    60
+}
+
+impl Default for GetFleetCompositionInput {
+    fn default() -> Self {
+        Self {
+            window_minutes: None,
+            interval_minutes: default_fleet_interval(),
+        }
+    }
+}
+
+/// What kind of link `generate_journey_link` returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JourneyLinkFormat {
+    /// Google Maps cycling directions through both stations
+    #[default]
+    GoogleMaps,
+    /// A `geo:` URI of the pickup station, opened by map apps on phones
+    Geo,
+    /// A `/journey/{id}` page on this server, kept for a day
+    Page,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateJourneyLinkInput {
+    pub pickup_station_code: String,
+    pub dropoff_station_code: String,
+    /// Where the walk to the pickup station starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Coordinates>,
+    /// Where the walk from the dropoff station ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<Coordinates>,
+    #[serde(default)]
+    pub format: JourneyLinkFormat,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -173,20 +496,87 @@ pub struct PlanBikeJourneyInput {
     pub destination: Coordinates,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preferences: Option<JourneyPreferences>,
+    /// Add the ride's route to each recommendation in this encoding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route_format: Option<RouteFormat>,
+}
+
+/// An origin and destination among those of [`PlanMultipleJourneysInput`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneyEndpoints {
+    pub origin: Coordinates,
+    pub destination: Coordinates,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanMultipleJourneysInput {
+    /// Up to 10 journeys, such as the legs between a delivery runner's stops
+    pub journeys: Vec<JourneyEndpoints>,
+    /// Shared by every journey
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferences: Option<JourneyPreferences>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route_format: Option<RouteFormat>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JourneyPreferences {
     #[serde(default)]
-    pub bike_type: BikeTypeFilter,
+    pub bike_type: BikeTypePreference,
     #[serde(default = "default_max_walk")]
     pub max_walk_distance: u32,
+    /// Measure walks around rivers and railways, through their crossings
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub barrier_aware: bool,
+    /// Also pick up from and drop off at stations suspected out of service
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_suspected_out_of_service: bool,
 }
 
-fn default_max_walk() -> u32 {
+pub(crate) fn default_max_walk() -> u32 {
     500
 }
 
+/// One bike type, or several in order of preference
+///
+/// With `["electric", "mechanical"]`, planning picks up an e-bike when one is
+/// within walking distance and falls back to a mechanical bike otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BikeTypePreference {
+    Single(BikeTypeFilter),
+    Ordered(Vec<BikeTypeFilter>),
+}
+
+impl Default for BikeTypePreference {
+    fn default() -> Self {
+        Self::Single(BikeTypeFilter::AnyType)
+    }
+}
+
+impl BikeTypePreference {
+    /// Bike types from most to least preferred
+    #[must_use]
+    pub fn levels(&self) -> &[BikeTypeFilter] {
+        match self {
+            Self::Single(bike_type) => std::slice::from_ref(bike_type),
+            Self::Ordered(bike_types) => bike_types,
+        }
+    }
+
+    /// 1-based preference level of the first type `accepts` takes, with that type
+    pub fn first_match(
+        &self,
+        mut accepts: impl FnMut(&BikeTypeFilter) -> bool,
+    ) -> Option<(usize, &BikeTypeFilter)> {
+        self.levels()
+            .iter()
+            .enumerate()
+            .find(|(_, bike_type)| accepts(bike_type))
+            .map(|(index, bike_type)| (index + 1, bike_type))
+    }
+}
+
 // MCP Tool Outputs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FindNearbyStationsOutput {
@@ -198,8 +588,34 @@ pub struct FindNearbyStationsOutput {
 pub struct SearchMetadata {
     pub query_point: Coordinates,
     pub radius_meters: u32,
+    /// The requested radius, when `auto_expand` had to widen it to `radius_meters`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expanded_from_meters: Option<u32>,
+    /// Stations matching the search, before `limit` was applied
     pub total_found: u32,
+    /// `limit` left out some of the `total_found` stations
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_more: bool,
+    /// How to reach the stations left out, when `has_more`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guidance: Option<String>,
     pub search_time_ms: u64,
+    /// Lenient mode clamped the request; `applied_limits` holds what was used
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_limits: Option<AppliedLimits>,
+    /// The transit line or stop `near_transit` designated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_transit: Option<String>,
+}
+
+/// The limits a lenient request actually ran with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedLimits {
+    pub limit: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius_meters: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -221,12 +637,367 @@ pub struct TextSearchMetadata {
     pub total_found: u32,
     pub fuzzy_enabled: bool,
     pub search_time_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_landmark: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_limits: Option<AppliedLimits>,
+    /// The transit line or stop `near_transit` designated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub near_transit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutocompleteStationNamesOutput {
+    pub prefix: String,
+    pub suggestions: Vec<StationNameSuggestion>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_limits: Option<AppliedLimits>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListFavoriteStationsOutput {
+    pub favorites: Vec<FavoriteStation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteStationStatus {
+    #[serde(flatten)]
+    pub favorite: FavoriteStation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub station: Option<VelibStation>,
+    pub found: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFavoritesStatusOutput {
+    pub favorites: Vec<FavoriteStationStatus>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderStationSparklineOutput {
+    pub station_code: String,
+    pub name: String,
+    pub hours: u16,
+    /// Samples drawn, oldest first; empty until the server has observed the station
+    pub samples: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_bikes: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bikes: Option<u16>,
+    /// Scale of the chart: the station's capacity
+    pub scale_max: u16,
+    pub sparkline: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svg: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetAreaStatisticsOutput {
     pub area_stats: AreaStatistics,
+    /// The requested bounds, or the box around the requested polygon
+    pub bounds: GeographicBounds,
+    /// The same statistics per district, for stations with a known district
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_district: Vec<DistrictStatistics>,
+    /// Available bikes over the requested trailing window, steadier than the snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<AvailabilityWindow>,
+}
+
+/// The area's total available bikes as recorded over a trailing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvailabilityWindow {
+    pub window_minutes: u32,
+    /// Instants at which a station in the area was observed, including now
+    pub samples: usize,
+    pub min_bikes: u32,
+    pub max_bikes: u32,
+    pub mean_bikes: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistrictStatistics {
+    pub district: String,
+    #[serde(flatten)]
+    pub stats: AreaStatistics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetStationDensityOutput {
     pub bounds: GeographicBounds,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    pub area_km2: f64,
+    pub total_stations: u32,
+    pub total_capacity: u32,
+    pub stations_per_km2: f64,
+    pub capacity_per_km2: f64,
+    pub coverage: CoverageSummary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageSummary {
+    /// Actual grid cell size, widened for large areas
+    pub cell_size_meters: u32,
+    pub gap_distance_meters: u32,
+    pub grid_cells: u32,
+    pub uncovered_cells: u32,
+    /// Share of cells with a station within the gap distance
+    pub covered_ratio: f64,
+    /// The most isolated uncovered cells, farthest from a station first
+    pub gaps: Vec<CoverageGap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageGap {
+    pub center: Coordinates,
+    /// Distance to the closest station, `None` when the area has none
+    pub nearest_station_meters: Option<u32>,
+}
+
+/// Connected uncovered cells, as reported by `velib://coverage/gaps`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageGapArea {
+    /// Mean of the gap's cell centers
+    pub centroid: Coordinates,
+    pub area_km2: f64,
+    pub cells: u32,
+    /// Distance from the gap's worst-served cell to the closest station,
+    /// `None` when there are no stations
+    pub nearest_station_meters: Option<u32>,
+}
+
+/// Part of the service area `velib://coverage/gaps` looks for gaps in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageExtent {
+    /// The configured service area polygon
+    ServicePolygon,
+    /// The bounding box of the stations, without a polygon
+    StationExtent,
+}
+
+/// Areas farther than `gap_distance_meters` from every station
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageGapsReport {
+    pub generated_at: DateTime<Utc>,
+    pub extent: CoverageExtent,
+    pub bounds: GeographicBounds,
+    pub gap_distance_meters: u32,
+    pub cell_size_meters: u32,
+    /// Grid cells analyzed within the extent
+    pub grid_cells: u32,
+    pub uncovered_km2: f64,
+    pub total_gaps: u32,
+    /// Largest first, at most 50
+    pub gaps: Vec<CoverageGapArea>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindStationsAlongRouteOutput {
+    /// Stations in the order the route passes them
+    pub stations: Vec<StationAlongRoute>,
+    pub route_length_meters: u32,
+    pub buffer_meters: u32,
+    pub total_found: u32,
+    /// Lenient mode clamped the request; `applied_limits` holds what was used
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_limits: Option<AppliedLimits>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationAlongRoute {
+    #[serde(flatten)]
+    pub station: VelibStation,
+    /// Distance from the station to the closest point of the route
+    pub distance_from_route_meters: u32,
+    /// Distance along the route from its start to that closest point
+    pub progress_meters: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareStationsOutput {
+    /// In the requested order
+    pub stations: Vec<StationComparison>,
+    /// The same comparison as a markdown table
+    pub markdown: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationComparison {
+    pub station_code: String,
+    pub name: String,
+    /// `None` without real-time data
+    pub status: Option<StationStatus>,
+    pub mechanical_bikes: u16,
+    pub electric_bikes: u16,
+    pub available_docks: u16,
+    pub capacity: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_meters: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walk_time_minutes: Option<u32>,
+    /// Share of the last day's samples with both a bike and a free dock
+    pub reliability_score: Option<f64>,
+    pub reliability_samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDropoffFeasibilityOutput {
+    pub arrival_time: DateTime<Utc>,
+    /// From now to the end of the hold window, which the estimates are for
+    pub minutes_ahead: u32,
+    /// The requested station, or the one closest to the destination; `None`
+    /// when no station is within `max_walk_meters`
+    pub station: Option<DropoffEstimate>,
+    /// Other stations within walking distance taking returns, most likely to have a dock first
+    pub alternatives: Vec<DropoffEstimate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DropoffEstimate {
+    pub station_code: String,
+    pub name: String,
+    /// From the destination
+    pub distance_meters: u32,
+    pub walk_time_minutes: u32,
+    pub available_docks: u16,
+    pub capacity: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trend: Option<AvailabilityTrend>,
+    /// Change in free docks per hour over the last 30 minutes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docks_change_per_hour: Option<f64>,
+    /// Free docks around the arrival time of day, the previous day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usual_free_docks: Option<u16>,
+    pub expected_free_docks: f64,
+    /// Chance at least one dock is free at the end of the hold window; 0 when
+    /// the station does not take returns
+    pub probability: f64,
+}
+
+/// How current bikes compare with the usual level at this weekday and hour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypicalLevel {
+    UnusuallyEmpty,
+    BelowTypical,
+    Typical,
+    AboveTypical,
+    UnusuallyFull,
+    /// No history from the same time in previous weeks
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareWithTypicalOutput {
+    /// Set when a single station was compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub station_code: Option<String>,
+    /// Stations whose bikes are added up, those with history in every week compared
+    pub stations_compared: u32,
+    /// Lowercase English weekday, in UTC
+    pub weekday: String,
+    pub hour_utc: u32,
+    pub current_bikes: u32,
+    /// Average bikes at the same time in the previous weeks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typical_bikes: Option<f64>,
+    pub weeks_compared: u32,
+    /// How many usual spreads `current_bikes` is above (positive) or below
+    /// `typical_bikes`, like a z-score
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviation: Option<f64>,
+    pub level: TypicalLevel,
+    /// One sentence an agent can repeat
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffStationStatusOutput {
+    /// Id of the current state, to pass as `since_snapshot` on the next call
+    pub snapshot: String,
+    pub snapshot_at: DateTime<Utc>,
+    /// The baseline is older than the server remembers, or missing: `changes`
+    /// then lists every station
+    pub full_resync: bool,
+    /// Sorted by station code
+    pub changes: Vec<StationStatusChange>,
+}
+
+/// A station's current availability, with what changed since the baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationStatusChange {
+    pub station_code: String,
+    pub mechanical_bikes: u16,
+    pub electric_bikes: u16,
+    pub available_docks: u16,
+    pub status: StationStatus,
+    /// Change in bikes since the baseline; absent for stations not in it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bikes_delta: Option<i32>,
+    /// Status in the baseline, when it was different
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<StationStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateJourneyLinkOutput {
+    pub url: String,
+    pub format: JourneyLinkFormat,
+    /// When a `page` link stops working; map links do not expire
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetSystemOverviewOutput {
+    /// Totals over every station in the network
+    pub system: AreaStatistics,
+    /// Unusual citywide conditions such as strike days or outages; empty most of the time
+    pub advisories: Vec<Advisory>,
+}
+
+/// Docked bikes by type
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FleetComposition {
+    pub mechanical: u32,
+    pub electric: u32,
+    pub total: u32,
+    /// Electric bikes over all bikes, 0 when none are docked
+    pub electric_share: f64,
+}
+
+/// The mean split over one interval of history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetCompositionPoint {
+    /// Start of the interval
+    pub at: DateTime<Utc>,
+    /// Refreshes averaged into this point
+    pub samples: usize,
+    #[serde(flatten)]
+    pub composition: FleetComposition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFleetCompositionOutput {
+    /// Bikes docked across the network now
+    pub current: FleetComposition,
+    /// Stations with real-time data counted in `current`
+    pub stations_reporting: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_minutes: Option<u32>,
+    /// One point per interval of the window, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<FleetCompositionPoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -234,23 +1005,63 @@ pub struct PlanBikeJourneyOutput {
     pub journey: BikeJourney,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanMultipleJourneysOutput {
+    /// One plan per requested journey, in the same order
+    pub journeys: Vec<BikeJourney>,
+    /// Stations several recommendations rely on with too few bikes or docks for all of them
+    pub warnings: Vec<SharedStationWarning>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedStationUse {
+    /// The journeys pick up bikes there
+    Pickup,
+    /// The journeys return bikes there
+    Dropoff,
+}
+
+/// Journeys whose best recommendations compete for the same bikes or docks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedStationWarning {
+    pub station_code: String,
+    pub station_name: String,
+    #[serde(rename = "use")]
+    pub station_use: SharedStationUse,
+    /// Positions of the competing journeys in the request, from 0
+    pub journeys: Vec<usize>,
+    /// Bikes of the recommended types, or docks, the station has now
+    pub available: u16,
+    pub message: String,
+}
+
 // Generic MCP Types
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "jsonrpc")]
 pub struct JsonRpcRequest {
     #[serde(default = "default_jsonrpc")]
     pub jsonrpc: String,
+    /// Absent for notifications
+    #[serde(default)]
     pub id: serde_json::Value,
     pub method: String,
+    #[serde(default)]
     pub params: serde_json::Value,
 }
 
+impl JsonRpcRequest {
+    /// Notifications (`notifications/initialized`, ...) expect no response
+    #[must_use]
+    pub fn is_notification(&self) -> bool {
+        self.method.starts_with("notifications/")
+    }
+}
+
 fn default_jsonrpc() -> String {
     "2.0".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "jsonrpc")]
 pub struct JsonRpcResponse {
     #[serde(default = "default_jsonrpc")]
     pub jsonrpc: String,
@@ -271,12 +1082,35 @@ pub struct JsonRpcError {
 
 impl From<crate::Error> for JsonRpcError {
     fn from(err: crate::Error) -> Self {
+        let mut data = serde_json::json!({
+            "error_type": err.error_type()
+        });
+        if let crate::Error::QuotaExceeded {
+            period,
+            limit,
+            used,
+            resets_at,
+        } = &err
+        {
+            data["quota"] = serde_json::json!({
+                "period": period,
+                "limit": limit,
+                "used": used,
+                "resets_at": resets_at,
+            });
+        }
+        if let crate::Error::Timeout {
+            operation,
+            timeout_ms,
+        } = &err
+        {
+            data["operation"] = serde_json::json!(operation);
+            data["timeout_ms"] = serde_json::json!(timeout_ms);
+        }
         Self {
             code: err.mcp_error_code(),
             message: err.to_string(),
-            data: Some(serde_json::json!({
-                "error_type": err.error_type()
-            })),
+            data: Some(data),
         }
     }
 }
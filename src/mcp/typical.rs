@@ -0,0 +1,118 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::mcp::types::TypicalLevel;
+
+/// Past weeks looked at, as far as the history retention allows
+pub const MAX_TYPICAL_WEEKS: i64 = 8;
+
+/// A sample this close to the same time a week earlier counts for that week
+pub const TYPICAL_TOLERANCE_MINUTES: i64 = 30;
+
+/// Deviation beyond which a level is reported as above or below typical
+const NOTABLE_DEVIATION: f64 = 1.0;
+
+/// Deviation beyond which a level is unusual
+const UNUSUAL_DEVIATION: f64 = 2.0;
+
+impl TypicalLevel {
+    #[must_use]
+    pub fn from_deviation(deviation: Option<f64>) -> Self {
+        match deviation {
+            None => Self::Unknown,
+            Some(d) if d <= -UNUSUAL_DEVIATION => Self::UnusuallyEmpty,
+            Some(d) if d <= -NOTABLE_DEVIATION => Self::BelowTypical,
+            Some(d) if d < NOTABLE_DEVIATION => Self::Typical,
+            Some(d) if d < UNUSUAL_DEVIATION => Self::AboveTypical,
+            Some(_) => Self::UnusuallyFull,
+        }
+    }
+
+    /// Opening words of a summary
+    #[must_use]
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::UnusuallyEmpty => "Unusually empty",
+            Self::BelowTypical => "Emptier than usual",
+            Self::Typical => "About as usual",
+            Self::AboveTypical => "Fuller than usual",
+            Self::UnusuallyFull => "Unusually full",
+            Self::Unknown => "Not comparable yet",
+        }
+    }
+}
+
+/// Bikes in the sample closest to `at`, if one is within `TYPICAL_TOLERANCE_MINUTES`
+#[must_use]
+pub fn observed_near(samples: &[(DateTime<Utc>, u16)], at: DateTime<Utc>) -> Option<u16> {
+    samples
+        .iter()
+        .filter(|(observed_at, _)| {
+            (*observed_at - at).abs() <= Duration::minutes(TYPICAL_TOLERANCE_MINUTES)
+        })
+        .min_by_key(|(observed_at, _)| (*observed_at - at).abs())
+        .map(|(_, bikes)| *bikes)
+}
+
+/// Mean of the weekly levels and how far `current` is from it, in spreads
+///
+/// The spread is the standard deviation of the weekly levels, but at least
+/// the square root of their mean (and 1), as bike counts vary that much from
+/// one week to the next anyway; so a single past week already gives a usable
+/// deviation. `None` without any weekly level.
+#[must_use]
+pub fn deviation(current: f64, weekly: &[f64]) -> Option<(f64, f64)> {
+    if weekly.is_empty() {
+        return None;
+    }
+    let count = weekly.len() as f64;
+    let mean = weekly.iter().sum::<f64>() / count;
+    let variance = weekly
+        .iter()
+        .map(|level| (level - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let spread = variance.sqrt().max(mean.sqrt()).max(1.0);
+    Some((mean, (current - mean) / spread))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_level_near_the_same_time() {
+        let at = Utc::now();
+        let samples = [
+            (at - Duration::minutes(50), 3),
+            (at - Duration::minutes(20), 7),
+            (at + Duration::minutes(5), 9),
+        ];
+        assert_eq!(observed_near(&samples, at), Some(9));
+        assert_eq!(observed_near(&samples[..1], at), None);
+    }
+
+    #[test]
+    fn test_deviation_from_weekly_levels() {
+        assert_eq!(deviation(5.0, &[]), None);
+
+        // Weeks at 15, 16 and 17 bikes: the spread is floored at sqrt(16) = 4
+        let (typical, z) = deviation(4.0, &[15.0, 16.0, 17.0]).unwrap();
+        assert_eq!(typical, 16.0);
+        assert_eq!(z, -3.0);
+        assert_eq!(
+            TypicalLevel::from_deviation(Some(z)),
+            TypicalLevel::UnusuallyEmpty
+        );
+
+        let (_, z) = deviation(20.0, &[16.0]).unwrap();
+        assert_eq!(
+            TypicalLevel::from_deviation(Some(z)),
+            TypicalLevel::AboveTypical
+        );
+        assert_eq!(
+            TypicalLevel::from_deviation(Some(0.5)),
+            TypicalLevel::Typical
+        );
+        assert_eq!(TypicalLevel::from_deviation(None), TypicalLevel::Unknown);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::types::{AvailabilityTrend, StationStatus, VelibStation};
+
+/// A self-contained HTML card showing one station's current availability
+///
+/// Styles are inline so the snippet renders the same wherever it is embedded.
+#[must_use]
+pub fn station_card(station: &VelibStation) -> String {
+    let name = escape_html(&station.reference.name);
+    let Some(rt) = &station.real_time else {
+        return card(
+            "#888",
+            &name,
+            "<div>No real-time data available</div>",
+            &station.reference.station_code,
+        );
+    };
+
+    let bikes = rt.bikes.total();
+    let accent = match rt.status {
+        StationStatus::Open if bikes == 0 => "#d7301f",
+        StationStatus::Open if bikes < 3 => "#fc8d59",
+        StationStatus::Open => "#1a9850",
+        StationStatus::Closed | StationStatus::Maintenance => "#888",
+    };
+    let mut body = format!(
+        "<div style=\"font-size:22px;font-weight:bold\">{bikes} bikes</div>\
+         <div>{} mechanical · {} electric · {} docks free</div>",
+        rt.bikes.mechanical, rt.bikes.electric, rt.available_docks
+    );
+    if rt.status != StationStatus::Open {
+        body.push_str(&format!("<div><b>{:?}</b></div>", rt.status));
+    }
+    match rt.trend {
+        Some(AvailabilityTrend::Emptying) => body.push_str("<div>Emptying fast</div>"),
+        Some(AvailabilityTrend::Filling) => body.push_str("<div>Filling up</div>"),
+        Some(AvailabilityTrend::Stable) | None => {}
+    }
+    if let Some(updated) = rt.last_update {
+        body.push_str(&format!(
+            "<div style=\"color:#666;font-size:11px\">Updated {}</div>",
+            updated.format("%H:%M UTC")
+        ));
+    }
+
+    card(accent, &name, &body, &station.reference.station_code)
+}
+
+fn card(accent: &str, name: &str, body: &str, station_code: &str) -> String {
+    format!(
+        "<div class=\"velib-station\" data-station-code=\"{code}\" \
+         style=\"font-family:sans-serif;font-size:13px;max-width:280px;padding:8px 12px;\
+         border-left:6px solid {accent};border-radius:4px;background:#fff;\
+         box-shadow:0 1px 3px rgba(0,0,0,.2)\">\
+         <div style=\"font-weight:bold;margin-bottom:4px\">{name}</div>{body}</div>",
+        code = escape_html(station_code),
+    )
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BikeAvailability, Coordinates, RealTimeStatus, ServiceCapabilities, StationReference,
+    };
+
+    #[test]
+    fn test_card_escapes_station_names() {
+        let station = VelibStation::new(StationReference {
+            station_code: "16107".into(),
+            name: "Quai <Branly> & \"Tour\"".into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity: 30,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        })
+        .with_real_time(RealTimeStatus::new(
+            BikeAvailability::new(0, 0),
+            30,
+            StationStatus::Open,
+            chrono::Utc::now(),
+        ));
+
+        let card = station_card(&station);
+        assert!(card.contains("Quai &lt;Branly&gt; &amp; &quot;Tour&quot;"));
+        assert!(card.contains("0 bikes"));
+        assert!(card.contains("#d7301f"));
+    }
+}
@@ -0,0 +1,591 @@
+use crate::data::DataVersion;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Tool calls answered faster than this count as meeting the latency SLO
+pub const TOOL_CALL_SLO_MS: u128 = 500;
+
+/// Window of the upstream fetch success rate
+const UPSTREAM_WINDOW_MINUTES: i64 = 5;
+
+/// How long tool calls are remembered for usage statistics
+const USAGE_WINDOW_HOURS: i64 = 24;
+
+/// Cap on remembered tool calls, so a traffic spike can't grow the log unbounded
+const MAX_USAGE_RECORDS: usize = 100_000;
+
+/// Error types listed in a usage summary
+const TOP_ERROR_TYPES: usize = 5;
+
+/// Process-wide counters, shared behind an `Arc` and exposed on `velib://health`
+/// and, in Prometheus format, on `/metrics`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    realtime_refreshes: AtomicU64,
+    upstream_frozen_alarms: AtomicU64,
+    unknown_freshness_records: AtomicU64,
+    tool_calls: AtomicU64,
+    tool_calls_within_slo: AtomicU64,
+    realtime_reads: AtomicU64,
+    realtime_reads_fresh: AtomicU64,
+    upstream_fetches: AtomicU64,
+    upstream_fetch_failures: AtomicU64,
+    upstream_shed: AtomicU64,
+    upstream_not_modified: AtomicU64,
+    station_merges: AtomicU64,
+    upstream_recent: Mutex<VecDeque<(DateTime<Utc>, bool)>>,
+    breaker_open_ms: AtomicU64,
+    breaker_open_since: Mutex<Option<DateTime<Utc>>>,
+    tool_usage: Mutex<VecDeque<ToolCallRecord>>,
+    realtime_snapshot_at: Mutex<Option<DateTime<Utc>>>,
+    data_version: Mutex<Option<DataVersion>>,
+    panics: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ToolCallRecord {
+    at: DateTime<Utc>,
+    tool: &'static str,
+    latency_ms: u64,
+    error_type: Option<&'static str>,
+}
+
+/// Point-in-time copy of [`Metrics`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub realtime_refreshes: u64,
+    pub upstream_frozen_alarms: u64,
+    /// Real-time records served without a parseable upstream timestamp
+    pub unknown_freshness_records: u64,
+    pub tool_calls: u64,
+    /// Tool calls answered within [`TOOL_CALL_SLO_MS`]
+    pub tool_calls_within_slo: u64,
+    pub realtime_reads: u64,
+    /// Real-time reads served while the feed's newest data was under 5 minutes old
+    pub realtime_reads_fresh: u64,
+    pub upstream_fetches: u64,
+    pub upstream_fetch_failures: u64,
+    /// Upstream fetches refused because the request budget ran out
+    pub upstream_shed: u64,
+    /// Upstream pages revalidated with a `304 Not Modified` instead of downloaded again
+    pub upstream_not_modified: u64,
+    /// Duplicate reference dataset entries merged into another with the same station code
+    pub station_merges: u64,
+    /// Share of upstream fetches that succeeded over the last 5 minutes
+    pub upstream_success_ratio_5m: Option<f64>,
+    pub breaker_open: bool,
+    /// Total time the upstream circuit breaker has spent open
+    pub breaker_open_seconds: u64,
+    /// Requests whose handler panicked and got a last-resort error instead
+    pub panics: u64,
+}
+
+impl Metrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_realtime_refresh(&self) {
+        self.realtime_refreshes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_frozen_alarm(&self) {
+        self.upstream_frozen_alarms.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_unknown_freshness(&self, records: u64) {
+        self.unknown_freshness_records
+            .fetch_add(records, Ordering::Relaxed);
+    }
+
+    /// Count a tool call, and remember it for [`Self::usage_at`]
+    ///
+    /// `error_type` is the [`Error::error_type`](crate::Error::error_type) of a failed call.
+    pub fn record_tool_call(
+        &self,
+        tool: &'static str,
+        elapsed: std::time::Duration,
+        error_type: Option<&'static str>,
+        now: DateTime<Utc>,
+    ) {
+        self.tool_calls.fetch_add(1, Ordering::Relaxed);
+        if elapsed.as_millis() < TOOL_CALL_SLO_MS {
+            self.tool_calls_within_slo.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut usage = lock(&self.tool_usage);
+        usage.push_back(ToolCallRecord {
+            at: now,
+            tool,
+            latency_ms: elapsed.as_millis() as u64,
+            error_type,
+        });
+        let cutoff = now - Duration::hours(USAGE_WINDOW_HOURS);
+        while usage
+            .front()
+            .is_some_and(|record| record.at < cutoff || usage.len() > MAX_USAGE_RECORDS)
+        {
+            usage.pop_front();
+        }
+    }
+
+    /// Tool usage over the last hour and the last day
+    #[must_use]
+    pub fn usage_at(&self, now: DateTime<Utc>) -> UsageReport {
+        let usage = lock(&self.tool_usage);
+        UsageReport {
+            generated_at: now,
+            last_hour: UsageSummary::from_records(
+                usage.iter().filter(|r| r.at >= now - Duration::hours(1)),
+            ),
+            last_day: UsageSummary::from_records(
+                usage
+                    .iter()
+                    .filter(|r| r.at >= now - Duration::hours(USAGE_WINDOW_HOURS)),
+            ),
+        }
+    }
+
+    pub fn record_realtime_read(&self, fresh: bool) {
+        self.realtime_reads.fetch_add(1, Ordering::Relaxed);
+        if fresh {
+            self.realtime_reads_fresh.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_upstream_fetch(&self, success: bool, now: DateTime<Utc>) {
+        self.upstream_fetches.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.upstream_fetch_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut recent = lock(&self.upstream_recent);
+        recent.push_back((now, success));
+        let cutoff = now - Duration::minutes(UPSTREAM_WINDOW_MINUTES);
+        while recent.front().is_some_and(|&(at, _)| at < cutoff) {
+            recent.pop_front();
+        }
+    }
+
+    /// Note when the real-time snapshot being served was fetched
+    pub fn record_realtime_snapshot(&self, fetched_at: DateTime<Utc>) {
+        *lock(&self.realtime_snapshot_at) = Some(fetched_at);
+    }
+
+    /// When the real-time snapshot being served was fetched, if there is one yet
+    ///
+    /// Readable without the data client's lock, which upstream fetches hold.
+    #[must_use]
+    pub fn realtime_snapshot_at(&self) -> Option<DateTime<Utc>> {
+        *lock(&self.realtime_snapshot_at)
+    }
+
+    /// Note the version of the data now served
+    pub fn record_data_version(&self, version: DataVersion) {
+        *lock(&self.data_version) = Some(version);
+    }
+
+    /// The version of the data now served, readable without the data client's lock
+    #[must_use]
+    pub fn data_version(&self) -> Option<DataVersion> {
+        lock(&self.data_version).clone()
+    }
+
+    pub fn record_panic(&self) {
+        self.panics.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_upstream_shed(&self) {
+        self.upstream_shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_upstream_not_modified(&self) {
+        self.upstream_not_modified.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_station_merges(&self, merges: u64) {
+        self.station_merges.fetch_add(merges, Ordering::Relaxed);
+    }
+
+    /// Track the upstream circuit breaker; repeated calls with the same state are no-ops
+    pub fn record_breaker_state(&self, open: bool, now: DateTime<Utc>) {
+        let mut open_since = lock(&self.breaker_open_since);
+        match (*open_since, open) {
+            (None, true) => *open_since = Some(now),
+            (Some(since), false) => {
+                let open_ms = (now - since).num_milliseconds().max(0) as u64;
+                self.breaker_open_ms.fetch_add(open_ms, Ordering::Relaxed);
+                *open_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot_at(Utc::now())
+    }
+
+    #[must_use]
+    pub fn snapshot_at(&self, now: DateTime<Utc>) -> MetricsSnapshot {
+        let cutoff = now - Duration::minutes(UPSTREAM_WINDOW_MINUTES);
+        let (attempts, successes) = lock(&self.upstream_recent)
+            .iter()
+            .filter(|&&(at, _)| at >= cutoff)
+            .fold((0u64, 0u64), |(attempts, successes), &(_, success)| {
+                (attempts + 1, successes + u64::from(success))
+            });
+
+        let open_since = *lock(&self.breaker_open_since);
+        let current_open_ms =
+            open_since.map_or(0, |since| (now - since).num_milliseconds().max(0) as u64);
+
+        MetricsSnapshot {
+            realtime_refreshes: self.realtime_refreshes.load(Ordering::Relaxed),
+            upstream_frozen_alarms: self.upstream_frozen_alarms.load(Ordering::Relaxed),
+            unknown_freshness_records: self.unknown_freshness_records.load(Ordering::Relaxed),
+            tool_calls: self.tool_calls.load(Ordering::Relaxed),
+            tool_calls_within_slo: self.tool_calls_within_slo.load(Ordering::Relaxed),
+            realtime_reads: self.realtime_reads.load(Ordering::Relaxed),
+            realtime_reads_fresh: self.realtime_reads_fresh.load(Ordering::Relaxed),
+            upstream_fetches: self.upstream_fetches.load(Ordering::Relaxed),
+            upstream_fetch_failures: self.upstream_fetch_failures.load(Ordering::Relaxed),
+            upstream_shed: self.upstream_shed.load(Ordering::Relaxed),
+            upstream_not_modified: self.upstream_not_modified.load(Ordering::Relaxed),
+            station_merges: self.station_merges.load(Ordering::Relaxed),
+            upstream_success_ratio_5m: ratio(successes, attempts),
+            breaker_open: open_since.is_some(),
+            breaker_open_seconds: (self.breaker_open_ms.load(Ordering::Relaxed) + current_open_ms)
+                / 1000,
+            panics: self.panics.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl MetricsSnapshot {
+    /// Share of tool calls answered within [`TOOL_CALL_SLO_MS`]
+    #[must_use]
+    pub fn tool_call_latency_ratio(&self) -> Option<f64> {
+        ratio(self.tool_calls_within_slo, self.tool_calls)
+    }
+
+    /// Share of real-time reads served from fresh data
+    #[must_use]
+    pub fn fresh_data_ratio(&self) -> Option<f64> {
+        ratio(self.realtime_reads_fresh, self.realtime_reads)
+    }
+
+    /// Render in the Prometheus text exposition format
+    ///
+    /// Ratios without any observation yet are left out rather than reported as 0.
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "velib_realtime_refreshes_total",
+                "Real-time snapshots observed",
+                self.realtime_refreshes,
+            ),
+            (
+                "velib_upstream_frozen_alarms_total",
+                "Frozen upstream feed alarms raised",
+                self.upstream_frozen_alarms,
+            ),
+            (
+                "velib_unknown_freshness_records_total",
+                "Real-time records without a parseable duedate",
+                self.unknown_freshness_records,
+            ),
+            (
+                "velib_tool_calls_total",
+                "MCP tool calls handled",
+                self.tool_calls,
+            ),
+            (
+                "velib_tool_calls_within_slo_total",
+                "MCP tool calls answered within 500 ms",
+                self.tool_calls_within_slo,
+            ),
+            (
+                "velib_realtime_reads_total",
+                "Real-time data reads",
+                self.realtime_reads,
+            ),
+            (
+                "velib_realtime_reads_fresh_total",
+                "Real-time data reads served from data under 5 minutes old",
+                self.realtime_reads_fresh,
+            ),
+            (
+                "velib_upstream_fetches_total",
+                "Upstream API fetches attempted",
+                self.upstream_fetches,
+            ),
+            (
+                "velib_upstream_fetch_failures_total",
+                "Upstream API fetches that failed",
+                self.upstream_fetch_failures,
+            ),
+            (
+                "velib_upstream_not_modified_total",
+                "Upstream pages revalidated with 304 Not Modified",
+                self.upstream_not_modified,
+            ),
+            (
+                "velib_station_merges_total",
+                "Duplicate reference station entries merged at ingestion",
+                self.station_merges,
+            ),
+            (
+                "velib_breaker_open_seconds_total",
+                "Time the upstream circuit breaker has spent open",
+                self.breaker_open_seconds,
+            ),
+            (
+                "velib_panics_total",
+                "Requests whose handler panicked",
+                self.panics,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+            );
+        }
+
+        let gauges = [
+            (
+                "velib_slo_tool_call_latency_ratio",
+                "Share of tool calls answered within 500 ms",
+                self.tool_call_latency_ratio(),
+            ),
+            (
+                "velib_slo_fresh_data_ratio",
+                "Share of real-time reads served from fresh data",
+                self.fresh_data_ratio(),
+            ),
+            (
+                "velib_slo_upstream_success_ratio_5m",
+                "Share of upstream fetches that succeeded over the last 5 minutes",
+                self.upstream_success_ratio_5m,
+            ),
+            (
+                "velib_breaker_open",
+                "1 while the upstream circuit breaker is open",
+                Some(f64::from(u8::from(self.breaker_open))),
+            ),
+        ];
+        for (name, help, value) in gauges {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Tool usage served by `velib://stats/usage`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub generated_at: DateTime<Utc>,
+    pub last_hour: UsageSummary,
+    pub last_day: UsageSummary,
+}
+
+/// Tool calls over one window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub total_calls: u64,
+    pub total_errors: u64,
+    /// Busiest tools first
+    pub tools: Vec<ToolUsage>,
+    /// Most frequent error types first, at most 5
+    pub top_errors: Vec<ErrorCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub median_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCount {
+    pub error_type: String,
+    pub count: u64,
+}
+
+impl UsageSummary {
+    fn from_records<'a>(records: impl Iterator<Item = &'a ToolCallRecord>) -> Self {
+        let mut latencies: HashMap<&str, Vec<u64>> = HashMap::new();
+        let mut tool_errors: HashMap<&str, u64> = HashMap::new();
+        let mut error_types: HashMap<&str, u64> = HashMap::new();
+        for record in records {
+            latencies
+                .entry(record.tool)
+                .or_default()
+                .push(record.latency_ms);
+            if let Some(error_type) = record.error_type {
+                *tool_errors.entry(record.tool).or_default() += 1;
+                *error_types.entry(error_type).or_default() += 1;
+            }
+        }
+
+        let mut tools: Vec<ToolUsage> = latencies
+            .into_iter()
+            .map(|(tool, mut latencies)| {
+                latencies.sort_unstable();
+                ToolUsage {
+                    tool: tool.to_string(),
+                    calls: latencies.len() as u64,
+                    errors: tool_errors.get(tool).copied().unwrap_or(0),
+                    median_latency_ms: latencies[latencies.len() / 2],
+                }
+            })
+            .collect();
+        tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+
+        let mut top_errors: Vec<ErrorCount> = error_types
+            .into_iter()
+            .map(|(error_type, count)| ErrorCount {
+                error_type: error_type.to_string(),
+                count,
+            })
+            .collect();
+        top_errors.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.error_type.cmp(&b.error_type))
+        });
+        top_errors.truncate(TOP_ERROR_TYPES);
+
+        Self {
+            total_calls: tools.iter().map(|tool| tool.calls).sum(),
+            total_errors: tools.iter().map(|tool| tool.errors).sum(),
+            tools,
+            top_errors,
+        }
+    }
+}
+
+fn ratio(part: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64)
+}
+
+// Metrics must keep working even if a panicking thread poisoned a lock
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slo_ratios_and_windows() {
+        let metrics = Metrics::new();
+        let now = Utc::now();
+
+        metrics.record_tool_call(
+            "plan_bike_journey",
+            std::time::Duration::from_millis(20),
+            None,
+            now,
+        );
+        metrics.record_tool_call(
+            "plan_bike_journey",
+            std::time::Duration::from_millis(900),
+            None,
+            now,
+        );
+        metrics.record_upstream_fetch(false, now - Duration::minutes(10));
+        metrics.record_upstream_fetch(true, now - Duration::minutes(1));
+        metrics.record_upstream_fetch(false, now);
+
+        metrics.record_breaker_state(true, now - Duration::seconds(90));
+        metrics.record_breaker_state(true, now - Duration::seconds(60));
+        metrics.record_breaker_state(false, now - Duration::seconds(30));
+        metrics.record_breaker_state(true, now - Duration::seconds(10));
+
+        let snapshot = metrics.snapshot_at(now);
+        assert_eq!(snapshot.tool_call_latency_ratio(), Some(0.5));
+        assert_eq!(snapshot.fresh_data_ratio(), None);
+        assert_eq!(snapshot.upstream_fetches, 3);
+        assert_eq!(snapshot.upstream_success_ratio_5m, Some(0.5));
+        assert!(snapshot.breaker_open);
+        assert_eq!(snapshot.breaker_open_seconds, 70);
+    }
+
+    #[test]
+    fn test_prometheus_output_skips_empty_ratios() {
+        let metrics = Metrics::new();
+        metrics.record_realtime_read(true);
+
+        let text = metrics.snapshot().to_prometheus();
+        assert!(text.contains("# TYPE velib_tool_calls_total counter\nvelib_tool_calls_total 0\n"));
+        assert!(text.contains("velib_slo_fresh_data_ratio 1\n"));
+        assert!(!text.contains("velib_slo_tool_call_latency_ratio"));
+        assert!(text.contains("velib_breaker_open 0\n"));
+    }
+
+    #[test]
+    fn test_usage_report_windows_and_medians() {
+        let metrics = Metrics::new();
+        let now = Utc::now();
+        let ms = std::time::Duration::from_millis;
+
+        metrics.record_tool_call(
+            "get_station_by_code",
+            ms(40),
+            None,
+            now - Duration::hours(30),
+        );
+        metrics.record_tool_call(
+            "get_station_by_code",
+            ms(10),
+            None,
+            now - Duration::hours(3),
+        );
+        for latency in [5, 80, 20] {
+            metrics.record_tool_call("find_nearby_stations", ms(latency), None, now);
+        }
+        metrics.record_tool_call(
+            "find_nearby_stations",
+            ms(1),
+            Some("invalid_coordinates"),
+            now - Duration::minutes(10),
+        );
+
+        let report = metrics.usage_at(now);
+        assert_eq!(report.last_hour.total_calls, 4);
+        assert_eq!(report.last_hour.tools.len(), 1);
+        let nearby = &report.last_hour.tools[0];
+        assert_eq!((nearby.calls, nearby.errors), (4, 1));
+        assert_eq!(nearby.median_latency_ms, 20);
+        assert_eq!(
+            report.last_hour.top_errors,
+            vec![ErrorCount {
+                error_type: "invalid_coordinates".to_string(),
+                count: 1
+            }]
+        );
+
+        // The call from 30 hours ago has been forgotten
+        assert_eq!(report.last_day.total_calls, 5);
+        assert_eq!(report.last_day.tools[1].tool, "get_station_by_code");
+        assert_eq!(report.last_day.tools[1].median_latency_ms, 10);
+    }
+}
@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static TIMINGS: RefCell<PhaseTimings>;
+}
+
+/// Where a tool call's time goes, besides the tool's own work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Response cache and data cache lookups and stores
+    Cache,
+    /// Upstream API requests, up to the parsed body
+    Upstream,
+    /// Encoding and presenting results
+    Serialization,
+}
+
+/// Time spent in each [`Phase`] during a measured call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub cache: Duration,
+    pub upstream: Duration,
+    pub serialization: Duration,
+}
+
+impl PhaseTimings {
+    /// The part of a call lasting `total` that took longest: a [`Phase`] by
+    /// its lowercase name, or `compute` for the time outside all of them
+    #[must_use]
+    pub fn dominant(&self, total: Duration) -> &'static str {
+        let compute = total.saturating_sub(self.cache + self.upstream + self.serialization);
+        [
+            ("cache", self.cache),
+            ("upstream", self.upstream),
+            ("serialization", self.serialization),
+            ("compute", compute),
+        ]
+        .into_iter()
+        .max_by_key(|&(_, spent)| spent)
+        .map_or("compute", |(phase, _)| phase)
+    }
+
+    fn add(&mut self, phase: Phase, elapsed: Duration) {
+        match phase {
+            Phase::Cache => self.cache += elapsed,
+            Phase::Upstream => self.upstream += elapsed,
+            Phase::Serialization => self.serialization += elapsed,
+        }
+    }
+}
+
+/// Run `future`, collecting the phases timed within it on the same task
+pub async fn measure<F: Future>(future: F) -> (F::Output, PhaseTimings) {
+    TIMINGS
+        .scope(RefCell::default(), async {
+            let output = future.await;
+            (output, TIMINGS.with(|timings| *timings.borrow()))
+        })
+        .await
+}
+
+/// Add `elapsed` to `phase` of the call being measured; a no-op outside [`measure`]
+pub fn record(phase: Phase, elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| timings.borrow_mut().add(phase, elapsed));
+}
+
+/// Run `operation`, timing it as `phase`
+pub fn time<T>(phase: Phase, operation: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let output = operation();
+    record(phase, started.elapsed());
+    output
+}
+
+/// Await `future`, timing it as `phase`
+pub async fn time_async<F: Future>(phase: Phase, future: F) -> F::Output {
+    let started = Instant::now();
+    let output = future.await;
+    record(phase, started.elapsed());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_phases_add_up_within_a_measured_call() {
+        let ((), timings) = measure(async {
+            record(Phase::Upstream, Duration::from_millis(900));
+            time_async(Phase::Upstream, async {}).await;
+            record(Phase::Cache, Duration::from_millis(5));
+            time(Phase::Serialization, || ());
+        })
+        .await;
+        assert!(timings.upstream >= Duration::from_millis(900));
+        assert_eq!(timings.cache, Duration::from_millis(5));
+        assert_eq!(timings.dominant(Duration::from_secs(1)), "upstream");
+        assert_eq!(timings.dominant(Duration::from_secs(3)), "compute");
+
+        // Outside a measured call nothing is collected, and nothing fails
+        record(Phase::Cache, Duration::from_secs(1));
+    }
+}
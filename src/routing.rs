@@ -0,0 +1,310 @@
+use crate::geometry::decode_polyline;
+use crate::types::Coordinates;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Multiplier on straight-line distances when no routing backend answers
+///
+/// 1.0 keeps crow-flies distances; around 1.3 matches the Paris street grid.
+pub const DEFAULT_DETOUR_FACTOR: f64 = 1.0;
+
+// Destinations asked per request, within the default OSRM table limit
+const MAX_TARGETS_PER_REQUEST: usize = 99;
+const ROUTING_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Street-network routing engine behind [`RoutingBackend`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoutingEngine {
+    /// OSRM `table` service with a foot profile
+    #[default]
+    Osrm,
+    /// Valhalla `sources_to_targets` with pedestrian costing
+    Valhalla,
+}
+
+impl FromStr for RoutingEngine {
+    type Err = Error;
+
+    fn from_str(engine: &str) -> Result<Self> {
+        match engine.trim().to_ascii_lowercase().as_str() {
+            "osrm" => Ok(Self::Osrm),
+            "valhalla" => Ok(Self::Valhalla),
+            other => Err(Error::Config(format!(
+                "Unknown routing engine '{other}', expected osrm or valhalla"
+            ))),
+        }
+    }
+}
+
+/// Walking distances over the street network from an external routing engine
+#[derive(Debug, Clone)]
+pub struct RoutingBackend {
+    engine: RoutingEngine,
+    url: String,
+    http: reqwest::Client,
+}
+
+impl RoutingBackend {
+    #[must_use]
+    pub fn new(engine: RoutingEngine, url: &str) -> Self {
+        Self {
+            engine,
+            url: url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::builder()
+                .timeout(ROUTING_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    #[must_use]
+    pub fn engine(&self) -> RoutingEngine {
+        self.engine
+    }
+
+    /// Meters walked from `from` to each of `targets`, in order
+    ///
+    /// `None` for targets the engine finds no path to. Large target lists are
+    /// split over several requests.
+    pub async fn walking_distances(
+        &self,
+        from: &Coordinates,
+        targets: &[Coordinates],
+    ) -> Result<Vec<Option<u32>>> {
+        let mut distances = Vec::with_capacity(targets.len());
+        for chunk in targets.chunks(MAX_TARGETS_PER_REQUEST) {
+            let answered = match self.engine {
+                RoutingEngine::Osrm => self.osrm_table(from, chunk).await?,
+                RoutingEngine::Valhalla => self.valhalla_matrix(from, chunk).await?,
+            };
+            if answered.len() != chunk.len() {
+                return Err(Error::Internal(anyhow::anyhow!(
+                    "Routing engine answered {} distances for {} targets",
+                    answered.len(),
+                    chunk.len()
+                )));
+            }
+            distances.extend(answered);
+        }
+        Ok(distances)
+    }
+
+    /// The path a bike would ride from `from` to `to`, both ends included
+    pub async fn cycling_route(
+        &self,
+        from: &Coordinates,
+        to: &Coordinates,
+    ) -> Result<Vec<Coordinates>> {
+        match self.engine {
+            RoutingEngine::Osrm => {
+                let url = format!(
+                    "{}/route/v1/bike/{},{};{},{}",
+                    self.url, from.longitude, from.latitude, to.longitude, to.latitude
+                );
+                let response: Value = self
+                    .http
+                    .get(url)
+                    .query(&[("overview", "full"), ("geometries", "geojson")])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                parse_osrm_route(&response)
+            }
+            RoutingEngine::Valhalla => {
+                let location =
+                    |point: &Coordinates| json!({"lat": point.latitude, "lon": point.longitude});
+                let body = json!({
+                    "locations": [location(from), location(to)],
+                    "costing": "bicycle",
+                });
+                let response: Value = self
+                    .http
+                    .post(format!("{}/route", self.url))
+                    .json(&body)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                parse_valhalla_route(&response)
+            }
+        }
+    }
+
+    async fn osrm_table(
+        &self,
+        from: &Coordinates,
+        targets: &[Coordinates],
+    ) -> Result<Vec<Option<u32>>> {
+        let coordinates: Vec<String> = std::iter::once(from)
+            .chain(targets)
+            .map(|point| format!("{},{}", point.longitude, point.latitude))
+            .collect();
+        let url = format!("{}/table/v1/foot/{}", self.url, coordinates.join(";"));
+        let response: Value = self
+            .http
+            .get(url)
+            .query(&[("sources", "0"), ("annotations", "distance")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_osrm_table(&response)
+    }
+
+    async fn valhalla_matrix(
+        &self,
+        from: &Coordinates,
+        targets: &[Coordinates],
+    ) -> Result<Vec<Option<u32>>> {
+        let location = |point: &Coordinates| json!({"lat": point.latitude, "lon": point.longitude});
+        let body = json!({
+            "sources": [location(from)],
+            "targets": targets.iter().map(location).collect::<Vec<_>>(),
+            "costing": "pedestrian",
+            "units": "kilometers",
+        });
+        let response: Value = self
+            .http
+            .post(format!("{}/sources_to_targets", self.url))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_valhalla_matrix(&response)
+    }
+}
+
+/// Distances from the first coordinate of an OSRM `table` answer, itself left out
+fn parse_osrm_table(response: &Value) -> Result<Vec<Option<u32>>> {
+    if response["code"] != "Ok" {
+        return Err(Error::Internal(anyhow::anyhow!(
+            "OSRM table failed: {}",
+            response["message"].as_str().unwrap_or("no message")
+        )));
+    }
+    let row = response["distances"][0]
+        .as_array()
+        .ok_or_else(|| Error::Internal(anyhow::anyhow!("OSRM table without distances")))?;
+    Ok(row
+        .iter()
+        .skip(1)
+        .map(|meters| meters.as_f64().map(|meters| meters.round() as u32))
+        .collect())
+}
+
+/// Distances in a Valhalla `sources_to_targets` answer for a single source
+fn parse_valhalla_matrix(response: &Value) -> Result<Vec<Option<u32>>> {
+    let row = response["sources_to_targets"][0]
+        .as_array()
+        .ok_or_else(|| Error::Internal(anyhow::anyhow!("Valhalla matrix without distances")))?;
+    Ok(row
+        .iter()
+        .map(|cell| {
+            cell["distance"]
+                .as_f64()
+                .map(|km| (km * 1000.0).round() as u32)
+        })
+        .collect())
+}
+
+/// The geometry of the first route of an OSRM `route` answer
+fn parse_osrm_route(response: &Value) -> Result<Vec<Coordinates>> {
+    if response["code"] != "Ok" {
+        return Err(Error::Internal(anyhow::anyhow!(
+            "OSRM route failed: {}",
+            response["message"].as_str().unwrap_or("no message")
+        )));
+    }
+    let positions = response["routes"][0]["geometry"]["coordinates"]
+        .as_array()
+        .ok_or_else(|| Error::Internal(anyhow::anyhow!("OSRM route without geometry")))?;
+    positions
+        .iter()
+        .map(
+            |position| match (position[0].as_f64(), position[1].as_f64()) {
+                (Some(longitude), Some(latitude)) => Ok(Coordinates::new(latitude, longitude)),
+                _ => Err(Error::Internal(anyhow::anyhow!(
+                    "OSRM route with an invalid position"
+                ))),
+            },
+        )
+        .collect()
+}
+
+/// The shape of a Valhalla `route` answer, its legs joined
+fn parse_valhalla_route(response: &Value) -> Result<Vec<Coordinates>> {
+    let legs = response["trip"]["legs"]
+        .as_array()
+        .ok_or_else(|| Error::Internal(anyhow::anyhow!("Valhalla route without legs")))?;
+    let mut route: Vec<Coordinates> = Vec::new();
+    for leg in legs {
+        let shape = leg["shape"]
+            .as_str()
+            .ok_or_else(|| Error::Internal(anyhow::anyhow!("Valhalla leg without shape")))?;
+        // Valhalla shapes are polyline6
+        let points = decode_polyline(shape, 6)?;
+        let skip = usize::from(route.last().is_some() && route.last() == points.first());
+        route.extend(points.into_iter().skip(skip));
+    }
+    Ok(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_engine_answers() {
+        let osrm = json!({"code": "Ok", "distances": [[0.0, 412.6, null]]});
+        assert_eq!(parse_osrm_table(&osrm).unwrap(), vec![Some(413), None]);
+        assert!(parse_osrm_table(&json!({"code": "TooBig", "message": "Too many"})).is_err());
+
+        let valhalla = json!({"sources_to_targets": [[
+            {"distance": 0.4126, "time": 300},
+            {"distance": null, "time": null}
+        ]]});
+        assert_eq!(
+            parse_valhalla_matrix(&valhalla).unwrap(),
+            vec![Some(413), None]
+        );
+    }
+
+    #[test]
+    fn test_parses_route_answers() {
+        let osrm = json!({"code": "Ok", "routes": [{"geometry": {
+            "type": "LineString",
+            "coordinates": [[2.3737, 48.8446], [2.3600, 48.8500], [2.3522, 48.8566]]
+        }}]});
+        let route = parse_osrm_route(&osrm).unwrap();
+        assert_eq!(route.len(), 3);
+        assert_eq!(route[2], Coordinates::new(48.8566, 2.3522));
+
+        let shape = crate::geometry::encode_polyline(&route, 6);
+        let valhalla = json!({"trip": {"legs": [{"shape": shape}]}});
+        assert_eq!(parse_valhalla_route(&valhalla).unwrap(), route);
+        assert!(parse_valhalla_route(&json!({"trip": {}})).is_err());
+    }
+
+    #[test]
+    fn test_engine_names() {
+        assert_eq!(
+            " Valhalla ".parse::<RoutingEngine>().unwrap(),
+            RoutingEngine::Valhalla
+        );
+        assert_eq!(
+            "osrm".parse::<RoutingEngine>().unwrap(),
+            RoutingEngine::Osrm
+        );
+        assert!("graphhopper".parse::<RoutingEngine>().is_err());
+    }
+}
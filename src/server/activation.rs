@@ -0,0 +1,240 @@
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::io;
+use std::net::SocketAddr;
+use std::os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use super::AppConfig;
+use crate::mcp::McpServer;
+
+/// First descriptor systemd passes sockets from
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Descriptor of the listener handed over by a restarting server
+const REEXEC_FD_VAR: &str = "VELIB_LISTEN_FD";
+
+/// Longest in-flight requests are waited for before stopping or restarting anyway
+pub const DRAIN_SECONDS: u64 = 10;
+
+/// The listening socket passed by systemd socket activation (`LISTEN_FDS`) or
+/// by the server this process replaced on `SIGUSR2`
+#[derive(Debug)]
+pub struct InheritedSocket(RawFd);
+
+impl InheritedSocket {
+    /// The socket this process was started with, if any
+    ///
+    /// The variables are cleared so processes started later don't claim the
+    /// socket. Changing the environment is only sound while no other thread
+    /// runs, so call this first thing in `main`, before starting the runtime.
+    #[must_use]
+    pub fn take() -> Option<Self> {
+        let reexec_fd = std::env::var(REEXEC_FD_VAR).ok();
+        let listen_pid = std::env::var("LISTEN_PID").ok();
+        let listen_fds = std::env::var("LISTEN_FDS").ok();
+        for name in [REEXEC_FD_VAR, "LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+            std::env::remove_var(name);
+        }
+
+        if let Some(fd) = reexec_fd.and_then(|fd| fd.parse().ok()) {
+            return Some(Self(fd));
+        }
+        // Sockets are meant for the process systemd started, not its children
+        let ours = listen_pid.is_some_and(|pid| pid.parse() == Ok(std::process::id()));
+        let count: u32 = listen_fds.and_then(|fds| fds.parse().ok()).unwrap_or(0);
+        if !ours || count == 0 {
+            return None;
+        }
+        if count > 1 {
+            warn!("systemd passed {} sockets, serving the first", count);
+        }
+        Some(Self(SD_LISTEN_FDS_START))
+    }
+
+    /// Take ownership of the socket, inside the runtime
+    pub fn into_listener(self) -> io::Result<TcpListener> {
+        // SAFETY: the descriptor was passed to this process for it to own
+        let listener = unsafe { std::net::TcpListener::from_raw_fd(self.0) };
+        set_close_on_exec(listener.as_raw_fd(), true)?;
+        listener.set_nonblocking(true)?;
+        TcpListener::from_std(listener)
+    }
+}
+
+/// Serve `app` until a signal: on `SIGTERM` or `SIGINT` stop once in-flight
+/// requests finish, on `SIGUSR2` then replace this process with a fresh copy
+/// that reads its whole configuration again and keeps accepting on the same socket;
+/// `stopped` runs once requests are drained, before either
+pub async fn serve_until_signal(
+    listener: TcpListener,
+    app: Router,
+    stopped: impl Future<Output = ()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The listener closes with `serve`, the handed-over copy stays open
+    let handover = listener.as_fd().try_clone_to_owned()?;
+    let mut restart = signal(SignalKind::user_defined2())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+    // The peer address feeds the IP allowlist
+    let serving = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let reexec = loop {
+            tokio::select! {
+                _ = restart.recv() => {
+                    // The new process would fail to start on a broken configuration
+                    match AppConfig::load() {
+                        Ok(_) => break true,
+                        Err(e) => warn!("Not restarting, configuration invalid: {}", e),
+                    }
+                }
+                _ = terminate.recv() => break false,
+                _ = interrupt.recv() => break false,
+            }
+        };
+        let _ = stop_tx.send(reexec);
+    })
+    .into_future();
+    tokio::pin!(serving);
+
+    let reexec = tokio::select! {
+        served = &mut serving => {
+            served?;
+            stopped.await;
+            return Ok(());
+        }
+        reexec = stop_rx => reexec.unwrap_or(false),
+    };
+    info!(
+        "{} received, draining requests",
+        if reexec { "SIGUSR2" } else { "Stop signal" }
+    );
+    if tokio::time::timeout(Duration::from_secs(DRAIN_SECONDS), serving)
+        .await
+        .is_err()
+    {
+        warn!("Requests still running after {}s", DRAIN_SECONDS);
+    }
+    stopped.await;
+    if reexec {
+        return Err(self::reexec(handover).into());
+    }
+    Ok(())
+}
+
+/// Apply the configuration again on every `SIGHUP`, keeping the current one
+/// of any server it can't be read for
+pub fn spawn_reload_on_hangup(servers: Vec<McpServer>) -> io::Result<JoinHandle<()>> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            for server in &servers {
+                if let Err(e) = server.reload().await {
+                    warn!("Configuration reload refused: {}", e);
+                }
+            }
+        }
+    }))
+}
+
+/// Replace this process with the same program and arguments, passing it `listener`
+fn reexec(listener: OwnedFd) -> io::Error {
+    let fd = listener.as_raw_fd();
+    if let Err(e) = set_close_on_exec(fd, false) {
+        return e;
+    }
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => return e,
+    };
+    info!("Re-executing {}", program.display());
+    std::process::Command::new(program)
+        .args(std::env::args_os().skip(1))
+        .env(REEXEC_FD_VAR, fd.to_string())
+        .exec()
+}
+
+fn set_close_on_exec(fd: RawFd, close: bool) -> io::Result<()> {
+    let flags = if close { libc::FD_CLOEXEC } else { 0 };
+    // SAFETY: only changes descriptor flags of a descriptor this process owns
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, flags) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// This process's id written to a file, removed when dropped
+///
+/// A restart in place keeps the process id, so the file stays valid across it.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        std::fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Cannot remove PID file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::fd::IntoRawFd;
+
+    #[test]
+    fn test_pid_file_lives_with_the_server() {
+        let path = std::env::temp_dir().join(format!("velib-{}.pid", uuid::Uuid::new_v4()));
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+        drop(pid_file);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_listener_is_taken_over() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let inherited = {
+            let _guard = crate::server::ENV_MUTEX.lock().unwrap();
+            assert!(InheritedSocket::take().is_none());
+
+            // As a restarting server hands it over
+            let handover = OwnedFd::from(listener);
+            set_close_on_exec(handover.as_raw_fd(), false).unwrap();
+            std::env::set_var(REEXEC_FD_VAR, handover.into_raw_fd().to_string());
+            let inherited = InheritedSocket::take().unwrap();
+            assert!(std::env::var(REEXEC_FD_VAR).is_err());
+            inherited
+        };
+
+        let inherited = inherited.into_listener().unwrap();
+        assert_eq!(inherited.local_addr().unwrap(), addr);
+        tokio::net::TcpStream::connect(addr).await.unwrap();
+        inherited.accept().await.unwrap();
+    }
+}
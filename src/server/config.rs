@@ -1,4 +1,600 @@
+use chrono::{DateTime, Utc};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::tunables::Tunables;
+use crate::data::catalog::DEFAULT_REFERENCE_REPORT_INTERVAL_HOURS;
+use crate::data::changes::ChangeRetention;
+use crate::data::history::{DEFAULT_HISTORY_COMPACTION_INTERVAL_SECONDS, HISTORY_RETENTION_HOURS};
+use crate::data::merge::DuplicatePolicy;
+use crate::data::probe::DEFAULT_PROBE_INTERVAL_SECONDS;
+use crate::data::scheduler::DEFAULT_REQUESTS_PER_MINUTE;
+use crate::mcp::dedup::DEFAULT_DEDUP_TTL_SECONDS;
+use crate::mcp::density::DEFAULT_GAP_DISTANCE_METERS;
+use crate::mcp::hardening::IpRange;
+use crate::mcp::journal::DEFAULT_JOURNAL_ENTRIES;
+use crate::mcp::oidc::OidcConfig;
+use crate::mcp::precision::{OutputPrecision, MAX_OUTPUT_DECIMALS};
+use crate::mcp::quota::QuotaLimits;
+use crate::mcp::registry::{ApiLifecycle, ToolSelection};
+use crate::mcp::resumption::DEFAULT_SESSION_GRACE_SECONDS;
+use crate::mcp::slow_calls::DEFAULT_SLOW_CALL_MS;
+use crate::routing::{RoutingEngine, DEFAULT_DETOUR_FACTOR};
+use crate::types::{AvailabilityThresholds, Coordinates, ServiceArea, WalkingSpeed};
+
+/// Application-level configuration shared by the MCP layer
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Optional TOML file extending or overriding the bundled landmark aliases
+    pub aliases_path: Option<PathBuf>,
+    /// Optional TOML file extending or overriding the bundled river and rail barriers
+    pub barriers_path: Option<PathBuf>,
+    /// Optional TOML file extending or overriding the bundled metro and RER stops
+    pub transit_path: Option<PathBuf>,
+    /// Optional JSON file persisting favorite stations across restarts
+    pub favorites_path: Option<PathBuf>,
+    /// Refresh real-time data in the background at this interval
+    pub poll_interval: Option<Duration>,
+    /// Minutes without a newer upstream `duedate` before the feed counts as frozen
+    pub feed_stale_after_minutes: Option<i64>,
+    /// Clamp out-of-range `limit`/`radius_meters` instead of rejecting the call
+    pub lenient_limits: bool,
+    /// Probe the upstream API for `velib://health` at this interval
+    pub health_probe_interval: Duration,
+    /// Answer identical tool calls within this window from one result (zero disables)
+    pub dedup_ttl: Duration,
+    /// How long a dropped WebSocket session can be resumed; zero drops it with the connection
+    pub session_grace: Duration,
+    /// Ceiling on Paris Open Data requests per minute, across all callers
+    pub upstream_requests_per_minute: u32,
+    /// Where query coordinates are accepted
+    pub service_area: ServiceArea,
+    /// Optional GeoJSON polygon further restricting the service area
+    pub service_area_path: Option<PathBuf>,
+    /// Announced deprecation and sunset of the current tool API, sent as HTTP headers
+    pub api_lifecycle: ApiLifecycle,
+    /// Tool calls allowed per API key and UTC day or month
+    pub quota_limits: QuotaLimits,
+    /// Optional JSON file persisting quota usage across restarts
+    pub quota_path: Option<PathBuf>,
+    /// Bearer token for the `/admin` endpoints, which are off without one
+    pub admin_token: Option<String>,
+    /// Hours of availability history kept per station
+    pub history_retention_hours: i64,
+    /// Trim and thin the availability history at this interval
+    pub history_compaction_interval: Duration,
+    /// Resident memory past which the history and caches are trimmed, unlimited when unset
+    pub memory_soft_limit_mb: Option<u64>,
+    /// Change log refreshes kept in memory for `diff_station_status`, and in storage after
+    pub change_retention: ChangeRetention,
+    /// Storage backend refreshes past the change log's memory retention spill to;
+    /// they are dropped without one
+    pub storage_url: Option<String>,
+    /// Re-validate the reference catalog for `velib://reports/reference-changes` at this interval
+    pub reference_report_interval: Duration,
+    /// Reference stations endpoint, Paris Open Data when unset
+    pub stations_url: Option<String>,
+    /// Real-time availability endpoint, Paris Open Data when unset
+    pub realtime_url: Option<String>,
+    /// Base URLs serving the same dataset paths, tried in order when the
+    /// endpoints above fail or their circuit breaker is open
+    pub upstream_mirrors: Vec<String>,
+    /// GBFS `station_information.json` station ids are read from, Velib Metropole's when unset
+    pub gbfs_url: Option<String>,
+    /// Which entry a station code listed twice in the reference dataset keeps
+    pub duplicate_stations: DuplicatePolicy,
+    /// Bearer keys allowed to use the MCP endpoints; anyone may when empty
+    pub api_keys: Vec<String>,
+    /// Identity provider whose JWT bearer tokens are also accepted on the MCP endpoints
+    pub oidc: Option<OidcConfig>,
+    /// Peers allowed to reach the HTTP server at all; anyone may when empty
+    pub allowed_ips: Vec<IpRange>,
+    /// Optional TOML file declaring tenants served alongside this configuration
+    pub tenants_path: Option<PathBuf>,
+    /// Where station availability turns `low` and `near_full`
+    pub availability_thresholds: AvailabilityThresholds,
+    /// Pace distances are converted to `walk_time_minutes` at
+    pub walking_speed: WalkingSpeed,
+    /// Absolute URL clients reach the server at, for links to its own pages
+    pub public_url: Option<String>,
+    /// Time limits overriding the tools' defaults; `default` applies to every tool
+    pub tool_timeouts: Vec<(String, Duration)>,
+    /// Tools this deployment serves, all by default
+    pub tools: ToolSelection,
+    /// File the server's process id is written to while it runs
+    pub pid_file: Option<PathBuf>,
+    /// Tool calls lasting at least this long are logged with where their time went;
+    /// never when `None`
+    pub slow_call_threshold: Option<Duration>,
+    /// Optional TOML file of tunables, read again on every reload
+    pub config_path: Option<PathBuf>,
+    /// Log filter in the `RUST_LOG` syntax
+    pub log_level: Option<String>,
+    /// Also serve the station lookups over gRPC here (built with `--features grpc`)
+    pub grpc_addr: Option<SocketAddr>,
+    /// Optional file recording the last requests, reported after a crash
+    pub journal_path: Option<PathBuf>,
+    /// PEM Ed25519 private key signing resource payloads
+    pub signing_key_path: Option<PathBuf>,
+    /// Requests the journal keeps
+    pub journal_entries: usize,
+    /// Routing engine endpoint answering street-network walking distances
+    pub routing_url: Option<String>,
+    /// API spoken at `routing_url`
+    pub routing_engine: RoutingEngine,
+    /// Multiplier on straight-line walking distances when no routing engine answers
+    pub walk_detour_factor: f64,
+    /// Distance to the nearest station beyond which `velib://coverage/gaps` reports a gap
+    pub coverage_gap_meters: u32,
+    /// Decimal places coordinates and other fractional numbers keep in tool results and resources
+    pub output_precision: OutputPrecision,
+    /// Serve a synthetic city of this many stations instead of Paris Open Data,
+    /// with the `simulation` feature
+    pub simulation_stations: Option<usize>,
+    /// Seed of the synthetic city, the same seed giving the same city
+    pub simulation_seed: u64,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            aliases_path: None,
+            barriers_path: None,
+            transit_path: None,
+            favorites_path: None,
+            poll_interval: None,
+            feed_stale_after_minutes: None,
+            lenient_limits: false,
+            health_probe_interval: Duration::from_secs(DEFAULT_PROBE_INTERVAL_SECONDS),
+            dedup_ttl: Duration::from_secs(DEFAULT_DEDUP_TTL_SECONDS),
+            session_grace: Duration::from_secs(DEFAULT_SESSION_GRACE_SECONDS),
+            upstream_requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            service_area: ServiceArea::paris(),
+            service_area_path: None,
+            api_lifecycle: ApiLifecycle::default(),
+            quota_limits: QuotaLimits::default(),
+            quota_path: None,
+            admin_token: None,
+            history_retention_hours: HISTORY_RETENTION_HOURS,
+            memory_soft_limit_mb: None,
+            history_compaction_interval: Duration::from_secs(
+                DEFAULT_HISTORY_COMPACTION_INTERVAL_SECONDS,
+            ),
+            change_retention: ChangeRetention::default(),
+            storage_url: None,
+            reference_report_interval: Duration::from_secs(
+                DEFAULT_REFERENCE_REPORT_INTERVAL_HOURS * 3600,
+            ),
+            stations_url: None,
+            realtime_url: None,
+            upstream_mirrors: Vec::new(),
+            gbfs_url: None,
+            duplicate_stations: DuplicatePolicy::default(),
+            api_keys: Vec::new(),
+            oidc: None,
+            allowed_ips: Vec::new(),
+            tenants_path: None,
+            availability_thresholds: AvailabilityThresholds::default(),
+            walking_speed: WalkingSpeed::default(),
+            public_url: None,
+            tool_timeouts: Vec::new(),
+            tools: ToolSelection::default(),
+            pid_file: None,
+            slow_call_threshold: Some(Duration::from_millis(DEFAULT_SLOW_CALL_MS)),
+            config_path: None,
+            log_level: None,
+            grpc_addr: None,
+            journal_path: None,
+            signing_key_path: None,
+            journal_entries: DEFAULT_JOURNAL_ENTRIES,
+            routing_url: None,
+            routing_engine: RoutingEngine::default(),
+            walk_detour_factor: DEFAULT_DETOUR_FACTOR,
+            coverage_gap_meters: DEFAULT_GAP_DISTANCE_METERS,
+            output_precision: OutputPrecision::default(),
+            simulation_stations: None,
+            simulation_seed: 0,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Read configuration from environment variables
+    ///
+    /// - `VELIB_ALIASES_PATH`: path to a landmark alias TOML file
+    /// - `VELIB_BARRIERS_PATH`: path to a river and rail barrier TOML file
+    /// - `VELIB_TRANSIT_PATH`: path to a metro and RER stop TOML file
+    /// - `VELIB_FAVORITES_PATH`: path to the favorite stations JSON file
+    /// - `VELIB_POLL_INTERVAL_SECS`: background real-time refresh interval
+    /// - `VELIB_FEED_STALE_AFTER_MINUTES`: frozen feed alarm threshold
+    /// - `VELIB_LENIENT_LIMITS`: `true` or `1` to clamp oversized limits
+    /// - `VELIB_HEALTH_PROBE_INTERVAL_SECS`: upstream health probe interval (default 60)
+    /// - `VELIB_UPSTREAM_RPM`: upstream requests per minute ceiling (default 120)
+    /// - `VELIB_DEDUP_TTL_SECS`: identical tool call deduplication window, 0 to disable (default 5)
+    /// - `VELIB_SESSION_GRACE_SECS`: how long a dropped WebSocket session can be resumed,
+    ///   0 to disable (default 300)
+    /// - `VELIB_SERVICE_CENTER`: `latitude,longitude` of the service area center (default Paris City Hall)
+    /// - `VELIB_SERVICE_RADIUS_KM`: maximum distance from the center (default 50)
+    /// - `VELIB_SERVICE_BOUNDS`: `south,west,north,east` box coordinates must fall in
+    /// - `VELIB_SERVICE_AREA_PATH`: GeoJSON polygon coordinates must also fall in
+    /// - `VELIB_API_DEPRECATED_AT`: RFC 3339 date the tool API was deprecated, sent as `Deprecation`
+    /// - `VELIB_API_SUNSET_AT`: RFC 3339 date the tool API stops being served, sent as `Sunset`
+    /// - `VELIB_QUOTA_DAILY` / `VELIB_QUOTA_MONTHLY`: tool calls allowed per API key (unlimited when unset)
+    /// - `VELIB_QUOTA_PATH`: path to the quota usage JSON file
+    /// - `VELIB_ADMIN_TOKEN`: bearer token enabling the `/admin` endpoints
+    /// - `VELIB_HISTORY_RETENTION_HOURS`: availability history kept per station (default 24)
+    /// - `VELIB_HISTORY_COMPACT_INTERVAL_SECS`: history compaction interval (default 600)
+    /// - `VELIB_MEMORY_SOFT_LIMIT_MB`: resident memory past which history and caches are trimmed
+    /// - `VELIB_CHANGE_RETENTION_HOURS`: change log refreshes kept in memory (default 2)
+    /// - `VELIB_CHANGE_LOG_MAX_ENTRIES`: most change log refreshes kept in memory (default 120)
+    /// - `VELIB_CHANGE_SPILL_HOURS`: spilled change log refreshes kept in storage (default 24)
+    /// - `VELIB_STORAGE_URL`: storage backend the change log spills to, `:memory:` or,
+    ///   with the `sqlite` feature, `sqlite://<path>`
+    /// - `VELIB_REFERENCE_REPORT_INTERVAL_HOURS`: reference catalog validation interval (default 24)
+    /// - `VELIB_STATIONS_URL` / `VELIB_REALTIME_URL`: upstream dataset endpoints (default Paris Open Data)
+    /// - `VELIB_UPSTREAM_MIRRORS`: comma-separated base URLs serving the same datasets, failed over to
+    /// - `VELIB_GBFS_URL`: GBFS `station_information.json` joined in `velib://stations/id-mapping`
+    /// - `VELIB_DUPLICATE_STATIONS`: `latest` (default) or `first`, the entry kept for a
+    ///   station code listed twice in the reference dataset
+    /// - `VELIB_API_KEYS`: comma-separated bearer keys allowed to use the MCP endpoints
+    /// - `VELIB_OIDC_ISSUER`: OpenID Connect issuer whose JWTs are also accepted as bearer tokens
+    /// - `VELIB_OIDC_AUDIENCE`: audience those tokens must be issued for
+    /// - `VELIB_OIDC_SCOPES`: comma-separated scopes those tokens must grant
+    /// - `VELIB_OIDC_JWKS_URL`: the issuer's key set, instead of discovering it
+    /// - `VELIB_ALLOWED_IPS`: comma-separated addresses or CIDR blocks allowed to reach the server
+    /// - `VELIB_TENANTS_PATH`: path to a TOML file of tenants selected by host or path prefix
+    /// - `VELIB_LABEL_LOW_BELOW` / `VELIB_LABEL_NEAR_FULL_FROM`: share of usable docks holding a
+    ///   bike below which a station is `low` (default 0.25) and from which it is `near_full` (default 0.75)
+    /// - `VELIB_WALKING_SPEED_KMH`: walking pace behind `walk_time_minutes` (default 4.8)
+    /// - `VELIB_PUBLIC_URL`: absolute URL the server is reached at, enabling `page` journey links
+    /// - `VELIB_TOOL_TIMEOUTS`: comma-separated `tool=seconds` time limits, `default=seconds` for all tools
+    /// - `VELIB_TOOLS_ENABLED` / `VELIB_TOOLS_DISABLED`: comma-separated tools to serve only, or never
+    /// - `VELIB_PID_FILE`: path the process id is written to while serving
+    /// - `VELIB_SLOW_CALL_MS`: tool calls lasting this long are logged with their slowest phase,
+    ///   0 to disable (default 2000)
+    /// - `VELIB_CONFIG_PATH`: path to a TOML file of tunables, see [`AppConfig::load`]
+    /// - `RUST_LOG`: log filter
+    /// - `VELIB_GRPC_ADDR`: `ip:port` to serve gRPC on, with the `grpc` feature
+    /// - `VELIB_JOURNAL_PATH`: path to the request journal, read back after a crash
+    /// - `VELIB_JOURNAL_ENTRIES`: requests the journal keeps (default 128)
+    /// - `VELIB_SIGNING_KEY_PATH`: PEM Ed25519 private key signing resource payloads
+    /// - `VELIB_ROUTING_URL`: OSRM or Valhalla endpoint for street-network walking distances
+    /// - `VELIB_ROUTING_ENGINE`: `osrm` (default) or `valhalla`
+    /// - `VELIB_WALK_DETOUR_FACTOR`: multiplier on straight-line walks without a routing engine,
+    ///   at least 1 (default 1.0)
+    /// - `VELIB_COVERAGE_GAP_METERS`: distance to the nearest station making a coverage gap (default 300)
+    /// - `VELIB_COORDINATE_DECIMALS`: decimal places of coordinates in responses, at most 15 (default 6)
+    /// - `VELIB_OUTPUT_DECIMALS`: decimal places of rates, scores and other fractional numbers
+    ///   in responses, at most 15 (default 3)
+    /// - `VELIB_SIMULATION_STATIONS`: stations of a synthetic city served instead of live data,
+    ///   with the `simulation` feature
+    /// - `VELIB_SIMULATION_SEED`: seed of the synthetic city (default 0)
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self {
+            aliases_path: std::env::var_os("VELIB_ALIASES_PATH").map(PathBuf::from),
+            barriers_path: std::env::var_os("VELIB_BARRIERS_PATH").map(PathBuf::from),
+            transit_path: std::env::var_os("VELIB_TRANSIT_PATH").map(PathBuf::from),
+            favorites_path: std::env::var_os("VELIB_FAVORITES_PATH").map(PathBuf::from),
+            poll_interval: std::env::var("VELIB_POLL_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            feed_stale_after_minutes: std::env::var("VELIB_FEED_STALE_AFTER_MINUTES")
+                .ok()
+                .and_then(|minutes| minutes.parse().ok()),
+            lenient_limits: std::env::var("VELIB_LENIENT_LIMITS")
+                .is_ok_and(|value| matches!(value.trim(), "1" | "true")),
+            health_probe_interval: std::env::var("VELIB_HEALTH_PROBE_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map_or(
+                    Duration::from_secs(DEFAULT_PROBE_INTERVAL_SECONDS),
+                    Duration::from_secs,
+                ),
+            dedup_ttl: std::env::var("VELIB_DEDUP_TTL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .map_or(
+                    Duration::from_secs(DEFAULT_DEDUP_TTL_SECONDS),
+                    Duration::from_secs,
+                ),
+            session_grace: std::env::var("VELIB_SESSION_GRACE_SECS")
+                .ok()
+                .and_then(|secs| secs.trim().parse().ok())
+                .map_or(
+                    Duration::from_secs(DEFAULT_SESSION_GRACE_SECONDS),
+                    Duration::from_secs,
+                ),
+            upstream_requests_per_minute: std::env::var("VELIB_UPSTREAM_RPM")
+                .ok()
+                .and_then(|rpm| rpm.parse().ok())
+                .filter(|&rpm| rpm > 0)
+                .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE),
+            service_area: service_area_from_env(),
+            service_area_path: std::env::var_os("VELIB_SERVICE_AREA_PATH").map(PathBuf::from),
+            api_lifecycle: ApiLifecycle {
+                deprecated_at: env_date("VELIB_API_DEPRECATED_AT"),
+                sunset_at: env_date("VELIB_API_SUNSET_AT"),
+            },
+            quota_limits: QuotaLimits {
+                daily: env_quota("VELIB_QUOTA_DAILY"),
+                monthly: env_quota("VELIB_QUOTA_MONTHLY"),
+            },
+            quota_path: std::env::var_os("VELIB_QUOTA_PATH").map(PathBuf::from),
+            admin_token: std::env::var("VELIB_ADMIN_TOKEN")
+                .ok()
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
+            history_retention_hours: std::env::var("VELIB_HISTORY_RETENTION_HOURS")
+                .ok()
+                .and_then(|hours| hours.parse().ok())
+                .filter(|&hours| hours > 0)
+                .unwrap_or(HISTORY_RETENTION_HOURS),
+            history_compaction_interval: std::env::var("VELIB_HISTORY_COMPACT_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map_or(
+                    Duration::from_secs(DEFAULT_HISTORY_COMPACTION_INTERVAL_SECONDS),
+                    Duration::from_secs,
+                ),
+            memory_soft_limit_mb: std::env::var("VELIB_MEMORY_SOFT_LIMIT_MB")
+                .ok()
+                .and_then(|mb| mb.trim().parse().ok())
+                .filter(|&mb| mb > 0),
+            change_retention: change_retention_from_env(),
+            storage_url: env_url("VELIB_STORAGE_URL"),
+            reference_report_interval: std::env::var("VELIB_REFERENCE_REPORT_INTERVAL_HOURS")
+                .ok()
+                .and_then(|hours| hours.parse::<u64>().ok())
+                .filter(|&hours| hours > 0)
+                .map_or(
+                    Duration::from_secs(DEFAULT_REFERENCE_REPORT_INTERVAL_HOURS * 3600),
+                    |hours| Duration::from_secs(hours * 3600),
+                ),
+            stations_url: env_url("VELIB_STATIONS_URL"),
+            realtime_url: env_url("VELIB_REALTIME_URL"),
+            upstream_mirrors: env_list("VELIB_UPSTREAM_MIRRORS").unwrap_or_default(),
+            gbfs_url: env_url("VELIB_GBFS_URL"),
+            duplicate_stations: std::env::var("VELIB_DUPLICATE_STATIONS")
+                .ok()
+                .and_then(|policy| policy.parse().ok())
+                .unwrap_or_default(),
+            api_keys: std::env::var("VELIB_API_KEYS")
+                .map(|keys| {
+                    keys.split(',')
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            oidc: env_url("VELIB_OIDC_ISSUER").map(|issuer| OidcConfig {
+                issuer,
+                audience: env_url("VELIB_OIDC_AUDIENCE"),
+                required_scopes: env_list("VELIB_OIDC_SCOPES").unwrap_or_default(),
+                jwks_url: env_url("VELIB_OIDC_JWKS_URL"),
+            }),
+            allowed_ips: allowed_ips_from_env(),
+            tenants_path: std::env::var_os("VELIB_TENANTS_PATH").map(PathBuf::from),
+            availability_thresholds: availability_thresholds_from_env(),
+            walking_speed: env_floats("VELIB_WALKING_SPEED_KMH")
+                .filter(|&[kmh]| kmh > 0.0)
+                .map_or_else(WalkingSpeed::default, |[kmh]| WalkingSpeed { kmh }),
+            public_url: env_url("VELIB_PUBLIC_URL"),
+            tool_timeouts: tool_timeouts_from_env(),
+            tools: ToolSelection {
+                enabled: env_list("VELIB_TOOLS_ENABLED"),
+                disabled: env_list("VELIB_TOOLS_DISABLED").unwrap_or_default(),
+            },
+            pid_file: std::env::var_os("VELIB_PID_FILE").map(PathBuf::from),
+            slow_call_threshold: std::env::var("VELIB_SLOW_CALL_MS")
+                .ok()
+                .and_then(|ms| ms.trim().parse().ok())
+                .map_or(Some(DEFAULT_SLOW_CALL_MS), |ms| {
+                    Some(ms).filter(|&ms| ms > 0)
+                })
+                .map(Duration::from_millis),
+            config_path: std::env::var_os("VELIB_CONFIG_PATH").map(PathBuf::from),
+            log_level: std::env::var("RUST_LOG").ok(),
+            grpc_addr: std::env::var("VELIB_GRPC_ADDR")
+                .ok()
+                .and_then(|addr| addr.trim().parse().ok()),
+            journal_path: std::env::var_os("VELIB_JOURNAL_PATH").map(PathBuf::from),
+            signing_key_path: std::env::var_os("VELIB_SIGNING_KEY_PATH").map(PathBuf::from),
+            journal_entries: std::env::var("VELIB_JOURNAL_ENTRIES")
+                .ok()
+                .and_then(|entries| entries.parse().ok())
+                .filter(|&entries| entries > 0)
+                .unwrap_or(DEFAULT_JOURNAL_ENTRIES),
+            routing_url: env_url("VELIB_ROUTING_URL"),
+            routing_engine: std::env::var("VELIB_ROUTING_ENGINE")
+                .ok()
+                .and_then(|engine| engine.parse().ok())
+                .unwrap_or_default(),
+            walk_detour_factor: env_floats("VELIB_WALK_DETOUR_FACTOR")
+                .filter(|&[factor]| factor >= 1.0)
+                .map_or(DEFAULT_DETOUR_FACTOR, |[factor]| factor),
+            coverage_gap_meters: std::env::var("VELIB_COVERAGE_GAP_METERS")
+                .ok()
+                .and_then(|meters| meters.trim().parse().ok())
+                .filter(|&meters| meters > 0)
+                .unwrap_or(DEFAULT_GAP_DISTANCE_METERS),
+            output_precision: output_precision_from_env(),
+            simulation_stations: std::env::var("VELIB_SIMULATION_STATIONS")
+                .ok()
+                .and_then(|stations| stations.trim().parse().ok())
+                .filter(|&stations| stations > 0),
+            simulation_seed: std::env::var("VELIB_SIMULATION_SEED")
+                .ok()
+                .and_then(|seed| seed.trim().parse().ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Read configuration from environment variables, then override it with
+    /// the [`Tunables`] file at `VELIB_CONFIG_PATH`, if set
+    ///
+    /// This is what a reload reads again.
+    pub fn load() -> crate::Result<Self> {
+        let mut config = Self::from_env();
+        if let Some(path) = &config.config_path {
+            Tunables::load(path)?.apply(&mut config)?;
+        }
+        Ok(config)
+    }
+}
+
+/// Override the default label thresholds, keeping the defaults if the result is inconsistent
+fn availability_thresholds_from_env() -> AvailabilityThresholds {
+    let mut thresholds = AvailabilityThresholds::default();
+    if let Some([share]) = env_floats("VELIB_LABEL_LOW_BELOW") {
+        thresholds.low_below = share;
+    }
+    if let Some([share]) = env_floats("VELIB_LABEL_NEAR_FULL_FROM") {
+        thresholds.near_full_from = share;
+    }
+    if thresholds.is_valid() {
+        thresholds
+    } else {
+        AvailabilityThresholds::default()
+    }
+}
+
+fn change_retention_from_env() -> ChangeRetention {
+    let hours = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|hours| hours.trim().parse::<i64>().ok())
+            .filter(|&hours| hours > 0)
+            .map(chrono::Duration::hours)
+    };
+    let defaults = ChangeRetention::default();
+    ChangeRetention {
+        memory: hours("VELIB_CHANGE_RETENTION_HOURS").unwrap_or(defaults.memory),
+        max_entries: std::env::var("VELIB_CHANGE_LOG_MAX_ENTRIES")
+            .ok()
+            .and_then(|entries| entries.trim().parse().ok())
+            .filter(|&entries| entries > 0)
+            .unwrap_or(defaults.max_entries),
+        spilled: hours("VELIB_CHANGE_SPILL_HOURS").unwrap_or(defaults.spilled),
+    }
+}
+
+/// Parse `tool=seconds` pairs, skipping malformed or non-positive ones
+fn tool_timeouts_from_env() -> Vec<(String, Duration)> {
+    let Ok(value) = std::env::var("VELIB_TOOL_TIMEOUTS") else {
+        return Vec::new();
+    };
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (tool, secs) = pair.split_once('=')?;
+            let secs: f64 = secs.trim().parse().ok().filter(|&secs: &f64| secs > 0.0)?;
+            let timeout = Duration::try_from_secs_f64(secs).ok()?;
+            Some((tool.trim().to_string(), timeout))
+        })
+        .collect()
+}
+
+/// Parse addresses and CIDR blocks, skipping malformed ones with a warning
+fn allowed_ips_from_env() -> Vec<IpRange> {
+    let Ok(value) = std::env::var("VELIB_ALLOWED_IPS") else {
+        return Vec::new();
+    };
+    value
+        .split(',')
+        .filter(|range| !range.trim().is_empty())
+        .filter_map(|range| {
+            range
+                .parse()
+                .map_err(|e| tracing::warn!("Ignoring VELIB_ALLOWED_IPS entry: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Override the Paris service area with whichever parts are configured
+fn service_area_from_env() -> ServiceArea {
+    let mut area = ServiceArea::paris();
+    if let Some([latitude, longitude]) = env_floats("VELIB_SERVICE_CENTER") {
+        area.center = Coordinates::new(latitude, longitude);
+    }
+    if let Some([radius_km]) = env_floats("VELIB_SERVICE_RADIUS_KM").filter(|&[km]| km > 0.0) {
+        area.radius_meters = radius_km * 1000.0;
+    }
+    if let Some([south, west, north, east]) = env_floats("VELIB_SERVICE_BOUNDS")
+        .filter(|&[south, west, north, east]| south < north && west < east)
+    {
+        area.south = south;
+        area.west = west;
+        area.north = north;
+        area.east = east;
+    }
+    area
+}
+
+/// Parse a comma-separated list of exactly `N` finite numbers
+fn env_floats<const N: usize>(name: &str) -> Option<[f64; N]> {
+    let value = std::env::var(name).ok()?;
+    let numbers: Vec<f64> = value
+        .split(',')
+        .map(|part| part.trim().parse().ok().filter(|n: &f64| n.is_finite()))
+        .collect::<Option<_>>()?;
+    numbers.try_into().ok()
+}
+
+/// Parse a comma-separated list, skipping empty entries
+fn output_precision_from_env() -> OutputPrecision {
+    let defaults = OutputPrecision::default();
+    let decimals = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|decimals| decimals.trim().parse().ok())
+            .filter(|&decimals| decimals <= MAX_OUTPUT_DECIMALS)
+    };
+    OutputPrecision {
+        coordinate_decimals: decimals("VELIB_COORDINATE_DECIMALS")
+            .unwrap_or(defaults.coordinate_decimals),
+        decimals: decimals("VELIB_OUTPUT_DECIMALS").unwrap_or(defaults.decimals),
+    }
+}
+
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn env_quota(name: &str) -> Option<u32> {
+    std::env::var(name)
+        .ok()
+        .and_then(|calls| calls.trim().parse().ok())
+        .filter(|&calls| calls > 0)
+}
+
+fn env_url(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+fn env_date(name: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(std::env::var(name).ok()?.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
 
 /// Parse server configuration from environment variables
 pub fn parse_server_address() -> Result<SocketAddr, String> {
@@ -17,11 +613,8 @@ pub fn parse_server_address() -> Result<SocketAddr, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::server::ENV_MUTEX;
     use std::env;
-    use std::sync::Mutex;
-
-    // Use a mutex to ensure tests don't interfere with each other
-    static ENV_MUTEX: Mutex<()> = Mutex::new(());
 
     #[test]
     fn test_default_address() {
@@ -139,4 +732,502 @@ mod tests {
         env::remove_var("IP");
         env::remove_var("PORT");
     }
+
+    #[test]
+    fn test_app_config_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::remove_var("VELIB_ALIASES_PATH");
+        env::remove_var("VELIB_BARRIERS_PATH");
+        env::remove_var("VELIB_TRANSIT_PATH");
+        env::remove_var("VELIB_FAVORITES_PATH");
+        env::remove_var("VELIB_POLL_INTERVAL_SECS");
+        env::remove_var("VELIB_FEED_STALE_AFTER_MINUTES");
+        env::remove_var("VELIB_LENIENT_LIMITS");
+        env::remove_var("VELIB_HEALTH_PROBE_INTERVAL_SECS");
+        env::remove_var("VELIB_DEDUP_TTL_SECS");
+        env::remove_var("VELIB_SESSION_GRACE_SECS");
+        env::remove_var("VELIB_UPSTREAM_RPM");
+        env::remove_var("VELIB_SERVICE_CENTER");
+        env::remove_var("VELIB_SERVICE_RADIUS_KM");
+        env::remove_var("VELIB_SERVICE_BOUNDS");
+        let config = AppConfig::from_env();
+        assert_eq!(config.upstream_requests_per_minute, 120);
+        assert_eq!(config.service_area, ServiceArea::paris());
+        assert!(config.service_area_path.is_none());
+        assert_eq!(config.dedup_ttl, Duration::from_secs(5));
+        assert_eq!(config.session_grace, Duration::from_secs(300));
+        assert!(!config.lenient_limits);
+        assert_eq!(config.health_probe_interval, Duration::from_secs(60));
+        assert!(config.aliases_path.is_none());
+        assert!(config.transit_path.is_none());
+        assert!(config.favorites_path.is_none());
+        assert!(config.poll_interval.is_none());
+        assert!(config.feed_stale_after_minutes.is_none());
+
+        env::set_var("VELIB_ALIASES_PATH", "/etc/velib/aliases.toml");
+        env::set_var("VELIB_BARRIERS_PATH", "/etc/velib/barriers.toml");
+        env::set_var("VELIB_TRANSIT_PATH", "/etc/velib/transit.toml");
+        env::set_var("VELIB_FAVORITES_PATH", "/var/lib/velib/favorites.json");
+        env::set_var("VELIB_POLL_INTERVAL_SECS", "60");
+        env::set_var("VELIB_FEED_STALE_AFTER_MINUTES", "20");
+        env::set_var("VELIB_LENIENT_LIMITS", "true");
+        env::set_var("VELIB_HEALTH_PROBE_INTERVAL_SECS", "300");
+        env::set_var("VELIB_DEDUP_TTL_SECS", "0");
+        env::set_var("VELIB_SESSION_GRACE_SECS", "0");
+        env::set_var("VELIB_UPSTREAM_RPM", "30");
+        env::set_var("VELIB_SERVICE_CENTER", "48.8462, 2.3447");
+        env::set_var("VELIB_SERVICE_RADIUS_KM", "5");
+        // Inverted boxes are ignored
+        env::set_var("VELIB_SERVICE_BOUNDS", "48.9,2.2,48.8,2.5");
+        let config = AppConfig::from_env();
+        assert_eq!(config.upstream_requests_per_minute, 30);
+        assert_eq!(
+            config.service_area,
+            ServiceArea {
+                center: Coordinates::new(48.8462, 2.3447),
+                radius_meters: 5_000.0,
+                ..ServiceArea::paris()
+            }
+        );
+        assert!(config.dedup_ttl.is_zero());
+        assert!(config.session_grace.is_zero());
+        assert!(config.lenient_limits);
+        assert_eq!(config.health_probe_interval, Duration::from_secs(300));
+        assert_eq!(config.poll_interval, Some(Duration::from_secs(60)));
+        assert_eq!(config.feed_stale_after_minutes, Some(20));
+        assert_eq!(
+            config.aliases_path,
+            Some(PathBuf::from("/etc/velib/aliases.toml"))
+        );
+        assert_eq!(
+            config.barriers_path,
+            Some(PathBuf::from("/etc/velib/barriers.toml"))
+        );
+        assert_eq!(
+            config.transit_path,
+            Some(PathBuf::from("/etc/velib/transit.toml"))
+        );
+        assert_eq!(
+            config.favorites_path,
+            Some(PathBuf::from("/var/lib/velib/favorites.json"))
+        );
+
+        env::remove_var("VELIB_ALIASES_PATH");
+        env::remove_var("VELIB_TRANSIT_PATH");
+        env::remove_var("VELIB_FAVORITES_PATH");
+        env::remove_var("VELIB_POLL_INTERVAL_SECS");
+        env::remove_var("VELIB_FEED_STALE_AFTER_MINUTES");
+        env::remove_var("VELIB_LENIENT_LIMITS");
+        env::remove_var("VELIB_HEALTH_PROBE_INTERVAL_SECS");
+        env::remove_var("VELIB_DEDUP_TTL_SECS");
+        env::remove_var("VELIB_SESSION_GRACE_SECS");
+        env::remove_var("VELIB_UPSTREAM_RPM");
+        env::remove_var("VELIB_SERVICE_CENTER");
+        env::remove_var("VELIB_SERVICE_RADIUS_KM");
+        env::remove_var("VELIB_SERVICE_BOUNDS");
+    }
+
+    #[test]
+    fn test_service_bounds_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_SERVICE_BOUNDS", "48.8,2.25,48.9,2.45");
+        env::set_var("VELIB_SERVICE_RADIUS_KM", "not a number");
+
+        let area = AppConfig::from_env().service_area;
+        assert_eq!(
+            (area.south, area.west, area.north, area.east),
+            (48.8, 2.25, 48.9, 2.45)
+        );
+        assert_eq!(area.radius_meters, 50_000.0);
+
+        env::remove_var("VELIB_SERVICE_BOUNDS");
+        env::remove_var("VELIB_SERVICE_RADIUS_KM");
+    }
+
+    #[test]
+    fn test_api_lifecycle_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_API_DEPRECATED_AT", "2026-03-01T00:00:00+01:00");
+        env::set_var("VELIB_API_SUNSET_AT", "next spring");
+
+        let lifecycle = AppConfig::from_env().api_lifecycle;
+        assert_eq!(
+            lifecycle.deprecated_at,
+            Some("2026-02-28T23:00:00Z".parse().unwrap())
+        );
+        assert_eq!(lifecycle.sunset_at, None);
+
+        env::remove_var("VELIB_API_DEPRECATED_AT");
+        env::remove_var("VELIB_API_SUNSET_AT");
+    }
+
+    #[test]
+    fn test_upstream_and_tenancy_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_REALTIME_URL", " http://fixtures.internal/realtime ");
+        env::set_var("VELIB_STATIONS_URL", "");
+        env::set_var(
+            "VELIB_UPSTREAM_MIRRORS",
+            "https://velib-proxy.example.org, ,https://mirror.example.net/paris",
+        );
+        env::set_var("VELIB_GBFS_URL", "http://fixtures.internal/gbfs.json");
+        env::set_var("VELIB_API_KEYS", "alpha, ,beta");
+        env::set_var("VELIB_TENANTS_PATH", "/etc/velib/tenants.toml");
+        env::set_var("VELIB_PID_FILE", "/run/velib-mcp.pid");
+        env::set_var("VELIB_GRPC_ADDR", "0.0.0.0:50051");
+        env::set_var("VELIB_JOURNAL_PATH", "/var/lib/velib/requests.journal");
+        env::set_var("VELIB_JOURNAL_ENTRIES", "0");
+        env::set_var("VELIB_SIGNING_KEY_PATH", "/etc/velib/signing.pem");
+        env::set_var("VELIB_DUPLICATE_STATIONS", "First");
+
+        let config = AppConfig::from_env();
+        assert_eq!(
+            config.realtime_url.as_deref(),
+            Some("http://fixtures.internal/realtime")
+        );
+        assert_eq!(config.stations_url, None);
+        assert_eq!(
+            config.upstream_mirrors,
+            vec![
+                "https://velib-proxy.example.org",
+                "https://mirror.example.net/paris"
+            ]
+        );
+        assert_eq!(
+            config.gbfs_url.as_deref(),
+            Some("http://fixtures.internal/gbfs.json")
+        );
+        assert_eq!(config.api_keys, vec!["alpha", "beta"]);
+        assert_eq!(
+            config.tenants_path,
+            Some(PathBuf::from("/etc/velib/tenants.toml"))
+        );
+        assert_eq!(config.pid_file, Some(PathBuf::from("/run/velib-mcp.pid")));
+        assert_eq!(config.grpc_addr, Some("0.0.0.0:50051".parse().unwrap()));
+        assert_eq!(
+            config.journal_path,
+            Some(PathBuf::from("/var/lib/velib/requests.journal"))
+        );
+        assert_eq!(config.journal_entries, DEFAULT_JOURNAL_ENTRIES);
+        assert_eq!(
+            config.signing_key_path,
+            Some(PathBuf::from("/etc/velib/signing.pem"))
+        );
+        assert_eq!(config.duplicate_stations, DuplicatePolicy::First);
+
+        env::remove_var("VELIB_REALTIME_URL");
+        env::remove_var("VELIB_STATIONS_URL");
+        env::remove_var("VELIB_UPSTREAM_MIRRORS");
+        env::remove_var("VELIB_GBFS_URL");
+        env::remove_var("VELIB_API_KEYS");
+        env::remove_var("VELIB_TENANTS_PATH");
+        env::remove_var("VELIB_PID_FILE");
+        env::remove_var("VELIB_GRPC_ADDR");
+        env::remove_var("VELIB_JOURNAL_PATH");
+        env::remove_var("VELIB_JOURNAL_ENTRIES");
+        env::remove_var("VELIB_SIGNING_KEY_PATH");
+        env::remove_var("VELIB_DUPLICATE_STATIONS");
+    }
+
+    #[test]
+    fn test_change_retention_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(
+            AppConfig::from_env().change_retention,
+            ChangeRetention::default()
+        );
+
+        env::set_var("VELIB_CHANGE_RETENTION_HOURS", "6");
+        env::set_var("VELIB_CHANGE_LOG_MAX_ENTRIES", "0");
+        env::set_var("VELIB_CHANGE_SPILL_HOURS", " 72 ");
+        env::set_var("VELIB_STORAGE_URL", ":memory:");
+        let config = AppConfig::from_env();
+        assert_eq!(
+            config.change_retention,
+            ChangeRetention {
+                memory: chrono::Duration::hours(6),
+                max_entries: ChangeRetention::default().max_entries,
+                spilled: chrono::Duration::hours(72),
+            }
+        );
+        assert_eq!(config.storage_url.as_deref(), Some(":memory:"));
+
+        env::remove_var("VELIB_CHANGE_RETENTION_HOURS");
+        env::remove_var("VELIB_CHANGE_LOG_MAX_ENTRIES");
+        env::remove_var("VELIB_CHANGE_SPILL_HOURS");
+        env::remove_var("VELIB_STORAGE_URL");
+    }
+
+    #[test]
+    fn test_oidc_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_OIDC_AUDIENCE", "velib-mcp");
+        assert_eq!(AppConfig::from_env().oidc, None);
+
+        env::set_var("VELIB_OIDC_ISSUER", " https://login.example.com/ ");
+        env::set_var("VELIB_OIDC_SCOPES", "velib.read, velib.write");
+        let config = AppConfig::from_env();
+        assert_eq!(
+            config.oidc,
+            Some(OidcConfig {
+                issuer: "https://login.example.com/".to_string(),
+                audience: Some("velib-mcp".to_string()),
+                required_scopes: vec!["velib.read".to_string(), "velib.write".to_string()],
+                jwks_url: None,
+            })
+        );
+
+        env::remove_var("VELIB_OIDC_ISSUER");
+        env::remove_var("VELIB_OIDC_AUDIENCE");
+        env::remove_var("VELIB_OIDC_SCOPES");
+    }
+
+    #[test]
+    fn test_quotas_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_QUOTA_DAILY", "500");
+        env::set_var("VELIB_QUOTA_MONTHLY", "0");
+        env::set_var("VELIB_ADMIN_TOKEN", "  ");
+
+        let config = AppConfig::from_env();
+        assert_eq!(
+            config.quota_limits,
+            QuotaLimits {
+                daily: Some(500),
+                monthly: None
+            }
+        );
+        assert_eq!(config.admin_token, None);
+
+        env::remove_var("VELIB_QUOTA_DAILY");
+        env::remove_var("VELIB_QUOTA_MONTHLY");
+        env::remove_var("VELIB_ADMIN_TOKEN");
+    }
+
+    #[test]
+    fn test_availability_thresholds_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_LABEL_LOW_BELOW", "0.1");
+        env::set_var("VELIB_LABEL_NEAR_FULL_FROM", "0.9");
+        let thresholds = AppConfig::from_env().availability_thresholds;
+        assert_eq!(
+            (thresholds.low_below, thresholds.near_full_from),
+            (0.1, 0.9)
+        );
+
+        // Crossed thresholds fall back to the defaults
+        env::set_var("VELIB_LABEL_LOW_BELOW", "0.95");
+        assert_eq!(
+            AppConfig::from_env().availability_thresholds,
+            AvailabilityThresholds::default()
+        );
+
+        env::remove_var("VELIB_LABEL_LOW_BELOW");
+        env::remove_var("VELIB_LABEL_NEAR_FULL_FROM");
+    }
+
+    #[test]
+    fn test_walking_speed_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("VELIB_WALKING_SPEED_KMH", "6");
+        assert_eq!(
+            AppConfig::from_env().walking_speed,
+            WalkingSpeed { kmh: 6.0 }
+        );
+
+        env::set_var("VELIB_WALKING_SPEED_KMH", "0");
+        assert_eq!(AppConfig::from_env().walking_speed, WalkingSpeed::default());
+        env::remove_var("VELIB_WALKING_SPEED_KMH");
+    }
+
+    #[test]
+    fn test_slow_call_threshold_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(
+            AppConfig::from_env().slow_call_threshold,
+            Some(Duration::from_millis(DEFAULT_SLOW_CALL_MS))
+        );
+        env::set_var("VELIB_SLOW_CALL_MS", "750");
+        assert_eq!(
+            AppConfig::from_env().slow_call_threshold,
+            Some(Duration::from_millis(750))
+        );
+        env::set_var("VELIB_SLOW_CALL_MS", "0");
+        assert_eq!(AppConfig::from_env().slow_call_threshold, None);
+        env::remove_var("VELIB_SLOW_CALL_MS");
+    }
+
+    #[test]
+    fn test_routing_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        let config = AppConfig::from_env();
+        assert!(config.routing_url.is_none());
+        assert_eq!(config.routing_engine, RoutingEngine::Osrm);
+        assert_eq!(config.walk_detour_factor, DEFAULT_DETOUR_FACTOR);
+
+        env::set_var("VELIB_ROUTING_URL", "http://valhalla:8002/");
+        env::set_var("VELIB_ROUTING_ENGINE", "Valhalla");
+        env::set_var("VELIB_WALK_DETOUR_FACTOR", "1.3");
+        let config = AppConfig::from_env();
+        assert_eq!(config.routing_url.as_deref(), Some("http://valhalla:8002/"));
+        assert_eq!(config.routing_engine, RoutingEngine::Valhalla);
+        assert_eq!(config.walk_detour_factor, 1.3);
+
+        // A factor below 1 would make walks shorter than the straight line
+        env::set_var("VELIB_WALK_DETOUR_FACTOR", "0.8");
+        assert_eq!(
+            AppConfig::from_env().walk_detour_factor,
+            DEFAULT_DETOUR_FACTOR
+        );
+
+        env::remove_var("VELIB_ROUTING_URL");
+        env::remove_var("VELIB_ROUTING_ENGINE");
+        env::remove_var("VELIB_WALK_DETOUR_FACTOR");
+    }
+
+    #[test]
+    fn test_coverage_gap_distance_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(
+            AppConfig::from_env().coverage_gap_meters,
+            DEFAULT_GAP_DISTANCE_METERS
+        );
+        env::set_var("VELIB_COVERAGE_GAP_METERS", "500");
+        assert_eq!(AppConfig::from_env().coverage_gap_meters, 500);
+        env::set_var("VELIB_COVERAGE_GAP_METERS", "0");
+        assert_eq!(
+            AppConfig::from_env().coverage_gap_meters,
+            DEFAULT_GAP_DISTANCE_METERS
+        );
+        env::remove_var("VELIB_COVERAGE_GAP_METERS");
+    }
+
+    #[test]
+    fn test_output_precision_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(
+            AppConfig::from_env().output_precision,
+            OutputPrecision::default()
+        );
+        env::set_var("VELIB_COORDINATE_DECIMALS", "5");
+        env::set_var("VELIB_OUTPUT_DECIMALS", "2");
+        assert_eq!(
+            AppConfig::from_env().output_precision,
+            OutputPrecision {
+                coordinate_decimals: 5,
+                decimals: 2,
+            }
+        );
+        env::set_var("VELIB_OUTPUT_DECIMALS", "16");
+        assert_eq!(AppConfig::from_env().output_precision.decimals, 3);
+        env::remove_var("VELIB_COORDINATE_DECIMALS");
+        env::remove_var("VELIB_OUTPUT_DECIMALS");
+    }
+
+    #[test]
+    fn test_reference_report_interval_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(
+            AppConfig::from_env().reference_report_interval,
+            Duration::from_secs(24 * 3600)
+        );
+        env::set_var("VELIB_REFERENCE_REPORT_INTERVAL_HOURS", "6");
+        assert_eq!(
+            AppConfig::from_env().reference_report_interval,
+            Duration::from_secs(6 * 3600)
+        );
+        env::remove_var("VELIB_REFERENCE_REPORT_INTERVAL_HOURS");
+    }
+
+    #[test]
+    fn test_memory_soft_limit_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(AppConfig::from_env().memory_soft_limit_mb, None);
+        env::set_var("VELIB_MEMORY_SOFT_LIMIT_MB", " 768 ");
+        assert_eq!(AppConfig::from_env().memory_soft_limit_mb, Some(768));
+        env::set_var("VELIB_MEMORY_SOFT_LIMIT_MB", "0");
+        assert_eq!(AppConfig::from_env().memory_soft_limit_mb, None);
+        env::remove_var("VELIB_MEMORY_SOFT_LIMIT_MB");
+    }
+
+    #[test]
+    fn test_tool_selection_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert_eq!(AppConfig::from_env().tools, ToolSelection::default());
+
+        env::set_var(
+            "VELIB_TOOLS_ENABLED",
+            "get_station_by_code, find_nearby_stations",
+        );
+        env::set_var("VELIB_TOOLS_DISABLED", "plan_bike_journey,");
+        let tools = AppConfig::from_env().tools;
+        assert_eq!(
+            tools.enabled,
+            Some(vec![
+                "get_station_by_code".to_string(),
+                "find_nearby_stations".to_string()
+            ])
+        );
+        assert_eq!(tools.disabled, vec!["plan_bike_journey"]);
+
+        env::remove_var("VELIB_TOOLS_ENABLED");
+        env::remove_var("VELIB_TOOLS_DISABLED");
+    }
+
+    #[test]
+    fn test_allowed_ips_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        assert!(AppConfig::from_env().allowed_ips.is_empty());
+
+        env::set_var("VELIB_ALLOWED_IPS", "10.0.0.0/8, bogus, ,2001:db8::1");
+        let config = AppConfig::from_env();
+        assert_eq!(
+            config.allowed_ips,
+            vec![
+                "10.0.0.0/8".parse::<IpRange>().unwrap(),
+                "2001:db8::1".parse().unwrap()
+            ]
+        );
+
+        env::remove_var("VELIB_ALLOWED_IPS");
+    }
+
+    #[test]
+    fn test_simulation_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        let config = AppConfig::from_env();
+        assert_eq!(config.simulation_stations, None);
+        assert_eq!(config.simulation_seed, 0);
+
+        env::set_var("VELIB_SIMULATION_STATIONS", "250");
+        env::set_var("VELIB_SIMULATION_SEED", "42");
+        let config = AppConfig::from_env();
+        assert_eq!(config.simulation_stations, Some(250));
+        assert_eq!(config.simulation_seed, 42);
+        env::set_var("VELIB_SIMULATION_STATIONS", "0");
+        assert_eq!(AppConfig::from_env().simulation_stations, None);
+
+        env::remove_var("VELIB_SIMULATION_STATIONS");
+        env::remove_var("VELIB_SIMULATION_SEED");
+    }
+
+    #[test]
+    fn test_tool_timeouts_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var(
+            "VELIB_TOOL_TIMEOUTS",
+            "default=20, plan_bike_journey=45,broken,get_area_statistics=0,x=1.5",
+        );
+        assert_eq!(
+            AppConfig::from_env().tool_timeouts,
+            vec![
+                ("default".to_string(), Duration::from_secs(20)),
+                ("plan_bike_journey".to_string(), Duration::from_secs(45)),
+                ("x".to_string(), Duration::from_millis(1500)),
+            ]
+        );
+
+        env::remove_var("VELIB_TOOL_TIMEOUTS");
+        assert!(AppConfig::from_env().tool_timeouts.is_empty());
+    }
 }
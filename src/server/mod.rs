@@ -1,48 +1,276 @@
+#[cfg(unix)]
+mod activation;
 pub mod config;
+pub mod selftest;
+pub mod tenants;
+pub mod tunables;
 
-pub use config::parse_server_address;
+#[cfg(unix)]
+pub use activation::InheritedSocket;
+pub use config::{parse_server_address, AppConfig};
+pub use selftest::SelfTestReport;
+pub use tenants::TenantConfig;
+pub use tunables::Tunables;
 
 use axum::{response::Json, routing::get, Router};
 use serde_json::{json, Value};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::info;
 
 use crate::mcp::McpServer;
 
 pub struct Server {
     addr: SocketAddr,
+    mcp_server: McpServer,
+    poll_interval: Option<Duration>,
+    health_probe_interval: Duration,
+    history_compaction_interval: Duration,
+    reference_report_interval: Duration,
+    tenants: Vec<Tenant>,
+    pid_file: Option<PathBuf>,
+    grpc_addr: Option<SocketAddr>,
+    #[cfg(unix)]
+    inherited_socket: Option<InheritedSocket>,
+}
+
+/// Tests that change the process environment hold this, so they don't race
+#[cfg(test)]
+pub(crate) static ENV_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// A tenant's own MCP server, with its own caches, metrics and sessions
+struct Tenant {
+    config: TenantConfig,
+    mcp_server: McpServer,
+    poll_interval: Option<Duration>,
 }
 
 impl Server {
     #[must_use]
     pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            mcp_server: McpServer::new(),
+            poll_interval: None,
+            health_probe_interval: AppConfig::default().health_probe_interval,
+            history_compaction_interval: AppConfig::default().history_compaction_interval,
+            reference_report_interval: AppConfig::default().reference_report_interval,
+            tenants: Vec::new(),
+            pid_file: None,
+            grpc_addr: None,
+            #[cfg(unix)]
+            inherited_socket: None,
+        }
     }
 
-    pub fn router(&self) -> Router {
-        let mcp_server = McpServer::new();
+    /// Create a server from application configuration
+    ///
+    /// Tenants declared in `config.tenants_path` each get their own MCP server.
+    pub fn with_config(addr: SocketAddr, config: &AppConfig) -> crate::Result<Self> {
+        let mut tenants = Vec::new();
+        if let Some(path) = &config.tenants_path {
+            for tenant in TenantConfig::load_all(path)? {
+                let tenant_config = tenant.apply(config);
+                let source = {
+                    let tenant = tenant.clone();
+                    Arc::new(move || Ok(tenant.apply(&AppConfig::load()?)))
+                };
+                tenants.push(Tenant {
+                    mcp_server: McpServer::with_config(&tenant_config)?.with_config_source(source),
+                    poll_interval: tenant_config.poll_interval,
+                    config: tenant,
+                });
+            }
+        }
+        Ok(Self {
+            addr,
+            mcp_server: McpServer::with_config(config)?,
+            poll_interval: config.poll_interval,
+            health_probe_interval: config.health_probe_interval,
+            history_compaction_interval: config.history_compaction_interval,
+            reference_report_interval: config.reference_report_interval,
+            tenants,
+            pid_file: config.pid_file.clone(),
+            grpc_addr: config.grpc_addr,
+            #[cfg(unix)]
+            inherited_socket: None,
+        })
+    }
+
+    /// Serve `socket`, see [`InheritedSocket::take`], instead of binding the address
+    #[cfg(unix)]
+    #[must_use]
+    pub fn with_inherited_socket(mut self, socket: Option<InheritedSocket>) -> Self {
+        self.inherited_socket = socket;
+        self
+    }
+
+    /// Serve `mcp_server` to requests selected by `tenant`'s hosts or path prefix
+    #[must_use]
+    pub fn with_tenant(mut self, tenant: TenantConfig, mcp_server: McpServer) -> Self {
+        self.tenants.push(Tenant {
+            config: tenant,
+            mcp_server,
+            poll_interval: None,
+        });
+        self
+    }
 
-        Router::new()
-            .route("/health", get(health_check))
-            .merge(mcp_server.router())
+    pub fn router(&self) -> Router {
+        if self.tenants.is_empty() {
+            return site(&self.mcp_server);
+        }
+        let tenants = self
+            .tenants
+            .iter()
+            .map(|tenant| (tenant.config.clone(), site(&tenant.mcp_server)))
+            .collect();
+        tenants::dispatch(site(&self.mcp_server), tenants)
     }
 
-    pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Serve until stopped
+    ///
+    /// On Unix, a socket passed by systemd socket activation or a restart
+    /// (see [`Self::with_inherited_socket`]) is served instead of binding the
+    /// address.
+    ///
+    /// `SIGTERM` stops the server once in-flight requests finish. `SIGHUP`
+    /// reloads the configuration tunables, see [`McpServer::reload`]. `SIGUSR2`
+    /// restarts the process in place without closing the socket.
+    pub async fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        crate::mcp::install_panic_hook();
         let app = self.router();
 
-        info!("Starting server on {}", self.addr);
+        if let Some(interval) = self.poll_interval {
+            info!("Polling real-time data every {:?}", interval);
+            self.mcp_server.spawn_realtime_polling(interval);
+        }
+
+        info!(
+            "Probing upstream health every {:?}",
+            self.health_probe_interval
+        );
+        self.mcp_server
+            .spawn_health_probe(self.health_probe_interval);
+
+        self.mcp_server
+            .spawn_history_compaction(self.history_compaction_interval);
+
+        self.mcp_server
+            .spawn_reference_validation(self.reference_report_interval);
 
-        let listener = tokio::net::TcpListener::bind(self.addr).await?;
-        axum::serve(listener, app).await?;
+        let quota_flush = Duration::from_secs(crate::mcp::quota::QUOTA_FLUSH_SECONDS);
+        self.mcp_server.spawn_quota_flush(quota_flush);
 
-        Ok(())
+        for tenant in &self.tenants {
+            info!(
+                "Serving tenant '{}' (hosts {:?}, path prefix {:?})",
+                tenant.config.name, tenant.config.hosts, tenant.config.path_prefix
+            );
+            if let Some(interval) = tenant.poll_interval {
+                tenant.mcp_server.spawn_realtime_polling(interval);
+            }
+            tenant
+                .mcp_server
+                .spawn_health_probe(self.health_probe_interval);
+            tenant
+                .mcp_server
+                .spawn_history_compaction(self.history_compaction_interval);
+            tenant
+                .mcp_server
+                .spawn_reference_validation(self.reference_report_interval);
+            tenant.mcp_server.spawn_quota_flush(quota_flush);
+        }
+        let servers: Vec<McpServer> = std::iter::once(&self.mcp_server)
+            .chain(self.tenants.iter().map(|tenant| &tenant.mcp_server))
+            .cloned()
+            .collect();
+        let flush_quotas = {
+            let servers = servers.clone();
+            async move {
+                for server in &servers {
+                    server.flush_quotas().await;
+                }
+            }
+        };
+
+        if let Some(addr) = self.grpc_addr {
+            #[cfg(feature = "grpc")]
+            {
+                let listener = tokio::net::TcpListener::bind(addr).await?;
+                let mcp_server = self.mcp_server.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = crate::mcp::grpc::serve(listener, mcp_server).await {
+                        tracing::error!("gRPC server stopped: {}", e);
+                    }
+                });
+            }
+            #[cfg(not(feature = "grpc"))]
+            tracing::warn!(
+                "Not serving gRPC on {}: built without the grpc feature",
+                addr
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            let _pid_file = self
+                .pid_file
+                .as_deref()
+                .map(activation::PidFile::create)
+                .transpose()?;
+            activation::spawn_reload_on_hangup(servers)?;
+            let listener = match self.inherited_socket.take() {
+                Some(socket) => {
+                    let listener = socket.into_listener()?;
+                    info!("Serving inherited socket on {}", listener.local_addr()?);
+                    listener
+                }
+                None => {
+                    info!("Starting server on {}", self.addr);
+                    tokio::net::TcpListener::bind(self.addr).await?
+                }
+            };
+            activation::serve_until_signal(listener, app, flush_quotas).await
+        }
+
+        #[cfg(not(unix))]
+        {
+            if self.pid_file.is_some() {
+                tracing::warn!("PID files are only written on Unix");
+            }
+            info!("Starting server on {}", self.addr);
+            let listener = tokio::net::TcpListener::bind(self.addr).await?;
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?;
+            flush_quotas.await;
+            Ok(())
+        }
     }
 }
 
-async fn health_check() -> Json<Value> {
+/// `/health` and the MCP routes of one server
+fn site(mcp_server: &McpServer) -> Router {
+    let server = mcp_server.clone();
+    mcp_server.with_hardening(
+        mcp_server.with_version_headers(
+            Router::new()
+                .route("/health", get(move || async move { health_check(&server) }))
+                .merge(mcp_server.routes()),
+        ),
+    )
+}
+
+fn health_check(mcp_server: &McpServer) -> Json<Value> {
     Json(json!({
         "status": "healthy",
         "timestamp": chrono::Utc::now(),
-        "service": "velib-mcp"
+        "service": "velib-mcp",
+        "config": mcp_server.config_version(),
     }))
 }
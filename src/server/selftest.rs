@@ -0,0 +1,281 @@
+use axum::{
+    body::Body,
+    http::{header, Request},
+    Router,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Instant;
+use tower::ServiceExt;
+
+use super::{AppConfig, TenantConfig};
+use crate::data::EnrichedStation;
+use crate::mcp::{McpServer, ToolRegistry};
+use crate::types::StationReference;
+use crate::{Error, Result};
+
+/// Outcome of `velib-mcp selftest`, printed as JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    /// Every check passed
+    pub passed: bool,
+    /// Checks in the order they ran; a failed configuration or fetch stops the rest
+    pub checks: Vec<SelfTestCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestCheck {
+    /// `configuration`, `upstream`, `fetch`, or `tool:<name>`
+    pub name: String,
+    pub passed: bool,
+    pub elapsed_ms: u64,
+    /// What was found, or why the check failed
+    pub detail: String,
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+/// Check that a server could start with `loaded` and answer every tool from
+/// the live upstream data
+///
+/// The server's favorites, quota and journal files are left alone, so this
+/// can run next to a serving instance.
+pub async fn run(loaded: Result<AppConfig>) -> SelfTestReport {
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    let server = match loaded.and_then(|config| isolated_server(&config)) {
+        Ok(server) => {
+            checks.push(check("configuration", started, Ok("valid".to_string())));
+            server
+        }
+        Err(e) => {
+            checks.push(check("configuration", started, Err(e.to_string())));
+            return SelfTestReport::from(checks);
+        }
+    };
+    let handler = server.handler();
+
+    let started = Instant::now();
+    let probe = handler.probe_upstream().await;
+    let upstream = if probe.healthy() {
+        Ok(format!(
+            "reference {} ms, real-time {} ms",
+            probe.reference.latency_ms, probe.realtime.latency_ms
+        ))
+    } else {
+        let failed = [
+            ("reference", &probe.reference),
+            ("real-time", &probe.realtime),
+        ]
+        .into_iter()
+        .filter(|(_, outcome)| !outcome.healthy)
+        .map(|(dataset, outcome)| {
+            format!(
+                "{dataset}: {}",
+                outcome.error.as_deref().unwrap_or("failed")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+        Err(failed)
+    };
+    let reachable = upstream.is_ok();
+    checks.push(check("upstream", started, upstream));
+    if !reachable {
+        return SelfTestReport::from(checks);
+    }
+
+    let started = Instant::now();
+    let samples = match handler
+        .get_complete_stations(true)
+        .await
+        .and_then(|stations| {
+            let with_realtime = stations
+                .iter()
+                .filter(|station| station.station.real_time.is_some())
+                .count();
+            let samples = sample_stations(&stations)?;
+            Ok((samples, stations.len(), with_realtime))
+        }) {
+        Ok((samples, total, with_realtime)) => {
+            checks.push(check(
+                "fetch",
+                started,
+                Ok(format!(
+                    "{total} stations, {with_realtime} with real-time status"
+                )),
+            ));
+            samples
+        }
+        Err(e) => {
+            checks.push(check("fetch", started, Err(e.to_string())));
+            return SelfTestReport::from(checks);
+        }
+    };
+
+    let router = server.router();
+    for tool in ToolRegistry::builtin().tools() {
+        if !server.serves_tool(tool.name) {
+            continue;
+        }
+        let started = Instant::now();
+        let outcome = match sample_arguments(tool.name, &samples) {
+            Some(arguments) => call_tool(&router, tool.name, arguments)
+                .await
+                .map_err(|e| e.to_string()),
+            None => Err("No sample arguments for this tool".to_string()),
+        };
+        checks.push(check(&format!("tool:{}", tool.name), started, outcome));
+    }
+    SelfTestReport::from(checks)
+}
+
+impl From<Vec<SelfTestCheck>> for SelfTestReport {
+    fn from(checks: Vec<SelfTestCheck>) -> Self {
+        Self {
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+fn check(
+    name: &str,
+    started: Instant,
+    outcome: std::result::Result<String, String>,
+) -> SelfTestCheck {
+    SelfTestCheck {
+        name: name.to_string(),
+        passed: outcome.is_ok(),
+        elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        detail: outcome.unwrap_or_else(|e| e),
+    }
+}
+
+/// The server `config` describes, without the files a running instance
+/// writes to and open to the self-test's calls
+fn isolated_server(config: &AppConfig) -> Result<McpServer> {
+    if let Some(path) = &config.tenants_path {
+        TenantConfig::load_all(path)?;
+    }
+    let config = AppConfig {
+        favorites_path: None,
+        quota_path: None,
+        journal_path: None,
+        api_keys: Vec::new(),
+        oidc: None,
+        allowed_ips: Vec::new(),
+        ..config.clone()
+    };
+    McpServer::with_config(&config)
+}
+
+/// Two stations to call the tools about, preferring ones that are in service
+fn sample_stations(stations: &[EnrichedStation]) -> Result<[StationReference; 2]> {
+    let mut operational = stations.iter().filter(|station| station.operational);
+    let pick = |first: &EnrichedStation, second: &EnrichedStation| {
+        [
+            first.station.reference.clone(),
+            second.station.reference.clone(),
+        ]
+    };
+    match (operational.next(), operational.next()) {
+        (Some(first), Some(second)) => Ok(pick(first, second)),
+        _ => match stations {
+            [first, second, ..] => Ok(pick(first, second)),
+            _ => Err(Error::Validation(format!(
+                "Upstream returned {} stations, at least 2 are needed",
+                stations.len()
+            ))),
+        },
+    }
+}
+
+/// Arguments for `tool` about the sample stations, `None` for tools this
+/// doesn't know yet
+fn sample_arguments(tool: &str, [from, to]: &[StationReference; 2]) -> Option<Value> {
+    let point = |reference: &StationReference| {
+        json!({
+            "latitude": reference.coordinates.latitude,
+            "longitude": reference.coordinates.longitude
+        })
+    };
+    let bounds = json!({
+        "north": from.coordinates.latitude.max(to.coordinates.latitude) + 0.005,
+        "south": from.coordinates.latitude.min(to.coordinates.latitude) - 0.005,
+        "east": from.coordinates.longitude.max(to.coordinates.longitude) + 0.005,
+        "west": from.coordinates.longitude.min(to.coordinates.longitude) - 0.005
+    });
+    Some(match tool {
+        "find_nearby_stations" => point(from),
+        "get_station_by_code" | "add_favorite_station" | "render_station_sparkline" => {
+            json!({"station_code": from.station_code})
+        }
+        "search_stations_by_name" => json!({"query": from.name}),
+        "autocomplete_station_names" => {
+            json!({"prefix": from.name.chars().take(3).collect::<String>()})
+        }
+        "set_preferences" => json!({"home": point(from), "work": point(to)}),
+        "list_favorite_stations" | "get_favorites_status" | "get_system_overview" => json!({}),
+        "get_area_statistics" | "get_station_density" => json!({"bounds": bounds}),
+        "find_stations_along_route" => json!({"waypoints": [point(from), point(to)]}),
+        "compare_stations" => json!({"station_codes": [from.station_code, to.station_code]}),
+        "check_dropoff_feasibility" => json!({"destination": point(to)}),
+        "compare_with_typical" => json!({"station_code": from.station_code}),
+        "diff_station_status" => json!({}),
+        "get_fleet_composition" => json!({"window_minutes": 60}),
+        "generate_journey_link" => json!({
+            "pickup_station_code": from.station_code,
+            "dropoff_station_code": to.station_code
+        }),
+        "plan_bike_journey" => json!({"origin": point(from), "destination": point(to)}),
+        "plan_multiple_journeys" => json!({"journeys": [
+            {"origin": point(from), "destination": point(to)},
+            {"origin": point(to), "destination": point(from)}
+        ]}),
+        _ => return None,
+    })
+}
+
+/// Call `tool` over JSON-RPC, as a client of the session tools would
+async fn call_tool(router: &Router, tool: &str, arguments: Value) -> Result<String> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": tool, "arguments": arguments}
+    });
+    let request = Request::post("/mcp")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, "Bearer selftest")
+        .body(Body::from(body.to_string()))
+        .map_err(|e| Error::Internal(e.into()))?;
+    let response = router
+        .clone()
+        .oneshot(request)
+        .await
+        .map_err(|e| Error::Internal(anyhow::anyhow!(e)))?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| Error::Internal(anyhow::anyhow!(e)))?;
+    let body: Value = serde_json::from_slice(&bytes)?;
+    if let Some(error) = body.get("error") {
+        return Err(Error::McpProtocol(format!(
+            "{} ({})",
+            error["message"].as_str().unwrap_or("error"),
+            error["data"]["error_type"].as_str().unwrap_or("unknown")
+        )));
+    }
+    if !status.is_success() || body["result"]["isError"] == true {
+        return Err(Error::McpProtocol(format!("HTTP {status}: {body}")));
+    }
+    Ok(format!("{} bytes", bytes.len()))
+}
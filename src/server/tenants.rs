@@ -0,0 +1,330 @@
+use axum::{
+    extract::Request,
+    http::{header, uri::Authority, Uri},
+    response::IntoResponse,
+    Router,
+};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+use super::AppConfig;
+use crate::{Error, Result};
+
+/// A configuration served next to the main one, selected by host or path prefix
+///
+/// Anything not set here is inherited from the main configuration, except the
+/// files favorites, quotas and the request journal persist to, so tenants
+/// never share state.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantConfig {
+    pub name: String,
+    /// `Host` names (without port) routed to this tenant
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Path prefix routed to this tenant, e.g. `/test`; stripped before routing
+    pub path_prefix: Option<String>,
+    pub stations_url: Option<String>,
+    pub realtime_url: Option<String>,
+    pub gbfs_url: Option<String>,
+    pub upstream_requests_per_minute: Option<u32>,
+    pub poll_interval_secs: Option<u64>,
+    pub quota_daily: Option<u32>,
+    pub quota_monthly: Option<u32>,
+    pub quota_path: Option<PathBuf>,
+    pub favorites_path: Option<PathBuf>,
+    pub journal_path: Option<PathBuf>,
+    pub admin_token: Option<String>,
+    /// Bearer keys allowed on this tenant; replaces the main list when set
+    pub api_keys: Option<Vec<String>>,
+    /// URL clients reach this tenant at; the main one plus `path_prefix` when unset
+    pub public_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenantFile {
+    #[serde(default)]
+    tenant: Vec<TenantConfig>,
+}
+
+impl TenantConfig {
+    /// A tenant reached through `path_prefix` only
+    #[must_use]
+    pub fn at_path(name: &str, path_prefix: &str) -> Self {
+        Self {
+            path_prefix: Some(path_prefix.to_string()),
+            ..Self::named(name)
+        }
+    }
+
+    /// A tenant reached through the `Host` header only
+    #[must_use]
+    pub fn at_host(name: &str, host: &str) -> Self {
+        Self {
+            hosts: vec![host.to_ascii_lowercase()],
+            ..Self::named(name)
+        }
+    }
+
+    fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hosts: Vec::new(),
+            path_prefix: None,
+            stations_url: None,
+            realtime_url: None,
+            gbfs_url: None,
+            upstream_requests_per_minute: None,
+            poll_interval_secs: None,
+            quota_daily: None,
+            quota_monthly: None,
+            quota_path: None,
+            favorites_path: None,
+            journal_path: None,
+            admin_token: None,
+            api_keys: None,
+            public_url: None,
+        }
+    }
+
+    /// Parse `[[tenant]]` tables from TOML
+    pub fn parse_all(source: &str) -> Result<Vec<Self>> {
+        let file: TenantFile = toml::from_str(source)
+            .map_err(|e| Error::Config(format!("Invalid tenant file: {e}")))?;
+
+        let mut names = HashSet::new();
+        let mut routes = HashSet::new();
+        let mut tenants = file.tenant;
+        for tenant in &mut tenants {
+            if !names.insert(tenant.name.clone()) {
+                return Err(Error::Config(format!(
+                    "Tenant '{}' is declared twice",
+                    tenant.name
+                )));
+            }
+            if let Some(prefix) = &mut tenant.path_prefix {
+                let trimmed = prefix.trim_end_matches('/');
+                if !trimmed.starts_with('/') {
+                    return Err(Error::Config(format!(
+                        "Tenant '{}' needs a path_prefix starting with '/'",
+                        tenant.name
+                    )));
+                }
+                *prefix = trimmed.to_string();
+            }
+            for host in &mut tenant.hosts {
+                *host = host.trim().to_ascii_lowercase();
+            }
+            if tenant.hosts.is_empty() && tenant.path_prefix.is_none() {
+                return Err(Error::Config(format!(
+                    "Tenant '{}' needs hosts or a path_prefix",
+                    tenant.name
+                )));
+            }
+            let claimed = tenant.hosts.iter().chain(&tenant.path_prefix);
+            for route in claimed {
+                if !routes.insert(route.clone()) {
+                    return Err(Error::Config(format!(
+                        "Tenant '{}' claims {route}, already routed to another tenant",
+                        tenant.name
+                    )));
+                }
+            }
+        }
+        Ok(tenants)
+    }
+
+    /// Load the tenants declared in a TOML file
+    pub fn load_all(path: &Path) -> Result<Vec<Self>> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read tenant file {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+        Self::parse_all(&source)
+    }
+
+    /// The main configuration with this tenant's settings applied
+    #[must_use]
+    pub fn apply(&self, base: &AppConfig) -> AppConfig {
+        let mut config = base.clone();
+        config.favorites_path = self.favorites_path.clone();
+        config.quota_path = self.quota_path.clone();
+        config.journal_path = self.journal_path.clone();
+        config.tenants_path = None;
+        if let Some(url) = &self.stations_url {
+            config.stations_url = Some(url.clone());
+        }
+        if let Some(url) = &self.realtime_url {
+            config.realtime_url = Some(url.clone());
+        }
+        if let Some(url) = &self.gbfs_url {
+            config.gbfs_url = Some(url.clone());
+        }
+        if let Some(rpm) = self.upstream_requests_per_minute.filter(|&rpm| rpm > 0) {
+            config.upstream_requests_per_minute = rpm;
+        }
+        if let Some(secs) = self.poll_interval_secs {
+            config.poll_interval = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if self.quota_daily.is_some() {
+            config.quota_limits.daily = self.quota_daily.filter(|&calls| calls > 0);
+        }
+        if self.quota_monthly.is_some() {
+            config.quota_limits.monthly = self.quota_monthly.filter(|&calls| calls > 0);
+        }
+        if let Some(token) = &self.admin_token {
+            config.admin_token = Some(token.clone()).filter(|token| !token.is_empty());
+        }
+        if let Some(keys) = &self.api_keys {
+            config.api_keys.clone_from(keys);
+        }
+        config.public_url = match (&self.public_url, &self.path_prefix) {
+            (Some(url), _) => Some(url.clone()),
+            (None, Some(prefix)) => base
+                .public_url
+                .as_ref()
+                .map(|url| format!("{}{prefix}", url.trim_end_matches('/'))),
+            // A host of its own is not reached at the main URL
+            (None, None) => None,
+        };
+        config
+    }
+
+    fn serves_host(&self, host: &str) -> bool {
+        self.hosts.iter().any(|served| served == host)
+    }
+
+    /// The path below this tenant's prefix, if `path` is under it
+    fn strip_prefix<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(self.path_prefix.as_deref()?)?;
+        match rest {
+            "" => Some("/"),
+            rest if rest.starts_with('/') => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+/// Route each request to the tenant its path prefix or `Host` selects, or to `default`
+///
+/// Path prefixes are checked first, so a tenant can also be reached from any
+/// host through its prefix.
+pub(crate) fn dispatch(default: Router, tenants: Vec<(TenantConfig, Router)>) -> Router {
+    let tenants = Arc::new(tenants);
+    Router::new().fallback(move |mut request: Request| {
+        let tenants = Arc::clone(&tenants);
+        let default = default.clone();
+        async move {
+            let router = select(&tenants, &mut request).unwrap_or(default);
+            router.oneshot(request).await.into_response()
+        }
+    })
+}
+
+fn select(tenants: &[(TenantConfig, Router)], request: &mut Request) -> Option<Router> {
+    for (tenant, router) in tenants {
+        let Some(rest) = tenant.strip_prefix(request.uri().path()) else {
+            continue;
+        };
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{rest}?{query}"),
+            None => rest.to_string(),
+        };
+        let mut parts = request.uri().clone().into_parts();
+        parts.path_and_query = path_and_query.parse().ok();
+        *request.uri_mut() = Uri::from_parts(parts).ok()?;
+        return Some(router.clone());
+    }
+
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<Authority>().ok())
+        .or_else(|| request.uri().authority().cloned())?;
+    let host = host.host().to_ascii_lowercase();
+    tenants
+        .iter()
+        .find(|(tenant, _)| tenant.serves_host(&host))
+        .map(|(_, router)| router.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply_tenants() {
+        let tenants = TenantConfig::parse_all(
+            r#"
+            [[tenant]]
+            name = "public"
+            hosts = ["Velib.Example.org"]
+
+            [[tenant]]
+            name = "test-network"
+            path_prefix = "/test/"
+            realtime_url = "http://fixtures.internal/realtime"
+            gbfs_url = "http://fixtures.internal/gbfs/station_information.json"
+            upstream_requests_per_minute = 10
+            quota_daily = 0
+            api_keys = ["qa"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(tenants[0].hosts, vec!["velib.example.org"]);
+        assert_eq!(tenants[1].path_prefix.as_deref(), Some("/test"));
+        assert_eq!(tenants[1].strip_prefix("/test/mcp"), Some("/mcp"));
+        assert_eq!(tenants[1].strip_prefix("/test"), Some("/"));
+        assert_eq!(tenants[1].strip_prefix("/testing/mcp"), None);
+
+        let base = AppConfig {
+            favorites_path: Some(PathBuf::from("/var/lib/velib/favorites.json")),
+            public_url: Some("https://velib.example.org/".to_string()),
+            quota_limits: crate::mcp::QuotaLimits {
+                daily: Some(500),
+                monthly: None,
+            },
+            ..AppConfig::default()
+        };
+        let config = tenants[1].apply(&base);
+        assert_eq!(
+            config.realtime_url.as_deref(),
+            Some("http://fixtures.internal/realtime")
+        );
+        assert_eq!(config.stations_url, None);
+        assert_eq!(
+            config.gbfs_url.as_deref(),
+            Some("http://fixtures.internal/gbfs/station_information.json")
+        );
+        assert_eq!(config.upstream_requests_per_minute, 10);
+        assert_eq!(config.quota_limits.daily, None);
+        assert_eq!(config.api_keys, vec!["qa"]);
+        // Never write to the main configuration's files
+        assert_eq!(config.favorites_path, None);
+        assert_eq!(
+            config.public_url.as_deref(),
+            Some("https://velib.example.org/test")
+        );
+        assert_eq!(tenants[0].apply(&base).public_url, None);
+    }
+
+    #[test]
+    fn test_rejects_ambiguous_tenants() {
+        for source in [
+            "[[tenant]]\nname = \"a\"",
+            "[[tenant]]\nname = \"a\"\npath_prefix = \"test\"",
+            "[[tenant]]\nname = \"a\"\nhosts = [\"x\"]\n[[tenant]]\nname = \"a\"\nhosts = [\"y\"]",
+            "[[tenant]]\nname = \"a\"\nhosts = [\"x\"]\n[[tenant]]\nname = \"b\"\nhosts = [\"X\"]",
+            "[[tenant]]\nname = \"a\"\nhosts = [\"x\"]\nrate = 3",
+        ] {
+            assert!(TenantConfig::parse_all(source).is_err(), "{source}");
+        }
+    }
+}
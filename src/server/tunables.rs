@@ -0,0 +1,199 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use super::AppConfig;
+use crate::types::WalkingSpeed;
+use crate::{Error, Result};
+
+/// Settings a running server picks up when its configuration is reloaded,
+/// read from the TOML file at `VELIB_CONFIG_PATH`
+///
+/// Anything set here overrides the environment; everything else (paths,
+/// endpoints, service area, keys, intervals) only changes on restart.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tunables {
+    pub upstream_requests_per_minute: Option<u32>,
+    pub dedup_ttl_secs: Option<u64>,
+    pub quota_daily: Option<u32>,
+    pub quota_monthly: Option<u32>,
+    pub lenient_limits: Option<bool>,
+    pub walking_speed_kmh: Option<f64>,
+    pub label_low_below: Option<f64>,
+    pub label_near_full_from: Option<f64>,
+    /// Seconds per tool, `default` for every tool
+    pub tool_timeouts: Option<BTreeMap<String, f64>>,
+    /// Log filter in the `RUST_LOG` syntax
+    pub log_level: Option<String>,
+    /// Tool calls lasting this long are logged, 0 for never
+    pub slow_call_ms: Option<u64>,
+    pub tools: Option<ToolTunables>,
+}
+
+/// The `[tools]` table: which tools the server serves
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolTunables {
+    /// Serve only these tools
+    pub enabled: Option<Vec<String>>,
+    /// Never serve these tools
+    pub disabled: Option<Vec<String>>,
+}
+
+impl Tunables {
+    pub fn parse(source: &str) -> Result<Self> {
+        toml::from_str(source).map_err(|e| Error::Config(format!("Invalid config file: {e}")))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!(
+                "Cannot read config file {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+        Self::parse(&source)
+    }
+
+    /// Override `config` with the values set here, refusing inconsistent ones
+    pub fn apply(&self, config: &mut AppConfig) -> Result<()> {
+        if let Some(rpm) = self.upstream_requests_per_minute {
+            if rpm == 0 {
+                return Err(invalid("upstream_requests_per_minute", "must be positive"));
+            }
+            config.upstream_requests_per_minute = rpm;
+        }
+        if let Some(secs) = self.dedup_ttl_secs {
+            config.dedup_ttl = Duration::from_secs(secs);
+        }
+        if self.quota_daily.is_some() {
+            config.quota_limits.daily = self.quota_daily.filter(|&calls| calls > 0);
+        }
+        if self.quota_monthly.is_some() {
+            config.quota_limits.monthly = self.quota_monthly.filter(|&calls| calls > 0);
+        }
+        if let Some(lenient) = self.lenient_limits {
+            config.lenient_limits = lenient;
+        }
+        if let Some(kmh) = self.walking_speed_kmh {
+            if !(kmh.is_finite() && kmh > 0.0) {
+                return Err(invalid("walking_speed_kmh", "must be positive"));
+            }
+            config.walking_speed = WalkingSpeed { kmh };
+        }
+        let mut thresholds = config.availability_thresholds;
+        if let Some(share) = self.label_low_below {
+            thresholds.low_below = share;
+        }
+        if let Some(share) = self.label_near_full_from {
+            thresholds.near_full_from = share;
+        }
+        if !thresholds.is_valid() {
+            return Err(invalid(
+                "label_low_below",
+                "labels need 0 < label_low_below < label_near_full_from < 1",
+            ));
+        }
+        config.availability_thresholds = thresholds;
+        if let Some(timeouts) = &self.tool_timeouts {
+            config.tool_timeouts = timeouts
+                .iter()
+                .map(|(tool, &secs)| {
+                    Duration::try_from_secs_f64(secs)
+                        .ok()
+                        .filter(|timeout| !timeout.is_zero())
+                        .map(|timeout| (tool.clone(), timeout))
+                        .ok_or_else(|| invalid("tool_timeouts", "must be positive seconds"))
+                })
+                .collect::<Result<_>>()?;
+        }
+        if let Some(level) = &self.log_level {
+            crate::logging::parse_filter(level)?;
+            config.log_level = Some(level.clone());
+        }
+        if let Some(ms) = self.slow_call_ms {
+            config.slow_call_threshold = (ms > 0).then(|| Duration::from_millis(ms));
+        }
+        if let Some(tools) = &self.tools {
+            if let Some(enabled) = &tools.enabled {
+                config.tools.enabled = Some(enabled.clone());
+            }
+            if let Some(disabled) = &tools.disabled {
+                config.tools.disabled = disabled.clone();
+            }
+            config.tools.validate()?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, reason: &str) -> Error {
+    Error::Config(format!("Invalid {field} in config file: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tunables_override_the_environment() {
+        let tunables = Tunables::parse(
+            r#"
+            upstream_requests_per_minute = 60
+            dedup_ttl_secs = 0
+            quota_daily = 500
+            walking_speed_kmh = 6.0
+            log_level = "info,velib_mcp=debug"
+            slow_call_ms = 0
+
+            [tool_timeouts]
+            default = 20
+            plan_bike_journey = 45.5
+
+            [tools]
+            disabled = ["plan_bike_journey"]
+            "#,
+        )
+        .unwrap();
+        let mut config = AppConfig::default();
+        tunables.apply(&mut config).unwrap();
+
+        assert_eq!(config.upstream_requests_per_minute, 60);
+        assert!(config.dedup_ttl.is_zero());
+        assert_eq!(config.quota_limits.daily, Some(500));
+        assert_eq!(config.walking_speed, WalkingSpeed { kmh: 6.0 });
+        assert_eq!(config.log_level.as_deref(), Some("info,velib_mcp=debug"));
+        assert_eq!(config.slow_call_threshold, None);
+        assert_eq!(
+            config.tool_timeouts,
+            vec![
+                ("default".to_string(), Duration::from_secs(20)),
+                (
+                    "plan_bike_journey".to_string(),
+                    Duration::from_millis(45_500)
+                ),
+            ]
+        );
+        assert_eq!(config.tools.disabled, vec!["plan_bike_journey"]);
+        // Unset values keep the environment's
+        assert!(!config.lenient_limits);
+        assert_eq!(config.tools.enabled, None);
+
+        assert!(Tunables::parse("poll_interval_secs = 5").is_err());
+        for invalid in [
+            "upstream_requests_per_minute = 0",
+            "label_low_below = 0.9",
+            "log_level = \"velib_mcp=loud\"",
+            "tool_timeouts = { default = -1 }",
+            "tools = { enabled = [\"plan_journey\"] }",
+        ] {
+            let tunables = Tunables::parse(invalid).unwrap();
+            assert!(
+                tunables.apply(&mut AppConfig::default()).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+}
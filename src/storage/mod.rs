@@ -0,0 +1,346 @@
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+/// Storage URL of the in-process backend, which keeps nothing across restarts
+pub const MEMORY_URL: &str = ":memory:";
+
+/// Prefix of SQLite database URLs, followed by the database file's path
+pub const SQLITE_URL_PREFIX: &str = "sqlite://";
+
+/// A step of the storage schema, applied once in `version` order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    /// Statements for SQL backends
+    pub sql: &'static str,
+}
+
+/// Schema shared by every backend: keyed records and time-ordered logs, each
+/// grouped by the namespace of the subsystem owning them
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "keyed records",
+        sql: "CREATE TABLE records (
+            namespace TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value BLOB NOT NULL,
+            PRIMARY KEY (namespace, key)
+        )",
+    },
+    Migration {
+        version: 2,
+        description: "append-only logs",
+        sql: "CREATE TABLE log_entries (
+            namespace TEXT NOT NULL,
+            at TEXT NOT NULL,
+            entry BLOB NOT NULL
+        );
+        CREATE INDEX log_entries_by_time ON log_entries (namespace, at)",
+    },
+];
+
+/// Version a freshly migrated store is at
+#[must_use]
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Durable state shared by the subsystems that outlive a restart (history,
+/// favorites, quotas, audit): keyed records, plus logs read back by time
+///
+/// Namespaces keep subsystems apart; values are opaque bytes, see the JSON
+/// helpers on `dyn Storage`.
+pub trait Storage: Send + Sync {
+    /// Schema version the store has been migrated to
+    fn schema_version(&self) -> u32;
+
+    /// Store `value` under `key`, replacing what was there
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()>;
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Whether there was a record to delete
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool>;
+
+    /// Records whose key starts with `prefix`, sorted by key
+    fn scan(&self, namespace: &str, prefix: &str) -> Result<Vec<(String, Vec<u8>)>>;
+
+    /// Add an entry at the end of a namespace's log
+    fn append(&self, namespace: &str, at: DateTime<Utc>, entry: &[u8]) -> Result<()>;
+
+    /// Log entries at or after `since`, oldest first
+    fn read_log(
+        &self,
+        namespace: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, Vec<u8>)>>;
+
+    /// Forget log entries older than `before`, returning how many went
+    fn prune_log(&self, namespace: &str, before: DateTime<Utc>) -> Result<usize>;
+}
+
+impl dyn Storage {
+    /// Store `value` as JSON
+    pub fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<()> {
+        self.put(namespace, key, &serde_json::to_vec(value)?)
+    }
+
+    pub fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>> {
+        self.get(namespace, key)?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(Error::from))
+            .transpose()
+    }
+}
+
+/// Open the store at `url`: [`MEMORY_URL`], or with the `sqlite` feature a
+/// database file as `sqlite://<path>`, created and migrated as needed
+pub fn open(url: &str) -> Result<Arc<dyn Storage>> {
+    let url = url.trim();
+    if url == MEMORY_URL {
+        return Ok(Arc::new(MemoryStorage::new()));
+    }
+    if let Some(path) = url.strip_prefix(SQLITE_URL_PREFIX) {
+        #[cfg(feature = "sqlite")]
+        return Ok(Arc::new(SqliteStorage::open(path)?));
+        #[cfg(not(feature = "sqlite"))]
+        return Err(Error::Config(format!(
+            "Storage URL '{url}' needs a build with the sqlite feature, {path} was not opened"
+        )));
+    }
+    Err(Error::Config(format!(
+        "Unsupported storage URL '{url}', expected {MEMORY_URL} or {SQLITE_URL_PREFIX}<path>"
+    )))
+}
+
+type Records = HashMap<String, BTreeMap<String, Vec<u8>>>;
+type Logs = HashMap<String, Vec<(DateTime<Utc>, Vec<u8>)>>;
+
+/// Storage kept in process memory, for tests and single-run deployments
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    records: RwLock<Records>,
+    logs: RwLock<Logs>,
+}
+
+impl MemoryStorage {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn poisoned<T>(_: T) -> Error {
+    Error::Storage("a writer panicked while holding the memory store".to_string())
+}
+
+impl Storage for MemoryStorage {
+    fn schema_version(&self) -> u32 {
+        latest_schema_version()
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.records
+            .write()
+            .map_err(poisoned)?
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .records
+            .read()
+            .map_err(poisoned)?
+            .get(namespace)
+            .and_then(|records| records.get(key).cloned()))
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        Ok(self
+            .records
+            .write()
+            .map_err(poisoned)?
+            .get_mut(namespace)
+            .is_some_and(|records| records.remove(key).is_some()))
+    }
+
+    fn scan(&self, namespace: &str, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let records = self.records.read().map_err(poisoned)?;
+        Ok(records
+            .get(namespace)
+            .map(|records| {
+                records
+                    .range(prefix.to_string()..)
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn append(&self, namespace: &str, at: DateTime<Utc>, entry: &[u8]) -> Result<()> {
+        let mut logs = self.logs.write().map_err(poisoned)?;
+        let log = logs.entry(namespace.to_string()).or_default();
+        // Entries may arrive slightly out of order; keep the log sorted by time
+        let position = log.partition_point(|(logged_at, _)| *logged_at <= at);
+        log.insert(position, (at, entry.to_vec()));
+        Ok(())
+    }
+
+    fn read_log(
+        &self,
+        namespace: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, Vec<u8>)>> {
+        let logs = self.logs.read().map_err(poisoned)?;
+        Ok(logs
+            .get(namespace)
+            .map(|log| {
+                let start = log.partition_point(|(at, _)| *at < since);
+                log[start..].to_vec()
+            })
+            .unwrap_or_default())
+    }
+
+    fn prune_log(&self, namespace: &str, before: DateTime<Utc>) -> Result<usize> {
+        let mut logs = self.logs.write().map_err(poisoned)?;
+        let Some(log) = logs.get_mut(namespace) else {
+            return Ok(0);
+        };
+        let pruned = log.partition_point(|(at, _)| *at < before);
+        log.drain(..pruned);
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS
+            .iter()
+            .map(|migration| migration.version)
+            .collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            open(MEMORY_URL).unwrap().schema_version(),
+            latest_schema_version()
+        );
+        assert!(matches!(open("postgres://nowhere"), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_records_by_namespace_and_prefix() {
+        check_records(&*open(MEMORY_URL).unwrap());
+    }
+
+    #[test]
+    fn test_logs_read_and_pruned_by_time() {
+        check_logs(&MemoryStorage::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_backend() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(storage.schema_version(), latest_schema_version());
+        check_records(&storage);
+        check_logs(&storage);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_file_survives_reopening() {
+        let path = std::env::temp_dir().join(format!("velib-storage-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("{SQLITE_URL_PREFIX}{}", path.display());
+        open(&url)
+            .unwrap()
+            .put("favorites", "key:alice", b"12001")
+            .unwrap();
+
+        // Migrations already applied are not run again
+        let reopened = open(&url).unwrap();
+        assert_eq!(reopened.schema_version(), latest_schema_version());
+        assert_eq!(
+            reopened.get("favorites", "key:alice").unwrap(),
+            Some(b"12001".to_vec())
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn check_records(storage: &(dyn Storage + 'static)) {
+        storage
+            .put_json("favorites", "key:alice/12001", &"Home")
+            .unwrap();
+        storage
+            .put_json("favorites", "key:alice/4001", &"Work")
+            .unwrap();
+        storage
+            .put_json("favorites", "key:bob/12001", &"Gym")
+            .unwrap();
+        storage.put_json("quotas", "key:alice/12001", &3).unwrap();
+
+        let label: Option<String> = storage.get_json("favorites", "key:alice/4001").unwrap();
+        assert_eq!(label.as_deref(), Some("Work"));
+        let keys: Vec<String> = storage
+            .scan("favorites", "key:alice/")
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["key:alice/12001", "key:alice/4001"]);
+
+        assert!(storage.delete("favorites", "key:alice/12001").unwrap());
+        assert!(!storage.delete("favorites", "key:alice/12001").unwrap());
+        assert_eq!(
+            storage.get("quotas", "key:alice/12001").unwrap(),
+            Some(b"3".to_vec())
+        );
+    }
+
+    fn check_logs(storage: &dyn Storage) {
+        let start = Utc::now();
+        for minutes in [0, 20, 10] {
+            storage
+                .append(
+                    "audit",
+                    start + Duration::minutes(minutes),
+                    minutes.to_string().as_bytes(),
+                )
+                .unwrap();
+        }
+
+        let entries: Vec<Vec<u8>> = storage
+            .read_log("audit", start + Duration::minutes(5))
+            .unwrap()
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect();
+        assert_eq!(entries, vec![b"10".to_vec(), b"20".to_vec()]);
+
+        assert_eq!(
+            storage
+                .prune_log("audit", start + Duration::minutes(15))
+                .unwrap(),
+            2
+        );
+        assert_eq!(storage.read_log("audit", start).unwrap().len(), 1);
+        assert_eq!(storage.prune_log("elsewhere", start).unwrap(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use super::{Storage, MIGRATIONS};
+use crate::{Error, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Storage in an SQLite database, migrated to the latest schema when opened
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open, creating it if needed, the database file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::migrated(Connection::open(path).map_err(failed)?)
+    }
+
+    /// A database living as long as the store, for tests
+    pub fn in_memory() -> Result<Self> {
+        Self::migrated(Connection::open_in_memory().map_err(failed)?)
+    }
+
+    /// Apply the [`MIGRATIONS`] past the database's `user_version`, each in
+    /// its own transaction
+    fn migrated(mut connection: Connection) -> Result<Self> {
+        let current = user_version(&connection)?;
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+            let transaction = connection.transaction().map_err(failed)?;
+            transaction
+                .execute_batch(migration.sql)
+                .and_then(|()| transaction.pragma_update(None, "user_version", migration.version))
+                .and_then(|()| transaction.commit())
+                .map_err(|e| {
+                    Error::Storage(format!(
+                        "migration {} ({}) failed: {e}",
+                        migration.version, migration.description
+                    ))
+                })?;
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| Error::Storage("a writer panicked while holding the database".to_string()))
+    }
+}
+
+fn failed(error: rusqlite::Error) -> Error {
+    Error::Storage(error.to_string())
+}
+
+fn user_version(connection: &Connection) -> Result<u32> {
+    connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(failed)
+}
+
+/// Timestamps are stored with a fixed width, so they sort as text
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+impl Storage for SqliteStorage {
+    fn schema_version(&self) -> u32 {
+        self.connection()
+            .and_then(|connection| user_version(&connection))
+            .unwrap_or(0)
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.connection()?
+            .execute(
+                "INSERT OR REPLACE INTO records (namespace, key, value) VALUES (?1, ?2, ?3)",
+                params![namespace, key, value],
+            )
+            .map_err(failed)?;
+        Ok(())
+    }
+
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        self.connection()?
+            .query_row(
+                "SELECT value FROM records WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(failed)
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        let deleted = self
+            .connection()?
+            .execute(
+                "DELETE FROM records WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+            )
+            .map_err(failed)?;
+        Ok(deleted > 0)
+    }
+
+    fn scan(&self, namespace: &str, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(
+                "SELECT key, value FROM records
+                WHERE namespace = ?1 AND substr(key, 1, length(?2)) = ?2
+                ORDER BY key",
+            )
+            .map_err(failed)?;
+        let records = statement
+            .query_map(params![namespace, prefix], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(failed)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(failed)?;
+        Ok(records)
+    }
+
+    fn append(&self, namespace: &str, at: DateTime<Utc>, entry: &[u8]) -> Result<()> {
+        self.connection()?
+            .execute(
+                "INSERT INTO log_entries (namespace, at, entry) VALUES (?1, ?2, ?3)",
+                params![namespace, timestamp(at), entry],
+            )
+            .map_err(failed)?;
+        Ok(())
+    }
+
+    fn read_log(
+        &self,
+        namespace: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, Vec<u8>)>> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(
+                "SELECT at, entry FROM log_entries
+                WHERE namespace = ?1 AND at >= ?2
+                ORDER BY at, rowid",
+            )
+            .map_err(failed)?;
+        let rows: Vec<(String, Vec<u8>)> = statement
+            .query_map(params![namespace, timestamp(since)], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(failed)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(failed)?;
+        rows.into_iter()
+            .map(|(at, entry)| {
+                let at = DateTime::parse_from_rfc3339(&at)
+                    .map_err(|e| Error::Storage(format!("bad log timestamp '{at}': {e}")))?;
+                Ok((at.with_timezone(&Utc), entry))
+            })
+            .collect()
+    }
+
+    fn prune_log(&self, namespace: &str, before: DateTime<Utc>) -> Result<usize> {
+        self.connection()?
+            .execute(
+                "DELETE FROM log_entries WHERE namespace = ?1 AND at < ?2",
+                params![namespace, timestamp(before)],
+            )
+            .map_err(failed)
+    }
+}
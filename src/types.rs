@@ -1,5 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::geometry::Geometry;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Coordinates {
@@ -37,25 +40,100 @@ impl Coordinates {
     /// Check if coordinates are within reasonable bounds for Paris metro area
     #[must_use]
     pub fn is_valid_paris_metro(&self) -> bool {
-        // Paris metro area bounds (approximate)
-        self.latitude >= 48.7
-            && self.latitude <= 49.0
-            && self.longitude >= 2.0
-            && self.longitude <= 2.6
+        ServiceArea::paris().in_bounds(self)
     }
 
     /// Check if coordinates are within 50km of Paris City Hall (Hôtel de Ville)
     /// Latitude: 48.8565° N, Longitude: 2.3514° E
     #[must_use]
     pub fn is_within_paris_service_area(&self) -> bool {
-        const PARIS_CITY_HALL_LAT: f64 = 48.8565;
-        const PARIS_CITY_HALL_LON: f64 = 2.3514;
-        const MAX_DISTANCE_METERS: f64 = 50_000.0; // 50km
+        ServiceArea::paris().contains(self)
+    }
+}
+
+/// Where a deployment answers queries: a bounding box coordinates must fall in,
+/// a maximum distance from a center point, and optionally an exact outline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceArea {
+    pub center: Coordinates,
+    pub radius_meters: f64,
+    pub south: f64,
+    pub north: f64,
+    pub west: f64,
+    pub east: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Geometry>,
+}
+
+impl Default for ServiceArea {
+    fn default() -> Self {
+        Self::paris()
+    }
+}
+
+impl ServiceArea {
+    /// 50km around Paris City Hall, within the Paris metro area
+    #[must_use]
+    pub fn paris() -> Self {
+        Self {
+            center: Coordinates::new(48.8565, 2.3514),
+            radius_meters: 50_000.0,
+            south: 48.7,
+            north: 49.0,
+            west: 2.0,
+            east: 2.6,
+            polygon: None,
+        }
+    }
+
+    /// Check if coordinates fall inside the bounding box
+    #[must_use]
+    pub fn in_bounds(&self, coords: &Coordinates) -> bool {
+        coords.latitude >= self.south
+            && coords.latitude <= self.north
+            && coords.longitude >= self.west
+            && coords.longitude <= self.east
+    }
 
-        let city_hall = Coordinates::new(PARIS_CITY_HALL_LAT, PARIS_CITY_HALL_LON);
-        let distance = self.distance_to(&city_hall);
+    /// Check if coordinates are within the radius of the center, and inside the polygon if any
+    #[must_use]
+    pub fn contains(&self, coords: &Coordinates) -> bool {
+        coords.distance_to(&self.center) <= self.radius_meters
+            && self
+                .polygon
+                .as_ref()
+                .is_none_or(|polygon| polygon.contains(coords))
+    }
+
+    /// Reject coordinates outside the bounding box, beyond the radius or outside the polygon
+    pub fn check(&self, coords: &Coordinates) -> crate::Result<()> {
+        if !self.in_bounds(coords) {
+            return Err(crate::Error::InvalidCoordinates {
+                latitude: coords.latitude,
+                longitude: coords.longitude,
+            });
+        }
+
+        let distance_meters = coords.distance_to(&self.center);
+        if distance_meters > self.radius_meters {
+            return Err(crate::Error::OutsideServiceArea {
+                distance_km: distance_meters / 1000.0,
+                max_km: self.radius_meters / 1000.0,
+            });
+        }
+
+        if self
+            .polygon
+            .as_ref()
+            .is_some_and(|polygon| !polygon.contains(coords))
+        {
+            return Err(crate::Error::OutsideServiceBoundary {
+                latitude: coords.latitude,
+                longitude: coords.longitude,
+            });
+        }
 
-        distance <= MAX_DISTANCE_METERS
+        Ok(())
     }
 }
 
@@ -118,7 +196,7 @@ impl Default for BikeAvailability {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DataFreshness {
     Fresh,     // < 5 minutes old
     Recent,    // 5-15 minutes old
@@ -136,6 +214,12 @@ impl DataFreshness {
             _ => DataFreshness::VeryStale,
         }
     }
+
+    /// This freshness, or `floor` if that is staler
+    #[must_use]
+    pub fn at_least(self, floor: DataFreshness) -> Self {
+        self.max(floor)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
@@ -151,14 +235,39 @@ pub enum BikeTypeFilter {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StationReference {
-    pub station_code: String,
-    pub name: String,
+    /// Shared with every other copy of the station, see [`crate::data::StringInterner`]
+    pub station_code: Arc<str>,
+    pub name: Arc<str>,
     pub coordinates: Coordinates,
     pub capacity: u16,
     pub capabilities: ServiceCapabilities,
+    /// Paris arrondissement ("Paris 12e"), or the commune outside Paris
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commune: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insee_code: Option<String>,
 }
 
 impl StationReference {
+    /// Set the commune and INSEE code, deriving `district` from them
+    ///
+    /// Paris stations get their arrondissement, from an arrondissement INSEE
+    /// code (751xx) or else from the station code, whose leading digits
+    /// number the arrondissement.
+    #[must_use]
+    pub fn with_commune(mut self, commune: Option<String>, insee_code: Option<String>) -> Self {
+        self.district = district_name(
+            commune.as_deref(),
+            insee_code.as_deref(),
+            &self.station_code,
+        );
+        self.commune = commune;
+        self.insee_code = insee_code;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.station_code.is_empty() {
             return Err("Station code cannot be empty".to_string());
@@ -185,13 +294,151 @@ impl StationReference {
     }
 }
 
+fn district_name(
+    commune: Option<&str>,
+    insee_code: Option<&str>,
+    station_code: &str,
+) -> Option<String> {
+    let from_insee = insee_code
+        .and_then(|code| code.strip_prefix("751"))
+        .and_then(|number| number.parse::<u8>().ok());
+    let is_paris = from_insee.is_some() || commune.is_some_and(|c| c.eq_ignore_ascii_case("paris"));
+    let from_station_code = || {
+        let digits = station_code.len().checked_sub(3)?;
+        station_code.get(..digits)?.parse::<u8>().ok()
+    };
+    let arrondissement = from_insee
+        .or_else(|| is_paris.then(from_station_code).flatten())
+        .filter(|number| (1..=20).contains(number));
+
+    match arrondissement {
+        Some(1) => Some("Paris 1er".to_string()),
+        Some(number) => Some(format!("Paris {number}e")),
+        None => commune.map(str::to_string),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealTimeStatus {
     pub bikes: BikeAvailability,
     pub available_docks: u16,
     pub status: StationStatus,
-    pub last_update: DateTime<Utc>,
+    /// Upstream `duedate`, or `None` when the record's timestamp could not be parsed
+    pub last_update: Option<DateTime<Utc>>,
     pub data_freshness: DataFreshness,
+    /// Set when `last_update` is unknown; `data_freshness` is then `VeryStale`
+    #[serde(default)]
+    pub unknown_freshness: bool,
+    /// How bike availability moved over the last minutes, once history allows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trend: Option<AvailabilityTrend>,
+    /// How full the station is, set with the configured thresholds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability_label: Option<AvailabilityLabel>,
+    /// Bikes can be taken from the station
+    #[serde(default = "default_true")]
+    pub is_renting: bool,
+    /// Bikes can be returned to the station
+    #[serde(default = "default_true")]
+    pub is_returning: bool,
+    /// Reports far fewer bikes and docks than the station holds, and has for a
+    /// while: likely broken rather than empty
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suspected_out_of_service: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// How full a station is, in words agents can repeat as-is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityLabel {
+    /// No bike to take
+    Empty,
+    Low,
+    Balanced,
+    NearFull,
+    /// No free dock to return a bike to
+    Full,
+}
+
+/// Walking pace used to report distances as `walk_time_minutes`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WalkingSpeed {
+    pub kmh: f64,
+}
+
+impl Default for WalkingSpeed {
+    /// An unhurried city pace, 80 m per minute
+    fn default() -> Self {
+        Self { kmh: 4.8 }
+    }
+}
+
+impl WalkingSpeed {
+    /// Minutes to walk `distance_meters`, rounded up
+    #[must_use]
+    pub fn minutes(self, distance_meters: u32) -> u32 {
+        let meters_per_minute = self.kmh * 1000.0 / 60.0;
+        (f64::from(distance_meters) / meters_per_minute).ceil() as u32
+    }
+}
+
+/// Share of a station's usable docks holding a bike at which labels change
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityThresholds {
+    /// `low` below this share
+    pub low_below: f64,
+    /// `near_full` from this share
+    pub near_full_from: f64,
+}
+
+impl Default for AvailabilityThresholds {
+    fn default() -> Self {
+        Self {
+            low_below: 0.25,
+            near_full_from: 0.75,
+        }
+    }
+}
+
+impl AvailabilityThresholds {
+    /// Both shares within `(0, 1]`, `low_below` under `near_full_from`
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        0.0 < self.low_below && self.low_below < self.near_full_from && self.near_full_from <= 1.0
+    }
+
+    #[must_use]
+    pub fn label(&self, bikes: u16, available_docks: u16) -> AvailabilityLabel {
+        if bikes == 0 {
+            return AvailabilityLabel::Empty;
+        }
+        if available_docks == 0 {
+            return AvailabilityLabel::Full;
+        }
+        // Out-of-service docks count neither way
+        let share = f64::from(bikes) / (f64::from(bikes) + f64::from(available_docks));
+        if share < self.low_below {
+            AvailabilityLabel::Low
+        } else if share >= self.near_full_from {
+            AvailabilityLabel::NearFull
+        } else {
+            AvailabilityLabel::Balanced
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityTrend {
+    /// Bikes are being returned faster than taken
+    Filling,
+    /// Bikes are being taken faster than returned
+    Emptying,
+    Stable,
 }
 
 impl RealTimeStatus {
@@ -202,16 +449,76 @@ impl RealTimeStatus {
         status: StationStatus,
         last_update: DateTime<Utc>,
     ) -> Self {
-        let age_minutes = (Utc::now() - last_update).num_minutes() as f64;
+        Self::new_at(bikes, available_docks, status, last_update, Utc::now())
+    }
+
+    /// Status whose data freshness is judged as of `now` rather than the wall clock
+    #[must_use]
+    pub fn new_at(
+        bikes: BikeAvailability,
+        available_docks: u16,
+        status: StationStatus,
+        last_update: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let age_minutes = (now - last_update).num_minutes() as f64;
         let data_freshness = DataFreshness::from_age(age_minutes);
+        let open = status == StationStatus::Open;
 
         Self {
             bikes,
             available_docks,
             status,
-            last_update,
+            last_update: Some(last_update),
             data_freshness,
+            unknown_freshness: false,
+            trend: None,
+            availability_label: None,
+            is_renting: open,
+            is_returning: open,
+            suspected_out_of_service: false,
+        }
+    }
+
+    /// Status of a record whose update time is unknown, which is never treated as fresh
+    #[must_use]
+    pub fn with_unknown_update(
+        bikes: BikeAvailability,
+        available_docks: u16,
+        status: StationStatus,
+    ) -> Self {
+        let open = status == StationStatus::Open;
+        Self {
+            bikes,
+            available_docks,
+            status,
+            last_update: None,
+            data_freshness: DataFreshness::VeryStale,
+            unknown_freshness: true,
+            trend: None,
+            availability_label: None,
+            is_renting: open,
+            is_returning: open,
+            suspected_out_of_service: false,
+        }
+    }
+
+    /// Set what an installed station currently allows
+    ///
+    /// `status` stays `Open` only when both renting and returning work, and
+    /// becomes `Maintenance` otherwise; a `Closed` station stays closed.
+    #[must_use]
+    pub fn with_service(mut self, is_renting: bool, is_returning: bool) -> Self {
+        if self.status != StationStatus::Closed {
+            self.is_renting = is_renting;
+            self.is_returning = is_returning;
+            self.status = if is_renting && is_returning {
+                StationStatus::Open
+            } else {
+                StationStatus::Maintenance
+            };
         }
+        self
     }
 }
 
@@ -236,6 +543,7 @@ impl VelibStation {
         self
     }
 
+    /// Whether bikes can be both taken and returned
     #[must_use]
     pub fn is_operational(&self) -> bool {
         match &self.real_time {
@@ -244,6 +552,24 @@ impl VelibStation {
         }
     }
 
+    /// Whether bikes can be taken, even if returns are suspended
+    #[must_use]
+    pub fn can_rent(&self) -> bool {
+        match &self.real_time {
+            Some(rt) => rt.status != StationStatus::Closed && rt.is_renting,
+            None => true,
+        }
+    }
+
+    /// Whether bikes can be returned, even if rentals are suspended
+    #[must_use]
+    pub fn can_return(&self) -> bool {
+        match &self.real_time {
+            Some(rt) => rt.status != StationStatus::Closed && rt.is_returning,
+            None => true,
+        }
+    }
+
     #[must_use]
     pub fn has_available_bikes(&self, bike_type: &BikeTypeFilter) -> bool {
         match &self.real_time {
@@ -341,6 +667,30 @@ mod tests {
         assert!(!very_far_point.is_within_paris_service_area());
     }
 
+    #[test]
+    fn test_custom_service_area() {
+        let area = ServiceArea {
+            radius_meters: 5_000.0,
+            ..ServiceArea::paris()
+        };
+
+        let louvre = Coordinates::new(48.8606, 2.3376);
+        assert!(area.check(&louvre).is_ok());
+
+        // Inside the Paris bounds but ~12km from City Hall
+        let orly = Coordinates::new(48.7262, 2.3652);
+        assert!(matches!(
+            area.check(&orly),
+            Err(crate::Error::OutsideServiceArea { max_km, .. }) if max_km == 5.0
+        ));
+
+        let london = Coordinates::new(51.5074, -0.1278);
+        assert!(matches!(
+            area.check(&london),
+            Err(crate::Error::InvalidCoordinates { .. })
+        ));
+    }
+
     #[test]
     fn test_bike_availability() {
         let bikes = BikeAvailability::new(5, 3);
@@ -362,23 +712,57 @@ mod tests {
         assert_eq!(DataFreshness::from_age(90.0), DataFreshness::VeryStale);
     }
 
+    #[test]
+    fn test_walk_time_rounds_up() {
+        let speed = WalkingSpeed::default();
+        assert_eq!(speed.minutes(0), 0);
+        assert_eq!(speed.minutes(80), 1);
+        assert_eq!(speed.minutes(81), 2);
+        assert_eq!(WalkingSpeed { kmh: 3.0 }.minutes(1000), 20);
+    }
+
+    #[test]
+    fn test_availability_labels() {
+        let thresholds = AvailabilityThresholds::default();
+        assert_eq!(thresholds.label(0, 20), AvailabilityLabel::Empty);
+        assert_eq!(thresholds.label(4, 16), AvailabilityLabel::Low);
+        assert_eq!(thresholds.label(10, 10), AvailabilityLabel::Balanced);
+        assert_eq!(thresholds.label(15, 5), AvailabilityLabel::NearFull);
+        assert_eq!(thresholds.label(20, 0), AvailabilityLabel::Full);
+        assert_eq!(thresholds.label(0, 0), AvailabilityLabel::Empty);
+        assert!(!AvailabilityThresholds {
+            low_below: 0.5,
+            near_full_from: 0.5
+        }
+        .is_valid());
+    }
+
     #[test]
     fn test_bike_type_filter() {
         let bikes = BikeAvailability::new(2, 3);
         let station = VelibStation {
             reference: StationReference {
-                station_code: "123".to_string(),
-                name: "Test Station".to_string(),
+                station_code: "123".into(),
+                name: "Test Station".into(),
                 coordinates: Coordinates::new(48.8566, 2.3522),
                 capacity: 20,
                 capabilities: ServiceCapabilities::default(),
+                district: None,
+                commune: None,
+                insee_code: None,
             },
             real_time: Some(RealTimeStatus {
                 bikes,
                 available_docks: 15,
                 status: StationStatus::Open,
-                last_update: Utc::now(),
+                last_update: Some(Utc::now()),
                 data_freshness: DataFreshness::Fresh,
+                unknown_freshness: false,
+                trend: None,
+                availability_label: None,
+                is_renting: true,
+                is_returning: true,
+                suspected_out_of_service: false,
             }),
         };
 
@@ -391,18 +775,27 @@ mod tests {
     fn test_station_validation() {
         let valid_station = VelibStation {
             reference: StationReference {
-                station_code: "123".to_string(),
-                name: "Test Station".to_string(),
+                station_code: "123".into(),
+                name: "Test Station".into(),
                 coordinates: Coordinates::new(48.8566, 2.3522),
                 capacity: 20,
                 capabilities: ServiceCapabilities::default(),
+                district: None,
+                commune: None,
+                insee_code: None,
             },
             real_time: Some(RealTimeStatus {
                 bikes: BikeAvailability::new(5, 3),
                 available_docks: 12,
                 status: StationStatus::Open,
-                last_update: Utc::now(),
+                last_update: Some(Utc::now()),
                 data_freshness: DataFreshness::Fresh,
+                unknown_freshness: false,
+                trend: None,
+                availability_label: None,
+                is_renting: true,
+                is_returning: true,
+                suspected_out_of_service: false,
             }),
         };
 
@@ -414,18 +807,27 @@ mod tests {
         // Test capacity overflow
         let invalid_station = VelibStation {
             reference: StationReference {
-                station_code: "123".to_string(),
-                name: "Test Station".to_string(),
+                station_code: "123".into(),
+                name: "Test Station".into(),
                 coordinates: Coordinates::new(48.8566, 2.3522),
                 capacity: 10,
                 capabilities: ServiceCapabilities::default(),
+                district: None,
+                commune: None,
+                insee_code: None,
             },
             real_time: Some(RealTimeStatus {
                 bikes: BikeAvailability::new(8, 5), // 13 bikes
                 available_docks: 5,                 // total 18 > capacity 10
                 status: StationStatus::Open,
-                last_update: Utc::now(),
+                last_update: Some(Utc::now()),
                 data_freshness: DataFreshness::Fresh,
+                unknown_freshness: false,
+                trend: None,
+                availability_label: None,
+                is_renting: true,
+                is_returning: true,
+                suspected_out_of_service: false,
             }),
         };
 
@@ -435,13 +837,46 @@ mod tests {
     #[test]
     fn test_capacity_overflow_validation() {
         let reference = StationReference {
-            station_code: "overflow_test".to_string(),
-            name: "Overflow Test Station".to_string(),
+            station_code: "overflow_test".into(),
+            name: "Overflow Test Station".into(),
             coordinates: Coordinates::new(48.8566, 2.3522),
             capacity: 300, // This should fail validation
             capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
         };
 
         assert!(reference.validate().is_err());
     }
+
+    #[test]
+    fn test_district_from_insee_code_or_station_code() {
+        let reference = |code: &str| StationReference {
+            station_code: code.into(),
+            name: "Test".into(),
+            coordinates: Coordinates::new(48.8566, 2.3522),
+            capacity: 20,
+            capabilities: ServiceCapabilities::default(),
+            district: None,
+            commune: None,
+            insee_code: None,
+        };
+
+        let by_insee = reference("99999").with_commune(Some("Paris".into()), Some("75112".into()));
+        assert_eq!(by_insee.district.as_deref(), Some("Paris 12e"));
+
+        let by_code = reference("1001").with_commune(Some("Paris".into()), Some("75056".into()));
+        assert_eq!(by_code.district.as_deref(), Some("Paris 1er"));
+
+        let suburb = reference("21010")
+            .with_commune(Some("Boulogne-Billancourt".into()), Some("92012".into()));
+        assert_eq!(suburb.district.as_deref(), Some("Boulogne-Billancourt"));
+        assert_eq!(suburb.insee_code.as_deref(), Some("92012"));
+
+        assert!(reference("12001")
+            .with_commune(None, None)
+            .district
+            .is_none());
+    }
 }
@@ -0,0 +1,116 @@
+mod common;
+
+use axum::{http::StatusCode, routing::get, Json, Router};
+use base64::prelude::{Engine as _, BASE64_URL_SAFE_NO_PAD};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::sign::Signer;
+use serde_json::{json, Value};
+use velib_mcp::mcp::{OidcConfig, OidcProvider};
+use velib_mcp::McpServer;
+
+/// An identity provider serving discovery and the public half of `key`, at the returned issuer URL
+async fn identity_provider(key: &PKey<Private>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let rsa = key.rsa().unwrap();
+    let jwks = json!({"keys": [{
+        "kty": "RSA",
+        "kid": "main",
+        "n": BASE64_URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+        "e": BASE64_URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+    }]});
+    let discovery = json!({"issuer": issuer, "jwks_uri": format!("{issuer}/jwks")});
+    let router = Router::new()
+        .route(
+            "/.well-known/openid-configuration",
+            get(move || async move { Json(discovery) }),
+        )
+        .route("/jwks", get(move || async move { Json(jwks) }));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    issuer
+}
+
+fn token(key: &PKey<Private>, claims: &Value) -> String {
+    let encode = |value: Value| BASE64_URL_SAFE_NO_PAD.encode(value.to_string());
+    let signed = format!(
+        "{}.{}",
+        encode(json!({"alg": "RS256", "kid": "main"})),
+        encode(claims.clone())
+    );
+    let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+    signer.update(signed.as_bytes()).unwrap();
+    let signature = signer.sign_to_vec().unwrap();
+    format!("{signed}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature))
+}
+
+#[tokio::test]
+async fn test_oidc_tokens_open_the_mcp_endpoints() {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let issuer = identity_provider(&key).await;
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_api_keys(&["static-key".to_string()])
+        .with_auth_provider(OidcProvider::new(OidcConfig {
+            issuer: issuer.clone(),
+            audience: Some("velib-mcp".to_string()),
+            required_scopes: vec!["velib.read".to_string()],
+            jwks_url: None,
+        }))
+        .router();
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = |sub: &str, scope: &str, exp: i64| {
+        json!({
+            "iss": issuer,
+            "sub": sub,
+            "aud": "velib-mcp",
+            "scope": scope,
+            "exp": exp,
+        })
+    };
+    let first = token(&key, &claims("alice", "velib.read", now + 300));
+    let (status, _) = common::post_mcp(
+        &router,
+        Some(&first),
+        &common::tool_call("add_favorite_station", json!({"station_code": "12001"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A refreshed token for the same subject finds the same session
+    let refreshed = token(&key, &claims("alice", "openid velib.read", now + 600));
+    let (_, listed) = common::post_mcp(
+        &router,
+        Some(&refreshed),
+        &common::tool_call("list_favorite_stations", json!({})),
+    )
+    .await;
+    let output: Value =
+        serde_json::from_str(listed["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(output["favorites"][0]["station_code"], "12001");
+
+    let (status, _) = common::post_mcp(
+        &router,
+        Some("static-key"),
+        &common::tool_call("get_system_overview", json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let other = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    for refused in [
+        None,
+        Some(token(&key, &claims("alice", "openid", now + 300))),
+        Some(token(&key, &claims("alice", "velib.read", now - 300))),
+        Some(token(&other, &claims("mallory", "velib.read", now + 300))),
+    ] {
+        let (status, _) = common::post_mcp(
+            &router,
+            refused.as_deref(),
+            &common::tool_call("get_system_overview", json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,32 @@
+use serde_json::json;
+use std::time::Duration;
+use velib_mcp::mcp::loadtest::serve_fixture;
+use velib_mcp::mcp::LoadTest;
+
+#[tokio::test]
+async fn test_bench_against_the_fixture_server() {
+    let server = format!("http://{}", serve_fixture().await.unwrap());
+    let arguments = LoadTest::sample_arguments("find_nearby_stations").unwrap();
+    let report = LoadTest::new("find_nearby_stations", arguments)
+        .with_concurrency(4)
+        .with_duration(Duration::from_millis(300))
+        .run(&server)
+        .await
+        .unwrap();
+    assert!(report.requests() > 0);
+    assert_eq!(report.failed(), 0, "{report}");
+    assert!(report.percentile(50.0) <= report.percentile(99.0));
+
+    // Failures are counted by the kind the server reports
+    let report = LoadTest::new(
+        "find_nearby_stations",
+        json!({"latitude": 91.0, "longitude": 2.35}),
+    )
+    .with_concurrency(2)
+    .with_duration(Duration::from_millis(100))
+    .run(&server)
+    .await
+    .unwrap();
+    assert_eq!(report.failed() as usize, report.requests());
+    assert_eq!(report.errors.len(), 1, "{report}");
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::fixture_handler;
+use serde::Deserialize;
+use velib_mcp::mcp::{McpClient, SearchStationsByNameInput};
+use velib_mcp::{Error, McpServer, StationReference};
+
+async fn serve_fixture() -> String {
+    let server = McpServer::with_tool_handler(fixture_handler().await);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = server.router();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    format!("http://{addr}")
+}
+
+#[tokio::test]
+async fn test_typed_client_round_trip() {
+    let client = McpClient::new(&serve_fixture().await).with_api_key("commuter");
+
+    let info = client.initialize().await.unwrap();
+    assert_eq!(info["serverInfo"]["name"], "velib-mcp");
+
+    let found = client
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "Gare de Lyon".to_string(),
+            limit: 5,
+            fuzzy: false,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(found.stations.len(), 2);
+
+    let unknown = client
+        .request("nonexistent/method", serde_json::json!({}))
+        .await;
+    assert!(matches!(unknown, Err(Error::McpProtocol(_))));
+}
+
+#[derive(Deserialize)]
+struct ReferenceResource {
+    stations: Vec<StationReference>,
+}
+
+#[tokio::test]
+async fn test_resources_in_msgpack() {
+    let base_url = serve_fixture().await;
+
+    let raw = reqwest::Client::new()
+        .get(format!("{base_url}/resources/velib://stations/reference"))
+        .header("Accept", "application/msgpack")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(raw.headers()["content-type"], "application/msgpack");
+
+    let json: ReferenceResource = McpClient::new(&base_url)
+        .read_resource("velib://stations/reference")
+        .await
+        .unwrap();
+    let msgpack: ReferenceResource = McpClient::new(&base_url)
+        .with_msgpack()
+        .read_resource("velib://stations/reference")
+        .await
+        .unwrap();
+    assert!(!msgpack.stations.is_empty());
+    assert_eq!(
+        serde_json::to_value(&msgpack.stations).unwrap(),
+        serde_json::to_value(&json.stations).unwrap()
+    );
+
+    let missing = McpClient::new(&base_url)
+        .with_msgpack()
+        .read_resource::<serde_json::Value>("velib://nowhere")
+        .await;
+    assert!(matches!(missing, Err(Error::ResourceNotFound { .. })));
+}
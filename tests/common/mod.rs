@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tower::ServiceExt;
+use velib_mcp::fixtures::{self, open_station};
+use velib_mcp::{McpToolHandler, RealTimeStatus, StationReference, VelibDataClient, VelibStation};
+
+/// A small fixed snapshot of central Paris, each station open with 18 docks:
+/// 5 mechanical and 3 electric bikes and 10 free docks
+pub fn fixture_snapshot() -> Vec<VelibStation> {
+    vec![
+        open_station("12001", "Gare de Lyon - Diderot", 48.8446, 2.3737),
+        open_station("12002", "Gare de Lyon - Chalon", 48.8440, 2.3755),
+        open_station(
+            "13001",
+            "Bibliothèque - Quai François Mauriac",
+            48.8335,
+            2.3765,
+        ),
+        open_station("4001", "Hôtel de Ville", 48.8566, 2.3522),
+        open_station("18001", "Lyon Street Garden", 48.8900, 2.3400),
+    ]
+}
+
+/// The reference data of [`fixture_snapshot`]
+pub fn fixture_stations() -> Vec<StationReference> {
+    fixtures::split(&fixture_snapshot()).0
+}
+
+/// The real-time status of every [`fixture_snapshot`] station
+pub fn fixture_realtime() -> HashMap<String, RealTimeStatus> {
+    fixtures::split(&fixture_snapshot()).1
+}
+
+/// [`fixtures::seeded_client`] for a snapshot given as reference data and
+/// statuses by station code, as tests changing a few statuses hold it
+pub async fn seeded_client(
+    stations: Vec<StationReference>,
+    mut realtime: HashMap<String, RealTimeStatus>,
+) -> VelibDataClient {
+    let snapshot: Vec<VelibStation> = stations
+        .into_iter()
+        .map(|reference| VelibStation {
+            real_time: realtime.remove(&*reference.station_code),
+            reference,
+        })
+        .collect();
+    fixtures::seeded_client(&snapshot).await
+}
+
+/// Build a handler serving the fixture snapshot
+pub async fn fixture_handler() -> McpToolHandler {
+    McpToolHandler::with_data_client(fixtures::seeded_client(&fixture_snapshot()).await)
+}
+
+/// A JSON-RPC `tools/call` of `tool` with `arguments`
+pub fn tool_call(tool: &str, arguments: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": tool, "arguments": arguments}
+    })
+}
+
+/// A POST of the JSON-RPC `body` to `/mcp`, with `bearer` as its bearer token
+pub fn mcp_request(bearer: Option<&str>, body: &Value) -> Request<Body> {
+    let mut request = Request::post("/mcp").header(header::CONTENT_TYPE, "application/json");
+    if let Some(bearer) = bearer {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {bearer}"));
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+/// Send `request` through `router`, returning the status and body text
+pub async fn send_text(router: &Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+/// Send `request` through `router`, returning the status and JSON body, null
+/// when there is none
+pub async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let (status, text) = send_text(router, request).await;
+    let body = if text.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&text).unwrap()
+    };
+    (status, body)
+}
+
+/// POST the JSON-RPC `body` to the router's `/mcp`, see [`mcp_request`]
+pub async fn post_mcp(router: &Router, bearer: Option<&str>, body: &Value) -> (StatusCode, Value) {
+    send(router, mcp_request(bearer, body)).await
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use velib_mcp::{AppConfig, McpServer, Server};
+
+fn reload(token: &str) -> Request<Body> {
+    Request::post("/admin/config/reload")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_admin_reload_applies_tunables() {
+    let config = Arc::new(Mutex::new(AppConfig::default()));
+    let source = {
+        let config = Arc::clone(&config);
+        Arc::new(move || Ok(config.lock().unwrap().clone()))
+    };
+    let server = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_admin_token("s3cret")
+        .with_config_source(source);
+    let router = server.router();
+    assert_eq!(server.config_version().version, 1);
+
+    let (status, _) = common::send(&router, reload("guess")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    config.lock().unwrap().quota_limits.daily = Some(1);
+    let (status, version) = common::send(&router, reload("s3cret")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(version["version"], 2);
+    assert!(version["loaded_at"].is_string());
+
+    let lookup = common::tool_call("get_station_by_code", json!({"station_code": "12001"}));
+    let (_, allowed) = common::post_mcp(&router, Some("commuter"), &lookup).await;
+    assert!(allowed["result"].is_object(), "{allowed}");
+    let (_, refused) = common::post_mcp(&router, Some("commuter"), &lookup).await;
+    assert_eq!(refused["error"]["data"]["error_type"], "quota_exceeded");
+
+    // A configuration that can't be applied leaves the current one in effect
+    config.lock().unwrap().log_level = Some("velib_mcp=loud".to_string());
+    let (status, failed) = common::send(&router, reload("s3cret")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(failed["error"].as_str().unwrap().contains("log level"));
+    assert_eq!(failed["config"]["version"], 2);
+    assert_eq!(server.config_version().version, 2);
+}
+
+#[tokio::test]
+async fn test_health_reports_config_version() {
+    let router = Server::new("127.0.0.1:0".parse().unwrap()).router();
+    let (status, health) = common::send(
+        &router,
+        Request::get("/health").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["config"]["version"], 1);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use serde_json::json;
+use velib_mcp::McpServer;
+
+#[tokio::test]
+async fn test_repeated_calls_are_served_from_cache() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let (_, first) = common::post_mcp(
+        &router,
+        Some("dedup"),
+        &common::tool_call("get_station_by_code", json!({"station_code": "4001"})),
+    )
+    .await;
+    let (_, second) = common::post_mcp(
+        &router,
+        Some("dedup"),
+        &common::tool_call("get_station_by_code", json!({"station_code": "4001"})),
+    )
+    .await;
+    assert_eq!(first["result"]["_meta"]["cache"], "miss");
+    assert_eq!(second["result"]["_meta"]["cache"], "hit");
+    assert_eq!(first["result"]["content"], second["result"]["content"]);
+
+    let nearby = json!({"latitude": 48.8443, "longitude": 2.3730, "radius_meters": 1000});
+    let reordered = json!({"radius_meters": 1000, "longitude": 2.3730, "latitude": 48.8443});
+    common::post_mcp(
+        &router,
+        Some("dedup"),
+        &common::tool_call("find_nearby_stations", nearby),
+    )
+    .await;
+    let (_, repeated) = common::post_mcp(
+        &router,
+        Some("dedup"),
+        &common::tool_call("find_nearby_stations", reordered),
+    )
+    .await;
+    assert_eq!(repeated["result"]["_meta"]["cache"], "hit");
+
+    let (_, favorites) = common::post_mcp(
+        &router,
+        Some("dedup"),
+        &common::tool_call("list_favorite_stations", json!({})),
+    )
+    .await;
+    assert_eq!(favorites["result"]["_meta"]["cache"], "bypass");
+}
+
+#[tokio::test]
+async fn test_zero_ttl_disables_deduplication() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_dedup_ttl(std::time::Duration::ZERO)
+        .router();
+
+    for _ in 0..2 {
+        let (_, response) = common::post_mcp(
+            &router,
+            Some("dedup"),
+            &common::tool_call("get_station_by_code", json!({"station_code": "4001"})),
+        )
+        .await;
+        assert_eq!(response["result"]["_meta"]["cache"], "bypass");
+    }
+}
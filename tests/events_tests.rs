@@ -0,0 +1,222 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request},
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+use tower::ServiceExt;
+use velib_mcp::{McpServer, ServerEvent, StationUpdate};
+
+#[tokio::test]
+async fn test_events_stream_availability_changes() {
+    let handler = common::fixture_handler().await;
+    let events = handler.events().await;
+    let server = McpServer::with_tool_handler(handler);
+
+    let response = server
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let realtime = common::fixture_realtime();
+    let current: HashMap<String, StationUpdate> = realtime
+        .iter()
+        .map(|(code, status)| (code.clone(), StationUpdate::new(code, status)))
+        .collect();
+    let published = events
+        .publish_availability(&HashMap::new(), &current)
+        .unwrap();
+
+    let mut body = response.into_body().into_data_stream();
+    let frame = body.next().await.unwrap().unwrap();
+    let frame = String::from_utf8(frame.to_vec()).unwrap();
+    assert!(frame.contains("event: availability_changed"));
+    assert!(frame.contains(&format!("id: {}", published.id())));
+
+    let data = frame
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .unwrap();
+    let event: ServerEvent = serde_json::from_str(data).unwrap();
+    let ServerEvent::AvailabilityChanged { stations, .. } = event else {
+        panic!("expected an availability change, got {event:?}");
+    };
+    assert_eq!(stations.len(), 5);
+}
+
+#[tokio::test]
+async fn test_subscribed_resources_are_notified_on_refresh() {
+    let handler = common::fixture_handler().await;
+    let events = handler.events().await;
+    let router = McpServer::with_tool_handler(handler).router();
+
+    let (_, subscribed) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "resources/subscribe",
+            "params": {"uri": "velib://stations/realtime"}
+        }),
+    )
+    .await;
+    assert!(subscribed["result"].is_object(), "{subscribed}");
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/events")
+                .header(header::AUTHORIZATION, "Bearer commuter")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let realtime = common::fixture_realtime();
+    let current: HashMap<String, StationUpdate> = realtime
+        .iter()
+        .map(|(code, status)| (code.clone(), StationUpdate::new(code, status)))
+        .collect();
+    events.publish_availability(&HashMap::new(), &current);
+
+    let mut body = response.into_body().into_data_stream();
+    let mut received = String::new();
+    while !received.contains("event: message") {
+        let frame = body.next().await.unwrap().unwrap();
+        received.push_str(std::str::from_utf8(&frame).unwrap());
+    }
+    let notification: Value = received
+        .split("event: message")
+        .nth(1)
+        .and_then(|rest| rest.lines().find_map(|line| line.strip_prefix("data: ")))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .unwrap();
+    assert_eq!(notification["method"], "notifications/resources/updated");
+    assert_eq!(notification["params"]["uri"], "velib://stations/realtime");
+}
+
+#[tokio::test]
+async fn test_announcements_reach_tool_results_and_streams() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_admin_token("s3cret")
+        .router();
+    let admin = |method: &str, uri: &str, body: Value| {
+        Request::builder()
+            .uri(uri)
+            .method(method)
+            .header(header::AUTHORIZATION, "Bearer s3cret")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let lookup = common::tool_call("get_station_by_code", json!({"station_code": "12001"}));
+
+    let (_, quiet) = common::post_mcp(&router, None, &lookup).await;
+    assert!(quiet["result"]["_meta"].get("announcements").is_none());
+
+    let stream = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let (status, announcement) = common::send(
+        &router,
+        admin(
+            "POST",
+            "/admin/announcements",
+            json!({"kind": "degraded_upstream", "message": "Open Data is lagging"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 201);
+    assert_eq!(announcement["kind"], "degraded_upstream");
+
+    let mut body = stream.into_body().into_data_stream();
+    let mut received = String::new();
+    while !received.contains("event: message") {
+        let frame = body.next().await.unwrap().unwrap();
+        received.push_str(std::str::from_utf8(&frame).unwrap());
+    }
+    assert!(received.contains("event: service_announcements"));
+    let notification: Value = received
+        .split("event: message")
+        .nth(1)
+        .and_then(|rest| rest.lines().find_map(|line| line.strip_prefix("data: ")))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .unwrap();
+    assert_eq!(notification["method"], "notifications/message");
+    assert_eq!(notification["params"]["level"], "warning");
+    assert_eq!(
+        notification["params"]["data"]["announcements"][0]["message"],
+        "Open Data is lagging"
+    );
+
+    let (_, noted) = common::post_mcp(&router, None, &lookup).await;
+    assert_eq!(
+        noted["result"]["_meta"]["announcements"][0]["id"],
+        announcement["id"]
+    );
+
+    let (status, _) = common::send(
+        &router,
+        admin(
+            "POST",
+            "/admin/announcements",
+            json!({"kind": "info", "message": " "}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 422);
+    let withdraw = format!(
+        "/admin/announcements/{}",
+        announcement["id"].as_str().unwrap()
+    );
+    let (status, _) = common::send(&router, admin("DELETE", &withdraw, Value::Null)).await;
+    assert_eq!(status, 204);
+    let (status, _) = common::send(&router, admin("DELETE", &withdraw, Value::Null)).await;
+    assert_eq!(status, 404);
+    let (_, listing) =
+        common::send(&router, admin("GET", "/admin/announcements", Value::Null)).await;
+    assert_eq!(listing["announcements"], json!([]));
+}
+
+#[cfg(feature = "dashboard")]
+#[tokio::test]
+async fn test_dashboard_page_is_served() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+    let response = server
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/dashboard")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let page = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(page.contains("new EventSource(\"/events\")"));
+}
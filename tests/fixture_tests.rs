@@ -0,0 +1,86 @@
+use velib_mcp::fixtures;
+use velib_mcp::mcp::types::{AvailabilityFilter, FindNearbyStationsInput};
+use velib_mcp::{DataFreshness, McpToolHandler, StationStatus};
+
+fn nearby(
+    latitude: f64,
+    longitude: f64,
+    filter: Option<AvailabilityFilter>,
+) -> FindNearbyStationsInput {
+    FindNearbyStationsInput {
+        latitude,
+        longitude,
+        radius_meters: 500,
+        limit: 50,
+        availability_filter: filter,
+        sort: Default::default(),
+        lenient: None,
+        district: None,
+        auto_expand: false,
+        barrier_aware: false,
+        min_results: 1,
+        near_transit: None,
+        transit_radius_meters: None,
+    }
+}
+
+#[tokio::test]
+async fn test_fixture_scenarios_through_the_tools() {
+    let central = fixtures::central_paris();
+    assert!(central
+        .iter()
+        .all(|station| station.reference.validate().is_ok()));
+    let handler = McpToolHandler::with_data_client(fixtures::seeded_client(&central).await);
+    let chatelet = handler
+        .find_nearby_stations(nearby(48.8589, 2.3470, None))
+        .await
+        .unwrap();
+    assert!(chatelet.stations.len() >= 10);
+    let with_bikes = handler
+        .find_nearby_stations(nearby(
+            48.8589,
+            2.3470,
+            Some(AvailabilityFilter {
+                min_bikes: Some(1),
+                ..Default::default()
+            }),
+        ))
+        .await
+        .unwrap();
+    assert!(with_bikes.stations.len() < chatelet.stations.len());
+    let overview = handler.get_system_overview().await.unwrap();
+    assert_eq!(overview.system.total_stations, 16);
+    assert_eq!(overview.system.operational_stations, 16);
+
+    // Between Vincennes and Fontenay-sous-Bois, a kilometre from either station
+    let handler =
+        McpToolHandler::with_data_client(fixtures::seeded_client(&fixtures::sparse_suburb()).await);
+    let between = handler
+        .find_nearby_stations(nearby(48.8560, 2.4520, None))
+        .await
+        .unwrap();
+    assert!(between.stations.is_empty());
+    let vincennes = handler
+        .find_nearby_stations(nearby(48.8440, 2.4380, None))
+        .await
+        .unwrap();
+    assert_eq!(vincennes.stations.len(), 1);
+
+    let closed = fixtures::all_closed();
+    assert!(closed.iter().all(|station| station
+        .real_time
+        .as_ref()
+        .is_some_and(|rt| rt.status == StationStatus::Closed && rt.bikes.total() == 0)));
+    let handler = McpToolHandler::with_data_client(fixtures::seeded_client(&closed).await);
+    let overview = handler.get_system_overview().await.unwrap();
+    assert_eq!(overview.system.operational_stations, 0);
+    assert_eq!(overview.system.available_bikes.total, 0);
+
+    let stale = fixtures::stale_data();
+    assert!(stale.iter().all(|station| station
+        .real_time
+        .as_ref()
+        .is_some_and(|rt| rt.data_freshness == DataFreshness::VeryStale)));
+    let (references, statuses) = fixtures::split(&stale);
+    assert_eq!((references.len(), statuses.len()), (16, 16));
+}
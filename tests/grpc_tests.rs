@@ -0,0 +1,191 @@
+#![cfg(feature = "grpc")]
+
+mod common;
+
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+use velib_mcp::mcp::grpc::proto::{self, velib_client::VelibClient};
+use velib_mcp::mcp::grpc::serve;
+use velib_mcp::mcp::{AuthFuture, AuthProvider, Principal};
+use velib_mcp::McpServer;
+
+async fn connect(server: McpServer) -> VelibClient<Channel> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, server));
+    VelibClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap()
+}
+
+fn gare_de_lyon() -> proto::Coordinates {
+    proto::Coordinates {
+        latitude: 48.8443,
+        longitude: 2.3741,
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_serves_the_station_lookups() {
+    let mut client = connect(McpServer::with_tool_handler(
+        common::fixture_handler().await,
+    ))
+    .await;
+
+    let nearby = client
+        .find_nearby_stations(proto::FindNearbyStationsRequest {
+            latitude: 48.8443,
+            longitude: 2.3741,
+            limit: Some(2),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(nearby.stations.len(), 2);
+    assert_eq!(nearby.radius_meters, 500);
+    let closest = nearby.stations[0].station.as_ref().unwrap();
+    assert!(closest.station_code.starts_with("1200"));
+    let real_time = closest.real_time.as_ref().unwrap();
+    assert_eq!(real_time.mechanical_bikes, 5);
+    assert_eq!(real_time.status(), proto::StationStatus::Open);
+
+    let found = client
+        .get_station_by_code(proto::GetStationByCodeRequest {
+            station_code: "12001".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(found.found);
+    assert_eq!(found.station.unwrap().name, "Gare de Lyon - Diderot");
+    let missing = client
+        .get_station_by_code(proto::GetStationByCodeRequest {
+            station_code: "99999".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!missing.found && missing.station.is_none());
+
+    let search = client
+        .search_stations_by_name(proto::SearchStationsByNameRequest {
+            query: "Gare de Lyon".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(search.total_found, 2);
+
+    let stats = client
+        .get_area_statistics(proto::GetAreaStatisticsRequest {
+            bounds: Some(proto::Bounds {
+                north: 48.86,
+                south: 48.83,
+                east: 2.38,
+                west: 2.34,
+            }),
+            polygon: None,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let area = stats.area_stats.unwrap();
+    assert_eq!(area.total_stations, 4);
+    assert_eq!(area.electric_bikes, 12);
+
+    let journey = client
+        .plan_bike_journey(proto::PlanBikeJourneyRequest {
+            origin: Some(gare_de_lyon()),
+            destination: Some(proto::Coordinates {
+                latitude: 48.8566,
+                longitude: 2.3522,
+            }),
+            bike_types: vec![
+                proto::BikeType::Electric.into(),
+                proto::BikeType::Mechanical.into(),
+            ],
+            max_walk_distance: None,
+            barrier_aware: false,
+            include_route: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let recommendation = &journey.recommendations[0];
+    assert_eq!(recommendation.bike_type(), proto::BikeType::Electric);
+    assert_eq!(recommendation.preference_level, 1);
+    assert!(recommendation.route_polyline.is_some());
+}
+
+#[tokio::test]
+async fn test_grpc_errors_and_api_keys() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_api_keys(&["commuter".to_string()]);
+    let mut client = connect(server).await;
+    let lookup = || proto::GetStationByCodeRequest {
+        station_code: "12001".to_string(),
+    };
+
+    let refused = client.get_station_by_code(lookup()).await.unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+
+    let mut request = Request::new(lookup());
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer commuter".parse().unwrap());
+    assert!(client.get_station_by_code(request).await.is_ok());
+
+    let mut request = Request::new(proto::FindNearbyStationsRequest {
+        latitude: 91.0,
+        longitude: 2.35,
+        ..Default::default()
+    });
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer commuter".parse().unwrap());
+    let invalid = client.find_nearby_stations(request).await.unwrap_err();
+    assert_eq!(invalid.code(), Code::InvalidArgument);
+}
+
+/// Accepts the token `badge-7` as the member `7`
+struct Badges;
+
+impl AuthProvider for Badges {
+    fn authenticate<'a>(&'a self, token: &'a str) -> AuthFuture<'a> {
+        Box::pin(async move { (token == "badge-7").then(|| Principal::new("badge", "7")) })
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_uses_the_servers_identity_providers() {
+    let server =
+        McpServer::with_tool_handler(common::fixture_handler().await).with_auth_provider(Badges);
+    let mut client = connect(server).await;
+    let lookup = || proto::GetStationByCodeRequest {
+        station_code: "12001".to_string(),
+    };
+
+    let refused = client.get_station_by_code(lookup()).await.unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+    let mut request = Request::new(lookup());
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer badge-8".parse().unwrap());
+    let refused = client.get_station_by_code(request).await.unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+
+    let mut request = Request::new(lookup());
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer badge-7".parse().unwrap());
+    assert!(
+        client
+            .get_station_by_code(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .found
+    );
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use axum::{body::Body, http::Request};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use tower::ServiceExt;
+use velib_mcp::{BikeAvailability, McpServer, McpToolHandler, RealTimeStatus, StationStatus};
+
+async fn health(server: &McpServer) -> Value {
+    let response = server
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/resources/velib://health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_health_reads_do_not_hit_upstream() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+
+    for _ in 0..3 {
+        let health = health(&server).await;
+        assert_eq!(health["status"], "healthy");
+        assert_eq!(health["data_sources"]["reference"]["status"], "unknown");
+        assert_eq!(health["data_sources"]["real_time"]["status"], "unknown");
+        assert!(health["data_sources"]["last_checked"].is_null());
+        assert_eq!(health["metrics"]["upstream_fetches"], 0);
+    }
+}
+
+#[tokio::test]
+async fn test_health_real_time_lag_follows_newest_update() {
+    let server = McpServer::with_tool_handler(McpToolHandler::new());
+    let real_time = &health(&server).await["data_sources"]["real_time"];
+    assert!(real_time["last_update"].is_null(), "{real_time}");
+    assert!(real_time["lag_seconds"].is_null());
+
+    let stations = common::fixture_stations();
+    let published = Utc::now() - Duration::minutes(5);
+    let realtime = stations
+        .iter()
+        .map(|station| {
+            let status = RealTimeStatus::new(
+                BikeAvailability::new(5, 3),
+                10,
+                StationStatus::Open,
+                published,
+            );
+            (station.station_code.to_string(), status)
+        })
+        .collect();
+    let handler = McpToolHandler::with_data_client(common::seeded_client(stations, realtime).await);
+    let server = McpServer::with_tool_handler(handler);
+    let real_time = &health(&server).await["data_sources"]["real_time"];
+    let last_update: DateTime<Utc> = real_time["last_update"].as_str().unwrap().parse().unwrap();
+    assert_eq!(last_update, published);
+    let lag = real_time["lag_seconds"].as_i64().unwrap();
+    assert!((300..310).contains(&lag), "{real_time}");
+}
+
+#[tokio::test]
+async fn test_health_reports_history_memory() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+
+    let before = health(&server).await;
+    assert_eq!(before["history"]["retention_hours"], 24);
+    assert!(before["history"]["last_compacted_at"].is_null());
+    assert!(before["advisories"].as_array().unwrap().is_empty());
+    assert_eq!(before["change_log"]["entries"], 1);
+    assert_eq!(before["change_log"]["retention_hours"], 2.0);
+    assert_eq!(before["change_log"]["max_entries"], 120);
+    assert_eq!(before["change_log"]["spills_to_storage"], false);
+    assert_eq!(before["memory"]["reference_stations"], 5);
+    assert_eq!(before["memory"]["realtime_stations"], 5);
+    assert_eq!(before["memory"]["history_samples"], 5);
+    assert!(before["memory"]["soft_limit_bytes"].is_null());
+    assert_eq!(before["memory"]["trims"], 0);
+
+    server.spawn_history_compaction(std::time::Duration::from_secs(60));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let after = health(&server).await;
+    assert!(after["history"]["last_compacted_at"].is_string());
+    assert!(after["history"]["approx_bytes"].is_u64());
+}
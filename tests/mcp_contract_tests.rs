@@ -0,0 +1,378 @@
+//! Protocol conformance: every response shape is checked against the MCP schema
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use velib_mcp::McpServer;
+
+const MCP_SCHEMA: &str = include_str!("schema/mcp-2024-11-05.json");
+
+/// Assert that `instance` is a valid `definition` of the MCP schema
+fn assert_conforms(definition: &str, instance: &Value) {
+    let mut schema: Value = serde_json::from_str(MCP_SCHEMA).unwrap();
+    schema["$ref"] = json!(format!("#/definitions/{definition}"));
+    let validator = jsonschema::validator_for(&schema).unwrap();
+
+    let errors: Vec<String> = validator
+        .iter_errors(instance)
+        .map(|error| format!("{} at {}", error, error.instance_path()))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "{definition} violations: {errors:#?}\nin {instance:#}"
+    );
+}
+
+async fn post(router: &Router, api_key: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let (status, raw) = common::send_text(router, common::mcp_request(api_key, &body)).await;
+    let body = if raw.is_empty() {
+        Value::Null
+    } else {
+        // Parsing into a `Value` would silently drop a duplicated envelope key
+        assert_eq!(raw.matches("\"jsonrpc\":").count(), 1, "envelope: {raw}");
+        serde_json::from_str(&raw).unwrap()
+    };
+    (status, body)
+}
+
+/// Send a request and check the envelope and result against `result_definition`
+async fn assert_result(
+    router: &Router,
+    method: &str,
+    params: Value,
+    result_definition: &str,
+) -> Value {
+    let (status, response) = post(
+        router,
+        Some("contract"),
+        json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": params}),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_conforms("JSONRPCResponse", &response);
+    assert_eq!(response["id"], 7);
+    assert_conforms(result_definition, &response["result"]);
+    response["result"].clone()
+}
+
+/// Send a request and check it fails with a well-formed JSON-RPC error
+async fn assert_error(router: &Router, method: &str, params: Value) -> Value {
+    let (_, response) = post(
+        router,
+        Some("contract"),
+        json!({"jsonrpc": "2.0", "id": "req-1", "method": method, "params": params}),
+    )
+    .await;
+
+    assert_conforms("JSONRPCError", &response);
+    assert_eq!(response["id"], "req-1");
+    assert!(response.get("result").is_none());
+    response["error"].clone()
+}
+
+async fn router() -> Router {
+    McpServer::with_tool_handler(common::fixture_handler().await).router()
+}
+
+#[tokio::test]
+async fn test_initialize_and_ping() {
+    let router = router().await;
+
+    let result = assert_result(
+        &router,
+        "initialize",
+        json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "contract-tests", "version": "0"}
+        }),
+        "InitializeResult",
+    )
+    .await;
+    assert_eq!(result["protocolVersion"], "2024-11-05");
+
+    assert_result(&router, "ping", json!({}), "EmptyResult").await;
+}
+
+#[tokio::test]
+async fn test_notifications_get_no_response() {
+    let (status, body) = post(
+        &router().await,
+        None,
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(body.is_null());
+}
+
+#[tokio::test]
+async fn test_tools_list_and_every_tool_call() {
+    let router = router().await;
+
+    // `params` is optional for list requests
+    let (_, listed) = post(
+        &router,
+        None,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+    )
+    .await;
+    assert_conforms("ListToolsResult", &listed["result"]);
+
+    let calls = [
+        (
+            "find_nearby_stations",
+            json!({"latitude": 48.8446, "longitude": 2.3737}),
+        ),
+        ("get_station_by_code", json!({"station_code": "12001"})),
+        ("search_stations_by_name", json!({"query": "Gare de Lyon"})),
+        ("autocomplete_station_names", json!({"prefix": "Gare"})),
+        ("set_preferences", json!({"bike_type": "electric"})),
+        ("add_favorite_station", json!({"station_code": "12001"})),
+        ("list_favorite_stations", json!({})),
+        ("get_favorites_status", json!({})),
+        (
+            "render_station_sparkline",
+            json!({"station_code": "12001", "format": "svg"}),
+        ),
+        (
+            "get_area_statistics",
+            json!({"bounds": {"north": 48.86, "south": 48.83, "east": 2.38, "west": 2.34}}),
+        ),
+        ("get_station_density", json!({"district": "12e"})),
+        (
+            "find_stations_along_route",
+            json!({"waypoints": [
+                {"latitude": 48.8446, "longitude": 2.3737},
+                {"latitude": 48.8566, "longitude": 2.3522}
+            ]}),
+        ),
+        (
+            "compare_stations",
+            json!({
+                "station_codes": ["12001", "12002"],
+                "reference_point": {"latitude": 48.8446, "longitude": 2.3737}
+            }),
+        ),
+        (
+            "check_dropoff_feasibility",
+            json!({"destination": {"latitude": 48.8446, "longitude": 2.3737}}),
+        ),
+        ("compare_with_typical", json!({"station_code": "12001"})),
+        ("diff_station_status", json!({})),
+        (
+            "generate_journey_link",
+            json!({"pickup_station_code": "12001", "dropoff_station_code": "4001"}),
+        ),
+        ("get_system_overview", json!({})),
+        ("get_fleet_composition", json!({"window_minutes": 60})),
+        (
+            "plan_bike_journey",
+            json!({
+                "origin": {"latitude": 48.8446, "longitude": 2.3737},
+                "destination": {"latitude": 48.8566, "longitude": 2.3522}
+            }),
+        ),
+        (
+            "plan_multiple_journeys",
+            json!({"journeys": [
+                {
+                    "origin": {"latitude": 48.8446, "longitude": 2.3737},
+                    "destination": {"latitude": 48.8566, "longitude": 2.3522}
+                },
+                {
+                    "origin": {"latitude": 48.8566, "longitude": 2.3522},
+                    "destination": {"latitude": 48.8446, "longitude": 2.3737}
+                }
+            ]}),
+        ),
+    ];
+
+    let listed_names: Vec<&str> = listed["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    let called_names: Vec<&str> = calls.iter().map(|(name, _)| *name).collect();
+    for name in &listed_names {
+        assert!(called_names.contains(name), "no contract call for {name}");
+    }
+
+    for (name, arguments) in calls {
+        let result = assert_result(
+            &router,
+            "tools/call",
+            json!({"name": name, "arguments": arguments}),
+            "CallToolResult",
+        )
+        .await;
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(
+            serde_json::from_str::<Value>(text).is_ok(),
+            "{name} text content is not JSON"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_resources_list_and_read() {
+    let router = router().await;
+
+    let listed = assert_result(&router, "resources/list", json!({}), "ListResourcesResult").await;
+
+    // Station resources need the live API; the rest are served from the fixture
+    for uri in [
+        "velib://health",
+        "velib://digest/morning",
+        "velib://stats/usage",
+        "velib://coverage/gaps",
+        "velib://reports/reference-changes",
+        "velib://docs/errors",
+    ] {
+        assert!(listed["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|resource| resource["uri"] == uri));
+
+        let read = assert_result(
+            &router,
+            "resources/read",
+            json!({"uri": uri}),
+            "ReadResourceResult",
+        )
+        .await;
+        assert_eq!(read["contents"][0]["uri"], uri);
+    }
+}
+
+#[tokio::test]
+async fn test_prompts_list_and_get() {
+    let router = router().await;
+
+    let listed = assert_result(&router, "prompts/list", json!({}), "ListPromptsResult").await;
+    assert!(!listed["prompts"].as_array().unwrap().is_empty());
+
+    assert_result(
+        &router,
+        "prompts/get",
+        json!({"name": "find_bike_near", "arguments": {"place": "BnF"}}),
+        "GetPromptResult",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_error_objects() {
+    let router = router().await;
+
+    let error = assert_error(&router, "does/not/exist", json!({})).await;
+    assert_eq!(error["code"], -32601);
+
+    let error = assert_error(&router, "resources/read", json!({"uri": "velib://nowhere"})).await;
+    assert_eq!(error["code"], -32002);
+
+    let error = assert_error(
+        &router,
+        "tools/call",
+        json!({"name": "get_station_by_code", "arguments": {}}),
+    )
+    .await;
+    assert!(error["message"].is_string());
+
+    let error = assert_error(
+        &router,
+        "prompts/get",
+        json!({"name": "plan_trip", "arguments": {"origin": "Bastille"}}),
+    )
+    .await;
+    assert_eq!(error["code"], -32602);
+}
+
+#[tokio::test]
+async fn test_results_carry_timing_meta() {
+    let router = router().await;
+
+    let pong = assert_result(&router, "ping", json!({}), "EmptyResult").await;
+    assert!(pong["_meta"]["processing_ms"].as_f64().unwrap() >= 0.0);
+
+    let result = assert_result(
+        &router,
+        "tools/call",
+        json!({"name": "get_station_by_code", "arguments": {"station_code": "4001"}}),
+        "CallToolResult",
+    )
+    .await;
+    let meta = &result["_meta"];
+    assert_eq!(meta["cache"], "miss");
+    assert!(meta["processing_ms"].is_number());
+    // The fixture snapshot was seeded when the router was built
+    let snapshot_at =
+        chrono::DateTime::parse_from_rfc3339(meta["data_snapshot_at"].as_str().unwrap()).unwrap();
+    assert!(
+        chrono::Utc::now() - snapshot_at.with_timezone(&chrono::Utc) < chrono::Duration::minutes(1)
+    );
+    assert!(meta["data_version"].as_u64().unwrap() >= 1);
+    assert_eq!(meta["data_hash"].as_str().unwrap().len(), 16);
+
+    // A resource read from the same snapshot reports the same data
+    let resource = assert_result(
+        &router,
+        "resources/read",
+        json!({"uri": "velib://stations/realtime"}),
+        "ReadResourceResult",
+    )
+    .await;
+    assert_eq!(resource["_meta"]["data_version"], meta["data_version"]);
+    assert_eq!(resource["_meta"]["data_hash"], meta["data_hash"]);
+}
+
+#[tokio::test]
+async fn test_malformed_bodies_get_jsonrpc_errors() {
+    let router = router().await;
+
+    let cases = [
+        ("{\"jsonrpc\": \"2.0\", \"id\": 1,", -32700, Value::Null),
+        ("{\"jsonrpc\": \"2.0\", \"id\": 9}", -32600, json!(9)),
+        ("[]", -32600, Value::Null),
+    ];
+    for (body, code, id) in cases {
+        // Deliberately without a content type, which the JSON extractor used to reject
+        let response = router
+            .clone()
+            .oneshot(Request::post("/mcp").body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/json",
+            "{body}"
+        );
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert_eq!(response["id"], id, "{body}");
+        assert_eq!(response["error"]["code"], code, "{body}");
+        assert!(response.get("result").is_none());
+    }
+}
+
+#[test]
+#[should_panic(expected = "CallToolResult violations")]
+fn test_schema_rejects_malformed_results() {
+    // Guards against a schema that accidentally accepts anything
+    assert_conforms("CallToolResult", &json!({"content": [{"type": "text"}]}));
+}
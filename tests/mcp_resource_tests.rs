@@ -151,7 +151,13 @@ async fn test_health_endpoint_returns_real_metrics() {
     // Validate data source statuses are real
     let data_sources = &json_response["data_sources"];
     assert!(data_sources["real_time"]["status"].is_string());
-    assert!(data_sources["real_time"]["last_update"].is_string());
+    // Without data yet there is no update to report, nor a lag
+    let real_time = &data_sources["real_time"];
+    assert!(real_time["last_update"].is_string() || real_time["last_update"].is_null());
+    assert_eq!(
+        real_time["lag_seconds"].is_null(),
+        real_time["last_update"].is_null()
+    );
     assert!(data_sources["reference"]["status"].is_string());
 
     // The hit_rate should not be exactly 0.85 (hardcoded value) when testing with real data
@@ -0,0 +1,46 @@
+mod common;
+
+use axum::{body::Body, http::Request};
+use serde_json::json;
+use tower::ServiceExt;
+use velib_mcp::McpServer;
+
+async fn body_text(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_counts_tool_calls() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+
+    let (status, _) = common::post_mcp(
+        &server.router(),
+        None,
+        &common::tool_call("get_station_by_code", json!({"station_code": "4001"})),
+    )
+    .await;
+    assert_eq!(status.as_u16(), 200);
+
+    let response = server
+        .router()
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+
+    let text = body_text(response).await;
+    assert!(text.contains("velib_tool_calls_total 1\n"));
+    assert!(text.contains("velib_slo_tool_call_latency_ratio 1\n"));
+    assert!(text.contains("velib_breaker_open 0\n"));
+    assert!(text.contains("velib_panics_total 0\n"));
+    assert!(text.contains("velib_realtime_stations 5\n"));
+    assert!(text.contains("velib_memory_trims_total 0\n"));
+    assert!(!text.contains("velib_memory_soft_limit_bytes"));
+}
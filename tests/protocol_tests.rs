@@ -0,0 +1,535 @@
+mod common;
+
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use velib_mcp::mcp::registry::ToolSelection;
+use velib_mcp::mcp::types::JsonRpcError;
+use velib_mcp::mcp::{
+    ApiLifecycle, JournalEntry, OutputPrecision, RequestJournal, TOOL_API_VERSION,
+};
+use velib_mcp::server::Server;
+use velib_mcp::{AppConfig, Error, McpServer};
+
+async fn rpc(router: &Router, method: &str, params: Value) -> Value {
+    let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    common::post_mcp(router, None, &body).await.1
+}
+
+#[tokio::test]
+async fn test_initialize_advertises_tool_api_version() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let response = rpc(&router, "initialize", json!({})).await;
+    let result = &response["result"];
+    assert_eq!(result["serverInfo"]["name"], "velib-mcp");
+    assert_eq!(result["serverInfo"]["toolApiVersion"], TOOL_API_VERSION);
+    assert!(result["protocolVersion"].is_string());
+}
+
+#[tokio::test]
+async fn test_deprecated_arguments_are_flagged_and_still_accepted() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let tools = rpc(&router, "tools/list", json!({})).await;
+    let get_station = tools["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "get_station_by_code")
+        .unwrap()
+        .clone();
+    assert_eq!(
+        get_station["inputSchema"]["properties"]["include_real_time"]["deprecated"],
+        true
+    );
+
+    // A 1.0-style call still succeeds
+    let response = rpc(
+        &router,
+        "tools/call",
+        json!({
+            "name": "get_station_by_code",
+            "arguments": {"station_code": "12001", "include_real_time": false}
+        }),
+    )
+    .await;
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let output: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(output["found"], true);
+}
+
+#[tokio::test]
+async fn test_functions_manifest_follows_tools_list() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let get = |uri: &str| {
+        router
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+    };
+
+    let tools = rpc(&router, "tools/list", json!({})).await;
+    let response = get("/functions.json").await.unwrap();
+    assert_eq!(response.status(), 200);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let functions: Value = serde_json::from_slice(&bytes).unwrap();
+    let plan = functions
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|function| function["function"]["name"] == "plan_bike_journey")
+        .unwrap();
+    let listed = tools["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "plan_bike_journey")
+        .unwrap();
+    assert_eq!(plan["function"]["parameters"], listed["inputSchema"]);
+    assert_eq!(plan["function"]["description"], listed["description"]);
+
+    let response = get("/functions.json?format=anthropic").await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let functions: Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(functions[0]["input_schema"].is_object());
+
+    let response = get("/functions.json?format=gemini").await.unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_http_responses_carry_version_headers() {
+    let lifecycle = ApiLifecycle {
+        deprecated_at: Some("2026-03-01T00:00:00Z".parse().unwrap()),
+        sunset_at: Some("2026-09-01T00:00:00Z".parse().unwrap()),
+    };
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_api_lifecycle(lifecycle)
+        .router();
+
+    for uri in ["/metrics", "/widget/station/unknown"] {
+        let response = router
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["x-api-version"], TOOL_API_VERSION, "{uri}");
+        assert_eq!(headers["deprecation"], "@1772323200", "{uri}");
+        assert_eq!(headers["sunset"], "Tue, 01 Sep 2026 00:00:00 GMT", "{uri}");
+    }
+
+    let health = Server::new("127.0.0.1:0".parse().unwrap())
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(health.headers()["x-api-version"], TOOL_API_VERSION);
+    assert!(!health.headers().contains_key("sunset"));
+}
+
+#[tokio::test]
+async fn test_hardening_rejects_unexpected_traffic() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+    let send = |router: Router, request: Request<Body>| async move {
+        router.oneshot(request).await.unwrap()
+    };
+    let initialize = |content_type: &str| {
+        Request::post("/mcp")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let router = server.router();
+    let response = send(
+        router.clone(),
+        initialize("application/json; charset=utf-8"),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    let response = send(router.clone(), initialize("text/plain")).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    let trace = Request::builder()
+        .method(Method::TRACE)
+        .uri("/mcp")
+        .body(Body::empty())
+        .unwrap();
+    let response = send(router, trace).await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[header::ALLOW], "GET, HEAD, POST, DELETE");
+
+    // With an allowlist, only peers within it get through
+    let router = server
+        .with_allowed_ips(&["10.1.0.0/16".parse().unwrap()])
+        .router();
+    let from = |peer: &str| {
+        router
+            .clone()
+            .layer(MockConnectInfo(peer.parse::<SocketAddr>().unwrap()))
+    };
+    let response = send(from("10.1.2.3:40000"), initialize("application/json")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(from("192.0.2.1:40000"), initialize("application/json")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    // Without a known peer address nobody does
+    let response = send(router, initialize("application/json")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_disabled_tools_are_hidden_and_refused() {
+    let config = AppConfig {
+        tools: ToolSelection {
+            enabled: None,
+            disabled: vec!["plan_bike_journey".to_string()],
+        },
+        ..AppConfig::default()
+    };
+    let router = McpServer::with_config(&config).unwrap().router();
+
+    let listed = rpc(&router, "tools/list", json!({})).await;
+    let names: Vec<&str> = listed["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"find_nearby_stations"));
+    assert!(!names.contains(&"plan_bike_journey"));
+
+    let response = rpc(
+        &router,
+        "tools/call",
+        json!({
+            "name": "plan_bike_journey",
+            "arguments": {
+                "origin": {"latitude": 48.8446, "longitude": 2.3737},
+                "destination": {"latitude": 48.8566, "longitude": 2.3522}
+            }
+        }),
+    )
+    .await;
+    assert_eq!(response["error"]["data"]["error_type"], "tool_disabled");
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("plan_bike_journey"));
+
+    let typo = AppConfig {
+        tools: ToolSelection {
+            enabled: Some(vec!["plan_journey".to_string()]),
+            disabled: Vec::new(),
+        },
+        ..AppConfig::default()
+    };
+    assert!(McpServer::with_config(&typo).is_err());
+}
+
+/// An upstream that accepts connections and never answers
+async fn silent_upstream() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+    url
+}
+
+async fn stalled_server() -> McpServer {
+    let upstream = silent_upstream().await;
+    let client = velib_mcp::VelibDataClient::new()
+        .with_stations_url(&upstream)
+        .with_realtime_url(&upstream);
+    McpServer::with_tool_handler(velib_mcp::McpToolHandler::with_data_client(client))
+}
+
+#[tokio::test]
+async fn test_slow_tools_time_out() {
+    let call = json!({"name": "get_station_by_code", "arguments": {"station_code": "12001"}});
+
+    // The client's hint is lower than the tool's own limit
+    let router = stalled_server().await.router();
+    let mut hinted = call.clone();
+    hinted["_meta"] = json!({"max_time_ms": 200});
+    let response = rpc(&router, "tools/call", hinted).await;
+    let error = &response["error"];
+    assert_eq!(error["code"], -32001);
+    assert_eq!(error["data"]["error_type"], "timeout");
+    assert_eq!(error["data"]["operation"], "get_station_by_code");
+    assert_eq!(error["data"]["timeout_ms"], 200);
+
+    // A configured limit applies without a hint, and a larger hint can't extend it
+    let router = stalled_server()
+        .await
+        .with_tool_timeouts(&[(
+            "get_station_by_code".to_string(),
+            std::time::Duration::from_millis(150),
+        )])
+        .router();
+    let mut hinted = call.clone();
+    hinted["_meta"] = json!({"max_time_ms": 60_000});
+    let response = rpc(&router, "tools/call", hinted).await;
+    assert_eq!(response["error"]["data"]["timeout_ms"], 150);
+    let response = rpc(&router, "tools/call", call).await;
+    assert_eq!(response["error"]["data"]["error_type"], "timeout");
+}
+
+#[tokio::test]
+async fn test_results_shrink_to_the_response_budget() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let nearby = |budget: Value| {
+        let mut arguments =
+            json!({"latitude": 48.8446, "longitude": 2.3737, "radius_meters": 5000});
+        if let Some(budget) = budget.as_object() {
+            arguments.as_object_mut().unwrap().extend(budget.clone());
+        }
+        json!({"name": "find_nearby_stations", "arguments": arguments})
+    };
+    let output = |response: &Value| -> Value {
+        serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+
+    let full = rpc(&router, "tools/call", nearby(Value::Null)).await;
+    let all = output(&full)["stations"].as_array().unwrap().len();
+    assert!(all > 2);
+    assert!(output(&full).get("truncated_due_to_budget").is_none());
+
+    let budgeted = rpc(
+        &router,
+        "tools/call",
+        nearby(json!({"max_response_bytes": 1500})),
+    )
+    .await;
+    assert!(budgeted["result"].to_string().len() <= 1500);
+    let shrunk = output(&budgeted);
+    assert_eq!(shrunk["truncated_due_to_budget"], true);
+    let kept = shrunk["stations"].as_array().unwrap().len();
+    assert!((1..all).contains(&kept), "{kept} of {all}");
+
+    // A generous budget leaves the result alone
+    let roomy = rpc(
+        &router,
+        "tools/call",
+        nearby(json!({"max_response_tokens": 100_000})),
+    )
+    .await;
+    assert_eq!(output(&roomy)["stations"].as_array().unwrap().len(), all);
+}
+
+#[tokio::test]
+async fn test_journal_leaves_only_unanswered_requests() {
+    let path = std::env::temp_dir().join(format!("velib-{}.journal", uuid::Uuid::new_v4()));
+    let (journal, _) = RequestJournal::open(&path, 8).unwrap();
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_journal(journal)
+        .router();
+
+    rpc(&router, "tools/list", json!({})).await;
+    let response = rpc(
+        &router,
+        "tools/call",
+        json!({"name": "get_station_by_code", "arguments": {}}),
+    )
+    .await;
+    assert!(response["error"].is_object());
+    drop(router);
+
+    let recorded: Vec<JournalEntry> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line.trim()).ok())
+        .collect();
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[1].tool.as_deref(), Some("get_station_by_code"));
+    assert_eq!(recorded[1].error.as_deref(), Some("json_error"));
+    assert!(recorded.iter().all(|entry| entry.elapsed_ms.is_some()));
+
+    let (_, unfinished) = RequestJournal::open(&path, 8).unwrap();
+    assert!(unfinished.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_signed_resources_verify_against_the_public_key() {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+    use openssl::pkey::PKey;
+    use openssl::sign::Verifier;
+    use velib_mcp::mcp::ResponseSigner;
+
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_response_signer(ResponseSigner::generate().unwrap())
+        .router();
+    let get = |uri: &'static str| {
+        let router = router.clone();
+        async move {
+            let response = router
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let headers = response.headers().clone();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (headers, bytes)
+        }
+    };
+    let (_, document) = get("/public-key").await;
+    let document: Value = serde_json::from_slice(&document).unwrap();
+    assert_eq!(document["algorithm"], "Ed25519");
+    let public = PKey::public_key_from_pem(document["pem"].as_str().unwrap().as_bytes()).unwrap();
+    let verifies = |signature: &str, payload: &[u8]| {
+        let signature = BASE64_STANDARD.decode(signature).unwrap();
+        Verifier::new_without_digest(&public)
+            .unwrap()
+            .verify_oneshot(&signature, payload)
+            .unwrap()
+    };
+
+    let (headers, body) = get("/resources/velib://stats/usage").await;
+    assert_eq!(
+        headers["x-signature-key-id"],
+        document["key_id"].as_str().unwrap()
+    );
+    assert!(verifies(headers["x-signature"].to_str().unwrap(), &body));
+    assert!(!verifies(headers["x-signature"].to_str().unwrap(), b"{}"));
+
+    let read = rpc(
+        &router,
+        "resources/read",
+        json!({"uri": "velib://stats/usage"}),
+    )
+    .await;
+    let content = &read["result"]["contents"][0];
+    assert_eq!(content["_meta"]["signatureKeyId"], document["key_id"]);
+    assert!(verifies(
+        content["_meta"]["signature"].as_str().unwrap(),
+        content["text"].as_str().unwrap().as_bytes()
+    ));
+
+    let unsigned = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let response = unsigned
+        .oneshot(Request::get("/public-key").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_error_catalog_matches_error_responses() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let read = rpc(
+        &router,
+        "resources/read",
+        json!({"uri": "velib://docs/errors"}),
+    )
+    .await;
+    let catalog: Value =
+        serde_json::from_str(read["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+    let entries = catalog["errors"].as_array().unwrap();
+    let entry = |error_type: &str| {
+        entries
+            .iter()
+            .find(|entry| entry["error_type"] == error_type)
+            .unwrap_or_else(|| panic!("{error_type} is missing from the catalog"))
+            .clone()
+    };
+
+    for error in Error::examples() {
+        let response = JsonRpcError::from(error);
+        let data = response.data.unwrap();
+        let listed = entry(data["error_type"].as_str().unwrap());
+        assert_eq!(listed["code"], response.code, "{}", data["error_type"]);
+        let mut fields: Vec<&str> = data
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .filter(|field| *field != "error_type")
+            .collect();
+        fields.sort_unstable();
+        let mut documented: Vec<&str> = listed["data_fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field.as_str().unwrap())
+            .collect();
+        documented.sort_unstable();
+        assert_eq!(fields, documented, "{}", data["error_type"]);
+    }
+
+    // Errors from real calls carry the documented codes
+    let missing = rpc(
+        &router,
+        "tools/call",
+        json!({"name": "render_station_sparkline", "arguments": {"station_code": "99999"}}),
+    )
+    .await;
+    let error = &missing["error"];
+    assert_eq!(error["data"]["error_type"], "station_not_found");
+    assert_eq!(error["code"], entry("station_not_found")["code"]);
+    let far = rpc(
+        &router,
+        "tools/call",
+        json!({"name": "find_nearby_stations", "arguments": {"latitude": 91.0, "longitude": 2.35}}),
+    )
+    .await;
+    assert_eq!(far["error"]["data"]["error_type"], "invalid_coordinates");
+    assert_eq!(far["error"]["code"], entry("invalid_coordinates")["code"]);
+}
+
+#[tokio::test]
+async fn test_numbers_are_rounded_in_results_and_resources() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_output_precision(OutputPrecision {
+            coordinate_decimals: 2,
+            decimals: 1,
+        })
+        .router();
+
+    let response = rpc(
+        &router,
+        "tools/call",
+        json!({"name": "get_area_statistics", "arguments": {
+            "bounds": {"north": 48.86123, "south": 48.83, "east": 2.38, "west": 2.34}
+        }}),
+    )
+    .await;
+    let output: Value =
+        serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    // 8 of 18 docks hold a bike at each station
+    assert_eq!(output["area_stats"]["occupancy_rate"], 0.4);
+    assert_eq!(output["bounds"]["north"], 48.86);
+
+    let read = rpc(
+        &router,
+        "resources/read",
+        json!({"uri": "velib://stations/reference"}),
+    )
+    .await;
+    let reference: Value =
+        serde_json::from_str(read["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+    assert!(reference.to_string().contains("\"latitude\":48.84,"));
+}
@@ -0,0 +1,221 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use tower::ServiceExt;
+use velib_mcp::mcp::{key_id, QuotaLimits, QuotaStore};
+use velib_mcp::McpServer;
+
+fn lookup() -> Value {
+    common::tool_call("get_station_by_code", json!({"station_code": "12001"}))
+}
+
+fn admin(method: &str, uri: &str, token: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().uri(uri).method(method);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_exhausted_quota_is_reported_and_reset_by_admin() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_quotas(QuotaStore::in_memory(QuotaLimits {
+            daily: Some(2),
+            monthly: None,
+        }))
+        .with_admin_token("s3cret")
+        .with_api_keys(&["commuter".to_string(), "tourist".to_string()])
+        .router();
+
+    for _ in 0..2 {
+        let (_, response) = common::post_mcp(&router, Some("commuter"), &lookup()).await;
+        assert!(response["result"].is_object(), "{response}");
+    }
+    let (_, refused) = common::post_mcp(&router, Some("commuter"), &lookup()).await;
+    let error = &refused["error"];
+    assert_eq!(error["data"]["error_type"], "quota_exceeded");
+    assert_eq!(error["data"]["quota"]["period"], "daily");
+    assert_eq!(error["data"]["quota"]["limit"], 2);
+    assert!(error["data"]["quota"]["resets_at"].is_string());
+
+    // Other keys keep their own quota
+    let (_, other) = common::post_mcp(&router, Some("tourist"), &lookup()).await;
+    assert!(other["result"].is_object());
+
+    let (status, _) = common::send(&router, admin("GET", "/admin/quotas", None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = common::send(&router, admin("GET", "/admin/quotas", Some("guess"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, listing) = common::send(&router, admin("GET", "/admin/quotas", Some("s3cret"))).await;
+    assert_eq!(listing["limits"]["daily"], 2);
+    assert_eq!(listing["keys"][0]["key_id"], key_id("key:commuter"));
+    assert_eq!(listing["keys"][0]["daily"]["remaining"], 0);
+
+    let (_, reset) = common::send(
+        &router,
+        admin("DELETE", "/admin/quotas/commuter", Some("s3cret")),
+    )
+    .await;
+    assert_eq!(reset["reset"], true);
+    let (_, usage) = common::send(
+        &router,
+        admin(
+            "GET",
+            &format!("/admin/quotas/{}", key_id("key:commuter")),
+            Some("s3cret"),
+        ),
+    )
+    .await;
+    assert_eq!(usage["daily"]["used"], 0);
+
+    let (_, response) = common::post_mcp(&router, Some("commuter"), &lookup()).await;
+    assert!(response["result"].is_object());
+}
+
+#[tokio::test]
+async fn test_unchecked_bearer_tokens_share_the_anonymous_quota() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_quotas(QuotaStore::in_memory(QuotaLimits {
+            daily: Some(2),
+            monthly: None,
+        }))
+        .with_admin_token("s3cret")
+        .router();
+
+    // Without API keys configured, a fresh token doesn't buy a fresh quota
+    for token in ["first", "second"] {
+        let (_, response) = common::post_mcp(&router, Some(token), &lookup()).await;
+        assert!(response["result"].is_object(), "{response}");
+    }
+    let (_, refused) = common::post_mcp(&router, Some("third"), &lookup()).await;
+    assert_eq!(refused["error"]["data"]["error_type"], "quota_exceeded");
+
+    let (_, listing) = common::send(&router, admin("GET", "/admin/quotas", Some("s3cret"))).await;
+    assert_eq!(listing["keys"].as_array().unwrap().len(), 1);
+    assert_eq!(listing["keys"][0]["key_id"], "anonymous");
+}
+
+#[tokio::test]
+async fn test_websocket_calls_are_charged_to_the_upgrading_key() {
+    let quotas = QuotaStore::in_memory(QuotaLimits {
+        daily: Some(1),
+        monthly: None,
+    });
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_quotas(quotas)
+        .with_admin_token("s3cret")
+        .with_api_keys(&["commuter".to_string()])
+        .router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/mcp/ws", listener.local_addr().unwrap());
+    tokio::spawn({
+        let router = router.clone();
+        async move { axum::serve(listener, router).await }
+    });
+
+    let mut request = url.into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, "Bearer commuter".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        socket
+            .send(Message::Text(lookup().to_string()))
+            .await
+            .unwrap();
+        // Skip the session greeting
+        while let Some(Ok(Message::Text(text))) = socket.next().await {
+            let message: Value = serde_json::from_str(&text).unwrap();
+            if message.get("id").is_some() {
+                responses.push(message);
+                break;
+            }
+        }
+    }
+    assert!(responses[0]["result"].is_object(), "{}", responses[0]);
+    assert_eq!(
+        responses[1]["error"]["data"]["error_type"],
+        "quota_exceeded"
+    );
+
+    let (_, usage) = common::send(
+        &router,
+        admin("GET", "/admin/quotas/commuter", Some("s3cret")),
+    )
+    .await;
+    assert_eq!(usage["daily"]["used"], 1);
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_are_off_without_token() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let response = router
+        .oneshot(admin("GET", "/admin/quotas", Some("anything")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_exports_history_as_csv() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await)
+        .with_admin_token("s3cret")
+        .router();
+
+    let (status, _) = common::send(&router, admin("GET", "/admin/history", None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, error) = common::send(
+        &router,
+        admin("GET", "/admin/history?format=xlsx", Some("s3cret")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("xlsx"));
+
+    let response = router
+        .clone()
+        .oneshot(admin(
+            "GET",
+            "/admin/history?format=csv&since=2000-01-01T00:00:00Z",
+            Some("s3cret"),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+    assert!(response.headers()[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap()
+        .contains("velib-history.csv"));
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let csv = String::from_utf8(bytes.to_vec()).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("station_code,observed_at,bikes"));
+    // One sample per fixture station, 8 bikes each
+    let rows: Vec<&str> = lines.collect();
+    assert_eq!(rows.len(), 5);
+    assert!(rows.iter().all(|row| row.ends_with(",8")), "{rows:?}");
+
+    let response = router
+        .oneshot(admin(
+            "GET",
+            "/admin/history?since=2999-01-01T00:00:00Z",
+            Some("s3cret"),
+        ))
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], b"station_code,observed_at,bikes\n");
+}
@@ -0,0 +1,94 @@
+use axum::{routing::get, Json, Router};
+use serde_json::{json, Value};
+use velib_mcp::mcp::ToolRegistry;
+use velib_mcp::server::selftest;
+use velib_mcp::AppConfig;
+
+/// Serve three stations in the Paris Open Data schema, for both datasets
+async fn fixture_upstream() -> String {
+    let records: Vec<Value> = [
+        (
+            "16107",
+            "Benjamin Godard - Victor Hugo",
+            48.865983,
+            2.275725,
+        ),
+        ("16108", "Victor Hugo - Poincaré", 48.867, 2.281),
+        ("8026", "Saint-Augustin", 48.8756, 2.3167),
+    ]
+    .into_iter()
+    .map(|(code, name, lat, lon)| {
+        json!({
+            "stationcode": code,
+            "name": name,
+            "capacity": 30,
+            "coordonnees_geo": {"lat": lat, "lon": lon},
+            "nom_arrondissement_communes": "Paris",
+            "mechanical": 4,
+            "ebike": 3,
+            "numdocksavailable": 20,
+            "is_installed": "OUI",
+            "is_renting": "OUI",
+            "is_returning": "OUI",
+            "duedate": chrono::Utc::now().to_rfc3339()
+        })
+    })
+    .collect();
+    let body = json!({"total_count": records.len(), "results": records});
+    let router = Router::new().route("/", get(move || async move { Json(body) }));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    url
+}
+
+#[tokio::test]
+async fn test_selftest_runs_every_tool() {
+    let upstream = fixture_upstream().await;
+    let config = AppConfig {
+        stations_url: Some(upstream.clone()),
+        realtime_url: Some(upstream),
+        api_keys: vec!["secret".to_string()],
+        ..AppConfig::default()
+    };
+
+    let report = selftest::run(Ok(config)).await;
+    assert!(report.passed, "{report}");
+    let names: Vec<&str> = report
+        .checks
+        .iter()
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(names[..3], ["configuration", "upstream", "fetch"]);
+    assert_eq!(
+        report.checks[2].detail,
+        "3 stations, 3 with real-time status"
+    );
+    for tool in ToolRegistry::builtin().tools() {
+        assert!(names.contains(&format!("tool:{}", tool.name).as_str()));
+    }
+}
+
+#[tokio::test]
+async fn test_selftest_stops_at_unreachable_upstream() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}/", listener.local_addr().unwrap());
+    drop(listener);
+    let config = AppConfig {
+        stations_url: Some(closed.clone()),
+        realtime_url: Some(closed),
+        ..AppConfig::default()
+    };
+
+    let report = selftest::run(Ok(config)).await;
+    assert!(!report.passed);
+    assert_eq!(report.checks.len(), 2);
+    assert!(!report.checks[1].passed);
+    assert!(report.checks[1].detail.starts_with("reference: "));
+
+    let report = selftest::run(Err(velib_mcp::Error::Config("bad".to_string()))).await;
+    let report: Value = serde_json::from_str(&report.to_string()).unwrap();
+    assert_eq!(report["passed"], false);
+    assert_eq!(report["checks"][0]["name"], "configuration");
+}
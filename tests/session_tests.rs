@@ -0,0 +1,348 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request},
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+use tower::ServiceExt;
+use velib_mcp::McpServer;
+
+fn tool_output(response: &Value) -> Value {
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    serde_json::from_str(text).unwrap()
+}
+
+#[tokio::test]
+async fn test_preferences_apply_to_later_calls() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let (_, stored) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call(
+            "set_preferences",
+            json!({"max_walk_distance": 150, "language": "fr"}),
+        ),
+    )
+    .await;
+    assert_eq!(tool_output(&stored)["max_walk_distance"], 150);
+
+    let arguments = json!({"latitude": 48.8566, "longitude": 2.3522});
+
+    // The stored walk distance becomes the default search radius
+    let (_, with_session) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("find_nearby_stations", arguments.clone()),
+    )
+    .await;
+    assert_eq!(
+        tool_output(&with_session)["search_metadata"]["radius_meters"],
+        150
+    );
+
+    // Other API keys are unaffected
+    let (_, other_session) = common::post_mcp(
+        &router,
+        Some("someone-else"),
+        &common::tool_call("find_nearby_stations", arguments),
+    )
+    .await;
+    assert_eq!(
+        tool_output(&other_session)["search_metadata"]["radius_meters"],
+        500
+    );
+}
+
+#[tokio::test]
+async fn test_humanize_follows_session_language() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let arguments = json!({"latitude": 48.8446, "longitude": 2.3737, "humanize": true});
+
+    common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("set_preferences", json!({"language": "fr"})),
+    )
+    .await;
+    let (_, french) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("find_nearby_stations", arguments.clone()),
+    )
+    .await;
+    let output = tool_output(&french);
+    assert_eq!(output["search_metadata"]["radius_text"], "500 m");
+    let walk = output["stations"][0]["walk_time_text"].as_str().unwrap();
+    assert!(walk.ends_with("à pied"), "{walk}");
+
+    // Without a session language, or when asked for one explicitly
+    let (_, english) = common::post_mcp(
+        &router,
+        None,
+        &common::tool_call("find_nearby_stations", arguments),
+    )
+    .await;
+    let walk = tool_output(&english)["stations"][0]["walk_time_text"].clone();
+    assert!(walk.as_str().unwrap().ends_with("walk"));
+    let (_, plain) = common::post_mcp(
+        &router,
+        None,
+        &common::tool_call(
+            "find_nearby_stations",
+            json!({"latitude": 48.8446, "longitude": 2.3737}),
+        ),
+    )
+    .await;
+    assert!(tool_output(&plain)["search_metadata"]
+        .get("radius_text")
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_request_meta_overrides_session_locale_and_units() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("set_preferences", json!({"language": "fr"})),
+    )
+    .await;
+
+    let mut request = common::tool_call(
+        "find_nearby_stations",
+        json!({"latitude": 48.8446, "longitude": 2.3737, "radius_meters": 1250, "humanize": true}),
+    );
+    request["params"]["_meta"] = json!({"locale": "en-US", "units": "imperial"});
+    let (_, imperial) = common::post_mcp(&router, Some("commuter"), &request).await;
+    let output = tool_output(&imperial);
+    assert_eq!(output["search_metadata"]["radius_meters"], 1250);
+    assert_eq!(output["search_metadata"]["radius_text"], "0.8 mi");
+    let walk = output["stations"][0]["walk_time_text"].as_str().unwrap();
+    assert!(walk.ends_with("walk"), "{walk}");
+
+    // Another request on the same session is back to its own settings
+    request["params"]["_meta"] = json!({});
+    let (_, metric) = common::post_mcp(&router, Some("commuter"), &request).await;
+    assert_eq!(
+        tool_output(&metric)["search_metadata"]["radius_text"],
+        "1,2 km"
+    );
+
+    request["params"]["_meta"] = json!({"units": "nautical"});
+    let (_, rejected) = common::post_mcp(&router, Some("commuter"), &request).await;
+    assert_eq!(rejected["error"]["data"]["error_type"], "validation_error");
+}
+
+#[tokio::test]
+async fn test_set_preferences_requires_session_over_http() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let (_, response) = common::post_mcp(
+        &router,
+        None,
+        &common::tool_call("set_preferences", json!({"bike_type": "electric"})),
+    )
+    .await;
+
+    assert!(response["result"].is_null());
+    assert_eq!(response["error"]["data"]["error_type"], "validation_error");
+}
+
+#[tokio::test]
+async fn test_favorites_status_per_session() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call(
+            "add_favorite_station",
+            json!({"station_code": "12001", "label": "Home"}),
+        ),
+    )
+    .await;
+    let (_, added) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("add_favorite_station", json!({"station_code": "13001"})),
+    )
+    .await;
+    assert_eq!(
+        tool_output(&added)["favorites"].as_array().unwrap().len(),
+        2
+    );
+
+    let (_, status) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("get_favorites_status", json!({})),
+    )
+    .await;
+    let favorites = tool_output(&status)["favorites"].clone();
+    assert_eq!(favorites[0]["station_code"], "12001");
+    assert_eq!(favorites[0]["label"], "Home");
+    assert_eq!(favorites[0]["found"], true);
+    assert_eq!(favorites[0]["station"]["real_time"]["bikes"]["electric"], 3);
+
+    let (_, other) = common::post_mcp(
+        &router,
+        Some("someone-else"),
+        &common::tool_call("list_favorite_stations", json!({})),
+    )
+    .await;
+    assert!(tool_output(&other)["favorites"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_add_favorite_rejects_unknown_station() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    let (_, response) = common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call("add_favorite_station", json!({"station_code": "99999"})),
+    )
+    .await;
+
+    assert_eq!(response["error"]["data"]["error_type"], "station_not_found");
+}
+
+async fn get_resource(router: &Router, api_key: Option<&str>, uri: &str) -> (u16, Value) {
+    let mut request = Request::builder().uri(format!("/resources/{uri}"));
+    if let Some(key) = api_key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {key}"));
+    }
+
+    let response = router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_commute_digest_resource() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call(
+            "set_preferences",
+            json!({"home": {"latitude": 48.8447, "longitude": 2.3738}}),
+        ),
+    )
+    .await;
+    common::post_mcp(
+        &router,
+        Some("commuter"),
+        &common::tool_call(
+            "add_favorite_station",
+            json!({"station_code": "4001", "label": "Office"}),
+        ),
+    )
+    .await;
+
+    let (status, morning) = get_resource(&router, Some("commuter"), "velib://digest/morning").await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        morning["digest"]["best_pickup"]["reference"]["station_code"],
+        "12001"
+    );
+    let summary = morning["summary"].as_str().unwrap();
+    assert!(summary.contains("Best pickup: Gare de Lyon - Diderot"));
+    assert!(summary.contains("- Office: 5 mechanical, 3 electric, 10 docks"));
+
+    // No work location stored yet
+    let (_, evening) = get_resource(&router, Some("commuter"), "velib://digest/evening").await;
+    assert!(evening["digest"]["best_pickup"].is_null());
+
+    let (status, _) = get_resource(&router, None, "velib://digest/morning").await;
+    assert_eq!(status, 400);
+    let (status, _) = get_resource(&router, Some("commuter"), "velib://digest/lunch").await;
+    assert_eq!(status, 400);
+}
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn next_json(socket: &mut Socket) -> Value {
+    loop {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Close(_) => panic!("connection closed"),
+            _ => {}
+        }
+    }
+}
+
+/// Connect, returning the socket and the `notifications/session` params it opened with
+async fn connect(url: &str) -> (Socket, Value) {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let greeting = next_json(&mut socket).await;
+    assert_eq!(greeting["method"], "notifications/session");
+    (socket, greeting["params"].clone())
+}
+
+async fn send(socket: &mut Socket, body: Value) -> Value {
+    socket.send(Message::Text(body.to_string())).await.unwrap();
+    next_json(socket).await
+}
+
+#[tokio::test]
+async fn test_websocket_sessions_resume_with_their_token() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/mcp/ws", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let (mut socket, session) = connect(&url).await;
+    assert_eq!(session["resumed"], false);
+    assert_eq!(session["graceSeconds"], 300);
+    let token = session["token"].as_str().unwrap().to_string();
+    send(
+        &mut socket,
+        common::tool_call("set_preferences", json!({"language": "fr"})),
+    )
+    .await;
+    socket.close(None).await.unwrap();
+    drop(socket);
+
+    let preferences = |response: &Value| -> Value {
+        serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+    // The server notices the close on its own time
+    let mut resumed = None;
+    for _ in 0..50 {
+        let (socket, session) = connect(&format!("{url}?resume={token}")).await;
+        if session["resumed"] == true {
+            resumed = Some((socket, session));
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let (mut socket, session) = resumed.expect("session was not resumed");
+    assert_eq!(session["token"], token.as_str());
+    let response = send(&mut socket, common::tool_call("set_preferences", json!({}))).await;
+    assert_eq!(preferences(&response)["language"], "fr");
+
+    // A token is good for one resumption, and unknown ones start afresh
+    let (mut other, session) = connect(&format!("{url}?resume={token}")).await;
+    assert_eq!(session["resumed"], false);
+    assert_ne!(session["token"], token.as_str());
+    let response = send(&mut other, common::tool_call("set_preferences", json!({}))).await;
+    assert!(preferences(&response).get("language").is_none());
+}
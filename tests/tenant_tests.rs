@@ -0,0 +1,77 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use velib_mcp::fixtures;
+use velib_mcp::server::TenantConfig;
+use velib_mcp::{McpServer, McpToolHandler, Server};
+
+fn station_call(uri: &str, host: &str, api_key: Option<&str>, code: &str) -> Request<Body> {
+    let call = common::tool_call("get_station_by_code", json!({"station_code": code}));
+    let mut request = common::mcp_request(api_key, &call);
+    *request.uri_mut() = uri.parse().unwrap();
+    request
+        .headers_mut()
+        .insert(header::HOST, host.parse().unwrap());
+    request
+}
+
+async fn found(router: &Router, request: Request<Body>) -> bool {
+    let (status, text) = common::send_text(router, request).await;
+    assert_eq!(status, StatusCode::OK, "{text}");
+    let response: Value = serde_json::from_str(&text).unwrap();
+    let output: Value =
+        serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    output["found"] == true
+}
+
+#[tokio::test]
+async fn test_tenants_are_isolated_by_host_and_path() {
+    // The test network only has a single station of its own
+    let test_network = McpToolHandler::with_data_client(
+        fixtures::seeded_client(&[fixtures::open_station("99001", "Banc d'essai", 48.85, 2.35)])
+            .await,
+    );
+
+    let addr = "127.0.0.1:0".parse().unwrap();
+    let router = Server::new(addr)
+        .with_tenant(
+            TenantConfig::at_host("public", "velib.example.org"),
+            McpServer::with_tool_handler(common::fixture_handler().await),
+        )
+        .with_tenant(
+            TenantConfig::at_path("test", "/test"),
+            McpServer::with_tool_handler(test_network).with_api_keys(&["qa".to_string()]),
+        )
+        .router();
+
+    let public = |code| station_call("/mcp", "Velib.Example.org:443", None, code);
+    assert!(found(&router, public("12001")).await);
+    assert!(!found(&router, public("99001")).await);
+
+    // The prefix selects the tenant from any host, and its key list applies
+    let test = |key, code| station_call("/test/mcp", "velib.example.org", key, code);
+    let (status, _) = common::send_text(&router, test(None, "99001")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(found(&router, test(Some("qa"), "99001")).await);
+    assert!(!found(&router, test(Some("qa"), "12001")).await);
+
+    let metrics = |uri: &str| {
+        Request::get(uri)
+            .header(header::HOST, "velib.example.org")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (_, public_metrics) = common::send_text(&router, metrics("/metrics")).await;
+    assert!(public_metrics.contains("velib_tool_calls_total 2\n"));
+    let (status, test_metrics) = common::send_text(&router, metrics("/test/metrics")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(test_metrics.contains("velib_tool_calls_total 2\n"));
+
+    let (status, _) = common::send_text(&router, metrics("/test/health")).await;
+    assert_eq!(status, StatusCode::OK);
+}
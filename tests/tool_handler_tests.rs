@@ -0,0 +1,1951 @@
+mod common;
+
+use chrono::Utc;
+use common::{fixture_handler, fixture_realtime, fixture_stations, seeded_client};
+use velib_mcp::data::{AdvisoryKind, AdvisorySeverity};
+use velib_mcp::fixtures::{self, open_station};
+use velib_mcp::mcp::types::{
+    AppliedLimits, AutocompleteStationNamesInput, AvailabilityFilter, BikeTypePreference,
+    CheckDropoffFeasibilityInput, CompareStationsInput, CompareWithTypicalInput, CoverageExtent,
+    DiffStationStatusInput, FindNearbyStationsInput, FindStationsAlongRouteInput, GeographicBounds,
+    GetAreaStatisticsInput, GetFleetCompositionInput, GetStationByCodeInput,
+    GetStationDensityInput, JourneyEndpoints, JourneyPreferences, PlanBikeJourneyInput,
+    PlanMultipleJourneysInput, RenderStationSparklineInput, RouteFormat, RouteSource,
+    SearchStationsByNameInput, SharedStationUse, SparklineFormat, StationPurpose, TypicalLevel,
+};
+use velib_mcp::mcp::{SortDirection, SortField, SortOptions};
+use velib_mcp::routing::{RoutingBackend, RoutingEngine};
+use velib_mcp::{
+    AvailabilityLabel, AvailabilityThresholds, BikeAvailability, BikeTypeFilter, Coordinates,
+    Error, Geometry, McpToolHandler, RealTimeStatus, ServiceArea, StationStatus, VelibDataClient,
+    WalkingSpeed,
+};
+
+#[tokio::test]
+async fn test_search_resolves_landmark_alias() {
+    let handler = fixture_handler().await;
+
+    let output = handler
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "BnF".to_string(),
+            limit: 10,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        output.search_metadata.matched_landmark.as_deref(),
+        Some("Bibliothèque François Mitterrand")
+    );
+    assert_eq!(output.stations.len(), 1);
+    assert_eq!(&*output.stations[0].reference.station_code, "13001");
+}
+
+#[tokio::test]
+async fn test_landmark_stations_rank_before_name_matches() {
+    let handler = fixture_handler().await;
+
+    let output = handler
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "gare de lyon".to_string(),
+            limit: 10,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+
+    let codes: Vec<&str> = output
+        .stations
+        .iter()
+        .map(|s| &*s.reference.station_code)
+        .collect();
+    // Both stations near the landmark, closest first, without duplicates
+    assert_eq!(codes, vec!["12001", "12002"]);
+}
+
+#[tokio::test]
+async fn test_plain_name_search_has_no_landmark() {
+    let handler = fixture_handler().await;
+
+    let output = handler
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "lyon".to_string(),
+            limit: 10,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+
+    assert!(output.search_metadata.matched_landmark.is_none());
+    assert_eq!(output.stations.len(), 3);
+}
+
+#[tokio::test]
+async fn test_autocomplete_station_names() {
+    let handler = fixture_handler().await;
+
+    let output = handler
+        .autocomplete_station_names(AutocompleteStationNamesInput {
+            prefix: "Ly".to_string(),
+            limit: 2,
+            lenient: None,
+        })
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = output.suggestions.iter().map(|s| &*s.name).collect();
+    assert_eq!(names, vec!["Lyon Street Garden", "Gare de Lyon - Chalon"]);
+
+    let empty = handler
+        .autocomplete_station_names(AutocompleteStationNamesInput {
+            prefix: " ".to_string(),
+            limit: 5,
+            lenient: None,
+        })
+        .await;
+    assert!(empty.is_err());
+}
+
+#[tokio::test]
+async fn test_nearby_honors_minimum_availability() {
+    let handler = fixture_handler().await;
+    let nearby = |filter: AvailabilityFilter| {
+        handler.find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8446,
+            longitude: 2.3737,
+            radius_meters: 500,
+            limit: 10,
+            availability_filter: Some(filter),
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+    };
+
+    let enough = nearby(AvailabilityFilter {
+        min_bikes: Some(8),
+        min_docks: Some(10),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(enough.stations.len(), 2);
+    assert_eq!(enough.search_metadata.total_found, 2);
+    assert!(!enough.search_metadata.has_more);
+    assert!(enough.search_metadata.guidance.is_none());
+
+    let too_few_electric = nearby(AvailabilityFilter {
+        bike_type: Some(BikeTypeFilter::ElectricOnly),
+        min_bikes: Some(4),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert!(too_few_electric.stations.is_empty());
+
+    let too_few_docks = nearby(AvailabilityFilter {
+        min_docks: Some(11),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert!(too_few_docks.stations.is_empty());
+}
+
+#[tokio::test]
+async fn test_stations_carry_availability_labels() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12002".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(0, 0),
+            30,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let client = seeded_client(stations, realtime)
+        .await
+        .with_availability_thresholds(AvailabilityThresholds {
+            low_below: 0.5,
+            near_full_from: 0.9,
+        });
+    let handler = McpToolHandler::with_data_client(client);
+    let label = |code: &str| {
+        let handler = &handler;
+        let code = code.to_string();
+        async move {
+            handler
+                .get_station_by_code(GetStationByCodeInput {
+                    station_code: code,
+                    include_real_time: true,
+                })
+                .await
+                .unwrap()
+                .station
+                .and_then(|station| station.real_time)
+                .and_then(|status| status.availability_label)
+        }
+    };
+
+    // 8 bikes for 10 free docks is under the raised `low_below`
+    assert_eq!(label("12001").await, Some(AvailabilityLabel::Low));
+    assert_eq!(label("12002").await, Some(AvailabilityLabel::Empty));
+}
+
+#[tokio::test]
+async fn test_distances_come_with_walking_times() {
+    let handler = fixture_handler()
+        .await
+        .with_walking_speed(WalkingSpeed { kmh: 3.0 });
+    let output = handler
+        .find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8446,
+            longitude: 2.3737,
+            radius_meters: 500,
+            limit: 10,
+            availability_filter: None,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    for station in &output.stations {
+        // 50 m per minute, rounded up
+        assert_eq!(
+            station.walk_time_minutes,
+            station.distance_meters.div_ceil(50),
+            "{}",
+            station.distance_meters
+        );
+    }
+    assert!(output.stations[1].walk_time_minutes > 0);
+
+    let journey = handler
+        .plan_bike_journey(PlanBikeJourneyInput {
+            origin: Coordinates::new(48.8446, 2.3737),
+            destination: Coordinates::new(48.8566, 2.3522),
+            preferences: None,
+            route_format: None,
+        })
+        .await
+        .unwrap()
+        .journey;
+    let recommendation = &journey.recommendations[0];
+    assert_eq!(
+        recommendation.walk_from_dropoff_minutes,
+        journey.dropoff_stations[0].walk_time_minutes
+    );
+}
+
+#[tokio::test]
+async fn test_sort_by_applies_before_limit() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12002".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(9, 4),
+            2,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+    let nearest = |sort: SortOptions| {
+        handler.find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8446,
+            longitude: 2.3737,
+            radius_meters: 500,
+            limit: 1,
+            availability_filter: None,
+            sort,
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+    };
+
+    let by_distance = nearest(SortOptions::default()).await.unwrap();
+    assert_eq!(
+        &*by_distance.stations[0].station.reference.station_code,
+        "12001"
+    );
+
+    let by_bikes = nearest(SortOptions::by(SortField::BikesAvailable))
+        .await
+        .unwrap();
+    assert_eq!(
+        &*by_bikes.stations[0].station.reference.station_code,
+        "12002"
+    );
+
+    let by_docks = nearest(SortOptions {
+        sort_by: Some(SortField::DocksAvailable),
+        sort_direction: Some(SortDirection::Ascending),
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        &*by_docks.stations[0].station.reference.station_code,
+        "12002"
+    );
+
+    let name_search = handler
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "Gare".to_string(),
+            limit: 10,
+            fuzzy: true,
+            sort: SortOptions::by(SortField::Distance),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await;
+    assert!(matches!(name_search, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_coordinates_are_sanitized_before_use() {
+    let handler = fixture_handler().await;
+    let nearby = |latitude: f64, longitude: f64| {
+        handler.find_nearby_stations(FindNearbyStationsInput {
+            latitude,
+            longitude,
+            radius_meters: 500,
+            limit: 10,
+            availability_filter: None,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+    };
+
+    let output = nearby(48.844_612_345_678_9, 2.373_698_765_432_1)
+        .await
+        .unwrap();
+    assert_eq!(
+        output.search_metadata.query_point,
+        Coordinates::new(48.844_612, 2.373_699)
+    );
+
+    for (latitude, longitude) in [(f64::NAN, 2.3737), (48.8446, f64::INFINITY)] {
+        assert!(matches!(
+            nearby(latitude, longitude).await,
+            Err(Error::InvalidCoordinates { .. })
+        ));
+    }
+
+    // Tools that never checked the service area still refuse garbage
+    let compared = handler
+        .compare_stations(CompareStationsInput {
+            station_codes: vec!["12001".to_string(), "12002".to_string()],
+            reference_point: Some(Coordinates::new(48.8446, f64::NAN)),
+        })
+        .await;
+    assert!(matches!(compared, Err(Error::InvalidCoordinates { .. })));
+    let statistics = handler
+        .get_area_statistics(GetAreaStatisticsInput {
+            bounds: Some(GeographicBounds {
+                north: f64::INFINITY,
+                south: 48.83,
+                east: 2.38,
+                west: 2.34,
+            }),
+            polygon: None,
+            include_real_time: true,
+            window_minutes: None,
+        })
+        .await;
+    assert!(matches!(statistics, Err(Error::InvalidCoordinates { .. })));
+    let route = handler
+        .find_stations_along_route(FindStationsAlongRouteInput {
+            waypoints: vec![
+                Coordinates::new(48.8446, 2.3737),
+                Coordinates::new(91.0, 2.3522),
+            ],
+            buffer_meters: 200,
+            limit: 10,
+            availability_filter: None,
+            lenient: None,
+        })
+        .await;
+    assert!(matches!(route, Err(Error::InvalidCoordinates { .. })));
+}
+
+#[tokio::test]
+async fn test_nearby_reports_stations_cut_by_limit() {
+    let handler = fixture_handler().await;
+    let output = handler
+        .find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8446,
+            longitude: 2.3737,
+            radius_meters: 5000,
+            limit: 2,
+            availability_filter: None,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(output.stations.len(), 2);
+    assert_eq!(output.search_metadata.total_found, 4);
+    assert!(output.search_metadata.has_more);
+    let guidance = output.search_metadata.guidance.unwrap();
+    assert!(guidance.starts_with("Showing 2 of 4 stations within 5000 m"));
+    assert!(guidance.contains("raise limit"));
+}
+
+#[tokio::test]
+async fn test_lenient_limits_clamp_instead_of_failing() {
+    let nearby = |lenient: Option<bool>| FindNearbyStationsInput {
+        district: None,
+        latitude: 48.8446,
+        longitude: 2.3737,
+        radius_meters: 20_000,
+        limit: 500,
+        availability_filter: None,
+        sort: Default::default(),
+        lenient,
+        auto_expand: false,
+        barrier_aware: false,
+        min_results: 1,
+        near_transit: None,
+        transit_radius_meters: None,
+    };
+
+    let strict = fixture_handler().await;
+    assert!(matches!(
+        strict.find_nearby_stations(nearby(None)).await,
+        Err(Error::SearchRadiusTooLarge { .. })
+    ));
+
+    let clamped = strict
+        .find_nearby_stations(nearby(Some(true)))
+        .await
+        .unwrap();
+    assert!(clamped.search_metadata.truncated);
+    assert_eq!(clamped.search_metadata.radius_meters, 5000);
+    assert_eq!(
+        clamped.search_metadata.applied_limits,
+        Some(AppliedLimits {
+            limit: 100,
+            radius_meters: Some(5000)
+        })
+    );
+    // Everything but Lyon Street Garden, over 5 km north
+    assert_eq!(clamped.stations.len(), 4);
+
+    // A server-wide lenient default can still be overridden per request
+    let lenient = fixture_handler().await.with_lenient_limits(true);
+    let search = lenient
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "Gare".to_string(),
+            limit: 150,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    assert!(search.search_metadata.truncated);
+    assert!(lenient
+        .find_nearby_stations(nearby(Some(false)))
+        .await
+        .is_err());
+
+    let within_limits = lenient
+        .find_nearby_stations(FindNearbyStationsInput {
+            radius_meters: 500,
+            limit: 10,
+            ..nearby(None)
+        })
+        .await
+        .unwrap();
+    assert!(!within_limits.search_metadata.truncated);
+    assert!(within_limits.search_metadata.applied_limits.is_none());
+}
+
+#[tokio::test]
+async fn test_sparkline_draws_observed_history() {
+    let stations = fixture_stations();
+    let mut client = seeded_client(stations.clone(), fixture_realtime()).await;
+    let mut later = fixture_realtime();
+    later.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(12, 6),
+            0,
+            StationStatus::Open,
+            Utc::now() + chrono::Duration::minutes(1),
+        ),
+    );
+    client.seed_cache(stations, later).await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let output = handler
+        .render_station_sparkline(RenderStationSparklineInput {
+            station_code: "12001".to_string(),
+            hours: 24,
+            format: SparklineFormat::Svg,
+        })
+        .await
+        .unwrap();
+    assert_eq!(output.samples, 2);
+    assert_eq!((output.min_bikes, output.max_bikes), (Some(8), Some(18)));
+    assert_eq!(output.sparkline, "▄█");
+    assert!(output.svg.unwrap().starts_with("<svg"));
+
+    let unknown = handler
+        .render_station_sparkline(RenderStationSparklineInput {
+            station_code: "99999".to_string(),
+            hours: 24,
+            format: SparklineFormat::Text,
+        })
+        .await;
+    assert!(matches!(unknown, Err(Error::StationNotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_area_statistics_over_a_trailing_window() {
+    let stations = fixture_stations();
+    let mut client = seeded_client(stations.clone(), fixture_realtime()).await;
+    let mut later = fixture_realtime();
+    later.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(20, 10),
+            0,
+            StationStatus::Open,
+            Utc::now() + chrono::Duration::minutes(1),
+        ),
+    );
+    client.seed_cache(stations, later).await;
+    let handler = McpToolHandler::with_data_client(client);
+    let input = |window_minutes| GetAreaStatisticsInput {
+        bounds: Some(GeographicBounds {
+            north: 48.90,
+            south: 48.83,
+            east: 2.40,
+            west: 2.33,
+        }),
+        polygon: None,
+        include_real_time: true,
+        window_minutes,
+    };
+
+    let stats = handler.get_area_statistics(input(Some(30))).await.unwrap();
+    let window = stats.window.unwrap();
+    let now = stats.area_stats.available_bikes.total;
+    // 12001 went from 8 to 30 bikes while the rest of the area stayed put
+    assert_eq!(window.window_minutes, 30);
+    assert_eq!((window.min_bikes, window.max_bikes), (now - 22, now));
+    assert!(window.samples >= 2);
+    assert!(window.mean_bikes > f64::from(window.min_bikes));
+    assert!(window.mean_bikes <= f64::from(window.max_bikes));
+
+    let snapshot_only = handler.get_area_statistics(input(None)).await.unwrap();
+    assert!(snapshot_only.window.is_none());
+    for minutes in [0, 1441] {
+        assert!(matches!(
+            handler.get_area_statistics(input(Some(minutes))).await,
+            Err(Error::Validation(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_district_filters_and_area_breakdown() {
+    let handler = fixture_handler().await;
+
+    let nearby = handler
+        .find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8400,
+            longitude: 2.3750,
+            radius_meters: 2000,
+            limit: 10,
+            availability_filter: None,
+            sort: Default::default(),
+            lenient: None,
+            district: Some("12e".to_string()),
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    let codes: Vec<&str> = nearby
+        .stations
+        .iter()
+        .map(|s| &*s.station.reference.station_code)
+        .collect();
+    assert_eq!(codes.len(), 2);
+    assert!(codes.iter().all(|code| code.starts_with("12")));
+
+    let search = handler
+        .search_stations_by_name(SearchStationsByNameInput {
+            query: "Gare de Lyon".to_string(),
+            limit: 10,
+            fuzzy: true,
+            sort: Default::default(),
+            lenient: None,
+            district: Some("Paris 13e".to_string()),
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    assert!(search.stations.is_empty());
+
+    let stats = handler
+        .get_area_statistics(GetAreaStatisticsInput {
+            bounds: Some(GeographicBounds {
+                north: 48.90,
+                south: 48.83,
+                east: 2.40,
+                west: 2.33,
+            }),
+            polygon: None,
+            include_real_time: true,
+            window_minutes: None,
+        })
+        .await
+        .unwrap();
+    let districts: Vec<(&str, u32)> = stats
+        .by_district
+        .iter()
+        .map(|d| (d.district.as_str(), d.stats.total_stations))
+        .collect();
+    assert_eq!(
+        districts,
+        vec![
+            ("Paris 4e", 1),
+            ("Paris 12e", 2),
+            ("Paris 13e", 1),
+            ("Paris 18e", 1)
+        ]
+    );
+    assert_eq!(stats.area_stats.total_stations, 5);
+}
+
+#[tokio::test]
+async fn test_near_transit_keeps_stations_by_the_line() {
+    let handler = fixture_handler().await;
+    let nearby = handler
+        .find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.85,
+            longitude: 2.36,
+            radius_meters: 3000,
+            limit: 10,
+            availability_filter: None,
+            sort: SortOptions::by(SortField::Name),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: Some("Ligne 1".to_string()),
+            transit_radius_meters: None,
+        })
+        .await
+        .unwrap();
+    let codes: Vec<&str> = nearby
+        .stations
+        .iter()
+        .map(|found| &*found.station.reference.station_code)
+        .collect();
+    // The library station is on line 14 only
+    assert_eq!(codes, vec!["12002", "12001", "4001"]);
+    assert_eq!(
+        nearby.search_metadata.near_transit.as_deref(),
+        Some("Ligne 1")
+    );
+
+    let along_line = |query: &str, near_transit: &str, radius: Option<u32>| {
+        handler.search_stations_by_name(SearchStationsByNameInput {
+            query: query.to_string(),
+            limit: 10,
+            fuzzy: false,
+            sort: SortOptions::default(),
+            lenient: None,
+            district: None,
+            near_transit: Some(near_transit.to_string()),
+            transit_radius_meters: radius,
+        })
+    };
+    let line_14 = along_line("", "M14", None).await.unwrap();
+    let codes: Vec<&str> = line_14
+        .stations
+        .iter()
+        .map(|station| &*station.reference.station_code)
+        .collect();
+    assert_eq!(codes, vec!["12001", "12002"]);
+
+    // Hôtel de Ville is about 380 m from Châtelet, the library 410 m from its stop
+    let wider = along_line("", "Ligne 14", Some(500)).await.unwrap();
+    let codes: Vec<&str> = wider
+        .stations
+        .iter()
+        .map(|station| &*station.reference.station_code)
+        .collect();
+    assert_eq!(codes, vec!["12001", "12002", "4001", "13001"]);
+
+    let by_stop = along_line("Gare", "Gare de Lyon", None).await.unwrap();
+    assert_eq!(by_stop.stations.len(), 2);
+    assert_eq!(
+        by_stop.search_metadata.near_transit.as_deref(),
+        Some("Gare de Lyon")
+    );
+
+    assert!(matches!(
+        along_line("Gare", "Ligne 42", None).await,
+        Err(Error::Validation(message)) if message.contains("known lines: 1, 4, 14")
+    ));
+}
+
+#[tokio::test]
+async fn test_bike_type_preferences_fall_back_in_order() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    let mechanical_only = |mechanical| {
+        RealTimeStatus::new(
+            BikeAvailability::new(mechanical, 0),
+            10,
+            StationStatus::Open,
+            Utc::now(),
+        )
+    };
+    realtime.insert("12001".to_string(), mechanical_only(5));
+    let handler =
+        McpToolHandler::with_data_client(seeded_client(stations.clone(), realtime.clone()).await);
+    let plan = |handler: &McpToolHandler, preferences: serde_json::Value| {
+        let handler = handler.clone();
+        async move {
+            handler
+                .plan_bike_journey(PlanBikeJourneyInput {
+                    origin: Coordinates::new(48.8446, 2.3737),
+                    destination: Coordinates::new(48.8566, 2.3522),
+                    preferences: Some(serde_json::from_value(preferences).unwrap()),
+                    route_format: None,
+                })
+                .await
+        }
+    };
+    let electric_first = serde_json::json!({
+        "bike_type": ["electric", "mechanical"],
+        "max_walk_distance": 500
+    });
+
+    // The closest station only has mechanical bikes: the next one has e-bikes
+    let journey = plan(&handler, electric_first.clone())
+        .await
+        .unwrap()
+        .journey;
+    let recommendation = &journey.recommendations[0];
+    assert_eq!(
+        &*recommendation.pickup_station.reference.station_code,
+        "12002"
+    );
+    assert_eq!(recommendation.bike_type, BikeTypeFilter::ElectricOnly);
+    assert_eq!(recommendation.preference_level, 1);
+
+    // No e-bikes nearby at all: fall back to the closest mechanical bike
+    realtime.insert("12002".to_string(), mechanical_only(5));
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+    let journey = plan(&handler, electric_first).await.unwrap().journey;
+    let recommendation = &journey.recommendations[0];
+    assert_eq!(
+        &*recommendation.pickup_station.reference.station_code,
+        "12001"
+    );
+    assert_eq!(recommendation.bike_type, BikeTypeFilter::MechanicalOnly);
+    assert_eq!(recommendation.preference_level, 2);
+
+    // A single type is still accepted, and electric only finds nothing
+    let electric_only = plan(&handler, serde_json::json!({"bike_type": "electric"}))
+        .await
+        .unwrap();
+    assert!(electric_only.journey.recommendations.is_empty());
+    assert!(matches!(
+        plan(&handler, serde_json::json!({"bike_type": []})).await,
+        Err(Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_multiple_journeys_flag_the_last_bike() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(1, 0),
+            10,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+    let journey = |destination: Coordinates| JourneyEndpoints {
+        origin: Coordinates::new(48.8446, 2.3737),
+        destination,
+    };
+    let plan = |journeys: Vec<JourneyEndpoints>| {
+        handler.plan_multiple_journeys(PlanMultipleJourneysInput {
+            journeys,
+            preferences: Some(JourneyPreferences {
+                bike_type: BikeTypePreference::Single(BikeTypeFilter::MechanicalOnly),
+                max_walk_distance: 100,
+                barrier_aware: false,
+                include_suspected_out_of_service: false,
+            }),
+            route_format: None,
+        })
+    };
+
+    let output = plan(vec![
+        journey(Coordinates::new(48.8566, 2.3522)),
+        journey(Coordinates::new(48.8566, 2.3522)),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(output.journeys.len(), 2);
+    for planned in &output.journeys {
+        assert_eq!(
+            &*planned.recommendations[0]
+                .pickup_station
+                .reference
+                .station_code,
+            "12001"
+        );
+    }
+    let pickup = output
+        .warnings
+        .iter()
+        .find(|warning| warning.station_use == SharedStationUse::Pickup)
+        .unwrap();
+    assert_eq!(pickup.station_code, "12001");
+    assert_eq!(pickup.journeys, [0, 1]);
+    assert_eq!(pickup.available, 1);
+
+    // A single journey competes with nobody
+    let alone = plan(vec![journey(Coordinates::new(48.8566, 2.3522))])
+        .await
+        .unwrap();
+    assert!(alone.warnings.is_empty());
+
+    assert!(matches!(plan(Vec::new()).await, Err(Error::Validation(_))));
+    assert!(matches!(
+        plan(vec![journey(Coordinates::new(48.8566, 2.3522)); 11]).await,
+        Err(Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_partial_service_splits_pickup_and_dropoff() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    for (code, renting, returning) in [("12001", false, true), ("12002", true, false)] {
+        let status = realtime
+            .remove(code)
+            .unwrap()
+            .with_service(renting, returning);
+        assert_eq!(status.status, StationStatus::Maintenance);
+        realtime.insert(code.to_string(), status);
+    }
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+
+    let journey = handler
+        .plan_bike_journey(PlanBikeJourneyInput {
+            origin: Coordinates::new(48.8446, 2.3737),
+            destination: Coordinates::new(48.8440, 2.3755),
+            preferences: None,
+            route_format: None,
+        })
+        .await
+        .unwrap()
+        .journey;
+    let codes = |stations: &[velib_mcp::mcp::types::StationWithDistance]| -> Vec<String> {
+        stations
+            .iter()
+            .map(|s| s.station.reference.station_code.to_string())
+            .collect()
+    };
+    assert_eq!(codes(&journey.pickup_stations), vec!["12002"]);
+    assert_eq!(codes(&journey.dropoff_stations), vec!["12001"]);
+
+    let nearby = |purpose: Option<StationPurpose>| {
+        handler.find_nearby_stations(FindNearbyStationsInput {
+            latitude: 48.8446,
+            longitude: 2.3737,
+            radius_meters: 500,
+            limit: 10,
+            availability_filter: Some(AvailabilityFilter {
+                purpose,
+                ..Default::default()
+            }),
+            sort: Default::default(),
+            lenient: None,
+            district: None,
+            auto_expand: false,
+            barrier_aware: false,
+            min_results: 1,
+            near_transit: None,
+            transit_radius_meters: None,
+        })
+    };
+    assert!(nearby(None).await.unwrap().stations.is_empty());
+    assert_eq!(
+        codes(
+            &nearby(Some(StationPurpose::Dropoff))
+                .await
+                .unwrap()
+                .stations
+        ),
+        vec!["12001"]
+    );
+}
+
+#[tokio::test]
+async fn test_journeys_skip_stations_suspected_out_of_service() {
+    let clock = std::sync::Arc::new(velib_mcp::ManualClock::new(Utc::now()));
+    let mut stations = fixture_stations();
+    stations[0].capacity = 30;
+    let mut realtime = fixture_realtime();
+    // Two of its 30 docks answering
+    realtime.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(1, 0),
+            1,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let mut client = VelibDataClient::new().with_clock(clock.clone());
+    client.seed_cache(stations.clone(), realtime.clone()).await;
+    clock.advance(chrono::Duration::hours(1));
+    client.seed_cache(stations, realtime).await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let station = handler
+        .get_station_by_code(GetStationByCodeInput {
+            station_code: "12001".to_string(),
+            include_real_time: true,
+        })
+        .await
+        .unwrap()
+        .station
+        .and_then(|station| station.real_time)
+        .unwrap();
+    assert!(station.suspected_out_of_service);
+
+    let plan = |include_suspected_out_of_service| {
+        handler.plan_bike_journey(PlanBikeJourneyInput {
+            origin: Coordinates::new(48.8446, 2.3737),
+            destination: Coordinates::new(48.8446, 2.3737),
+            preferences: Some(JourneyPreferences {
+                include_suspected_out_of_service,
+                ..Default::default()
+            }),
+            route_format: None,
+        })
+    };
+    let codes = |stations: &[velib_mcp::mcp::types::StationWithDistance]| -> Vec<String> {
+        stations
+            .iter()
+            .map(|s| s.station.reference.station_code.to_string())
+            .collect()
+    };
+    let journey = plan(false).await.unwrap().journey;
+    assert!(!codes(&journey.pickup_stations).contains(&"12001".to_string()));
+    assert!(!codes(&journey.dropoff_stations).contains(&"12001".to_string()));
+    let journey = plan(true).await.unwrap().journey;
+    assert_eq!(codes(&journey.pickup_stations)[0], "12001");
+    assert_eq!(codes(&journey.dropoff_stations)[0], "12001");
+}
+
+#[tokio::test]
+async fn test_configured_service_area_bounds_queries() {
+    let handler = fixture_handler().await.with_service_area(ServiceArea {
+        center: Coordinates::new(48.8446, 2.3737),
+        radius_meters: 2_000.0,
+        ..ServiceArea::paris()
+    });
+    let nearby = |latitude: f64, longitude: f64| FindNearbyStationsInput {
+        latitude,
+        longitude,
+        radius_meters: 500,
+        limit: 10,
+        availability_filter: None,
+        sort: Default::default(),
+        lenient: None,
+        district: None,
+        auto_expand: false,
+        barrier_aware: false,
+        min_results: 1,
+        near_transit: None,
+        transit_radius_meters: None,
+    };
+
+    let output = handler
+        .find_nearby_stations(nearby(48.8446, 2.3737))
+        .await
+        .unwrap();
+    assert_eq!(output.stations.len(), 2);
+
+    // Montmartre is ~5km from Gare de Lyon
+    let error = handler
+        .find_nearby_stations(nearby(48.8867, 2.3431))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::OutsideServiceArea { max_km, .. } if max_km == 2.0));
+    assert!(error.to_string().contains("(max: 2.0km)"));
+}
+
+#[tokio::test]
+async fn test_area_statistics_within_polygon() {
+    let handler = fixture_handler().await;
+    // A triangle around Gare de Lyon and the BnF, leaving out the rest of Paris
+    let polygon: Geometry = serde_json::from_value(serde_json::json!({
+        "type": "Polygon",
+        "coordinates": [[[2.365, 48.850], [2.385, 48.850], [2.375, 48.825], [2.365, 48.850]]]
+    }))
+    .unwrap();
+
+    let stats = handler
+        .get_area_statistics(GetAreaStatisticsInput {
+            bounds: None,
+            polygon: Some(polygon),
+            include_real_time: true,
+            window_minutes: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(stats.area_stats.total_stations, 3);
+    assert_eq!(stats.bounds.north, 48.850);
+    assert_eq!(stats.bounds.west, 2.365);
+
+    let neither = handler
+        .get_area_statistics(GetAreaStatisticsInput {
+            bounds: None,
+            polygon: None,
+            include_real_time: true,
+            window_minutes: None,
+        })
+        .await;
+    assert!(matches!(neither, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_service_area_polygon_rejects_outside_points() {
+    let handler = fixture_handler().await.with_service_area(ServiceArea {
+        polygon: Some(Geometry::Polygon {
+            coordinates: vec![vec![
+                [2.33, 48.83],
+                [2.40, 48.83],
+                [2.40, 48.87],
+                [2.33, 48.87],
+            ]],
+        }),
+        ..ServiceArea::paris()
+    });
+
+    let journey = |latitude: f64| PlanBikeJourneyInput {
+        origin: Coordinates::new(48.8446, 2.3737),
+        destination: Coordinates::new(latitude, 2.3431),
+        preferences: None,
+        route_format: None,
+    };
+    assert!(handler.plan_bike_journey(journey(48.8566)).await.is_ok());
+
+    // Montmartre is well inside 50km but north of the polygon
+    let error = handler
+        .plan_bike_journey(journey(48.8867))
+        .await
+        .unwrap_err();
+    assert_eq!(error.error_type(), "outside_service_area");
+}
+
+#[tokio::test]
+async fn test_station_density_and_coverage_gaps() {
+    let handler = fixture_handler().await;
+    let density = |bounds, district: Option<&str>| GetStationDensityInput {
+        bounds,
+        district: district.map(str::to_string),
+        gap_distance_meters: 300,
+        cell_size_meters: 300,
+    };
+
+    // About 1.1km², holding both Gare de Lyon stations
+    let output = handler
+        .get_station_density(density(
+            Some(GeographicBounds {
+                north: 48.850,
+                south: 48.840,
+                east: 2.3800,
+                west: 2.3650,
+            }),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(output.total_stations, 2);
+    assert_eq!(output.total_capacity, 36);
+    assert!((1.5..2.0).contains(&output.stations_per_km2));
+    assert_eq!(output.capacity_per_km2, output.stations_per_km2 * 18.0);
+    assert_eq!(output.coverage.grid_cells, 16);
+    assert!(output.coverage.uncovered_cells > 0);
+    assert!(output.coverage.covered_ratio > 0.0 && output.coverage.covered_ratio < 1.0);
+    assert!(output
+        .coverage
+        .gaps
+        .iter()
+        .all(|gap| gap.nearest_station_meters.unwrap() > 300));
+
+    let district = handler
+        .get_station_density(density(None, Some("12e")))
+        .await
+        .unwrap();
+    assert_eq!(district.total_stations, 2);
+    assert_eq!(district.district.as_deref(), Some("12e"));
+
+    let neither = handler.get_station_density(density(None, None)).await;
+    assert!(matches!(neither, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_stations_along_route_follow_its_progression() {
+    let handler = fixture_handler().await;
+    // From the BnF up past Gare de Lyon to the Hôtel de Ville
+    let route = |buffer_meters: u32| FindStationsAlongRouteInput {
+        waypoints: vec![
+            Coordinates::new(48.8335, 2.3765),
+            Coordinates::new(48.8446, 2.3737),
+            Coordinates::new(48.8566, 2.3522),
+        ],
+        buffer_meters,
+        limit: 10,
+        availability_filter: None,
+        lenient: None,
+    };
+
+    let output = handler.find_stations_along_route(route(200)).await.unwrap();
+    let codes: Vec<&str> = output
+        .stations
+        .iter()
+        .map(|s| &*s.station.reference.station_code)
+        .collect();
+    assert_eq!(codes, vec!["13001", "12002", "12001", "4001"]);
+    assert!(output
+        .stations
+        .windows(2)
+        .all(|pair| pair[0].progress_meters <= pair[1].progress_meters));
+    assert!((3000..3500).contains(&output.route_length_meters));
+
+    // Only the stations on the waypoints themselves
+    let tight = handler.find_stations_along_route(route(50)).await.unwrap();
+    assert_eq!(tight.total_found, 3);
+
+    let too_wide = handler.find_stations_along_route(route(5000)).await;
+    assert!(matches!(too_wide, Err(Error::SearchRadiusTooLarge { .. })));
+
+    let mut single_point = route(200);
+    single_point.waypoints.truncate(1);
+    assert!(matches!(
+        handler.find_stations_along_route(single_point).await,
+        Err(Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_auto_expand_widens_radius_until_enough_results() {
+    let handler = fixture_handler().await;
+    // Place de la Bastille, about 1.2km from Gare de Lyon and the Hôtel de Ville
+    let nearby = |auto_expand: bool, min_results: u16| FindNearbyStationsInput {
+        latitude: 48.8532,
+        longitude: 2.3691,
+        radius_meters: 300,
+        limit: 10,
+        availability_filter: None,
+        sort: Default::default(),
+        lenient: None,
+        district: None,
+        auto_expand,
+        barrier_aware: false,
+        min_results,
+        near_transit: None,
+        transit_radius_meters: None,
+    };
+
+    let fixed = handler
+        .find_nearby_stations(nearby(false, 1))
+        .await
+        .unwrap();
+    assert!(fixed.stations.is_empty());
+    assert_eq!(fixed.search_metadata.radius_meters, 300);
+    assert_eq!(fixed.search_metadata.expanded_from_meters, None);
+
+    let expanded = handler.find_nearby_stations(nearby(true, 3)).await.unwrap();
+    assert_eq!(expanded.search_metadata.radius_meters, 2400);
+    assert_eq!(expanded.search_metadata.expanded_from_meters, Some(300));
+    // Everything within the final radius, not just min_results
+    assert_eq!(expanded.stations.len(), 4);
+    assert!(expanded.stations.iter().all(|s| s.distance_meters <= 2400));
+
+    // Stops at the maximum radius even when too few stations exist
+    let capped = handler
+        .find_nearby_stations(nearby(true, 50))
+        .await
+        .unwrap();
+    assert_eq!(capped.search_metadata.radius_meters, 5000);
+    assert_eq!(capped.stations.len(), 5);
+}
+
+#[tokio::test]
+async fn test_dropoff_feasibility_suggests_safer_stations() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(20, 10),
+            0,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+    let check =
+        |arrival_in_minutes: i64, station_code: Option<&str>| CheckDropoffFeasibilityInput {
+            destination: Coordinates::new(48.8446, 2.3737),
+            arrival_time: Some(Utc::now() + chrono::Duration::minutes(arrival_in_minutes)),
+            hold_window_minutes: 5,
+            station_code: station_code.map(str::to_string),
+            max_walk_meters: 500,
+        };
+
+    let output = handler
+        .check_dropoff_feasibility(check(10, None))
+        .await
+        .unwrap();
+    assert!((14..=15).contains(&output.minutes_ahead));
+    let full = output.station.unwrap();
+    assert_eq!(full.station_code, "12001");
+    assert_eq!(full.available_docks, 0);
+    assert!(full.probability < 0.5);
+    let codes: Vec<&str> = output
+        .alternatives
+        .iter()
+        .map(|alternative| alternative.station_code.as_str())
+        .collect();
+    assert_eq!(codes, vec!["12002"]);
+    assert!(output.alternatives[0].probability > 0.9);
+
+    let chosen = handler
+        .check_dropoff_feasibility(check(0, Some("12002")))
+        .await
+        .unwrap();
+    assert_eq!(chosen.station.unwrap().station_code, "12002");
+    assert_eq!(chosen.alternatives[0].station_code, "12001");
+
+    assert!(matches!(
+        handler.check_dropoff_feasibility(check(180, None)).await,
+        Err(Error::Validation(_))
+    ));
+    assert!(matches!(
+        handler
+            .check_dropoff_feasibility(check(0, Some("99999")))
+            .await,
+        Err(Error::StationNotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_compare_with_typical_weekday_and_hour() {
+    let stations = fixture_stations();
+    let mut client = VelibDataClient::new().with_history_retention(chrono::Duration::days(15));
+    // 20 bikes everywhere at this time one and two weeks ago, 8 now
+    for weeks_ago in [2, 1] {
+        let at = Utc::now() - chrono::Duration::weeks(weeks_ago);
+        let past = stations
+            .iter()
+            .map(|station| {
+                (
+                    station.station_code.to_string(),
+                    RealTimeStatus::new(BikeAvailability::new(12, 8), 10, StationStatus::Open, at),
+                )
+            })
+            .collect();
+        client.seed_cache(stations.clone(), past).await;
+    }
+    client
+        .seed_cache(stations.clone(), fixture_realtime())
+        .await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let station = handler
+        .compare_with_typical(CompareWithTypicalInput {
+            station_code: Some("12001".to_string()),
+            latitude: None,
+            longitude: None,
+            radius_meters: 500,
+        })
+        .await
+        .unwrap();
+    assert_eq!(station.station_code.as_deref(), Some("12001"));
+    assert_eq!(
+        (station.current_bikes, station.typical_bikes),
+        (8, Some(20.0))
+    );
+    assert_eq!(station.weeks_compared, 2);
+    assert!(station.deviation.unwrap() < -2.0);
+    assert_eq!(station.level, TypicalLevel::UnusuallyEmpty);
+    assert!(station.summary.starts_with("Unusually empty"));
+
+    let area = handler
+        .compare_with_typical(CompareWithTypicalInput {
+            station_code: None,
+            latitude: Some(48.8446),
+            longitude: Some(2.3737),
+            radius_meters: 500,
+        })
+        .await
+        .unwrap();
+    assert_eq!(area.stations_compared, 2);
+    assert_eq!((area.current_bikes, area.typical_bikes), (16, Some(40.0)));
+
+    let without_history = fixture_handler()
+        .await
+        .compare_with_typical(CompareWithTypicalInput {
+            station_code: Some("12001".to_string()),
+            latitude: None,
+            longitude: None,
+            radius_meters: 500,
+        })
+        .await
+        .unwrap();
+    assert_eq!(without_history.level, TypicalLevel::Unknown);
+    assert_eq!(without_history.weeks_compared, 0);
+    assert!(without_history
+        .summary
+        .contains("VELIB_HISTORY_RETENTION_HOURS"));
+
+    assert!(matches!(
+        handler
+            .compare_with_typical(CompareWithTypicalInput {
+                station_code: Some("12001".to_string()),
+                latitude: Some(48.8446),
+                longitude: None,
+                radius_meters: 500,
+            })
+            .await,
+        Err(Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_diff_station_status_since_snapshot() {
+    let stations = fixture_stations();
+    let mut client = seeded_client(stations.clone(), fixture_realtime()).await;
+    let between = Utc::now();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(2, 0),
+            16,
+            StationStatus::Closed,
+            Utc::now(),
+        ),
+    );
+    client.seed_cache(stations.clone(), realtime).await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let everything = handler
+        .diff_station_status(DiffStationStatusInput::default())
+        .await
+        .unwrap();
+    assert!(everything.full_resync);
+    assert_eq!(everything.changes.len(), stations.len());
+
+    let diff = handler
+        .diff_station_status(DiffStationStatusInput {
+            since_snapshot: None,
+            since: Some(between),
+        })
+        .await
+        .unwrap();
+    assert!(!diff.full_resync);
+    assert_eq!(diff.snapshot, everything.snapshot);
+    assert_eq!(diff.changes.len(), 1);
+    let change = &diff.changes[0];
+    assert_eq!(change.station_code, "12001");
+    assert_eq!(change.bikes_delta, Some(-6));
+    assert_eq!(change.previous_status, Some(StationStatus::Open));
+
+    let caught_up = handler
+        .diff_station_status(DiffStationStatusInput {
+            since_snapshot: Some(diff.snapshot.clone()),
+            since: None,
+        })
+        .await
+        .unwrap();
+    assert!(caught_up.changes.is_empty());
+
+    let unknown = handler
+        .diff_station_status(DiffStationStatusInput {
+            since_snapshot: Some("ffffffffffffffff".to_string()),
+            since: None,
+        })
+        .await
+        .unwrap();
+    assert!(unknown.full_resync);
+}
+
+#[tokio::test]
+async fn test_compare_stations_side_by_side() {
+    let stations = fixture_stations();
+    let mut realtime = fixture_realtime();
+    realtime.insert(
+        "12002".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(0, 0),
+            30,
+            StationStatus::Open,
+            Utc::now(),
+        ),
+    );
+    let handler = McpToolHandler::with_data_client(seeded_client(stations, realtime).await);
+    let compare = |codes: &[&str]| CompareStationsInput {
+        station_codes: codes.iter().map(|code| code.to_string()).collect(),
+        reference_point: Some(Coordinates::new(48.8446, 2.3737)),
+    };
+
+    let output = handler
+        .compare_stations(compare(&["12002", "12001"]))
+        .await
+        .unwrap();
+    let codes: Vec<&str> = output
+        .stations
+        .iter()
+        .map(|s| s.station_code.as_str())
+        .collect();
+    assert_eq!(codes, vec!["12002", "12001"]);
+    assert_eq!(output.stations[1].distance_meters, Some(0));
+    assert_eq!(output.stations[1].mechanical_bikes, 5);
+    // One sample each so far: the empty station never had a bike
+    assert_eq!(output.stations[0].reliability_score, Some(0.0));
+    assert_eq!(output.stations[1].reliability_score, Some(1.0));
+    assert!(output
+        .markdown
+        .contains("| Gare de Lyon - Diderot (12001) | open | 5 | 3 | 10/18 | 0 m | 100% |"));
+
+    for codes in [
+        &["12001"][..],
+        &["12001", "12001"],
+        &["1", "2", "3", "4", "5", "6"],
+    ] {
+        assert!(matches!(
+            handler.compare_stations(compare(codes)).await,
+            Err(Error::Validation(_))
+        ));
+    }
+    assert!(matches!(
+        handler.compare_stations(compare(&["12001", "99999"])).await,
+        Err(Error::StationNotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_system_overview_flags_citywide_outage() {
+    let stations = fixture_stations();
+    let mut client = seeded_client(stations.clone(), fixture_realtime()).await;
+    for _ in 0..5 {
+        client
+            .seed_cache(stations.clone(), fixture_realtime())
+            .await;
+    }
+    let handler = McpToolHandler::with_data_client(client);
+
+    let usual = handler.get_system_overview().await.unwrap();
+    assert_eq!(usual.system.total_stations, 5);
+    assert_eq!(usual.system.available_bikes.total, 40);
+    assert!(usual.advisories.is_empty());
+
+    // Every station closed and emptied at once
+    let mut client = seeded_client(stations.clone(), fixture_realtime()).await;
+    for _ in 0..5 {
+        client
+            .seed_cache(stations.clone(), fixture_realtime())
+            .await;
+    }
+    let outage = stations
+        .iter()
+        .map(|station| {
+            (
+                station.station_code.to_string(),
+                RealTimeStatus::new(
+                    BikeAvailability::new(0, 0),
+                    0,
+                    StationStatus::Closed,
+                    Utc::now(),
+                ),
+            )
+        })
+        .collect();
+    client.seed_cache(stations, outage).await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let overview = handler.get_system_overview().await.unwrap();
+    assert_eq!(overview.system.operational_stations, 0);
+    let kinds: Vec<AdvisoryKind> = overview.advisories.iter().map(|a| a.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![AdvisoryKind::BikeShortage, AdvisoryKind::StationClosures]
+    );
+    assert!(overview
+        .advisories
+        .iter()
+        .all(|a| a.severity == AdvisorySeverity::Critical));
+    assert_eq!(handler.advisories().await, overview.advisories);
+}
+
+#[tokio::test]
+async fn test_fleet_composition_and_its_evolution() {
+    let start = "2026-10-15T08:00:00Z".parse().unwrap();
+    let clock = std::sync::Arc::new(velib_mcp::ManualClock::new(start));
+    let stations = fixture_stations();
+    let mut client = VelibDataClient::new().with_clock(clock.clone());
+    client
+        .seed_cache(stations.clone(), fixture_realtime())
+        .await;
+    // Every electric bike out on the road
+    clock.advance(chrono::Duration::minutes(30));
+    let no_electric = stations
+        .iter()
+        .map(|station| {
+            (
+                station.station_code.to_string(),
+                RealTimeStatus::new(
+                    BikeAvailability::new(5, 0),
+                    13,
+                    StationStatus::Open,
+                    Utc::now(),
+                ),
+            )
+        })
+        .collect();
+    client.seed_cache(stations.clone(), no_electric).await;
+    clock.advance(chrono::Duration::minutes(40));
+    client
+        .seed_cache(stations.clone(), fixture_realtime())
+        .await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let now = handler
+        .get_fleet_composition(GetFleetCompositionInput::default())
+        .await
+        .unwrap();
+    assert_eq!(now.stations_reporting, 5);
+    assert_eq!(
+        (
+            now.current.mechanical,
+            now.current.electric,
+            now.current.total
+        ),
+        (25, 15, 40)
+    );
+    assert_eq!(now.current.electric_share, 0.375);
+    assert!(now.history.is_empty());
+
+    let day = handler
+        .get_fleet_composition(GetFleetCompositionInput {
+            window_minutes: Some(120),
+            interval_minutes: 60,
+        })
+        .await
+        .unwrap();
+    assert_eq!(day.window_minutes, Some(120));
+    let points: Vec<(String, usize, u32, u32)> = day
+        .history
+        .iter()
+        .map(|point| {
+            (
+                point.at.to_rfc3339(),
+                point.samples,
+                point.composition.mechanical,
+                point.composition.electric,
+            )
+        })
+        .collect();
+    assert_eq!(
+        points,
+        vec![
+            ("2026-10-15T08:00:00+00:00".to_string(), 2, 25, 8),
+            ("2026-10-15T09:00:00+00:00".to_string(), 1, 25, 15),
+        ]
+    );
+    // 15 electric out of 65 bikes seen over the two refreshes
+    assert!((day.history[0].composition.electric_share - 15.0 / 65.0).abs() < 1e-9);
+
+    assert!(matches!(
+        handler
+            .get_fleet_composition(GetFleetCompositionInput {
+                window_minutes: Some(2000),
+                interval_minutes: 60,
+            })
+            .await,
+        Err(Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_station_found_by_kiosk_code() {
+    let handler = fixture_handler().await;
+    for displayed in [
+        "04001",
+        "N° 4001",
+        "https://www.velib-metropole.fr/station/04001",
+    ] {
+        let output = handler
+            .get_station_by_code(GetStationByCodeInput {
+                station_code: displayed.to_string(),
+                include_real_time: true,
+            })
+            .await
+            .unwrap();
+        let station = output.station.expect(displayed);
+        assert_eq!(&*station.reference.station_code, "4001");
+    }
+
+    let output = handler
+        .get_station_by_code(GetStationByCodeInput {
+            station_code: "99999".to_string(),
+            include_real_time: true,
+        })
+        .await
+        .unwrap();
+    assert!(!output.found);
+}
+
+#[tokio::test]
+async fn test_barrier_aware_nearby_walks_over_a_bridge() {
+    let stations = [
+        open_station("5001", "Quai Saint-Bernard", 48.8475, 2.3600),
+        open_station("12010", "Quai de la Rapée", 48.8478, 2.3660),
+    ];
+    let handler = McpToolHandler::with_data_client(fixtures::seeded_client(&stations).await);
+    let nearby = |barrier_aware: bool| FindNearbyStationsInput {
+        latitude: 48.8474,
+        longitude: 2.3602,
+        radius_meters: 500,
+        limit: 10,
+        availability_filter: None,
+        sort: Default::default(),
+        lenient: None,
+        district: None,
+        auto_expand: false,
+        barrier_aware,
+        min_results: 1,
+        near_transit: None,
+        transit_radius_meters: None,
+    };
+
+    let crow_flies = handler.find_nearby_stations(nearby(false)).await.unwrap();
+    assert_eq!(crow_flies.stations.len(), 2);
+
+    // The right bank is ~450m away, but the nearest bridge makes it ~800m
+    let walked = handler.find_nearby_stations(nearby(true)).await.unwrap();
+    let codes: Vec<&str> = walked
+        .stations
+        .iter()
+        .map(|s| &*s.station.reference.station_code)
+        .collect();
+    assert_eq!(codes, vec!["5001"]);
+}
+
+/// Serve an OSRM `table` answering 900 m to Gare de Lyon - Chalon, 40 m
+/// elsewhere, and `route` answering a detour through Place de la Bastille
+async fn fake_osrm() -> String {
+    use axum::{extract::Path, routing::get, Json, Router};
+
+    let table = |Path(coordinates): Path<String>| async move {
+        let distances: Vec<f64> = coordinates
+            .split(';')
+            .enumerate()
+            .map(|(i, point)| match (i, point.starts_with("2.3755,")) {
+                (0, _) => 0.0,
+                (_, true) => 900.0,
+                (_, false) => 40.0,
+            })
+            .collect();
+        Json(serde_json::json!({"code": "Ok", "distances": [distances]}))
+    };
+    let route = |Path(coordinates): Path<String>| async move {
+        let ends: Vec<Vec<f64>> = coordinates
+            .split(';')
+            .map(|point| point.split(',').map(|n| n.parse().unwrap()).collect())
+            .collect();
+        let geometry = [ends[0].clone(), vec![2.3692, 48.8532], ends[1].clone()];
+        Json(serde_json::json!({
+            "code": "Ok",
+            "routes": [{"geometry": {"type": "LineString", "coordinates": geometry}}]
+        }))
+    };
+    let router = Router::new()
+        .route("/table/v1/foot/:coordinates", get(table))
+        .route("/route/v1/bike/:coordinates", get(route));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    url
+}
+
+#[tokio::test]
+async fn test_walks_measured_by_routing_engine() {
+    let nearby = FindNearbyStationsInput {
+        latitude: 48.8446,
+        longitude: 2.3737,
+        radius_meters: 500,
+        limit: 10,
+        availability_filter: None,
+        sort: Default::default(),
+        lenient: None,
+        district: None,
+        auto_expand: false,
+        barrier_aware: false,
+        min_results: 1,
+        near_transit: None,
+        transit_radius_meters: None,
+    };
+
+    let routed = fixture_handler()
+        .await
+        .with_routing(RoutingBackend::new(RoutingEngine::Osrm, &fake_osrm().await));
+    let output = routed.find_nearby_stations(nearby.clone()).await.unwrap();
+    let stations: Vec<(&str, u32)> = output
+        .stations
+        .iter()
+        .map(|s| (&*s.station.reference.station_code, s.distance_meters))
+        .collect();
+    // Chalon is 150 m away as the crow flies, but 900 m by the streets
+    assert_eq!(stations, vec![("12001", 40)]);
+    assert_eq!(output.stations[0].walk_time_minutes, 1);
+
+    let journey = routed
+        .plan_bike_journey(PlanBikeJourneyInput {
+            origin: Coordinates::new(48.8446, 2.3737),
+            destination: Coordinates::new(48.8566, 2.3522),
+            preferences: None,
+            route_format: Some(RouteFormat::Polyline),
+        })
+        .await
+        .unwrap()
+        .journey;
+    let route = journey.recommendations[0].route.clone().unwrap();
+    assert_eq!(route.source, RouteSource::RoutingEngine);
+    let ridden = velib_mcp::geometry::decode_polyline(&route.polyline.unwrap(), 5).unwrap();
+    assert_eq!(ridden.len(), 3);
+
+    // Without an answer, walks fall back to the straight line times the detour factor
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let estimated = fixture_handler()
+        .await
+        .with_routing(RoutingBackend::new(RoutingEngine::Osrm, &closed))
+        .with_detour_factor(2.0);
+    let output = estimated.find_nearby_stations(nearby).await.unwrap();
+    let chalon = &output.stations[1];
+    let crow_flies =
+        Coordinates::new(48.8446, 2.3737).distance_to(&chalon.station.reference.coordinates);
+    assert_eq!(chalon.distance_meters, (crow_flies * 2.0) as u32);
+}
+
+#[tokio::test]
+async fn test_journey_route_geometry() {
+    let journey = |route_format: Option<RouteFormat>| PlanBikeJourneyInput {
+        origin: Coordinates::new(48.8446, 2.3737),
+        destination: Coordinates::new(48.8566, 2.3522),
+        preferences: None,
+        route_format,
+    };
+    let handler = fixture_handler().await;
+
+    let plain = handler.plan_bike_journey(journey(None)).await.unwrap();
+    assert!(plain.journey.recommendations[0].route.is_none());
+
+    let drawn = handler
+        .plan_bike_journey(journey(Some(RouteFormat::GeoJson)))
+        .await
+        .unwrap();
+    let recommendation = &drawn.journey.recommendations[0];
+    let route = recommendation.route.as_ref().unwrap();
+    assert_eq!(route.source, RouteSource::StraightLine);
+    assert!(route.polyline.is_none());
+    let pickup = recommendation.pickup_station.reference.coordinates;
+    let dropoff = recommendation.dropoff_station.reference.coordinates;
+    let value = serde_json::to_value(route).unwrap();
+    assert_eq!(value["geojson"]["type"], "LineString");
+    assert_eq!(
+        value["geojson"]["coordinates"],
+        serde_json::json!([
+            [pickup.longitude, pickup.latitude],
+            [dropoff.longitude, dropoff.latitude]
+        ])
+    );
+    assert_eq!(route.distance_meters, pickup.distance_to(&dropoff) as u32);
+
+    let encoded = handler
+        .plan_bike_journey(journey(Some(RouteFormat::Polyline)))
+        .await
+        .unwrap();
+    let route = encoded.journey.recommendations[0].route.clone().unwrap();
+    assert!(route.geojson.is_none());
+    let decoded = velib_mcp::geometry::decode_polyline(&route.polyline.unwrap(), 5).unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert!((decoded[1].latitude - dropoff.latitude).abs() < 1e-5);
+}
+
+#[tokio::test]
+async fn test_coverage_gaps_within_service_area() {
+    let handler = fixture_handler().await.with_coverage_gap_distance(500);
+    let report = handler.coverage_gaps().await.unwrap();
+    assert_eq!(report.extent, CoverageExtent::StationExtent);
+    assert_eq!(report.gap_distance_meters, 500);
+    assert!(report.total_gaps >= 1);
+    assert!(report
+        .gaps
+        .windows(2)
+        .all(|pair| pair[0].area_km2 >= pair[1].area_km2));
+    assert!(report
+        .gaps
+        .iter()
+        .all(|gap| gap.nearest_station_meters.unwrap() > 500));
+
+    // Around Gare de Lyon alone, both of its stations cover the polygon
+    let polygon: Geometry = serde_json::from_value(serde_json::json!({
+        "type": "Polygon",
+        "coordinates": [[[2.372, 48.843], [2.376, 48.843], [2.376, 48.846], [2.372, 48.846]]]
+    }))
+    .unwrap();
+    let handler = handler.with_service_area(ServiceArea {
+        polygon: Some(polygon),
+        ..ServiceArea::paris()
+    });
+    let report = handler.coverage_gaps().await.unwrap();
+    assert_eq!(report.extent, CoverageExtent::ServicePolygon);
+    assert!(report.grid_cells > 0);
+    assert_eq!(report.total_gaps, 0);
+    assert_eq!(report.uncovered_km2, 0.0);
+}
+
+#[tokio::test]
+async fn test_outputs_repeat_under_a_manual_clock() {
+    let start = chrono::DateTime::parse_from_rfc3339("2025-06-14T08:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let run = || async move {
+        let clock = std::sync::Arc::new(velib_mcp::ManualClock::new(start));
+        let stations = fixture_stations();
+        let realtime = stations
+            .iter()
+            .map(|station| {
+                let status = RealTimeStatus::new_at(
+                    BikeAvailability::new(5, 3),
+                    10,
+                    StationStatus::Open,
+                    start - chrono::Duration::minutes(7),
+                    start,
+                );
+                (station.station_code.to_string(), status)
+            })
+            .collect();
+        let mut client = VelibDataClient::new().with_clock(clock).with_jitter_seed(7);
+        client.seed_cache(stations, realtime).await;
+        let handler = McpToolHandler::with_data_client(client);
+
+        let nearby = handler
+            .find_nearby_stations(FindNearbyStationsInput {
+                latitude: 48.8446,
+                longitude: 2.3737,
+                radius_meters: 1000,
+                limit: 10,
+                availability_filter: None,
+                sort: Default::default(),
+                lenient: None,
+                district: None,
+                auto_expand: false,
+                barrier_aware: false,
+                min_results: 1,
+                near_transit: None,
+                transit_radius_meters: None,
+            })
+            .await
+            .unwrap();
+        let gaps = handler.coverage_gaps().await.unwrap();
+        assert_eq!(gaps.generated_at, start);
+        (
+            serde_json::to_value(nearby).unwrap(),
+            serde_json::to_value(gaps).unwrap(),
+        )
+    };
+
+    let first = run().await;
+    assert_eq!(first, run().await);
+    assert_eq!(
+        first.0["stations"][0]["real_time"]["data_freshness"],
+        "Recent"
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use velib_mcp::mcp::types::GetStationByCodeInput;
+use velib_mcp::{
+    AvailabilityTrend, BikeAvailability, McpToolHandler, RealTimeStatus, StationStatus,
+    VelibDataClient,
+};
+
+#[tokio::test]
+async fn test_realtime_responses_carry_trend_once_history_exists() {
+    let stations = common::fixture_stations();
+    let mut client = VelibDataClient::new();
+
+    // Gare de Lyon - Diderot had 12 bikes ten minutes ago; the fixture has 8
+    let mut earlier = common::fixture_realtime();
+    earlier.insert(
+        "12001".to_string(),
+        RealTimeStatus::new(
+            BikeAvailability::new(9, 3),
+            0,
+            StationStatus::Open,
+            Utc::now() - Duration::minutes(10),
+        ),
+    );
+    client.seed_cache(stations.clone(), earlier).await;
+    assert!(client.fetch_realtime_status().await.unwrap()["12001"]
+        .trend
+        .is_none());
+
+    client
+        .seed_cache(stations.clone(), common::fixture_realtime())
+        .await;
+    let handler = McpToolHandler::with_data_client(client);
+
+    let trend = |code: &str| {
+        handler.get_station_by_code(GetStationByCodeInput {
+            station_code: code.to_string(),
+            include_real_time: true,
+        })
+    };
+    let emptying = trend("12001").await.unwrap().station.unwrap();
+    assert_eq!(
+        emptying.real_time.unwrap().trend,
+        Some(AvailabilityTrend::Emptying)
+    );
+
+    // Other stations only have samples from the two refreshes, seconds apart
+    let untracked = trend("4001").await.unwrap().station.unwrap();
+    assert_eq!(untracked.real_time.unwrap().trend, None);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use serde_json::{json, Value};
+use velib_mcp::McpServer;
+
+#[tokio::test]
+async fn test_usage_resource_summarizes_tool_calls() {
+    let router = McpServer::with_tool_handler(common::fixture_handler().await).router();
+
+    common::post_mcp(
+        &router,
+        None,
+        &common::tool_call("get_station_by_code", json!({"station_code": "4001"})),
+    )
+    .await;
+    common::post_mcp(
+        &router,
+        None,
+        &common::tool_call("get_station_by_code", json!({"station_code": "12001"})),
+    )
+    .await;
+    let (_, failed) = common::post_mcp(
+        &router,
+        None,
+        &common::tool_call(
+            "find_nearby_stations",
+            json!({"latitude": 10.0, "longitude": 2.35}),
+        ),
+    )
+    .await;
+    assert!(failed.get("error").is_some());
+    common::post_mcp(&router, None, &common::tool_call("no_such_tool", json!({}))).await;
+
+    let (_, read) = common::post_mcp(
+        &router,
+        None,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "resources/read",
+            "params": {"uri": "velib://stats/usage"}
+        }),
+    )
+    .await;
+    let usage: Value =
+        serde_json::from_str(read["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+
+    let hour = &usage["last_hour"];
+    assert_eq!(hour["total_calls"], 4);
+    assert_eq!(hour["total_errors"], 2);
+    assert_eq!(hour["tools"][0]["tool"], "get_station_by_code");
+    assert_eq!(hour["tools"][0]["calls"], 2);
+    assert!(hour["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|tool| tool["tool"] == "unknown" && tool["errors"] == 1));
+    assert!(hour["top_errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|error| error["error_type"] == "invalid_coordinates"));
+    assert_eq!(usage["last_day"]["total_calls"], 4);
+}
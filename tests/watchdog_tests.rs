@@ -0,0 +1,56 @@
+mod common;
+
+use axum::{body::Body, http::Request};
+use serde_json::Value;
+use tower::ServiceExt;
+use velib_mcp::{DataFreshness, McpServer, McpToolHandler, VelibDataClient};
+
+async fn health(server: &McpServer) -> Value {
+    let response = server
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/resources/velib://health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_frozen_feed_degrades_health_and_freshness() {
+    let stations = common::fixture_stations();
+    let realtime = common::fixture_realtime();
+
+    // Two refreshes returning the same duedates, with no grace period
+    let mut client = VelibDataClient::new().with_feed_stale_after(chrono::Duration::zero());
+    client.seed_cache(stations.clone(), realtime.clone()).await;
+    client.seed_cache(stations, realtime).await;
+    assert!(client.feed_status().frozen);
+
+    let statuses = client.fetch_realtime_status().await.unwrap();
+    assert!(statuses
+        .values()
+        .all(|status| status.data_freshness >= DataFreshness::Stale));
+
+    let server = McpServer::with_tool_handler(McpToolHandler::with_data_client(client));
+    let health = health(&server).await;
+    assert_eq!(health["status"], "degraded");
+    assert_eq!(health["data_sources"]["real_time"]["frozen"], true);
+    assert_eq!(health["metrics"]["upstream_frozen_alarms"], 1);
+}
+
+#[tokio::test]
+async fn test_fresh_feed_stays_healthy() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+    let health = health(&server).await;
+
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["data_sources"]["real_time"]["frozen"], false);
+    assert_eq!(health["metrics"]["realtime_refreshes"], 1);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use axum::{body::Body, http::Request};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use velib_mcp::McpServer;
+
+async fn get(server: &McpServer, uri: &str) -> (u16, String) {
+    let response = server
+        .router()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_station_widget_renders_availability() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+
+    let (status, card) = get(&server, "/widget/station/4001").await;
+    assert_eq!(status, 200);
+    assert!(card.contains("Hôtel de Ville"));
+    assert!(card.contains("8 bikes"));
+    assert!(card.contains("5 mechanical · 3 electric · 10 docks free"));
+
+    let (status, _) = get(&server, "/widget/station/99999").await;
+    assert_eq!(status, 404);
+}
+
+async fn journey_link(server: &McpServer, format: &str) -> Value {
+    let call = common::tool_call(
+        "generate_journey_link",
+        json!({
+            "pickup_station_code": "12001",
+            "dropoff_station_code": "4001",
+            "format": format
+        }),
+    );
+    common::post_mcp(&server.router(), None, &call).await.1
+}
+
+#[tokio::test]
+async fn test_shared_journey_pages() {
+    let server = McpServer::with_tool_handler(common::fixture_handler().await);
+    let refused = journey_link(&server, "page").await;
+    assert_eq!(refused["error"]["data"]["error_type"], "validation_error");
+
+    let server = server.with_public_url("https://velib.example.org/");
+    let response = journey_link(&server, "page").await;
+    let output: Value =
+        serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    let url = output["url"].as_str().unwrap();
+    let path = url.strip_prefix("https://velib.example.org").unwrap();
+    assert!(path.starts_with("/journey/"), "{url}");
+    assert!(output["expires_at"].is_string());
+
+    let (status, page) = get(&server, path).await;
+    assert_eq!(status, 200);
+    assert!(page.contains("Gare de Lyon - Diderot"));
+    assert!(page.contains("https://www.google.com/maps/dir/"));
+
+    let (status, _) = get(&server, "/journey/000000000000").await;
+    assert_eq!(status, 404);
+
+    let maps = journey_link(&server, "google_maps").await;
+    assert!(maps["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("travelmode=bicycling"));
+}